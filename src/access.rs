@@ -0,0 +1,254 @@
+//! Operator and engineer mode. In operator mode the Robot tab fields an
+//! engineer locked can't be edited, commands still run with their values.
+//! The PIN keeps fields from being changed by accident, it is not security.
+
+use crate::storage;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+pub const FIELD_LOCKS_FILE: &str = "field_locks.json";
+
+pub fn check_file() -> Option<Result<(), String>> {
+    storage::check_json::<FieldLocks>(FIELD_LOCKS_FILE)
+}
+
+pub const LOCKED_TEXT: &str = "Locked in operator mode, switch to engineer mode to change it.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Operator,
+    Engineer,
+}
+
+impl std::fmt::Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Role::Operator => write!(f, "Operator"),
+            Role::Engineer => write!(f, "Engineer"),
+        }
+    }
+}
+
+// Checked by every locked field, so it is kept process-wide like the decimal
+// separator. The GUI always starts in operator mode.
+static ENGINEER: AtomicBool = AtomicBool::new(false);
+
+pub fn set_role(role: Role) {
+    ENGINEER.store(role == Role::Engineer, Ordering::Relaxed);
+}
+
+pub fn role() -> Role {
+    if ENGINEER.load(Ordering::Relaxed) {
+        Role::Engineer
+    } else {
+        Role::Operator
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum LockableField {
+    Tcp,
+    Faceplate,
+    Baseframe,
+    Acceleration,
+    Velocity,
+    JointPositions,
+    PreferredJointConfig,
+    Payload,
+    ExecutionTime,
+    BlendRadius,
+    ForceThreshold,
+    RelativePose,
+    SoftLimits,
+}
+
+impl LockableField {
+    pub const ALL: [LockableField; 13] = [
+        LockableField::Tcp,
+        LockableField::Faceplate,
+        LockableField::Baseframe,
+        LockableField::Acceleration,
+        LockableField::Velocity,
+        LockableField::JointPositions,
+        LockableField::PreferredJointConfig,
+        LockableField::Payload,
+        LockableField::ExecutionTime,
+        LockableField::BlendRadius,
+        LockableField::ForceThreshold,
+        LockableField::RelativePose,
+        LockableField::SoftLimits,
+    ];
+}
+
+impl std::fmt::Display for LockableField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LockableField::Tcp => write!(f, "TCP"),
+            LockableField::Faceplate => write!(f, "Faceplate"),
+            LockableField::Baseframe => write!(f, "Baseframe"),
+            LockableField::Acceleration => write!(f, "Acceleration"),
+            LockableField::Velocity => write!(f, "Velocity"),
+            LockableField::JointPositions => write!(f, "Joint positions"),
+            LockableField::PreferredJointConfig => write!(f, "Preferred joint config"),
+            LockableField::Payload => write!(f, "Payload"),
+            LockableField::ExecutionTime => write!(f, "Execution time"),
+            LockableField::BlendRadius => write!(f, "Blend radius"),
+            LockableField::ForceThreshold => write!(f, "Force threshold"),
+            LockableField::RelativePose => write!(f, "Relative pose"),
+            LockableField::SoftLimits => write!(f, "Joint soft limits"),
+        }
+    }
+}
+
+/// Fields locked in operator mode, saved on this station.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FieldLocks {
+    locked: BTreeSet<LockableField>,
+    /// Asked for when switching to engineer mode, none if empty.
+    pin: String,
+}
+
+impl FieldLocks {
+    pub fn load() -> Self {
+        storage::load_json(FIELD_LOCKS_FILE).unwrap_or_default()
+    }
+
+    fn save(&self) {
+        if let Err(e) = storage::save_json(FIELD_LOCKS_FILE, self) {
+            log::error!("Failed to save field locks: {}", e);
+        }
+    }
+
+    /// Whether the field can't be edited right now.
+    pub fn is_locked(&self, field: LockableField) -> bool {
+        role() == Role::Operator && self.locked.contains(&field)
+    }
+
+    /// Checkboxes of the lockable fields and the PIN, for engineer mode.
+    pub fn draw_editor(&mut self, ui: &mut egui::Ui) {
+        let mut changed = false;
+        ui.label("Fields checked here can't be changed in operator mode.");
+        ui.horizontal_wrapped(|ui| {
+            for field in LockableField::ALL {
+                let mut locked = self.locked.contains(&field);
+                if ui.checkbox(&mut locked, field.to_string()).changed() {
+                    if locked {
+                        self.locked.insert(field);
+                    } else {
+                        self.locked.remove(&field);
+                    }
+                    changed = true;
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Engineer PIN:");
+            changed |= ui
+                .add(
+                    egui::TextEdit::singleline(&mut self.pin)
+                        .password(true)
+                        .hint_text("none")
+                        .desired_width(80.0),
+                )
+                .changed();
+        });
+        if changed {
+            self.save();
+        }
+    }
+}
+
+/// Adds `add` disabled while `locked`, takes the state instead of the locks so
+/// the closure is free to borrow the rest of the tab.
+pub fn locked_ui<R>(
+    ui: &mut egui::Ui,
+    locked: bool,
+    add: impl FnOnce(&mut egui::Ui) -> R,
+) -> egui::InnerResponse<R> {
+    let response = ui.add_enabled_ui(!locked, add);
+    response
+        .response
+        .clone()
+        .on_disabled_hover_text(LOCKED_TEXT);
+    response
+}
+
+/// The mode button shown next to the speed override.
+pub struct RoleSwitch {
+    /// PIN typed so far while the dialog is open.
+    entering_pin: Option<String>,
+    wrong_pin: bool,
+}
+
+impl RoleSwitch {
+    pub fn new() -> Self {
+        Self {
+            entering_pin: None,
+            wrong_pin: false,
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        let (label, hover) = match role() {
+            Role::Operator => ("🔒 Operator", "Switch to engineer mode"),
+            Role::Engineer => ("🔓 Engineer", "Back to operator mode"),
+        };
+        if ui.button(label).on_hover_text(hover).clicked() {
+            match role() {
+                Role::Engineer => set_role(Role::Operator),
+                // Read at the click so a PIN changed in the Robot tab applies
+                Role::Operator if FieldLocks::load().pin.is_empty() => set_role(Role::Engineer),
+                Role::Operator => {
+                    self.entering_pin = Some(String::new());
+                    self.wrong_pin = false;
+                }
+            }
+        }
+        self.draw_pin_dialog(ui.ctx());
+    }
+
+    fn draw_pin_dialog(&mut self, ctx: &egui::Context) {
+        let Some(pin) = self.entering_pin.as_mut() else {
+            return;
+        };
+        let mut submit = false;
+        let mut cancel = false;
+        egui::Window::new("Engineer mode")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("PIN:");
+                    let response = ui.add(
+                        egui::TextEdit::singleline(pin)
+                            .password(true)
+                            .desired_width(80.0),
+                    );
+                    response.request_focus();
+                    submit = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                });
+                if self.wrong_pin {
+                    ui.colored_label(egui::Color32::RED, "Wrong PIN");
+                }
+                ui.horizontal(|ui| {
+                    submit |= ui.button("Switch").clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+        if cancel {
+            self.entering_pin = None;
+        } else if submit {
+            if *pin == FieldLocks::load().pin {
+                set_role(Role::Engineer);
+                self.entering_pin = None;
+            } else {
+                self.wrong_pin = true;
+                pin.clear();
+            }
+        }
+    }
+}
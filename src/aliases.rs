@@ -0,0 +1,271 @@
+//! Symbolic frame names like `ACTIVE_PICK_POSE`, each a state variable
+//! `frame_alias_{name}` holding the frame it points to. Commands may use an
+//! alias as the goal, it is replaced with the frame when the command is sent,
+//! so re-pointing an alias changes every program that uses it. Previews and
+//! the path checks resolve them the same way.
+
+use crate::access;
+use crate::workers;
+use eframe::egui;
+use micro_sp::*;
+use poll_promise::Promise;
+use std::{collections::BTreeMap, sync::Arc};
+
+const ALIAS_PREFIX: &str = "frame_alias_";
+
+fn alias_key(alias: &str) -> String {
+    format!("{}{}", ALIAS_PREFIX, alias)
+}
+
+async fn load_aliases(con: Arc<ConnectionManager>) -> Result<BTreeMap<String, String>, String> {
+    let mut connection = con.get_connection().await;
+    let state = StateManager::get_full_state(&mut connection)
+        .await
+        .ok_or("Failed to get the state")?;
+    Ok(state
+        .state
+        .iter()
+        .filter_map(|(key, assignment)| {
+            let alias = key.strip_prefix(ALIAS_PREFIX)?;
+            match &assignment.val {
+                SPValue::String(StringOrUnknown::String(frame)) => {
+                    Some((alias.to_string(), frame.clone()))
+                }
+                _ => None,
+            }
+        })
+        .collect())
+}
+
+async fn set_alias(con: Arc<ConnectionManager>, alias: String, frame: String) {
+    let state = State::new().add(assign!(
+        v!(&&alias_key(&alias)),
+        frame.as_str().to_spvalue()
+    ));
+    crate::recorder::record_state(&state);
+    let mut connection = con.get_connection().await;
+    StateManager::set_state(&mut connection, &state).await;
+}
+
+/// The frame `name` points to right now if it is an alias, else `name`.
+pub async fn resolve(con: &ConnectionManager, name: &str) -> String {
+    let mut connection = con.get_connection().await;
+    match StateManager::get_sp_value(&mut connection, &alias_key(name)).await {
+        Some(SPValue::String(StringOrUnknown::String(frame))) => frame,
+        _ => name.to_string(),
+    }
+}
+
+/// `state` with every `*_goal_feature_id` that names an alias replaced by the
+/// frame the alias points to right now.
+pub async fn resolve_goals(con: Arc<ConnectionManager>, state: &State) -> State {
+    let mut resolved = state.clone();
+    for (key, assignment) in resolved.state.iter_mut() {
+        if !key.ends_with("_goal_feature_id") {
+            continue;
+        }
+        let SPValue::String(StringOrUnknown::String(goal)) = &assignment.val else {
+            continue;
+        };
+        let frame = resolve(&con, goal).await;
+        if frame != *goal {
+            log::info!("Goal alias {} resolved to {}", goal, frame);
+            assignment.val = frame.as_str().to_spvalue();
+        }
+    }
+    resolved
+}
+
+/// Why `alias` can't be used as an alias name, if it can't.
+fn problem(alias: &str, transform_keys: &[String]) -> Option<String> {
+    if alias.is_empty() {
+        Some("Enter a name".to_string())
+    } else if alias
+        .chars()
+        .any(|c| !(c.is_ascii_alphanumeric() || c == '_'))
+    {
+        Some("Use letters, digits and _ only".to_string())
+    } else if transform_keys.iter().any(|key| key == alias) {
+        Some(format!("{} is already a frame", alias))
+    } else {
+        None
+    }
+}
+
+pub struct FrameAliases {
+    aliases: BTreeMap<String, String>,
+    load_promise: Option<Promise<Result<BTreeMap<String, String>, String>>>,
+    write_promise: Option<Promise<()>>,
+    new_alias: String,
+    new_frame: Option<String>,
+    error: Option<String>,
+}
+
+impl FrameAliases {
+    pub fn new() -> Self {
+        Self {
+            aliases: BTreeMap::new(),
+            load_promise: None,
+            write_promise: None,
+            new_alias: String::new(),
+            new_frame: None,
+            error: None,
+        }
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &String> {
+        self.aliases.keys()
+    }
+
+    pub fn contains(&self, alias: &str) -> bool {
+        self.aliases.contains_key(alias)
+    }
+
+    /// The frame `name` points to as last loaded if it is an alias, else `name`.
+    pub fn resolve<'a>(&'a self, name: &'a str) -> &'a str {
+        self.aliases.get(name).map_or(name, String::as_str)
+    }
+
+    /// Re-reads the aliases from the state.
+    pub fn spawn_load(
+        &mut self,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+    ) {
+        let handle = handle.clone();
+        let con_clone = connection.clone();
+        self.load_promise = Some(workers::spawn("frame_aliases", move || {
+            handle.block_on(load_aliases(con_clone))
+        }));
+    }
+
+    fn spawn_write(
+        &mut self,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+        alias: String,
+        frame: String,
+    ) {
+        self.aliases.insert(alias.clone(), frame.clone());
+        let handle = handle.clone();
+        let con_clone = connection.clone();
+        self.write_promise = Some(workers::spawn("frame_alias_write", move || {
+            handle.block_on(set_alias(con_clone, alias, frame))
+        }));
+    }
+
+    /// Call every frame, also while the panel is closed.
+    pub fn poll(&mut self) {
+        if let Some(promise) = self.load_promise.take() {
+            match promise.try_take() {
+                Ok(Ok(aliases)) => {
+                    self.aliases = aliases;
+                    self.error = None;
+                }
+                Ok(Err(e)) => self.error = Some(e),
+                Err(promise) => self.load_promise = Some(promise),
+            }
+        }
+        if let Some(promise) = self.write_promise.take() {
+            if let Err(promise) = promise.try_take() {
+                self.write_promise = Some(promise);
+            }
+        }
+    }
+
+    /// The aliases and where they point, re-pointed by engineers.
+    pub fn draw(
+        &mut self,
+        ui: &mut egui::Ui,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+        transform_keys: &[String],
+    ) {
+        let editable = access::role() == access::Role::Engineer;
+        let busy = self.load_promise.is_some() || self.write_promise.is_some();
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(!busy, egui::Button::new("Refresh"))
+                .clicked()
+            {
+                self.spawn_load(handle, connection);
+            }
+            if busy {
+                ui.spinner();
+            }
+            if !editable {
+                ui.weak("Engineers can re-point aliases.");
+            }
+        });
+        if let Some(e) = &self.error {
+            ui.colored_label(egui::Color32::RED, e);
+        }
+
+        let mut repoint = None;
+        egui::Grid::new("frame_aliases_grid")
+            .num_columns(3)
+            .striped(true)
+            .show(ui, |ui| {
+                for (alias, frame) in &self.aliases {
+                    ui.monospace(alias);
+                    ui.add_enabled_ui(editable && !busy, |ui| {
+                        egui::ComboBox::from_id_salt(("frame_alias", alias))
+                            .selected_text(frame)
+                            .show_ui(ui, |ui| {
+                                for key in transform_keys {
+                                    if ui.selectable_label(key == frame, key).clicked() {
+                                        repoint = Some((alias.clone(), key.clone()));
+                                    }
+                                }
+                            });
+                    });
+                    if !transform_keys.is_empty() && !transform_keys.contains(frame) {
+                        ui.colored_label(egui::Color32::YELLOW, "frame not found");
+                    }
+                    ui.end_row();
+                }
+            });
+        if self.aliases.is_empty() {
+            ui.weak("No aliases, or not loaded yet.");
+        }
+        if let Some((alias, frame)) = repoint {
+            self.spawn_write(handle, connection, alias, frame);
+        }
+
+        if !editable {
+            return;
+        }
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label("New alias:");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.new_alias)
+                    .hint_text("ACTIVE_PICK_POSE")
+                    .desired_width(140.0),
+            );
+            ui.label("→");
+            egui::ComboBox::from_id_salt("frame_alias_new")
+                .selected_text(self.new_frame.as_deref().unwrap_or("Select..."))
+                .show_ui(ui, |ui| {
+                    for key in transform_keys {
+                        ui.selectable_value(&mut self.new_frame, Some(key.clone()), key);
+                    }
+                });
+            let alias = self.new_alias.trim().to_string();
+            let problem = problem(&alias, transform_keys).or_else(|| {
+                self.new_frame
+                    .is_none()
+                    .then(|| "Select a frame".to_string())
+            });
+            let add = ui
+                .add_enabled(problem.is_none() && !busy, egui::Button::new("Add"))
+                .on_disabled_hover_text(problem.unwrap_or_default());
+            if add.clicked() {
+                if let Some(frame) = self.new_frame.take() {
+                    self.new_alias.clear();
+                    self.spawn_write(handle, connection, alias, frame);
+                }
+            }
+        });
+    }
+}
@@ -1,30 +1,1087 @@
+use crate::barcode::ScanBuffer;
+use crate::locale::LocalizedDragValue;
+use crate::mes::{FieldMapping, MesIntake};
+use crate::order_history::{self, ActiveOrder, CompletionRecord, Outcome, Progress};
+use crate::order_queue;
+use crate::orders::{Order, OrderBook, Priority, Program, Selection};
+use crate::products::{ParameterKind, ParameterValue, ProductCatalog, ProductType};
+use crate::state_poller::sp_value_to_display;
+use crate::workers;
 use eframe::egui;
+use micro_sp::*;
+use poll_promise::Promise;
+use rfd::FileDialog;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-/// Holds all the state for the "Another" tab
+/// How often the running order's goal and alarms are checked.
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+/// Completion records listed in the history view, newest first.
+const HISTORY_ROWS: usize = 100;
+
+fn format_duration(secs: u64) -> String {
+    format!("{}:{:02}:{:02}", secs / 3600, (secs % 3600) / 60, secs % 60)
+}
+
+/// Holds all the state for the "Order Handler" tab
 pub struct AnotherTab {
-    // You could add tab-specific state here
-    // counter: i32,
+    book: OrderBook,
+    scanner: ScanBuffer,
+    code_input: String,
+    /// Program or order that Start runs, chosen by scan or by hand.
+    armed: Option<Selection>,
+    new_order: Order,
+    new_program: Program,
+    start_promise: Option<Promise<()>>,
+    status: Option<Result<String, String>>,
+    mes: MesIntake,
+    accept_promise: Option<Promise<()>>,
+    active: Option<ActiveOrder>,
+    progress_promise: Option<Promise<Result<Progress, String>>>,
+    last_progress_check: Option<Instant>,
+    history: Vec<CompletionRecord>,
+    history_status: Option<String>,
+    products: ProductCatalog,
+    products_error: Option<String>,
+    /// Due date of the new order in hours from now, 0 for none.
+    new_order_due_hours: f64,
 }
 
 impl AnotherTab {
-    /// Create a new `AnotherTab` with default state
     pub fn new() -> Self {
-        Self {
-            // counter: 0,
+        let mut tab = Self {
+            book: OrderBook::load(),
+            scanner: ScanBuffer::default(),
+            code_input: String::new(),
+            armed: None,
+            new_order: Order {
+                quantity: 1,
+                ..Default::default()
+            },
+            new_program: Program::default(),
+            start_promise: None,
+            status: None,
+            mes: MesIntake::new(),
+            accept_promise: None,
+            active: None,
+            progress_promise: None,
+            last_progress_check: None,
+            history: order_history::load(),
+            history_status: None,
+            products: ProductCatalog::default(),
+            products_error: None,
+            new_order_due_hours: 0.0,
+        };
+        tab.reload_products();
+        tab
+    }
+
+    fn reload_products(&mut self) {
+        match ProductCatalog::load() {
+            Ok(products) => {
+                self.products = products;
+                self.products_error = None;
+            }
+            Err(e) => {
+                log::error!("{}", e);
+                self.products_error = Some(e);
+            }
+        }
+    }
+
+    /// Parameters an order writes to the state when it starts.
+    fn order_state_values(&self, order: &Order) -> Result<Vec<(String, SPValue)>, String> {
+        if order.product.is_empty() {
+            return Ok(Vec::new());
+        }
+        let product = self
+            .products
+            .get(&order.product)
+            .ok_or_else(|| format!("Unknown product type '{}'", order.product))?;
+        if let Some(problem) = product.validate(&order.parameters) {
+            return Err(format!("Order {}: {}", order.id, problem));
+        }
+        Ok(product.state_values(&order.parameters))
+    }
+
+    /// Keeps the MES intake and the running order's tracking going while
+    /// other tabs are open.
+    pub fn update_background(
+        &mut self,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+        robot_ids: Vec<String>,
+    ) {
+        self.mes.update(handle);
+        if let Some(promise) = &self.accept_promise {
+            if promise.ready().is_some() {
+                self.accept_promise = None;
+            }
+        }
+        if let Some(promise) = &self.start_promise {
+            if promise.ready().is_some() {
+                self.start_promise = None;
+            }
+        }
+        self.update_active(handle, connection, robot_ids);
+    }
+
+    fn update_active(
+        &mut self,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+        robot_ids: Vec<String>,
+    ) {
+        // Until the goal is written, it would look replaced
+        if self.start_promise.is_some() {
+            return;
+        }
+        let Some(active) = &mut self.active else {
+            return;
+        };
+        let mut outcome = None;
+        if let Some(promise) = self.progress_promise.take() {
+            match promise.try_take() {
+                Ok(Ok(progress)) => {
+                    active.update_alarms(progress.alarms);
+                    if progress.goal_reached {
+                        outcome = Some(Outcome::Completed);
+                    } else if !progress.goal_still_set {
+                        outcome = Some(Outcome::Cancelled);
+                    }
+                }
+                Ok(Err(e)) => log::error!("Failed to check order {}: {}", active.order.id, e),
+                Err(promise) => {
+                    self.progress_promise = Some(promise);
+                    return;
+                }
+            }
+        }
+        if let Some(outcome) = outcome {
+            self.finish_active(outcome);
+            return;
+        }
+        let due = self
+            .last_progress_check
+            .is_none_or(|last| last.elapsed() >= PROGRESS_INTERVAL);
+        if due {
+            self.last_progress_check = Some(Instant::now());
+            let handle = handle.clone();
+            let con_clone = connection.clone();
+            let goal_variable = active.goal_variable.clone();
+            let goal = active.goal.clone();
+            self.progress_promise = Some(workers::spawn("order_progress", move || {
+                handle.block_on(order_history::check_progress(
+                    con_clone,
+                    goal_variable,
+                    goal,
+                    robot_ids,
+                ))
+            }));
+        }
+    }
+
+    fn finish_active(&mut self, outcome: Outcome) {
+        let Some(active) = self.active.take() else {
+            return;
+        };
+        self.progress_promise = None;
+        let record = active.finish(outcome);
+        log::info!("Order {} {}", record.order_id, outcome);
+        if outcome == Outcome::Completed {
+            self.book.orders.retain(|o| o.id != record.order_id);
+            self.book.save();
+            self.armed = None;
+        }
+        self.status = Some(match outcome {
+            Outcome::Completed => Ok(format!(
+                "Order {} completed in {}",
+                record.order_id,
+                format_duration(record.duration_secs())
+            )),
+            _ => Err(format!("Order {} {}", record.order_id, outcome)),
+        });
+        self.history.push(record);
+    }
+
+    /// Draw the UI for the "Order Handler" tab
+    pub fn ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+        goal_variable: String,
+    ) {
+        ui.heading("Order Handler");
+        ui.separator();
+
+        if let Some(code) = self.scanner.poll(ui.ctx()) {
+            self.select_code(&code);
+        }
+        ui.horizontal(|ui| {
+            ui.label("Scan:");
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.code_input)
+                    .hint_text("order or program code")
+                    .desired_width(180.0),
+            );
+            let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            if submitted || ui.button("Select").clicked() {
+                let code = std::mem::take(&mut self.code_input);
+                self.select_code(&code);
+            }
+            ui.label("ℹ").on_hover_text(
+                "Scanning a barcode anywhere on this tab selects the order or \n\
+                 program and arms it. Nothing moves until Start is pressed.",
+            );
+        });
+
+        self.draw_active(ui, handle, connection);
+        self.draw_armed(ui, handle, connection, goal_variable);
+
+        match &self.status {
+            Some(Ok(msg)) => {
+                ui.colored_label(egui::Color32::GREEN, msg);
+            }
+            Some(Err(e)) => {
+                ui.colored_label(egui::Color32::RED, e);
+            }
+            None => (),
+        }
+
+        ui.separator();
+        self.draw_incoming(ui, handle, connection);
+        ui.separator();
+        self.draw_orders(ui);
+        ui.separator();
+        self.draw_programs(ui);
+        ui.separator();
+        egui::CollapsingHeader::new("Order History")
+            .id_salt("order_history")
+            .show(ui, |ui| self.draw_history(ui));
+    }
+
+    fn select_code(&mut self, code: &str) {
+        match self.book.find_code(code) {
+            Some(selection) => {
+                self.armed = Some(selection);
+                self.status = None;
+            }
+            None => {
+                log::warn!("Scanned unknown code '{}'", code);
+                self.armed = None;
+                self.status = Some(Err(format!("Unknown code '{}'", code.trim())));
+            }
         }
     }
 
-    /// Draw the UI for the "Another" tab
-    pub fn ui(&mut self, ui: &mut egui::Ui) {
-        ui.heading("This is Another Tab");
-        ui.label("You can put completely different UI elements here.");
-        ui.add_space(10.0);
-        ui.label("For example, this could be a settings page, a log viewer, or another tool.");
+    /// The program that Start runs and a description of what is armed.
+    fn armed_program(&self) -> Option<(String, Option<&Program>)> {
+        match self.armed? {
+            Selection::Order(i) => {
+                let order = self.book.orders.get(i)?;
+                Some((
+                    format!("Order {} – {} ×{}", order.id, order.program, order.quantity),
+                    self.book.program(&order.program),
+                ))
+            }
+            Selection::Program(i) => {
+                let program = self.book.programs.get(i)?;
+                Some((format!("Program {}", program.name), Some(program)))
+            }
+        }
+    }
 
-        // Example of stateful widget
-        // if ui.button("Click me").clicked() {
-        //     self.counter += 1;
-        // }
-        // ui.label(format!("Counter: {}", self.counter));
+    /// Work instruction of the running order, else of what is armed.
+    pub fn instructions_path(&self) -> Option<String> {
+        if let Some(active) = &self.active {
+            return self.book.instructions(&active.order);
+        }
+        match self.armed? {
+            Selection::Order(i) => self.book.instructions(self.book.orders.get(i)?),
+            Selection::Program(i) => self
+                .book
+                .programs
+                .get(i)
+                .map(|p| p.instructions.clone())
+                .filter(|path| !path.is_empty()),
+        }
+    }
+
+    fn draw_armed(
+        &mut self,
+        ui: &mut egui::Ui,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+        goal_variable: String,
+    ) {
+        let Some((description, program)) = self.armed_program() else {
+            ui.weak("Nothing armed.");
+            return;
+        };
+        let goal = program.map(|p| p.goal.trim().to_string());
+        let order = match self.armed {
+            Some(Selection::Order(i)) => self.book.orders.get(i).cloned(),
+            _ => None,
+        };
+        let mut start = false;
+        let mut disarm = false;
+        egui::Frame::group(ui.style()).show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(egui::RichText::new(format!("Armed: {}", description)).size(18.0));
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui.button("Disarm").clicked() {
+                        disarm = true;
+                    }
+                    let can_start = self.start_promise.is_none()
+                        && self.active.is_none()
+                        && goal.as_ref().is_some_and(|g| !g.is_empty());
+                    if ui
+                        .add_enabled(can_start, egui::Button::new("Start"))
+                        .on_disabled_hover_text(
+                            "The program is unknown or has no goal, or an order is running",
+                        )
+                        .clicked()
+                    {
+                        start = true;
+                    }
+                    if self.start_promise.is_some() {
+                        ui.spinner();
+                    }
+                });
+            });
+        });
+
+        if start {
+            let parameters = match &order {
+                Some(order) => self.order_state_values(order),
+                None => Ok(Vec::new()),
+            };
+            match (goal, parameters) {
+                (_, Err(e)) => self.status = Some(Err(e)),
+                (Some(goal), Ok(parameters)) => {
+                    self.status = Some(Ok(format!("Started {}", description)));
+                    if let Some(order) = order {
+                        self.active =
+                            Some(ActiveOrder::new(order, goal_variable.clone(), goal.clone()));
+                        self.last_progress_check = None;
+                    }
+                    let handle = handle.clone();
+                    let con_clone = connection.clone();
+                    self.start_promise = Some(workers::spawn("start_order", move || {
+                        handle.block_on(async move {
+                            // The program reads its parameters once the goal is set
+                            if !parameters.is_empty() {
+                                crate::backend::set_values(con_clone.clone(), parameters).await;
+                            }
+                            crate::goal::set_goal(con_clone, goal_variable, goal).await
+                        })
+                    }));
+                }
+                (None, Ok(_)) => (),
+            }
+            self.armed = None;
+        }
+        if disarm {
+            self.armed = None;
+        }
+    }
+
+    fn draw_active(
+        &mut self,
+        ui: &mut egui::Ui,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+    ) {
+        let Some(active) = &self.active else {
+            return;
+        };
+        let mut outcome = None;
+        egui::Frame::group(ui.style()).show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label(
+                    egui::RichText::new(format!(
+                        "Running: Order {} – {} ×{}",
+                        active.order.id, active.order.program, active.order.quantity
+                    ))
+                    .size(18.0),
+                );
+                ui.monospace(format_duration(
+                    crate::storage::now_secs().saturating_sub(active.started),
+                ));
+                if !active.alarms.is_empty() {
+                    ui.colored_label(
+                        egui::Color32::RED,
+                        format!("⚠ {} alarms", active.alarms.len()),
+                    )
+                    .on_hover_text(active.alarms.join("\n"));
+                }
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui
+                        .button("Cancel")
+                        .on_hover_text("Clear the planner goal and end the order")
+                        .clicked()
+                    {
+                        outcome = Some(Outcome::Cancelled);
+                    }
+                    if ui
+                        .button("Mark Failed")
+                        .on_hover_text("End the order as failed, the goal stays set")
+                        .clicked()
+                    {
+                        outcome = Some(Outcome::Failed);
+                    }
+                });
+            });
+        });
+        if outcome == Some(Outcome::Cancelled) {
+            let handle = handle.clone();
+            let con_clone = connection.clone();
+            let goal_variable = active.goal_variable.clone();
+            self.start_promise = Some(workers::spawn("cancel_order", move || {
+                handle.block_on(crate::goal::set_goal(
+                    con_clone,
+                    goal_variable,
+                    String::new(),
+                ))
+            }));
+        }
+        if let Some(outcome) = outcome {
+            self.finish_active(outcome);
+        }
     }
-}
\ No newline at end of file
+
+    fn draw_history(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui.button("Export JSON").clicked() {
+                self.export_history("json");
+            }
+            if ui.button("Export CSV").clicked() {
+                self.export_history("csv");
+            }
+            if let Some(status) = &self.history_status {
+                ui.label(status);
+            }
+        });
+        if self.history.is_empty() {
+            ui.weak("No finished orders yet.");
+            return;
+        }
+        egui::Grid::new("order_history_grid")
+            .num_columns(7)
+            .spacing([10.0, 4.0])
+            .striped(true)
+            .show(ui, |ui| {
+                for header in [
+                    "Finished", "Order", "Program", "Quantity", "Duration", "Outcome", "Alarms",
+                ] {
+                    ui.strong(header);
+                }
+                ui.end_row();
+                for record in self.history.iter().rev().take(HISTORY_ROWS) {
+                    ui.label(crate::storage::format_timestamp(record.finished));
+                    ui.label(&record.order_id);
+                    ui.label(&record.program);
+                    ui.label(record.quantity.to_string());
+                    ui.monospace(format_duration(record.duration_secs()));
+                    let color = match record.outcome {
+                        Outcome::Completed => egui::Color32::GREEN,
+                        Outcome::Failed => egui::Color32::RED,
+                        Outcome::Cancelled => egui::Color32::YELLOW,
+                    };
+                    ui.colored_label(color, record.outcome.to_string());
+                    if record.alarms.is_empty() {
+                        ui.label("-");
+                    } else {
+                        ui.label(record.alarms.len().to_string())
+                            .on_hover_text(record.alarms.join("\n"));
+                    }
+                    ui.end_row();
+                }
+            });
+    }
+
+    fn export_history(&mut self, extension: &str) {
+        let content = match extension {
+            "csv" => Ok(order_history::to_csv(&self.history)),
+            _ => order_history::to_json(&self.history),
+        };
+        let content = match content {
+            Ok(content) => content,
+            Err(e) => {
+                self.history_status = Some(format!("Failed to export: {}", e));
+                return;
+            }
+        };
+        let Some(path) = FileDialog::new()
+            .add_filter(extension.to_uppercase(), &[extension])
+            .set_file_name(format!("order_history.{}", extension))
+            .save_file()
+        else {
+            return;
+        };
+        self.history_status = Some(match std::fs::write(&path, content) {
+            Ok(_) => format!("Exported to {}", path.display()),
+            Err(e) => format!("Failed to export: {}", e),
+        });
+    }
+
+    fn draw_incoming(
+        &mut self,
+        ui: &mut egui::Ui,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+    ) {
+        ui.horizontal(|ui| {
+            ui.strong("Incoming from MES");
+            if ui
+                .add_enabled(
+                    !self.mes.is_polling() && !self.mes.config.url.trim().is_empty(),
+                    egui::Button::new("Poll Now"),
+                )
+                .clicked()
+            {
+                self.mes.poll_now(handle);
+            }
+            if self.mes.is_polling() {
+                ui.spinner();
+            }
+            match &self.mes.status {
+                Some(Ok(msg)) => {
+                    ui.weak(msg);
+                }
+                Some(Err(e)) => {
+                    ui.colored_label(egui::Color32::RED, e);
+                }
+                None => (),
+            }
+        });
+
+        let mut accept = None;
+        let mut reject = None;
+        if !self.mes.pending().is_empty() {
+            egui::Grid::new("incoming_orders_grid")
+                .num_columns(5)
+                .spacing([10.0, 4.0])
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.strong("Order");
+                    ui.strong("Program");
+                    ui.strong("Quantity");
+                    ui.strong("Received");
+                    ui.label("");
+                    ui.end_row();
+                    for (i, incoming) in self.mes.pending().iter().enumerate() {
+                        let order = &incoming.order;
+                        ui.label(&order.id).on_hover_text(
+                            incoming
+                                .variables
+                                .iter()
+                                .map(|(key, value)| {
+                                    format!("{} = {}", key, sp_value_to_display(Some(value)))
+                                })
+                                .collect::<Vec<String>>()
+                                .join("\n"),
+                        );
+                        if self.book.program(&order.program).is_some() {
+                            ui.label(&order.program);
+                        } else {
+                            ui.colored_label(egui::Color32::YELLOW, &order.program)
+                                .on_hover_text("Unknown program");
+                        }
+                        ui.label(order.quantity.to_string());
+                        ui.label(crate::storage::format_timestamp(incoming.received));
+                        ui.horizontal(|ui| {
+                            let duplicate = self.book.orders.iter().any(|o| o.id == order.id);
+                            if ui
+                                .add_enabled(
+                                    !duplicate && self.accept_promise.is_none(),
+                                    egui::Button::new("Accept"),
+                                )
+                                .on_disabled_hover_text("Already in the order list")
+                                .clicked()
+                            {
+                                accept = Some(i);
+                            }
+                            if ui.button("Reject").clicked() {
+                                reject = Some(i);
+                            }
+                        });
+                        ui.end_row();
+                    }
+                });
+        }
+        if let Some(incoming) = accept.and_then(|i| self.mes.take(i)) {
+            if !incoming.variables.is_empty() {
+                let handle = handle.clone();
+                let con_clone = connection.clone();
+                let values = incoming.variables.clone();
+                self.accept_promise = Some(workers::spawn("accept_order", move || {
+                    handle.block_on(crate::backend::set_values(con_clone, values))
+                }));
+            }
+            self.status = Some(Ok(format!("Accepted order {}", incoming.order.id)));
+            self.book.orders.push(incoming.order);
+            self.book.save();
+        }
+        if let Some(incoming) = reject.and_then(|i| self.mes.take(i)) {
+            log::info!("Rejected MES order {}", incoming.order.id);
+            self.status = Some(Ok(format!("Rejected order {}", incoming.order.id)));
+        }
+
+        egui::CollapsingHeader::new("MES Connection")
+            .id_salt("mes_connection")
+            .show(ui, |ui| self.draw_mes_config(ui));
+    }
+
+    fn draw_mes_config(&mut self, ui: &mut egui::Ui) {
+        let config = &mut self.mes.config;
+        let mut changed = false;
+        egui::Grid::new("mes_config_grid")
+            .num_columns(2)
+            .spacing([10.0, 4.0])
+            .show(ui, |ui| {
+                ui.label("Orders URL:");
+                changed |= ui
+                    .add(
+                        egui::TextEdit::singleline(&mut config.url)
+                            .hint_text("http://mes/api/stations/1/orders")
+                            .desired_width(320.0),
+                    )
+                    .changed();
+                ui.end_row();
+
+                ui.label("Polling:");
+                ui.horizontal(|ui| {
+                    changed |= ui.checkbox(&mut config.poll_enabled, "every").changed();
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut config.poll_interval_secs)
+                                .range(1..=3600)
+                                .suffix(" s"),
+                        )
+                        .changed();
+                });
+                ui.end_row();
+
+                for (label, field) in [
+                    ("Order ID field:", &mut config.id_field),
+                    ("Program field:", &mut config.program_field),
+                    ("Quantity field:", &mut config.quantity_field),
+                ] {
+                    ui.label(label);
+                    changed |= ui
+                        .add(egui::TextEdit::singleline(field).desired_width(160.0))
+                        .changed();
+                    ui.end_row();
+                }
+            });
+
+        ui.label("State variables written on accept:");
+        let mut remove = None;
+        egui::Grid::new("mes_mapping_grid")
+            .num_columns(3)
+            .spacing([10.0, 4.0])
+            .show(ui, |ui| {
+                for (i, mapping) in config.variables.iter_mut().enumerate() {
+                    changed |= ui
+                        .add(
+                            egui::TextEdit::singleline(&mut mapping.field)
+                                .hint_text("MES field")
+                                .desired_width(140.0),
+                        )
+                        .changed();
+                    changed |= ui
+                        .add(
+                            egui::TextEdit::singleline(&mut mapping.variable)
+                                .hint_text("variable")
+                                .desired_width(140.0),
+                        )
+                        .changed();
+                    if ui.button("🗑").clicked() {
+                        remove = Some(i);
+                    }
+                    ui.end_row();
+                }
+            });
+        if let Some(i) = remove {
+            config.variables.remove(i);
+            changed = true;
+        }
+        if ui.button("Add Variable").clicked() {
+            config.variables.push(FieldMapping::default());
+            changed = true;
+        }
+        ui.weak("The MES can also push orders with the submit_orders automation method.");
+
+        if changed {
+            config.save();
+        }
+    }
+
+    fn draw_queue(&self, ui: &mut egui::Ui) {
+        let now = crate::storage::now_secs();
+        let running = self
+            .active
+            .as_ref()
+            .map(|active| (active.order.id.as_str(), active.started));
+        let cycle_times = order_queue::cycle_times(&self.history);
+        let slots = order_queue::schedule(&self.book.orders, running, now, &cycle_times);
+        ui.horizontal(|ui| {
+            ui.strong("Queue");
+            for priority in Priority::ALL {
+                ui.colored_label(order_queue::priority_color(priority), "■");
+                ui.label(priority.to_string());
+            }
+            ui.separator();
+            ui.weak("Yellow outline: finishes within 15 min of its due date, red: late");
+        });
+        order_queue::draw_queue_strip(ui, &self.book.orders, &slots, now);
+    }
+
+    fn draw_orders(&mut self, ui: &mut egui::Ui) {
+        self.draw_queue(ui);
+        ui.strong("Orders");
+        let mut remove = None;
+        let mut move_up = None;
+        let mut priority_changed = false;
+        let mut instructions_changed = false;
+        let product = self.products.get(&self.new_order.product);
+        let parameter_problem = product.and_then(|p| p.validate(&self.new_order.parameters));
+        let mut product_changed = false;
+        egui::Grid::new("orders_grid")
+            .num_columns(7)
+            .spacing([10.0, 4.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("Order");
+                ui.strong("Program");
+                ui.strong("Product");
+                ui.strong("Quantity");
+                ui.strong("Priority");
+                ui.strong("Due");
+                ui.label("");
+                ui.end_row();
+                for (i, order) in self.book.orders.iter_mut().enumerate() {
+                    let armed = self.armed == Some(Selection::Order(i));
+                    if ui.selectable_label(armed, &order.id).clicked() {
+                        self.armed = Some(Selection::Order(i));
+                    }
+                    if self.book.programs.iter().any(|p| p.name == order.program) {
+                        ui.label(&order.program);
+                    } else {
+                        ui.colored_label(egui::Color32::YELLOW, &order.program)
+                            .on_hover_text("Unknown program");
+                    }
+                    if order.product.is_empty() {
+                        ui.label("-");
+                    } else {
+                        ui.label(&order.product).on_hover_text(
+                            order
+                                .parameters
+                                .iter()
+                                .map(|(name, value)| format!("{} = {}", name, value))
+                                .collect::<Vec<String>>()
+                                .join("\n"),
+                        );
+                    }
+                    ui.label(order.quantity.to_string());
+                    priority_changed |=
+                        priority_combo(ui, ("order_priority", i), &mut order.priority);
+                    ui.label(
+                        order
+                            .due
+                            .map_or("-".to_string(), crate::storage::format_timestamp),
+                    );
+                    ui.horizontal(|ui| {
+                        instructions_changed |= instructions_button(ui, &mut order.instructions);
+                        if ui.add_enabled(i > 0, egui::Button::new("⏶")).clicked() {
+                            move_up = Some(i);
+                        }
+                        if ui.button("🗑").clicked() {
+                            remove = Some(i);
+                        }
+                    });
+                    ui.end_row();
+                }
+
+                ui.add(egui::TextEdit::singleline(&mut self.new_order.id).hint_text("number"));
+                egui::ComboBox::from_id_salt("new_order_program")
+                    .selected_text(self.new_order.program.as_str())
+                    .show_ui(ui, |ui| {
+                        for program in &self.book.programs {
+                            ui.selectable_value(
+                                &mut self.new_order.program,
+                                program.name.clone(),
+                                &program.name,
+                            );
+                        }
+                    });
+                egui::ComboBox::from_id_salt("new_order_product")
+                    .selected_text(self.new_order.product.as_str())
+                    .show_ui(ui, |ui| {
+                        product_changed |= ui
+                            .selectable_value(&mut self.new_order.product, String::new(), "None")
+                            .changed();
+                        for product in &self.products.product_types {
+                            product_changed |= ui
+                                .selectable_value(
+                                    &mut self.new_order.product,
+                                    product.name.clone(),
+                                    &product.name,
+                                )
+                                .changed();
+                        }
+                    });
+                ui.add(egui::DragValue::new(&mut self.new_order.quantity).range(1..=9999));
+                priority_combo(ui, "new_order_priority", &mut self.new_order.priority);
+                ui.add(
+                    egui::DragValue::new(&mut self.new_order_due_hours)
+                        .localized()
+                        .range(0.0..=720.0)
+                        .prefix("in ")
+                        .suffix(" h"),
+                )
+                .on_hover_text("Due date in hours from now, 0 for none");
+                let id = self.new_order.id.trim();
+                let can_add = !id.is_empty()
+                    && !self.new_order.program.is_empty()
+                    && parameter_problem.is_none()
+                    && !self.book.orders.iter().any(|o| o.id == id);
+                if ui.add_enabled(can_add, egui::Button::new("Add")).clicked() {
+                    let due = (self.new_order_due_hours > 0.0).then(|| {
+                        crate::storage::now_secs() + (self.new_order_due_hours * 3600.0) as u64
+                    });
+                    let order = Order {
+                        id: id.to_string(),
+                        due,
+                        ..self.new_order.clone()
+                    };
+                    self.book.orders.push(order);
+                    self.book.save();
+                    self.new_order.id.clear();
+                }
+                ui.end_row();
+            });
+        if let Some(i) = remove {
+            self.book.orders.remove(i);
+            self.book.save();
+            self.armed = None;
+        }
+        if let Some(i) = move_up {
+            self.book.orders.swap(i - 1, i);
+            self.book.save();
+            self.armed = None;
+        }
+        if priority_changed || instructions_changed {
+            self.book.save();
+        }
+        if product_changed {
+            self.new_order.parameters = self
+                .products
+                .get(&self.new_order.product)
+                .map(ProductType::initial_values)
+                .unwrap_or_default();
+        }
+        if let Some(product) = self.products.get(&self.new_order.product) {
+            egui::Frame::group(ui.style()).show(ui, |ui| {
+                ui.label(format!("{} parameters of the new order:", product.name));
+                draw_parameter_form(ui, product, &mut self.new_order.parameters);
+                if let Some(problem) = &parameter_problem {
+                    ui.colored_label(egui::Color32::YELLOW, problem);
+                }
+            });
+        }
+        ui.horizontal(|ui| {
+            if ui
+                .small_button("Reload Product Types")
+                .on_hover_text(ProductCatalog::path().display().to_string())
+                .clicked()
+            {
+                self.reload_products();
+            }
+            if let Some(e) = &self.products_error {
+                ui.colored_label(egui::Color32::RED, e);
+            } else if self.products.product_types.is_empty() {
+                ui.weak(format!(
+                    "No product types, define them in {}",
+                    ProductCatalog::path().display()
+                ));
+            }
+        });
+    }
+
+    fn draw_programs(&mut self, ui: &mut egui::Ui) {
+        ui.strong("Programs");
+        let mut remove = None;
+        let mut changed = false;
+        egui::Grid::new("programs_grid")
+            .num_columns(4)
+            .spacing([10.0, 4.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("Name");
+                ui.strong("Code");
+                ui.strong("Goal");
+                ui.label("");
+                ui.end_row();
+                for (i, program) in self.book.programs.iter_mut().enumerate() {
+                    let armed = self.armed == Some(Selection::Program(i));
+                    if ui.selectable_label(armed, &program.name).clicked() {
+                        self.armed = Some(Selection::Program(i));
+                    }
+                    ui.label(&program.code);
+                    ui.monospace(&program.goal);
+                    ui.horizontal(|ui| {
+                        changed |= instructions_button(ui, &mut program.instructions);
+                        if ui.button("🗑").clicked() {
+                            remove = Some(i);
+                        }
+                    });
+                    ui.end_row();
+                }
+
+                ui.add(egui::TextEdit::singleline(&mut self.new_program.name).hint_text("name"));
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.new_program.code)
+                        .hint_text("barcode, optional"),
+                );
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.new_program.goal)
+                        .font(egui::TextStyle::Monospace)
+                        .hint_text("planner goal"),
+                );
+                let name = self.new_program.name.trim();
+                let can_add = !name.is_empty()
+                    && !self.new_program.goal.trim().is_empty()
+                    && self.book.program(name).is_none();
+                if ui.add_enabled(can_add, egui::Button::new("Add")).clicked() {
+                    let program = Program {
+                        name: name.to_string(),
+                        code: self.new_program.code.trim().to_string(),
+                        goal: self.new_program.goal.trim().to_string(),
+                        ..Default::default()
+                    };
+                    self.book.programs.push(program);
+                    self.book.save();
+                    self.new_program = Program::default();
+                }
+                ui.end_row();
+            });
+        if changed {
+            self.book.save();
+        }
+        if let Some(i) = remove {
+            self.book.programs.remove(i);
+            self.book.save();
+            self.armed = None;
+        }
+    }
+}
+
+/// One input per parameter of the product type, by kind.
+fn draw_parameter_form(
+    ui: &mut egui::Ui,
+    product: &ProductType,
+    values: &mut std::collections::BTreeMap<String, ParameterValue>,
+) {
+    egui::Grid::new(("product_form", &product.name))
+        .num_columns(2)
+        .spacing([10.0, 4.0])
+        .show(ui, |ui| {
+            for spec in &product.parameters {
+                let label = ui.label(format!("{}:", spec.name));
+                if !spec.description.is_empty() {
+                    label.on_hover_text(&spec.description);
+                }
+                let value = values
+                    .entry(spec.name.clone())
+                    .or_insert_with(|| spec.initial_value());
+                let min = spec.min.unwrap_or(f64::MIN);
+                let max = spec.max.unwrap_or(f64::MAX);
+                match (spec.kind, value) {
+                    (ParameterKind::Float, ParameterValue::Float(x)) => {
+                        let mut input = egui::DragValue::new(x).localized().range(min..=max);
+                        if !spec.unit.is_empty() {
+                            input = input.suffix(format!(" {}", spec.unit));
+                        }
+                        ui.add(input);
+                    }
+                    (ParameterKind::Integer, ParameterValue::Integer(i)) => {
+                        let mut input = egui::DragValue::new(i).range(min..=max);
+                        if !spec.unit.is_empty() {
+                            input = input.suffix(format!(" {}", spec.unit));
+                        }
+                        ui.add(input);
+                    }
+                    (ParameterKind::Bool, ParameterValue::Bool(b)) => {
+                        ui.checkbox(b, "");
+                    }
+                    (ParameterKind::Choice, ParameterValue::Text(s)) => {
+                        egui::ComboBox::from_id_salt(("product_choice", &spec.name))
+                            .selected_text(s.as_str())
+                            .show_ui(ui, |ui| {
+                                for option in &spec.options {
+                                    ui.selectable_value(s, option.clone(), option);
+                                }
+                            });
+                    }
+                    (ParameterKind::Text, ParameterValue::Text(s)) => {
+                        ui.text_edit_singleline(s);
+                    }
+                    (_, value) => {
+                        // Left over from an older definition of the type
+                        if ui.button("Reset").clicked() {
+                            *value = spec.initial_value();
+                        }
+                    }
+                }
+                ui.weak(product.variable(&spec.name));
+                ui.end_row();
+            }
+        });
+}
+
+fn priority_combo(ui: &mut egui::Ui, id: impl std::hash::Hash, priority: &mut Priority) -> bool {
+    let mut changed = false;
+    egui::ComboBox::from_id_salt(id)
+        .selected_text(
+            egui::RichText::new(priority.to_string()).color(order_queue::priority_color(*priority)),
+        )
+        .show_ui(ui, |ui| {
+            for option in Priority::ALL {
+                changed |= ui
+                    .selectable_value(priority, option, option.to_string())
+                    .changed();
+            }
+        });
+    changed
+}
+
+/// Picks the work instruction file, right-click clears it. Returns whether
+/// `path` changed.
+fn instructions_button(ui: &mut egui::Ui, path: &mut String) -> bool {
+    let hover = if path.is_empty() {
+        "Set work instruction (Markdown or HTML)".to_string()
+    } else {
+        format!("Work instruction: {}\nRight-click to clear", path)
+    };
+    let label = if path.is_empty() { "📄" } else { "📄✔" };
+    let response = ui.button(label).on_hover_text(hover);
+    if response.clicked() {
+        if let Some(file) = FileDialog::new()
+            .add_filter("Work instruction", &["md", "markdown", "html", "htm"])
+            .pick_file()
+        {
+            *path = file.display().to_string();
+            return true;
+        }
+    } else if response.secondary_clicked() && !path.is_empty() {
+        path.clear();
+        return true;
+    }
+    false
+}
@@ -0,0 +1,273 @@
+//! Backend operations shared by the scripting console and other automation,
+//! independent of any tab's UI state.
+
+use crate::history::{self, HistoryEntry};
+use crate::pose::{self, FrameMetadata, Pose};
+use crate::provenance::{self, Method};
+use crate::retry;
+use micro_sp::*;
+use std::sync::{Arc, atomic::AtomicBool};
+use std::time::Duration;
+
+/// Default time a scripted command may take before it is reported as failed.
+pub const COMMAND_TIMEOUT: Duration = Duration::from_secs(60);
+
+pub async fn frame_names(con: Arc<ConnectionManager>) -> Result<Vec<String>, String> {
+    let mut connection = con.get_connection().await;
+    let transforms = TransformsManager::get_all_transforms(&mut connection)
+        .await
+        .map_err(|e| format!("Failed to get all transforms: {}", e))?;
+    let mut names = transforms.into_keys().collect::<Vec<String>>();
+    names.sort_unstable();
+    Ok(names)
+}
+
+/// Pose of `child` expressed in `parent`.
+pub async fn lookup(
+    con: Arc<ConnectionManager>,
+    parent: &str,
+    child: &str,
+) -> Result<Pose, String> {
+    let mut connection = con.get_connection().await;
+    TransformsManager::lookup_transform(&mut connection, parent, child)
+        .await
+        .map(|tf| Pose::from_sp_transform(&tf.transform))
+        .map_err(|e| format!("Failed to lookup {} in {}: {}", child, parent, e))
+}
+
+/// Creates or overwrites a frame with the metadata the GUI uses for new frames.
+pub async fn publish_frame(
+    con: Arc<ConnectionManager>,
+    parent: &str,
+    child: &str,
+    pose: &Pose,
+    method: Method,
+) -> Result<(), String> {
+    let mut transform =
+        pose::make_transform_stamped(parent, child, pose, &FrameMetadata::for_frame(child));
+    provenance::stamp(&mut transform, method);
+    let mut connection = con.get_connection().await;
+    TransformsManager::insert_transform(&mut connection, &transform)
+        .await
+        .map_err(|e| format!("Failed to publish {}: {}", child, e))
+}
+
+pub async fn get_value(con: Arc<ConnectionManager>, key: &str) -> Option<SPValue> {
+    let mut connection = con.get_connection().await;
+    StateManager::get_sp_value(&mut connection, key).await
+}
+
+/// Picks the variable type from the value, since the keys come from untyped input.
+fn state_from_values(values: Vec<(String, SPValue)>) -> State {
+    values
+        .into_iter()
+        .fold(State::new(), |state, (key, value)| {
+            let var = match &value {
+                SPValue::Bool(_) => bv!(&&key),
+                SPValue::Float64(_) => fv!(&&key),
+                SPValue::Array(_) => av!(&&key),
+                _ => v!(&&key),
+            };
+            state.add(assign!(var, value))
+        })
+}
+
+pub async fn set_values(con: Arc<ConnectionManager>, values: Vec<(String, SPValue)>) {
+    let state = state_from_values(values);
+    let mut connection = con.get_connection().await;
+    StateManager::set_state(&mut connection, &state).await;
+}
+
+/// `set_values` for scripts and remote clients, refused while a cell guard is
+/// open since any variable they write may start a motion.
+pub async fn set_values_guarded(
+    con: Arc<ConnectionManager>,
+    values: Vec<(String, SPValue)>,
+) -> Result<(), String> {
+    crate::cell_guards::check(con.clone()).await?;
+    set_values(con, values).await;
+    Ok(())
+}
+
+/// Why a request didn't succeed.
+pub enum RequestError {
+    /// The driver reported `failed`.
+    Failed,
+    /// The driver didn't finish in time.
+    TimedOut,
+}
+
+/// Waits until `{prefix}_request_state` reports `succeeded` or `failed`.
+/// `prefix` is the robot id for robot commands, `{robot}_dashboard` or
+/// `{robot}_gripper` for the requests of those drivers.
+pub async fn await_request(
+    con: Arc<ConnectionManager>,
+    prefix: &str,
+    timeout: Duration,
+) -> Result<(), RequestError> {
+    let request_state_key = format!("{}_request_state", prefix);
+    let start = std::time::Instant::now();
+    loop {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        if let Some(SPValue::String(StringOrUnknown::String(request_state))) =
+            get_value(con.clone(), &request_state_key).await
+        {
+            match request_state.as_str() {
+                "succeeded" => return Ok(()),
+                "failed" => return Err(RequestError::Failed),
+                _ => (),
+            }
+        }
+        if start.elapsed() > timeout {
+            return Err(RequestError::TimedOut);
+        }
+    }
+}
+
+/// Writes `state` together with the `{prefix}_request_trigger` and waits for
+/// the request like `await_request`.
+pub async fn send_request(
+    con: Arc<ConnectionManager>,
+    prefix: &str,
+    state: State,
+    timeout: Duration,
+) -> Result<(), RequestError> {
+    let state = state
+        .add(assign!(
+            bv!(&&format!("{}_request_trigger", prefix)),
+            true.to_spvalue()
+        ))
+        .add(assign!(
+            v!(&&format!("{}_request_state", prefix)),
+            "initial".to_spvalue()
+        ));
+    crate::recorder::record_state(&state);
+    let mut connection = con.get_connection().await;
+    StateManager::set_state(&mut connection, &state).await;
+    drop(connection);
+    await_request(con, prefix, timeout).await
+}
+
+/// Variables `send_command` writes itself, without the robot prefix.
+const REQUEST_VARIABLES: [&str; 4] = [
+    "request_trigger",
+    "request_cancel",
+    "request_state",
+    "cancel_current_goal",
+];
+
+/// The `{robot}_*` values of a command state as `send_command` parameters,
+/// without the request variables it writes itself.
+pub fn command_parameters(robot_id: &str, state: &State) -> Vec<(String, SPValue)> {
+    state
+        .state
+        .iter()
+        .filter_map(|(key, assignment)| {
+            let parameter = key.strip_prefix(robot_id)?.strip_prefix('_')?;
+            (!REQUEST_VARIABLES.contains(&parameter))
+                .then(|| (parameter.to_string(), assignment.val.clone()))
+        })
+        .collect()
+}
+
+/// Fails while the robot is disabled or a cell guard is open. Checked before
+/// every command, so a run stops at the next step once the cell opens.
+pub async fn check_allowed(con: Arc<ConnectionManager>, robot_id: &str) -> Result<(), String> {
+    if let Some(SPValue::Bool(BoolOrUnknown::Bool(false))) =
+        get_value(con.clone(), &crate::resources::enabled_key(robot_id)).await
+    {
+        return Err(format!("Robot {} is disabled", robot_id));
+    }
+    crate::cell_guards::check(con).await
+}
+
+/// The parameters with the robot prefix, plus the request variables that
+/// start a new command.
+fn command_state(robot_id: &str, parameters: Vec<(String, SPValue)>) -> State {
+    let mut values = parameters
+        .into_iter()
+        .map(|(key, value)| (format!("{}_{}", robot_id, key), value))
+        .collect::<Vec<(String, SPValue)>>();
+    values.push((format!("{}_request_trigger", robot_id), true.to_spvalue()));
+    values.push((format!("{}_request_cancel", robot_id), false.to_spvalue()));
+    values.push((
+        format!("{}_cancel_current_goal", robot_id),
+        false.to_spvalue(),
+    ));
+    values.push((
+        format!("{}_request_state", robot_id),
+        "initial".to_spvalue(),
+    ));
+    state_from_values(values)
+}
+
+/// Writes the given `{robot}_<parameter>` values together with a request
+/// trigger and waits for the robot to finish, retrying per the command's
+/// retry policy. Parameters are given without the robot prefix, e.g.
+/// `("command_type", "unsafe_move_l")`.
+pub async fn send_command(
+    con: Arc<ConnectionManager>,
+    robot_id: &str,
+    parameters: Vec<(String, SPValue)>,
+    timeout: Duration,
+) -> Result<(), String> {
+    let cancel = AtomicBool::new(false);
+    send_command_cancellable(con, robot_id, parameters, timeout, &cancel)
+        .await
+        .map(|_| ())
+}
+
+/// Like `send_command` for the steps of a run, which isn't retried once
+/// `cancel` is set. Returns how many retries it took.
+pub async fn send_command_cancellable(
+    con: Arc<ConnectionManager>,
+    robot_id: &str,
+    parameters: Vec<(String, SPValue)>,
+    timeout: Duration,
+    cancel: &AtomicBool,
+) -> Result<u32, String> {
+    check_allowed(con.clone(), robot_id).await?;
+    let parameter = |name: &str| {
+        parameters
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| crate::state_poller::sp_value_to_display(Some(value)))
+    };
+    let command_type = parameter("command_type").unwrap_or_default();
+    let entry = HistoryEntry::now(
+        robot_id,
+        &command_type,
+        parameter("goal_feature_id").as_deref(),
+    );
+    let state = command_state(robot_id, parameters);
+    history::record(&entry);
+    let policy = retry::policy_for(&command_type);
+    match retry::send_with_retry(&state, con, &entry, &policy, timeout, cancel).await {
+        Ok(retries) => {
+            if retries > 0 {
+                log::info!(
+                    "{} on {} succeeded after {} retries",
+                    command_type,
+                    robot_id,
+                    retries
+                );
+            }
+            Ok(retries)
+        }
+        Err((e, 0)) => Err(e),
+        Err((e, retries)) => Err(format!("{} (after {} retries)", e, retries)),
+    }
+}
+
+/// Like `send_command`, but sent once and left out of the history, for
+/// commands repeated quickly like jog steps.
+pub async fn send_command_once(
+    con: Arc<ConnectionManager>,
+    robot_id: &str,
+    parameters: Vec<(String, SPValue)>,
+    timeout: Duration,
+) -> Result<(), String> {
+    check_allowed(con.clone(), robot_id).await?;
+    let state = command_state(robot_id, parameters);
+    crate::robot::send_robot_command_and_wait(&state, con, robot_id, timeout).await
+}
@@ -0,0 +1,93 @@
+use crate::state_poller::{StatePoller, sp_value_to_display};
+use eframe::egui;
+use micro_sp::{BoolOrUnknown, SPValue};
+
+/// State variables the banner shows for a robot.
+pub fn banner_keys(robot_id: &str) -> Vec<String> {
+    vec![
+        format!("{}_command_type", robot_id),
+        format!("{}_request_state", robot_id),
+        format!("{}_safety_mode", robot_id),
+        format!("{}_tcp_id", robot_id),
+        crate::speed_override::velocity_scaling_key(robot_id),
+        crate::resources::enabled_key(robot_id),
+    ]
+}
+
+/// False only when the resource has explicitly been disabled.
+pub fn is_robot_enabled(poller: &StatePoller, robot_id: &str) -> bool {
+    !matches!(
+        poller.get(&crate::resources::enabled_key(robot_id)),
+        Some(SPValue::Bool(BoolOrUnknown::Bool(false)))
+    )
+}
+
+/// One-line summary of what the active robot is doing, drawn under the tab bar.
+pub fn draw_robot_state_banner(
+    ui: &mut egui::Ui,
+    poller: &StatePoller,
+    robot_id: &str,
+    open_guards: Option<&[(String, &'static str)]>,
+) {
+    let request_state = sp_value_to_display(poller.get(&format!("{}_request_state", robot_id)));
+    let command = if request_state == "executing" {
+        sp_value_to_display(poller.get(&format!("{}_command_type", robot_id)))
+    } else {
+        "idle".to_string()
+    };
+    let safety = sp_value_to_display(poller.get(&format!("{}_safety_mode", robot_id)));
+    let tcp = sp_value_to_display(poller.get(&format!("{}_tcp_id", robot_id)));
+    // What the robot runs at, whichever control wrote it
+    let speed = match crate::speed_override::live_scaling(
+        poller,
+        &crate::speed_override::velocity_scaling_key(robot_id),
+    ) {
+        Some(scaling) => format!("{:.0} %", scaling * 100.0),
+        None => "-".to_string(),
+    };
+
+    let safety_color = match safety.to_lowercase().as_str() {
+        "normal" => egui::Color32::GREEN,
+        "-" => ui.visuals().weak_text_color(),
+        _ => egui::Color32::RED,
+    };
+
+    egui::Frame::default()
+        .inner_margin(egui::Margin::symmetric(8, 4))
+        .fill(ui.visuals().faint_bg_color)
+        .show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.strong(robot_id);
+                if !is_robot_enabled(poller, robot_id) {
+                    ui.colored_label(egui::Color32::from_rgb(230, 160, 40), "DISABLED");
+                }
+                ui.separator();
+                ui.label("Command:");
+                ui.monospace(format!("{} ({})", command, request_state));
+                ui.separator();
+                ui.label("Safety:");
+                ui.colored_label(safety_color, safety);
+                ui.separator();
+                ui.label("Speed:");
+                ui.monospace(speed);
+                ui.separator();
+                ui.label("TCP:");
+                ui.monospace(tcp);
+                // Only shown once guards are configured in the settings
+                if let Some(open_guards) = open_guards {
+                    ui.separator();
+                    ui.label("Cell:");
+                    if open_guards.is_empty() {
+                        ui.colored_label(egui::Color32::GREEN, "closed");
+                    } else {
+                        let open = open_guards
+                            .iter()
+                            .map(|(name, state)| format!("{} {}", name, state))
+                            .collect::<Vec<String>>()
+                            .join(", ");
+                        ui.colored_label(egui::Color32::RED, format!("OPEN: {}", open));
+                    }
+                }
+            });
+        });
+}
@@ -0,0 +1,78 @@
+//! Input from USB barcode scanners in keyboard wedge mode. A scanner types the
+//! code much faster than a person and ends it with Enter, which is how a scan
+//! is told apart from typing.
+
+use eframe::egui;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Longest pause between two characters of one scan.
+const MAX_KEY_GAP: Duration = Duration::from_millis(50);
+/// Shorter input is taken as stray key presses.
+const MIN_CODE_LENGTH: usize = 3;
+
+// Whichever tab polls the scanner, the Input Devices tab lists it from here.
+static LAST_SCAN: Mutex<Option<(Instant, String)>> = Mutex::new(None);
+
+/// The last completed scan and when it came in.
+pub fn last_scan() -> Option<(Instant, String)> {
+    LAST_SCAN.lock().ok()?.clone()
+}
+
+#[derive(Default)]
+pub struct ScanBuffer {
+    buffer: String,
+    last_key: Option<Instant>,
+}
+
+impl ScanBuffer {
+    /// Collects the keys of this frame and returns a completed scan. Keys typed
+    /// into a focused text field belong to that field and are ignored.
+    pub fn poll(&mut self, ctx: &egui::Context) -> Option<String> {
+        if ctx.memory(|m| m.focused().is_some()) {
+            self.clear();
+            return None;
+        }
+        let events = ctx.input(|i| i.events.clone());
+        let mut scanned = None;
+        for event in events {
+            match event {
+                egui::Event::Text(text) => {
+                    let now = Instant::now();
+                    if self
+                        .last_key
+                        .is_some_and(|last| now.duration_since(last) > MAX_KEY_GAP)
+                    {
+                        self.buffer.clear();
+                    }
+                    self.buffer.push_str(&text);
+                    self.last_key = Some(now);
+                }
+                egui::Event::Key {
+                    key: egui::Key::Enter,
+                    pressed: true,
+                    ..
+                } => {
+                    let typed_fast = self
+                        .last_key
+                        .is_some_and(|last| last.elapsed() <= MAX_KEY_GAP);
+                    if typed_fast && self.buffer.trim().len() >= MIN_CODE_LENGTH {
+                        let code = self.buffer.trim().to_string();
+                        if let Ok(mut last) = LAST_SCAN.lock() {
+                            *last = Some((Instant::now(), code.clone()));
+                        }
+                        scanned = Some(code);
+                    }
+                    self.clear();
+                }
+                _ => (),
+            }
+        }
+        scanned
+    }
+
+    fn clear(&mut self) {
+        self.buffer.clear();
+        self.last_key = None;
+    }
+}
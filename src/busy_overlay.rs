@@ -0,0 +1,43 @@
+//! Overlay over a tab while it runs an operation that other clicks in the
+//! tab would conflict with, like a command being sent or frames being written.
+//! The rest of the GUI stays usable.
+
+use eframe::egui;
+
+pub struct Busy {
+    pub message: String,
+    /// Label of the cancel button, for operations that can be cancelled safely.
+    pub cancel: Option<&'static str>,
+}
+
+/// Covers `rect` and takes the clicks meant for the tab below. Returns true
+/// when the cancel button was clicked.
+pub fn draw(ctx: &egui::Context, rect: egui::Rect, busy: &Busy) -> bool {
+    let mut cancel = false;
+    egui::Area::new(egui::Id::new("busy_overlay"))
+        .order(egui::Order::Foreground)
+        .fixed_pos(rect.min)
+        .show(ctx, |ui| {
+            ui.allocate_rect(rect, egui::Sense::click_and_drag());
+            ui.painter()
+                .rect_filled(rect, 0.0, egui::Color32::from_black_alpha(140));
+            let content = egui::Rect::from_center_size(rect.center(), egui::vec2(320.0, 80.0));
+            ui.scope_builder(
+                egui::UiBuilder::new()
+                    .max_rect(content)
+                    .layout(egui::Layout::top_down(egui::Align::Center)),
+                |ui| {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.strong(&busy.message);
+                        });
+                        if let Some(label) = busy.cancel {
+                            cancel = ui.button(label).clicked();
+                        }
+                    });
+                },
+            );
+        });
+    cancel
+}
@@ -0,0 +1,59 @@
+use micro_sp::*;
+use std::collections::BTreeSet;
+
+// Option names a driver can list next to its command types.
+pub const BLEND_RADIUS: &str = "blend_radius";
+pub const EXECUTION_TIME: &str = "execution_time";
+pub const JOINT_POSITIONS: &str = "joint_positions";
+pub const PREFERRED_JOINT_CONFIG: &str = "preferred_joint_config";
+pub const PAYLOAD: &str = "payload";
+pub const RELATIVE_POSE: &str = "relative_pose";
+pub const FORCE_THRESHOLD: &str = "force_threshold";
+
+pub fn capabilities_key(robot_id: &str) -> String {
+    format!("{}_driver_capabilities", robot_id)
+}
+
+/// Command types and options a driver announces in `{robot}_driver_capabilities`,
+/// either as an array of strings or as one comma separated string.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DriverCapabilities {
+    supported: BTreeSet<String>,
+}
+
+impl DriverCapabilities {
+    pub fn from_sp_value(value: &SPValue) -> Option<Self> {
+        let supported = match value {
+            SPValue::Array(ArrayOrUnknown::Array(values)) => values
+                .iter()
+                .filter_map(|v| match v {
+                    SPValue::String(StringOrUnknown::String(s)) => Some(s.trim().to_string()),
+                    _ => None,
+                })
+                .collect(),
+            SPValue::String(StringOrUnknown::String(s)) => s
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect(),
+            _ => return None,
+        };
+        Some(Self { supported })
+    }
+
+    pub fn supports(&self, name: &str) -> bool {
+        self.supported.contains(name)
+    }
+}
+
+/// Everything is supported by drivers that don't announce their capabilities.
+pub fn is_supported(capabilities: Option<&DriverCapabilities>, name: &str) -> bool {
+    capabilities.is_none_or(|c| c.supports(name))
+}
+
+pub fn unsupported_text(name: &str) -> String {
+    format!(
+        "The driver of this robot does not list '{}' in its driver_capabilities.",
+        name
+    )
+}
@@ -0,0 +1,151 @@
+//! Doors, fences and light curtains of the cell, each a boolean state
+//! variable. While one isn't closed the robot can't be commanded from the GUI,
+//! unless an engineer overrides it. Runs and scripts check them again before
+//! every command they send.
+
+use crate::access::{self, Role};
+use crate::backend;
+use crate::state_poller::StatePoller;
+use eframe::egui;
+use micro_sp::{BoolOrUnknown, ConnectionManager, SPValue};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+
+// The guards of the settings, kept process-wide like the role so commands
+// sent outside the UI can check them.
+static CONFIGURED: Mutex<Vec<CellGuard>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CellGuard {
+    pub name: String,
+    pub variable: String,
+    /// Value the variable has while the guard is closed, e.g. false for a
+    /// `door_open` signal.
+    pub closed_value: bool,
+}
+
+impl Default for CellGuard {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            variable: String::new(),
+            closed_value: true,
+        }
+    }
+}
+
+pub fn guard_keys(guards: &[CellGuard]) -> Vec<String> {
+    guards
+        .iter()
+        .filter(|g| !g.variable.trim().is_empty())
+        .map(|g| g.variable.clone())
+        .collect()
+}
+
+pub fn set_configured(guards: &[CellGuard]) {
+    let mut configured = CONFIGURED.lock().unwrap();
+    if configured.as_slice() != guards {
+        *configured = guards.to_vec();
+    }
+}
+
+/// "open" or "unknown" for a guard that isn't closed. A guard whose variable
+/// can't be read counts as not closed.
+fn guard_state(guard: &CellGuard, value: Option<&SPValue>) -> Option<&'static str> {
+    match value {
+        Some(SPValue::Bool(BoolOrUnknown::Bool(value))) if *value == guard.closed_value => None,
+        Some(SPValue::Bool(BoolOrUnknown::Bool(_))) => Some("open"),
+        _ => Some("unknown"),
+    }
+}
+
+/// Guards that aren't closed, with "open" or "unknown".
+pub fn open_guards(poller: &StatePoller, guards: &[CellGuard]) -> Vec<(String, &'static str)> {
+    guards
+        .iter()
+        .filter(|g| !g.variable.trim().is_empty())
+        .filter_map(|g| Some((g.name.clone(), guard_state(g, poller.get(&g.variable))?)))
+        .collect()
+}
+
+/// Reads the configured guards from the state and fails with the
+/// `blocked_reason` while one isn't closed, for commands sent outside the UI.
+pub async fn check(con: Arc<ConnectionManager>) -> Result<(), String> {
+    let guards = CONFIGURED.lock().unwrap().clone();
+    let mut open = Vec::new();
+    for guard in guards.iter().filter(|g| !g.variable.trim().is_empty()) {
+        let value = backend::get_value(con.clone(), &guard.variable).await;
+        if let Some(state) = guard_state(guard, value.as_ref()) {
+            open.push((guard.name.clone(), state));
+        }
+    }
+    blocked_reason(&open).map_or(Ok(()), Err)
+}
+
+/// Why commands are blocked, `None` while the cell is closed or for engineers.
+pub fn blocked_reason(open: &[(String, &'static str)]) -> Option<String> {
+    if open.is_empty() || access::role() == Role::Engineer {
+        return None;
+    }
+    Some(format!(
+        "The cell is open ({}). An engineer can override this.",
+        open.iter()
+            .map(|(name, state)| format!("{} {}", name, state))
+            .collect::<Vec<String>>()
+            .join(", ")
+    ))
+}
+
+/// Editor for the guards. Returns true if one changed.
+pub fn draw_editor(ui: &mut egui::Ui, guards: &mut Vec<CellGuard>) -> bool {
+    let mut changed = false;
+    let mut remove = None;
+    egui::Grid::new("cell_guards_grid")
+        .num_columns(4)
+        .striped(true)
+        .show(ui, |ui| {
+            ui.strong("Name");
+            ui.strong("Variable");
+            ui.strong("Closed when");
+            ui.label("");
+            ui.end_row();
+            for (i, guard) in guards.iter_mut().enumerate() {
+                changed |= ui
+                    .add(egui::TextEdit::singleline(&mut guard.name).desired_width(120.0))
+                    .changed();
+                changed |= ui
+                    .add(
+                        egui::TextEdit::singleline(&mut guard.variable)
+                            .font(egui::TextStyle::Monospace)
+                            .desired_width(160.0),
+                    )
+                    .changed();
+                egui::ComboBox::from_id_salt(("cell_guard_closed", i))
+                    .selected_text(guard.closed_value.to_string())
+                    .show_ui(ui, |ui| {
+                        for value in [true, false] {
+                            changed |= ui
+                                .selectable_value(&mut guard.closed_value, value, value.to_string())
+                                .changed();
+                        }
+                    });
+                if ui.button("🗑").clicked() {
+                    remove = Some(i);
+                }
+                ui.end_row();
+            }
+        });
+    if let Some(i) = remove {
+        guards.remove(i);
+        changed = true;
+    }
+    if ui.button("Add Guard").clicked() {
+        guards.push(CellGuard {
+            name: format!("Guard {}", guards.len() + 1),
+            ..Default::default()
+        });
+        changed = true;
+    }
+    changed
+}
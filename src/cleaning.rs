@@ -0,0 +1,265 @@
+//! Wiping routine for the sponge: a zig-zag over a rectangle of a surface
+//! frame, published as waypoint frames under it and added to the path as
+//! force-limited moves between an approach and a retract point.
+
+use crate::expression::Unit;
+use crate::locale::LocalizedDragValue;
+use crate::pose::{self, FrameMetadata, Pose};
+use crate::provenance::{self, Method};
+use eframe::egui;
+use micro_sp::*;
+use std::sync::Arc;
+
+/// More lanes than this is most likely a wrong sponge width or overlap.
+const MAX_LANES: usize = 200;
+
+/// A waypoint of the routine, in the surface frame.
+#[derive(Debug, Clone)]
+pub struct WipePoint {
+    pub name: String,
+    pub pose: Pose,
+    /// In contact with the surface, sent with the force threshold.
+    pub contact: bool,
+}
+
+pub struct WipeRoutine {
+    /// Its XY plane is the surface and Z points away from it. The
+    /// rectangle is centered on the origin, the waypoints keep its orientation.
+    pub surface: Option<String>,
+    /// Along X, m.
+    pub width: f64,
+    /// Along Y, m.
+    pub length: f64,
+    /// Width of the sponge across the wiping direction, m.
+    pub sponge_width: f64,
+    /// How much of the sponge width neighbouring lanes share, %.
+    pub overlap: f64,
+    /// N
+    pub force_threshold: f64,
+    /// Wiping speed, m/s.
+    pub velocity: f64,
+    /// Height above the surface of the approach and retract points, m.
+    pub approach_height: f64,
+}
+
+impl WipeRoutine {
+    pub fn new() -> Self {
+        Self {
+            surface: None,
+            width: 0.3,
+            length: 0.2,
+            sponge_width: 0.05,
+            overlap: 20.0,
+            force_threshold: 15.0,
+            velocity: 0.05,
+            approach_height: 0.05,
+        }
+    }
+
+    /// Distance between two lanes.
+    fn stepover(&self) -> f64 {
+        self.sponge_width * (1.0 - self.overlap / 100.0)
+    }
+
+    /// Centers of the sponge along one axis of the rectangle, from the lower
+    /// edge to the upper one.
+    fn lane_range(&self, size: f64) -> (f64, f64) {
+        let half = ((size - self.sponge_width) / 2.0).max(0.0);
+        (-half, half)
+    }
+
+    fn lane_count(&self) -> usize {
+        let (start, end) = self.lane_range(self.length);
+        ((end - start) / self.stepover() - 1e-9).ceil().max(0.0) as usize + 1
+    }
+
+    /// Why the routine can't be generated, if it can't.
+    pub fn problem(&self) -> Option<String> {
+        if self.surface.is_none() {
+            Some("Select the surface frame".to_string())
+        } else if self.width <= 0.0 || self.length <= 0.0 || self.sponge_width <= 0.0 {
+            Some("Area and sponge width must be above zero".to_string())
+        } else if !(0.0..=90.0).contains(&self.overlap) {
+            Some("Overlap must be between 0 and 90 %".to_string())
+        } else if self.force_threshold <= 0.0 || self.velocity <= 0.0 {
+            Some("Force threshold and velocity must be above zero".to_string())
+        } else if self.lane_count() > MAX_LANES {
+            Some(format!(
+                "{} lanes, more than {}. Check the sponge width and overlap.",
+                self.lane_count(),
+                MAX_LANES
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// Approach, the zig-zag and retract, in the order they are driven.
+    pub fn points(&self) -> Vec<WipePoint> {
+        let Some(surface) = &self.surface else {
+            return Vec::new();
+        };
+        let (x_start, x_end) = self.lane_range(self.width);
+        let (y_start, y_end) = self.lane_range(self.length);
+        let mut contact = Vec::new();
+        for lane in 0..self.lane_count() {
+            let y = (y_start + lane as f64 * self.stepover()).min(y_end);
+            let (from, to) = if lane % 2 == 0 {
+                (x_start, x_end)
+            } else {
+                (x_end, x_start)
+            };
+            contact.push([from, y, 0.0]);
+            contact.push([to, y, 0.0]);
+        }
+        let point = |name: String, translation: [f64; 3], contact: bool| WipePoint {
+            name,
+            pose: Pose {
+                translation,
+                ..Pose::default()
+            },
+            contact,
+        };
+        let above = |[x, y, _]: [f64; 3]| [x, y, self.approach_height];
+        let mut points = vec![point(
+            format!("{}_wipe_approach", surface),
+            above(contact[0]),
+            false,
+        )];
+        let last = *contact.last().unwrap_or(&[0.0; 3]);
+        points.extend(
+            contact
+                .into_iter()
+                .enumerate()
+                .map(|(i, t)| point(format!("{}_wipe_{}", surface, i + 1), t, true)),
+        );
+        points.push(point(
+            format!("{}_wipe_retract", surface),
+            above(last),
+            false,
+        ));
+        points
+    }
+
+    /// Settings of the routine. Returns true when Generate is clicked.
+    pub fn draw(&mut self, ui: &mut egui::Ui, transform_keys: &[String], busy: bool) -> bool {
+        let mut generate = false;
+        egui::Grid::new("wipe_routine_grid")
+            .num_columns(4)
+            .spacing([20.0, 4.0])
+            .show(ui, |ui| {
+                ui.label("Surface:");
+                egui::ComboBox::from_id_salt("wipe_surface_select")
+                    .selected_text(self.surface.as_deref().unwrap_or("Select..."))
+                    .show_ui(ui, |ui| {
+                        for key in transform_keys {
+                            ui.selectable_value(&mut self.surface, Some(key.clone()), key);
+                        }
+                    });
+                ui.label("Approach Height:");
+                ui.add(
+                    egui::DragValue::new(&mut self.approach_height)
+                        .localized_in(Unit::Meter)
+                        .suffix(" m")
+                        .speed(0.001)
+                        .range(0.0..=0.5),
+                );
+                ui.end_row();
+                ui.label("Width (X):");
+                ui.add(
+                    egui::DragValue::new(&mut self.width)
+                        .localized_in(Unit::Meter)
+                        .suffix(" m")
+                        .speed(0.001)
+                        .range(0.0..=5.0),
+                );
+                ui.label("Length (Y):");
+                ui.add(
+                    egui::DragValue::new(&mut self.length)
+                        .localized_in(Unit::Meter)
+                        .suffix(" m")
+                        .speed(0.001)
+                        .range(0.0..=5.0),
+                );
+                ui.end_row();
+                ui.label("Sponge Width:");
+                ui.add(
+                    egui::DragValue::new(&mut self.sponge_width)
+                        .localized_in(Unit::Meter)
+                        .suffix(" m")
+                        .speed(0.001)
+                        .range(0.0..=0.5),
+                );
+                ui.label("Overlap:");
+                ui.add(
+                    egui::DragValue::new(&mut self.overlap)
+                        .localized()
+                        .suffix(" %")
+                        .speed(1.0)
+                        .range(0.0..=90.0),
+                );
+                ui.end_row();
+                ui.label("Force Threshold:");
+                ui.add(
+                    egui::DragValue::new(&mut self.force_threshold)
+                        .localized()
+                        .suffix(" N")
+                        .speed(0.1)
+                        .range(0.0..=200.0),
+                );
+                ui.label("Velocity:");
+                ui.add(
+                    egui::DragValue::new(&mut self.velocity)
+                        .localized()
+                        .suffix(" m/s")
+                        .speed(0.01)
+                        .range(0.0..=1.0),
+                );
+                ui.end_row();
+            });
+        match self.problem() {
+            Some(problem) => {
+                ui.colored_label(egui::Color32::YELLOW, problem);
+            }
+            None => {
+                ui.horizontal(|ui| {
+                    ui.label(format!(
+                        "{} lanes, {} waypoints",
+                        self.lane_count(),
+                        self.points().len()
+                    ));
+                    generate = ui
+                        .add_enabled(!busy, egui::Button::new("Generate"))
+                        .on_hover_text(
+                            "Publish the waypoints under the surface frame and append them \n\
+                             to the path. Generating again overwrites the same frames.",
+                        )
+                        .clicked();
+                    if busy {
+                        ui.spinner();
+                    }
+                });
+            }
+        }
+        generate
+    }
+}
+
+/// Publishes the waypoints under `surface`, stopping at the first failure.
+pub async fn publish_points(
+    con: Arc<ConnectionManager>,
+    surface: String,
+    points: Vec<WipePoint>,
+) -> Result<Vec<WipePoint>, String> {
+    for point in &points {
+        let mut transform = pose::make_transform_stamped(
+            &surface,
+            &point.name,
+            &point.pose,
+            &FrameMetadata::for_frame(&point.name),
+        );
+        provenance::stamp(&mut transform, Method::Computed);
+        crate::transforms::publish_transform(con.clone(), transform).await?;
+    }
+    Ok(points)
+}
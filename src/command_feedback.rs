@@ -0,0 +1,134 @@
+//! What became of the last command sent from the Robot tab, read back from
+//! `{robot}_request_state` so the operator doesn't have to follow the runner
+//! logs. A failure shows the driver's `{robot}_fail_reason` when it has one.
+
+use crate::backend::COMMAND_TIMEOUT;
+use crate::state_poller::{StatePoller, sp_value_to_display};
+use eframe::egui;
+use std::time::{Duration, Instant};
+
+/// Until the first poll after sending, the state can still be the last command's.
+const GRACE: Duration = Duration::from_secs(1);
+
+pub fn keys(robot_id: &str) -> Vec<String> {
+    vec![
+        format!("{}_request_state", robot_id),
+        format!("{}_fail_reason", robot_id),
+    ]
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Outcome {
+    /// Written, the runner hasn't picked it up yet.
+    Sent,
+    Executing,
+    Succeeded,
+    Failed(Option<String>),
+    TimedOut,
+}
+
+struct SentCommand {
+    robot_id: String,
+    command: String,
+    goal: Option<String>,
+    at: Instant,
+    outcome: Outcome,
+    /// Seconds from sending to the outcome, once there is one.
+    took: Option<f64>,
+}
+
+#[derive(Default)]
+pub struct CommandFeedback {
+    last: Option<SentCommand>,
+}
+
+impl CommandFeedback {
+    /// Call when a command is written to the state.
+    pub fn sent(&mut self, robot_id: &str, command: &str, goal: Option<&str>) {
+        self.last = Some(SentCommand {
+            robot_id: robot_id.to_string(),
+            command: command.to_string(),
+            goal: goal.map(str::to_string),
+            at: Instant::now(),
+            outcome: Outcome::Sent,
+            took: None,
+        });
+    }
+
+    /// Call every frame with the polled state.
+    pub fn update(&mut self, poller: &StatePoller) {
+        let Some(last) = &mut self.last else {
+            return;
+        };
+        if last.took.is_some() {
+            return;
+        }
+        let elapsed = last.at.elapsed();
+        let state = sp_value_to_display(poller.get(&format!("{}_request_state", last.robot_id)));
+        let outcome = match state.as_str() {
+            "executing" => Outcome::Executing,
+            "succeeded" if elapsed > GRACE || last.outcome == Outcome::Executing => {
+                Outcome::Succeeded
+            }
+            "failed" if elapsed > GRACE || last.outcome == Outcome::Executing => {
+                let reason =
+                    sp_value_to_display(poller.get(&format!("{}_fail_reason", last.robot_id)));
+                Outcome::Failed((reason != "-" && !reason.is_empty()).then_some(reason))
+            }
+            _ if elapsed > COMMAND_TIMEOUT => Outcome::TimedOut,
+            _ => last.outcome.clone(),
+        };
+        if matches!(
+            outcome,
+            Outcome::Succeeded | Outcome::Failed(_) | Outcome::TimedOut
+        ) {
+            last.took = Some(elapsed.as_secs_f64());
+        }
+        last.outcome = outcome;
+    }
+
+    pub fn draw(&self, ui: &mut egui::Ui) {
+        let Some(last) = &self.last else {
+            return;
+        };
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Last command:");
+            ui.monospace(match &last.goal {
+                Some(goal) => format!("{} → {}", last.command, goal),
+                None => last.command.clone(),
+            });
+            match &last.outcome {
+                Outcome::Sent => {
+                    ui.spinner();
+                    ui.label("waiting for the runner");
+                }
+                Outcome::Executing => {
+                    ui.spinner();
+                    ui.label(format!(
+                        "executing, {:.0} s",
+                        last.at.elapsed().as_secs_f64()
+                    ));
+                }
+                Outcome::Succeeded => {
+                    ui.colored_label(egui::Color32::GREEN, "succeeded");
+                }
+                Outcome::Failed(reason) => {
+                    ui.colored_label(egui::Color32::RED, "failed");
+                    match reason {
+                        Some(reason) => ui.colored_label(egui::Color32::RED, reason),
+                        None => ui.weak("the driver gave no reason"),
+                    };
+                }
+                Outcome::TimedOut => {
+                    ui.colored_label(
+                        egui::Color32::YELLOW,
+                        format!("no result after {} s", COMMAND_TIMEOUT.as_secs()),
+                    );
+                }
+            }
+            if let Some(took) = last.took {
+                ui.weak(format!("({:.1} s)", took));
+            }
+        });
+    }
+}
@@ -0,0 +1,125 @@
+use eframe::egui;
+
+/// Force thresholds at or above this never trigger the safe stop in practice.
+const MAX_USEFUL_FORCE_THRESHOLD: f64 = 200.0;
+
+/// Snapshot of the Robot Controller parameters the lint rules look at.
+pub struct CommandParams {
+    pub is_safe_move: bool,
+    pub has_goal: bool,
+    pub acceleration: f64,
+    pub velocity: f64,
+    pub use_blend_radius: bool,
+    pub blend_radius: f64,
+    pub use_execution_time: bool,
+    pub execution_time: f64,
+    pub force_threshold: f64,
+    pub use_joint_positions: bool,
+    pub use_preferred_joint_config: bool,
+    pub joint_positions_in_limits: bool,
+    pub preferred_joint_config_in_limits: bool,
+    /// Mass of the manually entered payload, if one is used.
+    pub manual_payload_mass: Option<f64>,
+    pub use_relative_pose: bool,
+    pub relative_pose: [f64; 6],
+}
+
+pub struct LintRule {
+    applies: fn(&CommandParams) -> bool,
+    pub message: &'static str,
+    pub explanation: &'static str,
+}
+
+const RULES: &[LintRule] = &[
+    LintRule {
+        applies: |p| p.use_blend_radius && p.blend_radius > 0.0 && p.use_execution_time,
+        message: "Blend radius > 0 with execution time set is ignored by the driver",
+        explanation: "A move with an execution time is planned as a timed move and always \
+                      stops at its target, so the blend radius has no effect.",
+    },
+    LintRule {
+        applies: |p| p.use_blend_radius && p.blend_radius <= 0.0,
+        message: "Blend radius is enabled but 0 m",
+        explanation: "A blend radius of 0 m stops at the target exactly like an unblended move.",
+    },
+    LintRule {
+        applies: |p| p.use_execution_time && p.execution_time <= 0.0,
+        message: "Execution time is enabled but 0",
+        explanation: "An execution time of 0 is treated as not set, so the move uses the \
+                      velocity and acceleration instead.",
+    },
+    LintRule {
+        applies: |p| p.is_safe_move && p.force_threshold >= MAX_USEFUL_FORCE_THRESHOLD,
+        message: "Force threshold of 200 N effectively disables safe stop",
+        explanation: "The force monitor only stops the robot above the threshold. At 200 N \
+                      the robot enters protective stop before the threshold is reached, \
+                      which is what a safe move is meant to avoid.",
+    },
+    LintRule {
+        applies: |p| p.velocity <= 0.0 || p.acceleration <= 0.0,
+        message: "Velocity or acceleration is 0",
+        explanation: "The driver rejects or never finishes moves without velocity or \
+                      acceleration.",
+    },
+    LintRule {
+        applies: |p| p.use_joint_positions && p.use_preferred_joint_config,
+        message: "Preferred joint config is ignored when joint positions are used",
+        explanation: "The preferred configuration is a hint for the inverse kinematics. \
+                      With joint positions as target no inverse kinematics is solved.",
+    },
+    LintRule {
+        applies: |p| p.use_joint_positions && !p.joint_positions_in_limits,
+        message: "Joint positions are outside the soft limits",
+        explanation: "The soft limits of this robot keep the joints away from cables and \
+                      fixtures. They are set under Joint Soft Limits.",
+    },
+    LintRule {
+        applies: |p| {
+            p.use_preferred_joint_config
+                && !p.use_joint_positions
+                && !p.preferred_joint_config_in_limits
+        },
+        message: "Preferred joint config is outside the soft limits",
+        explanation: "The inverse kinematics picks the solution closest to the preferred \
+                      configuration, which then likely violates the soft limits as well.",
+    },
+    LintRule {
+        applies: |p| !p.use_joint_positions && !p.has_goal,
+        message: "No goal feature selected",
+        explanation: "Without joint positions the driver moves the TCP to the goal feature. \
+                      The command will fail if none is set.",
+    },
+    LintRule {
+        applies: |p| p.use_relative_pose && p.relative_pose.iter().all(|v| *v == 0.0),
+        message: "Relative pose is all zeros",
+        explanation: "A zero relative pose does not offset the goal, so the checkbox has no \
+                      effect.",
+    },
+    LintRule {
+        applies: |p| p.manual_payload_mass.is_some_and(|mass| mass <= 0.0),
+        message: "Manual payload mass is 0 kg",
+        explanation: "Setting a payload without mass tells the controller the tool is empty, \
+                      which can trigger protective stops when it actually carries a load.",
+    },
+];
+
+/// Rules that match the parameters, in table order.
+pub fn lint_command(params: &CommandParams) -> Vec<&'static LintRule> {
+    RULES.iter().filter(|rule| (rule.applies)(params)).collect()
+}
+
+/// Non-blocking warnings; hovering a warning shows why it matters.
+pub fn draw_command_lint(ui: &mut egui::Ui, warnings: &[&LintRule]) {
+    if warnings.is_empty() {
+        return;
+    }
+    egui::Frame::default()
+        .inner_margin(egui::Margin::symmetric(8, 4))
+        .fill(ui.visuals().faint_bg_color)
+        .show(ui, |ui| {
+            for warning in warnings {
+                ui.colored_label(egui::Color32::YELLOW, format!("⚠ {}", warning.message))
+                    .on_hover_text(warning.explanation);
+            }
+        });
+}
@@ -0,0 +1,130 @@
+//! Commands collected from the Robot tab form and run one after the other,
+//! each sent only once the previous one reports `succeeded`. Commands are
+//! retried per the retry policy of their type, the queue stops at the first
+//! command that still fails or times out.
+
+use crate::backend;
+use crate::sequence::{self, Run, Step};
+use eframe::egui;
+use micro_sp::*;
+use std::sync::Arc;
+
+#[derive(Default)]
+pub struct CommandQueue {
+    /// The form as it was when each command was queued.
+    commands: Vec<Step>,
+    run: Option<Run>,
+    status: Option<Result<String, String>>,
+}
+
+impl CommandQueue {
+    pub fn push(&mut self, command: Step) {
+        self.commands.push(command);
+    }
+
+    /// Shown under the queue, e.g. when the form can't be queued.
+    pub fn set_error(&mut self, error: String) {
+        self.status = Some(Err(error));
+    }
+
+    pub fn run(&self) -> Option<&Run> {
+        self.run.as_ref()
+    }
+
+    fn poll(&mut self) {
+        let Some(run) = self.run.take() else {
+            return;
+        };
+        match run.finish() {
+            Ok(outcome) => {
+                // Finished commands leave the queue, the failed one stays for a retry
+                self.commands = self.commands.split_off(outcome.done);
+                let retries = outcome.retries_text();
+                self.status = Some(match outcome.error {
+                    None => Ok(format!("Ran {} commands{}", outcome.done, retries)),
+                    Some(e) => Err(format!("{} done{}, then {}", outcome.done, retries, e)),
+                });
+            }
+            Err(run) => self.run = Some(run),
+        }
+    }
+
+    /// The queue with its controls, `blocked` being why it can't run now.
+    /// Returns true when the current form should be added.
+    pub fn draw(
+        &mut self,
+        ui: &mut egui::Ui,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+        blocked: Option<String>,
+    ) -> bool {
+        self.poll();
+        let running = self.run.as_ref().map(Run::current);
+        let add = ui
+            .add_enabled(running.is_none(), egui::Button::new("Add Current Command"))
+            .on_hover_text("Queue the command as it is set up above")
+            .clicked();
+
+        let mut edit = None;
+        let count = self.commands.len();
+        egui::Grid::new("command_queue_grid")
+            .num_columns(3)
+            .striped(true)
+            .show(ui, |ui| {
+                for (i, command) in self.commands.iter().enumerate() {
+                    sequence::draw_row_number(ui, i, running);
+                    ui.monospace(&command.label);
+                    if let Some(row_edit) =
+                        sequence::draw_row_controls(ui, i, count, running.is_none())
+                    {
+                        edit = Some(row_edit);
+                    }
+                    ui.end_row();
+                }
+            });
+        if let Some(edit) = edit {
+            edit.apply(&mut self.commands);
+        }
+        if self.commands.is_empty() {
+            ui.weak("The queue is empty.");
+        }
+
+        ui.horizontal(|ui| match &self.run {
+            Some(run) => {
+                if ui
+                    .button("Stop Queue")
+                    .on_hover_text("Stop after the running command")
+                    .clicked()
+                {
+                    run.cancel();
+                }
+            }
+            None => {
+                let run = ui
+                    .add_enabled(
+                        blocked.is_none() && !self.commands.is_empty(),
+                        egui::Button::new("Run Queue"),
+                    )
+                    .on_disabled_hover_text(blocked.clone().unwrap_or_default());
+                if run.clicked() {
+                    self.status = None;
+                    self.run = Some(Run::start(
+                        "command_queue",
+                        handle,
+                        connection,
+                        self.commands.clone(),
+                        backend::COMMAND_TIMEOUT,
+                    ));
+                }
+                if ui
+                    .add_enabled(!self.commands.is_empty(), egui::Button::new("Clear"))
+                    .clicked()
+                {
+                    self.commands.clear();
+                }
+            }
+        });
+        sequence::draw_status(ui, &self.status);
+        add
+    }
+}
@@ -0,0 +1,318 @@
+//! Commissioning test move: a small, slow square in the baseframe XY plane
+//! around the current TCP pose, reporting timing, overshoot and force per
+//! leg. A quick sanity check after driver or payload changes.
+
+use crate::backend;
+use crate::locale::{self, LocalizedDragValue};
+use crate::pose::Pose;
+use crate::provenance::Method;
+use crate::workers;
+use eframe::egui;
+use micro_sp::*;
+use poll_promise::Promise;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+const ACCELERATION: f64 = 0.05;
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(50);
+const LEG_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Frames the test move needs from the Robot tab.
+#[derive(Debug, Clone)]
+pub struct TestMoveFrames {
+    pub robot_id: String,
+    pub baseframe: String,
+    pub faceplate: String,
+    pub tcp: String,
+}
+
+pub struct LegReport {
+    pub seconds: f64,
+    /// Meters between the reached and the commanded corner.
+    pub final_error: f64,
+    /// Meters the TCP went past the corner along the leg.
+    pub overshoot: f64,
+    /// N, `None` when the driver doesn't write `{robot}_force_feedback`.
+    pub peak_force: Option<f64>,
+}
+
+fn distance(a: [f64; 3], b: [f64; 3]) -> f64 {
+    (0..3).map(|i| (a[i] - b[i]).powi(2)).sum::<f64>().sqrt()
+}
+
+/// Moves the TCP to `target` and samples it on the way.
+async fn run_leg(
+    con: Arc<ConnectionManager>,
+    frames: &TestMoveFrames,
+    goal: String,
+    from: [f64; 3],
+    target: [f64; 3],
+    velocity: f64,
+) -> Result<LegReport, String> {
+    let parameters = vec![
+        ("command_type".to_string(), "unsafe_move_l".to_spvalue()),
+        ("accelleration".to_string(), ACCELERATION.to_spvalue()),
+        ("velocity".to_string(), velocity.to_spvalue()),
+        ("use_execution_time".to_string(), false.to_spvalue()),
+        ("use_blend_radius".to_string(), false.to_spvalue()),
+        ("use_joint_positions".to_string(), false.to_spvalue()),
+        ("use_preferred_joint_config".to_string(), false.to_spvalue()),
+        ("use_relative_pose".to_string(), false.to_spvalue()),
+        (
+            "baseframe_id".to_string(),
+            frames.baseframe.as_str().to_spvalue(),
+        ),
+        (
+            "faceplate_id".to_string(),
+            frames.faceplate.as_str().to_spvalue(),
+        ),
+        ("goal_feature_id".to_string(), goal.as_str().to_spvalue()),
+        ("tcp_id".to_string(), frames.tcp.as_str().to_spvalue()),
+        ("root_frame_id".to_string(), "world".to_spvalue()),
+    ];
+    let started = Instant::now();
+    let robot_id = frames.robot_id.clone();
+    let send_con = con.clone();
+    let send = tokio::spawn(async move {
+        backend::send_command(send_con, &robot_id, parameters, LEG_TIMEOUT).await
+    });
+
+    let length = distance(from, target).max(1e-9);
+    let direction = [0, 1, 2].map(|i| (target[i] - from[i]) / length);
+    let force_key = crate::vibration::force_feedback_key(&frames.robot_id);
+    let mut overshoot: f64 = 0.0;
+    let mut peak_force: Option<f64> = None;
+    while !send.is_finished() {
+        tokio::time::sleep(SAMPLE_INTERVAL).await;
+        if let Ok(pose) = backend::lookup(con.clone(), &frames.baseframe, &frames.tcp).await {
+            let past = (0..3)
+                .map(|i| (pose.translation[i] - target[i]) * direction[i])
+                .sum::<f64>();
+            overshoot = overshoot.max(past);
+        }
+        if let Some(SPValue::Float64(FloatOrUnknown::Float64(force))) =
+            backend::get_value(con.clone(), &force_key).await
+        {
+            peak_force = Some(peak_force.unwrap_or(0.0).max(force.0.abs()));
+        }
+    }
+    send.await.map_err(|e| e.to_string())??;
+    let seconds = started.elapsed().as_secs_f64();
+    let reached = backend::lookup(con, &frames.baseframe, &frames.tcp).await?;
+    Ok(LegReport {
+        seconds,
+        final_error: distance(reached.translation, target),
+        overshoot,
+        peak_force,
+    })
+}
+
+/// Publishes the corners as `{robot}_test_move_1..4` under the baseframe
+/// and drives the square, back to the start.
+async fn run_test_move(
+    con: Arc<ConnectionManager>,
+    frames: TestMoveFrames,
+    side: f64,
+    velocity: f64,
+) -> Result<Vec<LegReport>, String> {
+    let start = backend::lookup(con.clone(), &frames.baseframe, &frames.tcp).await?;
+    let offsets = [[side, 0.0], [side, side], [0.0, side], [0.0, 0.0]];
+    let mut legs = Vec::new();
+    let mut from = start.translation;
+    for (i, [dx, dy]) in offsets.into_iter().enumerate() {
+        let goal = format!("{}_test_move_{}", frames.robot_id, i + 1);
+        let corner = Pose {
+            translation: [
+                start.translation[0] + dx,
+                start.translation[1] + dy,
+                start.translation[2],
+            ],
+            ..start
+        };
+        backend::publish_frame(
+            con.clone(),
+            &frames.baseframe,
+            &goal,
+            &corner,
+            Method::Computed,
+        )
+        .await?;
+        let leg = run_leg(
+            con.clone(),
+            &frames,
+            goal,
+            from,
+            corner.translation,
+            velocity,
+        )
+        .await
+        .map_err(|e| format!("Leg {}: {}", i + 1, e))?;
+        legs.push(leg);
+        from = corner.translation;
+    }
+    Ok(legs)
+}
+
+pub struct TestMove {
+    /// Side of the square, meters.
+    side: f64,
+    /// m/s.
+    velocity: f64,
+    confirming: Option<TestMoveFrames>,
+    promise: Option<Promise<Result<Vec<LegReport>, String>>>,
+    result: Option<Result<Vec<LegReport>, String>>,
+}
+
+impl TestMove {
+    pub fn new() -> Self {
+        Self {
+            side: 0.05,
+            velocity: 0.02,
+            confirming: None,
+            promise: None,
+            result: None,
+        }
+    }
+
+    pub fn draw(
+        &mut self,
+        ui: &mut egui::Ui,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+        frames: Result<TestMoveFrames, String>,
+        enabled: bool,
+    ) {
+        if let Some(promise) = self.promise.take() {
+            match promise.try_take() {
+                Ok(result) => self.result = Some(result),
+                Err(promise) => self.promise = Some(promise),
+            }
+        }
+        ui.label(
+            "Moves the TCP around a square in the baseframe XY plane, starting and \
+             ending at the current pose.",
+        );
+        ui.horizontal(|ui| {
+            ui.label("Side:");
+            ui.add(
+                egui::DragValue::new(&mut self.side)
+                    .localized()
+                    .suffix(" m")
+                    .speed(0.001)
+                    .range(0.01..=0.2),
+            );
+            ui.label("Velocity:");
+            ui.add(
+                egui::DragValue::new(&mut self.velocity)
+                    .localized()
+                    .suffix(" m/s")
+                    .speed(0.001)
+                    .range(0.005..=0.1),
+            );
+            let running = self.promise.is_some();
+            let button = ui
+                .add_enabled(
+                    enabled && !running && frames.is_ok(),
+                    egui::Button::new("Run Test Move…"),
+                )
+                .on_disabled_hover_text(match &frames {
+                    Err(e) => e.clone(),
+                    Ok(_) if !enabled => "This robot is disabled in the Resources tab.".to_string(),
+                    Ok(_) => "A test move is running.".to_string(),
+                });
+            if button.clicked() {
+                self.confirming = frames.ok();
+            }
+            if running {
+                ui.spinner();
+            }
+        });
+        self.draw_confirmation(ui.ctx(), handle, connection);
+        match &self.result {
+            None => (),
+            Some(Err(e)) => {
+                ui.colored_label(egui::Color32::RED, e);
+            }
+            Some(Ok(legs)) => draw_report(ui, legs),
+        }
+    }
+
+    fn draw_confirmation(
+        &mut self,
+        ctx: &egui::Context,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+    ) {
+        let Some(frames) = self.confirming.clone() else {
+            return;
+        };
+        let mut start = false;
+        let mut cancel = false;
+        egui::Window::new("Run test move?")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{} will move {} of {} around a {} mm square at {} mm/s.",
+                    frames.robot_id,
+                    frames.tcp,
+                    frames.baseframe,
+                    locale::format_number(self.side * 1000.0, 0),
+                    locale::format_number(self.velocity * 1000.0, 0)
+                ));
+                ui.label("Make sure the area around the tool is clear.");
+                ui.horizontal(|ui| {
+                    start = ui.button("Start").clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+        if cancel {
+            self.confirming = None;
+        }
+        if start {
+            self.confirming = None;
+            self.result = None;
+            let (side, velocity) = (self.side, self.velocity);
+            let handle = handle.clone();
+            let con_clone = connection.clone();
+            self.promise = Some(workers::spawn_long("test_move", move || {
+                handle.block_on(run_test_move(con_clone, frames, side, velocity))
+            }));
+        }
+    }
+}
+
+fn draw_report(ui: &mut egui::Ui, legs: &[LegReport]) {
+    let mm = |x: f64| format!("{} mm", locale::format_number(x * 1000.0, 2));
+    egui::Grid::new("test_move_report")
+        .num_columns(5)
+        .striped(true)
+        .show(ui, |ui| {
+            ui.strong("Leg");
+            ui.strong("Time");
+            ui.strong("Final error");
+            ui.strong("Overshoot");
+            ui.strong("Peak force");
+            ui.end_row();
+            for (i, leg) in legs.iter().enumerate() {
+                ui.label(format!("{}", i + 1));
+                ui.monospace(format!("{} s", locale::format_number(leg.seconds, 2)));
+                ui.monospace(mm(leg.final_error));
+                ui.monospace(mm(leg.overshoot));
+                match leg.peak_force {
+                    Some(force) => ui.monospace(format!("{} N", locale::format_number(force, 1))),
+                    None => ui.weak("-"),
+                };
+                ui.end_row();
+            }
+        });
+    let total = legs.iter().map(|l| l.seconds).sum::<f64>();
+    ui.label(format!(
+        "Total {} s, largest final error {}",
+        locale::format_number(total, 2),
+        mm(legs.iter().map(|l| l.final_error).fold(0.0, f64::max))
+    ));
+}
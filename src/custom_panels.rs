@@ -0,0 +1,393 @@
+//! Cell-specific panels defined in `custom_panels.toml` in the data directory,
+//! so site engineers can expose switches and readouts of their own state
+//! variables without changing the GUI. Each widget is a label, a state
+//! variable and a widget type.
+
+use crate::locale::LocalizedDragValue;
+use crate::state_poller::{StatePoller, sp_value_to_display};
+use crate::storage;
+use crate::workers;
+use eframe::egui;
+use micro_sp::*;
+use ordered_float::OrderedFloat;
+use poll_promise::Promise;
+use serde::Deserialize;
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+pub const CUSTOM_PANELS_FILE: &str = "custom_panels.toml";
+
+pub fn check_file() -> Option<Result<(), String>> {
+    storage::check_file(CUSTOM_PANELS_FILE, |content| {
+        toml::from_str::<PanelsFile>(content)
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    })
+}
+
+/// A write that isn't read back by then is reported as failed.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Written by "Create Example" so engineers start from a working file.
+const EXAMPLE: &str = r#"# Custom panels of the "Custom" tab. Reload the tab after editing.
+# Widget types: toggle, button, number, text, display.
+
+[[panel]]
+title = "Cell"
+
+[[panel.widget]]
+label = "Conveyor running"
+variable = "conveyor_run"
+type = "toggle"
+
+[[panel.widget]]
+label = "Reset light curtain"
+variable = "light_curtain_reset"
+type = "button"
+
+[[panel.widget]]
+label = "Conveyor speed"
+variable = "conveyor_speed"
+type = "number"
+unit = "m/s"
+min = 0.0
+max = 1.0
+
+[[panel.widget]]
+label = "Part counter"
+variable = "part_count"
+type = "display"
+"#;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum WidgetType {
+    /// Checkbox writing a bool.
+    Toggle,
+    /// Writes true when clicked, like a request trigger.
+    Button,
+    /// Float with a Set button.
+    Number,
+    /// String with a Set button.
+    Text,
+    /// Read only.
+    Display,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Widget {
+    label: String,
+    variable: String,
+    #[serde(rename = "type")]
+    widget_type: WidgetType,
+    #[serde(default)]
+    unit: Option<String>,
+    #[serde(default)]
+    min: Option<f64>,
+    #[serde(default)]
+    max: Option<f64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Panel {
+    title: String,
+    #[serde(default, rename = "widget")]
+    widgets: Vec<Widget>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct PanelsFile {
+    #[serde(default, rename = "panel")]
+    panels: Vec<Panel>,
+}
+
+/// `None` if the file doesn't exist.
+fn load_panels() -> Option<Result<Vec<Panel>, String>> {
+    let path = storage::data_file(CUSTOM_PANELS_FILE);
+    if !path.exists() {
+        return None;
+    }
+    let result = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {:?}: {}", path, e))
+        .and_then(|content| {
+            toml::from_str::<PanelsFile>(&content)
+                .map_err(|e| format!("Failed to parse {:?}: {}", path, e))
+        })
+        .map(|file| file.panels);
+    if let Err(e) = &result {
+        log::error!("{}", e);
+    }
+    Some(result)
+}
+
+/// Writes `value` to `key`. The write itself reports nothing, so values are
+/// read back, except for buttons whose trigger the cell may reset at once.
+async fn write_value(
+    con: Arc<ConnectionManager>,
+    key: String,
+    value: SPValue,
+    read_back: bool,
+) -> Result<(), String> {
+    let read = tokio::time::timeout(WRITE_TIMEOUT, async {
+        crate::backend::set_values(con.clone(), vec![(key.clone(), value.clone())]).await;
+        crate::backend::get_value(con, &key).await
+    })
+    .await
+    .map_err(|_| {
+        format!(
+            "No answer writing {} after {} s",
+            key,
+            WRITE_TIMEOUT.as_secs()
+        )
+    })?;
+    if read_back && read.as_ref() != Some(&value) {
+        return Err(format!(
+            "{} reads {} after writing {}",
+            key,
+            sp_value_to_display(read.as_ref()),
+            sp_value_to_display(Some(&value))
+        ));
+    }
+    Ok(())
+}
+
+/// Edit buffer of a number or text widget, only kept once the operator
+/// changed it. Until then the widget shows the live value.
+#[derive(Debug)]
+enum Draft {
+    Number(f64),
+    Text(String),
+}
+
+pub struct CustomTab {
+    panels: Option<Result<Vec<Panel>, String>>,
+    /// Keyed by variable.
+    drafts: HashMap<String, Draft>,
+    write_promise: Option<Promise<Result<(), String>>>,
+    write_error: Option<String>,
+}
+
+impl CustomTab {
+    pub fn new() -> Self {
+        Self {
+            panels: load_panels(),
+            drafts: HashMap::new(),
+            write_promise: None,
+            write_error: None,
+        }
+    }
+
+    /// Whether a widget value is still being written, checked on exit.
+    pub fn writing(&self) -> bool {
+        self.write_promise
+            .as_ref()
+            .is_some_and(|promise| promise.ready().is_none())
+    }
+
+    /// Variables the panels show.
+    pub fn keys(&self) -> Vec<String> {
+        match &self.panels {
+            Some(Ok(panels)) => panels
+                .iter()
+                .flat_map(|panel| panel.widgets.iter())
+                .map(|widget| widget.variable.clone())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn reload(&mut self) {
+        self.panels = load_panels();
+        self.drafts.clear();
+    }
+
+    pub fn ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+        poller: &StatePoller,
+    ) {
+        if let Some(promise) = self.write_promise.take() {
+            match promise.try_take() {
+                Ok(result) => self.write_error = result.err(),
+                Err(promise) => self.write_promise = Some(promise),
+            }
+        }
+
+        ui.horizontal(|ui| {
+            ui.heading("Custom");
+            if ui
+                .button("⟳")
+                .on_hover_text("Reload the panels file")
+                .clicked()
+            {
+                self.reload();
+            }
+            if self.write_promise.is_some() {
+                ui.spinner();
+            }
+        });
+        if let Some(e) = &self.write_error {
+            ui.colored_label(egui::Color32::RED, e);
+        }
+        ui.weak(format!(
+            "Panels from {}",
+            storage::data_file(CUSTOM_PANELS_FILE).display()
+        ));
+        ui.separator();
+
+        let mut write = None;
+        match &self.panels {
+            None => {
+                ui.label("No panels file yet. Site engineers define panels in it.");
+                if ui.button("Create Example").clicked() {
+                    let path = storage::data_file(CUSTOM_PANELS_FILE);
+                    let result = std::fs::create_dir_all(storage::data_dir())
+                        .and_then(|_| std::fs::write(&path, EXAMPLE));
+                    if let Err(e) = result {
+                        log::error!("Failed to write {:?}: {}", path, e);
+                    }
+                    self.reload();
+                }
+            }
+            Some(Err(e)) => {
+                ui.colored_label(egui::Color32::RED, e);
+            }
+            Some(Ok(panels)) if panels.is_empty() => {
+                ui.weak("The panels file defines no panels.");
+            }
+            Some(Ok(panels)) => {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for (i, panel) in panels.iter().enumerate() {
+                        egui::CollapsingHeader::new(&panel.title)
+                            .id_salt(("custom_panel", i))
+                            .default_open(true)
+                            .show(ui, |ui| {
+                                egui::Grid::new(("custom_panel_grid", i))
+                                    .num_columns(2)
+                                    .spacing([20.0, 6.0])
+                                    .show(ui, |ui| {
+                                        for widget in &panel.widgets {
+                                            ui.label(&widget.label).on_hover_text(&widget.variable);
+                                            let live = poller.get(&widget.variable);
+                                            if let Some(value) =
+                                                draw_widget(ui, widget, live, &mut self.drafts)
+                                            {
+                                                write = Some((
+                                                    widget.variable.clone(),
+                                                    value,
+                                                    widget.widget_type != WidgetType::Button,
+                                                ));
+                                            }
+                                            ui.end_row();
+                                        }
+                                    });
+                            });
+                    }
+                });
+            }
+        }
+
+        if let Some((key, value, read_back)) = write {
+            let handle = handle.clone();
+            let con_clone = connection.clone();
+            self.drafts.remove(&key);
+            self.write_error = None;
+            self.write_promise = Some(workers::spawn("custom_panel_write", move || {
+                handle.block_on(write_value(con_clone, key, value, read_back))
+            }));
+        }
+    }
+}
+
+/// Returns the value to write when the widget was used.
+fn draw_widget(
+    ui: &mut egui::Ui,
+    widget: &Widget,
+    live: Option<&SPValue>,
+    drafts: &mut HashMap<String, Draft>,
+) -> Option<SPValue> {
+    let mut write = None;
+    match widget.widget_type {
+        WidgetType::Toggle => {
+            let mut on = matches!(live, Some(SPValue::Bool(BoolOrUnknown::Bool(true))));
+            ui.horizontal(|ui| {
+                if ui.checkbox(&mut on, "").changed() {
+                    write = Some(on.to_spvalue());
+                }
+                if !matches!(live, Some(SPValue::Bool(BoolOrUnknown::Bool(_)))) {
+                    ui.weak(sp_value_to_display(live));
+                }
+            });
+        }
+        WidgetType::Button => {
+            if ui.button("Trigger").clicked() {
+                write = Some(true.to_spvalue());
+            }
+        }
+        WidgetType::Number => {
+            let live_number = match live {
+                Some(SPValue::Float64(FloatOrUnknown::Float64(OrderedFloat(x)))) => Some(*x),
+                _ => None,
+            };
+            ui.horizontal(|ui| {
+                let draft = match drafts.get(&widget.variable) {
+                    Some(Draft::Number(number)) => Some(*number),
+                    _ => None,
+                };
+                let mut number = draft.or(live_number).unwrap_or_default();
+                let mut drag = egui::DragValue::new(&mut number).localized().speed(0.01);
+                if let Some(unit) = &widget.unit {
+                    drag = drag.suffix(format!(" {}", unit));
+                }
+                drag = drag.range(
+                    widget.min.unwrap_or(f64::NEG_INFINITY)..=widget.max.unwrap_or(f64::INFINITY),
+                );
+                if ui.add(drag).changed() {
+                    drafts.insert(widget.variable.clone(), Draft::Number(number));
+                }
+                if ui
+                    .add_enabled(
+                        draft.is_some() || live_number.is_some(),
+                        egui::Button::new("Set"),
+                    )
+                    .clicked()
+                {
+                    write = Some(number.to_spvalue());
+                }
+                ui.weak(format!("live: {}", sp_value_to_display(live)));
+            });
+        }
+        WidgetType::Text => {
+            let live_text = match live {
+                Some(SPValue::String(StringOrUnknown::String(text))) => Some(text.clone()),
+                _ => None,
+            };
+            ui.horizontal(|ui| {
+                let draft = match drafts.get(&widget.variable) {
+                    Some(Draft::Text(text)) => Some(text.clone()),
+                    _ => None,
+                };
+                let edited = draft.is_some();
+                let mut text = draft.or(live_text.clone()).unwrap_or_default();
+                if ui
+                    .add(egui::TextEdit::singleline(&mut text).desired_width(200.0))
+                    .changed()
+                {
+                    drafts.insert(widget.variable.clone(), Draft::Text(text.clone()));
+                }
+                if ui
+                    .add_enabled(edited || live_text.is_some(), egui::Button::new("Set"))
+                    .clicked()
+                {
+                    write = Some(text.as_str().to_spvalue());
+                }
+                ui.weak(format!("live: {}", sp_value_to_display(live)));
+            });
+        }
+        WidgetType::Display => {
+            ui.monospace(sp_value_to_display(live));
+        }
+    }
+    write
+}
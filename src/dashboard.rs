@@ -0,0 +1,191 @@
+//! The robot driver's dashboard: commands that act on the controller rather
+//! than move the robot, sent as `{robot}_dashboard_command` with the dashboard
+//! request trigger, and the global velocity and acceleration scaling. Pause,
+//! Continue and Reset Protective Stop stay in the tab's header row and send
+//! their commands the same way.
+
+use crate::backend::{self, RequestError};
+use crate::locale::LocalizedDragValue;
+use crate::speed_override;
+use crate::workers;
+use eframe::egui;
+use micro_sp::*;
+use poll_promise::Promise;
+use std::{sync::Arc, time::Duration};
+
+/// Dashboard commands don't move the robot, they answer quickly.
+const DASHBOARD_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Label, dashboard command and what it does.
+const COMMANDS: [(&str, &str, &str); 3] = [
+    ("Power On", "power_on", "Power on the robot arm"),
+    (
+        "Brake Release",
+        "brake_release",
+        "Release the brakes, the robot is then ready to run",
+    ),
+    (
+        "Remote Control",
+        "enter_remote_control",
+        "Put the controller in remote control so it accepts commands from the driver",
+    ),
+];
+
+/// Writes the dashboard command and waits for `{robot}_dashboard_request_state`.
+/// Only the `{robot}_dashboard_*` variables are written.
+pub async fn send_dashboard_command(
+    con: Arc<ConnectionManager>,
+    robot_id: String,
+    command: String,
+) -> Result<String, String> {
+    let state = State::new().add(assign!(
+        v!(&&format!("{}_dashboard_command", robot_id)),
+        command.as_str().to_spvalue()
+    ));
+    let prefix = format!("{}_dashboard", robot_id);
+    match backend::send_request(con, &prefix, state, DASHBOARD_TIMEOUT).await {
+        Ok(()) => Ok(format!("{} done", command)),
+        Err(RequestError::Failed) => Err(format!("{} failed", command)),
+        Err(RequestError::TimedOut) => Err(format!(
+            "No answer to {} after {} s",
+            command,
+            DASHBOARD_TIMEOUT.as_secs()
+        )),
+    }
+}
+
+async fn set_scaling(
+    con: Arc<ConnectionManager>,
+    robot_id: String,
+    velocity: f64,
+    acceleration: f64,
+) -> Result<String, String> {
+    speed_override::write_scaling(con, robot_id, velocity, acceleration).await;
+    Ok(format!(
+        "Scaling set to {:.0} % velocity, {:.0} % acceleration",
+        velocity * 100.0,
+        acceleration * 100.0
+    ))
+}
+
+pub struct DashboardPanel {
+    /// Percent.
+    velocity_scaling: f64,
+    /// Percent.
+    acceleration_scaling: f64,
+    /// Polled velocity and acceleration scaling last copied into the inputs.
+    live_seen: (Option<f64>, Option<f64>),
+    promise: Option<Promise<Result<String, String>>>,
+    status: Option<Result<String, String>>,
+}
+
+impl DashboardPanel {
+    pub fn new() -> Self {
+        Self {
+            velocity_scaling: 100.0,
+            acceleration_scaling: 100.0,
+            live_seen: (None, None),
+            promise: None,
+            status: None,
+        }
+    }
+
+    /// `blocked` is why the robot can't take commands right now. The scaling
+    /// inputs follow the polled velocity and acceleration scaling in `live`
+    /// whenever it changes, also when the speed override wrote it.
+    pub fn draw(
+        &mut self,
+        ui: &mut egui::Ui,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+        robot_id: &str,
+        blocked: Option<String>,
+        live: (Option<f64>, Option<f64>),
+    ) {
+        if let Some(promise) = self.promise.take() {
+            match promise.try_take() {
+                Ok(result) => self.status = Some(result),
+                Err(promise) => self.promise = Some(promise),
+            }
+        }
+        let busy = self.promise.is_some();
+        if !busy && self.live_seen != live {
+            self.live_seen = live;
+            if let Some(velocity) = live.0 {
+                self.velocity_scaling = velocity * 100.0;
+            }
+            if let Some(acceleration) = live.1 {
+                self.acceleration_scaling = acceleration * 100.0;
+            }
+        }
+        let enabled = blocked.is_none() && !busy;
+        let handle = handle.clone();
+        let con_clone = connection.clone();
+        let robot_id = robot_id.to_string();
+
+        let mut command = None;
+        ui.horizontal_wrapped(|ui| {
+            for (label, dashboard_command, hover) in COMMANDS {
+                if ui
+                    .add_enabled(enabled, egui::Button::new(label))
+                    .on_hover_text(hover)
+                    .on_disabled_hover_text(blocked.clone().unwrap_or_default())
+                    .clicked()
+                {
+                    command = Some(dashboard_command);
+                }
+            }
+            if busy {
+                ui.spinner();
+            }
+        });
+
+        let mut scale = false;
+        ui.horizontal(|ui| {
+            ui.label("Velocity scaling:");
+            ui.add(
+                egui::DragValue::new(&mut self.velocity_scaling)
+                    .localized()
+                    .suffix(" %")
+                    .speed(1.0)
+                    .range(0.0..=100.0),
+            );
+            ui.label("Acceleration scaling:");
+            ui.add(
+                egui::DragValue::new(&mut self.acceleration_scaling)
+                    .localized()
+                    .suffix(" %")
+                    .speed(1.0)
+                    .range(0.0..=100.0),
+            );
+            scale = ui
+                .add_enabled(enabled, egui::Button::new("Set Scaling"))
+                .on_hover_text("The speed override in the tab bar follows the velocity scaling")
+                .clicked();
+        });
+        match &self.status {
+            Some(Ok(message)) => {
+                ui.colored_label(egui::Color32::GREEN, message);
+            }
+            Some(Err(e)) => {
+                ui.colored_label(egui::Color32::RED, e);
+            }
+            None => (),
+        }
+
+        if let Some(command) = command {
+            self.status = None;
+            let command = command.to_string();
+            self.promise = Some(workers::spawn("dashboard_command", move || {
+                handle.block_on(send_dashboard_command(con_clone, robot_id, command))
+            }));
+        } else if scale {
+            self.status = None;
+            let velocity = self.velocity_scaling / 100.0;
+            let acceleration = self.acceleration_scaling / 100.0;
+            self.promise = Some(workers::spawn("dashboard_scaling", move || {
+                handle.block_on(set_scaling(con_clone, robot_id, velocity, acceleration))
+            }));
+        }
+    }
+}
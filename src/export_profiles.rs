@@ -0,0 +1,264 @@
+//! Named export profiles for lookups: where to save, how to name the file,
+//! in which format and with which live data, so saving is one click instead
+//! of the Save As dialog.
+
+use crate::exporters::ExporterRegistry;
+use crate::lookup::JsonOutputWithMetadata;
+use crate::storage;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+pub const PROFILES_FILE: &str = "export_profiles.json";
+
+pub fn check_file() -> Option<Result<(), String>> {
+    storage::check_json::<ExportProfiles>(PROFILES_FILE)
+}
+
+pub const PLACEHOLDERS: &str = "{parent} {child} {robot} {date} {time}";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportProfile {
+    pub name: String,
+    pub dir: String,
+    /// File name without extension, with `PLACEHOLDERS`.
+    pub pattern: String,
+    /// Name of the exporter.
+    pub format: String,
+    /// Whether lookups embed joint states and the gantry position while the
+    /// profile is active.
+    pub joints: bool,
+    pub gantry: bool,
+}
+
+impl Default for ExportProfile {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            dir: String::new(),
+            pattern: "{parent}_to_{child}_{date}".to_string(),
+            format: "JSON".to_string(),
+            joints: true,
+            gantry: true,
+        }
+    }
+}
+
+impl ExportProfile {
+    /// The file name for a lookup, placeholders filled in.
+    pub fn file_stem(&self, lookup: &JsonOutputWithMetadata, robot_id: &str) -> String {
+        let timestamp = storage::format_timestamp(storage::now_secs());
+        let (date, time) = timestamp.split_once(' ').unwrap_or((&timestamp, ""));
+        self.pattern
+            .replace("{parent}", &lookup.parent_frame_id)
+            .replace("{child}", &lookup.child_frame_id)
+            .replace("{robot}", robot_id)
+            .replace("{date}", date)
+            .replace("{time}", &time.replace(':', "-"))
+            .replace(['/', '\\'], "_")
+    }
+
+    /// Exports the lookup into the profile's folder. An existing file is kept
+    /// and a number appended to the new one.
+    pub fn save(
+        &self,
+        exporters: &ExporterRegistry,
+        lookup: &JsonOutputWithMetadata,
+        robot_id: &str,
+    ) -> Result<String, String> {
+        let exporter = exporters
+            .get(&self.format)
+            .ok_or_else(|| format!("Unknown format {}", self.format))?;
+        let content = exporter.export_lookup(lookup)?;
+        let dir = PathBuf::from(&self.dir);
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        let stem = self.file_stem(lookup, robot_id);
+        let extension = exporter.file_extension();
+        let mut path = dir.join(format!("{}.{}", stem, extension));
+        let mut n = 2;
+        while path.exists() {
+            path = dir.join(format!("{}_{}.{}", stem, n, extension));
+            n += 1;
+        }
+        std::fs::write(&path, content)
+            .map_err(|e| format!("Failed to save {}: {}", path.display(), e))?;
+        Ok(format!("Saved {}", path.display()))
+    }
+
+    fn problem(&self, exporters: &ExporterRegistry) -> Option<String> {
+        if self.dir.trim().is_empty() {
+            Some("No folder set".to_string())
+        } else if self.pattern.trim().is_empty() {
+            Some("No file name pattern".to_string())
+        } else if exporters.get(&self.format).is_none() {
+            Some(format!("Unknown format {}", self.format))
+        } else {
+            None
+        }
+    }
+}
+
+/// Profiles of this station and the one Save to Profile uses.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportProfiles {
+    profiles: Vec<ExportProfile>,
+    active: Option<String>,
+}
+
+impl ExportProfiles {
+    pub fn load() -> Self {
+        storage::load_json(PROFILES_FILE).unwrap_or_default()
+    }
+
+    fn save(&self) {
+        if let Err(e) = storage::save_json(PROFILES_FILE, self) {
+            log::error!("Failed to save export profiles: {}", e);
+        }
+    }
+
+    pub fn active(&self) -> Option<&ExportProfile> {
+        let name = self.active.as_ref()?;
+        self.profiles.iter().find(|p| &p.name == name)
+    }
+
+    /// Profile selector. Returns the profile made active, whose capture
+    /// options the tab takes over.
+    pub fn draw_selector(&mut self, ui: &mut egui::Ui) -> Option<ExportProfile> {
+        let mut activated = None;
+        egui::ComboBox::from_id_salt("lookup_export_profile")
+            .selected_text(self.active.as_deref().unwrap_or("No profile"))
+            .show_ui(ui, |ui| {
+                if ui
+                    .selectable_label(self.active.is_none(), "No profile")
+                    .clicked()
+                {
+                    self.active = None;
+                    self.save();
+                }
+                for profile in &self.profiles {
+                    let selected = self.active.as_ref() == Some(&profile.name);
+                    if ui.selectable_label(selected, &profile.name).clicked() && !selected {
+                        self.active = Some(profile.name.clone());
+                        activated = Some(profile.clone());
+                    }
+                }
+            });
+        if activated.is_some() {
+            self.save();
+        }
+        activated
+    }
+
+    /// Editor window for the profiles, shown while `open`.
+    pub fn draw_editor(
+        &mut self,
+        ctx: &egui::Context,
+        open: &mut bool,
+        exporters: &ExporterRegistry,
+    ) {
+        let mut changed = false;
+        let mut remove = None;
+        egui::Window::new("Export Profiles")
+            .open(open)
+            .resizable(true)
+            .show(ctx, |ui| {
+                ui.weak(format!("File name placeholders: {}", PLACEHOLDERS));
+                for (i, profile) in self.profiles.iter_mut().enumerate() {
+                    egui::Frame::group(ui.style()).show(ui, |ui| {
+                        egui::Grid::new(("export_profile_grid", i))
+                            .num_columns(2)
+                            .spacing([10.0, 4.0])
+                            .show(ui, |ui| {
+                                ui.label("Name:");
+                                ui.horizontal(|ui| {
+                                    changed |= ui.text_edit_singleline(&mut profile.name).changed();
+                                    if ui.button("🗑").clicked() {
+                                        remove = Some(i);
+                                    }
+                                });
+                                ui.end_row();
+                                ui.label("Folder:");
+                                ui.horizontal(|ui| {
+                                    changed |= ui
+                                        .add(
+                                            egui::TextEdit::singleline(&mut profile.dir)
+                                                .desired_width(240.0),
+                                        )
+                                        .changed();
+                                    if ui.button("Browse…").clicked() {
+                                        if let Some(dir) = rfd::FileDialog::new().pick_folder() {
+                                            profile.dir = dir.display().to_string();
+                                            changed = true;
+                                        }
+                                    }
+                                });
+                                ui.end_row();
+                                ui.label("File name:");
+                                changed |= ui
+                                    .add(
+                                        egui::TextEdit::singleline(&mut profile.pattern)
+                                            .desired_width(240.0),
+                                    )
+                                    .changed();
+                                ui.end_row();
+                                ui.label("Format:");
+                                egui::ComboBox::from_id_salt(("export_profile_format", i))
+                                    .selected_text(&profile.format)
+                                    .show_ui(ui, |ui| {
+                                        for exporter in exporters.exporters() {
+                                            changed |= ui
+                                                .selectable_value(
+                                                    &mut profile.format,
+                                                    exporter.name().to_string(),
+                                                    exporter.name(),
+                                                )
+                                                .changed();
+                                        }
+                                    });
+                                ui.end_row();
+                                ui.label("Embed:");
+                                ui.horizontal(|ui| {
+                                    changed |=
+                                        ui.checkbox(&mut profile.joints, "Joint states").changed();
+                                    changed |= ui
+                                        .checkbox(&mut profile.gantry, "Gantry position")
+                                        .changed();
+                                });
+                                ui.end_row();
+                            });
+                        if let Some(problem) = profile.problem(exporters) {
+                            ui.colored_label(egui::Color32::YELLOW, problem);
+                        }
+                    });
+                }
+                if ui.button("Add Profile").clicked() {
+                    self.profiles.push(ExportProfile {
+                        name: format!("Profile {}", self.profiles.len() + 1),
+                        ..Default::default()
+                    });
+                    changed = true;
+                }
+            });
+        if let Some(i) = remove {
+            let removed = self.profiles.remove(i);
+            if self.active.as_ref() == Some(&removed.name) {
+                self.active = None;
+            }
+            changed = true;
+        }
+        if changed {
+            self.save();
+        }
+    }
+
+    /// Why Save to Profile can't run, if it can't.
+    pub fn problem(&self, exporters: &ExporterRegistry) -> Option<String> {
+        match self.active() {
+            Some(profile) => profile.problem(exporters),
+            None => Some("No export profile selected".to_string()),
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use crate::lookup::JsonOutputWithMetadata;
+use crate::pose::{self, Pose};
+use crate::schema::{self, Versioned};
+use micro_sp::SPTransformStamped;
+use rfd::FileDialog;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// An export format for lookup results and, optionally, whole scenes.
+///
+/// Company-specific formats implement this trait and are added in
+/// [`register_custom_exporters`], the lookup and transforms tabs then offer them
+/// next to the built-in ones.
+pub trait LookupExporter: Send + Sync {
+    /// Shown in the export menus.
+    fn name(&self) -> &str;
+
+    /// Without the dot, e.g. `json`.
+    fn file_extension(&self) -> &str;
+
+    fn export_lookup(&self, lookup: &JsonOutputWithMetadata) -> Result<String, String>;
+
+    fn supports_scenes(&self) -> bool {
+        false
+    }
+
+    fn export_scene(&self, _scene: &HashMap<String, SPTransformStamped>) -> Result<String, String> {
+        Err(format!("{} does not export scenes", self.name()))
+    }
+}
+
+/// The exporters available in this GUI, built-in ones first.
+pub struct ExporterRegistry {
+    exporters: Vec<Box<dyn LookupExporter>>,
+}
+
+impl ExporterRegistry {
+    pub fn new() -> Self {
+        let mut registry = Self {
+            exporters: vec![Box::new(JsonExporter), Box::new(CsvExporter)],
+        };
+        register_custom_exporters(&mut registry);
+        registry
+    }
+
+    pub fn register(&mut self, exporter: Box<dyn LookupExporter>) {
+        self.exporters.push(exporter);
+    }
+
+    pub fn exporters(&self) -> impl Iterator<Item = &dyn LookupExporter> {
+        self.exporters.iter().map(|e| e.as_ref())
+    }
+
+    pub fn scene_exporters(&self) -> impl Iterator<Item = &dyn LookupExporter> {
+        self.exporters().filter(|e| e.supports_scenes())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn LookupExporter> {
+        self.exporters().find(|e| e.name() == name)
+    }
+}
+
+/// Registration point for downstream exporters, e.g.
+/// `registry.register(Box::new(MyCompanyExporter));`
+fn register_custom_exporters(_registry: &mut ExporterRegistry) {}
+
+/// Asks for a file name and writes the exported content there.
+pub fn save_with_exporter(
+    exporter: &dyn LookupExporter,
+    default_stem: &str,
+    content: Result<String, String>,
+) -> Result<Option<String>, String> {
+    let content = content?;
+    let mut dialog = FileDialog::new();
+    if let Some(dir) = crate::scene_repo::scene_dir() {
+        dialog = dialog.set_directory(dir);
+    }
+    let file_path = dialog
+        .add_filter(exporter.name(), &[exporter.file_extension()])
+        .set_file_name(format!("{}.{}", default_stem, exporter.file_extension()))
+        .save_file();
+    let Some(path) = file_path else {
+        return Ok(None);
+    };
+    std::fs::write(&path, content).map_err(|e| format!("Failed to save file: {}", e))?;
+    Ok(Some(format!("Saved {}", path.display())))
+}
+
+/// The versioned JSON format the GUI itself can import again.
+struct JsonExporter;
+
+impl LookupExporter for JsonExporter {
+    fn name(&self) -> &str {
+        "JSON"
+    }
+
+    fn file_extension(&self) -> &str {
+        "json"
+    }
+
+    fn export_lookup(&self, lookup: &JsonOutputWithMetadata) -> Result<String, String> {
+        serde_json::to_string_pretty(&Versioned::new(schema::FRAME_KIND, lookup))
+            .map_err(|e| format!("JSON serialization error: {}", e))
+    }
+
+    fn supports_scenes(&self) -> bool {
+        true
+    }
+
+    fn export_scene(&self, scene: &HashMap<String, SPTransformStamped>) -> Result<String, String> {
+        // Sorted so exports of the same scene diff cleanly
+        let frames = scene
+            .iter()
+            .map(|(name, frame)| (name, pose::export_frame(frame)))
+            .collect::<BTreeMap<_, _>>();
+        serde_json::to_string_pretty(&Versioned::new(schema::SCENE_KIND, SceneExport { frames }))
+            .map_err(|e| format!("JSON serialization error: {}", e))
+    }
+}
+
+#[derive(Serialize)]
+struct SceneExport<'a> {
+    frames: BTreeMap<&'a String, SPTransformStamped>,
+}
+
+/// One row per frame with translation and quaternion, for spreadsheets.
+struct CsvExporter;
+
+const CSV_HEADER: &str = "child_frame_id,parent_frame_id,x,y,z,qx,qy,qz,qw\n";
+
+fn csv_row(child: &str, parent: &str, pose: &Pose) -> String {
+    let [x, y, z] = pose.translation;
+    let [qx, qy, qz, qw] = pose.rotation;
+    format!(
+        "{},{},{},{},{},{},{},{},{}\n",
+        child, parent, x, y, z, qx, qy, qz, qw
+    )
+}
+
+impl LookupExporter for CsvExporter {
+    fn name(&self) -> &str {
+        "CSV"
+    }
+
+    fn file_extension(&self) -> &str {
+        "csv"
+    }
+
+    fn export_lookup(&self, lookup: &JsonOutputWithMetadata) -> Result<String, String> {
+        Ok(format!(
+            "{}{}",
+            CSV_HEADER,
+            csv_row(
+                &lookup.child_frame_id,
+                &lookup.parent_frame_id,
+                &Pose::from_sp_transform(&pose::export_transform(&lookup.transform))
+            )
+        ))
+    }
+
+    fn supports_scenes(&self) -> bool {
+        true
+    }
+
+    fn export_scene(&self, scene: &HashMap<String, SPTransformStamped>) -> Result<String, String> {
+        let mut csv = CSV_HEADER.to_string();
+        for tf in scene.iter().collect::<BTreeMap<_, _>>().into_values() {
+            csv.push_str(&csv_row(
+                &tf.child_frame_id,
+                &tf.parent_frame_id,
+                &Pose::from_sp_transform(&pose::export_transform(&tf.transform)),
+            ));
+        }
+        Ok(csv)
+    }
+}
@@ -0,0 +1,165 @@
+/// Unit a numeric field is stored in. Values typed with another unit of the
+/// same kind are converted, e.g. `12mm` in a `Meter` field becomes `0.012`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    None,
+    Meter,
+    Radian,
+    Millisecond,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Dimension {
+    Length,
+    Angle,
+    Time,
+}
+
+impl Unit {
+    fn dimension_and_scale(&self) -> Option<(Dimension, f64)> {
+        match self {
+            Unit::None => None,
+            Unit::Meter => Some((Dimension::Length, 1.0)),
+            Unit::Radian => Some((Dimension::Angle, 1.0)),
+            Unit::Millisecond => Some((Dimension::Time, 0.001)),
+        }
+    }
+}
+
+/// Units that can be typed after a number, with their factor to SI.
+fn unit_suffix(name: &str) -> Option<(Dimension, f64)> {
+    match name {
+        "mm" => Some((Dimension::Length, 0.001)),
+        "cm" => Some((Dimension::Length, 0.01)),
+        "m" => Some((Dimension::Length, 1.0)),
+        "deg" | "°" => Some((Dimension::Angle, std::f64::consts::PI / 180.0)),
+        "rad" => Some((Dimension::Angle, 1.0)),
+        "ms" => Some((Dimension::Time, 0.001)),
+        "s" => Some((Dimension::Time, 1.0)),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Word(String),
+    Op(char),
+}
+
+fn tokenize(text: &str) -> Option<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() || c == '.' || c == ',' {
+            let mut number = String::new();
+            while let Some(&d) = chars.peek() {
+                if d.is_ascii_digit() || d == '.' || d == ',' {
+                    number.push(d);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            tokens.push(Token::Number(crate::locale::parse_number(&number)?));
+        } else if c.is_alphabetic() || c == '°' {
+            let mut word = String::new();
+            while let Some(&d) = chars.peek() {
+                if d.is_alphabetic() || d == '°' {
+                    word.push(d);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            tokens.push(Token::Word(word.to_lowercase()));
+        } else if "+-−*/()".contains(c) {
+            tokens.push(Token::Op(if c == '−' { '-' } else { c }));
+            chars.next();
+        } else {
+            return None;
+        }
+    }
+    Some(tokens)
+}
+
+struct Evaluator {
+    tokens: Vec<Token>,
+    pos: usize,
+    field: Option<(Dimension, f64)>,
+}
+
+impl Evaluator {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expr(&mut self) -> Option<f64> {
+        let mut value = self.term()?;
+        while let Some(Token::Op(op @ ('+' | '-'))) = self.peek().cloned() {
+            self.pos += 1;
+            let rhs = self.term()?;
+            value = if op == '+' { value + rhs } else { value - rhs };
+        }
+        Some(value)
+    }
+
+    fn term(&mut self) -> Option<f64> {
+        let mut value = self.factor()?;
+        while let Some(Token::Op(op @ ('*' | '/'))) = self.peek().cloned() {
+            self.pos += 1;
+            let rhs = self.factor()?;
+            value = if op == '*' { value * rhs } else { value / rhs };
+        }
+        Some(value)
+    }
+
+    fn factor(&mut self) -> Option<f64> {
+        let value = match self.next()? {
+            Token::Op('-') => return self.factor().map(|v| -v),
+            Token::Op('+') => return self.factor(),
+            Token::Op('(') => {
+                let value = self.expr()?;
+                (self.next()? == Token::Op(')')).then_some(value)?
+            }
+            Token::Number(n) => n,
+            Token::Word(w) if w == "pi" => std::f64::consts::PI,
+            _ => return None,
+        };
+        // An optional unit directly after a number or parenthesis
+        if let Some(Token::Word(w)) = self.peek().cloned() {
+            let (dimension, scale) = unit_suffix(&w)?;
+            let (field_dimension, field_scale) = self.field?;
+            if dimension != field_dimension {
+                return None;
+            }
+            self.pos += 1;
+            return Some(value * scale / field_scale);
+        }
+        Some(value)
+    }
+}
+
+/// Evaluates `+ - * /`, parentheses, `pi` and unit suffixes, converting to
+/// the field's unit. Numbers may use a decimal comma. `None` if invalid.
+pub fn evaluate(text: &str, unit: Unit) -> Option<f64> {
+    let tokens = tokenize(text)?;
+    if tokens.is_empty() {
+        return None;
+    }
+    let mut evaluator = Evaluator {
+        tokens,
+        pos: 0,
+        field: unit.dimension_and_scale(),
+    };
+    let value = evaluator.expr()?;
+    (evaluator.pos == evaluator.tokens.len() && value.is_finite()).then_some(value)
+}
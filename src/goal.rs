@@ -0,0 +1,528 @@
+use crate::workers;
+use eframe::egui;
+use micro_sp::*;
+use poll_promise::Promise;
+use std::{collections::HashMap, sync::Arc};
+
+async fn get_state_keys(con: Arc<ConnectionManager>) -> Vec<String> {
+    let mut connection = con.get_connection().await;
+    match StateManager::get_full_state(&mut connection).await {
+        Some(state) => {
+            let mut keys = state.state.keys().cloned().collect::<Vec<String>>();
+            keys.sort_unstable();
+            keys
+        }
+        None => {
+            log::error!("GUI Failed to get the full state!");
+            Vec::new()
+        }
+    }
+}
+
+pub async fn set_goal(con: Arc<ConnectionManager>, goal_variable: String, goal: String) {
+    let mut connection = con.get_connection().await;
+    let state = State::new().add(assign!(v!(&&goal_variable), goal.as_str().to_spvalue()));
+    crate::recorder::record_state(&state);
+    StateManager::set_state(&mut connection, &state).await;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Literal(String),
+    Eq,
+    Neq,
+    And,
+    Or,
+    Not,
+    LParen,
+    RParen,
+}
+
+fn tokenize(input: &str) -> Result<Vec<(Token, usize)>, String> {
+    let chars = input.char_indices().collect::<Vec<(usize, char)>>();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let (pos, c) = chars[i];
+        let next = chars.get(i + 1).map(|(_, c)| *c);
+        match c {
+            c if c.is_whitespace() => i += 1,
+            '(' => {
+                tokens.push((Token::LParen, pos));
+                i += 1;
+            }
+            ')' => {
+                tokens.push((Token::RParen, pos));
+                i += 1;
+            }
+            '=' if next == Some('=') => {
+                tokens.push((Token::Eq, pos));
+                i += 2;
+            }
+            '!' if next == Some('=') => {
+                tokens.push((Token::Neq, pos));
+                i += 2;
+            }
+            '!' => {
+                tokens.push((Token::Not, pos));
+                i += 1;
+            }
+            '&' if next == Some('&') => {
+                tokens.push((Token::And, pos));
+                i += 2;
+            }
+            '|' if next == Some('|') => {
+                tokens.push((Token::Or, pos));
+                i += 2;
+            }
+            '"' => {
+                let start = i + 1;
+                let mut end = start;
+                while end < chars.len() && chars[end].1 != '"' {
+                    end += 1;
+                }
+                if end == chars.len() {
+                    return Err(format!("Unterminated string at {}", pos));
+                }
+                let text = chars[start..end].iter().map(|(_, c)| c).collect();
+                tokens.push((Token::Literal(text), pos));
+                i = end + 1;
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '-' || c == '.' => {
+                let start = i;
+                while i < chars.len()
+                    && (chars[i].1.is_alphanumeric() || matches!(chars[i].1, '_' | '-' | '.'))
+                {
+                    i += 1;
+                }
+                let word = chars[start..i].iter().map(|(_, c)| c).collect::<String>();
+                let is_literal = word == "true"
+                    || word == "false"
+                    || word == "UNKNOWN"
+                    || word.parse::<f64>().is_ok();
+                if is_literal {
+                    tokens.push((Token::Literal(word), pos));
+                } else {
+                    tokens.push((Token::Ident(word), pos));
+                }
+            }
+            other => return Err(format!("Unexpected character '{}' at {}", other, pos)),
+        }
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent checker for the predicate syntax:
+/// `expr := and ('||' and)*`, `and := unary ('&&' unary)*`,
+/// `unary := '!' unary | '(' expr ')' | var (('==' | '!=') value)?`.
+/// Variables on the left of a comparison must exist in the state; a bare word
+/// on the right is a variable if it exists, otherwise a string value.
+struct GoalChecker<'a> {
+    tokens: &'a [(Token, usize)],
+    pos: usize,
+    state_keys: &'a [String],
+}
+
+impl GoalChecker<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+
+    fn location(&self) -> String {
+        match self.tokens.get(self.pos) {
+            Some((_, offset)) => format!("at {}", offset),
+            None => "at end of input".to_string(),
+        }
+    }
+
+    fn expr(&mut self) -> Result<(), String> {
+        self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            self.and()?;
+        }
+        Ok(())
+    }
+
+    fn and(&mut self) -> Result<(), String> {
+        self.unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            self.unary()?;
+        }
+        Ok(())
+    }
+
+    fn unary(&mut self) -> Result<(), String> {
+        match self.peek().cloned() {
+            Some(Token::Not) => {
+                self.pos += 1;
+                self.unary()
+            }
+            Some(Token::LParen) => {
+                self.pos += 1;
+                self.expr()?;
+                if self.peek() != Some(&Token::RParen) {
+                    return Err(format!("Expected ')' {}", self.location()));
+                }
+                self.pos += 1;
+                Ok(())
+            }
+            Some(Token::Ident(name)) => {
+                if !self.state_keys.contains(&name) {
+                    return Err(format!("Unknown variable '{}' {}", name, self.location()));
+                }
+                self.pos += 1;
+                if matches!(self.peek(), Some(Token::Eq) | Some(Token::Neq)) {
+                    self.pos += 1;
+                    match self.peek() {
+                        Some(Token::Ident(_)) | Some(Token::Literal(_)) => self.pos += 1,
+                        _ => return Err(format!("Expected a value {}", self.location())),
+                    }
+                }
+                Ok(())
+            }
+            Some(Token::Literal(value)) => match value.as_str() {
+                "true" | "false" => {
+                    self.pos += 1;
+                    Ok(())
+                }
+                _ => Err(format!(
+                    "Expected a variable, found '{}' {}",
+                    value,
+                    self.location()
+                )),
+            },
+            _ => Err(format!("Expected a predicate {}", self.location())),
+        }
+    }
+}
+
+/// Checks a goal expression against the known state variables.
+pub fn validate_goal(goal: &str, state_keys: &[String]) -> Result<(), String> {
+    let tokens = tokenize(goal)?;
+    if tokens.is_empty() {
+        return Err("Goal is empty".to_string());
+    }
+    let mut checker = GoalChecker {
+        tokens: &tokens,
+        pos: 0,
+        state_keys,
+    };
+    checker.expr()?;
+    if checker.pos != tokens.len() {
+        return Err(format!("Unexpected input {}", checker.location()));
+    }
+    Ok(())
+}
+
+/// Evaluates the predicate syntax of `GoalChecker` against concrete values.
+/// Missing variables are UNKNOWN, so a bare missing variable is false.
+struct PredicateEvaluator<'a> {
+    tokens: &'a [(Token, usize)],
+    pos: usize,
+    values: &'a HashMap<String, SPValue>,
+}
+
+/// Value as compared in predicates, numbers without locale formatting.
+fn predicate_text(value: &SPValue) -> String {
+    match value {
+        SPValue::String(StringOrUnknown::String(s)) => s.clone(),
+        SPValue::Bool(BoolOrUnknown::Bool(b)) => b.to_string(),
+        SPValue::Float64(FloatOrUnknown::Float64(f)) => f.0.to_string(),
+        SPValue::String(_) | SPValue::Bool(_) | SPValue::Float64(_) => "UNKNOWN".to_string(),
+        other => format!("{:?}", other),
+    }
+}
+
+fn predicate_values_equal(a: &str, b: &str) -> bool {
+    match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(a), Ok(b)) => (a - b).abs() < 1e-9,
+        _ => a == b,
+    }
+}
+
+impl PredicateEvaluator<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+
+    fn location(&self) -> String {
+        match self.tokens.get(self.pos) {
+            Some((_, offset)) => format!("at {}", offset),
+            None => "at end of input".to_string(),
+        }
+    }
+
+    fn expr(&mut self) -> Result<bool, String> {
+        let mut result = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            // Parse both sides so syntax errors surface regardless of the values
+            let rhs = self.and()?;
+            result = result || rhs;
+        }
+        Ok(result)
+    }
+
+    fn and(&mut self) -> Result<bool, String> {
+        let mut result = self.unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            let rhs = self.unary()?;
+            result = result && rhs;
+        }
+        Ok(result)
+    }
+
+    fn unary(&mut self) -> Result<bool, String> {
+        match self.peek().cloned() {
+            Some(Token::Not) => {
+                self.pos += 1;
+                Ok(!self.unary()?)
+            }
+            Some(Token::LParen) => {
+                self.pos += 1;
+                let result = self.expr()?;
+                if self.peek() != Some(&Token::RParen) {
+                    return Err(format!("Expected ')' {}", self.location()));
+                }
+                self.pos += 1;
+                Ok(result)
+            }
+            Some(Token::Ident(name)) => {
+                self.pos += 1;
+                let value = self.values.get(&name);
+                let negate = match self.peek() {
+                    Some(Token::Eq) => false,
+                    Some(Token::Neq) => true,
+                    _ => {
+                        return Ok(matches!(
+                            value,
+                            Some(SPValue::Bool(BoolOrUnknown::Bool(true)))
+                        ));
+                    }
+                };
+                self.pos += 1;
+                let rhs = match self.peek().cloned() {
+                    Some(Token::Ident(other)) => {
+                        self.values.get(&other).map(predicate_text).unwrap_or(other)
+                    }
+                    Some(Token::Literal(literal)) => literal,
+                    _ => return Err(format!("Expected a value {}", self.location())),
+                };
+                self.pos += 1;
+                let lhs = value
+                    .map(predicate_text)
+                    .unwrap_or_else(|| "UNKNOWN".to_string());
+                Ok(predicate_values_equal(&lhs, &rhs) != negate)
+            }
+            Some(Token::Literal(value)) => match value.as_str() {
+                "true" | "false" => {
+                    self.pos += 1;
+                    Ok(value == "true")
+                }
+                _ => Err(format!(
+                    "Expected a variable, found '{}' {}",
+                    value,
+                    self.location()
+                )),
+            },
+            _ => Err(format!("Expected a predicate {}", self.location())),
+        }
+    }
+}
+
+/// Evaluates a predicate in the goal syntax against the given state values.
+pub fn evaluate_predicate(
+    predicate: &str,
+    values: &HashMap<String, SPValue>,
+) -> Result<bool, String> {
+    let tokens = tokenize(predicate)?;
+    if tokens.is_empty() {
+        return Err("Predicate is empty".to_string());
+    }
+    let mut evaluator = PredicateEvaluator {
+        tokens: &tokens,
+        pos: 0,
+        values,
+    };
+    let result = evaluator.expr()?;
+    if evaluator.pos != tokens.len() {
+        return Err(format!("Unexpected input {}", evaluator.location()));
+    }
+    Ok(result)
+}
+
+/// Words of a predicate that may name state variables, to know what to fetch.
+pub fn predicate_variables(predicate: &str) -> Vec<String> {
+    tokenize(predicate)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|(token, _)| match token {
+            Token::Ident(name) => Some(name),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The identifier being typed at the end of the input, if any.
+fn trailing_word(input: &str) -> &str {
+    let start = input
+        .rfind(|c: char| !(c.is_alphanumeric() || matches!(c, '_' | '-' | '.')))
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    &input[start..]
+}
+
+pub struct GoalTab {
+    sp_id_input: String,
+    goal_input: String,
+    state_keys: Vec<String>,
+    state_keys_promise: Option<Promise<Vec<String>>>,
+    set_goal_promise: Option<Promise<()>>,
+    status: Option<Result<String, String>>,
+}
+
+impl GoalTab {
+    pub fn new() -> Self {
+        Self {
+            sp_id_input: "micro_sp".to_string(),
+            goal_input: String::new(),
+            state_keys: Vec::new(),
+            state_keys_promise: None,
+            set_goal_promise: None,
+            status: None,
+        }
+    }
+
+    /// Variable the planner reads its goal from.
+    pub fn goal_variable(&self) -> String {
+        format!("{}_goal", self.sp_id_input)
+    }
+
+    pub fn ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+    ) {
+        ui.horizontal(|ui| {
+            ui.heading("Planner Goal");
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                let text_box =
+                    egui::TextEdit::singleline(&mut self.sp_id_input).desired_width(80.0);
+                ui.add(text_box);
+                ui.label("SP ID:");
+            });
+        });
+        ui.separator();
+
+        if let Some(promise) = self.state_keys_promise.take() {
+            match promise.try_take() {
+                Ok(keys) => self.state_keys = keys,
+                Err(promise) => self.state_keys_promise = Some(promise),
+            }
+        }
+        if let Some(promise) = &self.set_goal_promise {
+            if promise.ready().is_some() {
+                self.set_goal_promise = None;
+                self.status = Some(Ok(format!("Goal set on {}_goal", self.sp_id_input)));
+            }
+        }
+
+        ui.horizontal(|ui| {
+            let is_fetching = self.state_keys_promise.is_some();
+            if ui
+                .add_enabled(!is_fetching, egui::Button::new("Fetch Variables"))
+                .clicked()
+            {
+                let handle = handle.clone();
+                let con_clone = connection.clone();
+                self.state_keys_promise = Some(workers::spawn("state_keys", move || {
+                    handle.block_on(get_state_keys(con_clone))
+                }));
+            }
+            if is_fetching {
+                ui.spinner();
+            } else {
+                ui.label(format!("{} variables known", self.state_keys.len()));
+            }
+            ui.label("ℹ").on_hover_text(
+                "Predicate syntax, e.g.\n\
+                 r1_request_state == succeeded && !gripper_part_detected\n\
+                 Combine with &&, ||, ! and parentheses. Bare words on the\n\
+                 right of == / != are variables if they exist, values otherwise.",
+            );
+        });
+
+        ui.label("Goal:");
+        ui.add(
+            egui::TextEdit::multiline(&mut self.goal_input)
+                .font(egui::FontId::monospace(12.0))
+                .desired_rows(3)
+                .desired_width(f32::INFINITY),
+        );
+
+        // Completion for the variable name being typed
+        let word = trailing_word(&self.goal_input).to_string();
+        if !word.is_empty() {
+            let matches = self
+                .state_keys
+                .iter()
+                .filter(|k| k.starts_with(&word) && **k != word)
+                .take(8)
+                .cloned()
+                .collect::<Vec<String>>();
+            if !matches.is_empty() {
+                ui.horizontal_wrapped(|ui| {
+                    ui.label("Complete:");
+                    for m in matches {
+                        if ui.small_button(&m).clicked() {
+                            let cut = self.goal_input.len() - word.len();
+                            self.goal_input.truncate(cut);
+                            self.goal_input.push_str(&m);
+                        }
+                    }
+                });
+            }
+        }
+
+        let validation = validate_goal(&self.goal_input, &self.state_keys);
+        match &validation {
+            Ok(()) => ui.colored_label(egui::Color32::GREEN, "Valid goal"),
+            Err(e) => ui.colored_label(egui::Color32::YELLOW, e),
+        };
+
+        ui.horizontal(|ui| {
+            let can_set = validation.is_ok() && self.set_goal_promise.is_none();
+            if ui
+                .add_enabled(can_set, egui::Button::new("Set Goal"))
+                .clicked()
+            {
+                self.status = None;
+                let goal_variable = self.goal_variable();
+                let goal = self.goal_input.trim().to_string();
+                let handle = handle.clone();
+                let con_clone = connection.clone();
+                self.set_goal_promise = Some(workers::spawn("set_goal", move || {
+                    handle.block_on(set_goal(con_clone, goal_variable, goal))
+                }));
+            }
+            if self.set_goal_promise.is_some() {
+                ui.spinner();
+            }
+        });
+
+        match &self.status {
+            Some(Ok(msg)) => {
+                ui.colored_label(egui::Color32::GREEN, msg);
+            }
+            Some(Err(e)) => {
+                ui.colored_label(egui::Color32::RED, format!("Error: {}", e));
+            }
+            None => (),
+        }
+    }
+}
@@ -0,0 +1,235 @@
+//! Gripper of the robot's tool, actuated through the state like the robot
+//! itself: `{robot}_gripper_command` with the width and force, and the gripper
+//! request trigger. The driver reports the actual width and whether a part is
+//! held back.
+
+use crate::backend::{self, RequestError};
+use crate::locale::LocalizedDragValue;
+use crate::state_poller::{StatePoller, sp_value_to_display};
+use crate::workers;
+use eframe::egui;
+use micro_sp::*;
+use poll_promise::Promise;
+use std::{sync::Arc, time::Duration};
+
+/// Opening or closing takes a second or two, grasping can take longer.
+const GRIPPER_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Meters.
+fn actual_width_key(robot_id: &str) -> String {
+    format!("{}_gripper_actual_width", robot_id)
+}
+
+fn part_detected_key(robot_id: &str) -> String {
+    format!("{}_gripper_part_detected", robot_id)
+}
+
+fn request_state_key(robot_id: &str) -> String {
+    format!("{}_gripper_request_state", robot_id)
+}
+
+/// Keys of the gripper feedback.
+pub fn keys(robot_id: &str) -> Vec<String> {
+    vec![
+        request_state_key(robot_id),
+        actual_width_key(robot_id),
+        part_detected_key(robot_id),
+    ]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GripperCommand {
+    Open,
+    Close,
+    /// Move the fingers to the set width.
+    Move,
+}
+
+impl GripperCommand {
+    fn as_str(self) -> &'static str {
+        match self {
+            GripperCommand::Open => "open",
+            GripperCommand::Close => "close",
+            GripperCommand::Move => "move",
+        }
+    }
+}
+
+/// Writes the gripper command and waits for `{robot}_gripper_request_state`.
+async fn send_gripper_command(
+    con: Arc<ConnectionManager>,
+    robot_id: String,
+    command: GripperCommand,
+    width: f64,
+    force: f64,
+) -> Result<String, String> {
+    let state = State::new()
+        .add(assign!(
+            v!(&&format!("{}_gripper_command", robot_id)),
+            command.as_str().to_spvalue()
+        ))
+        .add(assign!(
+            fv!(&&format!("{}_gripper_width", robot_id)),
+            width.to_spvalue()
+        ))
+        .add(assign!(
+            fv!(&&format!("{}_gripper_force", robot_id)),
+            force.to_spvalue()
+        ));
+    let prefix = format!("{}_gripper", robot_id);
+    match backend::send_request(con, &prefix, state, GRIPPER_TIMEOUT).await {
+        Ok(()) => Ok(format!("Gripper {} done", command.as_str())),
+        Err(RequestError::Failed) => Err(format!("Gripper {} failed", command.as_str())),
+        Err(RequestError::TimedOut) => Err(format!(
+            "No answer to gripper {} after {} s",
+            command.as_str(),
+            GRIPPER_TIMEOUT.as_secs()
+        )),
+    }
+}
+
+pub struct GripperPanel {
+    pub open: bool,
+    /// Millimeters.
+    width: f64,
+    /// Newtons.
+    force: f64,
+    promise: Option<Promise<Result<String, String>>>,
+    status: Option<Result<String, String>>,
+}
+
+impl GripperPanel {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            width: 50.0,
+            force: 20.0,
+            promise: None,
+            status: None,
+        }
+    }
+
+    /// `blocked` is why the robot can't take commands right now.
+    pub fn draw(
+        &mut self,
+        ui: &mut egui::Ui,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+        poller: &StatePoller,
+        robot_id: &str,
+        blocked: Option<String>,
+    ) {
+        if let Some(promise) = self.promise.take() {
+            match promise.try_take() {
+                Ok(result) => self.status = Some(result),
+                Err(promise) => self.promise = Some(promise),
+            }
+        }
+        let busy = self.promise.is_some();
+        let enabled = blocked.is_none() && !busy;
+
+        let mut command = None;
+        ui.horizontal(|ui| {
+            ui.label("Width:");
+            ui.add(
+                egui::DragValue::new(&mut self.width)
+                    .localized()
+                    .suffix(" mm")
+                    .speed(0.5)
+                    .range(0.0..=1000.0),
+            );
+            ui.label("Force:");
+            ui.add(
+                egui::DragValue::new(&mut self.force)
+                    .localized()
+                    .suffix(" N")
+                    .speed(1.0)
+                    .range(0.0..=1000.0),
+            );
+        });
+        ui.horizontal(|ui| {
+            for (label, gripper_command, hover) in [
+                ("Open", GripperCommand::Open, "Open the fingers fully"),
+                (
+                    "Close",
+                    GripperCommand::Close,
+                    "Close the fingers with the set force until they hold a part",
+                ),
+                (
+                    "Move To Width",
+                    GripperCommand::Move,
+                    "Move the fingers to the set width",
+                ),
+            ] {
+                if ui
+                    .add_enabled(enabled, egui::Button::new(label))
+                    .on_hover_text(hover)
+                    .on_disabled_hover_text(blocked.clone().unwrap_or_default())
+                    .clicked()
+                {
+                    command = Some(gripper_command);
+                }
+            }
+            if busy {
+                ui.spinner();
+            }
+        });
+        match &self.status {
+            Some(Ok(message)) => {
+                ui.colored_label(egui::Color32::GREEN, message);
+            }
+            Some(Err(e)) => {
+                ui.colored_label(egui::Color32::RED, e);
+            }
+            None => (),
+        }
+
+        ui.separator();
+        egui::Grid::new("gripper_feedback_grid")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Actual width:");
+                match poller.get(&actual_width_key(robot_id)) {
+                    Some(SPValue::Float64(FloatOrUnknown::Float64(width))) => {
+                        ui.monospace(format!("{:.1} mm", width.into_inner() * 1000.0));
+                    }
+                    other => {
+                        ui.monospace(sp_value_to_display(other));
+                    }
+                }
+                ui.end_row();
+                ui.label("Part detected:");
+                match poller.get(&part_detected_key(robot_id)) {
+                    Some(SPValue::Bool(BoolOrUnknown::Bool(true))) => {
+                        ui.colored_label(egui::Color32::GREEN, "yes");
+                    }
+                    Some(SPValue::Bool(BoolOrUnknown::Bool(false))) => {
+                        ui.label("no");
+                    }
+                    other => {
+                        ui.monospace(sp_value_to_display(other));
+                    }
+                }
+                ui.end_row();
+                ui.label("Request state:");
+                ui.monospace(sp_value_to_display(
+                    poller.get(&request_state_key(robot_id)),
+                ));
+                ui.end_row();
+            });
+
+        if let Some(command) = command {
+            self.status = None;
+            let handle = handle.clone();
+            let con_clone = connection.clone();
+            let robot_id = robot_id.to_string();
+            let width = self.width / 1000.0;
+            let force = self.force;
+            self.promise = Some(workers::spawn("gripper_command", move || {
+                handle.block_on(send_gripper_command(
+                    con_clone, robot_id, command, width, force,
+                ))
+            }));
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use crate::screwdriving::ProcessResult;
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, io::Write};
+
+/// Append-only log of sent commands, one JSON object per line.
+const HISTORY_FILE: &str = "command_history.jsonl";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Seconds since the unix epoch.
+    pub timestamp: u64,
+    pub robot_id: String,
+    pub command_type: String,
+    pub goal_feature_id: Option<String>,
+    /// Automatic retry this entry was sent as, 0 for the first attempt.
+    #[serde(default)]
+    pub retry: u32,
+    /// What the spindle reported, for screwdriving commands.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub process: Option<ProcessResult>,
+}
+
+impl HistoryEntry {
+    pub fn now(robot_id: &str, command_type: &str, goal_feature_id: Option<&str>) -> Self {
+        Self {
+            timestamp: storage::now_secs(),
+            robot_id: robot_id.to_string(),
+            command_type: command_type.to_string(),
+            goal_feature_id: goal_feature_id.map(|g| g.to_string()),
+            retry: 0,
+            process: None,
+        }
+    }
+}
+
+pub fn record(entry: &HistoryEntry) {
+    let path = storage::data_file(HISTORY_FILE);
+    let result = std::fs::create_dir_all(storage::data_dir())
+        .and_then(|_| {
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+        })
+        .and_then(|mut file| {
+            let line = serde_json::to_string(entry).map_err(std::io::Error::other)?;
+            writeln!(file, "{}", line)
+        });
+    if let Err(e) = result {
+        log::error!("Failed to record command history to {:?}: {}", path, e);
+    }
+}
+
+pub fn load() -> Vec<HistoryEntry> {
+    let Ok(content) = std::fs::read_to_string(storage::data_file(HISTORY_FILE)) else {
+        return Vec::new();
+    };
+    content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrameUsage {
+    pub count: usize,
+    /// Seconds since the unix epoch of the most recent use.
+    pub last_used: u64,
+}
+
+/// How often, and when last, each goal frame was used in a command.
+pub fn frame_usage(entries: &[HistoryEntry]) -> HashMap<String, FrameUsage> {
+    let mut usage: HashMap<String, FrameUsage> = HashMap::new();
+    for entry in entries {
+        if let Some(goal) = &entry.goal_feature_id {
+            let u = usage.entry(goal.clone()).or_default();
+            u.count += 1;
+            u.last_used = u.last_used.max(entry.timestamp);
+        }
+    }
+    usage
+}
+
+/// Sorts frame names by descending usage, alphabetically among equals.
+pub fn sort_by_usage(keys: &mut [String], usage: &HashMap<String, FrameUsage>) {
+    keys.sort_by(|a, b| {
+        let ca = usage.get(a).map(|u| u.count).unwrap_or(0);
+        let cb = usage.get(b).map(|u| u.count).unwrap_or(0);
+        cb.cmp(&ca).then_with(|| a.cmp(b))
+    });
+}
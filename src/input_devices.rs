@@ -0,0 +1,486 @@
+//! Gamepads and other game controllers, bound to Stop, preset runs or jog
+//! axes. A 3D mouse shows up here when the OS exposes it as a joystick, its
+//! six axes then map onto the stick axes. Barcode scanners type like a
+//! keyboard and are only listed once they have scanned something.
+
+use crate::barcode;
+use crate::storage;
+use eframe::egui;
+use gilrs::{Axis, Button, EventType, GamepadId, Gilrs};
+use serde::{Deserialize, Serialize};
+use std::{collections::VecDeque, time::Instant};
+
+pub const INPUT_BINDINGS_FILE: &str = "input_bindings.json";
+
+pub fn check_file() -> Option<Result<(), String>> {
+    storage::check_json::<Bindings>(INPUT_BINDINGS_FILE)
+}
+
+/// Axis values closer to zero than this are stick drift.
+const DEADZONE: f32 = 0.15;
+/// Events kept for the event log.
+const EVENT_LOG_LENGTH: usize = 30;
+
+const AXES: [Axis; 8] = [
+    Axis::LeftStickX,
+    Axis::LeftStickY,
+    Axis::LeftZ,
+    Axis::RightStickX,
+    Axis::RightStickY,
+    Axis::RightZ,
+    Axis::DPadX,
+    Axis::DPadY,
+];
+
+const BUTTONS: [Button; 19] = [
+    Button::South,
+    Button::East,
+    Button::North,
+    Button::West,
+    Button::C,
+    Button::Z,
+    Button::LeftTrigger,
+    Button::LeftTrigger2,
+    Button::RightTrigger,
+    Button::RightTrigger2,
+    Button::Select,
+    Button::Start,
+    Button::Mode,
+    Button::LeftThumb,
+    Button::RightThumb,
+    Button::DPadUp,
+    Button::DPadDown,
+    Button::DPadLeft,
+    Button::DPadRight,
+];
+
+/// Cartesian jog directions, in the order of the jog vector.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JogAxis {
+    X,
+    Y,
+    Z,
+    Rx,
+    Ry,
+    Rz,
+}
+
+impl JogAxis {
+    pub const ALL: [JogAxis; 6] = [
+        JogAxis::X,
+        JogAxis::Y,
+        JogAxis::Z,
+        JogAxis::Rx,
+        JogAxis::Ry,
+        JogAxis::Rz,
+    ];
+
+    pub fn index(self) -> usize {
+        JogAxis::ALL.iter().position(|a| *a == self).unwrap_or(0)
+    }
+}
+
+impl std::fmt::Display for JogAxis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JogAxis::X => write!(f, "X"),
+            JogAxis::Y => write!(f, "Y"),
+            JogAxis::Z => write!(f, "Z"),
+            JogAxis::Rx => write!(f, "Rx"),
+            JogAxis::Ry => write!(f, "Ry"),
+            JogAxis::Rz => write!(f, "Rz"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum InputAction {
+    Stop,
+    /// Opens the run form of the named Robot tab preset.
+    RunPreset(String),
+    Jog(JogAxis),
+}
+
+impl std::fmt::Display for InputAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InputAction::Stop => write!(f, "Stop"),
+            InputAction::RunPreset(name) => write!(f, "Run preset {}", name),
+            InputAction::Jog(axis) => write!(f, "Jog {}", axis),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Input {
+    Button(Button),
+    Axis(Axis),
+}
+
+impl std::fmt::Display for Input {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Input::Button(button) => write!(f, "{:?}", button),
+            Input::Axis(axis) => write!(f, "{:?} axis", axis),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Binding {
+    /// Device name, any device if `None`.
+    pub device: Option<String>,
+    pub input: Input,
+    pub action: InputAction,
+    /// Reverses the direction of a jog.
+    #[serde(default)]
+    pub invert: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Bindings {
+    bindings: Vec<Binding>,
+}
+
+impl Bindings {
+    fn load() -> Self {
+        storage::load_json(INPUT_BINDINGS_FILE).unwrap_or_default()
+    }
+
+    fn save(&self) {
+        if let Err(e) = storage::save_json(INPUT_BINDINGS_FILE, self) {
+            log::error!("Failed to save input bindings: {}", e);
+        }
+    }
+}
+
+fn deadzone(value: f32) -> f64 {
+    if value.abs() < DEADZONE {
+        0.0
+    } else {
+        value as f64
+    }
+}
+
+/// Polls the controllers every frame and owns the bindings.
+pub struct InputDevices {
+    /// `None` where the platform has no gamepad support.
+    gilrs: Option<Gilrs>,
+    error: Option<String>,
+    bindings: Bindings,
+    /// Waiting for an input to bind while set.
+    learning: bool,
+    learned: Option<(String, Input)>,
+    any_device: bool,
+    new_action: InputAction,
+    events: VecDeque<(Instant, String)>,
+    selected_device: Option<GamepadId>,
+}
+
+impl InputDevices {
+    pub fn new() -> Self {
+        let (gilrs, error) = match Gilrs::new() {
+            Ok(gilrs) => (Some(gilrs), None),
+            Err(gilrs::Error::NotImplemented(gilrs)) => (
+                Some(gilrs),
+                Some("Gamepads are not supported on this platform.".to_string()),
+            ),
+            Err(e) => {
+                log::error!("Failed to open gamepads: {}", e);
+                (None, Some(format!("Failed to open gamepads: {}", e)))
+            }
+        };
+        Self {
+            gilrs,
+            error,
+            bindings: Bindings::load(),
+            learning: false,
+            learned: None,
+            any_device: false,
+            new_action: InputAction::Stop,
+            events: VecDeque::new(),
+            selected_device: None,
+        }
+    }
+
+    fn log_event(&mut self, text: String) {
+        self.events.push_front((Instant::now(), text));
+        self.events.truncate(EVENT_LOG_LENGTH);
+    }
+
+    /// Drains the controller events, call every frame. Returns the Stop and
+    /// preset actions whose button was pressed, jogging is read with `jog`.
+    pub fn update(&mut self) -> Vec<InputAction> {
+        let mut actions = Vec::new();
+        let Some(gilrs) = self.gilrs.as_mut() else {
+            return actions;
+        };
+        let mut events = Vec::new();
+        while let Some(event) = gilrs.next_event() {
+            let name = gilrs.gamepad(event.id).name().to_string();
+            events.push((name, event.event));
+        }
+        for (name, event) in events {
+            match event {
+                EventType::Connected => self.log_event(format!("{} connected", name)),
+                EventType::Disconnected => self.log_event(format!("{} disconnected", name)),
+                EventType::ButtonPressed(button, _) => {
+                    self.log_event(format!("{}: {:?} pressed", name, button));
+                    let input = Input::Button(button);
+                    if self.learning {
+                        self.learned = Some((name, input));
+                        self.learning = false;
+                        continue;
+                    }
+                    for binding in &self.bindings.bindings {
+                        let matches = binding.input == input
+                            && binding.device.as_ref().is_none_or(|d| *d == name);
+                        if matches && !matches!(binding.action, InputAction::Jog(_)) {
+                            actions.push(binding.action.clone());
+                        }
+                    }
+                }
+                EventType::AxisChanged(axis, value, _) if self.learning && value.abs() > 0.5 => {
+                    self.log_event(format!("{}: {:?} moved", name, axis));
+                    self.learned = Some((name, Input::Axis(axis)));
+                    self.learning = false;
+                }
+                _ => (),
+            }
+        }
+        actions
+    }
+
+    /// Jog vector from the bound axes and buttons, each in -1..1.
+    pub fn jog(&self) -> [f64; 6] {
+        let mut jog = [0.0; 6];
+        let Some(gilrs) = self.gilrs.as_ref() else {
+            return jog;
+        };
+        for binding in &self.bindings.bindings {
+            let InputAction::Jog(axis) = binding.action else {
+                continue;
+            };
+            for (_, gamepad) in gilrs.gamepads() {
+                if binding.device.as_ref().is_some_and(|d| d != gamepad.name()) {
+                    continue;
+                }
+                let value = match binding.input {
+                    Input::Axis(a) => deadzone(gamepad.value(a)),
+                    Input::Button(b) if gamepad.is_pressed(b) => 1.0,
+                    Input::Button(_) => 0.0,
+                };
+                let value = if binding.invert { -value } else { value };
+                let i = axis.index();
+                jog[i] = (jog[i] + value).clamp(-1.0, 1.0);
+            }
+        }
+        jog
+    }
+
+    /// The Input Devices tab.
+    pub fn ui(&mut self, ui: &mut egui::Ui, presets: &[String]) {
+        ui.heading("Input Devices");
+        ui.separator();
+        if let Some(e) = &self.error {
+            ui.colored_label(egui::Color32::RED, e);
+        }
+        self.draw_devices(ui);
+        ui.separator();
+        ui.heading("Bindings");
+        self.draw_bindings(ui, presets);
+        ui.separator();
+        ui.heading("Live Input");
+        self.draw_visualizer(ui);
+        ui.label("Events:");
+        egui::ScrollArea::vertical()
+            .id_salt("input_event_log")
+            .max_height(150.0)
+            .show(ui, |ui| {
+                for (at, text) in &self.events {
+                    ui.monospace(format!(
+                        "{:>5.1} s ago  {}",
+                        at.elapsed().as_secs_f64(),
+                        text
+                    ));
+                }
+            });
+    }
+
+    fn draw_devices(&mut self, ui: &mut egui::Ui) {
+        egui::Grid::new("input_devices_grid")
+            .num_columns(3)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("Device");
+                ui.strong("Kind");
+                ui.strong("Status");
+                ui.end_row();
+                if let Some(gilrs) = self.gilrs.as_ref() {
+                    for (id, gamepad) in gilrs.gamepads() {
+                        if ui
+                            .selectable_label(self.selected_device == Some(id), gamepad.name())
+                            .clicked()
+                        {
+                            self.selected_device = Some(id);
+                        }
+                        ui.label("Game controller");
+                        ui.label(if gamepad.is_connected() {
+                            format!("connected, {:?}", gamepad.power_info())
+                        } else {
+                            "disconnected".to_string()
+                        });
+                        ui.end_row();
+                    }
+                }
+                if let Some((at, code)) = barcode::last_scan() {
+                    ui.label("Barcode scanner");
+                    ui.label("Keyboard wedge");
+                    ui.label(format!(
+                        "last scan {} ({} s ago)",
+                        code,
+                        at.elapsed().as_secs()
+                    ));
+                    ui.end_row();
+                }
+            });
+        if self
+            .gilrs
+            .as_ref()
+            .is_none_or(|g| g.gamepads().next().is_none())
+        {
+            ui.weak("No game controllers detected.");
+        }
+    }
+
+    fn draw_bindings(&mut self, ui: &mut egui::Ui, presets: &[String]) {
+        let mut remove = None;
+        let mut changed = false;
+        egui::Grid::new("input_bindings_grid")
+            .num_columns(5)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("Device");
+                ui.strong("Input");
+                ui.strong("Action");
+                ui.strong("Invert");
+                ui.label("");
+                ui.end_row();
+                for (i, binding) in self.bindings.bindings.iter_mut().enumerate() {
+                    ui.label(binding.device.as_deref().unwrap_or("any"));
+                    ui.monospace(binding.input.to_string());
+                    ui.label(binding.action.to_string());
+                    if matches!(binding.action, InputAction::Jog(_)) {
+                        changed |= ui.checkbox(&mut binding.invert, "").changed();
+                    } else {
+                        ui.label("");
+                    }
+                    if ui.button("🗑").clicked() {
+                        remove = Some(i);
+                    }
+                    ui.end_row();
+                }
+            });
+        if let Some(i) = remove {
+            self.bindings.bindings.remove(i);
+            changed = true;
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("New binding:");
+            egui::ComboBox::from_id_salt("input_binding_action")
+                .selected_text(self.new_action.to_string())
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.new_action, InputAction::Stop, "Stop");
+                    for axis in JogAxis::ALL {
+                        let action = InputAction::Jog(axis);
+                        let text = action.to_string();
+                        ui.selectable_value(&mut self.new_action, action, text);
+                    }
+                    for name in presets {
+                        let action = InputAction::RunPreset(name.clone());
+                        let text = action.to_string();
+                        ui.selectable_value(&mut self.new_action, action, text);
+                    }
+                });
+            if self.learning {
+                ui.spinner();
+                ui.label("Press a button or move an axis…");
+                if ui.button("Cancel").clicked() {
+                    self.learning = false;
+                }
+            } else if ui.button("Learn Input").clicked() {
+                self.learned = None;
+                self.learning = true;
+            }
+        });
+        if let Some((device, input)) = self.learned.clone() {
+            ui.horizontal(|ui| {
+                ui.label(format!("{} on {}", input, device));
+                ui.checkbox(&mut self.any_device, "Any device");
+                if ui.button("Add Binding").clicked() {
+                    self.bindings.bindings.push(Binding {
+                        device: (!self.any_device).then_some(device),
+                        input,
+                        action: self.new_action.clone(),
+                        invert: false,
+                    });
+                    self.learned = None;
+                    changed = true;
+                }
+            });
+        }
+        if changed {
+            self.bindings.save();
+        }
+    }
+
+    fn draw_visualizer(&self, ui: &mut egui::Ui) {
+        let Some(gilrs) = self.gilrs.as_ref() else {
+            return;
+        };
+        let gamepad = match self.selected_device {
+            Some(id) => gilrs.connected_gamepad(id),
+            None => gilrs.gamepads().next().map(|(_, g)| g),
+        };
+        let Some(gamepad) = gamepad else {
+            ui.weak("Select a connected device above.");
+            return;
+        };
+        ui.label(gamepad.name());
+        egui::Grid::new("input_axes_grid")
+            .num_columns(2)
+            .show(ui, |ui| {
+                for axis in AXES {
+                    let value = gamepad.value(axis);
+                    ui.monospace(format!("{:?}", axis));
+                    ui.add(
+                        egui::ProgressBar::new((value + 1.0) / 2.0)
+                            .desired_width(200.0)
+                            .text(format!("{:+.2}", value)),
+                    );
+                    ui.end_row();
+                }
+            });
+        ui.horizontal_wrapped(|ui| {
+            for button in BUTTONS {
+                let text = egui::RichText::new(format!("{:?}", button)).monospace();
+                if gamepad.is_pressed(button) {
+                    ui.label(text.strong().color(egui::Color32::GREEN));
+                } else {
+                    ui.label(text.weak());
+                }
+            }
+        });
+        let jog = self.jog();
+        ui.label(format!(
+            "Jog: {}",
+            JogAxis::ALL
+                .iter()
+                .zip(jog)
+                .map(|(axis, v)| format!("{} {:+.2}", axis, v))
+                .collect::<Vec<_>>()
+                .join("  ")
+        ));
+    }
+}
@@ -0,0 +1,89 @@
+//! Camera inspection poses on a grid over a surface frame, for scanning jobs.
+
+use crate::pose::{self, Pose};
+
+/// Grid over the XY plane of a surface frame, centered on its origin. The
+/// surface normal is its +Z axis.
+#[derive(Debug, Clone)]
+pub struct InspectionGrid {
+    pub prefix: String,
+    pub rows: usize,
+    pub columns: usize,
+    /// Distance between neighboring grid points along the surface X axis.
+    pub spacing_x: f64,
+    /// Distance between neighboring grid points along the surface Y axis.
+    pub spacing_y: f64,
+    /// Distance from the camera to the grid point it looks at.
+    pub standoff: f64,
+    /// Tilt of the viewing direction from the surface normal, about the surface Y axis.
+    pub tilt: f64,
+}
+
+impl Default for InspectionGrid {
+    fn default() -> Self {
+        Self {
+            prefix: "scan".to_string(),
+            rows: 3,
+            columns: 3,
+            spacing_x: 0.1,
+            spacing_y: 0.1,
+            standoff: 0.4,
+            tilt: 0.0,
+        }
+    }
+}
+
+impl InspectionGrid {
+    pub fn frame_name(&self, row: usize, column: usize) -> String {
+        format!("{}_{}_{}", self.prefix, row + 1, column + 1)
+    }
+
+    /// Camera poses in the surface frame in scan order, with the camera Z axis
+    /// pointing at its grid point. Rows alternate direction so the camera never
+    /// travels back across the surface between rows.
+    pub fn poses(&self) -> Vec<(String, Pose)> {
+        let (sin_tilt, cos_tilt) = self.tilt.sin_cos();
+        // Flip Z to face the surface, then tilt about the surface Y axis
+        let rotation = pose::quaternion_multiply(
+            pose::rpy_to_quaternion([0.0, self.tilt, 0.0]),
+            pose::rpy_to_quaternion([std::f64::consts::PI, 0.0, 0.0]),
+        );
+        let center_x = (self.columns.saturating_sub(1)) as f64 * self.spacing_x / 2.0;
+        let center_y = (self.rows.saturating_sub(1)) as f64 * self.spacing_y / 2.0;
+
+        let mut poses = Vec::with_capacity(self.rows * self.columns);
+        for row in 0..self.rows {
+            let columns: Box<dyn Iterator<Item = usize>> = if row % 2 == 0 {
+                Box::new(0..self.columns)
+            } else {
+                Box::new((0..self.columns).rev())
+            };
+            for column in columns {
+                let target_x = column as f64 * self.spacing_x - center_x;
+                let target_y = row as f64 * self.spacing_y - center_y;
+                let pose = Pose {
+                    translation: [
+                        target_x + self.standoff * sin_tilt,
+                        target_y,
+                        self.standoff * cos_tilt,
+                    ],
+                    rotation,
+                };
+                poses.push((self.frame_name(row, column), pose));
+            }
+        }
+        poses
+    }
+
+    pub fn validate(&self) -> Option<String> {
+        if self.prefix.trim().is_empty() {
+            Some("Name prefix is empty".to_string())
+        } else if self.rows == 0 || self.columns == 0 {
+            Some("The grid needs at least one row and one column".to_string())
+        } else if self.standoff <= 0.0 {
+            Some("Standoff must be positive".to_string())
+        } else {
+            None
+        }
+    }
+}
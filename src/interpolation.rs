@@ -0,0 +1,116 @@
+//! Frames between two frames, evenly spaced in translation and slerped in
+//! rotation, to break a long MoveL into a smoother chain. They are published
+//! under the start frame as `{from}_to_{to}_{n}`.
+
+use crate::backend;
+use crate::kinematics;
+use crate::pose::Pose;
+use crate::provenance::Method;
+use eframe::egui;
+use micro_sp::*;
+use std::sync::Arc;
+
+const MAX_COUNT: usize = 100;
+
+pub struct Densify {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    /// Frames between the two, not counting them.
+    pub count: usize,
+    /// Append the new frames and the end frame to the path.
+    pub add_to_path: bool,
+}
+
+impl Densify {
+    pub fn new() -> Self {
+        Self {
+            from: None,
+            to: None,
+            count: 5,
+            add_to_path: true,
+        }
+    }
+
+    fn frame_name(from: &str, to: &str, n: usize) -> String {
+        format!("{}_to_{}_{}", from, to, n)
+    }
+
+    /// Why the frames can't be generated, if they can't.
+    pub fn problem(&self) -> Option<String> {
+        match (&self.from, &self.to) {
+            (Some(from), Some(to)) if from == to => Some("Select two different frames".to_string()),
+            (Some(_), Some(_)) if !(1..=MAX_COUNT).contains(&self.count) => {
+                Some(format!("Between 1 and {} frames", MAX_COUNT))
+            }
+            (Some(_), Some(_)) => None,
+            _ => Some("Select the start and end frames".to_string()),
+        }
+    }
+
+    /// Settings of the tool. Returns true when Generate is clicked.
+    pub fn draw(&mut self, ui: &mut egui::Ui, transform_keys: &[String], busy: bool) -> bool {
+        let mut generate = false;
+        ui.horizontal(|ui| {
+            for (label, salt, frame) in [
+                ("From:", "densify_from_select", &mut self.from),
+                ("To:", "densify_to_select", &mut self.to),
+            ] {
+                ui.label(label);
+                egui::ComboBox::from_id_salt(salt)
+                    .selected_text(frame.as_deref().unwrap_or("Select..."))
+                    .show_ui(ui, |ui| {
+                        for key in transform_keys {
+                            ui.selectable_value(frame, Some(key.clone()), key);
+                        }
+                    });
+            }
+            ui.label("Frames between:");
+            ui.add(egui::DragValue::new(&mut self.count).range(1..=MAX_COUNT));
+        });
+        match self.problem() {
+            Some(problem) => {
+                ui.colored_label(egui::Color32::YELLOW, problem);
+            }
+            None => {
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.add_to_path, "Add to the path")
+                        .on_hover_text("Append the new frames and the end frame as steps.");
+                    generate = ui
+                        .add_enabled(!busy, egui::Button::new("Generate"))
+                        .on_hover_text(
+                            "Publish the frames under the start frame. Generating again \n\
+                             overwrites the same frames.",
+                        )
+                        .clicked();
+                    if busy {
+                        ui.spinner();
+                    }
+                });
+            }
+        }
+        generate
+    }
+}
+
+/// Publishes `count` frames between `from` and `to` and returns their names
+/// in order from `from`.
+pub async fn publish_between(
+    con: Arc<ConnectionManager>,
+    from: String,
+    to: String,
+    count: usize,
+) -> Result<Vec<String>, String> {
+    let end = backend::lookup(con.clone(), &from, &to).await?;
+    let mut names = Vec::new();
+    for n in 1..=count {
+        let t = n as f64 / (count + 1) as f64;
+        let pose = Pose {
+            translation: end.translation.map(|x| x * t),
+            rotation: kinematics::slerp(Pose::default().rotation, end.rotation, t),
+        };
+        let name = Densify::frame_name(&from, &to, n);
+        backend::publish_frame(con.clone(), &from, &name, &pose, Method::Computed).await?;
+        names.push(name);
+    }
+    Ok(names)
+}
@@ -0,0 +1,198 @@
+//! Job lists from other programs (e.g. nesting) as a CSV, one move per row.
+//! A row names an existing goal frame, or gives a raw pose that is published
+//! as that frame first. Every row is checked before anything is loaded.
+//!
+//! Columns, by header name in any order:
+//! `goal,parent_frame_id,x,y,z,qx,qy,qz,qw,velocity,acceleration,command_type,force_threshold`.
+//! Only `goal` is required, the pose columns go together like in the CSV
+//! frame export.
+
+use crate::pose::{self, FrameMetadata, Pose};
+use crate::provenance::{self, Method};
+use micro_sp::*;
+use std::{collections::HashMap, sync::Arc};
+
+pub const COMMAND_TYPES: [&str; 4] = [
+    "unsafe_move_l",
+    "unsafe_move_j",
+    "safe_move_l",
+    "safe_move_j",
+];
+
+const POSE_COLUMNS: [&str; 7] = ["x", "y", "z", "qx", "qy", "qz", "qw"];
+
+#[derive(Debug, Clone)]
+pub struct JobRow {
+    pub goal: String,
+    /// Parent frame and pose to publish the goal at, `None` for existing frames.
+    pub pose: Option<(String, Pose)>,
+    pub velocity: f64,
+    pub acceleration: f64,
+    pub command_type: String,
+    /// Only for safe moves.
+    pub force_threshold: Option<f64>,
+}
+
+impl JobRow {
+    pub fn is_joint_move(&self) -> bool {
+        self.command_type.ends_with("_j")
+    }
+}
+
+fn parse_row(fields: &HashMap<&str, &str>, known_frames: &[String]) -> Result<JobRow, String> {
+    let text = |column: &str| fields.get(column).copied().unwrap_or("").trim();
+    let number = |column: &str| -> Result<Option<f64>, String> {
+        match text(column) {
+            "" => Ok(None),
+            value => value
+                .parse::<f64>()
+                .map(Some)
+                .map_err(|_| format!("{} is not a number: {}", column, value)),
+        }
+    };
+
+    let goal = text("goal").to_string();
+    if goal.is_empty() {
+        return Err("goal is empty".to_string());
+    }
+    let values = POSE_COLUMNS
+        .iter()
+        .map(|column| number(column))
+        .collect::<Result<Vec<Option<f64>>, String>>()?;
+    let pose = match values.iter().filter(|v| v.is_some()).count() {
+        0 => {
+            if !known_frames.contains(&goal) {
+                return Err(format!("{} is not a frame and the row has no pose", goal));
+            }
+            None
+        }
+        7 => {
+            let v = values
+                .iter()
+                .map(|v| v.unwrap_or(0.0))
+                .collect::<Vec<f64>>();
+            let rotation = [v[3], v[4], v[5], v[6]];
+            if rotation.iter().map(|q| q * q).sum::<f64>() < 1e-9 {
+                return Err("the quaternion is zero".to_string());
+            }
+            let parent = match text("parent_frame_id") {
+                "" => "world".to_string(),
+                parent => parent.to_string(),
+            };
+            let pose = Pose {
+                translation: [v[0], v[1], v[2]],
+                rotation: pose::normalize_quaternion(rotation),
+            };
+            Some((parent, pose))
+        }
+        _ => return Err(format!("the pose needs all of {}", POSE_COLUMNS.join(", "))),
+    };
+
+    let velocity = number("velocity")?.unwrap_or(0.1);
+    let acceleration = number("acceleration")?.unwrap_or(0.1);
+    if !(velocity > 0.0 && velocity <= 1.0) || !(acceleration > 0.0 && acceleration <= 1.0) {
+        return Err("velocity and acceleration must be above 0 and at most 1".to_string());
+    }
+    let command_type = match text("command_type") {
+        "" => "unsafe_move_l".to_string(),
+        command_type if COMMAND_TYPES.contains(&command_type) => command_type.to_string(),
+        command_type => {
+            return Err(format!(
+                "unknown command_type {}, use one of {}",
+                command_type,
+                COMMAND_TYPES.join(", ")
+            ));
+        }
+    };
+    let force_threshold = number("force_threshold")?;
+    match (command_type.starts_with("safe_"), force_threshold) {
+        (true, None) => return Err(format!("{} needs a force_threshold", command_type)),
+        (true, Some(force)) if force <= 0.0 => {
+            return Err("force_threshold must be above zero".to_string());
+        }
+        (false, Some(_)) => {
+            return Err(format!("{} doesn't use a force_threshold", command_type));
+        }
+        _ => (),
+    }
+
+    Ok(JobRow {
+        goal,
+        pose,
+        velocity,
+        acceleration,
+        command_type,
+        force_threshold,
+    })
+}
+
+/// The rows of a job list, or every problem found with their line numbers.
+pub fn parse(content: &str, known_frames: &[String]) -> Result<Vec<JobRow>, Vec<String>> {
+    let mut lines = content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+    let Some((_, header)) = lines.next() else {
+        return Err(vec!["The file is empty".to_string()]);
+    };
+    let columns = header.split(',').map(str::trim).collect::<Vec<&str>>();
+    if !columns.contains(&"goal") {
+        return Err(vec!["The header has no goal column".to_string()]);
+    }
+    // Goals published by earlier rows can be driven to again
+    let mut frames = known_frames.to_vec();
+    let mut published = Vec::new();
+    let mut rows = Vec::new();
+    let mut errors = Vec::new();
+    for (i, line) in lines {
+        let fields = columns
+            .iter()
+            .copied()
+            .zip(line.split(','))
+            .collect::<HashMap<&str, &str>>();
+        match parse_row(&fields, &frames) {
+            Ok(row) if row.pose.is_some() && published.contains(&row.goal) => errors.push(format!(
+                "Line {}: {} already has a pose on an earlier line",
+                i + 1,
+                row.goal
+            )),
+            Ok(row) => {
+                if row.pose.is_some() {
+                    published.push(row.goal.clone());
+                    frames.push(row.goal.clone());
+                }
+                rows.push(row);
+            }
+            Err(e) => errors.push(format!("Line {}: {}", i + 1, e)),
+        }
+    }
+    if rows.is_empty() && errors.is_empty() {
+        errors.push("The file has no rows".to_string());
+    }
+    if errors.is_empty() {
+        Ok(rows)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Publishes the goals of rows with a pose, stopping at the first failure.
+pub async fn publish_poses(
+    con: Arc<ConnectionManager>,
+    rows: Vec<JobRow>,
+) -> Result<Vec<JobRow>, String> {
+    for row in &rows {
+        let Some((parent, pose)) = &row.pose else {
+            continue;
+        };
+        let mut transform = pose::make_transform_stamped(
+            parent,
+            &row.goal,
+            pose,
+            &FrameMetadata::for_frame(&row.goal),
+        );
+        provenance::stamp(&mut transform, Method::Imported);
+        crate::transforms::publish_transform(con.clone(), transform).await?;
+    }
+    Ok(rows)
+}
@@ -0,0 +1,322 @@
+//! Jogging for fine positioning while teaching: while a direction button is
+//! held, or a gamepad axis bound to jogging is deflected, small relative-pose
+//! moves of the TCP are sent one after the other. Joint buttons step a single
+//! joint from the current joint states instead, which also works close to
+//! singularities where moving the TCP fails. Steps are sent once, without
+//! retries or history entries, so nothing moves after the button is released.
+
+use crate::backend;
+use crate::commissioning::TestMoveFrames;
+use crate::input_devices::JogAxis;
+use crate::joint_limits::JointLimits;
+use crate::locale::LocalizedDragValue;
+use crate::workers;
+use eframe::egui;
+use micro_sp::*;
+use poll_promise::Promise;
+use std::{sync::Arc, time::Duration};
+
+const ACCELERATION: f64 = 0.1;
+const JOINT_ACCELERATION: f64 = 0.2;
+/// A step that takes longer than this has gone wrong, jogging stops.
+const STEP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// One `unsafe_move_l` of the TCP by `offset`, given in the TCP frame as
+/// [x, y, z, rx, ry, rz] in meters and radians.
+async fn jog_step(
+    con: Arc<ConnectionManager>,
+    frames: TestMoveFrames,
+    offset: [f64; 6],
+    velocity: f64,
+) -> Result<(), String> {
+    let parameters = vec![
+        ("command_type".to_string(), "unsafe_move_l".to_spvalue()),
+        ("accelleration".to_string(), ACCELERATION.to_spvalue()),
+        ("velocity".to_string(), velocity.to_spvalue()),
+        ("use_execution_time".to_string(), false.to_spvalue()),
+        ("use_blend_radius".to_string(), false.to_spvalue()),
+        ("use_joint_positions".to_string(), false.to_spvalue()),
+        ("use_preferred_joint_config".to_string(), false.to_spvalue()),
+        ("use_relative_pose".to_string(), true.to_spvalue()),
+        (
+            "relative_pose".to_string(),
+            SPValue::Array(ArrayOrUnknown::Array(
+                offset.iter().map(|x| x.to_spvalue()).collect(),
+            )),
+        ),
+        (
+            "baseframe_id".to_string(),
+            frames.baseframe.as_str().to_spvalue(),
+        ),
+        (
+            "faceplate_id".to_string(),
+            frames.faceplate.as_str().to_spvalue(),
+        ),
+        (
+            "goal_feature_id".to_string(),
+            frames.tcp.as_str().to_spvalue(),
+        ),
+        ("tcp_id".to_string(), frames.tcp.as_str().to_spvalue()),
+        ("root_frame_id".to_string(), "world".to_spvalue()),
+    ];
+    backend::send_command_once(con, &frames.robot_id, parameters, STEP_TIMEOUT).await
+}
+
+/// One `unsafe_move_j` turning `joint` by `delta` radians from where it is,
+/// stopping at the soft limits.
+async fn joint_step(
+    con: Arc<ConnectionManager>,
+    frames: TestMoveFrames,
+    limits: JointLimits,
+    joint: usize,
+    delta: f64,
+    velocity: f64,
+) -> Result<(), String> {
+    let mut joints = crate::lookup::get_joint_states(con.clone(), &frames.robot_id)
+        .await
+        .ok_or_else(|| format!("No joint states of robot {}", frames.robot_id))?;
+    let value = joints
+        .get_mut(joint)
+        .ok_or_else(|| format!("Robot {} has no joint J{}", frames.robot_id, joint + 1))?;
+    let (min, max) = (limits.min[joint], limits.max[joint]);
+    // Outside the limits, only steps back towards them are taken
+    let target = (*value + delta).clamp(min.min(*value), max.max(*value));
+    if target == *value {
+        return Err(format!(
+            "J{} is at its soft limit ({:.3} to {:.3} rad)",
+            joint + 1,
+            min,
+            max
+        ));
+    }
+    *value = target;
+    let parameters = vec![
+        ("command_type".to_string(), "unsafe_move_j".to_spvalue()),
+        ("accelleration".to_string(), JOINT_ACCELERATION.to_spvalue()),
+        ("velocity".to_string(), velocity.to_spvalue()),
+        ("use_execution_time".to_string(), false.to_spvalue()),
+        ("use_blend_radius".to_string(), false.to_spvalue()),
+        ("use_joint_positions".to_string(), true.to_spvalue()),
+        (
+            "joint_positions".to_string(),
+            SPValue::Array(ArrayOrUnknown::Array(
+                joints.iter().map(|x| x.to_spvalue()).collect(),
+            )),
+        ),
+        ("use_preferred_joint_config".to_string(), false.to_spvalue()),
+        ("use_relative_pose".to_string(), false.to_spvalue()),
+        (
+            "baseframe_id".to_string(),
+            frames.baseframe.as_str().to_spvalue(),
+        ),
+        (
+            "faceplate_id".to_string(),
+            frames.faceplate.as_str().to_spvalue(),
+        ),
+        (
+            "goal_feature_id".to_string(),
+            frames.tcp.as_str().to_spvalue(),
+        ),
+        ("tcp_id".to_string(), frames.tcp.as_str().to_spvalue()),
+        ("root_frame_id".to_string(), "world".to_spvalue()),
+    ];
+    backend::send_command_once(con, &frames.robot_id, parameters, STEP_TIMEOUT).await
+}
+
+pub struct JogPanel {
+    /// mm per step.
+    step_translation: f64,
+    /// Degrees per step.
+    step_rotation: f64,
+    /// m/s.
+    velocity: f64,
+    /// Radians per joint step.
+    step_joint: f64,
+    /// rad/s.
+    joint_velocity: f64,
+    /// Soft limits of the robot, joint steps stop at them.
+    limits: JointLimits,
+    promise: Option<Promise<Result<(), String>>>,
+    error: Option<String>,
+}
+
+impl JogPanel {
+    pub fn new() -> Self {
+        Self {
+            step_translation: 1.0,
+            step_rotation: 1.0,
+            velocity: 0.02,
+            step_joint: 0.01,
+            joint_velocity: 0.1,
+            limits: JointLimits::default(),
+            promise: None,
+            error: None,
+        }
+    }
+
+    pub fn set_limits(&mut self, limits: JointLimits) {
+        self.limits = limits;
+    }
+
+    /// The offset of one step in `direction`, each axis in -1..1.
+    fn offset(&self, direction: [f64; 6]) -> [f64; 6] {
+        let mut offset = [0.0; 6];
+        for (i, d) in direction.iter().enumerate() {
+            offset[i] = if i < 3 {
+                d * self.step_translation / 1000.0
+            } else {
+                d * self.step_rotation.to_radians()
+            };
+        }
+        offset
+    }
+
+    /// `device` is the jog vector of the input devices, `blocked` why the
+    /// robot can't be moved right now.
+    pub fn draw(
+        &mut self,
+        ui: &mut egui::Ui,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+        frames: Result<TestMoveFrames, String>,
+        device: [f64; 6],
+        blocked: Option<String>,
+    ) {
+        if let Some(promise) = self.promise.take() {
+            match promise.try_take() {
+                Ok(Ok(())) => (),
+                Ok(Err(e)) => self.error = Some(e),
+                Err(promise) => self.promise = Some(promise),
+            }
+        }
+        ui.horizontal(|ui| {
+            ui.label("Step:");
+            ui.add(
+                egui::DragValue::new(&mut self.step_translation)
+                    .localized()
+                    .suffix(" mm")
+                    .speed(0.1)
+                    .range(0.1..=50.0),
+            );
+            ui.add(
+                egui::DragValue::new(&mut self.step_rotation)
+                    .localized()
+                    .suffix(" °")
+                    .speed(0.1)
+                    .range(0.1..=15.0),
+            );
+            ui.label("Speed:");
+            ui.add(
+                egui::DragValue::new(&mut self.velocity)
+                    .localized()
+                    .suffix(" m/s")
+                    .speed(0.001)
+                    .range(0.001..=0.1),
+            );
+        });
+        let problem = frames.as_ref().err().cloned().or(blocked);
+        let mut direction = [0.0; 6];
+        ui.add_enabled_ui(problem.is_none(), |ui| {
+            egui::Grid::new("jog_buttons")
+                .num_columns(6)
+                .spacing([4.0, 4.0])
+                .show(ui, |ui| {
+                    for axis in JogAxis::ALL {
+                        for sign in [-1.0, 1.0] {
+                            let label = format!("{}{}", if sign < 0.0 { "−" } else { "+" }, axis);
+                            let button =
+                                ui.add(egui::Button::new(label).min_size([48.0, 0.0].into()));
+                            if button.is_pointer_button_down_on() {
+                                direction[axis.index()] = sign;
+                            }
+                        }
+                        if axis == JogAxis::Z || axis == JogAxis::Rz {
+                            ui.end_row();
+                        }
+                    }
+                });
+        });
+        ui.weak("Hold a button to move, in the TCP frame.");
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label("Joint step:");
+            ui.add(
+                egui::DragValue::new(&mut self.step_joint)
+                    .localized()
+                    .suffix(" rad")
+                    .speed(0.001)
+                    .range(0.001..=0.2),
+            );
+            ui.label("Speed:");
+            ui.add(
+                egui::DragValue::new(&mut self.joint_velocity)
+                    .localized()
+                    .suffix(" rad/s")
+                    .speed(0.01)
+                    .range(0.01..=0.5),
+            );
+        });
+        let mut joint_direction = None;
+        ui.add_enabled_ui(problem.is_none(), |ui| {
+            egui::Grid::new("jog_joint_buttons")
+                .num_columns(6)
+                .spacing([4.0, 4.0])
+                .show(ui, |ui| {
+                    for joint in 0..6 {
+                        for sign in [-1.0, 1.0] {
+                            let label =
+                                format!("{}J{}", if sign < 0.0 { "−" } else { "+" }, joint + 1);
+                            let button =
+                                ui.add(egui::Button::new(label).min_size([48.0, 0.0].into()));
+                            if button.is_pointer_button_down_on() {
+                                joint_direction = Some((joint, sign));
+                            }
+                        }
+                        if joint == 2 || joint == 5 {
+                            ui.end_row();
+                        }
+                    }
+                });
+        });
+        if let Some(problem) = &problem {
+            ui.colored_label(egui::Color32::YELLOW, problem);
+        }
+        if let Some(e) = &self.error {
+            ui.colored_label(egui::Color32::RED, e);
+        }
+
+        if direction.iter().all(|d| *d == 0.0) {
+            direction = device;
+        }
+        let jogging = direction.iter().any(|d| *d != 0.0) || joint_direction.is_some();
+        if jogging {
+            // Keep polling the held button and the promise
+            ui.ctx().request_repaint();
+        }
+        let Ok(frames) = frames else {
+            return;
+        };
+        if !jogging || problem.is_some() || self.promise.is_some() {
+            return;
+        }
+        self.error = None;
+        let handle = handle.clone();
+        let con_clone = connection.clone();
+        if let Some((joint, sign)) = joint_direction {
+            let delta = sign * self.step_joint;
+            let velocity = self.joint_velocity;
+            let limits = self.limits;
+            self.promise = Some(workers::spawn("jog_joint_step", move || {
+                handle.block_on(joint_step(
+                    con_clone, frames, limits, joint, delta, velocity,
+                ))
+            }));
+            return;
+        }
+        let offset = self.offset(direction);
+        let velocity = self.velocity;
+        self.promise = Some(workers::spawn("jog_step", move || {
+            handle.block_on(jog_step(con_clone, frames, offset, velocity))
+        }));
+    }
+}
@@ -0,0 +1,98 @@
+//! Live joints against the preferred joint configuration taught with the goal
+//! frame. A large difference usually means the frame was taught for a
+//! different cell layout and the configuration should be taught again.
+
+use crate::locale;
+use crate::pose;
+use eframe::egui;
+use micro_sp::{ArrayOrUnknown, FloatOrUnknown, SPTransformStamped, SPValue};
+
+pub fn joint_states_key(robot_id: &str) -> String {
+    format!("{}_joint_states", robot_id)
+}
+
+/// The numbers of an array value, `None` if it isn't an array of floats.
+pub fn floats(value: &SPValue) -> Option<Vec<f64>> {
+    let SPValue::Array(ArrayOrUnknown::Array(values)) = value else {
+        return None;
+    };
+    values
+        .iter()
+        .map(|v| match v {
+            SPValue::Float64(FloatOrUnknown::Float64(f)) => Some(f.0),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The frame's `preferred_joint_configuration` metadata, in radians.
+pub fn taught_configuration(transform: &SPTransformStamped) -> Option<Vec<f64>> {
+    pose::get_metadata_field(transform, "preferred_joint_configuration")
+        .and_then(floats)
+        .filter(|joints| !joints.is_empty())
+}
+
+/// Per joint difference to the taught configuration, warned about when a
+/// joint is more than `threshold` degrees off.
+pub fn draw(ui: &mut egui::Ui, taught: &[f64], live: Option<&SPValue>, threshold: f64) {
+    let Some(live) = live.and_then(floats) else {
+        ui.weak("Live joint states not available.");
+        return;
+    };
+    let differences = taught
+        .iter()
+        .zip(&live)
+        .map(|(taught, live)| (live - taught).to_degrees())
+        .collect::<Vec<f64>>();
+    let worst = differences.iter().fold(0.0_f64, |max, d| max.max(d.abs()));
+    egui::Grid::new("joint_drift_grid")
+        .num_columns(taught.len() + 1)
+        .spacing([12.0, 2.0])
+        .show(ui, |ui| {
+            ui.label("");
+            for i in 0..taught.len() {
+                ui.strong(format!("J{}", i + 1));
+            }
+            ui.end_row();
+            ui.label("Taught (°):");
+            for joint in taught {
+                ui.monospace(locale::format_number(joint.to_degrees(), 1));
+            }
+            ui.end_row();
+            ui.label("Live (°):");
+            for joint in &live {
+                ui.monospace(locale::format_number(joint.to_degrees(), 1));
+            }
+            ui.end_row();
+            ui.label("Difference (°):");
+            for difference in &differences {
+                let text = egui::RichText::new(locale::format_number(*difference, 1)).monospace();
+                if difference.abs() > threshold {
+                    ui.label(text.color(egui::Color32::YELLOW));
+                } else {
+                    ui.label(text);
+                }
+            }
+            ui.end_row();
+        });
+    if taught.len() != live.len() {
+        ui.colored_label(
+            egui::Color32::YELLOW,
+            format!(
+                "The frame stores {} joints, the robot reports {}.",
+                taught.len(),
+                live.len()
+            ),
+        );
+    }
+    if worst > threshold {
+        ui.colored_label(
+            egui::Color32::YELLOW,
+            format!(
+                "⚠ A joint is {}° away from the taught configuration, it may be stale for \
+                 the current cell layout.",
+                locale::format_number(worst, 1)
+            ),
+        );
+    }
+}
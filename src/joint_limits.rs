@@ -0,0 +1,116 @@
+//! Soft joint limits of each robot, usually tighter than the controller's
+//! own. They bound the joint editors and are checked before commands are sent.
+
+use crate::expression::Unit;
+use crate::locale::LocalizedDragValue;
+use crate::storage;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+
+pub const JOINT_LIMITS_FILE: &str = "joint_limits.json";
+
+pub fn check_file() -> Option<Result<(), String>> {
+    storage::check_json::<SoftLimits>(JOINT_LIMITS_FILE)
+}
+
+/// Two full turns, what UR joints allow.
+const HARD_LIMIT: f64 = 6.28;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct JointLimits {
+    /// Radians.
+    pub min: [f64; 6],
+    /// Radians.
+    pub max: [f64; 6],
+}
+
+impl Default for JointLimits {
+    fn default() -> Self {
+        Self {
+            min: [-HARD_LIMIT; 6],
+            max: [HARD_LIMIT; 6],
+        }
+    }
+}
+
+impl JointLimits {
+    pub fn range(&self, joint: usize) -> RangeInclusive<f64> {
+        self.min[joint]..=self.max[joint]
+    }
+
+    pub fn contains(&self, joints: &[f64; 6]) -> bool {
+        (0..6).all(|i| self.range(i).contains(&joints[i]))
+    }
+}
+
+/// Limits by robot id, saved on this station.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SoftLimits {
+    robots: BTreeMap<String, JointLimits>,
+}
+
+impl SoftLimits {
+    pub fn load() -> Self {
+        storage::load_json(JOINT_LIMITS_FILE).unwrap_or_default()
+    }
+
+    fn save(&self) {
+        if let Err(e) = storage::save_json(JOINT_LIMITS_FILE, self) {
+            log::error!("Failed to save joint limits: {}", e);
+        }
+    }
+
+    /// Limits of a robot, the hard limits until some are set.
+    pub fn get(&self, robot_id: &str) -> JointLimits {
+        self.robots.get(robot_id).copied().unwrap_or_default()
+    }
+
+    /// Editor for the limits of one robot, saved on every change.
+    pub fn draw_editor(&mut self, ui: &mut egui::Ui, robot_id: &str) {
+        let mut limits = self.get(robot_id);
+        let mut changed = false;
+        egui::Grid::new("joint_limits_grid")
+            .num_columns(3)
+            .spacing([20.0, 4.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("Joint");
+                ui.strong("Min");
+                ui.strong("Max");
+                ui.end_row();
+                for i in 0..6 {
+                    ui.label(format!("J{}:", i + 1));
+                    let max = limits.max[i];
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut limits.min[i])
+                                .localized_in(Unit::Radian)
+                                .suffix(" rad")
+                                .range(-HARD_LIMIT..=max)
+                                .speed(0.01),
+                        )
+                        .changed();
+                    let min = limits.min[i];
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut limits.max[i])
+                                .localized_in(Unit::Radian)
+                                .suffix(" rad")
+                                .range(min..=HARD_LIMIT)
+                                .speed(0.01),
+                        )
+                        .changed();
+                    ui.end_row();
+                }
+            });
+        if self.robots.contains_key(robot_id) && ui.button("Reset to hard limits").clicked() {
+            self.robots.remove(robot_id);
+            self.save();
+        } else if changed {
+            self.robots.insert(robot_id.to_string(), limits);
+            self.save();
+        }
+    }
+}
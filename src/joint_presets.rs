@@ -0,0 +1,198 @@
+//! Named joint positions and preferred joint configurations, kept apart from
+//! the payload presets. Picking one fills the joint fields of the Robot tab.
+//! Engineers add presets from the fields or the robot's current joints.
+
+use crate::access::{self, Role};
+use crate::locale;
+use crate::storage;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+
+pub const JOINT_PRESETS_FILE: &str = "joint_presets.json";
+
+pub fn check_file() -> Option<Result<(), String>> {
+    storage::check_json::<Library>(JOINT_PRESETS_FILE)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JointPresetKind {
+    /// Joint positions a command moves to.
+    Positions,
+    /// Preferred joint configuration, the hint for the IK solver.
+    Config,
+}
+
+impl JointPresetKind {
+    fn index(self) -> usize {
+        match self {
+            JointPresetKind::Positions => 0,
+            JointPresetKind::Config => 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct JointPreset {
+    name: String,
+    /// Radians, J1 to J6.
+    joints: [f64; 6],
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Library {
+    positions: Vec<JointPreset>,
+    configs: Vec<JointPreset>,
+}
+
+pub struct JointPresets {
+    library: Library,
+    /// Name typed for the next preset, per kind.
+    new_names: [String; 2],
+}
+
+impl JointPresets {
+    pub fn load() -> Self {
+        Self {
+            library: storage::load_json(JOINT_PRESETS_FILE).unwrap_or_default(),
+            new_names: Default::default(),
+        }
+    }
+
+    fn save(&self) {
+        if let Err(e) = storage::save_json(JOINT_PRESETS_FILE, &self.library) {
+            log::error!("Failed to save joint presets: {}", e);
+        }
+    }
+
+    fn list(&self, kind: JointPresetKind) -> &Vec<JointPreset> {
+        match kind {
+            JointPresetKind::Positions => &self.library.positions,
+            JointPresetKind::Config => &self.library.configs,
+        }
+    }
+
+    fn list_mut(&mut self, kind: JointPresetKind) -> &mut Vec<JointPreset> {
+        match kind {
+            JointPresetKind::Positions => &mut self.library.positions,
+            JointPresetKind::Config => &mut self.library.configs,
+        }
+    }
+
+    /// Combo box of the presets of `kind`. Returns the joints of a preset
+    /// when one is picked.
+    pub fn draw_select(
+        &self,
+        ui: &mut egui::Ui,
+        kind: JointPresetKind,
+        id_salt: &str,
+        selected: &mut Option<String>,
+    ) -> Option<[f64; 6]> {
+        let mut picked = None;
+        egui::ComboBox::from_id_salt(id_salt)
+            .selected_text(selected.as_deref().unwrap_or("Select..."))
+            .show_ui(ui, |ui| {
+                for preset in self.list(kind) {
+                    let response = ui
+                        .selectable_label(selected.as_ref() == Some(&preset.name), &preset.name)
+                        .on_hover_text(format!(
+                            "[{}] rad",
+                            locale::format_numbers(&preset.joints, 3)
+                        ));
+                    if response.clicked() {
+                        *selected = Some(preset.name.clone());
+                        picked = Some(preset.joints);
+                    }
+                }
+                if self.list(kind).is_empty() {
+                    ui.weak("No presets yet");
+                }
+            });
+        picked
+    }
+
+    /// The presets of `kind` with their values. Engineers can add the joint
+    /// `fields` or the robot's `live` joints as a preset, and delete presets.
+    pub fn draw_editor(
+        &mut self,
+        ui: &mut egui::Ui,
+        kind: JointPresetKind,
+        fields: [f64; 6],
+        live: Option<&[f64]>,
+    ) {
+        let editable = access::role() == Role::Engineer;
+        let mut remove = None;
+        egui::Grid::new(("joint_presets_grid", kind.index()))
+            .num_columns(3)
+            .striped(true)
+            .show(ui, |ui| {
+                for (i, preset) in self.list(kind).iter().enumerate() {
+                    ui.label(&preset.name);
+                    ui.monospace(format!("[{}]", locale::format_numbers(&preset.joints, 3)));
+                    if editable && ui.button("🗑").clicked() {
+                        remove = Some(i);
+                    }
+                    ui.end_row();
+                }
+            });
+        if self.list(kind).is_empty() {
+            ui.weak("No presets yet.");
+        }
+        if let Some(i) = remove {
+            self.list_mut(kind).remove(i);
+            self.save();
+        }
+        if !editable {
+            ui.weak("Engineers can add and delete presets.");
+            return;
+        }
+
+        let live = live
+            .and_then(|joints| <[f64; 6]>::try_from(joints).ok())
+            .ok_or("No joint states of 6 joints from the robot");
+        let mut add = None;
+        ui.horizontal(|ui| {
+            let name = self.new_names[kind.index()].trim().to_string();
+            let problem = if name.is_empty() {
+                Some("Enter a name")
+            } else if self.list(kind).iter().any(|preset| preset.name == name) {
+                Some("A preset with this name exists")
+            } else {
+                None
+            };
+            ui.add(
+                egui::TextEdit::singleline(&mut self.new_names[kind.index()])
+                    .hint_text("name")
+                    .desired_width(140.0),
+            );
+            if ui
+                .add_enabled(problem.is_none(), egui::Button::new("Add From Fields"))
+                .on_disabled_hover_text(problem.unwrap_or_default())
+                .clicked()
+            {
+                add = Some(JointPreset {
+                    name: name.clone(),
+                    joints: fields,
+                });
+            }
+            let from_robot = match problem {
+                Some(problem) => Err(problem),
+                None => live,
+            };
+            if ui
+                .add_enabled(from_robot.is_ok(), egui::Button::new("Add From Robot"))
+                .on_disabled_hover_text(from_robot.err().unwrap_or_default())
+                .clicked()
+            {
+                if let Ok(joints) = from_robot {
+                    add = Some(JointPreset { name, joints });
+                }
+            }
+        });
+        if let Some(preset) = add {
+            self.list_mut(kind).push(preset);
+            self.new_names[kind.index()].clear();
+            self.save();
+        }
+    }
+}
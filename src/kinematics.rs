@@ -0,0 +1,273 @@
+//! Forward and inverse kinematics of 6-axis arms from DH parameters, for
+//! previewing commands before they are sent. Not a replacement for the
+//! driver's own planner, only close enough to spot a wrong goal frame.
+
+use crate::pose::{self, Pose};
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::f64::consts::{FRAC_PI_2, PI};
+
+pub const ROBOT_MODELS_FILE: &str = "robot_models.json";
+
+pub fn check_file() -> Option<Result<(), String>> {
+    storage::check_json::<RobotModels>(ROBOT_MODELS_FILE)
+}
+
+/// Standard DH parameters, link `i` is `Rz(θi) Tz(di) Tx(ai) Rx(αi)`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DhModel {
+    pub name: String,
+    /// Meters.
+    pub a: [f64; 6],
+    /// Meters.
+    pub d: [f64; 6],
+    /// Radians.
+    pub alpha: [f64; 6],
+    /// Rotation of the DH base about z in the baseframe commands are given
+    /// in. UR drivers command in `base_link`, which is turned half a turn
+    /// from the DH base.
+    #[serde(default)]
+    pub base_yaw: f64,
+}
+
+impl DhModel {
+    pub fn ur5e() -> Self {
+        Self {
+            name: "UR5e".to_string(),
+            a: [0.0, -0.425, -0.3922, 0.0, 0.0, 0.0],
+            d: [0.1625, 0.0, 0.0, 0.1333, 0.0997, 0.0996],
+            alpha: [FRAC_PI_2, 0.0, 0.0, FRAC_PI_2, -FRAC_PI_2, 0.0],
+            base_yaw: PI,
+        }
+    }
+
+    pub fn ur10e() -> Self {
+        Self {
+            name: "UR10e".to_string(),
+            a: [0.0, -0.6127, -0.57155, 0.0, 0.0, 0.0],
+            d: [0.1807, 0.0, 0.0, 0.17415, 0.11985, 0.11655],
+            alpha: [FRAC_PI_2, 0.0, 0.0, FRAC_PI_2, -FRAC_PI_2, 0.0],
+            base_yaw: PI,
+        }
+    }
+
+    pub fn builtin() -> Vec<DhModel> {
+        vec![DhModel::ur5e(), DhModel::ur10e()]
+    }
+
+    /// Reads DH parameters from a JSON file with the fields of `DhModel`.
+    pub fn load(path: &std::path::Path) -> Result<DhModel, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Invalid DH parameters: {}", e))
+    }
+
+    /// Longest distance from the shoulder the flange can get, a bit generous.
+    pub fn reach(&self) -> f64 {
+        self.a.iter().map(|a| a.abs()).sum::<f64>() + self.d[1..].iter().sum::<f64>()
+    }
+
+    fn base(&self) -> Pose {
+        Pose {
+            translation: [0.0; 3],
+            rotation: pose::rpy_to_quaternion([0.0, 0.0, self.base_yaw]),
+        }
+    }
+
+    /// Origins of the base and of every link frame in the baseframe, the
+    /// last one being the flange.
+    pub fn link_poses(&self, joints: &[f64; 6]) -> [Pose; 7] {
+        let mut poses = [self.base(); 7];
+        for i in 0..6 {
+            let link = Pose {
+                translation: [0.0, 0.0, self.d[i]],
+                rotation: pose::rpy_to_quaternion([0.0, 0.0, joints[i]]),
+            }
+            .compose(&Pose {
+                translation: [self.a[i], 0.0, 0.0],
+                rotation: pose::rpy_to_quaternion([self.alpha[i], 0.0, 0.0]),
+            });
+            poses[i + 1] = poses[i].compose(&link);
+        }
+        poses
+    }
+
+    pub fn flange(&self, joints: &[f64; 6]) -> Pose {
+        self.link_poses(joints)[6]
+    }
+
+    /// Damped least squares from `seed`, `None` if it doesn't converge to
+    /// within a tenth of a millimeter and degree.
+    pub fn inverse(&self, target: &Pose, seed: &[f64; 6]) -> Option<[f64; 6]> {
+        const STEP: f64 = 1e-6;
+        const DAMPING: f64 = 0.05;
+        let mut joints = *seed;
+        for _ in 0..300 {
+            let error = pose_error(&self.flange(&joints), target);
+            let position_error = error[..3].iter().map(|e| e * e).sum::<f64>().sqrt();
+            let rotation_error = error[3..].iter().map(|e| e * e).sum::<f64>().sqrt();
+            if position_error < 1e-4 && rotation_error < 0.1_f64.to_radians() {
+                return Some(joints);
+            }
+            let mut jacobian = [[0.0; 6]; 6];
+            for j in 0..6 {
+                let mut moved = joints;
+                moved[j] += STEP;
+                let moved_error = pose_error(&self.flange(&moved), target);
+                for i in 0..6 {
+                    jacobian[i][j] = (error[i] - moved_error[i]) / STEP;
+                }
+            }
+            // (J Jᵀ + λ²I) y = e, Δq = Jᵀ y
+            let mut system = [[0.0; 6]; 6];
+            for i in 0..6 {
+                for k in 0..6 {
+                    system[i][k] = (0..6).map(|j| jacobian[i][j] * jacobian[k][j]).sum();
+                }
+                system[i][i] += DAMPING * DAMPING;
+            }
+            let y = solve(system, error)?;
+            for j in 0..6 {
+                joints[j] += (0..6).map(|i| jacobian[i][j] * y[i]).sum::<f64>();
+            }
+        }
+        None
+    }
+}
+
+/// Translation and rotation vector taking `current` to `target`, in the baseframe.
+fn pose_error(current: &Pose, target: &Pose) -> [f64; 6] {
+    let c = current.rotation;
+    let delta = pose::quaternion_multiply(target.rotation, [-c[0], -c[1], -c[2], c[3]]);
+    let r = rotation_vector(delta);
+    [
+        target.translation[0] - current.translation[0],
+        target.translation[1] - current.translation[1],
+        target.translation[2] - current.translation[2],
+        r[0],
+        r[1],
+        r[2],
+    ]
+}
+
+fn rotation_vector(q: [f64; 4]) -> [f64; 3] {
+    let mut q = pose::normalize_quaternion(q);
+    if q[3] < 0.0 {
+        q = q.map(|x| -x);
+    }
+    let sin_half = (q[0] * q[0] + q[1] * q[1] + q[2] * q[2]).sqrt();
+    if sin_half < 1e-12 {
+        return [2.0 * q[0], 2.0 * q[1], 2.0 * q[2]];
+    }
+    let angle = 2.0 * sin_half.atan2(q[3]);
+    [
+        q[0] / sin_half * angle,
+        q[1] / sin_half * angle,
+        q[2] / sin_half * angle,
+    ]
+}
+
+/// Gaussian elimination with partial pivoting, `None` if singular.
+fn solve(mut m: [[f64; 6]; 6], mut b: [f64; 6]) -> Option<[f64; 6]> {
+    for col in 0..6 {
+        let pivot = (col..6).max_by(|&i, &j| m[i][col].abs().total_cmp(&m[j][col].abs()))?;
+        if m[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        m.swap(col, pivot);
+        b.swap(col, pivot);
+        for row in col + 1..6 {
+            let factor = m[row][col] / m[col][col];
+            for k in col..6 {
+                m[row][k] -= factor * m[col][k];
+            }
+            b[row] -= factor * b[col];
+        }
+    }
+    let mut x = [0.0; 6];
+    for row in (0..6).rev() {
+        let sum = (row + 1..6).map(|k| m[row][k] * x[k]).sum::<f64>();
+        x[row] = (b[row] - sum) / m[row][row];
+    }
+    Some(x)
+}
+
+/// Elbow and wrist flips between two configurations of a UR-like arm, each
+/// a big sweeping motion for what may be a small move of the flange.
+pub fn configuration_flips(from: &[f64; 6], to: &[f64; 6]) -> Vec<&'static str> {
+    let flipped = |i: usize| from[i].sin() * to[i].sin() < 0.0;
+    [(2, "elbow"), (4, "wrist")]
+        .into_iter()
+        .filter(|(i, _)| flipped(*i))
+        .map(|(_, name)| name)
+        .collect()
+}
+
+/// Spherical linear interpolation between two unit quaternions.
+pub fn slerp(a: [f64; 4], b: [f64; 4], t: f64) -> [f64; 4] {
+    let mut dot = (0..4).map(|i| a[i] * b[i]).sum::<f64>();
+    let b = if dot < 0.0 {
+        dot = -dot;
+        b.map(|x| -x)
+    } else {
+        b
+    };
+    if dot > 0.9995 {
+        let lerp = [0, 1, 2, 3].map(|i| a[i] + (b[i] - a[i]) * t);
+        return pose::normalize_quaternion(lerp);
+    }
+    let theta = dot.acos();
+    let (wa, wb) = (
+        ((1.0 - t) * theta).sin() / theta.sin(),
+        (t * theta).sin() / theta.sin(),
+    );
+    [0, 1, 2, 3].map(|i| a[i] * wa + b[i] * wb)
+}
+
+/// Kinematic model of each robot, saved on this station.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RobotModels {
+    robots: BTreeMap<String, DhModel>,
+    /// DH files loaded so far, offered for every robot.
+    #[serde(default)]
+    custom: Vec<DhModel>,
+}
+
+impl RobotModels {
+    pub fn load() -> Self {
+        storage::load_json(ROBOT_MODELS_FILE).unwrap_or_default()
+    }
+
+    fn save(&self) {
+        if let Err(e) = storage::save_json(ROBOT_MODELS_FILE, self) {
+            log::error!("Failed to save robot models: {}", e);
+        }
+    }
+
+    /// The model of a robot, UR5e until another is picked.
+    pub fn get(&self, robot_id: &str) -> DhModel {
+        self.robots
+            .get(robot_id)
+            .cloned()
+            .unwrap_or_else(DhModel::ur5e)
+    }
+
+    pub fn available(&self) -> Vec<DhModel> {
+        let mut models = DhModel::builtin();
+        models.extend(self.custom.iter().cloned());
+        models
+    }
+
+    pub fn set(&mut self, robot_id: &str, model: DhModel) {
+        self.robots.insert(robot_id.to_string(), model);
+        self.save();
+    }
+
+    /// Adds a loaded model to the choices, replacing one with the same name.
+    pub fn add_custom(&mut self, model: DhModel) {
+        self.custom.retain(|m| m.name != model.name);
+        self.custom.push(model);
+        self.save();
+    }
+}
@@ -0,0 +1,152 @@
+use crate::expression::{self, Unit};
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// Decimal separator used when formatting numbers for display.
+/// Parsing always accepts both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DecimalSeparator {
+    #[default]
+    Point,
+    Comma,
+}
+
+impl std::fmt::Display for DecimalSeparator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecimalSeparator::Point => write!(f, "Point (0.5)"),
+            DecimalSeparator::Comma => write!(f, "Comma (0,5)"),
+        }
+    }
+}
+
+// Read from formatting helpers all over the GUI, so it is kept process-wide
+// instead of being threaded through every tab.
+static DECIMAL_COMMA: AtomicBool = AtomicBool::new(false);
+
+pub fn set_decimal_separator(separator: DecimalSeparator) {
+    DECIMAL_COMMA.store(separator == DecimalSeparator::Comma, Ordering::Relaxed);
+}
+
+pub fn decimal_separator() -> DecimalSeparator {
+    if DECIMAL_COMMA.load(Ordering::Relaxed) {
+        DecimalSeparator::Comma
+    } else {
+        DecimalSeparator::Point
+    }
+}
+
+/// Decimals poses are shown and exported with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Precision {
+    /// Of translations in meters, 6 keeps micrometers.
+    pub translation_decimals: usize,
+    /// Of quaternion components.
+    pub rotation_decimals: usize,
+    /// Round poses to these decimals when writing files, so re-exporting a
+    /// scene doesn't show float noise in its diff.
+    pub round_exports: bool,
+}
+
+impl Default for Precision {
+    fn default() -> Self {
+        Self {
+            translation_decimals: 6,
+            rotation_decimals: 4,
+            round_exports: false,
+        }
+    }
+}
+
+static TRANSLATION_DECIMALS: AtomicUsize = AtomicUsize::new(6);
+static ROTATION_DECIMALS: AtomicUsize = AtomicUsize::new(4);
+static ROUND_EXPORTS: AtomicBool = AtomicBool::new(false);
+
+pub fn set_precision(precision: Precision) {
+    TRANSLATION_DECIMALS.store(precision.translation_decimals, Ordering::Relaxed);
+    ROTATION_DECIMALS.store(precision.rotation_decimals, Ordering::Relaxed);
+    ROUND_EXPORTS.store(precision.round_exports, Ordering::Relaxed);
+}
+
+pub fn precision() -> Precision {
+    Precision {
+        translation_decimals: TRANSLATION_DECIMALS.load(Ordering::Relaxed),
+        rotation_decimals: ROTATION_DECIMALS.load(Ordering::Relaxed),
+        round_exports: ROUND_EXPORTS.load(Ordering::Relaxed),
+    }
+}
+
+pub fn round_to(value: f64, decimals: usize) -> f64 {
+    let factor = 10f64.powi(decimals as i32);
+    (value * factor).round() / factor
+}
+
+/// Parses `0.5`, `0,5`, `1 234,5`, `1,234.5` and `1.234,5`. When both separators
+/// appear, the last one is the decimal separator and the other groups thousands.
+pub fn parse_number(text: &str) -> Option<f64> {
+    let cleaned: String = text
+        .trim()
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '\'')
+        .map(|c| if c == '−' { '-' } else { c })
+        .collect();
+    let normalized = match (cleaned.rfind('.'), cleaned.rfind(',')) {
+        (Some(point), Some(comma)) if comma > point => cleaned.replace('.', "").replace(',', "."),
+        (Some(_), Some(_)) => cleaned.replace(',', ""),
+        (None, Some(_)) => cleaned.replace(',', "."),
+        _ => cleaned,
+    };
+    normalized.parse::<f64>().ok()
+}
+
+/// Formats with a fixed number of decimals and the configured separator.
+pub fn format_number(value: f64, decimals: usize) -> String {
+    let text = format!("{:.*}", decimals, value);
+    match decimal_separator() {
+        DecimalSeparator::Point => text,
+        DecimalSeparator::Comma => text.replace('.', ","),
+    }
+}
+
+/// Formats several numbers, separated so that decimal commas stay unambiguous.
+pub fn format_numbers(values: &[f64], decimals: usize) -> String {
+    let separator = match decimal_separator() {
+        DecimalSeparator::Point => ", ",
+        DecimalSeparator::Comma => "; ",
+    };
+    values
+        .iter()
+        .map(|v| format_number(*v, decimals))
+        .collect::<Vec<String>>()
+        .join(separator)
+}
+
+/// Makes a `DragValue` accept decimal commas and simple expressions such as
+/// `0.1*2`, and display the configured separator.
+pub trait LocalizedDragValue {
+    fn localized(self) -> Self;
+
+    /// Like `localized`, also converting typed units, e.g. `45deg` in a `Radian` field.
+    fn localized_in(self, unit: Unit) -> Self;
+}
+
+impl LocalizedDragValue for egui::DragValue<'_> {
+    fn localized(self) -> Self {
+        self.localized_in(Unit::None)
+    }
+
+    fn localized_in(self, unit: Unit) -> Self {
+        self.custom_parser(move |text| {
+            parse_number(text).or_else(|| expression::evaluate(text, unit))
+        })
+        .custom_formatter(|value, decimals| {
+            let text = egui::emath::format_with_decimals_in_range(value, decimals);
+            match decimal_separator() {
+                DecimalSeparator::Point => text,
+                DecimalSeparator::Comma => text.replace('.', ","),
+            }
+        })
+    }
+}
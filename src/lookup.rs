@@ -1,33 +1,152 @@
+use crate::export_profiles::ExportProfiles;
+use crate::exporters::{self, ExporterRegistry, LookupExporter};
+use crate::locale::LocalizedDragValue;
+use crate::pose::{self, FrameMetadata, Pose};
+use crate::pose_stats::PoseStatistics;
+use crate::schema::{self, Versioned};
+use crate::workers;
 use eframe::egui;
 use micro_sp::{
-    ConnectionManager, FloatOrUnknown, SPTransform, SPTransformStamped, SPValue, StateManager,
-    TransformsManager,
+    ArrayOrUnknown, ConnectionManager, FloatOrUnknown, SPTransform, SPTransformStamped, SPValue,
+    StateManager, ToSPValue, TransformsManager,
 };
 use ordered_float::OrderedFloat;
 use poll_promise::Promise;
-use rfd::FileDialog;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, sync::Arc};
 
-#[derive(Serialize)]
-struct PreferredJointConfiguration(HashMap<String, f64>);
+pub const CAPTURE_FILE: &str = "lookup_capture.json";
 
-#[derive(Serialize)]
-struct Metadata {
+pub fn check_file() -> Option<Result<(), String>> {
+    crate::storage::check_json::<CaptureOptions>(CAPTURE_FILE)
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct PreferredJointConfiguration(HashMap<String, f64>);
+
+impl PreferredJointConfiguration {
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Live data embedded in a lookup is optional, left out when not captured.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Metadata {
     tcp_id: String,
+    #[serde(default, skip_serializing_if = "PreferredJointConfiguration::is_empty")]
     preferred_joint_configuration: PreferredJointConfiguration,
     // preferred_joint_configuration: Vec<(String, f64)>,
     enable_transform: bool,
     active_transform: bool,
-    gantry: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    gantry: Option<f64>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    note: String,
+    /// Who looked the frame up.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    operator: String,
+    /// Captured fields the state didn't have, e.g. `gantry`, left out above
+    /// instead of being written as zeros.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    missing: Vec<String>,
 }
 
-#[derive(Serialize)]
-struct JsonOutputWithMetadata {
-    child_frame_id: String,
-    parent_frame_id: String,
-    transform: SPTransform,
-    metadata: Metadata,
+/// What a lookup embeds in the metadata besides the frame itself.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaptureOptions {
+    pub joints: bool,
+    pub gantry: bool,
+    pub operator: String,
+    /// Lookups averaged into the result, one for a plain lookup.
+    pub samples: usize,
+    /// Seconds between the samples.
+    pub sample_interval: f64,
+    /// Meant for one lookup, so not saved.
+    #[serde(skip)]
+    pub note: String,
+}
+
+impl Default for CaptureOptions {
+    fn default() -> Self {
+        Self {
+            joints: true,
+            gantry: true,
+            operator: String::new(),
+            samples: 1,
+            sample_interval: 0.2,
+            note: String::new(),
+        }
+    }
+}
+
+impl CaptureOptions {
+    pub fn load() -> Self {
+        crate::storage::load_json(CAPTURE_FILE).unwrap_or_default()
+    }
+
+    fn save(&self) {
+        if let Err(e) = crate::storage::save_json(CAPTURE_FILE, self) {
+            log::error!("Failed to save the lookup capture options: {}", e);
+        }
+    }
+}
+
+/// A looked up frame as exported to (and imported from) JSON files.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JsonOutputWithMetadata {
+    pub child_frame_id: String,
+    pub parent_frame_id: String,
+    pub transform: SPTransform,
+    pub metadata: Metadata,
+}
+
+impl JsonOutputWithMetadata {
+    pub fn to_transform_stamped(&self) -> SPTransformStamped {
+        let frame_metadata = FrameMetadata {
+            tcp_id: self.metadata.tcp_id.clone(),
+            enable_transform: self.metadata.enable_transform,
+            active_transform: self.metadata.active_transform,
+        };
+        let mut transform = pose::make_transform_stamped(
+            &self.parent_frame_id,
+            &self.child_frame_id,
+            &Pose::from_sp_transform(&self.transform),
+            &frame_metadata,
+        );
+        // Joints are stored by name (j0, j1, ...), the metadata wants them in order
+        let mut joints = self
+            .metadata
+            .preferred_joint_configuration
+            .0
+            .iter()
+            .collect::<Vec<(&String, &f64)>>();
+        joints.sort_by_key(|(name, _)| name.trim_start_matches('j').parse::<usize>().ok());
+        if !joints.is_empty() {
+            pose::set_metadata_field(
+                &mut transform,
+                "preferred_joint_configuration",
+                SPValue::Array(ArrayOrUnknown::Array(
+                    joints.iter().map(|(_, value)| value.to_spvalue()).collect(),
+                )),
+            );
+        }
+        if let Some(gantry) = self.metadata.gantry {
+            pose::set_metadata_field(&mut transform, "gantry", gantry.to_spvalue());
+        }
+        for (key, value) in [
+            ("note", &self.metadata.note),
+            ("operator", &self.metadata.operator),
+        ] {
+            if !value.is_empty() {
+                pose::set_metadata_field(&mut transform, key, value.as_str().to_spvalue());
+            }
+        }
+        transform
+    }
 }
 
 fn vec_to_joint_map(joints: Vec<f64>) -> PreferredJointConfiguration {
@@ -49,9 +168,14 @@ fn vec_to_joint_map(joints: Vec<f64>) -> PreferredJointConfiguration {
 // }
 
 struct LookupData {
+    /// The mean of the samples when sampled more than once.
     transform: SPTransformStamped,
-    joint_states: Vec<f64>,
-    gantry_position: f64,
+    statistics: Option<PoseStatistics>,
+    /// `None` when not captured or not available.
+    joint_states: Option<Vec<f64>>,
+    gantry_position: Option<f64>,
+    /// Metadata fields that were captured but not available.
+    missing: Vec<String>,
 }
 
 type LookupResult = Result<LookupData, String>;
@@ -61,23 +185,68 @@ async fn get_lookup_data(
     robot_id: &str,
     parent: String,
     child: String,
+    capture: CaptureOptions,
 ) -> LookupResult {
     let (transform_res, joints_res, gantry_res) = tokio::join!(
-        lookup_transform(con.clone(), &parent, &child),
-        get_joint_states(con.clone(), &robot_id),
-        get_opc_current_position(con.clone())
+        sample_transform(con.clone(), &parent, &child, &capture),
+        async {
+            match capture.joints {
+                true => Some(get_joint_states(con.clone(), robot_id).await),
+                false => None,
+            }
+        },
+        async {
+            match capture.gantry {
+                true => Some(get_opc_current_position(con.clone()).await),
+                false => None,
+            }
+        }
     );
 
+    let mut missing = Vec::new();
+    if let Some(None) = joints_res {
+        missing.push("preferred_joint_configuration".to_string());
+    }
+    if let Some(None) = gantry_res {
+        missing.push("gantry".to_string());
+    }
     match transform_res {
-        Ok(transform) => Ok(LookupData {
+        Ok((transform, statistics)) => Ok(LookupData {
             transform,
-            joint_states: joints_res,
-            gantry_position: gantry_res,
+            statistics,
+            joint_states: joints_res.flatten(),
+            gantry_position: gantry_res.flatten(),
+            missing,
         }),
         Err(e) => Err(e),
     }
 }
 
+/// Looks the transform up `capture.samples` times and returns the mean with
+/// the statistics of the samples, or the single lookup.
+async fn sample_transform(
+    con: Arc<ConnectionManager>,
+    parent: &str,
+    child: &str,
+    capture: &CaptureOptions,
+) -> Result<(SPTransformStamped, Option<PoseStatistics>), String> {
+    let mut transform = lookup_transform(con.clone(), parent, child).await?;
+    if capture.samples <= 1 {
+        return Ok((transform, None));
+    }
+    let mut poses = vec![Pose::from_sp_transform(&transform.transform)];
+    for _ in 1..capture.samples {
+        tokio::time::sleep(std::time::Duration::from_secs_f64(capture.sample_interval)).await;
+        let sample = lookup_transform(con.clone(), parent, child).await?;
+        poses.push(Pose::from_sp_transform(&sample.transform));
+    }
+    let statistics = PoseStatistics::of(&poses);
+    if let Some(statistics) = &statistics {
+        transform.transform = statistics.mean.to_sp_transform();
+    }
+    Ok((transform, statistics))
+}
+
 async fn get_all_transforms(con: Arc<ConnectionManager>) -> HashMap<String, SPTransformStamped> {
     let mut connection = con.get_connection().await;
     match TransformsManager::get_all_transforms(&mut connection).await {
@@ -89,49 +258,30 @@ async fn get_all_transforms(con: Arc<ConnectionManager>) -> HashMap<String, SPTr
     }
 }
 
-async fn get_opc_current_position(con: Arc<ConnectionManager>) -> f64 {
+/// `None` when the gantry position isn't in the state or unknown.
+async fn get_opc_current_position(con: Arc<ConnectionManager>) -> Option<f64> {
     let mut connection = con.get_connection().await;
     match StateManager::get_sp_value(&mut connection, "opc_current_position").await {
-        Some(sp_value) => {
-            if let SPValue::Float64(FloatOrUnknown::Float64(OrderedFloat(x))) = sp_value {
-                x
-            } else {
-                0.0
-            }
+        Some(SPValue::Float64(FloatOrUnknown::Float64(OrderedFloat(x)))) => Some(x),
+        _ => {
+            log::warn!("GUI Failed to get the gantry position");
+            None
         }
-        None => 0.0,
     }
 }
 
-async fn get_joint_states(con: Arc<ConnectionManager>, robot_id: &str) -> Vec<f64> {
+/// `None` when the joint states aren't in the state or not all known.
+pub async fn get_joint_states(con: Arc<ConnectionManager>, robot_id: &str) -> Option<Vec<f64>> {
     let mut connection = con.get_connection().await;
-    match StateManager::get_sp_value(&mut connection, &format!("{}_joint_states", robot_id)).await {
-        Some(joint_states) => {
-            if let SPValue::Array(micro_sp::ArrayOrUnknown::Array(joint_states_of_sp_value)) =
-                joint_states
-            {
-                joint_states_of_sp_value
-                    .iter()
-                    .map(|j| {
-                        if let SPValue::Float64(FloatOrUnknown::Float64(OrderedFloat(
-                            joint_value,
-                        ))) = j
-                        {
-                            joint_value.to_owned()
-                        } else {
-                            0.0
-                        }
-                    })
-                    .collect::<Vec<f64>>()
-            } else {
-                vec![]
-            }
-        }
-        None => {
-            log::error!("GUI Failed to get joint states for robot {}", robot_id);
-            vec![]
-        }
+    let joints = StateManager::get_sp_value(&mut connection, &format!("{}_joint_states", robot_id))
+        .await
+        .as_ref()
+        .and_then(crate::joint_drift::floats)
+        .filter(|joints| !joints.is_empty());
+    if joints.is_none() {
+        log::error!("GUI Failed to get joint states for robot {}", robot_id);
     }
+    joints
 }
 
 async fn lookup_transform(
@@ -159,6 +309,13 @@ pub struct LookupTab {
     // lookup_result_json: Option<String>,
     lookup_output: Option<(JsonOutputWithMetadata, String)>,
     lookup_error: Option<String>,
+    /// Spread of the samples of a multi-sample lookup.
+    lookup_statistics: Option<PoseStatistics>,
+    exporter_name: String,
+    save_status: Option<Result<String, String>>,
+    capture: CaptureOptions,
+    profiles: ExportProfiles,
+    show_profiles: bool,
 }
 
 impl LookupTab {
@@ -173,6 +330,12 @@ impl LookupTab {
             // lookup_result_json: None,
             lookup_output: None,
             lookup_error: None,
+            lookup_statistics: None,
+            exporter_name: "JSON".to_string(),
+            save_status: None,
+            capture: CaptureOptions::load(),
+            profiles: ExportProfiles::load(),
+            show_profiles: false,
         }
     }
 
@@ -181,6 +344,7 @@ impl LookupTab {
         ui: &mut egui::Ui,
         handle: &tokio::runtime::Handle,
         connection: &Arc<ConnectionManager>,
+        exporters: &ExporterRegistry,
     ) {
         ui.horizontal(|ui| {
             ui.heading("Transforms Lookup GUI"); // This stays on the left
@@ -236,6 +400,8 @@ impl LookupTab {
                     &self.transform_keys,
                 );
 
+                ui.add_space(10.0);
+                self.draw_capture_options(ui);
                 ui.add_space(10.0);
 
                 // --- Lookup Controls (moved from draw_lookup_section) ---
@@ -259,6 +425,22 @@ impl LookupTab {
         if self.lookup_promise.is_some() {
             self.poll_lookup_promise();
         }
+        if let Some(statistics) = &self.lookup_statistics {
+            ui.add_space(4.0);
+            statistics.draw(ui);
+        }
+        if let Some((output, _)) = &self.lookup_output {
+            if !output.metadata.missing.is_empty() {
+                ui.colored_label(
+                    egui::Color32::YELLOW,
+                    format!("⚠ Not available: {}", output.metadata.missing.join(", ")),
+                )
+                .on_hover_text(
+                    "These were captured but missing in the state. They are left out \n\
+                     of the metadata and listed under \"missing\".",
+                );
+            }
+        }
 
         ui.add_space(10.0);
 
@@ -267,13 +449,51 @@ impl LookupTab {
                 // This layout pushes the button to the far right
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                     if ui.button("Save As").clicked() {
-                        self.save_json_to_file();
+                        if let Some(exporter) = exporters.get(&self.exporter_name) {
+                            self.save_to_file(exporter);
+                        }
+                    }
+                    let problem = self.profiles.problem(exporters);
+                    let hover = match (&problem, self.profiles.active()) {
+                        (None, Some(profile)) => format!("{} as {}", profile.dir, profile.format),
+                        _ => String::new(),
+                    };
+                    if ui
+                        .add_enabled(problem.is_none(), egui::Button::new("Save to Profile"))
+                        .on_hover_text(hover)
+                        .on_disabled_hover_text(problem.unwrap_or_default())
+                        .clicked()
+                    {
+                        self.save_to_profile(exporters);
+                    }
+                    egui::ComboBox::from_id_salt("lookup_exporter_select")
+                        .selected_text(&self.exporter_name)
+                        .show_ui(ui, |ui| {
+                            for exporter in exporters.exporters() {
+                                ui.selectable_value(
+                                    &mut self.exporter_name,
+                                    exporter.name().to_string(),
+                                    exporter.name(),
+                                );
+                            }
+                        });
+                    match &self.save_status {
+                        Some(Ok(msg)) => {
+                            ui.colored_label(egui::Color32::GREEN, msg);
+                        }
+                        Some(Err(e)) => {
+                            ui.colored_label(egui::Color32::RED, e);
+                        }
+                        None => (),
                     }
                 });
             });
             ui.add_space(2.0); // Small space between button and output box
         }
 
+        self.profiles
+            .draw_editor(ui.ctx(), &mut self.show_profiles, exporters);
+
         // --- Window 2: Output (like MyApp's solution section) ---
         egui::Frame::default()
             .inner_margin(egui::Margin::same(0))
@@ -283,6 +503,71 @@ impl LookupTab {
             });
     }
 
+    /// What gets embedded in the metadata of the next lookup.
+    fn draw_capture_options(&mut self, ui: &mut egui::Ui) {
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            ui.label("Embed:");
+            changed |= ui
+                .checkbox(&mut self.capture.joints, "Joint states")
+                .changed();
+            changed |= ui
+                .checkbox(&mut self.capture.gantry, "Gantry position")
+                .changed();
+        });
+        ui.horizontal(|ui| {
+            ui.label("Samples:");
+            changed |= ui
+                .add(egui::DragValue::new(&mut self.capture.samples).range(1..=100))
+                .on_hover_text("Repeated lookups averaged into the result, with their noise")
+                .changed();
+            ui.add_enabled_ui(self.capture.samples > 1, |ui| {
+                ui.label("every");
+                changed |= ui
+                    .add(
+                        egui::DragValue::new(&mut self.capture.sample_interval)
+                            .localized()
+                            .range(0.05..=5.0)
+                            .speed(0.05)
+                            .suffix(" s"),
+                    )
+                    .changed();
+            });
+        });
+        ui.horizontal(|ui| {
+            ui.label("Operator:");
+            changed |= ui
+                .add(
+                    egui::TextEdit::singleline(&mut self.capture.operator)
+                        .hint_text("none")
+                        .desired_width(120.0),
+                )
+                .changed();
+        });
+        ui.horizontal(|ui| {
+            ui.label("Note:");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.capture.note)
+                    .hint_text("none")
+                    .desired_width(240.0),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label("Export profile:");
+            if let Some(profile) = self.profiles.draw_selector(ui) {
+                self.capture.joints = profile.joints;
+                self.capture.gantry = profile.gantry;
+                changed = true;
+            }
+            if ui.button("Profiles…").clicked() {
+                self.show_profiles = true;
+            }
+        });
+        if changed {
+            self.capture.save();
+        }
+    }
+
     /// Draws the output section (JSON result or error)
     fn draw_output_section(&mut self, ui: &mut egui::Ui) {
         ui.set_min_width(480.0); // Match control panel
@@ -318,16 +603,25 @@ impl LookupTab {
         // self.lookup_result_json = None;
         self.lookup_output = None;
         self.lookup_error = None;
+        self.lookup_statistics = None;
 
         if let (Some(parent), Some(child), robot_id_input) = (
             self.parent.clone(),
             self.child.clone(),
             self.robot_id_input.clone(),
         ) {
+            crate::recorder::record_lookup(&parent, &child);
             let handle = handle.clone();
             let con_clone = connection.clone();
-            self.lookup_promise = Some(Promise::spawn_thread("lookup_fetcher", move || {
-                handle.block_on(get_lookup_data(con_clone, &robot_id_input, parent, child))
+            let capture = self.capture.clone();
+            self.lookup_promise = Some(workers::spawn("lookup_fetcher", move || {
+                handle.block_on(get_lookup_data(
+                    con_clone,
+                    &robot_id_input,
+                    parent,
+                    child,
+                    capture,
+                ))
             }));
         }
     }
@@ -337,25 +631,34 @@ impl LookupTab {
             if let std::task::Poll::Ready(result) = promise.poll() {
                 match result {
                     Ok(data) => {
+                        self.lookup_statistics = data.statistics;
                         let child_frame_id = self.child.clone().unwrap_or_default();
-                        let joint_config_map = vec_to_joint_map(data.joint_states.clone());
+                        let joint_config_map =
+                            vec_to_joint_map(data.joint_states.clone().unwrap_or_default());
                         // let joint_config_map  = vec_to_joint_vec(data.joint_states.clone());
 
                         let output = JsonOutputWithMetadata {
                             // <--- We will store this
                             child_frame_id: child_frame_id.clone(),
                             parent_frame_id: self.parent.clone().unwrap_or_default(),
-                            transform: data.transform.transform.clone(),
+                            transform: pose::export_transform(&data.transform.transform),
                             metadata: Metadata {
                                 tcp_id: child_frame_id,
                                 preferred_joint_configuration: joint_config_map,
                                 enable_transform: true,
                                 active_transform: false,
                                 gantry: data.gantry_position,
+                                note: self.capture.note.trim().to_string(),
+                                operator: self.capture.operator.trim().to_string(),
+                                missing: data.missing.clone(),
                             },
                         };
 
-                        match serde_json::to_string_pretty(&output) {
+                        let json = serde_json::to_string_pretty(&Versioned::new(
+                            schema::FRAME_KIND,
+                            &output,
+                        ));
+                        match json {
                             // OLD: Ok(json_string) => self.lookup_result_json = Some(json_string),
                             // NEW:
                             Ok(json_string) => self.lookup_output = Some((output, json_string)),
@@ -415,32 +718,47 @@ impl LookupTab {
     ) {
         let handle = handle.clone();
         let con_clone = connection.clone();
-        self.get_all_transforms_promise = Some(Promise::spawn_thread("fetcher", move || {
+        self.get_all_transforms_promise = Some(workers::spawn("fetcher", move || {
             handle.block_on(get_all_transforms(con_clone))
         }));
     }
 
-    fn save_json_to_file(&self) {
-        // We use the data stored in self.lookup_output
-        if let Some((output_data, json_content)) = &self.lookup_output {
-            // Create a default filename like "parent_to_child.json"
-            let default_filename = format!(
-                "{}_to_{}.json",
-                output_data.parent_frame_id, output_data.child_frame_id
-            );
+    fn save_to_profile(&mut self, exporters: &ExporterRegistry) {
+        let (Some((_, json_content)), Some(profile)) =
+            (&self.lookup_output, self.profiles.active())
+        else {
+            return;
+        };
+        let result = schema::load::<JsonOutputWithMetadata>(json_content, schema::FRAME_KIND)
+            .and_then(|edited| profile.save(exporters, &edited.data, &self.robot_id_input));
+        match &result {
+            Ok(msg) => log::info!("{}", msg),
+            Err(e) => log::error!("Failed to save file: {}", e),
+        }
+        self.save_status = Some(result);
+    }
 
-            // Open the native "Save File" dialog
-            let file_path = FileDialog::new()
-                .add_filter("JSON", &["json"])
-                .set_file_name(&default_filename)
-                .save_file();
-
-            // If the user selected a path (didn't cancel)
-            if let Some(path) = file_path {
-                match std::fs::write(&path, json_content) {
-                    Ok(_) => log::info!("Successfully saved JSON to {:?}", path),
-                    Err(e) => log::error!("Failed to save file: {}", e),
-                }
+    fn save_to_file(&mut self, exporter: &dyn LookupExporter) {
+        let Some((output_data, json_content)) = &self.lookup_output else {
+            return;
+        };
+        // The output box is editable, so export what the user sees
+        let content = schema::load::<JsonOutputWithMetadata>(json_content, schema::FRAME_KIND)
+            .and_then(|edited| exporter.export_lookup(&edited.data));
+        // Default filename like "parent_to_child.json"
+        let default_stem = format!(
+            "{}_to_{}",
+            output_data.parent_frame_id, output_data.child_frame_id
+        );
+        match exporters::save_with_exporter(exporter, &default_stem, content) {
+            Ok(Some(msg)) => {
+                log::info!("{}", msg);
+                self.save_status = Some(Ok(msg));
+            }
+            Ok(None) => (),
+            Err(e) => {
+                log::error!("Failed to save file: {}", e);
+                self.save_status = Some(Err(e));
             }
         }
     }
@@ -1,13 +1,100 @@
 use eframe::egui;
-mod transforms;
+mod access;
+mod aliases;
 mod another;
+mod backend;
+mod banner;
+mod barcode;
+mod busy_overlay;
+mod capabilities;
+mod cell_guards;
+mod cleaning;
+mod command_feedback;
+mod command_lint;
+mod command_queue;
+mod commissioning;
+mod custom_panels;
+mod dashboard;
+mod export_profiles;
+mod exporters;
+mod expression;
+mod goal;
+mod gripper;
+mod history;
+mod input_devices;
+mod inspection;
+mod interpolation;
+mod job_import;
+mod jog;
+mod joint_drift;
+mod joint_limits;
+mod joint_presets;
+mod kinematics;
+mod locale;
 mod lookup;
+mod maintenance;
+mod measure;
+mod mes;
+mod model_export;
+mod offline_scene;
+mod operator_prompt;
+mod order_history;
+mod order_queue;
+mod orders;
+mod panic_guard;
+mod path;
+mod pause;
+mod payloads;
+mod pose;
+mod pose_filter;
+mod pose_stats;
+mod presets;
+mod preview;
+mod products;
+mod provenance;
+mod recorder;
+mod resources;
+mod reteach;
+mod retry;
 mod robot;
+mod robot_interface;
+mod rotation_editor;
+mod rpc;
+mod scene_lint;
+mod scene_repo;
+mod schema;
+mod screenshot;
+mod screwdriving;
+mod scripting;
+mod search_insertion;
+mod self_test;
+mod sequence;
+mod settings;
+mod shift_report;
+mod shutdown;
+mod signal_tower;
+mod speed_monitor;
+mod speed_override;
+mod speed_zones;
+mod state_poller;
+mod statistics;
+mod storage;
+mod subroutines;
 mod tabs;
+mod teach;
+mod transform_feed;
+mod transforms;
+mod utilization;
+mod vibration;
+mod waypoint_program;
+mod work_instructions;
+mod workers;
+mod workspace;
 
 #[tokio::main]
 async fn main() -> Result<(), eframe::Error> {
     env_logger::init();
+    panic_guard::install_hook();
 
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default().with_inner_size([750.0, 750.0]),
@@ -22,6 +109,8 @@ async fn main() -> Result<(), eframe::Error> {
         options,
         Box::new(|cc| {
             cc.egui_ctx.set_pixels_per_point(1.25);
+            // Photos of operator prompts are loaded from file:// URIs
+            egui_extras::install_image_loaders(&cc.egui_ctx);
             Ok(Box::new(my_app))
         }),
     )
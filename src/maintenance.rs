@@ -0,0 +1,144 @@
+use crate::locale::{self, LocalizedDragValue};
+use crate::storage;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub const MAINTENANCE_FILE: &str = "maintenance.json";
+
+pub fn check_file() -> Option<Result<(), String>> {
+    storage::check_json::<MaintenanceLog>(MAINTENANCE_FILE)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceNote {
+    /// Seconds since the unix epoch.
+    pub timestamp: u64,
+    pub text: String,
+    /// Service notes reset the operating hours counter.
+    pub service: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ResourceMaintenance {
+    pub notes: Vec<MaintenanceNote>,
+    /// Seconds spent executing commands since the last service note.
+    pub operating_seconds_since_service: f64,
+    /// Remind after this many operating hours since the last service, if set.
+    pub reminder_hours: Option<f64>,
+}
+
+impl ResourceMaintenance {
+    pub fn operating_hours(&self) -> f64 {
+        self.operating_seconds_since_service / 3600.0
+    }
+
+    pub fn is_service_due(&self) -> bool {
+        self.reminder_hours
+            .is_some_and(|hours| self.operating_hours() >= hours)
+    }
+}
+
+/// Maintenance notes and operating hours per resource, kept in a local JSON file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MaintenanceLog {
+    pub resources: BTreeMap<String, ResourceMaintenance>,
+}
+
+impl MaintenanceLog {
+    pub fn load() -> Self {
+        storage::load_json(MAINTENANCE_FILE).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Err(e) = storage::save_json(MAINTENANCE_FILE, self) {
+            log::error!("Failed to save maintenance log: {}", e);
+        }
+    }
+
+    pub fn add_note(&mut self, resource: &str, text: &str, service: bool) {
+        let entry = self.resources.entry(resource.to_string()).or_default();
+        entry.notes.push(MaintenanceNote {
+            timestamp: storage::now_secs(),
+            text: text.to_string(),
+            service,
+        });
+        if service {
+            entry.operating_seconds_since_service = 0.0;
+        }
+        self.save();
+    }
+
+    pub fn add_operating_time(&mut self, resource: &str, seconds: f64) {
+        self.resources
+            .entry(resource.to_string())
+            .or_default()
+            .operating_seconds_since_service += seconds;
+    }
+}
+
+/// Per-card input state for a new note.
+#[derive(Default)]
+pub struct NoteInput {
+    pub text: String,
+    pub service: bool,
+}
+
+/// Draws the maintenance section of a resource card.
+pub fn draw_maintenance_section(
+    ui: &mut egui::Ui,
+    log: &mut MaintenanceLog,
+    resource: &str,
+    input: &mut NoteInput,
+) {
+    let entry = log.resources.get(resource).cloned().unwrap_or_default();
+    egui::CollapsingHeader::new(format!("Maintenance ({})", entry.notes.len()))
+        .id_salt(format!("maintenance_{}", resource))
+        .show(ui, |ui| {
+            ui.label(format!(
+                "Operating since last service: {} h",
+                locale::format_number(entry.operating_hours(), 1)
+            ));
+            ui.horizontal(|ui| {
+                let mut use_reminder = entry.reminder_hours.is_some();
+                let mut hours = entry.reminder_hours.unwrap_or(500.0);
+                let mut changed = ui.checkbox(&mut use_reminder, "Remind after").changed();
+                changed |= ui
+                    .add_enabled(
+                        use_reminder,
+                        egui::DragValue::new(&mut hours)
+                            .localized()
+                            .suffix(" h")
+                            .range(1.0..=10_000.0),
+                    )
+                    .changed();
+                if changed {
+                    log.resources
+                        .entry(resource.to_string())
+                        .or_default()
+                        .reminder_hours = use_reminder.then_some(hours);
+                    log.save();
+                }
+            });
+            for note in entry.notes.iter().rev().take(5) {
+                ui.horizontal_wrapped(|ui| {
+                    ui.weak(storage::format_timestamp(note.timestamp));
+                    if note.service {
+                        ui.strong("[service]");
+                    }
+                    ui.label(&note.text);
+                });
+            }
+            ui.text_edit_singleline(&mut input.text);
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut input.service, "Service performed");
+                if ui
+                    .add_enabled(!input.text.trim().is_empty(), egui::Button::new("Add Note"))
+                    .clicked()
+                {
+                    log.add_note(resource, input.text.trim(), input.service);
+                    *input = NoteInput::default();
+                }
+            });
+        });
+}
@@ -0,0 +1,205 @@
+//! Distance between two frames, looked up again every half second while the
+//! window is open, for watching a fixture move into place.
+
+use crate::locale;
+use crate::pose::{self, Pose};
+use crate::workers;
+use eframe::egui;
+use micro_sp::*;
+use poll_promise::Promise;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+const UPDATE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// `to` relative to `from`.
+pub struct Measurement {
+    pub distance: f64,
+    /// Along the axes of `from`.
+    pub delta: [f64; 3],
+    /// Angle of the relative rotation in degrees.
+    pub angle: f64,
+    /// Relative rotation as roll, pitch, yaw in degrees.
+    pub rpy: [f64; 3],
+}
+
+impl Measurement {
+    pub fn new(relative: &Pose) -> Self {
+        let delta = relative.translation;
+        let w = pose::normalize_quaternion(relative.rotation)[3]
+            .abs()
+            .min(1.0);
+        Self {
+            distance: delta.iter().map(|d| d * d).sum::<f64>().sqrt(),
+            delta,
+            angle: 2.0 * w.acos().to_degrees(),
+            rpy: pose::quaternion_to_rpy(relative.rotation).map(f64::to_degrees),
+        }
+    }
+}
+
+async fn measure(
+    con: Arc<ConnectionManager>,
+    from: String,
+    to: String,
+) -> Result<Measurement, String> {
+    let mut connection = con.get_connection().await;
+    let tf = TransformsManager::lookup_transform(&mut connection, &from, &to)
+        .await
+        .map_err(|e| format!("Failed to look up {} in {}: {}", to, from, e))?;
+    Ok(Measurement::new(&Pose::from_sp_transform(&tf.transform)))
+}
+
+#[derive(Default)]
+pub struct MeasureWindow {
+    pub open: bool,
+    from: Option<String>,
+    to: Option<String>,
+    promise: Option<Promise<Result<Measurement, String>>>,
+    result: Option<Result<Measurement, String>>,
+    last_update: Option<Instant>,
+}
+
+impl MeasureWindow {
+    pub fn show(&mut self, from: Option<String>) {
+        self.open = true;
+        if from.is_some() {
+            self.from = from;
+            self.result = None;
+        }
+    }
+
+    fn poll(&mut self, handle: &tokio::runtime::Handle, connection: &Arc<ConnectionManager>) {
+        if let Some(promise) = self.promise.take() {
+            match promise.try_take() {
+                Ok(result) => self.result = Some(result),
+                Err(promise) => {
+                    self.promise = Some(promise);
+                    return;
+                }
+            }
+        }
+        let (Some(from), Some(to)) = (self.from.clone(), self.to.clone()) else {
+            return;
+        };
+        if self
+            .last_update
+            .is_some_and(|last| last.elapsed() < UPDATE_INTERVAL)
+        {
+            return;
+        }
+        self.last_update = Some(Instant::now());
+        let handle = handle.clone();
+        let con_clone = connection.clone();
+        self.promise = Some(workers::spawn("frame_measure", move || {
+            handle.block_on(measure(con_clone, from, to))
+        }));
+    }
+
+    pub fn draw(
+        &mut self,
+        ctx: &egui::Context,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+        frames: &[String],
+    ) {
+        if !self.open {
+            return;
+        }
+        self.poll(handle, connection);
+        ctx.request_repaint_after(UPDATE_INTERVAL);
+
+        let mut open = true;
+        egui::Window::new("Measure Frames")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let mut changed = false;
+                egui::Grid::new("measure_frames_select")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        for (label, id, selection) in [
+                            ("From:", "measure_from", &mut self.from),
+                            ("To:", "measure_to", &mut self.to),
+                        ] {
+                            ui.label(label);
+                            changed |= frame_combo(ui, id, selection, frames);
+                            ui.end_row();
+                        }
+                    });
+                if ui.small_button("⇄ Swap").clicked() {
+                    std::mem::swap(&mut self.from, &mut self.to);
+                    changed = true;
+                }
+                if changed {
+                    self.result = None;
+                    self.last_update = None;
+                }
+                ui.separator();
+                match &self.result {
+                    None if self.from.is_none() || self.to.is_none() => {
+                        ui.weak("Select two frames.");
+                    }
+                    None => {
+                        ui.spinner();
+                    }
+                    Some(Err(e)) => {
+                        ui.colored_label(egui::Color32::RED, e);
+                    }
+                    Some(Ok(m)) => draw_measurement(ui, m),
+                }
+            });
+        if !open {
+            self.open = false;
+            self.promise = None;
+            self.result = None;
+        }
+    }
+}
+
+fn frame_combo(
+    ui: &mut egui::Ui,
+    id: &str,
+    selection: &mut Option<String>,
+    frames: &[String],
+) -> bool {
+    let mut changed = false;
+    egui::ComboBox::from_id_salt(id)
+        .selected_text(selection.as_deref().unwrap_or("Select..."))
+        .show_ui(ui, |ui| {
+            for frame in frames {
+                changed |= ui
+                    .selectable_value(selection, Some(frame.clone()), frame)
+                    .changed();
+            }
+        });
+    changed
+}
+
+fn draw_measurement(ui: &mut egui::Ui, m: &Measurement) {
+    let mm = |x: f64| format!("{} mm", locale::format_number(x * 1000.0, 2));
+    let deg = |x: f64| format!("{}°", locale::format_number(x, 2));
+    egui::Grid::new("measure_frames_result")
+        .num_columns(4)
+        .spacing([16.0, 4.0])
+        .show(ui, |ui| {
+            ui.strong("Distance");
+            ui.monospace(mm(m.distance));
+            ui.end_row();
+            ui.label("Δ (in From axes)");
+            for d in m.delta {
+                ui.monospace(mm(d));
+            }
+            ui.end_row();
+            ui.strong("Rotation");
+            ui.monospace(deg(m.angle));
+            ui.end_row();
+            ui.label("Roll, pitch, yaw");
+            for r in m.rpy {
+                ui.monospace(deg(r));
+            }
+            ui.end_row();
+        });
+}
@@ -0,0 +1,289 @@
+//! Order intake from the MES. Orders are polled from its REST endpoint or
+//! pushed through the automation interface (`submit_orders`), then wait in the
+//! Order Handler until the operator accepts or rejects them.
+
+use crate::orders::Order;
+use crate::storage;
+use crate::workers;
+use micro_sp::*;
+use poll_promise::Promise;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::HashSet,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+pub const MES_FILE: &str = "mes.json";
+
+pub fn check_file() -> Option<Result<(), String>> {
+    storage::check_json::<MesConfig>(MES_FILE)
+}
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Orders pushed through the automation interface, collected by the intake.
+static RECEIVED: Mutex<Vec<Value>> = Mutex::new(Vec::new());
+
+/// Copies an MES order field into a state variable when the order is accepted.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FieldMapping {
+    pub field: String,
+    pub variable: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MesConfig {
+    /// Endpoint returning the open orders as a JSON array, or an object with
+    /// an `orders` array.
+    pub url: String,
+    pub poll_enabled: bool,
+    pub poll_interval_secs: u64,
+    /// Fields of an MES order, dotted for nested objects (`product.code`).
+    pub id_field: String,
+    pub program_field: String,
+    pub quantity_field: String,
+    pub variables: Vec<FieldMapping>,
+}
+
+impl Default for MesConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            poll_enabled: false,
+            poll_interval_secs: 30,
+            id_field: "id".to_string(),
+            program_field: "program".to_string(),
+            quantity_field: "quantity".to_string(),
+            variables: vec![FieldMapping {
+                field: "id".to_string(),
+                variable: "order_id".to_string(),
+            }],
+        }
+    }
+}
+
+impl MesConfig {
+    pub fn load() -> Self {
+        storage::load_json(MES_FILE).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Err(e) = storage::save_json(MES_FILE, self) {
+            log::error!("Failed to save the MES configuration: {}", e);
+        }
+    }
+}
+
+/// Queues orders pushed by the MES, from any thread.
+pub fn receive(body: Value) {
+    RECEIVED.lock().unwrap().push(body);
+}
+
+fn field<'a>(order: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .try_fold(order, |value, key| value.get(key.trim()))
+}
+
+fn field_text(order: &Value, path: &str) -> Option<String> {
+    match field(order, path)? {
+        Value::String(s) => Some(s.trim().to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+fn json_to_sp_value(value: &Value) -> Option<SPValue> {
+    match value {
+        Value::Bool(b) => Some(b.to_spvalue()),
+        Value::Number(n) => n.as_f64().map(|f| f.to_spvalue()),
+        Value::String(s) => Some(s.as_str().to_spvalue()),
+        _ => None,
+    }
+}
+
+/// An MES order waiting for the operator.
+#[derive(Debug, Clone)]
+pub struct IncomingOrder {
+    pub order: Order,
+    /// Mapped state variables, written when the order is accepted.
+    pub variables: Vec<(String, SPValue)>,
+    pub received: u64,
+}
+
+fn parse_order(config: &MesConfig, value: &Value) -> Result<IncomingOrder, String> {
+    let id = field_text(value, &config.id_field)
+        .filter(|id| !id.is_empty())
+        .ok_or_else(|| format!("Order without '{}'", config.id_field))?;
+    let program = field_text(value, &config.program_field)
+        .ok_or_else(|| format!("Order {}: no '{}'", id, config.program_field))?;
+    let quantity = match field(value, &config.quantity_field) {
+        None => 1,
+        Some(quantity) => quantity
+            .as_u64()
+            .or_else(|| quantity.as_str().and_then(|s| s.trim().parse().ok()))
+            .and_then(|q| u32::try_from(q).ok())
+            .ok_or_else(|| format!("Order {}: invalid '{}'", id, config.quantity_field))?,
+    };
+    let mut variables = Vec::new();
+    for mapping in &config.variables {
+        if mapping.variable.trim().is_empty() {
+            continue;
+        }
+        let value = field(value, &mapping.field)
+            .and_then(json_to_sp_value)
+            .ok_or_else(|| format!("Order {}: no value for '{}'", id, mapping.field))?;
+        variables.push((mapping.variable.trim().to_string(), value));
+    }
+    Ok(IncomingOrder {
+        order: Order {
+            id,
+            program,
+            quantity,
+            ..Default::default()
+        },
+        variables,
+        received: storage::now_secs(),
+    })
+}
+
+async fn fetch_orders(url: String) -> Result<Value, String> {
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    client
+        .get(&url)
+        .header("Accept", "application/json")
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("MES request failed: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("MES sent invalid JSON: {}", e))
+}
+
+pub struct MesIntake {
+    pub config: MesConfig,
+    pending: Vec<IncomingOrder>,
+    /// Orders accepted or rejected in this session, so polling doesn't bring
+    /// them back.
+    handled: HashSet<String>,
+    poll_promise: Option<Promise<Result<Value, String>>>,
+    last_poll: Option<Instant>,
+    pub status: Option<Result<String, String>>,
+}
+
+impl MesIntake {
+    pub fn new() -> Self {
+        Self {
+            config: MesConfig::load(),
+            pending: Vec::new(),
+            handled: HashSet::new(),
+            poll_promise: None,
+            last_poll: None,
+            status: None,
+        }
+    }
+
+    pub fn pending(&self) -> &[IncomingOrder] {
+        &self.pending
+    }
+
+    pub fn is_polling(&self) -> bool {
+        self.poll_promise.is_some()
+    }
+
+    /// Call once per frame. Collects pushed orders and finished polls, and
+    /// starts a poll when one is due.
+    pub fn update(&mut self, handle: &tokio::runtime::Handle) {
+        let received = std::mem::take(&mut *RECEIVED.lock().unwrap());
+        for body in received {
+            self.add(&body, "automation interface");
+        }
+        if let Some(promise) = self.poll_promise.take() {
+            match promise.try_take() {
+                Ok(Ok(body)) => self.add(&body, "MES"),
+                Ok(Err(e)) => {
+                    log::error!("{}", e);
+                    self.status = Some(Err(e));
+                }
+                Err(promise) => self.poll_promise = Some(promise),
+            }
+        }
+        let interval = Duration::from_secs(self.config.poll_interval_secs.max(1));
+        let due = self.last_poll.is_none_or(|last| last.elapsed() >= interval);
+        if self.config.poll_enabled && due {
+            self.poll_now(handle);
+        }
+    }
+
+    pub fn poll_now(&mut self, handle: &tokio::runtime::Handle) {
+        if self.poll_promise.is_some() || self.config.url.trim().is_empty() {
+            return;
+        }
+        self.last_poll = Some(Instant::now());
+        let url = self.config.url.trim().to_string();
+        let handle = handle.clone();
+        self.poll_promise = Some(workers::spawn_long("mes_poll", move || {
+            handle.block_on(fetch_orders(url))
+        }));
+    }
+
+    fn add(&mut self, body: &Value, source: &str) {
+        let orders = match body {
+            Value::Array(orders) => orders.as_slice(),
+            Value::Object(object) => match object.get("orders") {
+                Some(Value::Array(orders)) => orders.as_slice(),
+                _ => std::slice::from_ref(body),
+            },
+            _ => {
+                self.status = Some(Err(format!("Unexpected order data from {}", source)));
+                return;
+            }
+        };
+        let mut added = 0;
+        let mut problems = Vec::new();
+        for value in orders {
+            match parse_order(&self.config, value) {
+                Ok(incoming) => {
+                    let id = &incoming.order.id;
+                    let known =
+                        self.handled.contains(id) || self.pending.iter().any(|p| p.order.id == *id);
+                    if !known {
+                        self.pending.push(incoming);
+                        added += 1;
+                    }
+                }
+                Err(e) => problems.push(e),
+            }
+        }
+        for problem in &problems {
+            log::warn!("Skipped an order from {}: {}", source, problem);
+        }
+        self.status = Some(if problems.is_empty() {
+            Ok(format!("{} new orders from {}", added, source))
+        } else {
+            Err(format!(
+                "{} new orders from {}, {} skipped: {}",
+                added,
+                source,
+                problems.len(),
+                problems.join("; ")
+            ))
+        });
+    }
+
+    /// Removes a pending order for good, whether accepted or rejected.
+    pub fn take(&mut self, i: usize) -> Option<IncomingOrder> {
+        if i >= self.pending.len() {
+            return None;
+        }
+        let incoming = self.pending.remove(i);
+        self.handled.insert(incoming.order.id.clone());
+        Some(incoming)
+    }
+}
@@ -0,0 +1,173 @@
+//! Turns a taught path into micro_sp operation definitions, so a sequence
+//! prototyped in the GUI can be moved into the formal model.
+
+use micro_sp::*;
+
+/// One path step as the exporter sees it.
+pub struct ExportStep {
+    pub robot_id: String,
+    pub goal_feature_id: String,
+    /// The command variables of the step, with the robot prefix.
+    pub state: State,
+    /// Runs together with the previous step.
+    pub parallel: bool,
+    /// Conditions the model has to express itself, kept as comments.
+    pub notes: Vec<String>,
+}
+
+/// Variables the operations write themselves.
+fn is_request_variable(key: &str, robot_id: &str) -> bool {
+    ["request_trigger", "request_cancel", "request_state"]
+        .iter()
+        .any(|suffix| key == format!("{}_{}", robot_id, suffix))
+}
+
+fn action_value(value: &SPValue) -> String {
+    match value {
+        SPValue::String(StringOrUnknown::String(s)) => s.clone(),
+        SPValue::Bool(BoolOrUnknown::Bool(b)) => b.to_string(),
+        SPValue::Float64(FloatOrUnknown::Float64(f)) => format!("{:?}", f.0),
+        _ => "UNKNOWN".to_string(),
+    }
+}
+
+fn step_label(index: usize) -> String {
+    format!("step_{}", index + 1)
+}
+
+fn transition(name: &str, guard: &str, actions: &[String]) -> String {
+    let actions = actions
+        .iter()
+        .map(|a| format!("                \"{}\",", a))
+        .collect::<Vec<String>>()
+        .join("\n");
+    format!(
+        "            Transition::parse(\n                \"{}\",\n                \"{}\",\n                \"true\",\n                vec![\n{}\n                ],\n                Vec::<&str>::new(),\n                state,\n            )",
+        name, guard, actions
+    )
+}
+
+fn operation(name: &str, notes: &[String], precondition: String, postcondition: String) -> String {
+    let notes = notes
+        .iter()
+        .map(|n| format!("    // {}\n", n))
+        .collect::<String>();
+    format!(
+        "{}    operations.push(operation(\n        \"{}\",\n{},\n{},\n    ));\n",
+        notes, name, precondition, postcondition
+    )
+}
+
+/// Rust snippet with one operation per step. A `{program}_step` variable
+/// orders them; parallel steps start on the same value and a join operation
+/// advances it once all of them succeeded.
+pub fn export_operations(program: &str, steps: &[ExportStep]) -> String {
+    let step_variable = format!("{}_step", program);
+    let mut body = String::new();
+    let mut done_flags = Vec::new();
+    let mut group_start = 0;
+
+    for (i, step) in steps.iter().enumerate() {
+        if !step.parallel || i == 0 {
+            group_start = i;
+        }
+        let next_parallel = steps.get(i + 1).is_some_and(|s| s.parallel);
+        let in_group = (step.parallel && i > 0) || next_parallel;
+        let robot = &step.robot_id;
+        let op_name = format!("{}_{}_{}", program, step_label(i), step.goal_feature_id);
+
+        let mut start_actions = step
+            .state
+            .state
+            .iter()
+            .filter(|(key, _)| !is_request_variable(key, robot))
+            .map(|(key, assignment)| format!("var:{} <- {}", key, action_value(&assignment.val)))
+            .collect::<Vec<String>>();
+        start_actions.sort();
+        start_actions.push(format!("var:{}_request_trigger <- true", robot));
+        let done = format!("{}_done", op_name);
+        let mut guard = format!(
+            "var:{} == {} && var:{}_request_trigger == false && var:{}_request_state == initial",
+            step_variable,
+            step_label(group_start),
+            robot,
+            robot
+        );
+
+        let mut finish_actions = vec![
+            format!("var:{}_request_trigger <- false", robot),
+            format!("var:{}_request_state <- initial", robot),
+        ];
+        if in_group {
+            // The step variable only moves on at the join, so a finished
+            // parallel step must not start again in the meantime
+            guard.push_str(&format!(" && var:{} == false", done));
+            finish_actions.push(format!("var:{} <- true", done));
+            done_flags.push(done);
+        } else {
+            finish_actions.push(format!("var:{} <- {}", step_variable, step_label(i + 1)));
+        }
+
+        body.push_str(&operation(
+            &op_name,
+            &step.notes,
+            transition(&format!("start_{}", op_name), &guard, &start_actions),
+            transition(
+                &format!("complete_{}", op_name),
+                &format!("var:{}_request_state == succeeded", robot),
+                &finish_actions,
+            ),
+        ));
+
+        if in_group && !next_parallel {
+            let join_name = format!("{}_join_{}", program, step_label(group_start));
+            let guard = done_flags
+                .iter()
+                .map(|d| format!("var:{} == true", d))
+                .collect::<Vec<String>>()
+                .join(" && ");
+            let mut actions = done_flags
+                .drain(..)
+                .map(|d| format!("var:{} <- false", d))
+                .collect::<Vec<String>>();
+            actions.push(format!("var:{} <- {}", step_variable, step_label(i + 1)));
+            body.push_str(&operation(
+                &join_name,
+                &[format!(
+                    "Joins the parallel steps {} to {}",
+                    group_start + 1,
+                    i + 1
+                )],
+                transition(
+                    &format!("start_{}", join_name),
+                    &format!(
+                        "var:{} == {} && {}",
+                        step_variable,
+                        step_label(group_start),
+                        guard
+                    ),
+                    &actions,
+                ),
+                transition(&format!("complete_{}", join_name), "true", &[]),
+            ));
+        }
+    }
+
+    format!(
+        "// Generated by micro_sp_gui from a taught path. Review before adding to the model.\n\
+         // `{step}` starts at {first} and ends at {last}.\n\
+         //\n\
+         // `operation` stands for the model's constructor taking a name, one\n\
+         // precondition and one postcondition, e.g. a wrapper around Operation::new.\n\
+         pub fn {program}_operations(state: &State) -> Vec<Operation> {{\n\
+         \x20   let mut operations = Vec::new();\n\n\
+         {body}\n\
+         \x20   operations\n\
+         }}\n",
+        step = step_variable,
+        first = step_label(0),
+        last = step_label(steps.len()),
+        program = program,
+        body = body,
+    )
+}
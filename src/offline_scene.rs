@@ -0,0 +1,217 @@
+//! Scene files edited without the live transform store. The file is the
+//! Transforms tab's JSON scene export; once connected again the changes are
+//! compared with the live frames and pushed selectively.
+
+use crate::pose::{self, Pose};
+use crate::schema;
+use micro_sp::{MapOrUnknown, SPTransform, SPTransformStamped};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+    time::SystemTime,
+};
+
+#[derive(Serialize)]
+struct SceneFile {
+    frames: BTreeMap<String, SPTransformStamped>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneImport {
+    frames: BTreeMap<String, SceneFrame>,
+}
+
+/// A frame as read from a scene file. `SPTransformStamped` doesn't reject
+/// unknown fields, so the frame is read as this and converted. The metadata
+/// is the transform store's open map, other tools add their own keys to it.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneFrame {
+    active_transform: bool,
+    enable_transform: bool,
+    time_stamp: SystemTime,
+    parent_frame_id: String,
+    child_frame_id: String,
+    transform: SPTransform,
+    metadata: MapOrUnknown,
+}
+
+impl From<SceneFrame> for SPTransformStamped {
+    fn from(frame: SceneFrame) -> Self {
+        Self {
+            active_transform: frame.active_transform,
+            enable_transform: frame.enable_transform,
+            time_stamp: frame.time_stamp,
+            parent_frame_id: frame.parent_frame_id,
+            child_frame_id: frame.child_frame_id,
+            transform: frame.transform,
+            metadata: frame.metadata,
+        }
+    }
+}
+
+pub struct OfflineScene {
+    pub path: PathBuf,
+    pub frames: HashMap<String, SPTransformStamped>,
+    /// Edited since the file was opened or saved.
+    pub dirty: bool,
+}
+
+impl OfflineScene {
+    /// Returns the scene and a warning if the file is from a newer GUI.
+    pub fn open(path: PathBuf) -> Result<(Self, Option<String>), String> {
+        let text = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let loaded = schema::load::<SceneImport>(&text, schema::SCENE_KIND)?;
+        let scene = Self {
+            path,
+            frames: loaded
+                .data
+                .frames
+                .into_iter()
+                .map(|(name, frame)| (name, frame.into()))
+                .collect(),
+            dirty: false,
+        };
+        Ok((scene, loaded.warning))
+    }
+
+    pub fn save(&mut self) -> Result<String, String> {
+        let frames = self
+            .frames
+            .iter()
+            .map(|(name, frame)| (name.clone(), pose::export_frame(frame)))
+            .collect();
+        let content = serde_json::to_string_pretty(&schema::Versioned::new(
+            schema::SCENE_KIND,
+            SceneFile { frames },
+        ))
+        .map_err(|e| format!("JSON serialization error: {}", e))?;
+        std::fs::write(&self.path, content)
+            .map_err(|e| format!("Failed to save {}: {}", self.path.display(), e))?;
+        self.dirty = false;
+        Ok(format!("Saved {}", self.path.display()))
+    }
+
+    pub fn name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+
+    pub fn insert(&mut self, transforms: Vec<SPTransformStamped>) {
+        for transform in transforms {
+            self.frames
+                .insert(transform.child_frame_id.clone(), transform);
+        }
+        self.dirty = true;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    /// Only in the file, pushing creates it.
+    Added,
+    /// In both, with different content.
+    Modified,
+    /// Only in the live store. Never pushed, the file may just be older.
+    LiveOnly,
+}
+
+pub struct SceneChange {
+    pub name: String,
+    pub kind: ChangeKind,
+    pub local: Option<SPTransformStamped>,
+    pub summary: String,
+}
+
+/// What differs between two versions of a frame, in words.
+fn describe_difference(local: &SPTransformStamped, live: &SPTransformStamped) -> String {
+    let mut parts = Vec::new();
+    if local.parent_frame_id != live.parent_frame_id {
+        parts.push(format!(
+            "parent {} → {}",
+            live.parent_frame_id, local.parent_frame_id
+        ));
+    }
+    let a = Pose::from_sp_transform(&live.transform);
+    let b = Pose::from_sp_transform(&local.transform);
+    let moved = (0..3)
+        .map(|i| (b.translation[i] - a.translation[i]).powi(2))
+        .sum::<f64>()
+        .sqrt();
+    if moved > 1e-6 {
+        parts.push(format!(
+            "moved {} mm",
+            crate::locale::format_number(moved * 1000.0, 2)
+        ));
+    }
+    let dot = (0..4)
+        .map(|i| a.rotation[i] * b.rotation[i])
+        .sum::<f64>()
+        .abs()
+        .min(1.0);
+    let angle = 2.0 * dot.acos().to_degrees();
+    if angle > 1e-3 {
+        parts.push(format!(
+            "rotated {}°",
+            crate::locale::format_number(angle, 2)
+        ));
+    }
+    if parts.is_empty() {
+        parts.push("metadata".to_string());
+    }
+    parts.join(", ")
+}
+
+fn same(a: &SPTransformStamped, b: &SPTransformStamped) -> bool {
+    // The stamp changes on every publish, so it isn't a difference
+    let content = |t: &SPTransformStamped| {
+        let mut value = serde_json::to_value(t).unwrap_or_default();
+        if let Some(object) = value.as_object_mut() {
+            object.remove("time_stamp");
+        }
+        value
+    };
+    content(a) == content(b)
+}
+
+/// Frames that differ between the file and the live store, sorted by name.
+pub fn diff(
+    local: &HashMap<String, SPTransformStamped>,
+    live: &HashMap<String, SPTransformStamped>,
+) -> Vec<SceneChange> {
+    let mut changes = Vec::new();
+    for (name, frame) in local {
+        match live.get(name) {
+            None => changes.push(SceneChange {
+                name: name.clone(),
+                kind: ChangeKind::Added,
+                local: Some(frame.clone()),
+                summary: format!("new, under {}", frame.parent_frame_id),
+            }),
+            Some(live_frame) if !same(frame, live_frame) => changes.push(SceneChange {
+                name: name.clone(),
+                kind: ChangeKind::Modified,
+                local: Some(frame.clone()),
+                summary: describe_difference(frame, live_frame),
+            }),
+            Some(_) => (),
+        }
+    }
+    for (name, frame) in live {
+        if !local.contains_key(name) {
+            changes.push(SceneChange {
+                name: name.clone(),
+                kind: ChangeKind::LiveOnly,
+                local: None,
+                summary: format!("only live, under {}", frame.parent_frame_id),
+            });
+        }
+    }
+    changes.sort_by(|a, b| a.name.cmp(&b.name));
+    changes
+}
@@ -0,0 +1,103 @@
+//! Operator prompts of path steps ("insert new tray, then press continue").
+//! The executor waits in `ask` until the operator answers the modal.
+
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::{collections::VecDeque, sync::Mutex};
+use tokio::sync::oneshot;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OperatorPrompt {
+    pub message: String,
+    /// Longer instructions below the message, empty for none.
+    pub instructions: String,
+    /// Photo shown with the instructions, empty for none.
+    pub image_path: String,
+}
+
+struct PendingPrompt {
+    step: usize,
+    prompt: OperatorPrompt,
+    reply: oneshot::Sender<bool>,
+}
+
+/// Prompts waiting for the operator, oldest first. Parallel steps can ask at
+/// the same time, they are answered one after the other.
+#[derive(Default)]
+pub struct PromptQueue {
+    pending: Mutex<VecDeque<PendingPrompt>>,
+}
+
+impl PromptQueue {
+    /// Shows the prompt of the 0-based `step` and waits until the operator
+    /// continues. Aborting, or the GUI going away, fails the step.
+    pub async fn ask(&self, step: usize, prompt: OperatorPrompt) -> Result<(), String> {
+        let (reply, answer) = oneshot::channel();
+        self.pending.lock().unwrap().push_back(PendingPrompt {
+            step,
+            prompt,
+            reply,
+        });
+        match answer.await {
+            Ok(true) => Ok(()),
+            _ => Err("Aborted by the operator".to_string()),
+        }
+    }
+
+    /// Draws the oldest prompt, if any, as a modal over the whole window.
+    pub fn draw(&self, ctx: &egui::Context) {
+        let mut pending = self.pending.lock().unwrap();
+        let Some(current) = pending.front() else {
+            return;
+        };
+        if let Some(proceed) = draw_modal(ctx, current.step, &current.prompt) {
+            if let Some(answered) = pending.pop_front() {
+                // The executor may have stopped waiting already
+                let _ = answered.reply.send(proceed);
+            }
+        }
+    }
+}
+
+fn draw_modal(ctx: &egui::Context, step: usize, prompt: &OperatorPrompt) -> Option<bool> {
+    egui::Modal::new(egui::Id::new("operator_prompt_modal"))
+        .show(ctx, |ui| {
+            ui.set_width(480.0);
+            ui.weak(format!("Step {} is waiting for the operator", step + 1));
+            ui.add_space(4.0);
+            ui.label(egui::RichText::new(&prompt.message).size(22.0).strong());
+            if !prompt.instructions.trim().is_empty() {
+                ui.add_space(4.0);
+                ui.label(&prompt.instructions);
+            }
+            if !prompt.image_path.trim().is_empty() {
+                ui.add_space(4.0);
+                ui.add(
+                    egui::Image::new(format!("file://{}", prompt.image_path.trim()))
+                        .max_height(320.0)
+                        .max_width(480.0),
+                );
+            }
+            ui.add_space(12.0);
+            ui.vertical_centered(|ui| {
+                let continue_button =
+                    egui::Button::new(egui::RichText::new("Continue").size(28.0).strong())
+                        .fill(egui::Color32::DARK_GREEN);
+                if ui.add_sized([260.0, 72.0], continue_button).clicked() {
+                    return Some(true);
+                }
+                ui.add_space(8.0);
+                if ui
+                    .button("Abort Path")
+                    .on_hover_text("Stop the path here, the step is not executed")
+                    .clicked()
+                {
+                    return Some(false);
+                }
+                None
+            })
+            .inner
+        })
+        .inner
+}
@@ -0,0 +1,215 @@
+//! The order that is running and the completion records of finished ones. An
+//! order completes when its program's goal holds, alarms seen on the way are
+//! kept with the record.
+
+use crate::orders::Order;
+use crate::state_poller::sp_value_to_display;
+use crate::storage;
+use micro_sp::*;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, io::Write, sync::Arc};
+
+/// Append-only log of finished orders, one JSON object per line.
+const ORDER_HISTORY_FILE: &str = "order_history.jsonl";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Outcome {
+    Completed,
+    Failed,
+    /// The goal was replaced or cleared before it held.
+    Cancelled,
+}
+
+impl std::fmt::Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Outcome::Completed => write!(f, "completed"),
+            Outcome::Failed => write!(f, "failed"),
+            Outcome::Cancelled => write!(f, "cancelled"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompletionRecord {
+    pub order_id: String,
+    pub program: String,
+    pub quantity: u32,
+    /// Seconds since the unix epoch.
+    pub started: u64,
+    pub finished: u64,
+    pub outcome: Outcome,
+    pub alarms: Vec<String>,
+}
+
+impl CompletionRecord {
+    pub fn duration_secs(&self) -> u64 {
+        self.finished.saturating_sub(self.started)
+    }
+}
+
+pub fn record(entry: &CompletionRecord) {
+    let path = storage::data_file(ORDER_HISTORY_FILE);
+    let result = std::fs::create_dir_all(storage::data_dir())
+        .and_then(|_| {
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+        })
+        .and_then(|mut file| {
+            let line = serde_json::to_string(entry).map_err(std::io::Error::other)?;
+            writeln!(file, "{}", line)
+        });
+    if let Err(e) = result {
+        log::error!("Failed to record order history to {:?}: {}", path, e);
+    }
+}
+
+pub fn load() -> Vec<CompletionRecord> {
+    let Ok(content) = std::fs::read_to_string(storage::data_file(ORDER_HISTORY_FILE)) else {
+        return Vec::new();
+    };
+    content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+pub fn to_json(records: &[CompletionRecord]) -> Result<String, String> {
+    serde_json::to_string_pretty(records).map_err(|e| e.to_string())
+}
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// One row per order, alarms joined with `; `.
+pub fn to_csv(records: &[CompletionRecord]) -> String {
+    let mut csv =
+        "order_id,program,quantity,started,finished,duration_s,outcome,alarms\n".to_string();
+    for r in records {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{},{}\n",
+            csv_field(&r.order_id),
+            csv_field(&r.program),
+            r.quantity,
+            storage::format_timestamp(r.started),
+            storage::format_timestamp(r.finished),
+            r.duration_secs(),
+            r.outcome,
+            csv_field(&r.alarms.join("; "))
+        ));
+    }
+    csv
+}
+
+/// What one check of the running order found.
+pub struct Progress {
+    pub goal_reached: bool,
+    /// False once the goal variable holds something else than the order's goal.
+    pub goal_still_set: bool,
+    pub alarms: Vec<String>,
+}
+
+/// Alarm texts of a robot, empty while it is fine.
+pub fn robot_alarms(robot_id: &str, values: &HashMap<String, SPValue>) -> Vec<String> {
+    let mut alarms = Vec::new();
+    let request_state = sp_value_to_display(values.get(&format!("{}_request_state", robot_id)));
+    if request_state == "failed" {
+        alarms.push(format!("{} command failed", robot_id));
+    }
+    let safety = sp_value_to_display(values.get(&format!("{}_safety_mode", robot_id)));
+    if safety != "-" && !safety.eq_ignore_ascii_case("normal") {
+        alarms.push(format!("{} safety mode {}", robot_id, safety));
+    }
+    alarms
+}
+
+pub async fn check_progress(
+    con: Arc<ConnectionManager>,
+    goal_variable: String,
+    goal: String,
+    robot_ids: Vec<String>,
+) -> Result<Progress, String> {
+    let mut keys = crate::goal::predicate_variables(&goal);
+    keys.push(goal_variable.clone());
+    for robot_id in &robot_ids {
+        keys.push(format!("{}_request_state", robot_id));
+        keys.push(format!("{}_safety_mode", robot_id));
+    }
+    let mut connection = con.get_connection().await;
+    let mut values = HashMap::new();
+    for key in keys {
+        if let Some(value) = StateManager::get_sp_value(&mut connection, &key).await {
+            values.insert(key, value);
+        }
+    }
+    let goal_still_set = sp_value_to_display(values.get(&goal_variable)) == goal;
+    Ok(Progress {
+        goal_reached: crate::goal::evaluate_predicate(&goal, &values)?,
+        goal_still_set,
+        alarms: robot_ids
+            .iter()
+            .flat_map(|robot_id| robot_alarms(robot_id, &values))
+            .collect(),
+    })
+}
+
+/// The order whose goal is set, with what happened so far.
+pub struct ActiveOrder {
+    pub order: Order,
+    pub goal_variable: String,
+    pub goal: String,
+    pub started: u64,
+    pub alarms: Vec<String>,
+    /// Alarms active at the last check, so a lasting alarm is kept once.
+    current_alarms: Vec<String>,
+}
+
+impl ActiveOrder {
+    pub fn new(order: Order, goal_variable: String, goal: String) -> Self {
+        Self {
+            order,
+            goal_variable,
+            goal,
+            started: storage::now_secs(),
+            alarms: Vec::new(),
+            current_alarms: Vec::new(),
+        }
+    }
+
+    /// Keeps the alarms that weren't active at the previous check, timestamped.
+    pub fn update_alarms(&mut self, alarms: Vec<String>) {
+        for alarm in &alarms {
+            if !self.current_alarms.contains(alarm) {
+                log::warn!("Order {}: {}", self.order.id, alarm);
+                self.alarms.push(format!(
+                    "{} {}",
+                    storage::format_timestamp(storage::now_secs()),
+                    alarm
+                ));
+            }
+        }
+        self.current_alarms = alarms;
+    }
+
+    /// Ends the order, writing its completion record.
+    pub fn finish(self, outcome: Outcome) -> CompletionRecord {
+        let record = CompletionRecord {
+            order_id: self.order.id,
+            program: self.order.program,
+            quantity: self.order.quantity,
+            started: self.started,
+            finished: storage::now_secs(),
+            outcome,
+            alarms: self.alarms,
+        };
+        self::record(&record);
+        record
+    }
+}
@@ -0,0 +1,223 @@
+//! Estimated schedule of the order queue, from cycle times measured on
+//! completed orders, drawn as a strip with one bar per order.
+
+use crate::order_history::{CompletionRecord, Outcome};
+use crate::orders::{Order, Priority};
+use crate::storage;
+use eframe::egui;
+use std::collections::HashMap;
+
+/// Seconds per unit for programs that never completed an order.
+const DEFAULT_CYCLE_SECS: f64 = 60.0;
+/// An order finishing closer to its due date than this is at risk.
+const AT_RISK_MARGIN_SECS: u64 = 15 * 60;
+const STRIP_HEIGHT: f32 = 56.0;
+
+/// Average seconds per unit of each program over its completed orders.
+pub fn cycle_times(history: &[CompletionRecord]) -> HashMap<String, f64> {
+    let mut totals: HashMap<String, (f64, u64)> = HashMap::new();
+    for record in history {
+        if record.outcome != Outcome::Completed || record.quantity == 0 {
+            continue;
+        }
+        let total = totals.entry(record.program.clone()).or_default();
+        total.0 += record.duration_secs() as f64;
+        total.1 += record.quantity as u64;
+    }
+    totals
+        .into_iter()
+        .map(|(program, (secs, units))| (program, secs / units as f64))
+        .collect()
+}
+
+pub struct Slot {
+    /// Index into the order list.
+    pub order: usize,
+    pub start: u64,
+    pub finish: u64,
+    /// False when no completed order of the program was measured yet.
+    pub measured: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lateness {
+    OnTime,
+    AtRisk,
+    Late,
+}
+
+impl Slot {
+    pub fn lateness(&self, order: &Order) -> Lateness {
+        match order.due {
+            Some(due) if self.finish > due => Lateness::Late,
+            Some(due) if self.finish + AT_RISK_MARGIN_SECS > due => Lateness::AtRisk,
+            _ => Lateness::OnTime,
+        }
+    }
+}
+
+/// Orders run one after the other in list order, the running one first.
+pub fn schedule(
+    orders: &[Order],
+    running: Option<(&str, u64)>,
+    now: u64,
+    cycle_times: &HashMap<String, f64>,
+) -> Vec<Slot> {
+    let estimate = |order: &Order| {
+        let cycle = cycle_times.get(&order.program);
+        let secs = cycle.copied().unwrap_or(DEFAULT_CYCLE_SECS) * order.quantity.max(1) as f64;
+        (secs.round() as u64, cycle.is_some())
+    };
+    let mut slots = Vec::with_capacity(orders.len());
+    let mut cursor = now;
+    if let Some((id, started)) = running {
+        if let Some(i) = orders.iter().position(|o| o.id == id) {
+            let (secs, measured) = estimate(&orders[i]);
+            // An order running longer than estimated is assumed to finish now
+            cursor = (started + secs).max(now);
+            slots.push(Slot {
+                order: i,
+                start: started,
+                finish: cursor,
+                measured,
+            });
+        }
+    }
+    for (i, order) in orders.iter().enumerate() {
+        if running.is_some_and(|(id, _)| id == order.id) {
+            continue;
+        }
+        let (secs, measured) = estimate(order);
+        slots.push(Slot {
+            order: i,
+            start: cursor,
+            finish: cursor + secs,
+            measured,
+        });
+        cursor += secs;
+    }
+    slots
+}
+
+pub fn priority_color(priority: Priority) -> egui::Color32 {
+    match priority {
+        Priority::Low => egui::Color32::from_rgb(90, 110, 130),
+        Priority::Normal => egui::Color32::from_rgb(60, 120, 200),
+        Priority::High => egui::Color32::from_rgb(220, 140, 40),
+        Priority::Urgent => egui::Color32::from_rgb(200, 60, 160),
+    }
+}
+
+fn lateness_stroke(lateness: Lateness) -> egui::Stroke {
+    match lateness {
+        Lateness::OnTime => egui::Stroke::new(1.0, egui::Color32::DARK_GRAY),
+        Lateness::AtRisk => egui::Stroke::new(2.5, egui::Color32::YELLOW),
+        Lateness::Late => egui::Stroke::new(2.5, egui::Color32::RED),
+    }
+}
+
+/// Gantt-like strip: bar fill is the priority, the outline the lateness, and
+/// a tick marks each due date.
+pub fn draw_queue_strip(ui: &mut egui::Ui, orders: &[Order], slots: &[Slot], now: u64) {
+    if slots.is_empty() {
+        ui.weak("The queue is empty.");
+        return;
+    }
+    let begin = slots.iter().map(|s| s.start).min().unwrap_or(now).min(now);
+    let end = slots
+        .iter()
+        .map(|s| s.finish)
+        .chain(slots.iter().filter_map(|s| orders[s.order].due))
+        .max()
+        .unwrap_or(now)
+        .max(begin + 60);
+    let width = ui.available_width();
+    let (response, painter) =
+        ui.allocate_painter(egui::vec2(width, STRIP_HEIGHT), egui::Sense::hover());
+    let rect = response.rect;
+    let bars = egui::Rect::from_min_max(rect.min, egui::pos2(rect.max.x, rect.max.y - 16.0));
+    let to_x = |t: u64| {
+        bars.min.x + (t.saturating_sub(begin) as f64 / (end - begin) as f64) as f32 * bars.width()
+    };
+    let text_color = ui.visuals().text_color();
+
+    let mut hovered = None;
+    for slot in slots {
+        let order = &orders[slot.order];
+        let bar = egui::Rect::from_min_max(
+            egui::pos2(to_x(slot.start), bars.min.y + 4.0),
+            egui::pos2(
+                to_x(slot.finish).max(to_x(slot.start) + 2.0),
+                bars.max.y - 4.0,
+            ),
+        );
+        painter.rect_filled(bar, 2.0, priority_color(order.priority));
+        painter.rect_stroke(
+            bar,
+            2.0,
+            lateness_stroke(slot.lateness(order)),
+            egui::StrokeKind::Inside,
+        );
+        if bar.width() > 40.0 {
+            painter.text(
+                bar.left_center() + egui::vec2(4.0, 0.0),
+                egui::Align2::LEFT_CENTER,
+                &order.id,
+                egui::FontId::proportional(11.0),
+                egui::Color32::WHITE,
+            );
+        }
+        if let Some(due) = order.due {
+            let x = to_x(due);
+            painter.line_segment(
+                [egui::pos2(x, bars.min.y), egui::pos2(x, bars.max.y)],
+                egui::Stroke::new(1.0, priority_color(order.priority)),
+            );
+        }
+        if response.hover_pos().is_some_and(|p| bar.contains(p)) {
+            hovered = Some(slot);
+        }
+    }
+
+    let x = to_x(now);
+    painter.line_segment(
+        [egui::pos2(x, rect.min.y), egui::pos2(x, bars.max.y)],
+        egui::Stroke::new(1.5, text_color),
+    );
+    painter.text(
+        egui::pos2(rect.min.x, rect.max.y),
+        egui::Align2::LEFT_BOTTOM,
+        storage::format_timestamp(begin),
+        egui::FontId::proportional(10.0),
+        text_color,
+    );
+    painter.text(
+        rect.max,
+        egui::Align2::RIGHT_BOTTOM,
+        storage::format_timestamp(end),
+        egui::FontId::proportional(10.0),
+        text_color,
+    );
+
+    if let Some(slot) = hovered {
+        let order = &orders[slot.order];
+        let mut text = format!(
+            "Order {} – {} ×{}\nPriority: {}\nStart: {}\nFinish: {}{}",
+            order.id,
+            order.program,
+            order.quantity,
+            order.priority,
+            storage::format_timestamp(slot.start),
+            storage::format_timestamp(slot.finish),
+            if slot.measured {
+                ""
+            } else {
+                " (no measured cycle time)"
+            }
+        );
+        if let Some(due) = order.due {
+            text.push_str(&format!("\nDue: {}", storage::format_timestamp(due)));
+        }
+        response.on_hover_text_at_pointer(text);
+    }
+}
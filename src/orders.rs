@@ -0,0 +1,133 @@
+//! Programs the station can run and the orders waiting for them. A program is
+//! a planner goal; starting an order sets the goal of its program.
+
+use crate::products::ParameterValue;
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub const ORDERS_FILE: &str = "orders.json";
+
+pub fn check_file() -> Option<Result<(), String>> {
+    storage::check_json::<OrderBook>(ORDERS_FILE)
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Program {
+    pub name: String,
+    /// Barcode printed on the program's sheet or fixture, empty for none.
+    pub code: String,
+    /// Planner goal that runs the program.
+    pub goal: String,
+    /// Markdown or HTML work instruction shown next to the controls, empty
+    /// for none.
+    pub instructions: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+    Urgent,
+}
+
+impl Priority {
+    pub const ALL: [Priority; 4] = [
+        Priority::Low,
+        Priority::Normal,
+        Priority::High,
+        Priority::Urgent,
+    ];
+}
+
+impl std::fmt::Display for Priority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Priority::Low => write!(f, "Low"),
+            Priority::Normal => write!(f, "Normal"),
+            Priority::High => write!(f, "High"),
+            Priority::Urgent => write!(f, "Urgent"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Order {
+    /// Order number, also what the order's barcode contains.
+    pub id: String,
+    pub program: String,
+    pub quantity: u32,
+    /// Product type from the catalog, empty for orders without parameters.
+    pub product: String,
+    pub parameters: BTreeMap<String, ParameterValue>,
+    pub priority: Priority,
+    /// Seconds since the unix epoch, `None` if the order has no due date.
+    pub due: Option<u64>,
+    /// Work instruction for this order, empty to use the program's.
+    pub instructions: String,
+}
+
+/// What a scanned code refers to, by index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Selection {
+    Order(usize),
+    Program(usize),
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OrderBook {
+    pub programs: Vec<Program>,
+    pub orders: Vec<Order>,
+}
+
+impl OrderBook {
+    pub fn load() -> Self {
+        storage::load_json(ORDERS_FILE).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Err(e) = storage::save_json(ORDERS_FILE, self) {
+            log::error!("Failed to save orders: {}", e);
+        }
+    }
+
+    pub fn program(&self, name: &str) -> Option<&Program> {
+        self.programs.iter().find(|p| p.name == name)
+    }
+
+    /// The order's own work instruction, else its program's.
+    pub fn instructions(&self, order: &Order) -> Option<String> {
+        if !order.instructions.is_empty() {
+            return Some(order.instructions.clone());
+        }
+        self.program(&order.program)
+            .map(|p| p.instructions.clone())
+            .filter(|path| !path.is_empty())
+    }
+
+    /// Order ids take precedence over program codes, then program names are
+    /// tried. Case and surrounding whitespace are ignored, scanners differ there.
+    pub fn find_code(&self, code: &str) -> Option<Selection> {
+        let code = code.trim();
+        if code.is_empty() {
+            return None;
+        }
+        let matches = |candidate: &str| candidate.trim().eq_ignore_ascii_case(code);
+        self.orders
+            .iter()
+            .position(|o| matches(&o.id))
+            .map(Selection::Order)
+            .or_else(|| {
+                self.programs
+                    .iter()
+                    .position(|p| matches(&p.code))
+                    .or_else(|| self.programs.iter().position(|p| matches(&p.name)))
+                    .map(Selection::Program)
+            })
+    }
+}
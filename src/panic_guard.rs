@@ -0,0 +1,120 @@
+//! Keeps a panicking tab from taking the whole controller down. The panic hook
+//! records a report with backtrace, `catch` turns the unwind into that report.
+
+use eframe::egui;
+use std::{backtrace::Backtrace, cell::RefCell, panic::AssertUnwindSafe};
+
+#[derive(Debug, Clone)]
+pub struct PanicReport {
+    pub message: String,
+    pub location: String,
+    pub backtrace: String,
+}
+
+impl PanicReport {
+    pub fn to_text(&self, context: &str) -> String {
+        format!(
+            "{} panicked at {}:\n{}\n\nBacktrace:\n{}",
+            context, self.location, self.message, self.backtrace
+        )
+    }
+}
+
+thread_local! {
+    // The hook runs on the panicking thread, so `catch` on the same thread
+    // finds its own report even when other threads panic at the same time.
+    static LAST_PANIC: RefCell<Option<PanicReport>> = const { RefCell::new(None) };
+}
+
+/// Call once at startup. The previous hook still runs, so panics keep being
+/// printed as before.
+pub fn install_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let message = info
+            .payload()
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| info.payload().downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic payload".to_string());
+        let location = info
+            .location()
+            .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()))
+            .unwrap_or_else(|| "unknown location".to_string());
+        let report = PanicReport {
+            message,
+            location,
+            backtrace: Backtrace::force_capture().to_string(),
+        };
+        LAST_PANIC.with(|last| *last.borrow_mut() = Some(report));
+        previous(info);
+    }));
+}
+
+/// Runs `f`, returning the panic report instead of unwinding further.
+pub fn catch<R>(f: impl FnOnce() -> R) -> Result<R, PanicReport> {
+    std::panic::catch_unwind(AssertUnwindSafe(f)).map_err(|_| {
+        LAST_PANIC
+            .with(|last| last.borrow_mut().take())
+            .unwrap_or_else(|| PanicReport {
+                message: "panic without report (hook not installed)".to_string(),
+                location: "unknown location".to_string(),
+                backtrace: String::new(),
+            })
+    })
+}
+
+pub enum ReportAction {
+    Retry,
+    Disable,
+}
+
+/// Error dialog for a crashed tab. Returns what the operator chose, if anything.
+pub fn draw_report_dialog(
+    ctx: &egui::Context,
+    tab_name: &str,
+    report: &PanicReport,
+) -> Option<ReportAction> {
+    let mut action = None;
+    egui::Window::new(format!("Error in {}", tab_name))
+        .id(egui::Id::new("panic_report_dialog"))
+        .collapsible(false)
+        .default_width(560.0)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(ctx, |ui| {
+            ui.label(format!(
+                "The {} tab hit an internal error and was paused. \
+                 The other tabs and the robots are not affected.",
+                tab_name
+            ));
+            ui.add_space(4.0);
+            ui.colored_label(egui::Color32::RED, &report.message);
+            ui.weak(&report.location);
+            egui::CollapsingHeader::new("Backtrace")
+                .id_salt("panic_report_backtrace")
+                .show(ui, |ui| {
+                    egui::ScrollArea::vertical()
+                        .max_height(300.0)
+                        .show(ui, |ui| {
+                            ui.monospace(&report.backtrace);
+                        });
+                });
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui.button("Copy Report").clicked() {
+                    ui.ctx().copy_text(report.to_text(tab_name));
+                }
+                if ui
+                    .button("Disable Tab")
+                    .on_hover_text("Keep the tab off until it is re-enabled")
+                    .clicked()
+                {
+                    action = Some(ReportAction::Disable);
+                }
+                if ui.button("Retry").clicked() {
+                    action = Some(ReportAction::Retry);
+                }
+            });
+        });
+    action
+}
@@ -0,0 +1,2314 @@
+use crate::aliases::{self, FrameAliases};
+use crate::cleaning::{self, WipePoint, WipeRoutine};
+use crate::expression::Unit;
+use crate::goal;
+use crate::history::{self, HistoryEntry};
+use crate::interpolation::{self, Densify};
+use crate::job_import::{self, JobRow};
+use crate::locale::{self, LocalizedDragValue};
+use crate::model_export::{self, ExportStep};
+use crate::operator_prompt::{OperatorPrompt, PromptQueue};
+use crate::pause::{PauseControl, PauseState};
+use crate::pose::{self, FrameMetadata, Pose};
+use crate::provenance::{self, Method};
+use crate::retry;
+use crate::speed_zones::{self, SpeedZones};
+use crate::state_poller::StatePoller;
+use crate::subroutines::{self, Subroutine, SubroutineCall, SubroutineLibrary};
+use crate::workers;
+use crate::workspace::{self, EnvelopeShape, Workspaces};
+use eframe::egui;
+use micro_sp::*;
+use ordered_float::OrderedFloat;
+use poll_promise::Promise;
+use rfd::FileDialog;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+/// How long a single path segment may take before execution is aborted.
+const SEGMENT_TIMEOUT: Duration = Duration::from_secs(60);
+/// How long a step waits for its wait-until predicate.
+const WAIT_TIMEOUT: Duration = Duration::from_secs(300);
+/// Upper bound on branch jumps, so a condition that never holds can't loop forever.
+const MAX_JUMPS: usize = 100;
+/// Nesting limit for subroutine calls, which also stops a subroutine calling itself.
+const MAX_CALL_DEPTH: usize = 8;
+/// Command path steps are sent as, unless they set one of the options below.
+const PATH_COMMAND: &str = "unsafe_move_l";
+/// Command of steps with a contact force threshold.
+const CONTACT_COMMAND: &str = "safe_move_l";
+/// Commands of joint space steps, without and with a force threshold.
+const JOINT_COMMAND: &str = "unsafe_move_j";
+const CONTACT_JOINT_COMMAND: &str = "safe_move_j";
+
+async fn get_all_transforms(con: Arc<ConnectionManager>) -> HashMap<String, SPTransformStamped> {
+    let mut connection = con.get_connection().await;
+    match TransformsManager::get_all_transforms(&mut connection).await {
+        Ok(tfs) => tfs,
+        Err(e) => {
+            log::error!("GUI Failed to get all transforms with: {e}!");
+            HashMap::new()
+        }
+    }
+}
+
+/// Looks up every waypoint in the view frame, skipping the ones that fail.
+async fn lookup_waypoints(
+    con: Arc<ConnectionManager>,
+    view_frame: String,
+    waypoints: Vec<String>,
+) -> HashMap<String, Pose> {
+    let mut connection = con.get_connection().await;
+    let mut poses = HashMap::new();
+    for waypoint in waypoints {
+        // Aliases are kept under their own name, that's what the steps use
+        let frame = aliases::resolve(&con, &waypoint).await;
+        match TransformsManager::lookup_transform(&mut connection, &view_frame, &frame).await {
+            Ok(tf) => {
+                poses.insert(waypoint, Pose::from_sp_transform(&tf.transform));
+            }
+            Err(e) => log::error!("GUI Failed to lookup waypoint {waypoint} with: {e}!"),
+        }
+    }
+    poses
+}
+
+/// Current values of the variables a predicate mentions.
+async fn read_predicate_values(
+    con: Arc<ConnectionManager>,
+    predicate: &str,
+) -> HashMap<String, SPValue> {
+    let mut connection = con.get_connection().await;
+    let mut values = HashMap::new();
+    for key in goal::predicate_variables(predicate) {
+        if let Some(value) = StateManager::get_sp_value(&mut connection, &key).await {
+            values.insert(key, value);
+        }
+    }
+    values
+}
+
+async fn check_predicate(con: Arc<ConnectionManager>, predicate: &str) -> Result<bool, String> {
+    let values = read_predicate_values(con, predicate).await;
+    goal::evaluate_predicate(predicate, &values)
+}
+
+async fn wait_for_predicate(con: Arc<ConnectionManager>, predicate: &str) -> Result<(), String> {
+    let start = std::time::Instant::now();
+    while !check_predicate(con.clone(), predicate).await? {
+        if start.elapsed() > WAIT_TIMEOUT {
+            return Err(format!("Timed out waiting until {}", predicate));
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    Ok(())
+}
+
+/// A segment's command together with the conditions around it.
+#[derive(Clone)]
+struct ProgramStep {
+    robot_id: String,
+    command: &'static str,
+    goal_feature_id: String,
+    state: State,
+    wait_until: Option<String>,
+    retries: u32,
+    /// Predicate checked after the step and the 0-based step to go to if it is false.
+    branch: Option<(String, usize)>,
+    /// Runs together with the previous step.
+    parallel: bool,
+    /// Asked before the move, which only starts once the operator continues.
+    prompt: Option<OperatorPrompt>,
+    /// State variable the goal is read from right before the move.
+    goal_variable: Option<String>,
+}
+
+impl ProgramStep {
+    /// Sends the step to `frame` instead of the goal it was built with.
+    fn set_goal(&mut self, frame: String) {
+        let key = format!("{}_goal_feature_id", self.robot_id);
+        if let Some(assignment) = self.state.state.get_mut(&key) {
+            assignment.val = frame.as_str().to_spvalue();
+        }
+        self.goal_feature_id = frame;
+    }
+}
+
+/// The frame a goal variable names, `None` while it is UNKNOWN or empty.
+fn goal_frame(value: Option<&SPValue>) -> Option<String> {
+    match value {
+        Some(SPValue::String(StringOrUnknown::String(frame))) if !frame.trim().is_empty() => {
+            Some(frame.trim().to_string())
+        }
+        _ => None,
+    }
+}
+
+/// A goal variable as the state poller last read it.
+pub enum GoalVariable {
+    /// Not read yet, e.g. right after it was typed in.
+    Pending,
+    /// UNKNOWN or empty, the step would fail.
+    Unknown,
+    Frame(String),
+}
+
+impl GoalVariable {
+    pub fn polled(poller: &StatePoller, key: &str) -> Self {
+        if !poller.polled(key) {
+            return GoalVariable::Pending;
+        }
+        match goal_frame(poller.get(key)) {
+            Some(frame) => GoalVariable::Frame(frame),
+            None => GoalVariable::Unknown,
+        }
+    }
+}
+
+/// Waits for the step's precondition and operator prompt, then sends its
+/// command, retrying on failure. A goal variable is read last and fails the
+/// step while it is UNKNOWN. The step goes into the history once it ran.
+/// Returns how many retries it took.
+async fn run_step(
+    con: Arc<ConnectionManager>,
+    prompts: Arc<PromptQueue>,
+    pause: Arc<PauseControl>,
+    index: usize,
+    mut step: ProgramStep,
+) -> Result<u32, String> {
+    if let Some(predicate) = &step.wait_until {
+        wait_for_predicate(con.clone(), predicate).await?;
+    }
+    if let Some(prompt) = step.prompt.clone() {
+        prompts.ask(index, prompt).await?;
+    }
+    crate::backend::check_allowed(con.clone(), &step.robot_id).await?;
+    if let Some(variable) = step.goal_variable.clone() {
+        let value = crate::backend::get_value(con.clone(), &variable).await;
+        let frame = goal_frame(value.as_ref())
+            .ok_or_else(|| format!("Goal variable {} is UNKNOWN", variable))?;
+        log::info!("Goal variable {} resolved to {}", variable, frame);
+        step.set_goal(frame);
+    }
+    let mut policy = retry::policy_for(step.command);
+    if step.retries > 0 {
+        // Only the count is set on the segment, backoff and error classes are configured
+        policy.max_retries = step.retries;
+    }
+    if pause.is_cancelled() {
+        return Err("Stopped".to_string());
+    }
+    let entry = HistoryEntry::now(&step.robot_id, step.command, Some(&step.goal_feature_id));
+    let retries = retry::send_with_retry(
+        &step.state,
+        con,
+        &entry,
+        &policy,
+        SEGMENT_TIMEOUT,
+        pause.cancelled(),
+    )
+    .await
+    .map_err(|(e, retries)| match retries {
+        0 => e,
+        _ => format!("{} (after {} retries)", e, retries),
+    })?;
+    history::record(&entry);
+    Ok(retries)
+}
+
+/// Index after the last step that runs in parallel with the one at `start`.
+fn group_end(steps: &[ProgramStep], start: usize) -> usize {
+    let mut end = start + 1;
+    while steps.get(end).is_some_and(|s| s.parallel) {
+        end += 1;
+    }
+    end
+}
+
+/// Runs the steps as MoveL commands, waiting for each to finish and evaluating
+/// the step conditions against the live state in between. Parallel steps are
+/// started together and joined before the program continues. A pause holds
+/// the program before the next group of steps, a stop ends it there.
+async fn execute_path(
+    con: Arc<ConnectionManager>,
+    prompts: Arc<PromptQueue>,
+    pause: Arc<PauseControl>,
+    steps: Vec<ProgramStep>,
+) -> Result<String, String> {
+    let count = steps.len();
+    let context = |i: usize, e: String| format!("Step {} of {}: {}", i + 1, count, e);
+    let mut executed = 0;
+    let mut retries = 0;
+    let mut jumps = 0;
+    let mut i = 0;
+    while i < count {
+        pause.hold_before(i).await;
+        if pause.is_cancelled() {
+            return Err(context(i, format!("Stopped after {} steps", executed)));
+        }
+        let end = group_end(&steps, i);
+        let tasks = (i..end)
+            .map(|j| {
+                let task = run_step(
+                    con.clone(),
+                    prompts.clone(),
+                    pause.clone(),
+                    j,
+                    steps[j].clone(),
+                );
+                (j, tokio::spawn(task))
+            })
+            .collect::<Vec<_>>();
+        // Join barrier: every step of the group finishes before anything else happens
+        let mut failures = Vec::new();
+        for (j, task) in tasks {
+            match task.await {
+                Ok(Ok(step_retries)) => {
+                    executed += 1;
+                    retries += step_retries;
+                }
+                Ok(Err(e)) => failures.push(context(j, e)),
+                Err(e) => failures.push(context(j, e.to_string())),
+            }
+        }
+        if !failures.is_empty() {
+            return Err(failures.join("; "));
+        }
+
+        let mut next = end;
+        for j in i..end {
+            let Some((predicate, target)) = &steps[j].branch else {
+                continue;
+            };
+            if !check_predicate(con.clone(), predicate)
+                .await
+                .map_err(|e| context(j, e))?
+            {
+                jumps += 1;
+                if jumps > MAX_JUMPS {
+                    return Err(context(
+                        j,
+                        format!(
+                            "Gave up after {} jumps, '{}' never held",
+                            MAX_JUMPS, predicate
+                        ),
+                    ));
+                }
+                next = *target;
+                break;
+            }
+        }
+        i = next;
+    }
+    Ok(match retries {
+        0 => format!("Executed {} steps", executed),
+        _ => format!("Executed {} steps, {} retries", executed, retries),
+    })
+}
+
+/// One waypoint of a path and the motion used to reach it, or a subroutine call.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PathSegment {
+    goal_feature_id: String,
+    velocity: f64,
+    acceleration: f64,
+    blend_radius: f64,
+    /// Predicate to wait for before the move, empty for none.
+    wait_until: String,
+    /// How often a failed move is sent again before execution stops.
+    retries: u32,
+    /// Predicate checked after the move, empty for none.
+    check: String,
+    /// 1-based step to continue at when `check` is false.
+    goto_step: usize,
+    /// Resource that executes the step, empty for the path's robot.
+    robot_id: String,
+    /// Runs together with the previous step, which must target another resource.
+    parallel: bool,
+    /// Set for steps that run a subroutine instead of a single move.
+    call: Option<SubroutineCall>,
+    /// Pauses before the move until the operator continues.
+    prompt: Option<OperatorPrompt>,
+    /// Contact force in N the move stops at, sent as a safe move when set.
+    force_threshold: Option<f64>,
+    /// Moves in joint space instead of linearly.
+    joint_move: bool,
+    /// State variable naming the goal frame when the step runs, empty for the
+    /// fixed goal. The fixed goal is still what the view and checks use.
+    goal_variable: String,
+}
+
+impl Default for PathSegment {
+    fn default() -> Self {
+        Self::new(String::new())
+    }
+}
+
+impl PathSegment {
+    fn new(goal_feature_id: String) -> Self {
+        Self {
+            goal_feature_id,
+            velocity: 0.1,
+            acceleration: 0.1,
+            blend_radius: 0.0,
+            wait_until: String::new(),
+            retries: 0,
+            check: String::new(),
+            goto_step: 1,
+            robot_id: String::new(),
+            parallel: false,
+            call: None,
+            prompt: None,
+            force_threshold: None,
+            joint_move: false,
+            goal_variable: String::new(),
+        }
+    }
+
+    fn call(name: &str, parameters: &[String]) -> Self {
+        Self {
+            call: Some(SubroutineCall {
+                name: name.to_string(),
+                bindings: parameters.iter().map(|p| (p.clone(), p.clone())).collect(),
+            }),
+            ..Self::new(String::new())
+        }
+    }
+
+    fn robot_id<'a>(&'a self, path_robot_id: &'a str) -> &'a str {
+        match self.robot_id.trim() {
+            "" => path_robot_id,
+            robot_id => robot_id,
+        }
+    }
+
+    fn command(&self) -> &'static str {
+        match (self.force_threshold, self.joint_move) {
+            (None, false) => PATH_COMMAND,
+            (Some(_), false) => CONTACT_COMMAND,
+            (None, true) => JOINT_COMMAND,
+            (Some(_), true) => CONTACT_JOINT_COMMAND,
+        }
+    }
+
+    fn has_conditions(&self) -> bool {
+        !self.wait_until.trim().is_empty()
+            || self.retries > 0
+            || !self.check.trim().is_empty()
+            || self.prompt.is_some()
+            || !self.goal_variable.trim().is_empty()
+    }
+
+    /// Short description for the segment list.
+    fn conditions_summary(&self) -> String {
+        let mut parts = Vec::new();
+        if self.prompt.is_some() {
+            parts.push("⚠ prompt".to_string());
+        }
+        if !self.goal_variable.trim().is_empty() {
+            parts.push(format!("goal ← {}", self.goal_variable.trim()));
+        }
+        if !self.wait_until.trim().is_empty() {
+            parts.push("wait".to_string());
+        }
+        if self.retries > 0 {
+            parts.push(format!("retry ×{}", self.retries));
+        }
+        if !self.check.trim().is_empty() {
+            parts.push(format!("else → {}", self.goto_step));
+        }
+        if let Some(force) = self.force_threshold {
+            parts.push(format!("≤ {} N", locale::format_number(force, 1)));
+        }
+        if self.joint_move {
+            parts.push("MoveJ".to_string());
+        }
+        if parts.is_empty() {
+            "-".to_string()
+        } else {
+            parts.join(", ")
+        }
+    }
+
+    /// Syntax problems of the predicates, checked without live values.
+    fn validate_conditions(&self, segments: &[PathSegment]) -> Option<String> {
+        if self
+            .prompt
+            .as_ref()
+            .is_some_and(|prompt| prompt.message.trim().is_empty())
+        {
+            return Some("Operator prompt: message is empty".to_string());
+        }
+        if self.goal_variable.trim().contains(char::is_whitespace) {
+            return Some("Goal variable: a variable name has no spaces".to_string());
+        }
+        for (name, predicate) in [("Wait until", &self.wait_until), ("Check", &self.check)] {
+            if predicate.trim().is_empty() {
+                continue;
+            }
+            if let Err(e) = goal::evaluate_predicate(predicate, &HashMap::new()) {
+                return Some(format!("{}: {}", name, e));
+            }
+        }
+        if !self.check.trim().is_empty() {
+            match segments.get(self.goto_step.wrapping_sub(1)) {
+                None => return Some(format!("Step {} does not exist", self.goto_step)),
+                Some(target) if target.parallel => {
+                    return Some(format!(
+                        "Step {} runs in parallel with the step before it, jump to the first \
+                         step of the group",
+                        self.goto_step
+                    ));
+                }
+                Some(_) => (),
+            }
+        }
+        None
+    }
+}
+
+/// Turns steps of a path into a subroutine, every frame they use becoming a
+/// parameter of the same name.
+fn make_subroutine(steps: &[PathSegment], first_step: usize) -> Subroutine {
+    let mut parameters: Vec<String> = Vec::new();
+    let mut parameterize = |frame: &mut String| {
+        if frame.is_empty() || frame.starts_with(subroutines::PARAMETER_PREFIX) {
+            return;
+        }
+        if !parameters.contains(frame) {
+            parameters.push(frame.clone());
+        }
+        *frame = format!("{}{}", subroutines::PARAMETER_PREFIX, frame);
+    };
+    let steps = steps
+        .iter()
+        .enumerate()
+        .map(|(i, step)| {
+            let mut step = step.clone();
+            // There is no step before the first one inside the subroutine
+            step.parallel &= i > 0;
+            parameterize(&mut step.goal_feature_id);
+            if let Some(call) = &mut step.call {
+                for frame in call.bindings.values_mut() {
+                    parameterize(frame);
+                }
+            }
+            if !step.check.trim().is_empty() {
+                step.goto_step -= first_step - 1;
+            }
+            step
+        })
+        .collect();
+    Subroutine { parameters, steps }
+}
+
+/// State of the "Save as Subroutine…" dialog, steps are 1-based and inclusive.
+struct SaveSubroutineDialog {
+    name: String,
+    from: usize,
+    to: usize,
+}
+
+impl SaveSubroutineDialog {
+    fn validate(&self, segments: &[PathSegment]) -> Option<String> {
+        let name = self.name.trim();
+        if name.is_empty() {
+            return Some("Name is empty".to_string());
+        }
+        if self.from < 1 || self.from > self.to || self.to > segments.len() {
+            return Some("Invalid step range".to_string());
+        }
+        for (i, segment) in segments[self.from - 1..self.to].iter().enumerate() {
+            if segment.call.as_ref().is_some_and(|call| call.name == name) {
+                return Some("A subroutine can't call itself".to_string());
+            }
+            let branches_out = !segment.check.trim().is_empty()
+                && !(self.from..=self.to).contains(&segment.goto_step);
+            if branches_out {
+                return Some(format!(
+                    "Step {} branches to a step outside the range",
+                    self.from + i
+                ));
+            }
+        }
+        None
+    }
+}
+
+/// Replaces subroutine calls by their steps with the parameters bound, and
+/// renumbers branch targets to the expanded list.
+fn expand_segments(
+    segments: &[PathSegment],
+    library: &SubroutineLibrary,
+    depth: usize,
+) -> Result<Vec<PathSegment>, String> {
+    if depth > MAX_CALL_DEPTH {
+        return Err(format!(
+            "Subroutines nested deeper than {} calls",
+            MAX_CALL_DEPTH
+        ));
+    }
+    let mut blocks = Vec::with_capacity(segments.len());
+    for segment in segments {
+        let Some(call) = &segment.call else {
+            blocks.push(vec![segment.clone()]);
+            continue;
+        };
+        let subroutine = library
+            .get(&call.name)
+            .ok_or_else(|| format!("Unknown subroutine '{}'", call.name))?;
+        let bind = |frame: &str| match frame.strip_prefix(subroutines::PARAMETER_PREFIX) {
+            Some(parameter) => call.binding(parameter).to_string(),
+            None => frame.to_string(),
+        };
+        let bound = subroutine
+            .steps
+            .iter()
+            .map(|step| {
+                let mut step = step.clone();
+                step.goal_feature_id = bind(&step.goal_feature_id);
+                if let Some(inner) = &mut step.call {
+                    for frame in inner.bindings.values_mut() {
+                        *frame = bind(frame);
+                    }
+                }
+                // A resource set on the call applies to the steps that don't name one
+                if step.robot_id.trim().is_empty() {
+                    step.robot_id = segment.robot_id.clone();
+                }
+                step
+            })
+            .collect::<Vec<PathSegment>>();
+        blocks.push(
+            expand_segments(&bound, library, depth + 1)
+                .map_err(|e| format!("{}: {}", call.name, e))?,
+        );
+    }
+
+    let mut starts = Vec::with_capacity(blocks.len());
+    let mut start = 0;
+    for block in &blocks {
+        starts.push(start);
+        start += block.len();
+    }
+    let mut expanded = Vec::with_capacity(start);
+    for (i, (segment, block)) in segments.iter().zip(blocks).enumerate() {
+        for mut step in block {
+            if !step.check.trim().is_empty() {
+                step.goto_step = if segment.call.is_some() {
+                    // Targets inside a subroutine are relative to its own steps
+                    step.goto_step + starts[i]
+                } else {
+                    starts
+                        .get(step.goto_step.wrapping_sub(1))
+                        .map(|start| start + 1)
+                        .unwrap_or(step.goto_step)
+                };
+            }
+            expanded.push(step);
+        }
+    }
+    Ok(expanded)
+}
+
+/// Parallel steps have to target different resources, one command per robot at a time.
+fn validate_parallel_groups(segments: &[PathSegment], path_robot_id: &str) -> Option<String> {
+    let mut group: Vec<&str> = Vec::new();
+    for (i, segment) in segments.iter().enumerate() {
+        let robot_id = segment.robot_id(path_robot_id);
+        if !segment.parallel || i == 0 {
+            group.clear();
+        } else if group.contains(&robot_id) {
+            return Some(format!(
+                "Step {} runs in parallel with another step of {}",
+                i + 1,
+                robot_id
+            ));
+        }
+        group.push(robot_id);
+    }
+    None
+}
+
+pub struct PathTab {
+    robot_id_input: String,
+    resource_enabled: bool,
+    /// Why a path can't be executed right now, e.g. an open cell guard.
+    command_blocked: Option<String>,
+    get_all_transforms_promise: Option<Promise<HashMap<String, SPTransformStamped>>>,
+    transform_keys: Vec<String>,
+    /// Offered as waypoints next to the frames.
+    aliases: FrameAliases,
+    selected_tcp: Option<String>,
+    selected_faceplate: Option<String>,
+    selected_baseframe: Option<String>,
+    view_frame: Option<String>,
+    waypoint_to_add: Option<String>,
+    segments: Vec<PathSegment>,
+    /// Segment whose conditions are shown in the editor below the list.
+    editing_conditions: Option<usize>,
+    subroutines: SubroutineLibrary,
+    subroutine_to_insert: Option<String>,
+    save_subroutine_dialog: Option<SaveSubroutineDialog>,
+    lookup_promise: Option<Promise<HashMap<String, Pose>>>,
+    waypoint_poses: HashMap<String, Pose>,
+    /// Height of frames created from the view, in the view frame.
+    table_height: f64,
+    /// Where the view was right-clicked, in the view frame.
+    view_click: Option<[f64; 2]>,
+    place_promise: Option<Promise<Result<(String, Pose), String>>>,
+    workspaces: Workspaces,
+    speed_zones: SpeedZones,
+    execute_promise: Option<Promise<Result<String, String>>>,
+    execute_status: Option<Result<String, String>>,
+    prompts: Arc<PromptQueue>,
+    pause: Arc<PauseControl>,
+    wipe: WipeRoutine,
+    wipe_promise: Option<Promise<Result<Vec<WipePoint>, String>>>,
+    densify: Densify,
+    /// The published frames and the end frame they lead to.
+    densify_promise: Option<Promise<Result<(Vec<String>, String), String>>>,
+    import_promise: Option<Promise<Result<Vec<JobRow>, String>>>,
+    /// Problems of the last job list that wasn't loaded, one per line.
+    import_errors: Vec<String>,
+    /// Live frames named by the steps' goal variables.
+    goal_variables: HashMap<String, GoalVariable>,
+}
+
+impl PathTab {
+    pub fn new() -> Self {
+        Self {
+            robot_id_input: "r1".to_string(),
+            resource_enabled: true,
+            command_blocked: None,
+            get_all_transforms_promise: None,
+            transform_keys: Vec::new(),
+            aliases: FrameAliases::new(),
+            selected_tcp: None,
+            selected_faceplate: Some("tool0".to_string()),
+            selected_baseframe: Some("base_link".to_string()),
+            view_frame: Some("world".to_string()),
+            waypoint_to_add: None,
+            segments: Vec::new(),
+            editing_conditions: None,
+            subroutines: SubroutineLibrary::load(),
+            subroutine_to_insert: None,
+            save_subroutine_dialog: None,
+            lookup_promise: None,
+            waypoint_poses: HashMap::new(),
+            table_height: 0.0,
+            view_click: None,
+            place_promise: None,
+            workspaces: Workspaces::load(),
+            speed_zones: SpeedZones::load(),
+            execute_promise: None,
+            execute_status: None,
+            prompts: Arc::new(PromptQueue::default()),
+            pause: Arc::new(PauseControl::default()),
+            wipe: WipeRoutine::new(),
+            wipe_promise: None,
+            densify: Densify::new(),
+            densify_promise: None,
+            import_promise: None,
+            import_errors: Vec::new(),
+            goal_variables: HashMap::new(),
+        }
+    }
+
+    /// Operator prompts of a running path, drawn whatever tab is open.
+    pub fn draw_operator_prompt(&self, ctx: &egui::Context) {
+        self.prompts.draw(ctx);
+    }
+
+    pub fn robot_id(&self) -> &str {
+        &self.robot_id_input
+    }
+
+    pub fn set_robot_id(&mut self, robot_id: &str) {
+        self.robot_id_input = robot_id.to_string();
+    }
+
+    /// Replaces the path with one segment per waypoint, in order.
+    pub fn load_waypoints(&mut self, waypoints: Vec<String>) {
+        self.segments = waypoints.into_iter().map(PathSegment::new).collect();
+        self.editing_conditions = None;
+        self.waypoint_poses.clear();
+    }
+
+    /// Goal variables of the steps, watched for the preview in the editor.
+    pub fn goal_variable_keys(&self) -> Vec<String> {
+        self.segments
+            .iter()
+            .map(|segment| segment.goal_variable.trim().to_string())
+            .filter(|variable| !variable.is_empty())
+            .collect()
+    }
+
+    pub fn set_goal_variables(&mut self, values: HashMap<String, GoalVariable>) {
+        self.goal_variables = values;
+    }
+
+    /// Disabled resources (under maintenance) don't accept commands from the GUI.
+    pub fn set_resource_enabled(&mut self, enabled: bool) {
+        self.resource_enabled = enabled;
+    }
+
+    /// Blocks Execute Path while set, with the reason shown.
+    pub fn set_command_blocked(&mut self, reason: Option<String>) {
+        self.command_blocked = reason;
+    }
+
+    pub fn ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+    ) {
+        let is_executing = self.execute_promise.is_some();
+        ui.horizontal(|ui| {
+            ui.heading("Path Editor");
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                let disabled_reason = if !self.resource_enabled {
+                    "This robot is disabled in the Resources tab.".to_string()
+                } else if let Some(reason) = &self.command_blocked {
+                    reason.clone()
+                } else {
+                    "Add waypoints first.".to_string()
+                };
+                if ui
+                    .add_enabled(
+                        self.resource_enabled
+                            && self.command_blocked.is_none()
+                            && !is_executing
+                            && !self.segments.is_empty(),
+                        egui::Button::new("Execute Path"),
+                    )
+                    .on_disabled_hover_text(disabled_reason)
+                    .clicked()
+                {
+                    self.spawn_execute_promise(handle, connection);
+                }
+                if is_executing {
+                    self.draw_pause_controls(ui);
+                }
+                if ui
+                    .add_enabled(
+                        !self.segments.is_empty(),
+                        egui::Button::new("Export as micro_sp…"),
+                    )
+                    .on_hover_text("Save the path as micro_sp operations for the model")
+                    .clicked()
+                {
+                    self.export_operations();
+                }
+                if ui
+                    .add_enabled(
+                        !is_executing && self.import_promise.is_none(),
+                        egui::Button::new("Import Jobs…"),
+                    )
+                    .on_hover_text(
+                        "Replace the path with a CSV job list, one move per row:\n\
+                         goal,parent_frame_id,x,y,z,qx,qy,qz,qw,velocity,acceleration,\n\
+                         command_type,force_threshold. Rows with a pose publish the goal.",
+                    )
+                    .clicked()
+                {
+                    self.import_jobs(handle, connection);
+                }
+                let text_box =
+                    egui::TextEdit::singleline(&mut self.robot_id_input).desired_width(50.0);
+                ui.add(text_box);
+                ui.label("Robot ID:");
+            });
+        });
+        ui.separator();
+
+        self.poll_execute_promise();
+        self.poll_import_promise();
+        match &self.execute_status {
+            Some(Ok(msg)) => {
+                ui.colored_label(egui::Color32::GREEN, msg);
+            }
+            Some(Err(e)) => {
+                ui.colored_label(egui::Color32::RED, format!("Error: {}", e));
+            }
+            None => (),
+        }
+        if !self.import_errors.is_empty() {
+            egui::ScrollArea::vertical()
+                .id_salt("path_import_errors")
+                .max_height(120.0)
+                .show(ui, |ui| {
+                    for error in &self.import_errors {
+                        ui.colored_label(egui::Color32::YELLOW, error);
+                    }
+                });
+            if ui.button("Dismiss").clicked() {
+                self.import_errors.clear();
+            }
+        }
+
+        ui.horizontal(|ui| {
+            let is_fetching_list = self.poll_transforms_promise(ui);
+            if !is_fetching_list && ui.button("Fetch Transforms").clicked() {
+                self.spawn_transforms_promise(handle, connection);
+            }
+            if is_fetching_list {
+                ui.label("Loading...");
+            }
+        });
+        draw_frame_selector(
+            ui,
+            "TCP ID:",
+            "path_tcp_select",
+            &mut self.selected_tcp,
+            &self.transform_keys,
+        );
+        draw_frame_selector(
+            ui,
+            "Faceplate ID:",
+            "path_faceplate_select",
+            &mut self.selected_faceplate,
+            &self.transform_keys,
+        );
+        draw_frame_selector(
+            ui,
+            "Baseframe ID:",
+            "path_baseframe_select",
+            &mut self.selected_baseframe,
+            &self.transform_keys,
+        );
+
+        ui.separator();
+        self.aliases.poll();
+        let waypoints = self
+            .transform_keys
+            .iter()
+            .chain(self.aliases.names())
+            .cloned()
+            .collect::<Vec<String>>();
+        ui.horizontal(|ui| {
+            draw_frame_selector(
+                ui,
+                "Waypoint:",
+                "path_waypoint_select",
+                &mut self.waypoint_to_add,
+                &waypoints,
+            );
+            ui.add_enabled_ui(self.waypoint_to_add.is_some(), |ui| {
+                if ui.button("Add").clicked() {
+                    if let Some(waypoint) = self.waypoint_to_add.clone() {
+                        self.segments.push(PathSegment::new(waypoint));
+                    }
+                }
+            });
+        });
+        self.draw_subroutine_controls(ui);
+        self.poll_wipe_promise();
+        egui::CollapsingHeader::new("Cleaning Routine")
+            .id_salt("path_wipe_routine")
+            .show(ui, |ui| {
+                let busy = self.wipe_promise.is_some();
+                if self.wipe.draw(ui, &self.transform_keys, busy) {
+                    self.spawn_wipe_promise(handle, connection);
+                }
+            });
+        self.poll_densify_promise();
+        egui::CollapsingHeader::new("Interpolate Between Frames")
+            .id_salt("path_densify")
+            .show(ui, |ui| {
+                let busy = self.densify_promise.is_some();
+                if self.densify.draw(ui, &self.transform_keys, busy) {
+                    self.spawn_densify_promise(handle, connection);
+                }
+            });
+
+        ui.add_enabled_ui(!is_executing, |ui| {
+            self.draw_segment_list(ui);
+        });
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            draw_frame_selector(
+                ui,
+                "View Frame:",
+                "path_view_frame_select",
+                &mut self.view_frame,
+                &self.transform_keys,
+            );
+            let is_looking_up = self.poll_lookup_promise();
+            if ui
+                .add_enabled(
+                    !is_looking_up && self.view_frame.is_some(),
+                    egui::Button::new("Refresh View"),
+                )
+                .clicked()
+            {
+                self.spawn_lookup_promise(handle, connection);
+            }
+            if is_looking_up {
+                ui.spinner();
+            }
+            ui.separator();
+            ui.label("Table height:");
+            ui.add(
+                egui::DragValue::new(&mut self.table_height)
+                    .localized()
+                    .speed(0.001)
+                    .suffix(" m"),
+            )
+            .on_hover_text("Z of frames created by right-clicking the view");
+        });
+        egui::CollapsingHeader::new(format!("Workspace boundary of {}", self.robot_id_input))
+            .id_salt("path_workspace")
+            .show(ui, |ui| {
+                self.workspaces
+                    .draw_editor(ui, &self.robot_id_input, &self.transform_keys);
+            });
+        egui::CollapsingHeader::new("Reduced Speed Zones")
+            .id_salt("path_speed_zones")
+            .show(ui, |ui| {
+                self.speed_zones.draw_editor(ui, &self.transform_keys);
+            });
+        for warning in self
+            .reach_warnings()
+            .into_iter()
+            .chain(self.zone_warnings())
+        {
+            ui.colored_label(egui::Color32::YELLOW, format!("⚠ {}", warning));
+        }
+        self.poll_place_promise();
+        if let Some(point) = self.draw_path_view(ui) {
+            self.spawn_place_promise(handle, connection, point);
+        }
+    }
+
+    fn draw_segment_list(&mut self, ui: &mut egui::Ui) {
+        let mut move_up = None;
+        let mut remove = None;
+        let mut edit_conditions = None;
+        let path_robot_id = self.robot_id_input.clone();
+        egui::Grid::new("path_segments_grid")
+            .num_columns(9)
+            .spacing([10.0, 4.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("#");
+                ui.strong("∥").on_hover_text(
+                    "Run together with the previous step. The group finishes \n\
+                     completely before the next step starts.",
+                );
+                ui.strong("Resource");
+                ui.strong("Goal Feature ID");
+                ui.strong("Velocity");
+                ui.strong("Acceleration");
+                ui.strong("Blend Radius");
+                ui.strong("Conditions");
+                ui.label("");
+                ui.end_row();
+                for (i, segment) in self.segments.iter_mut().enumerate() {
+                    ui.label(format!("{}", i + 1));
+                    ui.add_enabled(
+                        i > 0 && segment.call.is_none(),
+                        egui::Checkbox::without_text(&mut segment.parallel),
+                    );
+                    ui.add(
+                        egui::TextEdit::singleline(&mut segment.robot_id)
+                            .hint_text(path_robot_id.as_str())
+                            .desired_width(50.0),
+                    );
+                    if let Some(call) = &segment.call {
+                        // The subroutine's own steps carry motion and conditions
+                        ui.label(call.summary());
+                        ui.label("-");
+                        ui.label("-");
+                        ui.label("-");
+                        let editing = self.editing_conditions == Some(i);
+                        if ui
+                            .selectable_label(editing, "Frames")
+                            .on_hover_text("Frames bound to the subroutine's parameters")
+                            .clicked()
+                        {
+                            edit_conditions = Some((!editing).then_some(i));
+                        }
+                        ui.horizontal(|ui| {
+                            if ui.add_enabled(i > 0, egui::Button::new("⏶")).clicked() {
+                                move_up = Some(i);
+                            }
+                            if ui.button("🗑").clicked() {
+                                remove = Some(i);
+                            }
+                        });
+                        ui.end_row();
+                        continue;
+                    }
+                    ui.label(&segment.goal_feature_id);
+                    ui.add(
+                        egui::DragValue::new(&mut segment.velocity)
+                            .localized()
+                            .suffix(" m/s")
+                            .speed(0.01)
+                            .range(0.0..=1.0),
+                    );
+                    ui.add(
+                        egui::DragValue::new(&mut segment.acceleration)
+                            .localized()
+                            .suffix(" m/s²")
+                            .speed(0.01)
+                            .range(0.0..=1.0),
+                    );
+                    ui.add(
+                        egui::DragValue::new(&mut segment.blend_radius)
+                            .localized_in(Unit::Meter)
+                            .suffix(" m")
+                            .speed(0.001)
+                            .range(0.0..=0.5),
+                    );
+                    let editing = self.editing_conditions == Some(i);
+                    if ui
+                        .selectable_label(editing, segment.conditions_summary())
+                        .on_hover_text(
+                            "Operator prompt, wait, retry and branch conditions of this step",
+                        )
+                        .clicked()
+                    {
+                        edit_conditions = Some((!editing).then_some(i));
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.add_enabled(i > 0, egui::Button::new("⏶")).clicked() {
+                            move_up = Some(i);
+                        }
+                        if ui.button("🗑").clicked() {
+                            remove = Some(i);
+                        }
+                    });
+                    ui.end_row();
+                }
+            });
+        if let Some(editing) = edit_conditions {
+            self.editing_conditions = editing;
+        }
+        if let Some(i) = move_up {
+            self.segments.swap(i - 1, i);
+            self.editing_conditions = None;
+        }
+        if let Some(i) = remove {
+            self.segments.remove(i);
+            self.editing_conditions = None;
+        }
+        self.draw_conditions_editor(ui);
+        if let Some(problem) = validate_parallel_groups(&self.segments, &self.robot_id_input) {
+            ui.colored_label(egui::Color32::YELLOW, problem);
+        }
+        if self.segments.last().is_some_and(|s| s.blend_radius > 0.0) {
+            ui.colored_label(
+                egui::Color32::YELLOW,
+                "The last waypoint's blend radius is ignored, the robot stops there.",
+            );
+        }
+    }
+
+    fn draw_subroutine_controls(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Subroutine:");
+            egui::ComboBox::from_id_salt("path_subroutine_select")
+                .selected_text(self.subroutine_to_insert.as_deref().unwrap_or("Select..."))
+                .show_ui(ui, |ui| {
+                    for name in self.subroutines.names() {
+                        ui.selectable_value(
+                            &mut self.subroutine_to_insert,
+                            Some(name.clone()),
+                            name,
+                        );
+                    }
+                });
+            let selected = self
+                .subroutine_to_insert
+                .as_deref()
+                .and_then(|name| self.subroutines.get(name).map(|s| (name, s)));
+            let mut insert = None;
+            let mut delete = None;
+            ui.add_enabled_ui(selected.is_some(), |ui| {
+                if ui.button("Insert").clicked() {
+                    insert = selected.map(|(name, s)| PathSegment::call(name, &s.parameters));
+                }
+                if ui
+                    .button("Delete")
+                    .on_hover_text("Remove from the library. Paths calling it can't run anymore.")
+                    .clicked()
+                {
+                    delete = selected.map(|(name, _)| name.to_string());
+                }
+            });
+            if let Some(segment) = insert {
+                self.segments.push(segment);
+            }
+            if let Some(name) = delete {
+                self.subroutines.remove(&name);
+                self.subroutine_to_insert = None;
+            }
+            if ui
+                .add_enabled(
+                    !self.segments.is_empty(),
+                    egui::Button::new("Save Steps as Subroutine…"),
+                )
+                .clicked()
+            {
+                self.save_subroutine_dialog = Some(SaveSubroutineDialog {
+                    name: String::new(),
+                    from: 1,
+                    to: self.segments.len(),
+                });
+            }
+        });
+        self.draw_save_subroutine_dialog(ui.ctx());
+    }
+
+    fn draw_save_subroutine_dialog(&mut self, ctx: &egui::Context) {
+        let Some(dialog) = &mut self.save_subroutine_dialog else {
+            return;
+        };
+        let mut open = true;
+        let mut save = false;
+        let mut cancel = false;
+        let step_count = self.segments.len();
+        let validation = dialog.validate(&self.segments);
+        let overwrites = self.subroutines.get(dialog.name.trim()).is_some();
+
+        egui::Window::new("Save as Subroutine")
+            .id(egui::Id::new("save_subroutine_window"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Name:");
+                    ui.text_edit_singleline(&mut dialog.name);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Steps:");
+                    ui.add(egui::DragValue::new(&mut dialog.from).range(1..=step_count));
+                    ui.label("to");
+                    ui.add(egui::DragValue::new(&mut dialog.to).range(1..=step_count));
+                });
+                ui.weak(
+                    "Every frame the steps use becomes a parameter that \
+                     each call binds to a frame.",
+                );
+                if overwrites {
+                    ui.colored_label(
+                        egui::Color32::YELLOW,
+                        "Replaces the existing subroutine, paths calling it change too.",
+                    );
+                }
+                if let Some(reason) = &validation {
+                    ui.colored_label(egui::Color32::YELLOW, reason);
+                }
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(validation.is_none(), egui::Button::new("Save"))
+                        .clicked()
+                    {
+                        save = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if save {
+            let subroutine =
+                make_subroutine(&self.segments[dialog.from - 1..dialog.to], dialog.from);
+            let name = dialog.name.trim().to_string();
+            self.subroutines.insert(&name, subroutine);
+            self.subroutine_to_insert = Some(name);
+        }
+        if !open || cancel || save {
+            self.save_subroutine_dialog = None;
+        }
+    }
+
+    fn draw_conditions_editor(&mut self, ui: &mut egui::Ui) {
+        let step_count = self.segments.len();
+        let Some(i) = self.editing_conditions else {
+            return;
+        };
+        let problem = self
+            .segments
+            .get(i)
+            .and_then(|segment| segment.validate_conditions(&self.segments));
+        let Some(segment) = self.segments.get_mut(i) else {
+            self.editing_conditions = None;
+            return;
+        };
+        if let Some(call) = &mut segment.call {
+            draw_bindings_editor(ui, i, call, &self.subroutines, &self.transform_keys);
+            return;
+        }
+        egui::Frame::group(ui.style()).show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.strong(format!("Step {} ({})", i + 1, segment.goal_feature_id));
+                ui.label("ℹ").on_hover_text(
+                    "Predicates use the Planner Goal syntax and are evaluated against \n\
+                     the live state, e.g. gripper_part_detected == true && r1_safety_mode == normal",
+                );
+            });
+            egui::Grid::new("path_conditions_grid")
+                .num_columns(2)
+                .spacing([10.0, 4.0])
+                .show(ui, |ui| {
+                    ui.label("Goal from variable:");
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            egui::TextEdit::singleline(&mut segment.goal_variable)
+                                .font(egui::TextStyle::Monospace)
+                                .hint_text("e.g. vision_best_pick_frame")
+                                .desired_width(220.0),
+                        );
+                        let variable = segment.goal_variable.trim();
+                        if variable.is_empty() {
+                            return;
+                        }
+                        match self.goal_variables.get(variable) {
+                            Some(GoalVariable::Frame(frame))
+                                if self.transform_keys.contains(frame) =>
+                            {
+                                ui.label(format!("→ {}", frame));
+                            }
+                            Some(GoalVariable::Frame(frame)) => {
+                                ui.colored_label(
+                                    egui::Color32::YELLOW,
+                                    format!("→ {} (not a known frame)", frame),
+                                );
+                            }
+                            Some(GoalVariable::Unknown) => {
+                                ui.colored_label(
+                                    egui::Color32::YELLOW,
+                                    "UNKNOWN, the step would fail",
+                                );
+                            }
+                            Some(GoalVariable::Pending) | None => {
+                                ui.spinner();
+                            }
+                        }
+                    });
+                    ui.end_row();
+
+                    ui.label("Wait until:");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut segment.wait_until)
+                            .font(egui::TextStyle::Monospace)
+                            .hint_text("predicate, checked before the move")
+                            .desired_width(320.0),
+                    );
+                    ui.end_row();
+
+                    ui.label("Retries:");
+                    ui.add(egui::DragValue::new(&mut segment.retries).range(0..=10));
+                    ui.end_row();
+
+                    ui.label("Check:");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut segment.check)
+                            .font(egui::TextStyle::Monospace)
+                            .hint_text("predicate, checked after the move")
+                            .desired_width(320.0),
+                    );
+                    ui.end_row();
+
+                    ui.label("If false, go to step:");
+                    ui.add_enabled(
+                        !segment.check.trim().is_empty(),
+                        egui::DragValue::new(&mut segment.goto_step).range(1..=step_count.max(1)),
+                    );
+                    ui.end_row();
+
+                    ui.label("Operator prompt:");
+                    let mut has_prompt = segment.prompt.is_some();
+                    if ui
+                        .checkbox(&mut has_prompt, "Pause before the move")
+                        .changed()
+                    {
+                        segment.prompt = has_prompt.then(OperatorPrompt::default);
+                    }
+                    ui.end_row();
+                    if let Some(prompt) = &mut segment.prompt {
+                        draw_prompt_fields(ui, prompt);
+                    }
+
+                    ui.label("Motion:");
+                    ui.checkbox(&mut segment.joint_move, "Joint move (MoveJ)");
+                    ui.end_row();
+
+                    ui.label("Contact force:");
+                    ui.horizontal(|ui| {
+                        let mut limited = segment.force_threshold.is_some();
+                        if ui
+                            .checkbox(&mut limited, "Safe move, stop above")
+                            .changed()
+                        {
+                            segment.force_threshold = limited.then_some(20.0);
+                        }
+                        if let Some(force) = &mut segment.force_threshold {
+                            ui.add(
+                                egui::DragValue::new(force)
+                                    .localized()
+                                    .suffix(" N")
+                                    .speed(0.1)
+                                    .range(0.0..=200.0),
+                            );
+                        }
+                    });
+                    ui.end_row();
+                });
+            if let Some(problem) = &problem {
+                ui.colored_label(egui::Color32::YELLOW, problem);
+            }
+        });
+    }
+
+    /// Steps to draw, with the subroutines expanded as far as they resolve.
+    fn view_segments(&self) -> Vec<PathSegment> {
+        expand_segments(&self.segments, &self.subroutines, 0)
+            .unwrap_or_else(|_| self.segments.clone())
+    }
+
+    /// Top-down (XY) view of the path in the view frame, with blend circles.
+    /// Shows the coordinates under the cursor; returns the point to create a
+    /// frame at when "Create frame here" is picked from the context menu.
+    fn draw_path_view(&mut self, ui: &mut egui::Ui) -> Option<[f64; 2]> {
+        let size = egui::vec2(ui.available_width(), ui.available_height().max(200.0));
+        let (response, painter) = ui.allocate_painter(size, egui::Sense::click());
+        let rect = response.rect;
+        painter.rect_stroke(
+            rect,
+            0.0,
+            egui::Stroke::new(1.0, egui::Color32::DARK_GRAY),
+            egui::StrokeKind::Inside,
+        );
+
+        let segments = self.view_segments();
+        let points = segments
+            .iter()
+            .filter_map(|s| {
+                self.waypoint_poses
+                    .get(&s.goal_feature_id)
+                    .map(|p| (s, p.translation))
+            })
+            .collect::<Vec<_>>();
+
+        // Without waypoints the view shows one metre around the origin
+        let (mut min_x, mut max_x, mut min_y, mut max_y) = if points.is_empty() {
+            (-0.5, 0.5, -0.5, 0.5)
+        } else {
+            (f64::MAX, f64::MIN, f64::MAX, f64::MIN)
+        };
+        for (_, t) in &points {
+            min_x = min_x.min(t[0]);
+            max_x = max_x.max(t[0]);
+            min_y = min_y.min(t[1]);
+            max_y = max_y.max(t[1]);
+        }
+        let span = (max_x - min_x).max(max_y - min_y).max(0.1);
+        let scale = (rect.width().min(rect.height()) as f64 - 40.0) / span;
+        let center = ((min_x + max_x) / 2.0, (min_y + max_y) / 2.0);
+        let to_screen = |t: [f64; 3]| {
+            egui::pos2(
+                rect.center().x + ((t[0] - center.0) * scale) as f32,
+                rect.center().y - ((t[1] - center.1) * scale) as f32,
+            )
+        };
+        let from_screen = |pos: egui::Pos2| {
+            [
+                center.0 + (pos.x - rect.center().x) as f64 / scale,
+                center.1 - (pos.y - rect.center().y) as f64 / scale,
+            ]
+        };
+
+        if let Some(envelope) = self.workspaces.get(&self.robot_id_input) {
+            if let Some(frame_pose) = self.waypoint_poses.get(&envelope.frame) {
+                let to_view = |p: [f64; 3]| {
+                    let r = pose::rotate_vector(frame_pose.rotation, p);
+                    let t = frame_pose.translation;
+                    to_screen([r[0] + t[0], r[1] + t[1], r[2] + t[2]])
+                };
+                let stroke = egui::Stroke::new(1.0, egui::Color32::from_rgb(120, 200, 120));
+                let painter = painter.with_clip_rect(rect);
+                match envelope.shape {
+                    EnvelopeShape::Sphere { center, radius } => {
+                        painter.circle_stroke(to_view(center), (radius * scale) as f32, stroke);
+                    }
+                    EnvelopeShape::Box { min, max } => {
+                        let corners = [
+                            [min[0], min[1], min[2]],
+                            [max[0], min[1], min[2]],
+                            [max[0], max[1], min[2]],
+                            [min[0], max[1], min[2]],
+                        ];
+                        painter.add(egui::Shape::closed_line(
+                            corners.into_iter().map(to_view).collect(),
+                            stroke,
+                        ));
+                    }
+                }
+            }
+        }
+
+        if points.is_empty() {
+            painter.text(
+                rect.center(),
+                egui::Align2::CENTER_CENTER,
+                "Add waypoints and press Refresh View",
+                egui::FontId::proportional(12.0),
+                egui::Color32::GRAY,
+            );
+        } else {
+            let line = points
+                .iter()
+                .map(|(_, t)| to_screen(*t))
+                .collect::<Vec<_>>();
+            painter.add(egui::Shape::line(
+                line,
+                egui::Stroke::new(1.5, egui::Color32::LIGHT_BLUE),
+            ));
+            let last = points.len() - 1;
+            for (i, (segment, t)) in points.iter().enumerate() {
+                let pos = to_screen(*t);
+                if segment.blend_radius > 0.0 && i != last {
+                    painter.circle_stroke(
+                        pos,
+                        (segment.blend_radius * scale) as f32,
+                        egui::Stroke::new(1.0, egui::Color32::YELLOW),
+                    );
+                }
+                painter.circle_filled(pos, 3.0, egui::Color32::WHITE);
+                painter.text(
+                    pos + egui::vec2(5.0, -5.0),
+                    egui::Align2::LEFT_BOTTOM,
+                    format!("{} {}", i + 1, segment.goal_feature_id),
+                    egui::FontId::proportional(11.0),
+                    egui::Color32::LIGHT_GRAY,
+                );
+            }
+        }
+
+        let view_frame = self.view_frame.clone().unwrap_or_default();
+        if let Some(pos) = response.hover_pos() {
+            let [x, y] = from_screen(pos);
+            let stroke = egui::Stroke::new(0.5, egui::Color32::from_gray(90));
+            painter.hline(rect.x_range(), pos.y, stroke);
+            painter.vline(pos.x, rect.y_range(), stroke);
+            painter.text(
+                rect.left_top() + egui::vec2(6.0, 6.0),
+                egui::Align2::LEFT_TOP,
+                format!(
+                    "x {} m, y {} m in {}",
+                    locale::format_number(x, 3),
+                    locale::format_number(y, 3),
+                    view_frame
+                ),
+                egui::FontId::monospace(11.0),
+                egui::Color32::LIGHT_GRAY,
+            );
+        }
+        if response.secondary_clicked() {
+            self.view_click = response.interact_pointer_pos().map(from_screen);
+        }
+
+        let mut place = None;
+        response.context_menu(|ui| {
+            let Some([x, y]) = self.view_click else {
+                ui.close();
+                return;
+            };
+            ui.weak(format!(
+                "{}, {}, {} in {}",
+                locale::format_number(x, 3),
+                locale::format_number(y, 3),
+                locale::format_number(self.table_height, 3),
+                view_frame
+            ));
+            if ui
+                .add_enabled(
+                    self.place_promise.is_none() && self.view_frame.is_some(),
+                    egui::Button::new("Create frame here (at table height)"),
+                )
+                .on_hover_text("A rough waypoint, appended to the path. Refine it by teaching.")
+                .clicked()
+            {
+                place = Some([x, y]);
+                ui.close();
+            }
+        });
+        place
+    }
+
+    /// Steps whose waypoint is outside the robot's workspace, as far as the
+    /// last view refresh looked them up.
+    fn reach_warnings(&self) -> Vec<String> {
+        let Some(envelope) = self.workspaces.get(&self.robot_id_input) else {
+            return Vec::new();
+        };
+        let Some(frame_pose) = self.waypoint_poses.get(&envelope.frame) else {
+            return Vec::new();
+        };
+        self.view_segments()
+            .iter()
+            .enumerate()
+            .filter_map(|(i, segment)| {
+                let waypoint = self.waypoint_poses.get(&segment.goal_feature_id)?;
+                let outside_by = envelope.outside_by(frame_pose, waypoint.translation);
+                (outside_by > 0.001).then(|| {
+                    workspace::reach_warning(
+                        i + 1,
+                        &segment.goal_feature_id,
+                        &self.robot_id_input,
+                        outside_by,
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Steps with their goal inside a speed zone, of the ones looked up.
+    fn zone_warnings(&self) -> Vec<String> {
+        self.view_segments()
+            .iter()
+            .enumerate()
+            .filter_map(|(i, segment)| {
+                let zone = self
+                    .speed_zones
+                    .zone_at(&self.waypoint_poses, &segment.goal_feature_id)
+                    .ok()??;
+                Some(speed_zones::zone_warning(
+                    i + 1,
+                    &segment.goal_feature_id,
+                    zone,
+                    self.speed_zones.enforce,
+                ))
+            })
+            .collect()
+    }
+
+    /// First `waypoint_N` that isn't a frame yet.
+    fn next_waypoint_name(&self) -> String {
+        (1..)
+            .map(|n| format!("waypoint_{}", n))
+            .find(|name| !self.transform_keys.contains(name))
+            .unwrap_or_default()
+    }
+
+    /// Publishes a frame at `point` and table height under the view frame.
+    fn spawn_place_promise(
+        &mut self,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+        point: [f64; 2],
+    ) {
+        let Some(view_frame) = self.view_frame.clone() else {
+            return;
+        };
+        let name = self.next_waypoint_name();
+        let pose = Pose {
+            translation: [point[0], point[1], self.table_height],
+            ..Pose::default()
+        };
+        let mut transform = pose::make_transform_stamped(
+            &view_frame,
+            &name,
+            &pose,
+            &FrameMetadata::for_frame(&name),
+        );
+        provenance::stamp(&mut transform, Method::Manual);
+        let handle = handle.clone();
+        let con_clone = connection.clone();
+        self.place_promise = Some(workers::spawn("path_place_frame", move || {
+            handle
+                .block_on(crate::transforms::publish_transform(con_clone, transform))
+                .map(|_| (name, pose))
+        }));
+    }
+
+    /// Appends the created frame to the path once it's published.
+    fn poll_place_promise(&mut self) {
+        let Some(promise) = self.place_promise.take() else {
+            return;
+        };
+        match promise.try_take() {
+            Ok(Ok((name, pose))) => {
+                self.execute_status = Some(Ok(format!(
+                    "Created {} as step {}, teach it to refine the pose",
+                    name,
+                    self.segments.len() + 1
+                )));
+                self.transform_keys.push(name.clone());
+                self.transform_keys.sort();
+                self.waypoint_poses.insert(name.clone(), pose);
+                self.segments.push(PathSegment::new(name));
+            }
+            Ok(Err(e)) => self.execute_status = Some(Err(e)),
+            Err(promise) => self.place_promise = Some(promise),
+        }
+    }
+
+    fn spawn_wipe_promise(
+        &mut self,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+    ) {
+        let Some(surface) = self.wipe.surface.clone() else {
+            return;
+        };
+        let points = self.wipe.points();
+        let handle = handle.clone();
+        let con_clone = connection.clone();
+        self.wipe_promise = Some(workers::spawn("path_wipe_routine", move || {
+            handle.block_on(cleaning::publish_points(con_clone, surface, points))
+        }));
+    }
+
+    /// Appends the routine to the path once its frames are published.
+    fn poll_wipe_promise(&mut self) {
+        let Some(promise) = self.wipe_promise.take() else {
+            return;
+        };
+        match promise.try_take() {
+            Ok(Ok(points)) => {
+                self.execute_status = Some(Ok(format!(
+                    "Added the cleaning routine as steps {} to {}",
+                    self.segments.len() + 1,
+                    self.segments.len() + points.len()
+                )));
+                for point in points {
+                    if !self.transform_keys.contains(&point.name) {
+                        self.transform_keys.push(point.name.clone());
+                    }
+                    let mut segment = PathSegment::new(point.name);
+                    if point.contact {
+                        segment.velocity = self.wipe.velocity;
+                        segment.force_threshold = Some(self.wipe.force_threshold);
+                    }
+                    self.segments.push(segment);
+                }
+                self.transform_keys.sort();
+            }
+            Ok(Err(e)) => self.execute_status = Some(Err(e)),
+            Err(promise) => self.wipe_promise = Some(promise),
+        }
+    }
+
+    fn spawn_densify_promise(
+        &mut self,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+    ) {
+        let (Some(from), Some(to)) = (self.densify.from.clone(), self.densify.to.clone()) else {
+            return;
+        };
+        let count = self.densify.count;
+        let handle = handle.clone();
+        let con_clone = connection.clone();
+        self.densify_promise = Some(workers::spawn("path_densify", move || {
+            handle
+                .block_on(interpolation::publish_between(
+                    con_clone,
+                    from,
+                    to.clone(),
+                    count,
+                ))
+                .map(|names| (names, to))
+        }));
+    }
+
+    /// Appends the frames and the end frame once they are published, if the
+    /// tool is set to add them to the path.
+    fn poll_densify_promise(&mut self) {
+        let Some(promise) = self.densify_promise.take() else {
+            return;
+        };
+        match promise.try_take() {
+            Ok(Ok((names, to))) => {
+                for name in &names {
+                    if !self.transform_keys.contains(name) {
+                        self.transform_keys.push(name.clone());
+                    }
+                }
+                self.transform_keys.sort();
+                if self.densify.add_to_path {
+                    self.execute_status = Some(Ok(format!(
+                        "Added {} frames and {} as steps {} to {}",
+                        names.len(),
+                        to,
+                        self.segments.len() + 1,
+                        self.segments.len() + names.len() + 1
+                    )));
+                    self.segments
+                        .extend(names.into_iter().map(PathSegment::new));
+                    self.segments.push(PathSegment::new(to));
+                } else {
+                    self.execute_status =
+                        Some(Ok(format!("Published {} frames up to {}", names.len(), to)));
+                }
+            }
+            Ok(Err(e)) => self.execute_status = Some(Err(e)),
+            Err(promise) => self.densify_promise = Some(promise),
+        }
+    }
+
+    /// Checks a CSV job list and loads it once the goals with a pose are
+    /// published. Nothing is loaded if a row has a problem.
+    fn import_jobs(
+        &mut self,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+    ) {
+        let Some(path) = FileDialog::new().add_filter("CSV", &["csv"]).pick_file() else {
+            return;
+        };
+        self.import_errors.clear();
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) => {
+                self.execute_status =
+                    Some(Err(format!("Failed to read {}: {}", path.display(), e)));
+                return;
+            }
+        };
+        let rows = match job_import::parse(&content, &self.transform_keys) {
+            Ok(rows) => rows,
+            Err(errors) => {
+                self.execute_status = Some(Err(format!(
+                    "{} problems in {}, nothing loaded",
+                    errors.len(),
+                    path.display()
+                )));
+                self.import_errors = errors;
+                return;
+            }
+        };
+        let handle = handle.clone();
+        let con_clone = connection.clone();
+        self.import_promise = Some(workers::spawn("path_import_jobs", move || {
+            handle.block_on(job_import::publish_poses(con_clone, rows))
+        }));
+    }
+
+    fn poll_import_promise(&mut self) {
+        let Some(promise) = self.import_promise.take() else {
+            return;
+        };
+        match promise.try_take() {
+            Ok(Ok(rows)) => {
+                self.execute_status = Some(Ok(format!("Loaded {} jobs", rows.len())));
+                self.segments = rows
+                    .into_iter()
+                    .map(|row| {
+                        if !self.transform_keys.contains(&row.goal) {
+                            self.transform_keys.push(row.goal.clone());
+                        }
+                        PathSegment {
+                            velocity: row.velocity,
+                            acceleration: row.acceleration,
+                            force_threshold: row.force_threshold,
+                            joint_move: row.is_joint_move(),
+                            ..PathSegment::new(row.goal)
+                        }
+                    })
+                    .collect();
+                self.transform_keys.sort();
+                self.editing_conditions = None;
+                self.waypoint_poses.clear();
+            }
+            Ok(Err(e)) => self.execute_status = Some(Err(e)),
+            Err(promise) => self.import_promise = Some(promise),
+        }
+    }
+
+    /// The steps with subroutine calls expanded, and their command states.
+    fn expanded_program(&self) -> Result<(Vec<PathSegment>, Vec<State>), String> {
+        let segments = expand_segments(&self.segments, &self.subroutines, 0)?;
+        let states = path_to_states(self, &segments)?;
+        for (i, segment) in segments.iter().enumerate() {
+            if let Some(problem) = segment.validate_conditions(&segments) {
+                return Err(format!("Step {}: {}", i + 1, problem));
+            }
+        }
+        if let Some(problem) = validate_parallel_groups(&segments, &self.robot_id_input) {
+            return Err(problem);
+        }
+        Ok((segments, states))
+    }
+
+    /// Stop and Pause/Continue for the running path, with what the executor
+    /// is doing. Drawn right to left.
+    fn draw_pause_controls(&self, ui: &mut egui::Ui) {
+        let stopping = self.pause.is_cancelled();
+        if ui
+            .add_enabled(!stopping, egui::Button::new("⏹ Stop"))
+            .on_hover_text("End the path after the current step")
+            .clicked()
+        {
+            self.pause.cancel();
+        }
+        if stopping {
+            ui.spinner();
+            ui.colored_label(egui::Color32::YELLOW, "Stopping after the current step…");
+            return;
+        }
+        match self.pause.state() {
+            PauseState::Running => {
+                if ui
+                    .button("⏸ Pause")
+                    .on_hover_text("Hold the path after the current step")
+                    .clicked()
+                {
+                    self.pause.pause();
+                }
+                ui.spinner();
+            }
+            PauseState::Pausing => {
+                if ui.button("▶ Continue").clicked() {
+                    self.pause.resume();
+                }
+                ui.colored_label(egui::Color32::YELLOW, "Pausing after the current step…");
+            }
+            PauseState::Paused(step) => {
+                if ui.button("▶ Continue").clicked() {
+                    self.pause.resume();
+                }
+                ui.colored_label(
+                    egui::Color32::from_rgb(230, 160, 40),
+                    format!("PAUSED before step {}", step + 1),
+                );
+            }
+        }
+    }
+
+    fn spawn_execute_promise(
+        &mut self,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+    ) {
+        self.execute_status = None;
+        let (segments, states) = match self.expanded_program() {
+            Ok(program) => program,
+            Err(e) => {
+                self.execute_status = Some(Err(e));
+                return;
+            }
+        };
+        let non_empty = |predicate: &String| {
+            let predicate = predicate.trim();
+            (!predicate.is_empty()).then(|| predicate.to_string())
+        };
+        let steps = states
+            .into_iter()
+            .zip(&segments)
+            .map(|(state, segment)| ProgramStep {
+                robot_id: segment.robot_id(&self.robot_id_input).to_string(),
+                command: segment.command(),
+                goal_feature_id: segment.goal_feature_id.clone(),
+                state,
+                wait_until: non_empty(&segment.wait_until),
+                retries: segment.retries,
+                branch: non_empty(&segment.check).map(|check| (check, segment.goto_step - 1)),
+                parallel: segment.parallel,
+                prompt: segment.prompt.clone(),
+                goal_variable: non_empty(&segment.goal_variable),
+            })
+            .collect::<Vec<ProgramStep>>();
+        let handle = handle.clone();
+        let con_clone = connection.clone();
+        let prompts = self.prompts.clone();
+        self.pause.reset();
+        let pause = self.pause.clone();
+        self.execute_promise = Some(workers::spawn_long("path_executor", move || {
+            handle.block_on(execute_path(con_clone, prompts, pause, steps))
+        }));
+    }
+
+    fn export_operations(&mut self) {
+        let (segments, states) = match self.expanded_program() {
+            Ok(program) => program,
+            Err(e) => {
+                self.execute_status = Some(Err(e));
+                return;
+            }
+        };
+        let steps = states
+            .into_iter()
+            .zip(&segments)
+            .map(|(state, segment)| {
+                let mut notes = Vec::new();
+                if let Some(prompt) = &segment.prompt {
+                    notes.push(format!("Operator prompt: {}", prompt.message.trim()));
+                }
+                if !segment.wait_until.trim().is_empty() {
+                    notes.push(format!("Waits until: {}", segment.wait_until.trim()));
+                }
+                if !segment.goal_variable.trim().is_empty() {
+                    notes.push(format!(
+                        "Goal read from {} when the step runs",
+                        segment.goal_variable.trim()
+                    ));
+                }
+                if segment.retries > 0 {
+                    notes.push(format!("Retried {} times on failure", segment.retries));
+                }
+                if !segment.check.trim().is_empty() {
+                    notes.push(format!(
+                        "If not {}, continues at step {}",
+                        segment.check.trim(),
+                        segment.goto_step
+                    ));
+                }
+                ExportStep {
+                    robot_id: segment.robot_id(&self.robot_id_input).to_string(),
+                    goal_feature_id: segment.goal_feature_id.clone(),
+                    state,
+                    parallel: segment.parallel,
+                    notes,
+                }
+            })
+            .collect::<Vec<ExportStep>>();
+        let program = format!("path_{}", self.robot_id_input);
+        let content = model_export::export_operations(&program, &steps);
+
+        let Some(path) = FileDialog::new()
+            .add_filter("Rust", &["rs"])
+            .set_file_name(format!("{}_operations.rs", program))
+            .save_file()
+        else {
+            return;
+        };
+        self.execute_status = Some(
+            std::fs::write(&path, content)
+                .map(|()| format!("Saved {}", path.display()))
+                .map_err(|e| format!("Failed to save file: {}", e)),
+        );
+    }
+
+    /// Whether a path is still sending motions, checked on exit.
+    pub fn running(&self) -> bool {
+        self.execute_promise
+            .as_ref()
+            .is_some_and(|promise| promise.ready().is_none())
+    }
+
+    /// Ends the running path before its next step.
+    pub fn cancel_run(&self) {
+        self.pause.cancel();
+    }
+
+    fn poll_execute_promise(&mut self) {
+        if let Some(promise) = &self.execute_promise {
+            if let std::task::Poll::Ready(result) = promise.poll() {
+                self.execute_status = Some(result.clone());
+                self.execute_promise = None;
+            }
+        }
+    }
+
+    /// Returns true while the waypoint lookup is still pending.
+    fn poll_lookup_promise(&mut self) -> bool {
+        let Some(promise) = self.lookup_promise.take() else {
+            return false;
+        };
+        match promise.try_take() {
+            Ok(poses) => {
+                self.waypoint_poses = poses;
+                false
+            }
+            Err(promise) => {
+                self.lookup_promise = Some(promise);
+                true
+            }
+        }
+    }
+
+    fn spawn_lookup_promise(
+        &mut self,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+    ) {
+        let Some(view_frame) = self.view_frame.clone() else {
+            return;
+        };
+        let mut waypoints = self
+            .view_segments()
+            .iter()
+            .map(|s| s.goal_feature_id.clone())
+            .collect::<Vec<String>>();
+        // Also where the workspace is, to draw it and check the waypoints against it
+        if let Some(envelope) = self.workspaces.get(&self.robot_id_input) {
+            waypoints.push(envelope.frame.clone());
+        }
+        waypoints.extend(self.speed_zones.frames());
+        let handle = handle.clone();
+        let con_clone = connection.clone();
+        self.lookup_promise = Some(workers::spawn("path_lookup", move || {
+            handle.block_on(lookup_waypoints(con_clone, view_frame, waypoints))
+        }));
+    }
+
+    fn poll_transforms_promise(&mut self, ui: &mut egui::Ui) -> bool {
+        let Some(promise) = self.get_all_transforms_promise.take() else {
+            return false;
+        };
+
+        match promise.poll() {
+            std::task::Poll::Ready(result) => {
+                let mut keys: Vec<String> = result.keys().cloned().collect();
+                keys.sort_unstable();
+                self.transform_keys = keys;
+                false
+            }
+            std::task::Poll::Pending => {
+                self.get_all_transforms_promise = Some(promise);
+                ui.spinner();
+                true
+            }
+        }
+    }
+
+    fn spawn_transforms_promise(
+        &mut self,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+    ) {
+        self.aliases.spawn_load(handle, connection);
+        let handle = handle.clone();
+        let con_clone = connection.clone();
+        self.get_all_transforms_promise = Some(workers::spawn("fetcher", move || {
+            handle.block_on(get_all_transforms(con_clone))
+        }));
+    }
+}
+
+fn draw_prompt_fields(ui: &mut egui::Ui, prompt: &mut OperatorPrompt) {
+    ui.label("Message:");
+    ui.add(
+        egui::TextEdit::singleline(&mut prompt.message)
+            .hint_text("e.g. insert new tray, then press continue")
+            .desired_width(320.0),
+    );
+    ui.end_row();
+
+    ui.label("Instructions:");
+    ui.add(
+        egui::TextEdit::multiline(&mut prompt.instructions)
+            .hint_text("optional")
+            .desired_rows(2)
+            .desired_width(320.0),
+    );
+    ui.end_row();
+
+    ui.label("Photo:");
+    ui.horizontal(|ui| {
+        ui.add(
+            egui::TextEdit::singleline(&mut prompt.image_path)
+                .hint_text("optional")
+                .desired_width(240.0),
+        );
+        if ui.button("Browse…").clicked() {
+            if let Some(path) = FileDialog::new()
+                .add_filter("Images", &["png", "jpg", "jpeg"])
+                .pick_file()
+            {
+                prompt.image_path = path.display().to_string();
+            }
+        }
+    });
+    ui.end_row();
+}
+
+fn draw_bindings_editor(
+    ui: &mut egui::Ui,
+    i: usize,
+    call: &mut SubroutineCall,
+    library: &SubroutineLibrary,
+    keys: &[String],
+) {
+    egui::Frame::group(ui.style()).show(ui, |ui| {
+        ui.strong(format!("Step {} ({})", i + 1, call.name));
+        let Some(subroutine) = library.get(&call.name) else {
+            ui.colored_label(
+                egui::Color32::YELLOW,
+                format!("Subroutine '{}' is not in the library", call.name),
+            );
+            return;
+        };
+        egui::Grid::new("path_bindings_grid")
+            .num_columns(2)
+            .spacing([10.0, 4.0])
+            .show(ui, |ui| {
+                for parameter in &subroutine.parameters {
+                    ui.label(format!("{}{}:", subroutines::PARAMETER_PREFIX, parameter));
+                    let frame = call.bindings.entry(parameter.clone()).or_default();
+                    egui::ComboBox::from_id_salt(("path_binding", parameter))
+                        .selected_text(frame.as_str())
+                        .show_ui(ui, |ui| {
+                            for key in keys {
+                                ui.selectable_value(frame, key.clone(), key);
+                            }
+                        });
+                    ui.end_row();
+                }
+            });
+        if subroutine.parameters.is_empty() {
+            ui.weak("The subroutine has no frame parameters.");
+        }
+    });
+}
+
+fn draw_frame_selector(
+    ui: &mut egui::Ui,
+    label_text: &str,
+    id_source: &str,
+    selection: &mut Option<String>,
+    keys: &[String],
+) {
+    ui.horizontal(|ui| {
+        ui.label(label_text);
+        let selected_text = selection.as_deref().unwrap_or("Select...");
+
+        egui::ComboBox::from_id_salt(id_source)
+            .selected_text(selected_text)
+            .show_ui(ui, |ui| {
+                ui.selectable_value(selection, None, "None");
+                for key in keys {
+                    ui.selectable_value(selection, Some(key.clone()), key);
+                }
+            });
+    });
+}
+
+/// Builds one MoveL command state per segment, using the same variables as
+/// `robot_command_tab_to_state`.
+fn path_to_states(tab: &PathTab, segments: &[PathSegment]) -> Result<Vec<State>, String> {
+    let Some(baseframe) = &tab.selected_baseframe else {
+        return Err("Baseframe not selected".to_string());
+    };
+    let Some(faceplate) = &tab.selected_faceplate else {
+        return Err("Faceplate not selected".to_string());
+    };
+    let Some(tcp) = &tab.selected_tcp else {
+        return Err("Tcp not selected".to_string());
+    };
+
+    let last = segments.len().saturating_sub(1);
+    let states = segments
+        .iter()
+        .enumerate()
+        .map(|(i, segment)| {
+            let next_is_parallel = segments.get(i + 1).is_some_and(|s| s.parallel);
+            let next_has_prompt = segments.get(i + 1).is_some_and(|s| s.prompt.is_some());
+            // The robot has to stop where conditions are evaluated, other
+            // resources are joined or the operator is asked
+            let blend = if i == last
+                || segment.has_conditions()
+                || segment.parallel
+                || next_is_parallel
+                || next_has_prompt
+            {
+                0.0
+            } else {
+                segment.blend_radius
+            };
+            let mut segment = segment.clone();
+            if tab.speed_zones.enforce {
+                let zone = tab
+                    .speed_zones
+                    .zone_at(&tab.waypoint_poses, &segment.goal_feature_id)
+                    .map_err(|e| {
+                        format!(
+                            "Step {}: {}, refresh the view to check it against the speed zones",
+                            i + 1,
+                            e
+                        )
+                    })?;
+                if let Some(zone) = zone {
+                    segment.velocity *= zone.factor();
+                    segment.acceleration *= zone.factor();
+                }
+            }
+            Ok(segment_to_state(
+                segment.robot_id(&tab.robot_id_input),
+                &segment,
+                blend,
+                baseframe,
+                faceplate,
+                tcp,
+            ))
+        })
+        .collect::<Result<Vec<State>, String>>()?;
+    Ok(states)
+}
+
+fn segment_to_state(
+    robot_name: &str,
+    segment: &PathSegment,
+    blend: f64,
+    baseframe: &str,
+    faceplate: &str,
+    tcp: &str,
+) -> State {
+    let request_trigger = bv!(&&format!("{}_request_trigger", robot_name));
+    let request_state = v!(&&format!("{}_request_state", robot_name));
+    let request_cancel = bv!(&&format!("{}_request_cancel", robot_name));
+    let command_type = v!(&&format!("{}_command_type", robot_name));
+    let accelleration = fv!(&&format!("{}_accelleration", robot_name));
+    let velocity = fv!(&&format!("{}_velocity", robot_name));
+    let use_execution_time = bv!(&&format!("{}_use_execution_time", robot_name));
+    let use_blend_radius = bv!(&&format!("{}_use_blend_radius", robot_name));
+    let blend_radius = fv!(&&format!("{}_blend_radius", robot_name));
+    let use_joint_positions = bv!(&&format!("{}_use_joint_positions", robot_name));
+    let use_relative_pose = bv!(&&format!("{}_use_relative_pose", robot_name));
+    let baseframe_id = v!(&&format!("{}_baseframe_id", robot_name));
+    let faceplate_id = v!(&&format!("{}_faceplate_id", robot_name));
+    let goal_feature_id = v!(&&format!("{}_goal_feature_id", robot_name));
+    let tcp_id = v!(&&format!("{}_tcp_id", robot_name));
+    let root_frame_id = v!(&&format!("{}_root_frame_id", robot_name));
+
+    let state = State::new()
+        .add(assign!(request_trigger, true.to_spvalue()))
+        .add(assign!(request_cancel, false.to_spvalue()))
+        .add(assign!(request_state, "initial".to_spvalue()))
+        .add(assign!(command_type, segment.command().to_spvalue()))
+        .add(assign!(
+            accelleration,
+            SPValue::Float64(FloatOrUnknown::Float64(OrderedFloat(segment.acceleration)))
+        ))
+        .add(assign!(
+            velocity,
+            SPValue::Float64(FloatOrUnknown::Float64(OrderedFloat(segment.velocity)))
+        ))
+        .add(assign!(use_execution_time, false.to_spvalue()))
+        .add(assign!(use_blend_radius, (blend > 0.0).to_spvalue()))
+        .add(assign!(
+            blend_radius,
+            SPValue::Float64(FloatOrUnknown::Float64(OrderedFloat(blend)))
+        ))
+        .add(assign!(use_joint_positions, false.to_spvalue()))
+        .add(assign!(use_relative_pose, false.to_spvalue()))
+        .add(assign!(baseframe_id, baseframe.to_spvalue()))
+        .add(assign!(faceplate_id, faceplate.to_spvalue()))
+        .add(assign!(
+            goal_feature_id,
+            segment.goal_feature_id.as_str().to_spvalue()
+        ))
+        .add(assign!(tcp_id, tcp.to_spvalue()))
+        .add(assign!(root_frame_id, "world".to_spvalue()));
+    match segment.force_threshold {
+        Some(force) => {
+            let force_threshold = fv!(&&format!("{}_force_threshold", robot_name));
+            state.add(assign!(
+                force_threshold,
+                SPValue::Float64(FloatOrUnknown::Float64(OrderedFloat(force)))
+            ))
+        }
+        None => state,
+    }
+}
@@ -0,0 +1,83 @@
+//! Pausing or stopping a running path between steps. The move in progress
+//! always finishes; the executor then holds before the next step until
+//! continued, or ends the run once stopped.
+
+use std::sync::{
+    Mutex,
+    atomic::{AtomicBool, Ordering},
+};
+use tokio::sync::Notify;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PauseState {
+    Running,
+    /// Pause pressed, the current step is still finishing.
+    Pausing,
+    /// Holding before the 0-based step.
+    Paused(usize),
+}
+
+#[derive(Default)]
+pub struct PauseControl {
+    requested: AtomicBool,
+    cancelled: AtomicBool,
+    holding_at: Mutex<Option<usize>>,
+    resumed: Notify,
+}
+
+impl PauseControl {
+    pub fn pause(&self) {
+        self.requested.store(true, Ordering::SeqCst);
+    }
+
+    pub fn resume(&self) {
+        self.requested.store(false, Ordering::SeqCst);
+        self.resumed.notify_waiters();
+    }
+
+    /// Ends the run before its next step, also while it is paused.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        self.resumed.notify_waiters();
+    }
+
+    /// Set by `cancel`, which also keeps a failed step from being retried.
+    pub fn cancelled(&self) -> &AtomicBool {
+        &self.cancelled
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Clears a pause or stop left over from a previous run.
+    pub fn reset(&self) {
+        self.requested.store(false, Ordering::SeqCst);
+        self.cancelled.store(false, Ordering::SeqCst);
+        *self.holding_at.lock().unwrap() = None;
+    }
+
+    pub fn state(&self) -> PauseState {
+        match *self.holding_at.lock().unwrap() {
+            Some(step) => PauseState::Paused(step),
+            None if self.requested.load(Ordering::SeqCst) => PauseState::Pausing,
+            None => PauseState::Running,
+        }
+    }
+
+    /// Called by the executor before the 0-based `step`, returns once no
+    /// pause is requested or the run is stopped.
+    pub async fn hold_before(&self, step: usize) {
+        loop {
+            // Registered before checking the flags, so a resume in between isn't lost
+            let resumed = self.resumed.notified();
+            if !self.requested.load(Ordering::SeqCst) || self.is_cancelled() {
+                break;
+            }
+            *self.holding_at.lock().unwrap() = Some(step);
+            log::info!("Path paused before step {}", step + 1);
+            resumed.await;
+        }
+        *self.holding_at.lock().unwrap() = None;
+    }
+}
@@ -0,0 +1,79 @@
+//! Payload presets of the tools, read from `payload_presets.json` in the data
+//! directory so a new tool only needs an entry there. The file is written with
+//! the known tools on first start. Commands send the preset's name and its
+//! mass, CoG and inertia, the values can be taken over as a manual payload.
+
+use crate::storage;
+use serde::{Deserialize, Serialize};
+
+pub const PAYLOADS_FILE: &str = "payload_presets.json";
+
+pub fn check_file() -> Option<Result<(), String>> {
+    storage::check_json::<PayloadPresets>(PAYLOADS_FILE)
+}
+
+/// Sent when no payload is selected.
+pub const NO_PAYLOAD: &str = "none";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PayloadPreset {
+    pub name: String,
+    /// kg.
+    pub mass: f64,
+    /// Center of gravity from the tool mount, in meters.
+    pub cog: [f64; 3],
+    /// [ixx, iyy, izz, ixy, ixz, iyz] in kg*m^2 about the CoG.
+    pub inertia: [f64; 6],
+}
+
+impl PayloadPreset {
+    fn named(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            mass: 0.0,
+            cog: [0.0; 3],
+            inertia: [0.0; 6],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PayloadPresets {
+    presets: Vec<PayloadPreset>,
+}
+
+impl Default for PayloadPresets {
+    fn default() -> Self {
+        Self {
+            presets: [NO_PAYLOAD, "gripper", "svt", "bvt", "photoneo", "sponge"]
+                .into_iter()
+                .map(PayloadPreset::named)
+                .collect(),
+        }
+    }
+}
+
+impl PayloadPresets {
+    pub fn load() -> Self {
+        if !storage::data_file(PAYLOADS_FILE).exists() {
+            let presets = Self::default();
+            if let Err(e) = storage::save_json(PAYLOADS_FILE, &presets) {
+                log::error!("Failed to save payload presets: {}", e);
+            }
+            return presets;
+        }
+        storage::load_json(PAYLOADS_FILE).unwrap_or_default()
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.presets.iter().map(|preset| preset.name.as_str())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&PayloadPreset> {
+        self.presets.iter().find(|preset| preset.name == name)
+    }
+
+    pub fn path() -> std::path::PathBuf {
+        storage::data_file(PAYLOADS_FILE)
+    }
+}
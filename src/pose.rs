@@ -0,0 +1,334 @@
+use micro_sp::{
+    MapOrUnknown, SPRotation, SPTransform, SPTransformStamped, SPTranslation, SPValue, ToSPValue,
+};
+use ordered_float::OrderedFloat;
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+/// Plain f64 pose used by the GUI editors, converted to and from `SPTransform`
+/// only when talking to the backend.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pose {
+    /// Translation in meters.
+    pub translation: [f64; 3],
+    /// Rotation as a unit quaternion [x, y, z, w].
+    pub rotation: [f64; 4],
+}
+
+impl Default for Pose {
+    fn default() -> Self {
+        Self {
+            translation: [0.0; 3],
+            rotation: [0.0, 0.0, 0.0, 1.0],
+        }
+    }
+}
+
+impl Pose {
+    pub fn from_sp_transform(transform: &SPTransform) -> Self {
+        Self {
+            translation: [
+                transform.translation.x.0,
+                transform.translation.y.0,
+                transform.translation.z.0,
+            ],
+            rotation: [
+                transform.rotation.x.0,
+                transform.rotation.y.0,
+                transform.rotation.z.0,
+                transform.rotation.w.0,
+            ],
+        }
+    }
+
+    /// Returns `self * other`, i.e. `other` expressed in the frame of `self`.
+    pub fn compose(&self, other: &Pose) -> Pose {
+        let rotated = rotate_vector(self.rotation, other.translation);
+        Pose {
+            translation: [
+                self.translation[0] + rotated[0],
+                self.translation[1] + rotated[1],
+                self.translation[2] + rotated[2],
+            ],
+            rotation: normalize_quaternion(quaternion_multiply(self.rotation, other.rotation)),
+        }
+    }
+
+    /// The parent expressed in this frame.
+    pub fn inverse(&self) -> Pose {
+        let q = normalize_quaternion(self.rotation);
+        let conj = [-q[0], -q[1], -q[2], q[3]];
+        let t = rotate_vector(conj, self.translation);
+        Pose {
+            translation: [-t[0], -t[1], -t[2]],
+            rotation: conj,
+        }
+    }
+
+    /// Applies `delta` in the axes of the parent frame instead of the frame's own axes.
+    pub fn offset_in_parent(&self, delta: &Pose) -> Pose {
+        Pose {
+            translation: [
+                self.translation[0] + delta.translation[0],
+                self.translation[1] + delta.translation[1],
+                self.translation[2] + delta.translation[2],
+            ],
+            rotation: normalize_quaternion(quaternion_multiply(delta.rotation, self.rotation)),
+        }
+    }
+
+    pub fn to_sp_transform(&self) -> SPTransform {
+        let q = normalize_quaternion(self.rotation);
+        SPTransform {
+            translation: SPTranslation {
+                x: OrderedFloat(self.translation[0]),
+                y: OrderedFloat(self.translation[1]),
+                z: OrderedFloat(self.translation[2]),
+            },
+            rotation: SPRotation {
+                x: OrderedFloat(q[0]),
+                y: OrderedFloat(q[1]),
+                z: OrderedFloat(q[2]),
+                w: OrderedFloat(q[3]),
+            },
+        }
+    }
+}
+
+/// `transform` as it is written to files: rounded to the configured
+/// decimals when rounding on save is on, unchanged otherwise.
+pub fn export_transform(transform: &SPTransform) -> SPTransform {
+    let precision = crate::locale::precision();
+    if !precision.round_exports {
+        return transform.clone();
+    }
+    let t = |v: &OrderedFloat<f64>| {
+        OrderedFloat(crate::locale::round_to(v.0, precision.translation_decimals))
+    };
+    let r = |v: &OrderedFloat<f64>| {
+        OrderedFloat(crate::locale::round_to(v.0, precision.rotation_decimals))
+    };
+    SPTransform {
+        translation: SPTranslation {
+            x: t(&transform.translation.x),
+            y: t(&transform.translation.y),
+            z: t(&transform.translation.z),
+        },
+        rotation: SPRotation {
+            x: r(&transform.rotation.x),
+            y: r(&transform.rotation.y),
+            z: r(&transform.rotation.z),
+            w: r(&transform.rotation.w),
+        },
+    }
+}
+
+/// Frames as they are written to files, see `export_transform`.
+pub fn export_frame(frame: &SPTransformStamped) -> SPTransformStamped {
+    SPTransformStamped {
+        transform: export_transform(&frame.transform),
+        ..frame.clone()
+    }
+}
+
+/// Pose of `frame` in the root of its tree and the root's name, following
+/// parents through `transforms`. `None` on a loop.
+fn pose_in_root(
+    transforms: &HashMap<String, SPTransformStamped>,
+    frame: &str,
+) -> Option<(Pose, String)> {
+    let mut pose = Pose::default();
+    let mut current = frame.to_string();
+    for _ in 0..=transforms.len() {
+        let Some(tf) = transforms.get(&current) else {
+            return Some((pose, current));
+        };
+        pose = Pose::from_sp_transform(&tf.transform).compose(&pose);
+        current = tf.parent_frame_id.clone();
+    }
+    None
+}
+
+/// `frame` expressed in `reference`, resolved from the fetched frames without
+/// a lookup. `None` if they aren't in the same tree.
+pub fn resolve(
+    transforms: &HashMap<String, SPTransformStamped>,
+    frame: &str,
+    reference: &str,
+) -> Option<Pose> {
+    let (frame_pose, frame_root) = pose_in_root(transforms, frame)?;
+    let (reference_pose, reference_root) = pose_in_root(transforms, reference)?;
+    (frame_root == reference_root).then(|| reference_pose.inverse().compose(&frame_pose))
+}
+
+/// Metadata the GUI writes alongside every frame it creates.
+#[derive(Debug, Clone)]
+pub struct FrameMetadata {
+    pub tcp_id: String,
+    pub enable_transform: bool,
+    pub active_transform: bool,
+}
+
+impl FrameMetadata {
+    pub fn for_frame(child_frame_id: &str) -> Self {
+        Self {
+            tcp_id: child_frame_id.to_string(),
+            enable_transform: true,
+            active_transform: false,
+        }
+    }
+
+    fn to_map(&self) -> MapOrUnknown {
+        MapOrUnknown::Map(vec![
+            ("tcp_id".to_spvalue(), self.tcp_id.as_str().to_spvalue()),
+            (
+                "enable_transform".to_spvalue(),
+                self.enable_transform.to_spvalue(),
+            ),
+            (
+                "active_transform".to_spvalue(),
+                self.active_transform.to_spvalue(),
+            ),
+        ])
+    }
+}
+
+pub fn make_transform_stamped(
+    parent_frame_id: &str,
+    child_frame_id: &str,
+    pose: &Pose,
+    metadata: &FrameMetadata,
+) -> SPTransformStamped {
+    SPTransformStamped {
+        active_transform: metadata.active_transform,
+        enable_transform: metadata.enable_transform,
+        time_stamp: SystemTime::now(),
+        parent_frame_id: parent_frame_id.to_string(),
+        child_frame_id: child_frame_id.to_string(),
+        transform: pose.to_sp_transform(),
+        metadata: metadata.to_map(),
+    }
+}
+
+/// Sets (or adds) a metadata field on a transform. `enable_transform` and
+/// `active_transform` are mirrored into the transform's own flags.
+pub fn set_metadata_field(transform: &mut SPTransformStamped, key: &str, value: SPValue) {
+    if let SPValue::Bool(micro_sp::BoolOrUnknown::Bool(b)) = &value {
+        match key {
+            "enable_transform" => transform.enable_transform = *b,
+            "active_transform" => transform.active_transform = *b,
+            _ => (),
+        }
+    }
+    let key_value = key.to_spvalue();
+    let mut entries = match &transform.metadata {
+        MapOrUnknown::Map(entries) => entries.clone(),
+        MapOrUnknown::UNKNOWN => Vec::new(),
+    };
+    match entries.iter_mut().find(|(k, _)| *k == key_value) {
+        Some(entry) => entry.1 = value,
+        None => entries.push((key_value, value)),
+    }
+    transform.metadata = MapOrUnknown::Map(entries);
+}
+
+/// Reads a metadata field of a transform, if present.
+pub fn get_metadata_field<'a>(transform: &'a SPTransformStamped, key: &str) -> Option<&'a SPValue> {
+    let key_value = key.to_spvalue();
+    match &transform.metadata {
+        MapOrUnknown::Map(entries) => entries
+            .iter()
+            .find(|(k, _)| *k == key_value)
+            .map(|(_, v)| v),
+        MapOrUnknown::UNKNOWN => None,
+    }
+}
+
+pub fn normalize_quaternion(q: [f64; 4]) -> [f64; 4] {
+    let norm = quaternion_norm(q);
+    if norm < 1e-12 {
+        [0.0, 0.0, 0.0, 1.0]
+    } else {
+        [q[0] / norm, q[1] / norm, q[2] / norm, q[3] / norm]
+    }
+}
+
+pub fn quaternion_norm(q: [f64; 4]) -> f64 {
+    (q[0] * q[0] + q[1] * q[1] + q[2] * q[2] + q[3] * q[3]).sqrt()
+}
+
+/// Hamilton product of two quaternions [x, y, z, w].
+pub fn quaternion_multiply(a: [f64; 4], b: [f64; 4]) -> [f64; 4] {
+    let [ax, ay, az, aw] = a;
+    let [bx, by, bz, bw] = b;
+    [
+        aw * bx + ax * bw + ay * bz - az * by,
+        aw * by - ax * bz + ay * bw + az * bx,
+        aw * bz + ax * by - ay * bx + az * bw,
+        aw * bw - ax * bx - ay * by - az * bz,
+    ]
+}
+
+/// Rotates vector `v` by the (not necessarily normalized) quaternion `q`.
+pub fn rotate_vector(q: [f64; 4], v: [f64; 3]) -> [f64; 3] {
+    let q = normalize_quaternion(q);
+    let conj = [-q[0], -q[1], -q[2], q[3]];
+    let r = quaternion_multiply(quaternion_multiply(q, [v[0], v[1], v[2], 0.0]), conj);
+    [r[0], r[1], r[2]]
+}
+
+/// Rotation by `angle` radians about `axis` (need not be unit length) as a
+/// quaternion [x, y, z, w]. A zero axis gives the identity.
+pub fn axis_angle_to_quaternion(axis: [f64; 3], angle: f64) -> [f64; 4] {
+    let norm = (axis[0] * axis[0] + axis[1] * axis[1] + axis[2] * axis[2]).sqrt();
+    if norm < 1e-12 {
+        return [0.0, 0.0, 0.0, 1.0];
+    }
+    let (s, c) = (angle * 0.5).sin_cos();
+    [
+        axis[0] / norm * s,
+        axis[1] / norm * s,
+        axis[2] / norm * s,
+        c,
+    ]
+}
+
+/// Quaternion [x, y, z, w] to a unit axis and an angle in 0..=π radians.
+/// Without a rotation the axis is z.
+pub fn quaternion_to_axis_angle(q: [f64; 4]) -> ([f64; 3], f64) {
+    let mut q = normalize_quaternion(q);
+    if q[3] < 0.0 {
+        q = [-q[0], -q[1], -q[2], -q[3]];
+    }
+    let s = (1.0 - q[3] * q[3]).max(0.0).sqrt();
+    if s < 1e-9 {
+        return ([0.0, 0.0, 1.0], 0.0);
+    }
+    (
+        [q[0] / s, q[1] / s, q[2] / s],
+        2.0 * q[3].clamp(-1.0, 1.0).acos(),
+    )
+}
+
+/// Roll, pitch, yaw (radians, fixed XYZ axes) to a quaternion [x, y, z, w].
+pub fn rpy_to_quaternion(rpy: [f64; 3]) -> [f64; 4] {
+    let (sr, cr) = (rpy[0] * 0.5).sin_cos();
+    let (sp, cp) = (rpy[1] * 0.5).sin_cos();
+    let (sy, cy) = (rpy[2] * 0.5).sin_cos();
+    [
+        sr * cp * cy - cr * sp * sy,
+        cr * sp * cy + sr * cp * sy,
+        cr * cp * sy - sr * sp * cy,
+        cr * cp * cy + sr * sp * sy,
+    ]
+}
+
+/// Quaternion [x, y, z, w] to roll, pitch, yaw (radians, fixed XYZ axes).
+pub fn quaternion_to_rpy(q: [f64; 4]) -> [f64; 3] {
+    let [x, y, z, w] = normalize_quaternion(q);
+    let roll = (2.0 * (w * x + y * z)).atan2(1.0 - 2.0 * (x * x + y * y));
+    let sin_pitch = (2.0 * (w * y - z * x)).clamp(-1.0, 1.0);
+    let pitch = sin_pitch.asin();
+    let yaw = (2.0 * (w * z + x * y)).atan2(1.0 - 2.0 * (y * y + z * z));
+    [roll, pitch, yaw]
+}
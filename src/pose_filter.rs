@@ -0,0 +1,406 @@
+//! Smoothing of frames moved by a noisy source like vision. Each filtered
+//! frame is read a few times a second and run through an exponential or a
+//! median filter, and the result can be published as `{frame}_filtered`
+//! under the same parent, so goals can use it instead of the raw frame and
+//! aren't sent to a momentary outlier.
+
+use crate::kinematics;
+use crate::locale;
+use crate::measure::Measurement;
+use crate::pose::{self, FrameMetadata, Pose};
+use crate::provenance::{self, Method};
+use crate::storage;
+use crate::workers;
+use eframe::egui;
+use micro_sp::*;
+use poll_promise::Promise;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+pub const FILTERS_FILE: &str = "pose_filters.json";
+
+pub fn check_file() -> Option<Result<(), String>> {
+    storage::check_json::<Vec<FilteredFrame>>(FILTERS_FILE)
+}
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+const MAX_WINDOW: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum FilterKind {
+    /// Each read moves the output this share of the way to it, 0..1.
+    Exponential { alpha: f64 },
+    /// Median of the last reads.
+    Median { window: usize },
+}
+
+impl FilterKind {
+    fn label(&self) -> &'static str {
+        match self {
+            FilterKind::Exponential { .. } => "Exponential",
+            FilterKind::Median { .. } => "Median",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilteredFrame {
+    pub frame: String,
+    pub kind: FilterKind,
+    /// Publish the output as `{frame}_filtered`.
+    pub publish: bool,
+}
+
+pub fn filtered_name(frame: &str) -> String {
+    format!("{}_filtered", frame)
+}
+
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_by(|a, b| a.total_cmp(b));
+    let mid = values.len() / 2;
+    if values.len() % 2 == 0 {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+/// Per axis median of the translation, and the rotation of the sample
+/// closest to all others, which unlike a mean ignores a single flipped one.
+fn median_pose(samples: &VecDeque<Pose>) -> Option<Pose> {
+    let translation = [0, 1, 2].map(|i| {
+        let mut values = samples
+            .iter()
+            .map(|p| p.translation[i])
+            .collect::<Vec<f64>>();
+        median(&mut values)
+    });
+    let angle = |a: &Pose, b: &Pose| Measurement::new(&a.inverse().compose(b)).angle;
+    let rotation = samples
+        .iter()
+        .min_by(|a, b| {
+            let sum = |p: &Pose| samples.iter().map(|q| angle(p, q)).sum::<f64>();
+            sum(a).total_cmp(&sum(b))
+        })?
+        .rotation;
+    Some(Pose {
+        translation,
+        rotation,
+    })
+}
+
+/// State of the filter of one frame.
+#[derive(Default)]
+struct FilterState {
+    parent: String,
+    raw: Option<Pose>,
+    samples: VecDeque<Pose>,
+    output: Option<Pose>,
+}
+
+impl FilterState {
+    fn push(&mut self, kind: FilterKind, parent: &str, raw: Pose) {
+        if self.parent != parent {
+            // Reparented, the old poses are in another frame
+            *self = Self {
+                parent: parent.to_string(),
+                ..Default::default()
+            };
+        }
+        self.raw = Some(raw);
+        self.output = match kind {
+            FilterKind::Exponential { alpha } => Some(match self.output {
+                None => raw,
+                Some(last) => Pose {
+                    translation: [0, 1, 2].map(|i| {
+                        last.translation[i] + alpha * (raw.translation[i] - last.translation[i])
+                    }),
+                    rotation: kinematics::slerp(last.rotation, raw.rotation, alpha),
+                },
+            }),
+            FilterKind::Median { window } => {
+                self.samples.push_back(raw);
+                while self.samples.len() > window.max(1) {
+                    self.samples.pop_front();
+                }
+                median_pose(&self.samples)
+            }
+        };
+    }
+}
+
+/// Publishes the last outputs, then reads the frames again.
+async fn publish_and_read(
+    con: Arc<ConnectionManager>,
+    outputs: Vec<SPTransformStamped>,
+    frames: Vec<String>,
+) -> Result<HashMap<String, SPTransformStamped>, String> {
+    for transform in outputs {
+        crate::transforms::publish_transform(con.clone(), transform).await?;
+    }
+    let mut connection = con.get_connection().await;
+    let transforms = TransformsManager::get_all_transforms(&mut connection)
+        .await
+        .map_err(|e| format!("Failed to get the transforms: {}", e))?;
+    Ok(transforms
+        .into_iter()
+        .filter(|(name, _)| frames.contains(name))
+        .collect())
+}
+
+pub struct PoseFilterWindow {
+    pub open: bool,
+    filters: Vec<FilteredFrame>,
+    states: HashMap<String, FilterState>,
+    /// Show the raw poses instead of the filtered ones.
+    show_raw: bool,
+    frame_to_add: Option<String>,
+    promise: Option<Promise<Result<HashMap<String, SPTransformStamped>, String>>>,
+    last_poll: Option<Instant>,
+    error: Option<String>,
+}
+
+impl PoseFilterWindow {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            filters: storage::load_json(FILTERS_FILE).unwrap_or_default(),
+            states: HashMap::new(),
+            show_raw: false,
+            frame_to_add: None,
+            promise: None,
+            last_poll: None,
+            error: None,
+        }
+    }
+
+    fn save(&self) {
+        if let Err(e) = storage::save_json(FILTERS_FILE, &self.filters) {
+            log::error!("Failed to save the pose filters: {}", e);
+        }
+    }
+
+    /// Outputs of the filters set to publish.
+    fn outputs(&self) -> Vec<SPTransformStamped> {
+        self.filters
+            .iter()
+            .filter(|f| f.publish)
+            .filter_map(|f| {
+                let state = self.states.get(&f.frame)?;
+                let name = filtered_name(&f.frame);
+                let mut transform = pose::make_transform_stamped(
+                    &state.parent,
+                    &name,
+                    state.output.as_ref()?,
+                    &FrameMetadata::for_frame(&name),
+                );
+                provenance::stamp(&mut transform, Method::Computed);
+                Some(transform)
+            })
+            .collect()
+    }
+
+    /// Call once per frame, whatever tab is shown, so the published outputs
+    /// keep following their frames.
+    pub fn update(&mut self, handle: &tokio::runtime::Handle, connection: &Arc<ConnectionManager>) {
+        if let Some(promise) = self.promise.take() {
+            match promise.try_take() {
+                Ok(Ok(transforms)) => {
+                    self.error = None;
+                    for filter in &self.filters {
+                        let Some(tf) = transforms.get(&filter.frame) else {
+                            continue;
+                        };
+                        self.states.entry(filter.frame.clone()).or_default().push(
+                            filter.kind,
+                            &tf.parent_frame_id,
+                            Pose::from_sp_transform(&tf.transform),
+                        );
+                    }
+                }
+                Ok(Err(e)) => self.error = Some(e),
+                Err(promise) => {
+                    self.promise = Some(promise);
+                    return;
+                }
+            }
+        }
+        let due = self
+            .last_poll
+            .is_none_or(|last| last.elapsed() >= POLL_INTERVAL);
+        if self.filters.is_empty() || !due {
+            return;
+        }
+        self.last_poll = Some(Instant::now());
+        let outputs = self.outputs();
+        let frames = self.filters.iter().map(|f| f.frame.clone()).collect();
+        let handle = handle.clone();
+        let con_clone = connection.clone();
+        self.promise = Some(workers::spawn("pose_filter", move || {
+            handle.block_on(publish_and_read(con_clone, outputs, frames))
+        }));
+    }
+
+    pub fn draw(&mut self, ctx: &egui::Context, frames: &[String]) {
+        if !self.open {
+            return;
+        }
+        if !self.filters.is_empty() {
+            ctx.request_repaint_after(POLL_INTERVAL);
+        }
+        let mut open = true;
+        let mut changed = false;
+        let mut remove = None;
+        let mut restart = Vec::new();
+        egui::Window::new("Pose Filters")
+            .open(&mut open)
+            .resizable(true)
+            .show(ctx, |ui| {
+                ui.checkbox(&mut self.show_raw, "Show raw poses")
+                    .on_hover_text("The deviation column is always raw against filtered.");
+                egui::Grid::new("pose_filters_grid")
+                    .num_columns(6)
+                    .spacing([12.0, 4.0])
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.strong("Frame");
+                        ui.strong("Filter");
+                        ui.strong(if self.show_raw {
+                            "Raw (m)"
+                        } else {
+                            "Filtered (m)"
+                        });
+                        ui.strong("Deviation");
+                        ui.strong("Publish");
+                        ui.label("");
+                        ui.end_row();
+                        for (i, filter) in self.filters.iter_mut().enumerate() {
+                            ui.monospace(&filter.frame);
+                            let kind = std::mem::discriminant(&filter.kind);
+                            ui.horizontal(|ui| {
+                                changed |= draw_kind(ui, i, &mut filter.kind);
+                            });
+                            if std::mem::discriminant(&filter.kind) != kind {
+                                // Start over instead of going on from the other filter's output
+                                restart.push(filter.frame.clone());
+                            }
+                            let state = self.states.get(&filter.frame);
+                            let (raw, output) =
+                                (state.and_then(|s| s.raw), state.and_then(|s| s.output));
+                            match if self.show_raw { raw } else { output } {
+                                Some(pose) => {
+                                    ui.monospace(locale::format_numbers(&pose.translation, 4))
+                                }
+                                None => ui.weak("-"),
+                            };
+                            match raw.zip(output) {
+                                Some((raw, output)) => {
+                                    let deviation =
+                                        Measurement::new(&output.inverse().compose(&raw));
+                                    ui.monospace(format!(
+                                        "{} mm, {} °",
+                                        locale::format_number(deviation.distance * 1000.0, 2),
+                                        locale::format_number(deviation.angle, 2)
+                                    ))
+                                }
+                                None => ui.weak("-"),
+                            };
+                            changed |= ui
+                                .checkbox(&mut filter.publish, "")
+                                .on_hover_text(format!("As {}", filtered_name(&filter.frame)))
+                                .changed();
+                            if ui.button("🗑").clicked() {
+                                remove = Some(i);
+                            }
+                            ui.end_row();
+                        }
+                    });
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_id_salt("pose_filter_add_select")
+                        .selected_text(self.frame_to_add.as_deref().unwrap_or("Select..."))
+                        .show_ui(ui, |ui| {
+                            for frame in frames {
+                                ui.selectable_value(
+                                    &mut self.frame_to_add,
+                                    Some(frame.clone()),
+                                    frame,
+                                );
+                            }
+                        });
+                    let addable = self.frame_to_add.as_ref().filter(|frame| {
+                        !self.filters.iter().any(|f| &f.frame == *frame)
+                            && !frame.ends_with("_filtered")
+                    });
+                    if ui
+                        .add_enabled(addable.is_some(), egui::Button::new("Add Filter"))
+                        .clicked()
+                    {
+                        if let Some(frame) = addable.cloned() {
+                            self.filters.push(FilteredFrame {
+                                frame,
+                                kind: FilterKind::Median { window: 5 },
+                                publish: false,
+                            });
+                            changed = true;
+                        }
+                    }
+                });
+                if let Some(e) = &self.error {
+                    ui.colored_label(egui::Color32::RED, e);
+                }
+            });
+        self.open = open;
+        if let Some(i) = remove {
+            let filter = self.filters.remove(i);
+            self.states.remove(&filter.frame);
+            changed = true;
+        }
+        for frame in restart {
+            self.states.remove(&frame);
+        }
+        if changed {
+            self.save();
+        }
+    }
+}
+
+/// Filter type and its parameter. Returns true if either changed.
+fn draw_kind(ui: &mut egui::Ui, i: usize, kind: &mut FilterKind) -> bool {
+    let mut changed = false;
+    egui::ComboBox::from_id_salt(("pose_filter_kind", i))
+        .selected_text(kind.label())
+        .show_ui(ui, |ui| {
+            for option in [
+                FilterKind::Exponential { alpha: 0.3 },
+                FilterKind::Median { window: 5 },
+            ] {
+                let selected = kind.label() == option.label();
+                if ui.selectable_label(selected, option.label()).clicked() && !selected {
+                    *kind = option;
+                    changed = true;
+                }
+            }
+        });
+    changed |= match kind {
+        FilterKind::Exponential { alpha } => ui
+            .add(
+                egui::DragValue::new(alpha)
+                    .prefix("α ")
+                    .speed(0.01)
+                    .range(0.01..=1.0),
+            )
+            .on_hover_text("Lower is smoother but lags more")
+            .changed(),
+        FilterKind::Median { window } => ui
+            .add(
+                egui::DragValue::new(window)
+                    .suffix(" reads")
+                    .range(1..=MAX_WINDOW),
+            )
+            .changed(),
+    };
+    changed
+}
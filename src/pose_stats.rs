@@ -0,0 +1,110 @@
+//! Mean and spread of repeated lookups of the same frame, to spot frames
+//! driven by noisy sources like vision before they're saved as calibration.
+
+use crate::locale;
+use crate::pose::{self, Pose};
+use eframe::egui;
+
+/// Standard deviations above these make a frame noisy.
+const NOISY_TRANSLATION_MM: f64 = 0.2;
+const NOISY_ROTATION_DEG: f64 = 0.1;
+
+#[derive(Debug, Clone, Copy)]
+pub struct PoseStatistics {
+    pub samples: usize,
+    pub mean: Pose,
+    /// Per axis, in meters.
+    pub translation_std: [f64; 3],
+    /// Of the angle between each sample and the mean, in degrees.
+    pub rotation_std: f64,
+    /// Farthest sample from the mean position, in meters.
+    pub max_deviation: f64,
+}
+
+fn dot(a: [f64; 4], b: [f64; 4]) -> f64 {
+    a.iter().zip(&b).map(|(a, b)| a * b).sum()
+}
+
+/// Angle between two rotations in degrees.
+fn angle_between(a: [f64; 4], b: [f64; 4]) -> f64 {
+    2.0 * dot(a, b).abs().min(1.0).acos().to_degrees()
+}
+
+impl PoseStatistics {
+    /// `None` without samples.
+    pub fn of(poses: &[Pose]) -> Option<Self> {
+        let first = poses.first()?;
+        let n = poses.len() as f64;
+        let mut translation = [0.0; 3];
+        let mut rotation = [0.0; 4];
+        for p in poses {
+            for (sum, t) in translation.iter_mut().zip(p.translation) {
+                *sum += t / n;
+            }
+            // q and -q are the same rotation, keep them on one side
+            let q = pose::normalize_quaternion(p.rotation);
+            let sign = if dot(q, first.rotation) < 0.0 {
+                -1.0
+            } else {
+                1.0
+            };
+            for (sum, q) in rotation.iter_mut().zip(q) {
+                *sum += sign * q;
+            }
+        }
+        let mean = Pose {
+            translation,
+            rotation: pose::normalize_quaternion(rotation),
+        };
+        let mut translation_std = [0.0; 3];
+        let mut rotation_var = 0.0;
+        let mut max_deviation = 0.0_f64;
+        for p in poses {
+            let mut squared = 0.0;
+            for axis in 0..3 {
+                let d = p.translation[axis] - mean.translation[axis];
+                translation_std[axis] += d * d / n;
+                squared += d * d;
+            }
+            max_deviation = max_deviation.max(squared.sqrt());
+            rotation_var += angle_between(p.rotation, mean.rotation).powi(2) / n;
+        }
+        Some(Self {
+            samples: poses.len(),
+            mean,
+            translation_std: translation_std.map(f64::sqrt),
+            rotation_std: rotation_var.sqrt(),
+            max_deviation,
+        })
+    }
+
+    pub fn is_noisy(&self) -> bool {
+        self.translation_std
+            .iter()
+            .any(|s| s * 1000.0 > NOISY_TRANSLATION_MM)
+            || self.rotation_std > NOISY_ROTATION_DEG
+    }
+
+    pub fn draw(&self, ui: &mut egui::Ui) {
+        let std_mm = self.translation_std.map(|s| s * 1000.0);
+        ui.label(format!(
+            "{} samples, the result is their mean. Std dev XYZ: {} mm, rotation: {}°, \
+             farthest sample {} mm from the mean.",
+            self.samples,
+            locale::format_numbers(&std_mm, 3),
+            locale::format_number(self.rotation_std, 3),
+            locale::format_number(self.max_deviation * 1000.0, 3)
+        ));
+        if self.is_noisy() {
+            ui.colored_label(
+                egui::Color32::YELLOW,
+                format!(
+                    "⚠ Noisy frame (over {} mm or {}°), check its source before saving it as \
+                     calibration.",
+                    locale::format_number(NOISY_TRANSLATION_MM, 1),
+                    locale::format_number(NOISY_ROTATION_DEG, 1)
+                ),
+            );
+        }
+    }
+}
@@ -0,0 +1,399 @@
+//! Saved Robot tab configurations. A preset can declare parameters that the
+//! operator fills in a short form when running it, everything else is sent as
+//! saved. Saving and editing presets needs engineer mode.
+
+use crate::access::{self, Role};
+use crate::locale::LocalizedDragValue;
+use crate::screwdriving::ScrewParams;
+use crate::search_insertion::SearchParams;
+use crate::storage;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub const PRESETS_FILE: &str = "command_presets.json";
+
+pub fn check_file() -> Option<Result<(), String>> {
+    storage::check_json::<Presets>(PRESETS_FILE)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ParameterKind {
+    GoalFrame,
+    Tcp,
+    Velocity,
+    ForceThreshold,
+    /// How many times the command is sent, waiting for each to finish.
+    Count,
+}
+
+impl ParameterKind {
+    pub const ALL: [ParameterKind; 5] = [
+        ParameterKind::GoalFrame,
+        ParameterKind::Tcp,
+        ParameterKind::Velocity,
+        ParameterKind::ForceThreshold,
+        ParameterKind::Count,
+    ];
+}
+
+impl std::fmt::Display for ParameterKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParameterKind::GoalFrame => write!(f, "Goal frame"),
+            ParameterKind::Tcp => write!(f, "TCP"),
+            ParameterKind::Velocity => write!(f, "Velocity"),
+            ParameterKind::ForceThreshold => write!(f, "Force threshold"),
+            ParameterKind::Count => write!(f, "Count"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PresetParameter {
+    /// What the operator sees in the form, e.g. "Pick position".
+    pub label: String,
+    pub kind: ParameterKind,
+}
+
+/// A filled-in parameter.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParameterValue {
+    Frame(Option<String>),
+    Number(f64),
+    Count(u32),
+}
+
+/// The Robot tab fields a command is built from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandPreset {
+    pub command_type: String,
+    pub acceleration: f64,
+    pub velocity: f64,
+    pub goal_feature_id: Option<String>,
+    pub tcp: Option<String>,
+    pub faceplate: Option<String>,
+    pub baseframe: Option<String>,
+    pub use_joint_positions: bool,
+    pub joint_positions: [f64; 6],
+    pub use_preferred_joint_config: bool,
+    pub preferred_joint_config: [f64; 6],
+    pub use_payload: bool,
+    pub payload: String,
+    pub use_execution_time: bool,
+    pub execution_time_s: f64,
+    pub use_blend_radius: bool,
+    pub blend_radius: f64,
+    pub force_threshold: f64,
+    pub use_relative_pose: bool,
+    pub relative_pose: [f64; 6],
+    #[serde(default)]
+    pub search: SearchParams,
+    #[serde(default)]
+    pub screw: ScrewParams,
+    #[serde(default)]
+    pub parameters: Vec<PresetParameter>,
+}
+
+impl CommandPreset {
+    /// The saved value of a parameter, what the form starts from.
+    pub fn default_value(&self, kind: ParameterKind) -> ParameterValue {
+        match kind {
+            ParameterKind::GoalFrame => ParameterValue::Frame(self.goal_feature_id.clone()),
+            ParameterKind::Tcp => ParameterValue::Frame(self.tcp.clone()),
+            ParameterKind::Velocity => ParameterValue::Number(self.velocity),
+            ParameterKind::ForceThreshold => ParameterValue::Number(self.force_threshold),
+            ParameterKind::Count => ParameterValue::Count(1),
+        }
+    }
+
+    /// The preset with the filled-in parameters and how many times to send it.
+    pub fn with_values(&self, values: &[ParameterValue]) -> (CommandPreset, u32) {
+        let mut preset = self.clone();
+        let mut count = 1;
+        for (parameter, value) in self.parameters.iter().zip(values) {
+            match (parameter.kind, value) {
+                (ParameterKind::GoalFrame, ParameterValue::Frame(frame)) => {
+                    preset.goal_feature_id = frame.clone()
+                }
+                (ParameterKind::Tcp, ParameterValue::Frame(frame)) => preset.tcp = frame.clone(),
+                (ParameterKind::Velocity, ParameterValue::Number(v)) => preset.velocity = *v,
+                (ParameterKind::ForceThreshold, ParameterValue::Number(v)) => {
+                    preset.force_threshold = *v
+                }
+                (ParameterKind::Count, ParameterValue::Count(n)) => count = *n,
+                _ => (),
+            }
+        }
+        (preset, count)
+    }
+}
+
+/// Presets by name, saved on this station.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Presets {
+    presets: BTreeMap<String, CommandPreset>,
+}
+
+impl Presets {
+    pub fn load() -> Self {
+        storage::load_json(PRESETS_FILE).unwrap_or_default()
+    }
+
+    fn save(&self) {
+        if let Err(e) = storage::save_json(PRESETS_FILE, self) {
+            log::error!("Failed to save command presets: {}", e);
+        }
+    }
+}
+
+/// A preset the operator started, with the parameters applied.
+pub struct PresetRun {
+    pub name: String,
+    pub preset: CommandPreset,
+    pub count: u32,
+}
+
+/// Preset list, parameter editor and run form of the Robot tab.
+pub struct PresetPanel {
+    presets: Presets,
+    selected: Option<String>,
+    new_name: String,
+    /// Form values while the run form is open.
+    form: Option<Vec<ParameterValue>>,
+}
+
+impl PresetPanel {
+    pub fn new() -> Self {
+        Self {
+            presets: Presets::load(),
+            selected: None,
+            new_name: String::new(),
+            form: None,
+        }
+    }
+
+    /// Draws the panel. `current` is the tab's configuration, offered for
+    /// saving.
+    pub fn draw(&mut self, ui: &mut egui::Ui, current: &CommandPreset, can_run: bool) {
+        let engineer = access::role() == Role::Engineer;
+        if self
+            .selected
+            .as_ref()
+            .is_some_and(|name| !self.presets.presets.contains_key(name))
+        {
+            self.selected = None;
+        }
+        ui.horizontal(|ui| {
+            ui.label("Preset:");
+            egui::ComboBox::from_id_salt("command_preset_select")
+                .selected_text(self.selected.as_deref().unwrap_or("Select..."))
+                .show_ui(ui, |ui| {
+                    for name in self.presets.presets.keys() {
+                        if ui
+                            .selectable_label(self.selected.as_ref() == Some(name), name)
+                            .clicked()
+                        {
+                            self.selected = Some(name.clone());
+                            self.form = None;
+                        }
+                    }
+                });
+            let run = ui
+                .add_enabled(
+                    can_run && self.selected.is_some(),
+                    egui::Button::new("Run…"),
+                )
+                .on_hover_text("Fill in the preset's parameters and send it");
+            if run.clicked() {
+                self.form = self.selected_preset().map(|preset| {
+                    preset
+                        .parameters
+                        .iter()
+                        .map(|p| preset.default_value(p.kind))
+                        .collect()
+                });
+            }
+            if engineer
+                && self.selected.is_some()
+                && ui.button("🗑").on_hover_text("Delete preset").clicked()
+            {
+                if let Some(name) = self.selected.take() {
+                    self.presets.presets.remove(&name);
+                    self.presets.save();
+                }
+            }
+        });
+
+        if engineer {
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.new_name)
+                        .hint_text("preset name")
+                        .desired_width(150.0),
+                );
+                let name = self.new_name.trim().to_string();
+                if ui
+                    .add_enabled(
+                        !name.is_empty(),
+                        egui::Button::new("Save Current as Preset"),
+                    )
+                    .on_hover_text("Overwrites a preset with the same name, keeping its parameters")
+                    .clicked()
+                {
+                    let mut preset = current.clone();
+                    if let Some(old) = self.presets.presets.get(&name) {
+                        preset.parameters = old.parameters.clone();
+                    }
+                    self.presets.presets.insert(name.clone(), preset);
+                    self.presets.save();
+                    self.selected = Some(name);
+                    self.new_name.clear();
+                }
+            });
+            self.draw_parameter_editor(ui);
+        } else if let Some(preset) = self.selected_preset() {
+            if !preset.parameters.is_empty() {
+                let labels = preset
+                    .parameters
+                    .iter()
+                    .map(|p| p.label.as_str())
+                    .collect::<Vec<_>>();
+                ui.weak(format!("Asks for: {}", labels.join(", ")));
+            }
+        }
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.presets.presets.keys().cloned().collect()
+    }
+
+    /// Selects a preset and opens its run form, as if Run was clicked.
+    pub fn open(&mut self, name: &str) {
+        let Some(preset) = self.presets.presets.get(name) else {
+            log::warn!("No preset named {}", name);
+            return;
+        };
+        self.form = Some(
+            preset
+                .parameters
+                .iter()
+                .map(|p| preset.default_value(p.kind))
+                .collect(),
+        );
+        self.selected = Some(name.to_string());
+    }
+
+    fn selected_preset(&self) -> Option<&CommandPreset> {
+        self.presets.presets.get(self.selected.as_ref()?)
+    }
+
+    fn draw_parameter_editor(&mut self, ui: &mut egui::Ui) {
+        let Some(name) = self.selected.clone() else {
+            return;
+        };
+        let Some(preset) = self.presets.presets.get_mut(&name) else {
+            return;
+        };
+        ui.label(format!("Parameters of {}:", name));
+        let mut changed = false;
+        let mut remove = None;
+        for (i, parameter) in preset.parameters.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                changed |= ui
+                    .add(egui::TextEdit::singleline(&mut parameter.label).desired_width(150.0))
+                    .changed();
+                egui::ComboBox::from_id_salt(format!("preset_parameter_kind_{}", i))
+                    .selected_text(parameter.kind.to_string())
+                    .show_ui(ui, |ui| {
+                        for kind in ParameterKind::ALL {
+                            changed |= ui
+                                .selectable_value(&mut parameter.kind, kind, kind.to_string())
+                                .changed();
+                        }
+                    });
+                if ui.button("🗑").clicked() {
+                    remove = Some(i);
+                }
+            });
+        }
+        if let Some(i) = remove {
+            preset.parameters.remove(i);
+            changed = true;
+        }
+        if ui.button("Add Parameter").clicked() {
+            preset.parameters.push(PresetParameter {
+                label: format!("Parameter {}", preset.parameters.len() + 1),
+                kind: ParameterKind::GoalFrame,
+            });
+            changed = true;
+        }
+        if changed {
+            self.presets.save();
+        }
+    }
+
+    /// The run form while open, `frames` fill the frame parameters' combo
+    /// boxes. Returns the run once the operator confirms it.
+    pub fn draw_form(&mut self, ctx: &egui::Context, frames: &[String]) -> Option<PresetRun> {
+        let name = self.selected.clone()?;
+        let preset = self.presets.presets.get(&name)?;
+        let values = self.form.as_mut()?;
+        let mut run = false;
+        let mut cancel = false;
+        egui::Window::new(format!("Run {}", name))
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                if preset.parameters.is_empty() {
+                    ui.label("This preset has no parameters.");
+                }
+                egui::Grid::new("preset_form_grid")
+                    .num_columns(2)
+                    .spacing([20.0, 4.0])
+                    .show(ui, |ui| {
+                        for (i, (parameter, value)) in
+                            preset.parameters.iter().zip(values.iter_mut()).enumerate()
+                        {
+                            ui.label(format!("{}:", parameter.label));
+                            draw_value(ui, i, value, frames);
+                            ui.end_row();
+                        }
+                    });
+                ui.horizontal(|ui| {
+                    run = ui.button("Run").clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+        let result = run.then(|| preset.with_values(values));
+        if run || cancel {
+            self.form = None;
+        }
+        let (preset, count) = result?;
+        Some(PresetRun {
+            name,
+            preset,
+            count,
+        })
+    }
+}
+
+fn draw_value(ui: &mut egui::Ui, i: usize, value: &mut ParameterValue, frames: &[String]) {
+    match value {
+        ParameterValue::Frame(frame) => {
+            egui::ComboBox::from_id_salt(format!("preset_form_frame_{}", i))
+                .selected_text(frame.as_deref().unwrap_or("Select..."))
+                .show_ui(ui, |ui| {
+                    for key in frames {
+                        ui.selectable_value(frame, Some(key.clone()), key);
+                    }
+                });
+        }
+        ParameterValue::Number(number) => {
+            ui.add(egui::DragValue::new(number).localized().speed(0.01));
+        }
+        ParameterValue::Count(count) => {
+            ui.add(egui::DragValue::new(count).range(1..=1000));
+        }
+    }
+}
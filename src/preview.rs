@@ -0,0 +1,562 @@
+//! Animated preview of a MoveJ or MoveL on the kinematic model of the robot,
+//! from its current joint states to the commanded goal, to catch a goal in
+//! the wrong frame before the real robot goes there.
+
+use crate::kinematics::{self, DhModel, RobotModels};
+use crate::locale;
+use crate::pose::Pose;
+use crate::workers;
+use eframe::egui;
+use micro_sp::*;
+use poll_promise::Promise;
+use rfd::FileDialog;
+use std::sync::Arc;
+
+/// Steps the motion is split into.
+const SAMPLES: usize = 60;
+/// Joint change between two steps of a linear move that means a singularity.
+const SINGULARITY_JUMP: f64 = 0.35;
+const PLAYBACK_SECONDS: f32 = 2.0;
+
+/// What the Robot tab is about to command.
+#[derive(Debug, Clone)]
+pub struct PreviewRequest {
+    pub robot_id: String,
+    /// MoveL, otherwise MoveJ.
+    pub linear: bool,
+    pub baseframe: String,
+    pub faceplate: String,
+    pub goal: Option<String>,
+    pub tcp: Option<String>,
+    /// Joint positions commanded instead of the goal.
+    pub joint_target: Option<[f64; 6]>,
+    /// Seed of the IK for joint moves, the current joints otherwise.
+    pub preferred_joint_config: Option<[f64; 6]>,
+    pub use_relative_pose: bool,
+}
+
+pub struct Preview {
+    /// Joint positions from the current ones to the commanded ones.
+    samples: Vec<[f64; 6]>,
+    /// Where the TCP should end up, in the baseframe.
+    goal: Option<Pose>,
+    /// TCP in the faceplate.
+    tcp: Pose,
+    warnings: Vec<String>,
+}
+
+async fn lookup(con: &Arc<ConnectionManager>, parent: &str, child: &str) -> Result<Pose, String> {
+    let mut connection = con.get_connection().await;
+    TransformsManager::lookup_transform(&mut connection, parent, child)
+        .await
+        .map(|tf| Pose::from_sp_transform(&tf.transform))
+        .map_err(|e| format!("Failed to look up {} in {}: {}", child, parent, e))
+}
+
+async fn plan(
+    con: Arc<ConnectionManager>,
+    request: PreviewRequest,
+    model: DhModel,
+) -> Result<Preview, String> {
+    let current: [f64; 6] = crate::lookup::get_joint_states(con.clone(), &request.robot_id)
+        .await
+        .unwrap_or_default()
+        .try_into()
+        .map_err(|_| format!("No joint states of robot {}", request.robot_id))?;
+    let tcp = match &request.tcp {
+        Some(tcp) if *tcp != request.faceplate => lookup(&con, &request.faceplate, tcp).await?,
+        _ => Pose::default(),
+    };
+    let goal = match &request.goal {
+        Some(goal) if request.joint_target.is_none() => {
+            let frame = crate::aliases::resolve(&con, goal).await;
+            Some(lookup(&con, &request.baseframe, &frame).await?)
+        }
+        _ => None,
+    };
+    let target_flange = match (request.joint_target, goal) {
+        (Some(joints), _) => model.flange(&joints),
+        (None, Some(goal)) => goal.compose(&tcp.inverse()),
+        (None, None) => return Err("Select a goal feature or use joint positions".to_string()),
+    };
+
+    let mut warnings = Vec::new();
+    if request.use_relative_pose {
+        warnings.push("The relative pose is not included in the preview.".to_string());
+    }
+    let shoulder = model.link_poses(&[0.0; 6])[1].translation;
+    let from_shoulder = (0..3)
+        .map(|i| (target_flange.translation[i] - shoulder[i]).powi(2))
+        .sum::<f64>()
+        .sqrt();
+    if from_shoulder > model.reach() {
+        warnings.push(format!(
+            "The goal is {} m from the shoulder, beyond the {} m reach of a {}. Is it in the right frame?",
+            locale::format_number(from_shoulder, 3),
+            locale::format_number(model.reach(), 3),
+            model.name
+        ));
+    }
+    if target_flange.translation[2] < 0.0 {
+        warnings.push(format!(
+            "The goal is {} mm below {}.",
+            locale::format_number(-target_flange.translation[2] * 1000.0, 0),
+            request.baseframe
+        ));
+    }
+
+    let samples = if request.linear {
+        plan_linear(&model, current, &target_flange, &mut warnings)
+    } else {
+        let target = match request.joint_target {
+            Some(joints) => Some(joints),
+            None => model.inverse(
+                &target_flange,
+                &request.preferred_joint_config.unwrap_or(current),
+            ),
+        };
+        match target {
+            Some(target) => {
+                for (i, (from, to)) in current.iter().zip(target.iter()).enumerate() {
+                    if (to - from).abs() > std::f64::consts::PI {
+                        warnings.push(format!(
+                            "J{} turns {}°.",
+                            i + 1,
+                            locale::format_number((to - from).to_degrees(), 0)
+                        ));
+                    }
+                }
+                (0..=SAMPLES)
+                    .map(|step| {
+                        let t = step as f64 / SAMPLES as f64;
+                        [0, 1, 2, 3, 4, 5].map(|i| current[i] + (target[i] - current[i]) * t)
+                    })
+                    .collect()
+            }
+            None => {
+                warnings.push(format!("{} can't reach the goal.", model.name));
+                vec![current]
+            }
+        }
+    };
+    Ok(Preview {
+        samples,
+        goal: goal.or(Some(target_flange.compose(&tcp))),
+        tcp,
+        warnings,
+    })
+}
+
+/// Follows the straight line from the current flange pose, stopping where
+/// the IK loses it.
+fn plan_linear(
+    model: &DhModel,
+    current: [f64; 6],
+    target: &Pose,
+    warnings: &mut Vec<String>,
+) -> Vec<[f64; 6]> {
+    let start = model.flange(&current);
+    let mut samples = vec![current];
+    let mut singularity_warned = false;
+    for step in 1..=SAMPLES {
+        let t = step as f64 / SAMPLES as f64;
+        let pose = Pose {
+            translation: [0, 1, 2]
+                .map(|i| start.translation[i] + (target.translation[i] - start.translation[i]) * t),
+            rotation: kinematics::slerp(start.rotation, target.rotation, t),
+        };
+        let previous = samples[samples.len() - 1];
+        let Some(joints) = model.inverse(&pose, &previous) else {
+            warnings.push(format!(
+                "The linear move can't be followed past {}%.",
+                (step - 1) * 100 / SAMPLES
+            ));
+            break;
+        };
+        let jump = (0..6)
+            .map(|i| (joints[i] - previous[i]).abs())
+            .fold(0.0, f64::max);
+        if jump > SINGULARITY_JUMP && !singularity_warned {
+            warnings.push(format!(
+                "The linear move passes close to a singularity at {}%.",
+                step * 100 / SAMPLES
+            ));
+            singularity_warned = true;
+        }
+        samples.push(joints);
+    }
+    samples
+}
+
+pub struct PreviewWindow {
+    pub open: bool,
+    models: RobotModels,
+    request: Option<PreviewRequest>,
+    promise: Option<Promise<Result<Preview, String>>>,
+    result: Option<Result<Preview, String>>,
+    load_error: Option<String>,
+    /// 0 at the current joints, 1 at the goal.
+    progress: f32,
+    playing: bool,
+    yaw: f32,
+    pitch: f32,
+}
+
+impl PreviewWindow {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            models: RobotModels::load(),
+            request: None,
+            promise: None,
+            result: None,
+            load_error: None,
+            progress: 0.0,
+            playing: false,
+            yaw: 0.6,
+            pitch: 0.4,
+        }
+    }
+
+    /// Opens the window and plans the command, or shows why it can't be.
+    pub fn show(
+        &mut self,
+        request: Result<PreviewRequest, String>,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+    ) {
+        self.open = true;
+        match request {
+            Ok(request) => {
+                self.request = Some(request);
+                self.spawn(handle, connection);
+            }
+            Err(e) => {
+                self.request = None;
+                self.promise = None;
+                self.result = Some(Err(e));
+            }
+        }
+    }
+
+    fn spawn(&mut self, handle: &tokio::runtime::Handle, connection: &Arc<ConnectionManager>) {
+        let Some(request) = self.request.clone() else {
+            return;
+        };
+        let model = self.models.get(&request.robot_id);
+        let handle = handle.clone();
+        let con_clone = connection.clone();
+        self.result = None;
+        self.promise = Some(workers::spawn("command_preview", move || {
+            handle.block_on(plan(con_clone, request, model))
+        }));
+    }
+
+    fn poll(&mut self) {
+        if let Some(promise) = self.promise.take() {
+            match promise.try_take() {
+                Ok(result) => {
+                    self.result = Some(result);
+                    self.progress = 0.0;
+                    self.playing = true;
+                }
+                Err(promise) => self.promise = Some(promise),
+            }
+        }
+    }
+
+    pub fn draw(
+        &mut self,
+        ctx: &egui::Context,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+    ) {
+        if !self.open {
+            return;
+        }
+        self.poll();
+        if self.playing {
+            self.progress += ctx.input(|i| i.stable_dt) / PLAYBACK_SECONDS;
+            if self.progress >= 1.0 {
+                self.progress = 1.0;
+                self.playing = false;
+            }
+        }
+
+        let mut open = true;
+        let mut replan = false;
+        egui::Window::new("Command Preview")
+            .open(&mut open)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                if let Some(robot_id) = self.request.as_ref().map(|r| r.robot_id.clone()) {
+                    replan |= self.draw_model_select(ui, &robot_id);
+                }
+                if let Some(e) = &self.load_error {
+                    ui.colored_label(egui::Color32::RED, e);
+                }
+                ui.separator();
+                match &self.result {
+                    None => {
+                        ui.spinner();
+                    }
+                    Some(Err(e)) => {
+                        ui.colored_label(egui::Color32::RED, e);
+                    }
+                    Some(Ok(preview)) => {
+                        for warning in &preview.warnings {
+                            ui.colored_label(egui::Color32::YELLOW, format!("⚠ {}", warning));
+                        }
+                        ui.horizontal(|ui| {
+                            let label = if self.playing { "⏸" } else { "▶" };
+                            if ui.button(label).clicked() {
+                                if !self.playing && self.progress >= 1.0 {
+                                    self.progress = 0.0;
+                                }
+                                self.playing = !self.playing;
+                            }
+                            if ui
+                                .add(
+                                    egui::Slider::new(&mut self.progress, 0.0..=1.0)
+                                        .show_value(false),
+                                )
+                                .changed()
+                            {
+                                self.playing = false;
+                            }
+                        });
+                        if let Some(request) = &self.request {
+                            let model = self.models.get(&request.robot_id);
+                            draw_view(
+                                ui,
+                                &model,
+                                preview,
+                                self.progress,
+                                &mut self.yaw,
+                                &mut self.pitch,
+                            );
+                            ui.weak("Drag to rotate the view.");
+                            draw_joint_comparison(
+                                ui,
+                                preview,
+                                request.preferred_joint_config.as_ref(),
+                            );
+                        }
+                    }
+                }
+            });
+        if replan {
+            self.spawn(handle, connection);
+        }
+        if !open {
+            self.open = false;
+            self.promise = None;
+            self.result = None;
+        }
+    }
+
+    /// Model combo of the robot and loading of DH files, true on a change.
+    fn draw_model_select(&mut self, ui: &mut egui::Ui, robot_id: &str) -> bool {
+        let current = self.models.get(robot_id);
+        let mut selected = None;
+        ui.horizontal(|ui| {
+            ui.label(format!("Model of {}:", robot_id));
+            egui::ComboBox::from_id_salt("preview_model")
+                .selected_text(&current.name)
+                .show_ui(ui, |ui| {
+                    for model in self.models.available() {
+                        if ui.selectable_label(model == current, &model.name).clicked() {
+                            selected = Some(model);
+                        }
+                    }
+                });
+            if ui
+                .button("Load DH…")
+                .on_hover_text("JSON with name, a, d, alpha and optionally base_yaw")
+                .clicked()
+            {
+                if let Some(path) = FileDialog::new().add_filter("JSON", &["json"]).pick_file() {
+                    match DhModel::load(&path) {
+                        Ok(model) => {
+                            self.load_error = None;
+                            self.models.add_custom(model.clone());
+                            selected = Some(model);
+                        }
+                        Err(e) => self.load_error = Some(e),
+                    }
+                }
+            }
+        });
+        match selected {
+            Some(model) if model != current => {
+                self.models.set(robot_id, model);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Orthographic view of the arm at `progress`, with the start and end poses
+/// as ghosts, the TCP trace and the goal.
+fn draw_view(
+    ui: &mut egui::Ui,
+    model: &DhModel,
+    preview: &Preview,
+    progress: f32,
+    yaw: &mut f32,
+    pitch: &mut f32,
+) {
+    let (response, painter) =
+        ui.allocate_painter(egui::vec2(ui.available_width(), 320.0), egui::Sense::drag());
+    let rect = response.rect;
+    painter.rect_filled(rect, 4.0, ui.visuals().extreme_bg_color);
+    if response.dragged() {
+        let delta = response.drag_delta();
+        *yaw -= delta.x * 0.01;
+        *pitch = (*pitch + delta.y * 0.01).clamp(-1.5, 1.5);
+    }
+
+    let scale = rect.height() as f64 * 0.45 / model.reach().max(0.1);
+    let (sin_yaw, cos_yaw) = (*yaw as f64).sin_cos();
+    let (sin_pitch, cos_pitch) = (*pitch as f64).sin_cos();
+    let origin = rect.center() + egui::vec2(0.0, rect.height() * 0.2);
+    let to_screen = |p: [f64; 3]| {
+        let x = p[0] * cos_yaw - p[1] * sin_yaw;
+        let depth = p[0] * sin_yaw + p[1] * cos_yaw;
+        let up = p[2] * cos_pitch + depth * sin_pitch;
+        origin + egui::vec2((x * scale) as f32, -(up * scale) as f32)
+    };
+    let painter = painter.with_clip_rect(rect);
+
+    let axes = |pose: &Pose, length: f64, width: f32| {
+        let colors = [
+            egui::Color32::from_rgb(220, 80, 80),
+            egui::Color32::from_rgb(80, 200, 80),
+            egui::Color32::from_rgb(80, 120, 230),
+        ];
+        for (axis, color) in colors.into_iter().enumerate() {
+            let mut tip = [0.0; 3];
+            tip[axis] = length;
+            let end = pose.compose(&Pose {
+                translation: tip,
+                ..Default::default()
+            });
+            painter.line_segment(
+                [to_screen(pose.translation), to_screen(end.translation)],
+                egui::Stroke::new(width, color),
+            );
+        }
+    };
+    axes(&Pose::default(), 0.1, 1.0);
+
+    let arm = |joints: &[f64; 6], stroke: egui::Stroke| {
+        let points = model
+            .link_poses(joints)
+            .iter()
+            .map(|p| to_screen(p.translation))
+            .collect::<Vec<egui::Pos2>>();
+        painter.add(egui::Shape::line(points.clone(), stroke));
+        for point in points {
+            painter.circle_filled(point, stroke.width, stroke.color);
+        }
+    };
+    let ghost = egui::Stroke::new(2.0, egui::Color32::from_gray(90));
+    let first = preview.samples[0];
+    let last = preview.samples[preview.samples.len() - 1];
+    arm(&first, ghost);
+    arm(&last, ghost);
+
+    let trace = preview
+        .samples
+        .iter()
+        .map(|joints| to_screen(model.flange(joints).compose(&preview.tcp).translation))
+        .collect::<Vec<egui::Pos2>>();
+    painter.add(egui::Shape::dashed_line(
+        &trace,
+        egui::Stroke::new(1.0, egui::Color32::LIGHT_BLUE),
+        4.0,
+        3.0,
+    ));
+    if let Some(goal) = &preview.goal {
+        axes(goal, 0.08, 2.0);
+    }
+
+    let position = progress as f64 * (preview.samples.len() - 1) as f64;
+    let index = (position.floor() as usize).min(preview.samples.len() - 1);
+    let next = (index + 1).min(preview.samples.len() - 1);
+    let t = position - index as f64;
+    let joints = [0, 1, 2, 3, 4, 5]
+        .map(|i| preview.samples[index][i] * (1.0 - t) + preview.samples[next][i] * t);
+    arm(
+        &joints,
+        egui::Stroke::new(4.0, egui::Color32::from_rgb(90, 160, 230)),
+    );
+    axes(&model.flange(&joints).compose(&preview.tcp), 0.05, 1.5);
+}
+
+/// Current, preferred and previewed joints side by side, with the deltas
+/// that mean a big sweeping motion flagged.
+fn draw_joint_comparison(ui: &mut egui::Ui, preview: &Preview, preferred: Option<&[f64; 6]>) {
+    const SWEEP: f64 = std::f64::consts::FRAC_PI_2;
+    let current = &preview.samples[0];
+    let solution = &preview.samples[preview.samples.len() - 1];
+    let deg = |x: f64| format!("{}°", locale::format_number(x.to_degrees(), 1));
+    let delta = |ui: &mut egui::Ui, d: f64| {
+        let text = egui::RichText::new(deg(d)).monospace();
+        if d.abs() > SWEEP {
+            ui.label(text.color(egui::Color32::YELLOW))
+                .on_hover_text("More than a quarter turn");
+        } else {
+            ui.label(text);
+        }
+    };
+    egui::CollapsingHeader::new("Joint configurations")
+        .id_salt("preview_joint_comparison")
+        .default_open(true)
+        .show(ui, |ui| {
+            egui::Grid::new("preview_joint_grid")
+                .num_columns(6)
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.strong("Joint");
+                    ui.strong("Current");
+                    ui.strong("Preferred");
+                    ui.strong("Preview");
+                    ui.strong("Δ current");
+                    ui.strong("Δ preferred");
+                    ui.end_row();
+                    for i in 0..6 {
+                        ui.label(format!("J{}", i + 1));
+                        ui.monospace(deg(current[i]));
+                        match preferred {
+                            Some(preferred) => ui.monospace(deg(preferred[i])),
+                            None => ui.weak("-"),
+                        };
+                        ui.monospace(deg(solution[i]));
+                        delta(ui, solution[i] - current[i]);
+                        match preferred {
+                            Some(preferred) => delta(ui, solution[i] - preferred[i]),
+                            None => {
+                                ui.weak("-");
+                            }
+                        }
+                        ui.end_row();
+                    }
+                });
+            for flip in kinematics::configuration_flips(current, solution) {
+                ui.colored_label(
+                    egui::Color32::YELLOW,
+                    format!("⚠ The {} flips from the current configuration.", flip),
+                );
+            }
+            if let Some(preferred) = preferred {
+                for flip in kinematics::configuration_flips(preferred, solution) {
+                    ui.colored_label(
+                        egui::Color32::YELLOW,
+                        format!("⚠ The {} flips from the preferred configuration.", flip),
+                    );
+                }
+            }
+        });
+}
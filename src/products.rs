@@ -0,0 +1,209 @@
+//! Product types and the parameters an order of each type carries, defined
+//! in `products.json` in the data directory, e.g.
+//!
+//! ```json
+//! {"product_types": [{"name": "box", "parameters": [
+//!     {"name": "length", "kind": "float", "unit": "mm", "min": 100, "max": 600, "default": 300},
+//!     {"name": "count", "kind": "integer", "min": 1, "max": 48, "default": 12},
+//!     {"name": "target_pallet", "kind": "choice", "options": ["pallet_a", "pallet_b"]}
+//! ]}]}
+//! ```
+//!
+//! Starting an order writes each parameter to `{variable_prefix}_{name}`.
+
+use crate::storage;
+use micro_sp::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub const PRODUCTS_FILE: &str = "products.json";
+
+pub fn check_file() -> Option<Result<(), String>> {
+    storage::check_json::<ProductCatalog>(PRODUCTS_FILE)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParameterKind {
+    Float,
+    Integer,
+    Bool,
+    Text,
+    /// One of `options`, written as a string.
+    Choice,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ParameterValue {
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    Text(String),
+}
+
+impl ParameterValue {
+    pub fn to_spvalue(&self) -> SPValue {
+        match self {
+            ParameterValue::Bool(b) => b.to_spvalue(),
+            ParameterValue::Integer(i) => i.to_spvalue(),
+            ParameterValue::Float(f) => f.to_spvalue(),
+            ParameterValue::Text(s) => s.as_str().to_spvalue(),
+        }
+    }
+}
+
+impl std::fmt::Display for ParameterValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParameterValue::Bool(b) => write!(f, "{}", b),
+            ParameterValue::Integer(i) => write!(f, "{}", i),
+            ParameterValue::Float(x) => write!(f, "{}", crate::locale::format_number(*x, 3)),
+            ParameterValue::Text(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParameterSpec {
+    pub name: String,
+    pub kind: ParameterKind,
+    #[serde(default)]
+    pub unit: String,
+    #[serde(default)]
+    pub description: String,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub default: Option<ParameterValue>,
+    #[serde(default)]
+    pub options: Vec<String>,
+}
+
+impl ParameterSpec {
+    /// Value a new order starts with, the configured default if it fits the kind.
+    pub fn initial_value(&self) -> ParameterValue {
+        let min = self.min.unwrap_or(0.0);
+        match (self.kind, &self.default) {
+            (ParameterKind::Float, Some(ParameterValue::Float(x))) => ParameterValue::Float(*x),
+            (ParameterKind::Float, Some(ParameterValue::Integer(i))) => {
+                ParameterValue::Float(*i as f64)
+            }
+            (ParameterKind::Float, _) => ParameterValue::Float(min),
+            (ParameterKind::Integer, Some(ParameterValue::Integer(i))) => {
+                ParameterValue::Integer(*i)
+            }
+            (ParameterKind::Integer, _) => ParameterValue::Integer(min.ceil() as i64),
+            (ParameterKind::Bool, Some(ParameterValue::Bool(b))) => ParameterValue::Bool(*b),
+            (ParameterKind::Bool, _) => ParameterValue::Bool(false),
+            (ParameterKind::Text | ParameterKind::Choice, Some(ParameterValue::Text(s))) => {
+                ParameterValue::Text(s.clone())
+            }
+            (ParameterKind::Choice, _) => {
+                ParameterValue::Text(self.options.first().cloned().unwrap_or_default())
+            }
+            (ParameterKind::Text, _) => ParameterValue::Text(String::new()),
+        }
+    }
+
+    /// Problem with a value entered for this parameter, if any.
+    pub fn validate(&self, value: &ParameterValue) -> Option<String> {
+        let number = match value {
+            ParameterValue::Float(x) => Some(*x),
+            ParameterValue::Integer(i) => Some(*i as f64),
+            _ => None,
+        };
+        if let Some(x) = number {
+            if self.min.is_some_and(|min| x < min) || self.max.is_some_and(|max| x > max) {
+                return Some(format!(
+                    "{} must be within {} and {}",
+                    self.name,
+                    self.min.map_or("-∞".to_string(), |m| m.to_string()),
+                    self.max.map_or("∞".to_string(), |m| m.to_string())
+                ));
+            }
+        }
+        if let (ParameterKind::Choice, ParameterValue::Text(s)) = (self.kind, value) {
+            if !self.options.contains(s) {
+                return Some(format!("{}: '{}' is not an option", self.name, s));
+            }
+        }
+        None
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProductType {
+    pub name: String,
+    #[serde(default = "default_variable_prefix")]
+    pub variable_prefix: String,
+    #[serde(default)]
+    pub parameters: Vec<ParameterSpec>,
+}
+
+fn default_variable_prefix() -> String {
+    "order".to_string()
+}
+
+impl ProductType {
+    pub fn variable(&self, parameter: &str) -> String {
+        format!("{}_{}", self.variable_prefix, parameter)
+    }
+
+    pub fn initial_values(&self) -> BTreeMap<String, ParameterValue> {
+        self.parameters
+            .iter()
+            .map(|p| (p.name.clone(), p.initial_value()))
+            .collect()
+    }
+
+    pub fn validate(&self, values: &BTreeMap<String, ParameterValue>) -> Option<String> {
+        self.parameters
+            .iter()
+            .find_map(|spec| match values.get(&spec.name) {
+                Some(value) => spec.validate(value),
+                None => Some(format!("{} is missing", spec.name)),
+            })
+    }
+
+    /// The state variables an order of this type writes when it starts.
+    pub fn state_values(
+        &self,
+        values: &BTreeMap<String, ParameterValue>,
+    ) -> Vec<(String, SPValue)> {
+        self.parameters
+            .iter()
+            .filter_map(|spec| {
+                let value = values.get(&spec.name)?;
+                Some((self.variable(&spec.name), value.to_spvalue()))
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProductCatalog {
+    #[serde(default)]
+    pub product_types: Vec<ProductType>,
+}
+
+impl ProductCatalog {
+    /// Reads the config file; a missing file is an empty catalog, a broken
+    /// one an error to show.
+    pub fn load() -> Result<Self, String> {
+        let path = storage::data_file(PRODUCTS_FILE);
+        match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|e| format!("Failed to parse {}: {}", path.display(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+        }
+    }
+
+    pub fn path() -> std::path::PathBuf {
+        storage::data_file(PRODUCTS_FILE)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ProductType> {
+        self.product_types.iter().find(|p| p.name == name)
+    }
+}
@@ -0,0 +1,128 @@
+//! Who made a frame, when and how, kept in its metadata next to `tcp_id`.
+//! Every frame the GUI creates or moves is stamped, so a wrong goal can be
+//! traced back to the teach-in or import it came from. Metadata-only edits
+//! keep the stamp, it is about where the pose came from.
+
+use crate::pose;
+use crate::storage;
+use eframe::egui;
+use micro_sp::*;
+use std::sync::RwLock;
+
+const OPERATOR_KEY: &str = "provenance_operator";
+const METHOD_KEY: &str = "provenance_method";
+const VERSION_KEY: &str = "provenance_gui_version";
+const TIME_KEY: &str = "provenance_time";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    /// Captured from the robot's TCP.
+    Taught,
+    /// Loaded from a file.
+    Imported,
+    /// Derived from other frames, e.g. offsets, grids or filters.
+    Computed,
+    /// Typed in or edited by hand.
+    Manual,
+}
+
+impl Method {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Method::Taught => "taught",
+            Method::Imported => "imported",
+            Method::Computed => "computed",
+            Method::Manual => "manual",
+        }
+    }
+}
+
+// Set from the settings, like the decimal separator, so the places that
+// create frames don't all need them passed in.
+static OPERATOR: RwLock<String> = RwLock::new(String::new());
+
+pub fn set_operator(name: &str) {
+    if let Ok(mut operator) = OPERATOR.write() {
+        *operator = name.trim().to_string();
+    }
+}
+
+fn operator() -> String {
+    match OPERATOR.read() {
+        Ok(operator) if !operator.is_empty() => operator.clone(),
+        _ => "unknown".to_string(),
+    }
+}
+
+/// Records the current operator, GUI version and time with `method`,
+/// replacing what the frame had.
+pub fn stamp(transform: &mut SPTransformStamped, method: Method) {
+    for (key, value) in [
+        (OPERATOR_KEY, operator().as_str().to_spvalue()),
+        (METHOD_KEY, method.as_str().to_spvalue()),
+        (VERSION_KEY, env!("CARGO_PKG_VERSION").to_spvalue()),
+        (TIME_KEY, (storage::now_secs() as f64).to_spvalue()),
+    ] {
+        pose::set_metadata_field(transform, key, value);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Provenance {
+    pub operator: String,
+    pub method: String,
+    pub version: String,
+    /// Seconds since the unix epoch.
+    pub time: Option<u64>,
+}
+
+impl Provenance {
+    /// `None` for frames the GUI hasn't stamped.
+    pub fn of(transform: &SPTransformStamped) -> Option<Self> {
+        let text = |key: &str| match pose::get_metadata_field(transform, key) {
+            Some(SPValue::String(StringOrUnknown::String(s))) => Some(s.clone()),
+            _ => None,
+        };
+        let time = match pose::get_metadata_field(transform, TIME_KEY) {
+            Some(SPValue::Float64(FloatOrUnknown::Float64(t))) => Some(t.0.max(0.0) as u64),
+            _ => None,
+        };
+        Some(Self {
+            method: text(METHOD_KEY)?,
+            operator: text(OPERATOR_KEY).unwrap_or_default(),
+            version: text(VERSION_KEY).unwrap_or_default(),
+            time,
+        })
+    }
+
+    fn when(&self) -> String {
+        match self.time {
+            Some(time) => format!("{} UTC", storage::format_timestamp(time)),
+            None => "-".to_string(),
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "{} by {}, {}, GUI {}",
+            self.method,
+            self.operator,
+            self.when(),
+            self.version
+        )
+    }
+}
+
+/// The method, with the rest on hover, or a dash for unstamped frames.
+pub fn draw(ui: &mut egui::Ui, transform: &SPTransformStamped) {
+    match Provenance::of(transform) {
+        Some(provenance) => {
+            ui.label(&provenance.method)
+                .on_hover_text(provenance.summary());
+        }
+        None => {
+            ui.weak("-")
+                .on_hover_text("Not created or changed by this GUI");
+        }
+    }
+}
@@ -0,0 +1,115 @@
+//! Records backend-affecting GUI actions as a script for the script console,
+//! so a session can be replayed for bug reports and regression scenarios.
+
+use crate::pose::Pose;
+use micro_sp::*;
+use std::sync::Mutex;
+
+// Actions happen in every tab and on promise threads, so the recording is
+// process-wide. `None` while not recording.
+static RECORDING: Mutex<Option<Vec<String>>> = Mutex::new(None);
+
+pub fn start() {
+    *RECORDING.lock().unwrap() = Some(Vec::new());
+}
+
+/// Stops recording and returns the script.
+pub fn stop() -> Option<String> {
+    let lines = RECORDING.lock().unwrap().take()?;
+    let mut script = format!(
+        "// Session recorded {} UTC\n",
+        crate::storage::format_timestamp(crate::storage::now_secs())
+    );
+    for line in lines {
+        script.push_str(&line);
+        script.push('\n');
+    }
+    Some(script)
+}
+
+pub fn is_recording() -> bool {
+    RECORDING.lock().unwrap().is_some()
+}
+
+fn push(line: String) {
+    if let Some(lines) = RECORDING.lock().unwrap().as_mut() {
+        lines.push(line);
+    }
+}
+
+fn script_literal(value: &SPValue) -> String {
+    match value {
+        SPValue::String(StringOrUnknown::String(s)) => format!("{:?}", s),
+        SPValue::Bool(BoolOrUnknown::Bool(b)) => b.to_string(),
+        // Debug keeps the decimal point, so the script sees a float
+        SPValue::Float64(FloatOrUnknown::Float64(f)) => format!("{:?}", f.0),
+        SPValue::Array(ArrayOrUnknown::Array(values)) => format!(
+            "[{}]",
+            values
+                .iter()
+                .map(script_literal)
+                .collect::<Vec<String>>()
+                .join(", ")
+        ),
+        other => format!("{:?}", format!("{:?}", other)),
+    }
+}
+
+pub fn record_lookup(parent: &str, child: &str) {
+    push(format!("print(lookup({:?}, {:?}));", parent, child));
+}
+
+pub fn record_publish_frame(transform: &SPTransformStamped) {
+    let pose = Pose::from_sp_transform(&transform.transform);
+    let [x, y, z] = pose.translation;
+    let [qx, qy, qz, qw] = pose.rotation;
+    push(format!(
+        "publish_frame({:?}, {:?}, #{{x: {:?}, y: {:?}, z: {:?}, qx: {:?}, qy: {:?}, qz: {:?}, qw: {:?}}});",
+        transform.parent_frame_id, transform.child_frame_id, x, y, z, qx, qy, qz, qw
+    ));
+}
+
+/// A state with a true `{robot}_request_trigger` becomes a `send_command`,
+/// which also waits for the result on replay. Everything else becomes `set` calls.
+pub fn record_state(state: &State) {
+    if !is_recording() {
+        return;
+    }
+    let mut entries = state
+        .state
+        .iter()
+        .map(|(key, assignment)| (key.as_str(), &assignment.val))
+        .collect::<Vec<(&str, &SPValue)>>();
+    entries.sort_by_key(|(key, _)| *key);
+
+    let robot_id = entries.iter().find_map(|(key, value)| {
+        let robot = key.strip_suffix("_request_trigger")?;
+        matches!(value, SPValue::Bool(BoolOrUnknown::Bool(true))).then_some(robot)
+    });
+    let Some(robot_id) = robot_id else {
+        for (key, value) in entries {
+            push(format!("set({:?}, {});", key, script_literal(value)));
+        }
+        return;
+    };
+
+    let mut parameters = Vec::new();
+    for (key, value) in entries {
+        match key
+            .strip_prefix(robot_id)
+            .and_then(|rest| rest.strip_prefix('_'))
+        {
+            // send_command writes the request variables itself
+            Some(
+                "request_trigger" | "request_cancel" | "request_state" | "cancel_current_goal",
+            ) => (),
+            Some(parameter) => parameters.push(format!("{}: {}", parameter, script_literal(value))),
+            None => push(format!("set({:?}, {});", key, script_literal(value))),
+        }
+    }
+    push(format!(
+        "send_command({:?}, #{{{}}});",
+        robot_id,
+        parameters.join(", ")
+    ));
+}
@@ -0,0 +1,654 @@
+use crate::maintenance::{self, MaintenanceLog, NoteInput};
+use crate::robot_interface::{self, INTERFACE, Issue};
+use crate::state_poller::sp_value_to_display;
+use crate::workers;
+use eframe::egui;
+use micro_sp::*;
+use poll_promise::Promise;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+const REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+/// How often accumulated operating hours are written to the maintenance log.
+const MAINTENANCE_SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Runner variable marking a resource as available for planning and GUI commands.
+pub fn enabled_key(resource: &str) -> String {
+    format!("{}_enabled", resource)
+}
+
+async fn set_resource_enabled(con: Arc<ConnectionManager>, resource: String, enabled: bool) {
+    let mut connection = con.get_connection().await;
+    let state = State::new().add(assign!(bv!(&&enabled_key(&resource)), enabled.to_spvalue()));
+    crate::recorder::record_state(&state);
+    StateManager::set_state(&mut connection, &state).await;
+}
+
+/// Runner variables of one resource, keyed by full variable name.
+#[derive(Debug, Clone, Default)]
+pub struct ResourceInfo {
+    pub variables: BTreeMap<String, SPValue>,
+    /// Robots are resources that also take motion commands.
+    pub is_robot: bool,
+    /// `false` only if `{resource}_enabled` is explicitly false.
+    pub enabled: bool,
+}
+
+/// Every key ending in `_request_trigger` marks a resource with that prefix.
+async fn get_resources(con: Arc<ConnectionManager>) -> BTreeMap<String, ResourceInfo> {
+    let mut connection = con.get_connection().await;
+    let Some(state) = StateManager::get_full_state(&mut connection).await else {
+        log::error!("GUI Failed to get the full state!");
+        return BTreeMap::new();
+    };
+    let mut resources = BTreeMap::new();
+    for key in state.state.keys() {
+        if let Some(prefix) = key.strip_suffix("_request_trigger") {
+            resources.insert(prefix.to_string(), ResourceInfo::default());
+        }
+    }
+    for (prefix, info) in resources.iter_mut() {
+        info.is_robot = state
+            .state
+            .contains_key(&format!("{}_command_type", prefix));
+        info.enabled = !matches!(
+            state.state.get(&enabled_key(prefix)).map(|a| &a.val),
+            Some(SPValue::Bool(BoolOrUnknown::Bool(false)))
+        );
+    }
+    for (key, assignment) in &state.state {
+        for (prefix, info) in resources.iter_mut() {
+            if is_runner_variable(prefix, key) {
+                info.variables.insert(key.clone(), assignment.val.clone());
+            }
+        }
+    }
+    resources
+}
+
+/// The variables shown on a resource card: trigger, state and failure flags.
+fn is_runner_variable(prefix: &str, key: &str) -> bool {
+    let Some(rest) = key.strip_prefix(prefix).and_then(|r| r.strip_prefix('_')) else {
+        return false;
+    };
+    matches!(rest, "request_trigger" | "request_state" | "enabled") || rest.contains("fail")
+}
+
+/// The "Register new robot" wizard.
+#[derive(Default)]
+struct RegisterRobotDialog {
+    robot_id: String,
+    /// Also reset variables that already exist to their defaults.
+    overwrite: bool,
+}
+
+/// The interface conformance check of one robot.
+#[derive(Default)]
+struct InterfaceCheck {
+    robot_id: String,
+    /// Issues found and the number of variables checked, from the last check.
+    result: Option<Result<(Vec<Issue>, usize), String>>,
+    fixed: Option<usize>,
+}
+
+/// What the user asked for from a resource card.
+pub enum ResourceAction {
+    OpenRobotController(String),
+    OpenPathEditor(String),
+    SetEnabled(String, bool),
+    CheckInterface(String),
+}
+
+pub struct ResourcesTab {
+    resources: BTreeMap<String, ResourceInfo>,
+    resources_promise: Option<Promise<BTreeMap<String, ResourceInfo>>>,
+    auto_refresh: bool,
+    last_refresh: Option<Instant>,
+    set_enabled_promise: Option<Promise<()>>,
+    maintenance: MaintenanceLog,
+    note_inputs: HashMap<String, NoteInput>,
+    /// When the resources were last sampled for operating time.
+    last_sample: Option<Instant>,
+    last_maintenance_save: Instant,
+    register_dialog: Option<RegisterRobotDialog>,
+    register_promise: Option<Promise<Result<usize, String>>>,
+    register_status: Option<Result<String, String>>,
+    interface_check: Option<InterfaceCheck>,
+    check_promise: Option<Promise<Result<(Vec<Issue>, usize), String>>>,
+    fix_promise: Option<Promise<usize>>,
+}
+
+impl ResourcesTab {
+    pub fn new() -> Self {
+        Self {
+            resources: BTreeMap::new(),
+            resources_promise: None,
+            auto_refresh: true,
+            last_refresh: None,
+            set_enabled_promise: None,
+            maintenance: MaintenanceLog::load(),
+            note_inputs: HashMap::new(),
+            last_sample: None,
+            last_maintenance_save: Instant::now(),
+            register_dialog: None,
+            register_promise: None,
+            register_status: None,
+            interface_check: None,
+            check_promise: None,
+            fix_promise: None,
+        }
+    }
+
+    /// Robots found in the last refresh.
+    pub fn robot_ids(&self) -> Vec<String> {
+        self.resources
+            .iter()
+            .filter(|(_, info)| info.is_robot)
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    pub fn ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+    ) -> Option<ResourceAction> {
+        if let Some(promise) = self.resources_promise.take() {
+            match promise.try_take() {
+                Ok(resources) => {
+                    self.resources = resources;
+                    self.accumulate_operating_time();
+                }
+                Err(promise) => self.resources_promise = Some(promise),
+            }
+        }
+        if let Some(promise) = self.register_promise.take() {
+            match promise.try_take() {
+                Ok(result) => {
+                    if let Err(e) = &result {
+                        log::error!("Failed to register the robot: {}", e);
+                    }
+                    self.register_status = Some(
+                        result.map(|written| format!("Registered, {} variables written", written)),
+                    );
+                    self.spawn_resources_promise(handle, connection);
+                }
+                Err(promise) => self.register_promise = Some(promise),
+            }
+        }
+        self.poll_interface_check(handle, connection);
+        if let Some(promise) = &self.set_enabled_promise {
+            if promise.ready().is_some() {
+                self.set_enabled_promise = None;
+                self.spawn_resources_promise(handle, connection);
+            }
+        }
+        let due = self
+            .last_refresh
+            .is_none_or(|last| last.elapsed() >= REFRESH_INTERVAL);
+        if self.auto_refresh && due && self.resources_promise.is_none() {
+            self.spawn_resources_promise(handle, connection);
+        }
+
+        ui.horizontal(|ui| {
+            ui.heading("Resources");
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui
+                    .add_enabled(
+                        self.resources_promise.is_none(),
+                        egui::Button::new("Refresh"),
+                    )
+                    .clicked()
+                {
+                    self.spawn_resources_promise(handle, connection);
+                }
+                ui.checkbox(&mut self.auto_refresh, "Auto Refresh");
+                if ui.button("Check Interface").clicked() {
+                    self.interface_check = Some(InterfaceCheck::default());
+                }
+                if ui.button("Register New Robot").clicked() {
+                    self.register_dialog = Some(RegisterRobotDialog::default());
+                    self.register_status = None;
+                }
+                if self.resources_promise.is_some() {
+                    ui.spinner();
+                }
+            });
+        });
+        ui.separator();
+        self.draw_register_dialog(ui.ctx(), handle, connection);
+        self.draw_interface_check(ui.ctx(), handle, connection);
+
+        if self.resources.is_empty() {
+            ui.label(
+                "No resources found. Resources are detected by their *_request_trigger variable.",
+            );
+            return None;
+        }
+
+        let mut action = None;
+        let can_toggle = self.set_enabled_promise.is_none();
+        egui::ScrollArea::vertical()
+            .id_salt("resources_scroll_area")
+            .auto_shrink([false; 2])
+            .show(ui, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    for (name, info) in &self.resources {
+                        let input = self.note_inputs.entry(name.clone()).or_default();
+                        if let Some(a) = draw_resource_card(
+                            ui,
+                            name,
+                            info,
+                            can_toggle,
+                            &mut self.maintenance,
+                            input,
+                        ) {
+                            action = Some(a);
+                        }
+                    }
+                });
+            });
+
+        if let Some(ResourceAction::SetEnabled(resource, enabled)) = action {
+            if let Some(info) = self.resources.get_mut(&resource) {
+                info.enabled = enabled;
+            }
+            let handle = handle.clone();
+            let con_clone = connection.clone();
+            self.set_enabled_promise = Some(workers::spawn("resource_enable", move || {
+                handle.block_on(set_resource_enabled(con_clone, resource, enabled))
+            }));
+            return None;
+        }
+        if let Some(ResourceAction::CheckInterface(robot_id)) = action {
+            self.interface_check = Some(InterfaceCheck {
+                robot_id,
+                ..Default::default()
+            });
+            self.spawn_check_promise(handle, connection);
+            return None;
+        }
+        action
+    }
+
+    fn poll_interface_check(
+        &mut self,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+    ) {
+        if let Some(promise) = self.check_promise.take() {
+            match promise.try_take() {
+                Ok(result) => {
+                    if let Some(check) = &mut self.interface_check {
+                        check.result = Some(result);
+                    }
+                }
+                Err(promise) => self.check_promise = Some(promise),
+            }
+        }
+        if let Some(promise) = self.fix_promise.take() {
+            match promise.try_take() {
+                Ok(written) => {
+                    if let Some(check) = &mut self.interface_check {
+                        check.fixed = Some(written);
+                    }
+                    // Check again so the list shows what is left
+                    self.spawn_check_promise(handle, connection);
+                }
+                Err(promise) => self.fix_promise = Some(promise),
+            }
+        }
+    }
+
+    fn spawn_check_promise(
+        &mut self,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+    ) {
+        let Some(check) = &self.interface_check else {
+            return;
+        };
+        let robot_id = check.robot_id.trim().to_string();
+        let handle = handle.clone();
+        let con_clone = connection.clone();
+        self.check_promise = Some(workers::spawn("interface_check", move || {
+            handle.block_on(robot_interface::check_interface(con_clone, robot_id))
+        }));
+    }
+
+    fn draw_interface_check(
+        &mut self,
+        ctx: &egui::Context,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+    ) {
+        let Some(check) = &mut self.interface_check else {
+            return;
+        };
+        let mut open = true;
+        let mut run_check = false;
+        let mut fix = None;
+        let busy = self.check_promise.is_some() || self.fix_promise.is_some();
+        egui::Window::new("Interface Check")
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.label(
+                    "Checks that every variable the GUI uses for a robot exists with the \
+                     right type.",
+                );
+                ui.horizontal(|ui| {
+                    ui.label("Robot id:");
+                    if ui.text_edit_singleline(&mut check.robot_id).changed() {
+                        check.result = None;
+                        check.fixed = None;
+                    }
+                    let valid = robot_interface::validate_robot_id(check.robot_id.trim()).is_ok();
+                    if ui
+                        .add_enabled(valid && !busy, egui::Button::new("Check"))
+                        .clicked()
+                    {
+                        run_check = true;
+                    }
+                    if busy {
+                        ui.spinner();
+                    }
+                });
+                if let Some(written) = check.fixed {
+                    ui.colored_label(egui::Color32::GREEN, format!("Fixed {} variables", written));
+                }
+                match &check.result {
+                    None => (),
+                    Some(Err(e)) => {
+                        ui.colored_label(egui::Color32::RED, e);
+                    }
+                    Some(Ok((issues, checked))) if issues.is_empty() => {
+                        ui.colored_label(
+                            egui::Color32::GREEN,
+                            format!("All {} variables conform", checked),
+                        );
+                    }
+                    Some(Ok((issues, checked))) => {
+                        ui.colored_label(
+                            egui::Color32::YELLOW,
+                            format!("{} of {} variables have issues", issues.len(), checked),
+                        );
+                        egui::ScrollArea::vertical()
+                            .max_height(300.0)
+                            .show(ui, |ui| {
+                                egui::Grid::new("interface_check_grid")
+                                    .num_columns(3)
+                                    .striped(true)
+                                    .show(ui, |ui| {
+                                        for issue in issues {
+                                            ui.monospace(&issue.key);
+                                            ui.label(issue.description());
+                                            if ui
+                                                .add_enabled(!busy, egui::Button::new("Fix"))
+                                                .on_hover_text(format!(
+                                                    "Write {}",
+                                                    sp_value_to_display(Some(&issue.fix))
+                                                ))
+                                                .clicked()
+                                            {
+                                                fix = Some(vec![issue.clone()]);
+                                            }
+                                            ui.end_row();
+                                        }
+                                    });
+                            });
+                        let mistyped = issues.iter().any(|issue| issue.found.is_some());
+                        let fix_all = ui
+                            .add_enabled(!busy, egui::Button::new("Fix All"))
+                            .on_hover_text(if mistyped {
+                                "Writes the defaults of all listed variables.\n\
+                                 Mistyped values are replaced."
+                            } else {
+                                "Writes the defaults of all listed variables."
+                            });
+                        if fix_all.clicked() {
+                            fix = Some(issues.clone());
+                        }
+                    }
+                }
+            });
+        if let Some(issues) = fix {
+            check.fixed = None;
+            let handle = handle.clone();
+            let con_clone = connection.clone();
+            self.fix_promise = Some(workers::spawn("interface_fix", move || {
+                handle.block_on(robot_interface::fix_issues(con_clone, issues))
+            }));
+        }
+        if run_check {
+            check.fixed = None;
+            self.spawn_check_promise(handle, connection);
+        }
+        if !open {
+            self.interface_check = None;
+        }
+    }
+
+    fn draw_register_dialog(
+        &mut self,
+        ctx: &egui::Context,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+    ) {
+        let Some(dialog) = &mut self.register_dialog else {
+            return;
+        };
+        let mut open = true;
+        let mut register = false;
+        egui::Window::new("Register New Robot")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(
+                    "Writes the robot's interface variables with neutral defaults, \
+                     so the driver and the GUI agree on them from the start.",
+                );
+                ui.horizontal(|ui| {
+                    ui.label("Robot id:");
+                    ui.text_edit_singleline(&mut dialog.robot_id);
+                });
+                let robot_id = dialog.robot_id.trim();
+                let problem = robot_interface::validate_robot_id(robot_id).err();
+                if let Some(problem) = &problem {
+                    ui.colored_label(egui::Color32::YELLOW, problem);
+                } else if self.resources.contains_key(robot_id) {
+                    ui.colored_label(
+                        egui::Color32::YELLOW,
+                        format!("{} already exists", robot_id),
+                    );
+                }
+                ui.checkbox(
+                    &mut dialog.overwrite,
+                    "Reset existing variables to defaults",
+                )
+                .on_hover_text("Otherwise only missing variables are written");
+                egui::CollapsingHeader::new(format!("{} variables", INTERFACE.len()))
+                    .id_salt("register_robot_variables")
+                    .show(ui, |ui| {
+                        egui::ScrollArea::vertical()
+                            .max_height(240.0)
+                            .show(ui, |ui| {
+                                egui::Grid::new("register_robot_grid")
+                                    .num_columns(3)
+                                    .striped(true)
+                                    .show(ui, |ui| {
+                                        for variable in INTERFACE {
+                                            ui.monospace(variable.key(robot_id));
+                                            ui.label(variable.variable_type.to_string());
+                                            ui.monospace(sp_value_to_display(Some(
+                                                &variable.default_value(),
+                                            )));
+                                            ui.end_row();
+                                        }
+                                    });
+                            });
+                    });
+                ui.horizontal(|ui| {
+                    let busy = self.register_promise.is_some();
+                    if ui
+                        .add_enabled(problem.is_none() && !busy, egui::Button::new("Register"))
+                        .clicked()
+                    {
+                        register = true;
+                    }
+                    if busy {
+                        ui.spinner();
+                    }
+                    match &self.register_status {
+                        Some(Ok(message)) => {
+                            ui.colored_label(egui::Color32::GREEN, message);
+                        }
+                        Some(Err(e)) => {
+                            ui.colored_label(egui::Color32::RED, e);
+                        }
+                        None => (),
+                    }
+                });
+            });
+        if register {
+            let robot_id = dialog.robot_id.trim().to_string();
+            let overwrite = dialog.overwrite;
+            self.register_status = None;
+            let handle = handle.clone();
+            let con_clone = connection.clone();
+            self.register_promise = Some(workers::spawn("register_robot", move || {
+                handle.block_on(robot_interface::register_robot(
+                    con_clone, robot_id, overwrite,
+                ))
+            }));
+        }
+        if !open {
+            self.register_dialog = None;
+        }
+    }
+
+    /// Counts the time since the previous sample towards every resource that is
+    /// executing now, so maintenance reminders follow actual operating hours.
+    fn accumulate_operating_time(&mut self) {
+        let now = Instant::now();
+        if let Some(last) = self.last_sample {
+            let dt = now.duration_since(last).as_secs_f64();
+            for (name, info) in &self.resources {
+                let request_state =
+                    sp_value_to_display(info.variables.get(&format!("{}_request_state", name)));
+                if request_state == "executing" {
+                    self.maintenance.add_operating_time(name, dt);
+                }
+            }
+        }
+        self.last_sample = Some(now);
+        if self.last_maintenance_save.elapsed() >= MAINTENANCE_SAVE_INTERVAL {
+            self.maintenance.save();
+            self.last_maintenance_save = now;
+        }
+    }
+
+    fn spawn_resources_promise(
+        &mut self,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+    ) {
+        self.last_refresh = Some(Instant::now());
+        let handle = handle.clone();
+        let con_clone = connection.clone();
+        self.resources_promise = Some(workers::spawn("resources", move || {
+            handle.block_on(get_resources(con_clone))
+        }));
+    }
+}
+
+fn draw_resource_card(
+    ui: &mut egui::Ui,
+    name: &str,
+    info: &ResourceInfo,
+    can_toggle: bool,
+    maintenance_log: &mut MaintenanceLog,
+    note_input: &mut NoteInput,
+) -> Option<ResourceAction> {
+    let mut action = None;
+    let request_state = sp_value_to_display(info.variables.get(&format!("{}_request_state", name)));
+    let failed = request_state == "failed";
+    let border = if !info.enabled {
+        egui::Color32::from_rgb(230, 160, 40)
+    } else if failed {
+        egui::Color32::RED
+    } else {
+        egui::Color32::DARK_GRAY
+    };
+
+    egui::Frame::default()
+        .inner_margin(egui::Margin::same(8))
+        .stroke(egui::Stroke::new(1.0, border))
+        .fill(if info.enabled {
+            egui::Color32::TRANSPARENT
+        } else {
+            ui.visuals().faint_bg_color
+        })
+        .show(ui, |ui| {
+            ui.set_width(220.0);
+            ui.vertical(|ui| {
+                ui.horizontal(|ui| {
+                    ui.heading(name);
+                    if !info.enabled {
+                        ui.colored_label(border, "DISABLED");
+                    }
+                    if maintenance_log
+                        .resources
+                        .get(name)
+                        .is_some_and(|m| m.is_service_due())
+                    {
+                        ui.colored_label(egui::Color32::YELLOW, "SERVICE DUE");
+                    }
+                });
+                let mut enabled = info.enabled;
+                if ui
+                    .add_enabled(can_toggle, egui::Checkbox::new(&mut enabled, "Enabled"))
+                    .on_hover_text(
+                        "Disabled resources are skipped by planning and GUI commands,\n\
+                         e.g. while under maintenance.",
+                    )
+                    .changed()
+                {
+                    action = Some(ResourceAction::SetEnabled(name.to_string(), enabled));
+                }
+                egui::Grid::new(format!("resource_card_{}", name))
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        for (key, value) in &info.variables {
+                            let short = key
+                                .strip_prefix(name)
+                                .unwrap_or(key)
+                                .trim_start_matches('_');
+                            ui.label(short);
+                            ui.monospace(sp_value_to_display(Some(value)));
+                            ui.end_row();
+                        }
+                    });
+                if info.is_robot {
+                    ui.add_enabled_ui(info.enabled, |ui| {
+                        if ui.button("Robot Controller").clicked() {
+                            action = Some(ResourceAction::OpenRobotController(name.to_string()));
+                        }
+                        if ui.button("Path Editor").clicked() {
+                            action = Some(ResourceAction::OpenPathEditor(name.to_string()));
+                        }
+                        if ui.button("Check Interface").clicked() {
+                            action = Some(ResourceAction::CheckInterface(name.to_string()));
+                        }
+                    });
+                }
+                maintenance::draw_maintenance_section(ui, maintenance_log, name, note_input);
+            });
+        });
+    action
+}
@@ -0,0 +1,431 @@
+//! Guided re-teach after a fixture is replaced: the frames under the fixture
+//! frame are walked through in order, each captured again at the TCP and
+//! compared to the old pose before it is overwritten, ending with a report
+//! of how far every frame moved.
+
+use crate::backend;
+use crate::locale;
+use crate::measure::Measurement;
+use crate::pose::Pose;
+use crate::provenance::{self, Method};
+use crate::workers;
+use eframe::egui;
+use micro_sp::*;
+use poll_promise::Promise;
+use rfd::FileDialog;
+use std::sync::Arc;
+
+/// Frames directly under `fixture`, sorted by name.
+async fn fixture_frames(
+    con: Arc<ConnectionManager>,
+    fixture: String,
+) -> Result<Vec<SPTransformStamped>, String> {
+    let mut connection = con.get_connection().await;
+    let transforms = TransformsManager::get_all_transforms(&mut connection)
+        .await
+        .map_err(|e| format!("Failed to get all transforms: {}", e))?;
+    let mut frames = transforms
+        .into_values()
+        .filter(|t| t.parent_frame_id == fixture)
+        .collect::<Vec<SPTransformStamped>>();
+    frames.sort_by(|a, b| a.child_frame_id.cmp(&b.child_frame_id));
+    Ok(frames)
+}
+
+/// Overwrites the pose of the frame, keeping its parent and metadata.
+async fn publish_new_pose(
+    con: Arc<ConnectionManager>,
+    mut transform: SPTransformStamped,
+    pose: Pose,
+) -> Result<String, String> {
+    transform.transform = pose.to_sp_transform();
+    provenance::stamp(&mut transform, Method::Taught);
+    crate::transforms::publish_transform(con, transform).await
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Retaught,
+    Skipped,
+}
+
+struct ReteachItem {
+    old: SPTransformStamped,
+    new: Option<Pose>,
+    outcome: Option<Outcome>,
+}
+
+impl ReteachItem {
+    fn name(&self) -> &str {
+        &self.old.child_frame_id
+    }
+
+    fn old_pose(&self) -> Pose {
+        Pose::from_sp_transform(&self.old.transform)
+    }
+
+    /// The new pose relative to the old one.
+    fn delta(&self) -> Option<Measurement> {
+        let new = self.new.as_ref()?;
+        Some(Measurement::new(&self.old_pose().inverse().compose(new)))
+    }
+}
+
+fn mm(meters: f64) -> String {
+    locale::format_number(meters * 1000.0, 2)
+}
+
+fn pose_text(pose: &Pose) -> String {
+    let rpy = crate::pose::quaternion_to_rpy(pose.rotation).map(f64::to_degrees);
+    format!(
+        "x {} y {} z {} mm, r {} p {} y {} °",
+        mm(pose.translation[0]),
+        mm(pose.translation[1]),
+        mm(pose.translation[2]),
+        locale::format_number(rpy[0], 2),
+        locale::format_number(rpy[1], 2),
+        locale::format_number(rpy[2], 2)
+    )
+}
+
+pub struct ReteachWizard {
+    fixture: Option<String>,
+    items: Vec<ReteachItem>,
+    /// Index of the frame being re-taught, `items.len()` once all are done.
+    current: usize,
+    load_promise: Option<Promise<Result<Vec<SPTransformStamped>, String>>>,
+    capture_promise: Option<Promise<Result<Pose, String>>>,
+    publish_promise: Option<Promise<Result<String, String>>>,
+    error: Option<String>,
+    report_status: Option<Result<String, String>>,
+}
+
+impl ReteachWizard {
+    pub fn new() -> Self {
+        Self {
+            fixture: None,
+            items: Vec::new(),
+            current: 0,
+            load_promise: None,
+            capture_promise: None,
+            publish_promise: None,
+            error: None,
+            report_status: None,
+        }
+    }
+
+    fn busy(&self) -> bool {
+        self.load_promise.is_some()
+            || self.capture_promise.is_some()
+            || self.publish_promise.is_some()
+    }
+
+    fn poll(&mut self) {
+        if let Some(promise) = self.load_promise.take() {
+            match promise.try_take() {
+                Ok(Ok(frames)) if frames.is_empty() => {
+                    self.error = Some("The fixture frame has no frames under it".to_string());
+                }
+                Ok(Ok(frames)) => {
+                    self.items = frames
+                        .into_iter()
+                        .map(|old| ReteachItem {
+                            old,
+                            new: None,
+                            outcome: None,
+                        })
+                        .collect();
+                    self.current = 0;
+                    self.report_status = None;
+                }
+                Ok(Err(e)) => self.error = Some(e),
+                Err(promise) => self.load_promise = Some(promise),
+            }
+        }
+        if let Some(promise) = self.capture_promise.take() {
+            match promise.try_take() {
+                Ok(Ok(pose)) => {
+                    if let Some(item) = self.items.get_mut(self.current) {
+                        item.new = Some(pose);
+                    }
+                }
+                Ok(Err(e)) => self.error = Some(e),
+                Err(promise) => self.capture_promise = Some(promise),
+            }
+        }
+        if let Some(promise) = self.publish_promise.take() {
+            match promise.try_take() {
+                Ok(Ok(message)) => {
+                    log::info!("Re-teach: {}", message);
+                    if let Some(item) = self.items.get_mut(self.current) {
+                        item.outcome = Some(Outcome::Retaught);
+                    }
+                    self.current += 1;
+                }
+                Ok(Err(e)) => self.error = Some(e),
+                Err(promise) => self.publish_promise = Some(promise),
+            }
+        }
+    }
+
+    /// `tcp` is the TCP from the Robot tab, which is moved onto each frame.
+    pub fn draw(
+        &mut self,
+        ui: &mut egui::Ui,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+        tcp: Option<String>,
+        transform_keys: &[String],
+    ) {
+        self.poll();
+        if self.items.is_empty() {
+            self.draw_start(ui, handle, connection, transform_keys);
+        } else if self.current < self.items.len() {
+            self.draw_step(ui, handle, connection, tcp);
+        } else {
+            self.draw_report(ui);
+        }
+        if self.busy() {
+            ui.spinner();
+        }
+        if let Some(e) = &self.error {
+            ui.colored_label(egui::Color32::RED, e);
+        }
+    }
+
+    fn draw_start(
+        &mut self,
+        ui: &mut egui::Ui,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+        transform_keys: &[String],
+    ) {
+        ui.label(
+            "Re-teaches the frames under a fixture frame one by one, for after the \
+             fixture was replaced.",
+        );
+        ui.horizontal(|ui| {
+            ui.label("Fixture:");
+            egui::ComboBox::from_id_salt("reteach_fixture_select")
+                .selected_text(self.fixture.as_deref().unwrap_or("Select..."))
+                .show_ui(ui, |ui| {
+                    for key in transform_keys {
+                        ui.selectable_value(&mut self.fixture, Some(key.clone()), key);
+                    }
+                });
+            let start = ui
+                .add_enabled(
+                    self.fixture.is_some() && !self.busy(),
+                    egui::Button::new("Start Re-teach"),
+                )
+                .clicked();
+            if let (true, Some(fixture)) = (start, self.fixture.clone()) {
+                self.error = None;
+                let handle = handle.clone();
+                let con_clone = connection.clone();
+                self.load_promise = Some(workers::spawn("reteach_load", move || {
+                    handle.block_on(fixture_frames(con_clone, fixture))
+                }));
+            }
+        });
+    }
+
+    fn draw_step(
+        &mut self,
+        ui: &mut egui::Ui,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+        tcp: Option<String>,
+    ) {
+        let busy = self.busy();
+        let total = self.items.len();
+        let item = &self.items[self.current];
+        ui.strong(format!(
+            "Frame {} of {}: {} in {}",
+            self.current + 1,
+            total,
+            item.name(),
+            item.old.parent_frame_id
+        ));
+        egui::Grid::new("reteach_step_grid")
+            .num_columns(2)
+            .spacing([20.0, 4.0])
+            .show(ui, |ui| {
+                ui.label("Old pose:");
+                ui.monospace(pose_text(&item.old_pose()));
+                ui.end_row();
+                if let (Some(new), Some(delta)) = (&item.new, item.delta()) {
+                    ui.label("New pose:");
+                    ui.monospace(pose_text(new));
+                    ui.end_row();
+                    ui.label("Delta:");
+                    ui.monospace(format!(
+                        "{} mm, {} ° (x {} y {} z {} mm)",
+                        mm(delta.distance),
+                        locale::format_number(delta.angle, 2),
+                        mm(delta.delta[0]),
+                        mm(delta.delta[1]),
+                        mm(delta.delta[2])
+                    ));
+                    ui.end_row();
+                }
+            });
+        let captured = item.new.clone();
+        let mut capture = false;
+        let mut confirm = false;
+        let mut skip = false;
+        let mut abort = false;
+        ui.horizontal(|ui| {
+            let capture_text = if captured.is_some() {
+                "Capture Again"
+            } else {
+                "Capture"
+            };
+            capture = ui
+                .add_enabled(!busy && tcp.is_some(), egui::Button::new(capture_text))
+                .on_hover_text("Move the TCP onto the new fixture, then capture its pose.")
+                .on_disabled_hover_text("Select the TCP")
+                .clicked();
+            confirm = ui
+                .add_enabled(!busy && captured.is_some(), egui::Button::new("Confirm"))
+                .on_hover_text("Overwrite the frame with the new pose.")
+                .clicked();
+            skip = ui
+                .add_enabled(!busy, egui::Button::new("Skip"))
+                .on_hover_text("Keep the old pose.")
+                .clicked();
+            abort = ui.add_enabled(!busy, egui::Button::new("Abort")).clicked();
+        });
+        if capture {
+            if let Some(tcp) = tcp {
+                self.error = None;
+                let parent = item.old.parent_frame_id.clone();
+                let handle = handle.clone();
+                let con_clone = connection.clone();
+                self.capture_promise = Some(workers::spawn("reteach_capture", move || {
+                    handle.block_on(backend::lookup(con_clone, &parent, &tcp))
+                }));
+            }
+        }
+        if let (true, Some(pose)) = (confirm, captured) {
+            self.error = None;
+            let old = item.old.clone();
+            let handle = handle.clone();
+            let con_clone = connection.clone();
+            self.publish_promise = Some(workers::spawn("reteach_publish", move || {
+                handle.block_on(publish_new_pose(con_clone, old, pose))
+            }));
+        }
+        if skip {
+            self.error = None;
+            self.items[self.current].outcome = Some(Outcome::Skipped);
+            self.current += 1;
+        }
+        if abort {
+            log::info!(
+                "Re-teach aborted after {} of {} frames",
+                self.current,
+                self.items.len()
+            );
+            self.items.clear();
+            self.error = None;
+        }
+    }
+
+    fn draw_report(&mut self, ui: &mut egui::Ui) {
+        ui.strong(format!(
+            "Re-teach of {} done",
+            self.fixture.as_deref().unwrap_or("the fixture")
+        ));
+        egui::Grid::new("reteach_report_grid")
+            .num_columns(4)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("Frame");
+                ui.strong("Outcome");
+                ui.strong("Moved");
+                ui.strong("Rotated");
+                ui.end_row();
+                for item in &self.items {
+                    ui.monospace(item.name());
+                    match (item.outcome, item.delta()) {
+                        (Some(Outcome::Retaught), Some(delta)) => {
+                            ui.label("Re-taught");
+                            ui.monospace(format!("{} mm", mm(delta.distance)));
+                            ui.monospace(format!("{} °", locale::format_number(delta.angle, 2)));
+                        }
+                        _ => {
+                            ui.weak("Skipped");
+                            ui.weak("-");
+                            ui.weak("-");
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+        ui.horizontal(|ui| {
+            if ui.button("Save Report…").clicked() {
+                self.report_status = self.save_report().transpose();
+            }
+            if ui.button("Done").clicked() {
+                self.items.clear();
+                self.report_status = None;
+            }
+        });
+        match &self.report_status {
+            None => (),
+            Some(Ok(message)) => {
+                ui.colored_label(egui::Color32::GREEN, message);
+            }
+            Some(Err(e)) => {
+                ui.colored_label(egui::Color32::RED, e);
+            }
+        }
+    }
+
+    /// The report as CSV, one line per frame with the delta in its old axes.
+    fn report_csv(&self) -> String {
+        let mut csv =
+            "frame,parent_frame_id,outcome,dx_mm,dy_mm,dz_mm,distance_mm,angle_deg\n".to_string();
+        for item in &self.items {
+            let outcome = match item.outcome {
+                Some(Outcome::Retaught) => "retaught",
+                _ => "skipped",
+            };
+            let values = match (item.outcome, item.delta()) {
+                (Some(Outcome::Retaught), Some(d)) => [
+                    d.delta[0] * 1000.0,
+                    d.delta[1] * 1000.0,
+                    d.delta[2] * 1000.0,
+                    d.distance * 1000.0,
+                    d.angle,
+                ]
+                .map(|v| format!("{:.3}", v))
+                .join(","),
+                _ => ",,,,".to_string(),
+            };
+            csv.push_str(&format!(
+                "{},{},{},{}\n",
+                item.name(),
+                item.old.parent_frame_id,
+                outcome,
+                values
+            ));
+        }
+        csv
+    }
+
+    fn save_report(&self) -> Result<Option<String>, String> {
+        let stem = format!("{}_reteach", self.fixture.as_deref().unwrap_or("fixture"));
+        let Some(path) = FileDialog::new()
+            .add_filter("CSV", &["csv"])
+            .set_file_name(format!("{}.csv", stem))
+            .save_file()
+        else {
+            return Ok(None);
+        };
+        std::fs::write(&path, self.report_csv())
+            .map_err(|e| format!("Failed to save the report: {}", e))?;
+        Ok(Some(format!("Saved {}", path.display())))
+    }
+}
@@ -0,0 +1,175 @@
+//! Automatic retries of robot commands that fail for transient reasons. The
+//! policy is configured per command type in the settings and applied wherever
+//! the GUI sends a command and waits for it.
+
+use crate::backend::{self, RequestError};
+use crate::history::{self, HistoryEntry};
+use micro_sp::*;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    sync::{
+        Arc, RwLock,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+static POLICIES: RwLock<Option<RetryPolicies>> = RwLock::new(None);
+
+/// How long a timed out goal may take to acknowledge its cancel before it is
+/// sent again.
+const CANCEL_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Why a command failed, as far as retrying is concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorClass {
+    /// The driver reported `failed`.
+    Failed,
+    /// The driver didn't finish in time.
+    Timeout,
+}
+
+impl ErrorClass {
+    pub const ALL: [ErrorClass; 2] = [ErrorClass::Failed, ErrorClass::Timeout];
+}
+
+impl From<&RequestError> for ErrorClass {
+    fn from(error: &RequestError) -> Self {
+        match error {
+            RequestError::Failed => ErrorClass::Failed,
+            RequestError::TimedOut => ErrorClass::Timeout,
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ErrorClass::Failed => write!(f, "failed"),
+            ErrorClass::Timeout => write!(f, "timeout"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    /// Wait before the first retry.
+    pub backoff_secs: f64,
+    /// Each further wait is this many times longer than the previous one.
+    pub backoff_factor: f64,
+    pub retry_on: Vec<ErrorClass>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 0,
+            backoff_secs: 1.0,
+            backoff_factor: 2.0,
+            retry_on: vec![ErrorClass::Failed],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Wait before the 1-based `retry`.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let secs = self.backoff_secs * self.backoff_factor.max(1.0).powi(retry as i32 - 1);
+        Duration::from_secs_f64(secs.clamp(0.0, 600.0))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicies {
+    /// For command types without their own policy.
+    pub default: RetryPolicy,
+    /// By command type, e.g. `unsafe_move_l`.
+    pub commands: BTreeMap<String, RetryPolicy>,
+}
+
+impl RetryPolicies {
+    pub fn for_command(&self, command_type: &str) -> &RetryPolicy {
+        self.commands.get(command_type).unwrap_or(&self.default)
+    }
+}
+
+pub fn set_policies(policies: RetryPolicies) {
+    *POLICIES.write().unwrap() = Some(policies);
+}
+
+pub fn policy_for(command_type: &str) -> RetryPolicy {
+    POLICIES
+        .read()
+        .unwrap()
+        .as_ref()
+        .map(|policies| policies.for_command(command_type).clone())
+        .unwrap_or_default()
+}
+
+/// Whether Stop cancelled the robot's goal. Every command clears
+/// `cancel_current_goal` when it is sent, so it is only set by a later Stop.
+async fn goal_cancelled(con: Arc<ConnectionManager>, robot_id: &str) -> bool {
+    matches!(
+        backend::get_value(con, &format!("{}_cancel_current_goal", robot_id)).await,
+        Some(SPValue::Bool(BoolOrUnknown::Bool(true)))
+    )
+}
+
+/// Cancels a command that timed out, since its goal may still be running.
+async fn cancel_request(con: Arc<ConnectionManager>, robot_id: &str) {
+    backend::set_values(
+        con.clone(),
+        vec![(format!("{}_request_cancel", robot_id), true.to_spvalue())],
+    )
+    .await;
+    let _ = backend::await_request(con, robot_id, CANCEL_TIMEOUT).await;
+}
+
+/// Sends the command and waits for it, retrying per `policy`. Every retry is
+/// recorded in the command history. Nothing is sent again once `cancel` or
+/// the robot's `cancel_current_goal` is set, so a retry never undoes Stop.
+/// Returns how many retries were made.
+pub async fn send_with_retry(
+    state: &State,
+    con: Arc<ConnectionManager>,
+    entry: &HistoryEntry,
+    policy: &RetryPolicy,
+    timeout: Duration,
+    cancel: &AtomicBool,
+) -> Result<u32, (String, u32)> {
+    let robot_id = entry.robot_id.as_str();
+    let mut retry = 0;
+    loop {
+        let error =
+            match crate::robot::send_robot_command_and_await(state, con.clone(), robot_id, timeout)
+                .await
+            {
+                Ok(()) => return Ok(retry),
+                Err(error) => error,
+            };
+        let class = ErrorClass::from(&error);
+        let e = crate::robot::request_error_text(&error, robot_id, timeout);
+        if retry >= policy.max_retries || !policy.retry_on.contains(&class) {
+            return Err((e, retry));
+        }
+        if class == ErrorClass::Timeout {
+            cancel_request(con.clone(), robot_id).await;
+        }
+        tokio::time::sleep(policy.backoff(retry + 1)).await;
+        if cancel.load(Ordering::Relaxed) || goal_cancelled(con.clone(), robot_id).await {
+            return Err((format!("{}, not retried after Stop", e), retry));
+        }
+        retry += 1;
+        log::warn!("{}, retry {} of {}", e, retry, policy.max_retries);
+        history::record(&HistoryEntry {
+            timestamp: crate::storage::now_secs(),
+            retry,
+            ..entry.clone()
+        });
+    }
+}
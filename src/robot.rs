@@ -1,44 +1,92 @@
+use crate::access::{self, FieldLocks, LockableField};
+use crate::aliases::{self, FrameAliases};
+use crate::backend::RequestError;
+use crate::busy_overlay::Busy;
+use crate::capabilities::{self, DriverCapabilities};
+use crate::command_feedback::{self, CommandFeedback};
+use crate::command_lint::{self, CommandParams};
+use crate::command_queue::CommandQueue;
+use crate::commissioning::{TestMove, TestMoveFrames};
+use crate::dashboard::DashboardPanel;
+use crate::expression::Unit;
+use crate::gripper::{self, GripperPanel};
+use crate::history::{self, FrameUsage, HistoryEntry};
+use crate::jog::JogPanel;
+use crate::joint_drift;
+use crate::joint_limits::{JointLimits, SoftLimits};
+use crate::joint_presets::{JointPresetKind, JointPresets};
+use crate::locale::{self, LocalizedDragValue};
+use crate::payloads::{self, PayloadPreset, PayloadPresets};
+use crate::presets::{CommandPreset, PresetPanel, PresetRun};
+use crate::preview::{PreviewRequest, PreviewWindow};
+use crate::reteach::ReteachWizard;
+use crate::screwdriving::{ProcessResult, ScrewParams};
+use crate::search_insertion::SearchParams;
+use crate::sequence::{Run, Step};
+use crate::speed_monitor::{self, SpeedMonitor};
+use crate::state_poller::{StatePoller, sp_value_to_display};
+use crate::teach::TeachSeries;
+use crate::vibration::{self, VibrationMonitor};
+use crate::waypoint_program::ProgramPanel;
+use crate::workers;
 use eframe::egui;
 use micro_sp::*;
 use ordered_float::OrderedFloat;
 use poll_promise::Promise;
-use std::{collections::HashMap, fmt, sync::Arc};
-
-#[derive(Debug, Clone, PartialEq)]
-enum SavedPayload {
-    Gripper,
-    Svt,
-    Bvt,
-    Photoneo,
-    Sponge,
-    None,
-}
-
-impl std::fmt::Display for SavedPayload {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            SavedPayload::Gripper => write!(f, "gripper"),
-            SavedPayload::Svt => write!(f, "svt"),
-            SavedPayload::Bvt => write!(f, "bvt"),
-            SavedPayload::Photoneo => write!(f, "photoneo"),
-            SavedPayload::Sponge => write!(f, "sponge"),
-            SavedPayload::None => write!(f, "none"),
-        }
-    }
-}
-
-impl SavedPayload {
-    fn variants() -> &'static [SavedPayload] {
-        &[
-            SavedPayload::None,
-            SavedPayload::Gripper,
-            SavedPayload::Svt,
-            SavedPayload::Bvt,
-            SavedPayload::Photoneo,
-            SavedPayload::Sponge,
-        ]
-    }
-}
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    sync::Arc,
+};
+
+/// Every variable `robot_command_tab_to_state` writes, without the robot prefix.
+/// The frame ids are only written with motion commands, the search parameters
+/// only with the spiral search and the spindle program only with screwdriving.
+const COMMAND_VARIABLES: &[&str] = &[
+    "request_trigger",
+    "request_cancel",
+    "request_state",
+    "cancel_current_goal",
+    "command_type",
+    "accelleration",
+    "velocity",
+    "use_execution_time",
+    "execution_time",
+    "use_blend_radius",
+    "blend_radius",
+    "use_joint_positions",
+    "joint_positions",
+    "use_preferred_joint_config",
+    "preferred_joint_config",
+    "use_payload",
+    "payload",
+    "payload_mass",
+    "payload_cog",
+    "payload_inertia",
+    "baseframe_id",
+    "faceplate_id",
+    "goal_feature_id",
+    "tcp_id",
+    "root_frame_id",
+    "force_threshold",
+    "use_relative_pose",
+    "relative_pose",
+    "search_radius",
+    "search_pitch",
+    "search_max_force",
+    "search_timeout",
+    "screw_program",
+    "screw_torque_target",
+];
+
+/// How long one run of a repeated preset may take.
+const PRESET_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(300);
+
+/// How long a screwdriving command may take before its result is read.
+const PROCESS_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Sent as the payload name when the values are set manually.
+const MANUAL_PAYLOAD: &str = "manual";
 
 /// Represents a manual payload configuration.
 #[derive(Debug, Clone)]
@@ -75,6 +123,24 @@ impl Default for Payload {
     }
 }
 
+impl From<&PayloadPreset> for Payload {
+    fn from(preset: &PayloadPreset) -> Self {
+        let [ixx, iyy, izz, ixy, ixz, iyz] = preset.inertia;
+        Self {
+            mass: preset.mass,
+            cog_x: preset.cog[0],
+            cog_y: preset.cog[1],
+            cog_z: preset.cog[2],
+            ixx,
+            iyy,
+            izz,
+            ixy,
+            ixz,
+            iyz,
+        }
+    }
+}
+
 impl fmt::Display for Payload {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
@@ -106,10 +172,77 @@ async fn get_all_transforms(con: Arc<ConnectionManager>) -> HashMap<String, SPTr
 }
 
 async fn send_robot_command(state: &State, con: Arc<ConnectionManager>) -> () {
+    crate::recorder::record_state(state);
+    // Recorded with the alias so a replay resolves it again
+    let state = aliases::resolve_goals(con.clone(), state).await;
+    let mut connection = con.get_connection().await;
+    StateManager::set_state(&mut connection, &state).await;
+}
+
+/// Cancels the goal the robot is running and stops it through the dashboard,
+/// without touching the command parameters. Closing reuses it to stop robots.
+pub async fn cancel_current_goal(con: Arc<ConnectionManager>, robot_name: String) {
+    let state = State::new()
+        .add(assign!(
+            bv!(&&format!("{}_cancel_current_goal", robot_name)),
+            true.to_spvalue()
+        ))
+        .add(assign!(
+            bv!(&&format!("{}_dashboard_request_trigger", robot_name)),
+            true.to_spvalue()
+        ))
+        .add(assign!(
+            v!(&&format!("{}_dashboard_request_state", robot_name)),
+            "initial".to_spvalue()
+        ))
+        .add(assign!(
+            v!(&&format!("{}_dashboard_command", robot_name)),
+            "stop".to_spvalue()
+        ));
+    crate::recorder::record_state(&state);
     let mut connection = con.get_connection().await;
     StateManager::set_state(&mut connection, &state).await;
 }
 
+/// Sends a command and waits until `{robot}_request_state` reports that the
+/// runner is done with it. Used where commands have to be chained.
+pub async fn send_robot_command_and_wait(
+    state: &State,
+    con: Arc<ConnectionManager>,
+    robot_id: &str,
+    timeout: std::time::Duration,
+) -> Result<(), String> {
+    send_robot_command_and_await(state, con, robot_id, timeout)
+        .await
+        .map_err(|e| request_error_text(&e, robot_id, timeout))
+}
+
+/// Like `send_robot_command_and_wait`, with the error for deciding on a retry.
+pub async fn send_robot_command_and_await(
+    state: &State,
+    con: Arc<ConnectionManager>,
+    robot_id: &str,
+    timeout: std::time::Duration,
+) -> Result<(), RequestError> {
+    send_robot_command(state, con.clone()).await;
+    crate::backend::await_request(con, robot_id, timeout).await
+}
+
+pub fn request_error_text(
+    error: &RequestError,
+    robot_id: &str,
+    timeout: std::time::Duration,
+) -> String {
+    match error {
+        RequestError::Failed => format!("Command failed for robot {}", robot_id),
+        RequestError::TimedOut => format!(
+            "Timed out after {:.1} s waiting for robot {}",
+            timeout.as_secs_f64(),
+            robot_id
+        ),
+    }
+}
+
 // --- RobotTab Specific ---
 
 #[derive(Debug, Clone, PartialEq)]
@@ -120,6 +253,8 @@ enum CommandType {
     SafeMoveJ,
     PickVacuum,
     PlaceVacuum,
+    SpiralSearch,
+    Screwdrive,
 }
 
 impl std::fmt::Display for CommandType {
@@ -131,11 +266,20 @@ impl std::fmt::Display for CommandType {
             CommandType::SafeMoveJ => write!(f, "safe_move_j"),
             CommandType::PickVacuum => write!(f, "pick_vacuum"),
             CommandType::PlaceVacuum => write!(f, "place_vacuum"),
+            CommandType::SpiralSearch => write!(f, "spiral_search"),
+            CommandType::Screwdrive => write!(f, "screwdrive"),
         }
     }
 }
 
 impl CommandType {
+    fn from_name(name: &str) -> Option<CommandType> {
+        Self::variants()
+            .iter()
+            .find(|v| v.to_string() == name)
+            .cloned()
+    }
+
     fn variants() -> &'static [CommandType] {
         &[
             CommandType::UnsafeMoveL,
@@ -144,6 +288,8 @@ impl CommandType {
             CommandType::SafeMoveJ,
             CommandType::PickVacuum,
             CommandType::PlaceVacuum,
+            CommandType::SpiralSearch,
+            CommandType::Screwdrive,
         ]
     }
 }
@@ -153,7 +299,29 @@ pub struct RobotTab {
     robot_id_input: String,
     get_all_transforms_promise: Option<Promise<HashMap<String, SPTransformStamped>>>,
     robot_control_promise: Option<Promise<()>>,
+    /// Dashboard command of the header row and its result. It doesn't block
+    /// the tab.
+    dashboard_promise: Option<Promise<(&'static str, Result<String, String>)>>,
+    dashboard_error: Option<String>,
+    /// Bottom of the header row with Stop, Pause and Reset Protective Stop,
+    /// which the busy overlay leaves uncovered.
+    controls_bottom: f32,
+    stop_promise: Option<Promise<()>>,
+    resource_enabled: bool,
+    /// Why commands can't be sent right now, e.g. an open cell door.
+    command_blocked: Option<String>,
+    driver_capabilities: Option<DriverCapabilities>,
+    /// Last read `{robot}_joint_states`, in radians.
+    live_joints: Option<Vec<f64>>,
+    jog: JogPanel,
+    /// Jog vector of the input devices, each axis in -1..1.
+    device_jog: [f64; 6],
+    /// Polled global velocity and acceleration scaling.
+    live_scaling: (Option<f64>, Option<f64>),
     transform_keys: Vec<String>,
+    /// Preferred joint configuration metadata of the fetched frames.
+    taught_joints: HashMap<String, Vec<f64>>,
+    frame_usage: HashMap<String, FrameUsage>,
     selected_goal_feature_id: Option<String>,
     tcp_keys: Vec<String>,
     selected_tcp: Option<String>,
@@ -164,13 +332,11 @@ pub struct RobotTab {
     // --- Command State ---
     command_type: CommandType,
     command_trigger: bool,
-    dashboard_command: String,
-    dashboard_trigger: bool,
+    /// Set once the dashboard pause succeeded, until continuing did.
+    dashboard_paused: bool,
     cancel_request: bool,
     acceleration: f64,
     velocity: f64,
-    global_acceleration_scaling: f64,
-    global_velocity_scaling: f64,
 
     // --- New Blend/Joint State ---
     use_blend_radius: bool,
@@ -178,13 +344,19 @@ pub struct RobotTab {
     use_joint_positions: bool,
     set_manual_joint_positions: bool,
     joint_positions: [f64; 6],
+    saved_joint_positions: Option<String>,
     use_preferred_joint_config: bool,
     preferred_joint_config: [f64; 6],
     set_manual_joint_config: bool,
+    saved_joint_config: Option<String>,
+    joint_presets: JointPresets,
+    soft_limits: SoftLimits,
+    field_locks: FieldLocks,
 
     use_payload: bool,
     set_manual_payload: bool,
-    saved_payload: SavedPayload,
+    saved_payload: String,
+    payload_presets: PayloadPresets,
     manual_payload: Payload,
 
     use_execution_time: bool,
@@ -192,6 +364,34 @@ pub struct RobotTab {
     force_threshold: f64,
     use_relative_pose: bool,
     relative_pose: [f64; 6],
+    search: SearchParams,
+    screw: ScrewParams,
+    /// Screwdriving commands wait for the spindle and read its result.
+    process_promise: Option<Promise<Result<ProcessResult, String>>>,
+    last_process: Option<Result<ProcessResult, String>>,
+
+    // --- Debugging the driver interface ---
+    show_written_variables: bool,
+    /// What the last command wrote, by full key, and when.
+    last_written: BTreeMap<String, SPValue>,
+    last_written_at: Option<u64>,
+
+    preview: PreviewWindow,
+    speed_monitor: SpeedMonitor,
+    gripper: GripperPanel,
+    vibration: VibrationMonitor,
+    command_feedback: CommandFeedback,
+    command_queue: CommandQueue,
+    aliases: FrameAliases,
+    waypoint_program: ProgramPanel,
+    dashboard: DashboardPanel,
+    test_move: TestMove,
+    teach: TeachSeries,
+    reteach: ReteachWizard,
+    presets: PresetPanel,
+    /// Repeated preset runs, the single ones go through `robot_control_promise`.
+    preset_promise: Option<Promise<Result<u32, String>>>,
+    preset_status: Option<Result<String, String>>,
 }
 
 impl RobotTab {
@@ -201,7 +401,20 @@ impl RobotTab {
             robot_id_input: "r1".to_string(),
             get_all_transforms_promise: None,
             robot_control_promise: None,
+            dashboard_promise: None,
+            dashboard_error: None,
+            controls_bottom: 0.0,
+            stop_promise: None,
+            resource_enabled: true,
+            command_blocked: None,
+            driver_capabilities: None,
+            live_joints: None,
+            jog: JogPanel::new(),
+            device_jog: [0.0; 6],
+            live_scaling: (None, None),
             transform_keys: Vec::new(),
+            taught_joints: HashMap::new(),
+            frame_usage: HashMap::new(),
             selected_goal_feature_id: None,
             tcp_keys: Vec::new(),
             selected_tcp: None,
@@ -211,13 +424,10 @@ impl RobotTab {
             // --- Command State ---
             command_type: CommandType::UnsafeMoveL,
             command_trigger: false,
-            dashboard_command: "stop".to_string(),
-            dashboard_trigger: false,
+            dashboard_paused: false,
             cancel_request: false,
             acceleration: 0.1,
             velocity: 0.1,
-            global_acceleration_scaling: 1.0,
-            global_velocity_scaling: 1.0,
 
             // --- New State ---
             use_blend_radius: false,
@@ -225,13 +435,19 @@ impl RobotTab {
             use_joint_positions: false,
             set_manual_joint_positions: false,
             joint_positions: [0.0; 6],
+            saved_joint_positions: None,
             use_preferred_joint_config: false,
             preferred_joint_config: [0.0; 6],
             set_manual_joint_config: false,
+            saved_joint_config: None,
+            joint_presets: JointPresets::load(),
+            soft_limits: SoftLimits::load(),
+            field_locks: FieldLocks::load(),
 
             use_payload: false,
             set_manual_payload: false,
-            saved_payload: SavedPayload::None,
+            saved_payload: payloads::NO_PAYLOAD.to_string(),
+            payload_presets: PayloadPresets::load(),
             manual_payload: Payload::default(),
 
             use_execution_time: false,
@@ -239,6 +455,427 @@ impl RobotTab {
             force_threshold: 20.0,
             use_relative_pose: false,
             relative_pose: [0.0; 6],
+            search: SearchParams::default(),
+            screw: ScrewParams::default(),
+            process_promise: None,
+            last_process: None,
+
+            show_written_variables: false,
+            last_written: BTreeMap::new(),
+            last_written_at: None,
+
+            preview: PreviewWindow::new(),
+            speed_monitor: SpeedMonitor::new(),
+            gripper: GripperPanel::new(),
+            vibration: VibrationMonitor::default(),
+            command_feedback: CommandFeedback::default(),
+            command_queue: CommandQueue::default(),
+            aliases: FrameAliases::new(),
+            waypoint_program: ProgramPanel::new(),
+            dashboard: DashboardPanel::new(),
+            test_move: TestMove::new(),
+            teach: TeachSeries::new(),
+            reteach: ReteachWizard::new(),
+            presets: PresetPanel::new(),
+            preset_promise: None,
+            preset_status: None,
+        }
+    }
+
+    /// Keys of the active robot that the written variables panel shows live,
+    /// empty while the panel is closed.
+    pub fn written_variable_keys(&self) -> Vec<String> {
+        if !self.show_written_variables {
+            return Vec::new();
+        }
+        COMMAND_VARIABLES
+            .iter()
+            .map(|suffix| format!("{}_{}", self.robot_id_input, suffix))
+            .collect()
+    }
+
+    /// Debug panel with the live value of every variable the tab writes next
+    /// to what the last command wrote, to see whether the driver consumed them.
+    pub fn draw_written_variables(&mut self, ui: &mut egui::Ui, poller: &StatePoller) {
+        let header = egui::CollapsingHeader::new("Written Variables (debug)")
+            .id_salt("robot_written_variables")
+            .open(Some(self.show_written_variables))
+            .show(ui, |ui| {
+                match self.last_written_at {
+                    Some(at) => ui.label(format!(
+                        "Last command written at {}",
+                        crate::storage::format_timestamp(at)
+                    )),
+                    None => ui.label("Nothing written in this session yet."),
+                };
+                egui::Grid::new("robot_written_variables_grid")
+                    .num_columns(3)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.strong("Variable");
+                        ui.strong("Written");
+                        ui.strong("Live");
+                        ui.end_row();
+                        for suffix in COMMAND_VARIABLES {
+                            let key = format!("{}_{}", self.robot_id_input, suffix);
+                            let written = self.last_written.get(&key);
+                            let live = poller.get(&key);
+                            ui.monospace(&key);
+                            ui.monospace(sp_value_to_display(written));
+                            let text = egui::RichText::new(sp_value_to_display(live)).monospace();
+                            if live.is_none() {
+                                ui.label(text.color(egui::Color32::RED))
+                                    .on_hover_text("Missing from the state");
+                            } else if written.is_some() && written != live {
+                                ui.label(text.color(egui::Color32::LIGHT_BLUE))
+                                    .on_hover_text("Changed since the GUI wrote it");
+                            } else {
+                                ui.label(text);
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+        if header.header_response.clicked() {
+            self.show_written_variables = !self.show_written_variables;
+        }
+    }
+
+    /// Keys the vibration indicator reads, always watched so it catches every move.
+    pub fn vibration_keys(&self) -> Vec<String> {
+        vec![
+            vibration::force_feedback_key(&self.robot_id_input),
+            format!("{}_request_state", self.robot_id_input),
+        ]
+    }
+
+    /// Force ripple of the running or last move, warned about above `threshold`.
+    pub fn draw_vibration(&mut self, ui: &mut egui::Ui, poller: &StatePoller, threshold: f64) {
+        self.vibration.update(poller, &self.robot_id_input);
+        self.vibration.draw(ui, threshold);
+    }
+
+    /// Keys the result of the last sent command is read from.
+    pub fn command_feedback_keys(&self) -> Vec<String> {
+        command_feedback::keys(&self.robot_id_input)
+    }
+
+    /// Outcome of the last command sent from this tab.
+    pub fn draw_command_feedback(&mut self, ui: &mut egui::Ui, poller: &StatePoller) {
+        self.command_feedback.update(poller);
+        self.command_feedback.draw(ui);
+    }
+
+    fn taught_goal_joints(&self) -> Option<&Vec<f64>> {
+        let goal = self.selected_goal_feature_id.as_deref()?;
+        self.taught_joints.get(self.aliases.resolve(goal))
+    }
+
+    /// Live joints against the goal frame's preferred joint configuration,
+    /// warned about when a joint is more than `threshold` degrees off.
+    pub fn draw_joint_drift(&self, ui: &mut egui::Ui, poller: &StatePoller, threshold: f64) {
+        let Some(taught) = self.taught_goal_joints() else {
+            return;
+        };
+        egui::CollapsingHeader::new("Taught vs Live Joints")
+            .id_salt("robot_joint_drift")
+            .default_open(true)
+            .show(ui, |ui| {
+                let live = poller.get(&joint_drift::joint_states_key(&self.robot_id_input));
+                joint_drift::draw(ui, taught, live, threshold);
+            });
+    }
+
+    /// Keys the speed monitor reads while it is open.
+    pub fn speed_monitor_keys(&self) -> Vec<String> {
+        if !self.speed_monitor.open {
+            return Vec::new();
+        }
+        vec![
+            speed_monitor::tcp_speed_key(&self.robot_id_input),
+            format!("{}_request_state", self.robot_id_input),
+        ]
+    }
+
+    /// Actual TCP speed against the commanded velocity scaled by the speed
+    /// override. Joint moves have no commanded TCP speed to compare with.
+    pub fn draw_speed_monitor(
+        &mut self,
+        ui: &mut egui::Ui,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+        poller: &StatePoller,
+        speed_scaling: f64,
+    ) {
+        let header = egui::CollapsingHeader::new("Speed Monitor")
+            .id_salt("robot_speed_monitor")
+            .open(Some(self.speed_monitor.open))
+            .show(ui, |ui| {
+                let tcp = self
+                    .selected_tcp
+                    .clone()
+                    .or(self.selected_faceplate.clone());
+                let frames = self.selected_baseframe.clone().zip(tcp);
+                let commanded = matches!(
+                    self.command_type,
+                    CommandType::UnsafeMoveL | CommandType::SafeMoveL
+                )
+                .then_some(self.velocity * speed_scaling);
+                self.speed_monitor.update(
+                    handle,
+                    connection,
+                    poller,
+                    &self.robot_id_input,
+                    frames,
+                    commanded,
+                );
+                self.speed_monitor.draw(ui);
+            });
+        if header.header_response.clicked() {
+            self.speed_monitor.open = !self.speed_monitor.open;
+        }
+    }
+
+    /// Keys of the gripper feedback while the gripper panel is open.
+    pub fn gripper_keys(&self) -> Vec<String> {
+        if !self.gripper.open {
+            return Vec::new();
+        }
+        gripper::keys(&self.robot_id_input)
+    }
+
+    /// Open, close and width commands for the gripper with its feedback.
+    pub fn draw_gripper(
+        &mut self,
+        ui: &mut egui::Ui,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+        poller: &StatePoller,
+    ) {
+        let header = egui::CollapsingHeader::new("Gripper")
+            .id_salt("robot_gripper")
+            .open(Some(self.gripper.open))
+            .show(ui, |ui| {
+                let blocked = self.motion_blocked();
+                self.gripper.draw(
+                    ui,
+                    handle,
+                    connection,
+                    poller,
+                    &self.robot_id_input,
+                    blocked,
+                );
+            });
+        if header.header_response.clicked() {
+            self.gripper.open = !self.gripper.open;
+        }
+    }
+
+    pub fn robot_id(&self) -> &str {
+        &self.robot_id_input
+    }
+
+    /// What the Stop button does, also bound to input devices.
+    pub fn stop(&mut self, handle: &tokio::runtime::Handle, connection: &Arc<ConnectionManager>) {
+        self.dashboard_paused = false;
+        let handle = handle.clone();
+        let con_clone = connection.clone();
+        let robot_name = self.robot_id_input.clone();
+        self.stop_promise = Some(workers::spawn_urgent("robot_stop", move || {
+            handle.block_on(cancel_current_goal(con_clone, robot_name))
+        }));
+        self.cancel_runs();
+    }
+
+    fn runs(&self) -> impl Iterator<Item = &Run> {
+        [self.command_queue.run(), self.waypoint_program.run()]
+            .into_iter()
+            .flatten()
+    }
+
+    /// Lets the queue and program runs stop after their running step.
+    pub fn cancel_runs(&self) {
+        self.runs().for_each(Run::cancel);
+    }
+
+    /// Whether a queue or program step is still running.
+    pub fn running(&self) -> bool {
+        self.runs().any(Run::is_running)
+    }
+
+    /// Why controls that move the robot are disabled, if they are.
+    fn motion_blocked(&self) -> Option<String> {
+        if !self.resource_enabled {
+            Some("This robot is disabled in the Resources tab.".to_string())
+        } else {
+            self.command_blocked.clone()
+        }
+    }
+
+    /// Where the busy overlay may start, so Stop, Pause and Reset Protective
+    /// Stop stay reachable while the tab is busy.
+    pub fn controls_bottom(&self) -> f32 {
+        self.controls_bottom
+    }
+
+    /// What the tab is busy with while its controls would conflict with it.
+    /// Stopping the robot is always safe, so every operation offers it.
+    pub fn busy(&self) -> Option<Busy> {
+        let message = if self
+            .robot_control_promise
+            .as_ref()
+            .is_some_and(|promise| promise.ready().is_none())
+        {
+            "Sending the command…".to_string()
+        } else if self.preset_promise.is_some() {
+            "Running the preset…".to_string()
+        } else if self.process_promise.is_some() {
+            "Screwdriving…".to_string()
+        } else if let Some(run) = self.command_queue.run() {
+            format!("Running the queue, {}…", run.progress())
+        } else if let Some(run) = self.waypoint_program.run() {
+            format!("Running the program, {}…", run.progress())
+        } else {
+            return None;
+        };
+        Some(Busy {
+            message,
+            cancel: Some("Stop Robot"),
+        })
+    }
+
+    pub fn preset_names(&self) -> Vec<String> {
+        self.presets.names()
+    }
+
+    /// Opens the run form of a preset, the operator confirms it on screen.
+    pub fn open_preset(&mut self, name: &str) {
+        self.presets.open(name);
+    }
+
+    pub fn set_robot_id(&mut self, robot_id: &str) {
+        self.robot_id_input = robot_id.to_string();
+    }
+
+    /// Disabled resources (under maintenance) don't accept commands from the GUI.
+    pub fn set_resource_enabled(&mut self, enabled: bool) {
+        self.resource_enabled = enabled;
+    }
+
+    /// Blocks Send Command and presets while set, with the reason shown.
+    pub fn set_command_blocked(&mut self, reason: Option<String>) {
+        self.command_blocked = reason;
+    }
+
+    /// Joint states read by the state poller, shown next to the joint inputs.
+    pub fn set_live_joints(&mut self, joints: Option<Vec<f64>>) {
+        self.live_joints = joints;
+    }
+
+    /// Gamepad axes and buttons bound to jogging, read every frame.
+    pub fn set_device_jog(&mut self, jog: [f64; 6]) {
+        self.device_jog = jog;
+    }
+
+    /// Polled global velocity and acceleration scaling, the dashboard
+    /// scaling inputs follow it.
+    pub fn set_live_scaling(&mut self, velocity: Option<f64>, acceleration: Option<f64>) {
+        self.live_scaling = (velocity, acceleration);
+    }
+
+    /// Sends a dashboard command of the header row. It only writes the
+    /// dashboard variables, so a running command and a pending cancel stay
+    /// as they are.
+    fn spawn_dashboard_promise(
+        &mut self,
+        command: &'static str,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+    ) {
+        self.dashboard_error = None;
+        let handle = handle.clone();
+        let con_clone = connection.clone();
+        let robot_id = self.robot_id_input.clone();
+        self.dashboard_promise = Some(workers::spawn("dashboard_command", move || {
+            let result = handle.block_on(crate::dashboard::send_dashboard_command(
+                con_clone,
+                robot_id,
+                command.to_string(),
+            ));
+            (command, result)
+        }));
+    }
+
+    /// The pause state only changes once the driver confirmed the command.
+    fn poll_dashboard_promise(&mut self) {
+        let Some(promise) = self.dashboard_promise.take() else {
+            return;
+        };
+        match promise.try_take() {
+            Ok((command, Ok(_))) => match command {
+                "pause" => self.dashboard_paused = true,
+                "play" => self.dashboard_paused = false,
+                _ => (),
+            },
+            Ok((_, Err(e))) => {
+                log::error!("Dashboard command failed: {}", e);
+                self.dashboard_error = Some(e);
+            }
+            Err(promise) => self.dashboard_promise = Some(promise),
+        }
+    }
+
+    /// Features the driver doesn't announce are greyed out and switched off.
+    pub fn set_driver_capabilities(&mut self, capabilities: Option<DriverCapabilities>) {
+        if self.driver_capabilities == capabilities {
+            return;
+        }
+        self.driver_capabilities = capabilities;
+        let options = [
+            (capabilities::BLEND_RADIUS, &mut self.use_blend_radius),
+            (capabilities::EXECUTION_TIME, &mut self.use_execution_time),
+            (capabilities::JOINT_POSITIONS, &mut self.use_joint_positions),
+            (
+                capabilities::PREFERRED_JOINT_CONFIG,
+                &mut self.use_preferred_joint_config,
+            ),
+            (capabilities::PAYLOAD, &mut self.use_payload),
+            (capabilities::RELATIVE_POSE, &mut self.use_relative_pose),
+        ];
+        for (name, flag) in options {
+            if !capabilities::is_supported(self.driver_capabilities.as_ref(), name) {
+                *flag = false;
+            }
+        }
+    }
+
+    fn supports(&self, name: &str) -> bool {
+        capabilities::is_supported(self.driver_capabilities.as_ref(), name)
+    }
+
+    fn command_params(&self) -> CommandParams {
+        let limits = self.soft_limits.get(&self.robot_id_input);
+        CommandParams {
+            is_safe_move: matches!(
+                self.command_type,
+                CommandType::SafeMoveL | CommandType::SafeMoveJ
+            ),
+            has_goal: self.selected_goal_feature_id.is_some(),
+            acceleration: self.acceleration,
+            velocity: self.velocity,
+            use_blend_radius: self.use_blend_radius,
+            blend_radius: self.blend_radius,
+            use_execution_time: self.use_execution_time,
+            execution_time: self.execution_time_s,
+            force_threshold: self.force_threshold,
+            use_joint_positions: self.use_joint_positions,
+            use_preferred_joint_config: self.use_preferred_joint_config,
+            joint_positions_in_limits: limits.contains(&self.joint_positions),
+            preferred_joint_config_in_limits: limits.contains(&self.preferred_joint_config),
+            manual_payload_mass: (self.use_payload && self.set_manual_payload)
+                .then_some(self.manual_payload.mass),
+            use_relative_pose: self.use_relative_pose,
+            relative_pose: self.relative_pose,
         }
     }
 
@@ -259,46 +896,109 @@ impl RobotTab {
         //     });
         // });
         // Add all right-aligned items here, in reverse order
-        ui.horizontal(|ui| {
+        let header = ui.horizontal(|ui| {
             ui.heading("Robot Controller"); // This stays on the left
 
             // Add all right-aligned items here, in reverse order
             ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                 // 1. The Button (will be furthest right)
-                if ui.add_enabled(true, egui::Button::new("Stop")).clicked() {
-                    // self.dashboard_trigger = true;
-                    // self.command_trigger = false;
-                    self.cancel_request = true;
-                    self.dashboard_command = "stop".to_string();
-                    self.spawn_robot_control_promise(handle, connection)
+                if self
+                    .stop_promise
+                    .as_ref()
+                    .is_some_and(|promise| promise.ready().is_some())
+                {
+                    self.stop_promise = None;
+                }
+                if ui
+                    .add_enabled(true, egui::Button::new("Stop"))
+                    .on_hover_text("Cancel the running goal and stop the robot")
+                    .clicked()
+                {
+                    self.stop(handle, connection);
+                }
+                if self.stop_promise.is_some() {
+                    ui.spinner();
+                }
+
+                self.poll_dashboard_promise();
+                let sending = self.dashboard_promise.is_some();
+                // Dashboard pause holds the motion in place, continue resumes it
+                let (label, command) = if self.dashboard_paused {
+                    ("▶ Continue", "play")
+                } else {
+                    ("⏸ Pause", "pause")
+                };
+                // Pausing is as safe as Stop, continuing moves the robot again
+                let blocked = self.motion_blocked().filter(|_| self.dashboard_paused);
+                if ui
+                    .add_enabled(blocked.is_none() && !sending, egui::Button::new(label))
+                    .on_disabled_hover_text(blocked.unwrap_or_default())
+                    .clicked()
+                {
+                    self.spawn_dashboard_promise(command, handle, connection);
+                }
+                if sending {
+                    ui.spinner();
+                }
+                if self.dashboard_paused {
+                    ui.colored_label(egui::Color32::from_rgb(230, 160, 40), "PAUSED");
+                }
+                if let Some(e) = &self.dashboard_error {
+                    ui.colored_label(egui::Color32::RED, e);
                 }
 
                 ui.label("ℹ").on_hover_text(
-                            "Press Stop after Reset Protective Stop \n\
+                    "Press Stop after Reset Protective Stop \n\
                              to put the robot back to the Normal operation state.",
-                        );
+                );
 
+                let blocked = self.motion_blocked();
                 if ui
-                    .add_enabled(true, egui::Button::new("Reset Protective Stop"))
+                    .add_enabled(
+                        blocked.is_none() && !sending,
+                        egui::Button::new("Reset Protective Stop"),
+                    )
+                    .on_disabled_hover_text(blocked.unwrap_or_default())
                     .clicked()
                 {
-                    self.dashboard_trigger = true;
-                    self.command_trigger = false;
-                    // self.cancel_request = true;
-                    self.dashboard_command = "reset_protective_stop".to_string();
-                    self.spawn_robot_control_promise(handle, connection)
+                    self.spawn_dashboard_promise("reset_protective_stop", handle, connection);
                 };
 
                 // The `.clicked()` method returns true on the frame the button is pressed
+                let command_supported = self.supports(&self.command_type.to_string());
+                let busy = self.busy();
+                let disabled_reason = if !self.resource_enabled {
+                    "This robot is disabled in the Resources tab.".to_string()
+                } else if let Some(reason) = &self.command_blocked {
+                    reason.clone()
+                } else if let Some(busy) = &busy {
+                    busy.message.clone()
+                } else {
+                    capabilities::unsupported_text(&self.command_type.to_string())
+                };
                 if ui
-                    .add_enabled(true, egui::Button::new("Send Command"))
+                    .add_enabled(
+                        self.resource_enabled
+                            && command_supported
+                            && self.command_blocked.is_none()
+                            && busy.is_none(),
+                        egui::Button::new("Send Command"),
+                    )
+                    .on_disabled_hover_text(disabled_reason)
                     .clicked()
                 {
-                    self.dashboard_trigger = false;
                     self.command_trigger = true;
                     self.cancel_request = false;
                     self.spawn_robot_control_promise(handle, connection)
                 }
+                if ui
+                    .button("Preview")
+                    .on_hover_text("Animate the command on the kinematic model before sending it")
+                    .clicked()
+                {
+                    self.preview
+                        .show(self.preview_request(), handle, connection);
+                }
 
                 // 2. The Text Box (will be to the left of the button)
                 // We make it "small" by setting a desired_width.
@@ -309,6 +1009,8 @@ impl RobotTab {
                 // 3. The Label (will be to the left of the text box)
             });
         });
+        self.controls_bottom = header.response.rect.bottom();
+        command_lint::draw_command_lint(ui, &command_lint::lint_command(&self.command_params()));
         ui.separator();
 
         // --- Top Section: Pose/Motion and Command Config ---
@@ -329,33 +1031,54 @@ impl RobotTab {
                         }
                     });
 
-                    draw_pose_selector(
+                    let goals = self
+                        .transform_keys
+                        .iter()
+                        .chain(self.aliases.names())
+                        .cloned()
+                        .collect::<Vec<String>>();
+                    draw_goal_selector(
                         ui,
                         "Goal Feature ID (Where to go):",
                         "pose_select",
                         &mut self.selected_goal_feature_id,
-                        &self.transform_keys,
-                    );
-                    draw_pose_selector(
-                        ui,
-                        "TCP ID (With what frame):",
-                        "tcp_select",
-                        &mut self.selected_tcp,
-                        &self.transform_keys,
+                        &goals,
+                        &self.frame_usage,
                     );
-                    draw_pose_selector(
+                    access::locked_ui(ui, self.field_locks.is_locked(LockableField::Tcp), |ui| {
+                        draw_pose_selector(
+                            ui,
+                            "TCP ID (With what frame):",
+                            "tcp_select",
+                            &mut self.selected_tcp,
+                            &self.transform_keys,
+                        );
+                    });
+                    access::locked_ui(
                         ui,
-                        "Faceplate ID (Robot's final link):",
-                        "faceplate_select",
-                        &mut self.selected_faceplate,
-                        &self.transform_keys,
+                        self.field_locks.is_locked(LockableField::Faceplate),
+                        |ui| {
+                            draw_pose_selector(
+                                ui,
+                                "Faceplate ID (Robot's final link):",
+                                "faceplate_select",
+                                &mut self.selected_faceplate,
+                                &self.transform_keys,
+                            );
+                        },
                     );
-                    draw_pose_selector(
+                    access::locked_ui(
                         ui,
-                        "Baseframe ID (base or base_link):",
-                        "baseframe_select",
-                        &mut self.selected_baseframe,
-                        &self.transform_keys,
+                        self.field_locks.is_locked(LockableField::Baseframe),
+                        |ui| {
+                            draw_pose_selector(
+                                ui,
+                                "Baseframe ID (base or base_link):",
+                                "baseframe_select",
+                                &mut self.selected_baseframe,
+                                &self.transform_keys,
+                            );
+                        },
                     );
                     // draw_pose_selector(
                     //     ui,
@@ -379,11 +1102,20 @@ impl RobotTab {
                             .selected_text(self.command_type.to_string())
                             .show_ui(ui, |ui| {
                                 for variant in CommandType::variants() {
-                                    ui.selectable_value(
-                                        &mut self.command_type,
-                                        variant.clone(),
-                                        variant.to_string(),
+                                    let name = variant.to_string();
+                                    let supported = capabilities::is_supported(
+                                        self.driver_capabilities.as_ref(),
+                                        &name,
                                     );
+                                    ui.add_enabled_ui(supported, |ui| {
+                                        ui.selectable_value(
+                                            &mut self.command_type,
+                                            variant.clone(),
+                                            &name,
+                                        )
+                                    })
+                                    .inner
+                                    .on_disabled_hover_text(capabilities::unsupported_text(&name));
                                 }
                             });
                         ui.label("ℹ").on_hover_text(
@@ -406,52 +1138,73 @@ impl RobotTab {
                         CommandType::SafeMoveJ => " rad/s²",
                         CommandType::PickVacuum => " m/s²",
                         CommandType::PlaceVacuum => " m/s²",
+                        CommandType::SpiralSearch => " m/s²",
+                        CommandType::Screwdrive => " m/s²",
                     };
 
-                    ui.horizontal(|ui| {
-                        ui.label("Acceleration:");
-
-                        ui.add(
-                            egui::DragValue::new(&mut self.acceleration)
-                                .suffix(accel_vel_suffix) // Use the dynamically set suffix
-                                .speed(0.01)
-                                .range(0.0..=1.0),
-                        );
-                    });
-
-                    ui.horizontal(|ui| {
-                        ui.label("Velocity:");
-                        ui.add(
-                            egui::DragValue::new(&mut self.velocity)
-                                .suffix(accel_vel_suffix)
-                                .speed(0.01)
-                                .range(0.0..=1.0),
-                        );
-                    });
-
-                    ui.horizontal(|ui| {
-                        ui.label("Global Acceleration Scaling:");
-                        ui.add(
-                            egui::DragValue::new(&mut self.global_acceleration_scaling)
-                                .suffix(" (0.0-1.0)")
-                                .speed(0.01)
-                                .range(0.0..=1.0),
-                        );
-                    });
+                    access::locked_ui(
+                        ui,
+                        self.field_locks.is_locked(LockableField::Acceleration),
+                        |ui| {
+                            ui.horizontal(|ui| {
+                                ui.label("Acceleration:");
+                                ui.add(
+                                    egui::DragValue::new(&mut self.acceleration)
+                                        .localized()
+                                        .suffix(accel_vel_suffix) // Use the dynamically set suffix
+                                        .speed(0.01)
+                                        .range(0.0..=1.0),
+                                );
+                            });
+                        },
+                    );
 
-                    ui.horizontal(|ui| {
-                        ui.label("Global Velocity Scaling:");
-                        ui.add(
-                            egui::DragValue::new(&mut self.global_velocity_scaling)
-                                .suffix(" (0.0-1.0)")
-                                .speed(0.01)
-                                .range(0.0..=1.0),
-                        );
-                    });
+                    access::locked_ui(
+                        ui,
+                        self.field_locks.is_locked(LockableField::Velocity),
+                        |ui| {
+                            ui.horizontal(|ui| {
+                                ui.label("Velocity:");
+                                ui.add(
+                                    egui::DragValue::new(&mut self.velocity)
+                                        .localized()
+                                        .suffix(accel_vel_suffix)
+                                        .speed(0.01)
+                                        .range(0.0..=1.0),
+                                );
+                            });
+                        },
+                    );
                 });
             });
         });
 
+        if self.command_type == CommandType::SpiralSearch {
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.heading("Spiral Search");
+                ui.label("ℹ").on_hover_text(
+                    "Force-guided insertion: the TCP presses towards the goal along its Z \n\
+                     axis and spirals outwards in the goal's XY plane until it drops in, \n\
+                     or the radius, the max force or the timeout is reached.",
+                );
+            });
+            self.search.draw(ui);
+        }
+        if self.command_type == CommandType::Screwdrive {
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.heading("Screwdriving");
+                ui.label("ℹ").on_hover_text(
+                    "Runs the program on the spindle at the goal frame. When it is done, \n\
+                     the final torque, angle and OK/NOK are read back and recorded in the \n\
+                     history, see the Statistics tab.",
+                );
+            });
+            self.screw.draw(ui);
+            self.draw_process_result(ui);
+        }
+
         ui.separator(); // --- Horizontal Separator ---
 
         // --- Bottom Section: Blend and Joint Configs ---
@@ -469,38 +1222,56 @@ impl RobotTab {
 
                     // ui.separator();
 
-                    ui.checkbox(&mut self.use_joint_positions, "Use Joint Positions");
+                    access::locked_ui(
+                        ui,
+                        self.field_locks.is_locked(LockableField::JointPositions),
+                        |ui| {
+                            ui.add_enabled(
+                                self.supports(capabilities::JOINT_POSITIONS),
+                                egui::Checkbox::new(
+                                    &mut self.use_joint_positions,
+                                    "Use Joint Positions",
+                                ),
+                            )
+                            .on_disabled_hover_text(
+                                capabilities::unsupported_text(capabilities::JOINT_POSITIONS),
+                            );
 
-                    // Everything in this section is disabled if `use_payload` is false
-                    ui.add_enabled_ui(self.use_joint_positions, |ui| {
-                        // --- Dropdown for saved payloads ---
-                        // Disabled if "Set Manual" is checked
-                        ui.add_enabled_ui(!self.set_manual_joint_positions, |ui| {
-                            ui.horizontal(|ui| {
-                                ui.label("Saved Joint Positions:");
-                                egui::ComboBox::from_id_salt("saved_joint_positions_select")
-                                    .selected_text(self.saved_payload.to_string())
-                                    .show_ui(ui, |ui| {
-                                        for variant in SavedPayload::variants() {
-                                            ui.selectable_value(
-                                                &mut self.saved_payload,
-                                                variant.clone(),
-                                                variant.to_string(),
-                                            );
+                            // Everything in this section is disabled if `use_joint_positions` is false
+                            ui.add_enabled_ui(self.use_joint_positions, |ui| {
+                                // --- Dropdown for saved joint positions ---
+                                // Disabled if "Set Manual" is checked
+                                ui.add_enabled_ui(!self.set_manual_joint_positions, |ui| {
+                                    ui.horizontal(|ui| {
+                                        ui.label("Saved Joint Positions:");
+                                        if let Some(joints) = self.joint_presets.draw_select(
+                                            ui,
+                                            JointPresetKind::Positions,
+                                            "saved_joint_positions_select",
+                                            &mut self.saved_joint_positions,
+                                        ) {
+                                            self.joint_positions = joints;
                                         }
                                     });
-                            });
-                        });
-
-                        ui.checkbox(
-                            &mut self.set_manual_joint_positions,
-                            "Set Manual Joint Positions",
-                        );
+                                });
 
-                        ui.add_enabled_ui(self.set_manual_joint_positions, |ui| {
-                            draw_joint_inputs(ui, &mut self.joint_positions, "joint_pos");
-                        });
-                    });
+                                ui.checkbox(
+                                    &mut self.set_manual_joint_positions,
+                                    "Set Manual Joint Positions",
+                                );
+
+                                ui.add_enabled_ui(self.set_manual_joint_positions, |ui| {
+                                    draw_joint_inputs(
+                                        ui,
+                                        &mut self.joint_positions,
+                                        "joint_pos",
+                                        &self.soft_limits.get(&self.robot_id_input),
+                                    );
+                                });
+                            });
+                        },
+                    );
+                    draw_live_joints(ui, self.live_joints.as_deref());
 
                     // ui.separator();
 
@@ -529,39 +1300,56 @@ impl RobotTab {
                         );
                     });
 
-                    ui.checkbox(
-                        &mut self.use_preferred_joint_config,
-                        "Use Preferred Joint Config",
-                    );
-                    ui.add_enabled_ui(self.use_preferred_joint_config, |ui| {
-                        // --- Dropdown for saved payloads ---
-                        // Disabled if "Set Manual" is checked
-                        ui.add_enabled_ui(!self.set_manual_joint_config, |ui| {
-                            ui.horizontal(|ui| {
-                                ui.label("Saved Joint Configurations:");
-                                egui::ComboBox::from_id_salt("saved_joint_configuration_select")
-                                    .selected_text(self.saved_payload.to_string())
-                                    .show_ui(ui, |ui| {
-                                        for variant in SavedPayload::variants() {
-                                            ui.selectable_value(
-                                                &mut self.saved_payload,
-                                                variant.clone(),
-                                                variant.to_string(),
-                                            );
+                    access::locked_ui(
+                        ui,
+                        self.field_locks
+                            .is_locked(LockableField::PreferredJointConfig),
+                        |ui| {
+                            ui.add_enabled(
+                                self.supports(capabilities::PREFERRED_JOINT_CONFIG),
+                                egui::Checkbox::new(
+                                    &mut self.use_preferred_joint_config,
+                                    "Use Preferred Joint Config",
+                                ),
+                            )
+                            .on_disabled_hover_text(
+                                capabilities::unsupported_text(
+                                    capabilities::PREFERRED_JOINT_CONFIG,
+                                ),
+                            );
+                            ui.add_enabled_ui(self.use_preferred_joint_config, |ui| {
+                                // --- Dropdown for saved joint configurations ---
+                                // Disabled if "Set Manual" is checked
+                                ui.add_enabled_ui(!self.set_manual_joint_config, |ui| {
+                                    ui.horizontal(|ui| {
+                                        ui.label("Saved Joint Configurations:");
+                                        if let Some(joints) = self.joint_presets.draw_select(
+                                            ui,
+                                            JointPresetKind::Config,
+                                            "saved_joint_configuration_select",
+                                            &mut self.saved_joint_config,
+                                        ) {
+                                            self.preferred_joint_config = joints;
                                         }
                                     });
-                            });
-                        });
-
-                        ui.checkbox(
-                            &mut self.set_manual_joint_config,
-                            "Set Manual Preferred Joint Config",
-                        );
+                                });
 
-                        ui.add_enabled_ui(self.set_manual_joint_config, |ui| {
-                            draw_joint_inputs(ui, &mut self.preferred_joint_config, "joint_config");
-                        });
-                    });
+                                ui.checkbox(
+                                    &mut self.set_manual_joint_config,
+                                    "Set Manual Preferred Joint Config",
+                                );
+
+                                ui.add_enabled_ui(self.set_manual_joint_config, |ui| {
+                                    draw_joint_inputs(
+                                        ui,
+                                        &mut self.preferred_joint_config,
+                                        "joint_config",
+                                        &self.soft_limits.get(&self.robot_id_input),
+                                    );
+                                });
+                            });
+                        },
+                    );
                 });
             });
         });
@@ -574,153 +1362,643 @@ impl RobotTab {
             ui.horizontal_top(|ui| {
                 ui.vertical(|ui| {
                     ui.heading("Payload (Optional)");
-                    ui.checkbox(&mut self.use_payload, "Use Payload");
-
-                    // Everything in this section is disabled if `use_payload` is false
-                    ui.add_enabled_ui(self.use_payload, |ui| {
-                        // --- Dropdown for saved payloads ---
-                        // Disabled if "Set Manual" is checked
-                        ui.add_enabled_ui(!self.set_manual_payload, |ui| {
-                            ui.horizontal(|ui| {
-                                ui.label("Saved Payloads:");
-                                egui::ComboBox::from_id_salt("saved_payload_select")
-                                    .selected_text(self.saved_payload.to_string())
-                                    .show_ui(ui, |ui| {
-                                        for variant in SavedPayload::variants() {
-                                            ui.selectable_value(
-                                                &mut self.saved_payload,
-                                                variant.clone(),
-                                                variant.to_string(),
-                                            );
-                                        }
-                                    });
-                            });
-                        });
-
-                        ui.checkbox(&mut self.set_manual_payload, "Set Manual Payload");
-
-                        // ui.separator();
-
-                        // --- Manual Payload Inputs ---
-                        // Enabled *only if* "Set Manual" is checked
-                        ui.add_enabled_ui(self.set_manual_payload, |ui| {
-                            egui::Frame::default()
-                                // .inner_margin(egui::Margin::same(5))
-                                // .stroke(egui::Stroke::new(1.0, egui::Color32::))
-                                .show(ui, |ui| {
-                                    ui.label("Manual Payload Configuration:");
-
-                                    ui.horizontal(|ui| {
-                                        ui.label("Mass (kg):");
-                                        ui.add(
-                                            egui::DragValue::new(&mut self.manual_payload.mass)
-                                                .speed(0.01)
-                                                .range(0.0..=f64::MAX),
-                                        );
-                                    });
+                    access::locked_ui(
+                        ui,
+                        self.field_locks.is_locked(LockableField::Payload),
+                        |ui| {
+                            ui.add_enabled(
+                                self.supports(capabilities::PAYLOAD),
+                                egui::Checkbox::new(&mut self.use_payload, "Use Payload"),
+                            )
+                            .on_disabled_hover_text(
+                                capabilities::unsupported_text(capabilities::PAYLOAD),
+                            );
 
-                                    ui.label("Center of Gravity (m):");
+                            // Everything in this section is disabled if `use_payload` is false
+                            ui.add_enabled_ui(self.use_payload, |ui| {
+                                // --- Dropdown for saved payloads ---
+                                // Disabled if "Set Manual" is checked
+                                ui.add_enabled_ui(!self.set_manual_payload, |ui| {
                                     ui.horizontal(|ui| {
-                                        ui.label("CoG X:");
-                                        ui.add(
-                                            egui::DragValue::new(&mut self.manual_payload.cog_x)
-                                                .speed(0.001),
-                                        );
-                                        ui.label("CoG Y:");
-                                        ui.add(
-                                            egui::DragValue::new(&mut self.manual_payload.cog_y)
-                                                .speed(0.001),
-                                        );
-                                        ui.label("CoG Z:");
-                                        ui.add(
-                                            egui::DragValue::new(&mut self.manual_payload.cog_z)
-                                                .speed(0.001),
+                                        ui.label("Saved Payloads:");
+                                        draw_saved_payload_select(
+                                            ui,
+                                            "saved_payload_select",
+                                            &mut self.saved_payload,
+                                            &self.payload_presets,
                                         );
+                                        if ui
+                                            .small_button("⟳")
+                                            .on_hover_text(format!(
+                                                "Reload the presets from {}",
+                                                PayloadPresets::path().display()
+                                            ))
+                                            .clicked()
+                                        {
+                                            self.payload_presets = PayloadPresets::load();
+                                        }
                                     });
+                                    let preset = self.payload_presets.get(&self.saved_payload);
+                                    if let Some(preset) = preset {
+                                        if ui
+                                            .button("Copy to Manual")
+                                            .on_hover_text(
+                                                "Edit the preset's values as a manual payload",
+                                            )
+                                            .clicked()
+                                        {
+                                            self.manual_payload = Payload::from(preset);
+                                            self.set_manual_payload = true;
+                                        }
+                                    }
+                                });
 
-                                    ui.label("Inertia Matrix (kg*m^2):");
-                                    ui.horizontal(|ui| {
-                                        ui.label("Ixx:");
-                                        ui.add(
-                                            egui::DragValue::new(&mut self.manual_payload.ixx)
-                                                .speed(0.001),
-                                        );
-                                        ui.label("Iyy:");
-                                        ui.add(
-                                            egui::DragValue::new(&mut self.manual_payload.iyy)
-                                                .speed(0.001),
-                                        );
-                                        ui.label("Izz:");
-                                        ui.add(
-                                            egui::DragValue::new(&mut self.manual_payload.izz)
-                                                .speed(0.001),
-                                        );
-                                    });
-                                    ui.horizontal(|ui| {
-                                        ui.label("Ixy:");
-                                        ui.add(
-                                            egui::DragValue::new(&mut self.manual_payload.ixy)
-                                                .speed(0.001),
-                                        );
-                                        ui.label("Ixz:");
-                                        ui.add(
-                                            egui::DragValue::new(&mut self.manual_payload.ixz)
-                                                .speed(0.001),
-                                        );
-                                        ui.label("Iyz:");
-                                        ui.add(
-                                            egui::DragValue::new(&mut self.manual_payload.iyz)
-                                                .speed(0.001),
-                                        );
-                                    });
+                                ui.checkbox(&mut self.set_manual_payload, "Set Manual Payload");
+
+                                // ui.separator();
+
+                                // --- Manual Payload Inputs ---
+                                // Enabled *only if* "Set Manual" is checked
+                                ui.add_enabled_ui(self.set_manual_payload, |ui| {
+                                    egui::Frame::default()
+                                        // .inner_margin(egui::Margin::same(5))
+                                        // .stroke(egui::Stroke::new(1.0, egui::Color32::))
+                                        .show(ui, |ui| {
+                                            ui.label("Manual Payload Configuration:");
+
+                                            ui.horizontal(|ui| {
+                                                ui.label("Mass (kg):");
+                                                ui.add(
+                                                    egui::DragValue::new(
+                                                        &mut self.manual_payload.mass,
+                                                    )
+                                                    .localized()
+                                                    .speed(0.01)
+                                                    .range(0.0..=f64::MAX),
+                                                );
+                                            });
+
+                                            ui.label("Center of Gravity (m):");
+                                            ui.horizontal(|ui| {
+                                                ui.label("CoG X:");
+                                                ui.add(
+                                                    egui::DragValue::new(
+                                                        &mut self.manual_payload.cog_x,
+                                                    )
+                                                    .localized()
+                                                    .speed(0.001),
+                                                );
+                                                ui.label("CoG Y:");
+                                                ui.add(
+                                                    egui::DragValue::new(
+                                                        &mut self.manual_payload.cog_y,
+                                                    )
+                                                    .localized()
+                                                    .speed(0.001),
+                                                );
+                                                ui.label("CoG Z:");
+                                                ui.add(
+                                                    egui::DragValue::new(
+                                                        &mut self.manual_payload.cog_z,
+                                                    )
+                                                    .localized()
+                                                    .speed(0.001),
+                                                );
+                                            });
+
+                                            ui.label("Inertia Matrix (kg*m^2):");
+                                            ui.horizontal(|ui| {
+                                                ui.label("Ixx:");
+                                                ui.add(
+                                                    egui::DragValue::new(
+                                                        &mut self.manual_payload.ixx,
+                                                    )
+                                                    .localized()
+                                                    .speed(0.001),
+                                                );
+                                                ui.label("Iyy:");
+                                                ui.add(
+                                                    egui::DragValue::new(
+                                                        &mut self.manual_payload.iyy,
+                                                    )
+                                                    .localized()
+                                                    .speed(0.001),
+                                                );
+                                                ui.label("Izz:");
+                                                ui.add(
+                                                    egui::DragValue::new(
+                                                        &mut self.manual_payload.izz,
+                                                    )
+                                                    .localized()
+                                                    .speed(0.001),
+                                                );
+                                            });
+                                            ui.horizontal(|ui| {
+                                                ui.label("Ixy:");
+                                                ui.add(
+                                                    egui::DragValue::new(
+                                                        &mut self.manual_payload.ixy,
+                                                    )
+                                                    .localized()
+                                                    .speed(0.001),
+                                                );
+                                                ui.label("Ixz:");
+                                                ui.add(
+                                                    egui::DragValue::new(
+                                                        &mut self.manual_payload.ixz,
+                                                    )
+                                                    .localized()
+                                                    .speed(0.001),
+                                                );
+                                                ui.label("Iyz:");
+                                                ui.add(
+                                                    egui::DragValue::new(
+                                                        &mut self.manual_payload.iyz,
+                                                    )
+                                                    .localized()
+                                                    .speed(0.001),
+                                                );
+                                            });
+                                        });
                                 });
-                        });
-                    });
+                            });
+                        },
+                    );
                 });
                 ui.add(egui::Separator::default().vertical());
                 // ui.allocate_ui(egui::vec2(ui.available_width(), 260.0), |ui| {
                 ui.vertical(|ui| {
                     ui.heading("Miscelaneous (Optional)");
-                    ui.checkbox(&mut self.use_execution_time, "Use Execution Time");
-
-                    ui.add_enabled_ui(self.use_execution_time, |ui| {
-                        ui.horizontal(|ui| {
-                            ui.label("Execution Time:");
-                            ui.add(
-                                egui::DragValue::new(&mut self.execution_time_s)
-                                    .suffix(" ms")
-                                    .speed(10.0),
+                    access::locked_ui(
+                        ui,
+                        self.field_locks.is_locked(LockableField::ExecutionTime),
+                        |ui| {
+                            ui.add_enabled(
+                                self.supports(capabilities::EXECUTION_TIME),
+                                egui::Checkbox::new(
+                                    &mut self.use_execution_time,
+                                    "Use Execution Time",
+                                ),
+                            )
+                            .on_disabled_hover_text(
+                                capabilities::unsupported_text(capabilities::EXECUTION_TIME),
                             );
-                        });
-                    });
-                    ui.checkbox(&mut self.use_blend_radius, "Use Blend Radius");
-                    ui.add_enabled_ui(self.use_blend_radius, |ui| {
-                        ui.horizontal(|ui| {
-                            ui.label("Blend Radius:");
-                            ui.add(
-                                egui::DragValue::new(&mut self.blend_radius)
-                                    .suffix(" m")
-                                    .speed(0.001)
-                                    .range(0.0..=0.5), // Example range
+
+                            ui.add_enabled_ui(self.use_execution_time, |ui| {
+                                ui.horizontal(|ui| {
+                                    ui.label("Execution Time:");
+                                    ui.add(
+                                        egui::DragValue::new(&mut self.execution_time_s)
+                                            .localized_in(Unit::Millisecond)
+                                            .suffix(" ms")
+                                            .speed(10.0),
+                                    );
+                                });
+                            });
+                        },
+                    );
+
+                    access::locked_ui(
+                        ui,
+                        self.field_locks.is_locked(LockableField::BlendRadius),
+                        |ui| {
+                            ui.add_enabled(
+                                self.supports(capabilities::BLEND_RADIUS),
+                                egui::Checkbox::new(&mut self.use_blend_radius, "Use Blend Radius"),
+                            )
+                            .on_disabled_hover_text(
+                                capabilities::unsupported_text(capabilities::BLEND_RADIUS),
                             );
-                        });
-                    });
-                    ui.horizontal(|ui| {
-                        ui.label("Force Threshold:");
-                        ui.add(
-                            egui::DragValue::new(&mut self.force_threshold)
-                                .suffix(" N")
-                                .speed(0.1)
-                                .range(0.0..=200.0),
-                        );
-                    });
-                    ui.checkbox(&mut self.use_relative_pose, "Use Relative Pose");
-                    ui.add_enabled_ui(self.use_relative_pose, |ui| {
-                        draw_relative_pose_inputs(ui, &mut self.relative_pose, "relative_pose");
-                    });
+                            ui.add_enabled_ui(self.use_blend_radius, |ui| {
+                                ui.horizontal(|ui| {
+                                    ui.label("Blend Radius:");
+                                    ui.add(
+                                        egui::DragValue::new(&mut self.blend_radius)
+                                            .localized_in(Unit::Meter)
+                                            .suffix(" m")
+                                            .speed(0.001)
+                                            .range(0.0..=0.5), // Example range
+                                    );
+                                });
+                            });
+                        },
+                    );
+
+                    access::locked_ui(
+                        ui,
+                        self.field_locks.is_locked(LockableField::ForceThreshold),
+                        |ui| {
+                            ui.horizontal(|ui| {
+                                ui.label("Force Threshold:");
+                                ui.add_enabled(
+                                    self.supports(capabilities::FORCE_THRESHOLD),
+                                    egui::DragValue::new(&mut self.force_threshold)
+                                        .localized()
+                                        .suffix(" N")
+                                        .speed(0.1)
+                                        .range(0.0..=200.0),
+                                )
+                                .on_disabled_hover_text(
+                                    capabilities::unsupported_text(capabilities::FORCE_THRESHOLD),
+                                );
+                            });
+                        },
+                    );
+
+                    access::locked_ui(
+                        ui,
+                        self.field_locks.is_locked(LockableField::RelativePose),
+                        |ui| {
+                            ui.add_enabled(
+                                self.supports(capabilities::RELATIVE_POSE),
+                                egui::Checkbox::new(
+                                    &mut self.use_relative_pose,
+                                    "Use Relative Pose",
+                                ),
+                            )
+                            .on_disabled_hover_text(
+                                capabilities::unsupported_text(capabilities::RELATIVE_POSE),
+                            );
+                            ui.add_enabled_ui(self.use_relative_pose, |ui| {
+                                draw_relative_pose_inputs(
+                                    ui,
+                                    &mut self.relative_pose,
+                                    "relative_pose",
+                                );
+                            });
+                        },
+                    );
                 });
             });
         });
+        egui::CollapsingHeader::new("Command Presets")
+            .id_salt("robot_command_presets")
+            .show(ui, |ui| {
+                self.draw_presets(ui);
+            });
+        self.aliases.poll();
+        egui::CollapsingHeader::new("Frame Aliases")
+            .id_salt("robot_frame_aliases")
+            .show(ui, |ui| {
+                self.aliases
+                    .draw(ui, handle, connection, &self.transform_keys);
+            });
+        egui::CollapsingHeader::new("Command Queue")
+            .id_salt("robot_command_queue")
+            .show(ui, |ui| {
+                let blocked = self.motion_blocked();
+                if self.command_queue.draw(ui, handle, connection, blocked) {
+                    self.queue_current_command();
+                }
+            });
+        egui::CollapsingHeader::new("Waypoint Program")
+            .id_salt("robot_waypoint_program")
+            .show(ui, |ui| {
+                let frames = if !self.resource_enabled {
+                    Err("This robot is disabled in the Resources tab.".to_string())
+                } else if let Some(blocked) = &self.command_blocked {
+                    Err(blocked.clone())
+                } else {
+                    self.test_move_frames()
+                };
+                self.waypoint_program.draw(
+                    ui,
+                    handle,
+                    connection,
+                    frames,
+                    self.live_joints.as_deref(),
+                    self.selected_goal_feature_id.as_deref(),
+                );
+            });
+        egui::CollapsingHeader::new("Joint Presets")
+            .id_salt("robot_joint_presets")
+            .show(ui, |ui| {
+                ui.strong("Joint Positions");
+                self.joint_presets.draw_editor(
+                    ui,
+                    JointPresetKind::Positions,
+                    self.joint_positions,
+                    self.live_joints.as_deref(),
+                );
+                ui.separator();
+                ui.strong("Joint Configurations");
+                self.joint_presets.draw_editor(
+                    ui,
+                    JointPresetKind::Config,
+                    self.preferred_joint_config,
+                    self.live_joints.as_deref(),
+                );
+            });
+        egui::CollapsingHeader::new(format!("Joint Soft Limits of {}", self.robot_id_input))
+            .id_salt("robot_joint_limits")
+            .show(ui, |ui| {
+                access::locked_ui(
+                    ui,
+                    self.field_locks.is_locked(LockableField::SoftLimits),
+                    |ui| {
+                        self.soft_limits.draw_editor(ui, &self.robot_id_input);
+                    },
+                );
+            });
+        if access::role() == access::Role::Engineer {
+            egui::CollapsingHeader::new("Field Locks")
+                .id_salt("robot_field_locks")
+                .show(ui, |ui| {
+                    self.field_locks.draw_editor(ui);
+                });
+        }
+        egui::CollapsingHeader::new("Dashboard")
+            .id_salt("robot_dashboard")
+            .show(ui, |ui| {
+                let blocked = self.motion_blocked();
+                self.dashboard.draw(
+                    ui,
+                    handle,
+                    connection,
+                    &self.robot_id_input,
+                    blocked,
+                    self.live_scaling,
+                );
+            });
+        egui::CollapsingHeader::new("Jog")
+            .id_salt("robot_jog")
+            .show(ui, |ui| {
+                let blocked = self.motion_blocked();
+                let frames = self.test_move_frames();
+                self.jog
+                    .set_limits(self.soft_limits.get(&self.robot_id_input));
+                self.jog
+                    .draw(ui, handle, connection, frames, self.device_jog, blocked);
+            });
+        egui::CollapsingHeader::new("Teach Poses")
+            .id_salt("robot_teach_poses")
+            .show(ui, |ui| {
+                let tcp = self
+                    .selected_tcp
+                    .clone()
+                    .or(self.selected_faceplate.clone());
+                let frames = self
+                    .selected_baseframe
+                    .clone()
+                    .zip(tcp)
+                    .ok_or_else(|| "Select the baseframe and the TCP".to_string());
+                self.teach
+                    .draw(ui, handle, connection, frames, &self.transform_keys);
+            });
+        egui::CollapsingHeader::new("Re-teach Fixture")
+            .id_salt("robot_reteach_fixture")
+            .show(ui, |ui| {
+                let tcp = self
+                    .selected_tcp
+                    .clone()
+                    .or(self.selected_faceplate.clone());
+                self.reteach
+                    .draw(ui, handle, connection, tcp, &self.transform_keys);
+            });
+        egui::CollapsingHeader::new("Commissioning Test Move")
+            .id_salt("robot_test_move")
+            .show(ui, |ui| {
+                let frames = match self.command_blocked.clone() {
+                    Some(blocked) => Err(blocked),
+                    None => self.test_move_frames(),
+                };
+                self.test_move
+                    .draw(ui, handle, connection, frames, self.resource_enabled);
+            });
+        self.preview.draw(ui.ctx(), handle, connection);
+        if let Some(promise) = self.preset_promise.take() {
+            match promise.try_take() {
+                Ok(result) => {
+                    self.preset_status = Some(result.map(|n| format!("Sent {} times", n)))
+                }
+                Err(promise) => self.preset_promise = Some(promise),
+            }
+        }
+        if let Some(run) = self.presets.draw_form(ui.ctx(), &self.transform_keys) {
+            if let Some(reason) = &self.command_blocked {
+                self.preset_status = Some(Err(format!("{} not run. {}", run.name, reason)));
+            } else if self.resource_enabled && self.preset_promise.is_none() {
+                self.run_preset(run, handle, connection);
+            } else {
+                self.preset_status = Some(Err(format!(
+                    "{} not run, the robot is disabled or a preset is running",
+                    run.name
+                )));
+            }
+        }
+    }
+
+    fn draw_presets(&mut self, ui: &mut egui::Ui) {
+        let current = self.to_preset();
+        let can_run = self.resource_enabled
+            && self.command_blocked.is_none()
+            && self.preset_promise.is_none();
+        self.presets.draw(ui, &current, can_run);
+        if self.preset_promise.is_some() {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Running preset…");
+            });
+        }
+        match &self.preset_status {
+            Some(Ok(status)) => {
+                ui.label(status);
+            }
+            Some(Err(e)) => {
+                ui.colored_label(egui::Color32::RED, e);
+            }
+            None => (),
+        }
+    }
+
+    /// The tab's configuration as a preset without parameters.
+    fn to_preset(&self) -> CommandPreset {
+        CommandPreset {
+            command_type: self.command_type.to_string(),
+            acceleration: self.acceleration,
+            velocity: self.velocity,
+            goal_feature_id: self.selected_goal_feature_id.clone(),
+            tcp: self.selected_tcp.clone(),
+            faceplate: self.selected_faceplate.clone(),
+            baseframe: self.selected_baseframe.clone(),
+            use_joint_positions: self.use_joint_positions,
+            joint_positions: self.joint_positions,
+            use_preferred_joint_config: self.use_preferred_joint_config,
+            preferred_joint_config: self.preferred_joint_config,
+            use_payload: self.use_payload,
+            payload: self.saved_payload.clone(),
+            use_execution_time: self.use_execution_time,
+            execution_time_s: self.execution_time_s,
+            use_blend_radius: self.use_blend_radius,
+            blend_radius: self.blend_radius,
+            force_threshold: self.force_threshold,
+            use_relative_pose: self.use_relative_pose,
+            relative_pose: self.relative_pose,
+            search: self.search,
+            screw: self.screw,
+            parameters: Vec::new(),
+        }
+    }
+
+    fn apply_preset(&mut self, preset: &CommandPreset) {
+        if let Some(command_type) = CommandType::from_name(&preset.command_type) {
+            self.command_type = command_type;
+        }
+        self.acceleration = preset.acceleration;
+        self.velocity = preset.velocity;
+        self.selected_goal_feature_id = preset.goal_feature_id.clone();
+        self.selected_tcp = preset.tcp.clone();
+        self.selected_faceplate = preset.faceplate.clone();
+        self.selected_baseframe = preset.baseframe.clone();
+        self.use_joint_positions = preset.use_joint_positions;
+        self.set_manual_joint_positions = preset.use_joint_positions;
+        self.joint_positions = preset.joint_positions;
+        self.saved_joint_positions = None;
+        self.use_preferred_joint_config = preset.use_preferred_joint_config;
+        self.set_manual_joint_config = preset.use_preferred_joint_config;
+        self.preferred_joint_config = preset.preferred_joint_config;
+        self.saved_joint_config = None;
+        self.use_payload = preset.use_payload;
+        self.saved_payload = match self.payload_presets.get(&preset.payload) {
+            Some(payload) => payload.name.clone(),
+            None => {
+                log::warn!("Payload {} is not in the payload presets", preset.payload);
+                payloads::NO_PAYLOAD.to_string()
+            }
+        };
+        self.use_execution_time = preset.use_execution_time;
+        self.execution_time_s = preset.execution_time_s;
+        self.use_blend_radius = preset.use_blend_radius;
+        self.blend_radius = preset.blend_radius;
+        self.force_threshold = preset.force_threshold;
+        self.use_relative_pose = preset.use_relative_pose;
+        self.relative_pose = preset.relative_pose;
+        self.search = preset.search;
+        self.screw = preset.screw;
+    }
+
+    /// Loads the filled-in preset into the tab and sends it, `count` times
+    /// waiting for each to finish.
+    fn run_preset(
+        &mut self,
+        run: PresetRun,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+    ) {
+        log::info!("Running preset {} ({} times)", run.name, run.count);
+        self.apply_preset(&run.preset);
+        self.command_trigger = true;
+        self.cancel_request = false;
+        self.preset_status = None;
+        if run.count <= 1 {
+            self.spawn_robot_control_promise(handle, connection);
+            return;
+        }
+        let state = match robot_command_tab_to_state(self) {
+            Ok(state) => state,
+            Err(e) => {
+                self.preset_status = Some(Err(e));
+                return;
+            }
+        };
+        let robot_id = self.robot_id_input.clone();
+        let command_type = self.command_type.to_string();
+        let goal = self.selected_goal_feature_id.clone();
+        let count = run.count;
+        let handle = handle.clone();
+        let con_clone = connection.clone();
+        self.preset_promise = Some(workers::spawn_long("preset_run", move || {
+            handle.block_on(async move {
+                for i in 0..count {
+                    history::record(&HistoryEntry::now(
+                        &robot_id,
+                        &command_type,
+                        goal.as_deref(),
+                    ));
+                    send_robot_command_and_wait(
+                        &state,
+                        con_clone.clone(),
+                        &robot_id,
+                        PRESET_TIMEOUT,
+                    )
+                    .await
+                    .map_err(|e| format!("Run {} of {}: {}", i + 1, count, e))?;
+                }
+                Ok(count)
+            })
+        }));
+    }
+
+    /// Adds the form as it is now to the command queue.
+    fn queue_current_command(&mut self) {
+        // The form is written as if it were sent, the flags are put back after
+        let flags = (self.command_trigger, self.cancel_request);
+        self.command_trigger = true;
+        self.cancel_request = false;
+        let state = robot_command_tab_to_state(self);
+        (self.command_trigger, self.cancel_request) = flags;
+        match state {
+            Ok(state) => self.command_queue.push(Step::new(
+                &self.robot_id_input,
+                &self.command_type.to_string(),
+                self.selected_goal_feature_id.as_deref(),
+                crate::backend::command_parameters(&self.robot_id_input, &state),
+            )),
+            Err(e) => {
+                log::error!("Failed to queue the command: {}", e);
+                self.command_queue
+                    .set_error(format!("Failed to queue the command: {}", e));
+            }
+        }
+    }
+
+    fn test_move_frames(&self) -> Result<TestMoveFrames, String> {
+        Ok(TestMoveFrames {
+            robot_id: self.robot_id_input.clone(),
+            baseframe: self
+                .selected_baseframe
+                .clone()
+                .ok_or("Baseframe not selected")?,
+            faceplate: self
+                .selected_faceplate
+                .clone()
+                .ok_or("Faceplate not selected")?,
+            tcp: self.selected_tcp.clone().ok_or("Tcp not selected")?,
+        })
+    }
+
+    /// The command as the preview needs it, from the same selections
+    /// `robot_command_tab_to_state` writes.
+    fn preview_request(&self) -> Result<PreviewRequest, String> {
+        let linear = match self.command_type {
+            CommandType::UnsafeMoveJ | CommandType::SafeMoveJ => false,
+            CommandType::UnsafeMoveL | CommandType::SafeMoveL => true,
+            CommandType::PickVacuum
+            | CommandType::PlaceVacuum
+            | CommandType::SpiralSearch
+            | CommandType::Screwdrive => {
+                return Err(format!("{} can't be previewed", self.command_type));
+            }
+        };
+        Ok(PreviewRequest {
+            robot_id: self.robot_id_input.clone(),
+            linear,
+            baseframe: self
+                .selected_baseframe
+                .clone()
+                .ok_or("Baseframe not selected")?,
+            faceplate: self
+                .selected_faceplate
+                .clone()
+                .ok_or("Faceplate not selected")?,
+            goal: self.selected_goal_feature_id.clone(),
+            tcp: self.selected_tcp.clone(),
+            joint_target: self.use_joint_positions.then_some(self.joint_positions),
+            preferred_joint_config: self
+                .use_preferred_joint_config
+                .then_some(self.preferred_joint_config),
+            use_relative_pose: self.use_relative_pose,
+        })
     }
 
     // --- Transform Polling Functions (Copied) ---
@@ -747,9 +2025,14 @@ impl RobotTab {
         let mut keys: Vec<String> = result.keys().cloned().collect();
         keys.sort_unstable();
         self.transform_keys = keys;
+        self.taught_joints = result
+            .iter()
+            .filter_map(|(key, tf)| Some((key.clone(), joint_drift::taught_configuration(tf)?)))
+            .collect();
+        self.frame_usage = history::frame_usage(&history::load());
 
         if let Some(pose) = &self.selected_goal_feature_id {
-            if !self.transform_keys.contains(pose) {
+            if !self.transform_keys.contains(pose) && !self.aliases.contains(pose) {
                 self.selected_goal_feature_id = None;
             }
         }
@@ -760,13 +2043,67 @@ impl RobotTab {
         handle: &tokio::runtime::Handle,
         connection: &Arc<ConnectionManager>,
     ) {
+        self.aliases.spawn_load(handle, connection);
         let handle = handle.clone();
         let con_clone = connection.clone();
-        self.get_all_transforms_promise = Some(Promise::spawn_thread("fetcher", move || {
+        self.get_all_transforms_promise = Some(workers::spawn("fetcher", move || {
             handle.block_on(get_all_transforms(con_clone))
         }));
     }
 
+    /// Sends the screwdriving command, waits for the spindle and records the
+    /// command with its result once it is read.
+    fn spawn_process_promise(
+        &mut self,
+        state: State,
+        mut entry: HistoryEntry,
+        handle: tokio::runtime::Handle,
+        connection: Arc<ConnectionManager>,
+    ) {
+        let program = self.screw.program;
+        self.last_process = None;
+        self.process_promise = Some(workers::spawn_long("screwdriving", move || {
+            handle.block_on(async move {
+                let sent = send_robot_command_and_wait(
+                    &state,
+                    connection.clone(),
+                    &entry.robot_id,
+                    PROCESS_TIMEOUT,
+                )
+                .await;
+                // A failed screw still reports why, e.g. torque not reached
+                let result =
+                    crate::screwdriving::read_result(connection, &entry.robot_id, program).await;
+                entry.process = Some(result);
+                history::record(&entry);
+                sent.map(|_| result)
+            })
+        }));
+    }
+
+    fn draw_process_result(&mut self, ui: &mut egui::Ui) {
+        if let Some(promise) = self.process_promise.take() {
+            match promise.try_take() {
+                Ok(result) => self.last_process = Some(result),
+                Err(promise) => self.process_promise = Some(promise),
+            }
+        }
+        if self.process_promise.is_some() {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Screwing…");
+            });
+            ui.ctx().request_repaint();
+        }
+        match &self.last_process {
+            Some(Ok(result)) => result.draw(ui),
+            Some(Err(e)) => {
+                ui.colored_label(egui::Color32::RED, e);
+            }
+            None => (),
+        }
+    }
+
     fn spawn_robot_control_promise(
         &mut self,
         handle: &tokio::runtime::Handle,
@@ -776,10 +2113,38 @@ impl RobotTab {
         let con_clone = connection.clone();
         match robot_command_tab_to_state(&self) {
             Ok(state) => {
-                self.robot_control_promise =
-                    Some(Promise::spawn_thread("robot_control", move || {
-                        handle.block_on(send_robot_command(&state, con_clone))
-                    }));
+                self.last_written = state
+                    .state
+                    .iter()
+                    .map(|(key, assignment)| (key.clone(), assignment.val.clone()))
+                    .collect();
+                self.last_written_at = Some(crate::storage::now_secs());
+                if self.command_trigger {
+                    let goal = self.selected_goal_feature_id.as_deref();
+                    let entry = HistoryEntry::now(
+                        &self.robot_id_input,
+                        &self.command_type.to_string(),
+                        goal,
+                    );
+                    if let Some(goal) = goal {
+                        let usage = self.frame_usage.entry(goal.to_string()).or_default();
+                        usage.count += 1;
+                        usage.last_used = entry.timestamp;
+                    }
+                    self.command_feedback.sent(
+                        &self.robot_id_input,
+                        &self.command_type.to_string(),
+                        goal,
+                    );
+                    if self.command_type == CommandType::Screwdrive {
+                        self.spawn_process_promise(state, entry, handle, con_clone);
+                        return;
+                    }
+                    history::record(&entry);
+                }
+                self.robot_control_promise = Some(workers::spawn("robot_control", move || {
+                    handle.block_on(send_robot_command(&state, con_clone))
+                }));
             }
             Err(_) => (),
         }
@@ -811,10 +2176,89 @@ fn draw_pose_selector(
     });
 }
 
-/// Helper to draw 6 joint input fields in a grid
-fn draw_joint_inputs(ui: &mut egui::Ui, joints: &mut [f64; 6], id_prefix: &str) {
-    let rad_range = -6.28..=6.28;
+/// Like `draw_pose_selector`, but most used frames first with their use counts
+fn draw_goal_selector(
+    ui: &mut egui::Ui,
+    label_text: &str,
+    id_source: &str,
+    selection: &mut Option<String>,
+    keys: &[String],
+    usage: &HashMap<String, FrameUsage>,
+) {
+    let mut sorted = keys.to_vec();
+    history::sort_by_usage(&mut sorted, usage);
+    ui.horizontal(|ui| {
+        ui.label(label_text);
+        let selected_text = selection.as_deref().unwrap_or("Select...");
 
+        egui::ComboBox::from_id_salt(id_source)
+            .selected_text(selected_text)
+            .show_ui(ui, |ui| {
+                ui.selectable_value(selection, None, "None");
+                for key in &sorted {
+                    let label = match usage.get(key) {
+                        Some(u) if u.count > 0 => format!("{} ({})", key, u.count),
+                        _ => key.clone(),
+                    };
+                    ui.selectable_value(selection, Some(key.clone()), label);
+                }
+            });
+    });
+}
+
+/// Combo box of the payload preset names, with the selected preset's values on hover.
+fn draw_saved_payload_select(
+    ui: &mut egui::Ui,
+    id_salt: &str,
+    selected: &mut String,
+    presets: &PayloadPresets,
+) {
+    let response = egui::ComboBox::from_id_salt(id_salt)
+        .selected_text(selected.as_str())
+        .show_ui(ui, |ui| {
+            for name in presets.names() {
+                ui.selectable_value(selected, name.to_string(), name);
+            }
+        })
+        .response;
+    if let Some(preset) = presets.get(selected) {
+        response.on_hover_text(format!(
+            "{} kg, CoG [{}] m",
+            locale::format_number(preset.mass, 3),
+            locale::format_numbers(&preset.cog, 3)
+        ));
+    }
+}
+
+/// The robot's current joints, to compare with the inputs above.
+fn draw_live_joints(ui: &mut egui::Ui, joints: Option<&[f64]>) {
+    ui.horizontal(|ui| {
+        ui.label("Live Joints:");
+        match joints {
+            Some(joints) => {
+                for (i, joint) in joints.iter().enumerate() {
+                    ui.monospace(format!(
+                        "J{}: {} rad",
+                        i + 1,
+                        locale::format_number(*joint, 3)
+                    ))
+                    .on_hover_text(format!("{}°", locale::format_number(joint.to_degrees(), 1)));
+                }
+            }
+            None => {
+                ui.weak("not available");
+            }
+        }
+    });
+}
+
+/// Helper to draw 6 joint input fields in a grid, bounded by the soft limits
+fn draw_joint_inputs(
+    ui: &mut egui::Ui,
+    joints: &mut [f64; 6],
+    id_prefix: &str,
+    limits: &JointLimits,
+) {
     egui::Grid::new(id_prefix)
         .num_columns(4)
         .spacing([20.0, 4.0])
@@ -823,15 +2267,17 @@ fn draw_joint_inputs(ui: &mut egui::Ui, joints: &mut [f64; 6], id_prefix: &str)
             ui.label("J1:");
             ui.add(
                 egui::DragValue::new(&mut joints[0])
+                    .localized_in(Unit::Radian)
                     .suffix(" rad")
-                    .range(rad_range.clone())
+                    .range(limits.range(0))
                     .speed(0.01),
             );
             ui.label("J2:");
             ui.add(
                 egui::DragValue::new(&mut joints[1])
+                    .localized_in(Unit::Radian)
                     .suffix(" rad")
-                    .range(rad_range.clone())
+                    .range(limits.range(1))
                     .speed(0.01),
             );
             ui.end_row();
@@ -839,15 +2285,17 @@ fn draw_joint_inputs(ui: &mut egui::Ui, joints: &mut [f64; 6], id_prefix: &str)
             ui.label("J3:");
             ui.add(
                 egui::DragValue::new(&mut joints[2])
+                    .localized_in(Unit::Radian)
                     .suffix(" rad")
-                    .range(rad_range.clone())
+                    .range(limits.range(2))
                     .speed(0.01),
             );
             ui.label("J4:");
             ui.add(
                 egui::DragValue::new(&mut joints[3])
+                    .localized_in(Unit::Radian)
                     .suffix(" rad")
-                    .range(rad_range.clone())
+                    .range(limits.range(3))
                     .speed(0.01),
             );
             ui.end_row();
@@ -855,15 +2303,17 @@ fn draw_joint_inputs(ui: &mut egui::Ui, joints: &mut [f64; 6], id_prefix: &str)
             ui.label("J5:");
             ui.add(
                 egui::DragValue::new(&mut joints[4])
+                    .localized_in(Unit::Radian)
                     .suffix(" rad")
-                    .range(rad_range.clone())
+                    .range(limits.range(4))
                     .speed(0.01),
             );
             ui.label("J6:");
             ui.add(
                 egui::DragValue::new(&mut joints[5])
+                    .localized_in(Unit::Radian)
                     .suffix(" rad")
-                    .range(rad_range.clone())
+                    .range(limits.range(5))
                     .speed(0.01),
             );
             ui.end_row();
@@ -879,12 +2329,14 @@ fn draw_relative_pose_inputs(ui: &mut egui::Ui, poses: &mut [f64; 6], id_prefix:
             ui.label("x:");
             ui.add(
                 egui::DragValue::new(&mut poses[0])
+                    .localized_in(Unit::Meter)
                     .suffix(" m")
                     .speed(0.001),
             );
             ui.label("rx:");
             ui.add(
                 egui::DragValue::new(&mut poses[3])
+                    .localized_in(Unit::Radian)
                     .suffix(" rad")
                     .speed(0.01),
             );
@@ -893,12 +2345,14 @@ fn draw_relative_pose_inputs(ui: &mut egui::Ui, poses: &mut [f64; 6], id_prefix:
             ui.label("y:");
             ui.add(
                 egui::DragValue::new(&mut poses[1])
+                    .localized_in(Unit::Meter)
                     .suffix(" m")
                     .speed(0.001),
             );
             ui.label("ry:");
             ui.add(
                 egui::DragValue::new(&mut poses[4])
+                    .localized_in(Unit::Radian)
                     .suffix(" rad")
                     .speed(0.01),
             );
@@ -907,12 +2361,14 @@ fn draw_relative_pose_inputs(ui: &mut egui::Ui, poses: &mut [f64; 6], id_prefix:
             ui.label("z:");
             ui.add(
                 egui::DragValue::new(&mut poses[2])
+                    .localized_in(Unit::Meter)
                     .suffix(" m")
                     .speed(0.001),
             );
             ui.label("rz:");
             ui.add(
                 egui::DragValue::new(&mut poses[5])
+                    .localized_in(Unit::Radian)
                     .suffix(" rad")
                     .speed(0.01),
             );
@@ -920,7 +2376,6 @@ fn draw_relative_pose_inputs(ui: &mut egui::Ui, poses: &mut [f64; 6], id_prefix:
         });
 }
 
-// Should have one for dashboard as well
 pub fn robot_command_tab_to_state(tab: &RobotTab) -> Result<State, String> {
     let robot_name = &tab.robot_id_input;
     let state = State::new();
@@ -939,13 +2394,9 @@ pub fn robot_command_tab_to_state(tab: &RobotTab) -> Result<State, String> {
     let accelleration = fv!(&&format!("{}_accelleration", robot_name));
     let velocity = fv!(&&format!("{}_velocity", robot_name));
 
-    // Is this Dashboard? We should also have protective stop / violation release, pause and continue, get into remote control, set max force (safety)
-    // let global_acceleration_scaling = fv!(&&format!("{}_global_acceleration_scaling", robot_name));
-    // let global_velocity_scaling = fv!(&&format!("{}_global_velocity_scaling", robot_name));
+    // Global velocity/acceleration scaling is written by the speed override in the tab bar
+    // and the dashboard panel, which also sends the dashboard commands.
 
-    let dashboard_request_trigger = bv!(&&format!("{}_dashboard_request_trigger", robot_name));
-    let dashboard_request_state = v!(&&format!("{}_dashboard_request_state", robot_name));
-    let dashboard_command = v!(&&format!("{}_dashboard_command", robot_name));
     let use_execution_time = bv!(&&format!("{}_use_execution_time", robot_name));
     let execution_time = fv!(&&format!("{}_execution_time", robot_name));
     let use_blend_radius = bv!(&&format!("{}_use_blend_radius", robot_name));
@@ -959,28 +2410,21 @@ pub fn robot_command_tab_to_state(tab: &RobotTab) -> Result<State, String> {
     let preferred_joint_config = av!(&&format!("{}_preferred_joint_config", robot_name));
     let use_payload = bv!(&&format!("{}_use_payload", robot_name));
     let payload = v!(&&format!("{}_payload", robot_name));
+    let payload_mass = fv!(&&format!("{}_payload_mass", robot_name));
+    let payload_cog = av!(&&format!("{}_payload_cog", robot_name));
+    let payload_inertia = av!(&&format!("{}_payload_inertia", robot_name));
     let baseframe_id = v!(&&format!("{}_baseframe_id", robot_name));
     let faceplate_id = v!(&&format!("{}_faceplate_id", robot_name));
     let goal_feature_id = v!(&&format!("{}_goal_feature_id", robot_name));
     let tcp_id = v!(&&format!("{}_tcp_id", robot_name));
     let root_frame_id = v!(&&format!("{}_root_frame_id", robot_name));
-    // let cancel_current_goal = bv!(&&format!("{}_cancel_current_goal", robot_name));
+    let cancel_current_goal = bv!(&&format!("{}_cancel_current_goal", robot_name));
     let force_threshold = fv!(&&format!("{}_force_threshold", robot_name));
     // let force_feedback = fv!(&&format!("{}_force_feedback", robot_name));
     // let estimated_position = v!(&&format!("{}_estimated_position", robot_name));
     let use_relative_pose = bv!(&&format!("{}_use_relative_pose", robot_name));
     let relative_pose = av!(&&format!("{}_relative_pose", robot_name));
 
-    let state = state.add(assign!(
-        dashboard_request_trigger,
-        tab.dashboard_trigger.to_spvalue()
-    ));
-    let state = state.add(assign!(dashboard_request_state, "initial".to_spvalue()));
-    let state = state.add(assign!(
-        dashboard_command,
-        SPValue::String(StringOrUnknown::String(tab.dashboard_command.clone()))
-    ));
-
     let state = state.add(assign!(
         command_type,
         SPValue::String(StringOrUnknown::String(tab.command_type.to_string()))
@@ -995,15 +2439,6 @@ pub fn robot_command_tab_to_state(tab: &RobotTab) -> Result<State, String> {
         SPValue::Float64(FloatOrUnknown::Float64(OrderedFloat(tab.velocity)))
     ));
 
-    // Is this dashboard?
-    // let state = state.add(assign!(
-    //     global_acceleration_scaling,
-    //     SPValue::Float64(FloatOrUnknown::Float64(OrderedFloat(tab.global_acceleration_scaling)))
-    // ));
-    // let state = state.add(assign!(
-    //     global_velocity_scaling,
-    //     SPValue::Float64(FloatOrUnknown::UNKNOWN)
-    // ));
     let state = state.add(assign!(
         use_execution_time,
         SPValue::Bool(BoolOrUnknown::Bool(tab.use_execution_time))
@@ -1053,9 +2488,51 @@ pub fn robot_command_tab_to_state(tab: &RobotTab) -> Result<State, String> {
         use_payload,
         SPValue::Bool(BoolOrUnknown::Bool(tab.use_payload))
     ));
+    // The driver sets the values, the name is only for the logs
+    let (payload_name, payload_values) = if tab.set_manual_payload {
+        (MANUAL_PAYLOAD.to_string(), tab.manual_payload.clone())
+    } else {
+        (
+            tab.saved_payload.clone(),
+            tab.payload_presets
+                .get(&tab.saved_payload)
+                .map(Payload::from)
+                .unwrap_or_default(),
+        )
+    };
     let state = state.add(assign!(
         payload,
-        SPValue::String(StringOrUnknown::String(tab.saved_payload.to_string()))
+        SPValue::String(StringOrUnknown::String(payload_name))
+    ));
+    let state = state.add(assign!(payload_mass, payload_values.mass.to_spvalue()));
+    let state = state.add(assign!(
+        payload_cog,
+        SPValue::Array(ArrayOrUnknown::Array(
+            [
+                payload_values.cog_x,
+                payload_values.cog_y,
+                payload_values.cog_z
+            ]
+            .iter()
+            .map(|x| x.to_spvalue())
+            .collect()
+        ))
+    ));
+    let state = state.add(assign!(
+        payload_inertia,
+        SPValue::Array(ArrayOrUnknown::Array(
+            [
+                payload_values.ixx,
+                payload_values.iyy,
+                payload_values.izz,
+                payload_values.ixy,
+                payload_values.ixz,
+                payload_values.iyz,
+            ]
+            .iter()
+            .map(|x| x.to_spvalue())
+            .collect()
+        ))
     ));
     let mut state = state.clone();
     if tab.command_trigger {
@@ -1106,11 +2583,8 @@ pub fn robot_command_tab_to_state(tab: &RobotTab) -> Result<State, String> {
         SPValue::String(StringOrUnknown::String("world".to_string()))
     ));
 
-    // Add later, connect to the Stop button. This is the action client and the stop is the dachboard
-    // let state = state.add(assign!(
-    //     cancel_current_goal,
-    //     SPValue::Bool(BoolOrUnknown::UNKNOWN)
-    // ));
+    // Set by the Stop button, cleared with every new command so it doesn't cancel it
+    let state = state.add(assign!(cancel_current_goal, false.to_spvalue()));
     // let state = state.add(assign!(
     //     estimated_position,
     //     SPValue::String(StringOrUnknown::UNKNOWN)
@@ -1136,6 +2610,29 @@ pub fn robot_command_tab_to_state(tab: &RobotTab) -> Result<State, String> {
             tab.relative_pose.iter().map(|x| x.to_spvalue()).collect()
         ))
     ));
+    let mut state = state;
+    if tab.command_type == CommandType::SpiralSearch {
+        if tab.command_trigger {
+            if let Some(problem) = tab.search.problem() {
+                log::error!("{}", problem);
+                return Err(problem.to_string());
+            }
+        }
+        for (key, value) in tab.search.values(robot_name) {
+            state = state.add(assign!(fv!(&&key), value));
+        }
+    }
+    if tab.command_type == CommandType::Screwdrive {
+        if tab.command_trigger {
+            if let Some(problem) = tab.screw.problem() {
+                log::error!("{}", problem);
+                return Err(problem.to_string());
+            }
+        }
+        for (key, value) in tab.screw.values(robot_name) {
+            state = state.add(assign!(fv!(&&key), value));
+        }
+    }
 
     Ok(state)
 }
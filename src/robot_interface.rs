@@ -0,0 +1,387 @@
+//! The `{robot}_*` state variables the GUI and a robot driver share. Registering
+//! a robot writes all of them with neutral defaults, so a new driver finds the
+//! interface in place instead of hand-made redis keys.
+
+use micro_sp::*;
+use ordered_float::OrderedFloat;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VariableType {
+    Bool,
+    Float,
+    String,
+    Array,
+}
+
+impl VariableType {
+    pub fn matches(self, value: &SPValue) -> bool {
+        matches!(
+            (self, value),
+            (VariableType::Bool, SPValue::Bool(_))
+                | (VariableType::Float, SPValue::Float64(_))
+                | (VariableType::String, SPValue::String(_))
+                | (VariableType::Array, SPValue::Array(_))
+        )
+    }
+}
+
+impl std::fmt::Display for VariableType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VariableType::Bool => write!(f, "bool"),
+            VariableType::Float => write!(f, "float"),
+            VariableType::String => write!(f, "string"),
+            VariableType::Array => write!(f, "array"),
+        }
+    }
+}
+
+/// Default written when the variable is registered.
+#[derive(Debug, Clone, Copy)]
+pub enum DefaultValue {
+    Unknown,
+    Bool(bool),
+    Float(f64),
+    Text(&'static str),
+    /// Six zeros, one per joint.
+    Joints,
+}
+
+pub struct InterfaceVariable {
+    /// Name without the robot prefix, e.g. `request_trigger`.
+    pub suffix: &'static str,
+    pub variable_type: VariableType,
+    pub default: DefaultValue,
+}
+
+const fn var(
+    suffix: &'static str,
+    variable_type: VariableType,
+    default: DefaultValue,
+) -> InterfaceVariable {
+    InterfaceVariable {
+        suffix,
+        variable_type,
+        default,
+    }
+}
+
+/// Everything the GUI writes in `robot_command_tab_to_state` and the speed
+/// override, plus what it reads back from the driver.
+pub const INTERFACE: &[InterfaceVariable] = &[
+    var(
+        "request_trigger",
+        VariableType::Bool,
+        DefaultValue::Bool(false),
+    ),
+    var(
+        "request_state",
+        VariableType::String,
+        DefaultValue::Text("initial"),
+    ),
+    var("fail_reason", VariableType::String, DefaultValue::Unknown),
+    var(
+        "request_cancel",
+        VariableType::Bool,
+        DefaultValue::Bool(false),
+    ),
+    var(
+        "cancel_current_goal",
+        VariableType::Bool,
+        DefaultValue::Bool(false),
+    ),
+    var("command_type", VariableType::String, DefaultValue::Unknown),
+    var(
+        "accelleration",
+        VariableType::Float,
+        DefaultValue::Float(0.0),
+    ),
+    var("velocity", VariableType::Float, DefaultValue::Float(0.0)),
+    var(
+        "global_acceleration_scaling",
+        VariableType::Float,
+        DefaultValue::Float(1.0),
+    ),
+    var(
+        "global_velocity_scaling",
+        VariableType::Float,
+        DefaultValue::Float(1.0),
+    ),
+    var(
+        "dashboard_request_trigger",
+        VariableType::Bool,
+        DefaultValue::Bool(false),
+    ),
+    var(
+        "dashboard_request_state",
+        VariableType::String,
+        DefaultValue::Text("initial"),
+    ),
+    var(
+        "dashboard_command",
+        VariableType::String,
+        DefaultValue::Unknown,
+    ),
+    var(
+        "use_execution_time",
+        VariableType::Bool,
+        DefaultValue::Bool(false),
+    ),
+    var(
+        "execution_time",
+        VariableType::Float,
+        DefaultValue::Float(0.0),
+    ),
+    var(
+        "use_blend_radius",
+        VariableType::Bool,
+        DefaultValue::Bool(false),
+    ),
+    var(
+        "blend_radius",
+        VariableType::Float,
+        DefaultValue::Float(0.0),
+    ),
+    var(
+        "use_joint_positions",
+        VariableType::Bool,
+        DefaultValue::Bool(false),
+    ),
+    var("joint_positions", VariableType::Array, DefaultValue::Joints),
+    var(
+        "use_preferred_joint_config",
+        VariableType::Bool,
+        DefaultValue::Bool(false),
+    ),
+    var(
+        "preferred_joint_config",
+        VariableType::Array,
+        DefaultValue::Joints,
+    ),
+    var("use_payload", VariableType::Bool, DefaultValue::Bool(false)),
+    var("payload", VariableType::String, DefaultValue::Unknown),
+    var(
+        "payload_mass",
+        VariableType::Float,
+        DefaultValue::Float(0.0),
+    ),
+    var("payload_cog", VariableType::Array, DefaultValue::Unknown),
+    var(
+        "payload_inertia",
+        VariableType::Array,
+        DefaultValue::Unknown,
+    ),
+    var("baseframe_id", VariableType::String, DefaultValue::Unknown),
+    var("faceplate_id", VariableType::String, DefaultValue::Unknown),
+    var(
+        "goal_feature_id",
+        VariableType::String,
+        DefaultValue::Unknown,
+    ),
+    var("tcp_id", VariableType::String, DefaultValue::Unknown),
+    var(
+        "root_frame_id",
+        VariableType::String,
+        DefaultValue::Text("world"),
+    ),
+    var(
+        "force_threshold",
+        VariableType::Float,
+        DefaultValue::Float(0.0),
+    ),
+    var(
+        "use_relative_pose",
+        VariableType::Bool,
+        DefaultValue::Bool(false),
+    ),
+    var("relative_pose", VariableType::Array, DefaultValue::Joints),
+    var(
+        "search_radius",
+        VariableType::Float,
+        DefaultValue::Float(0.005),
+    ),
+    var(
+        "search_pitch",
+        VariableType::Float,
+        DefaultValue::Float(0.0005),
+    ),
+    var(
+        "search_max_force",
+        VariableType::Float,
+        DefaultValue::Float(20.0),
+    ),
+    var(
+        "search_timeout",
+        VariableType::Float,
+        DefaultValue::Float(10.0),
+    ),
+    var(
+        "screw_program",
+        VariableType::Float,
+        DefaultValue::Float(1.0),
+    ),
+    var(
+        "screw_torque_target",
+        VariableType::Float,
+        DefaultValue::Float(2.0),
+    ),
+    var(
+        "screw_final_torque",
+        VariableType::Float,
+        DefaultValue::Unknown,
+    ),
+    var(
+        "screw_final_angle",
+        VariableType::Float,
+        DefaultValue::Unknown,
+    ),
+    var("screw_result", VariableType::String, DefaultValue::Unknown),
+    var("joint_states", VariableType::Array, DefaultValue::Unknown),
+    var("safety_mode", VariableType::String, DefaultValue::Unknown),
+    var("enabled", VariableType::Bool, DefaultValue::Bool(true)),
+];
+
+impl InterfaceVariable {
+    pub fn key(&self, robot_id: &str) -> String {
+        format!("{}_{}", robot_id, self.suffix)
+    }
+
+    pub fn default_value(&self) -> SPValue {
+        match (self.default, self.variable_type) {
+            (DefaultValue::Bool(b), _) => b.to_spvalue(),
+            (DefaultValue::Float(x), _) => {
+                SPValue::Float64(FloatOrUnknown::Float64(OrderedFloat(x)))
+            }
+            (DefaultValue::Text(s), _) => s.to_spvalue(),
+            (DefaultValue::Joints, _) => SPValue::Array(ArrayOrUnknown::Array(
+                [0.0; 6].iter().map(|x| x.to_spvalue()).collect(),
+            )),
+            (DefaultValue::Unknown, VariableType::Bool) => SPValue::Bool(BoolOrUnknown::UNKNOWN),
+            (DefaultValue::Unknown, VariableType::Float) => {
+                SPValue::Float64(FloatOrUnknown::UNKNOWN)
+            }
+            (DefaultValue::Unknown, VariableType::String) => {
+                SPValue::String(StringOrUnknown::UNKNOWN)
+            }
+            (DefaultValue::Unknown, VariableType::Array) => SPValue::Array(ArrayOrUnknown::UNKNOWN),
+        }
+    }
+}
+
+/// Robot ids become key prefixes, so only letters, digits and `_` are allowed.
+pub fn validate_robot_id(robot_id: &str) -> Result<(), String> {
+    if robot_id.is_empty() {
+        return Err("Enter a robot id".to_string());
+    }
+    if !robot_id
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        return Err("Use only letters, digits and _ in the robot id".to_string());
+    }
+    Ok(())
+}
+
+/// Writes the interface of a robot, keeping variables that already exist
+/// unless `overwrite` is set. Returns how many variables were written.
+pub async fn register_robot(
+    con: Arc<ConnectionManager>,
+    robot_id: String,
+    overwrite: bool,
+) -> Result<usize, String> {
+    validate_robot_id(&robot_id)?;
+    let mut connection = con.get_connection().await;
+    let existing = if overwrite {
+        State::new()
+    } else {
+        StateManager::get_full_state(&mut connection)
+            .await
+            .ok_or_else(|| "Failed to get the full state".to_string())?
+    };
+    let values = INTERFACE
+        .iter()
+        .map(|variable| (variable.key(&robot_id), variable.default_value()))
+        .filter(|(key, _)| !existing.state.contains_key(key))
+        .collect::<Vec<(String, SPValue)>>();
+    let written = values.len();
+    if written > 0 {
+        crate::backend::set_values(con.clone(), values).await;
+    }
+    log::info!("Registered robot {}, wrote {} variables", robot_id, written);
+    Ok(written)
+}
+
+fn type_name(value: &SPValue) -> &'static str {
+    match value {
+        SPValue::Bool(_) => "bool",
+        SPValue::Float64(_) => "float",
+        SPValue::String(_) => "string",
+        SPValue::Array(_) => "array",
+        _ => "other",
+    }
+}
+
+/// A variable of the interface that the state doesn't have as expected.
+#[derive(Debug, Clone)]
+pub struct Issue {
+    pub key: String,
+    pub expected: VariableType,
+    /// Type found in the state, `None` if the variable is missing.
+    pub found: Option<&'static str>,
+    /// Value written by the fix-up.
+    pub fix: SPValue,
+}
+
+impl Issue {
+    pub fn description(&self) -> String {
+        match self.found {
+            None => format!("missing, expected {}", self.expected),
+            Some(found) => format!("is {}, expected {}", found, self.expected),
+        }
+    }
+}
+
+/// Compares the state with the interface of a robot. Returns the issues and
+/// how many variables were checked.
+pub async fn check_interface(
+    con: Arc<ConnectionManager>,
+    robot_id: String,
+) -> Result<(Vec<Issue>, usize), String> {
+    validate_robot_id(&robot_id)?;
+    let mut connection = con.get_connection().await;
+    let state = StateManager::get_full_state(&mut connection)
+        .await
+        .ok_or_else(|| "Failed to get the full state".to_string())?;
+    let issues = INTERFACE
+        .iter()
+        .filter_map(|variable| {
+            let key = variable.key(&robot_id);
+            let found = match state.state.get(&key) {
+                None => None,
+                Some(assignment) if variable.variable_type.matches(&assignment.val) => {
+                    return None;
+                }
+                Some(assignment) => Some(type_name(&assignment.val)),
+            };
+            Some(Issue {
+                key,
+                expected: variable.variable_type,
+                found,
+                fix: variable.default_value(),
+            })
+        })
+        .collect();
+    Ok((issues, INTERFACE.len()))
+}
+
+/// Writes the default of each variable with an issue, replacing mistyped values.
+pub async fn fix_issues(con: Arc<ConnectionManager>, issues: Vec<Issue>) -> usize {
+    let values = issues
+        .into_iter()
+        .map(|issue| (issue.key, issue.fix))
+        .collect::<Vec<(String, SPValue)>>();
+    let written = values.len();
+    crate::backend::set_values(con, values).await;
+    written
+}
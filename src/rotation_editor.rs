@@ -0,0 +1,169 @@
+//! Rotation input as an axis and an angle, with a small 3D preview of the
+//! rotated axes that can be dragged to turn the frame. Easier to get right
+//! than typing a quaternion by hand.
+
+use crate::locale::LocalizedDragValue;
+use crate::pose;
+use eframe::egui;
+
+/// Direction the preview is seen from, like the usual RViz view.
+const AZIMUTH: f64 = std::f64::consts::FRAC_PI_4;
+const ELEVATION: f64 = std::f64::consts::FRAC_PI_6;
+/// Radians the frame turns per point dragged on the preview.
+const DRAG_SPEED: f64 = 0.01;
+const GIZMO_SIZE: f32 = 120.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AxisAngle {
+    /// Unit axis, kept while the angle is zero.
+    axis: [f64; 3],
+    /// Radians.
+    angle: f64,
+}
+
+impl Default for AxisAngle {
+    fn default() -> Self {
+        Self {
+            axis: [0.0, 0.0, 1.0],
+            angle: 0.0,
+        }
+    }
+}
+
+impl AxisAngle {
+    pub fn quaternion(&self) -> [f64; 4] {
+        pose::axis_angle_to_quaternion(self.axis, self.angle)
+    }
+
+    pub fn set_quaternion(&mut self, q: [f64; 4]) {
+        let (axis, angle) = pose::quaternion_to_axis_angle(q);
+        if angle > 1e-9 {
+            self.axis = axis;
+        }
+        self.angle = angle;
+    }
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+/// Screen right, screen up and towards the viewer, in the frame's parent.
+fn view_axes() -> [[f64; 3]; 3] {
+    let (sa, ca) = AZIMUTH.sin_cos();
+    let (se, ce) = ELEVATION.sin_cos();
+    [
+        [-sa, ca, 0.0],
+        [-se * ca, -se * sa, ce],
+        [ce * ca, ce * sa, se],
+    ]
+}
+
+/// Axis, angle in degrees and the preview next to them.
+pub fn draw(ui: &mut egui::Ui, value: &mut AxisAngle, id_prefix: &str) {
+    ui.horizontal(|ui| {
+        egui::Grid::new(id_prefix)
+            .num_columns(2)
+            .spacing([10.0, 4.0])
+            .show(ui, |ui| {
+                ui.label("Axis:");
+                ui.horizontal(|ui| {
+                    let mut axis = value.axis;
+                    for (label, component) in ["x", "y", "z"].iter().zip(axis.iter_mut()) {
+                        ui.add(
+                            egui::DragValue::new(component)
+                                .localized()
+                                .prefix(format!("{} ", label))
+                                .range(-1.0..=1.0)
+                                .speed(0.01),
+                        );
+                    }
+                    // A zero axis has no direction, the last one is kept
+                    let norm = dot(axis, axis).sqrt();
+                    if norm > 1e-9 {
+                        value.axis = axis.map(|c| c / norm);
+                    }
+                    for (label, axis) in [
+                        ("X", [1.0, 0.0, 0.0]),
+                        ("Y", [0.0, 1.0, 0.0]),
+                        ("Z", [0.0, 0.0, 1.0]),
+                    ] {
+                        if ui.small_button(label).clicked() {
+                            value.axis = axis;
+                        }
+                    }
+                });
+                ui.end_row();
+
+                ui.label("Angle:");
+                let mut degrees = value.angle.to_degrees();
+                if ui
+                    .add(egui::Slider::new(&mut degrees, -180.0..=180.0).suffix("°"))
+                    .changed()
+                {
+                    value.angle = degrees.to_radians();
+                }
+                ui.end_row();
+            });
+        draw_gizmo(ui, value);
+    });
+}
+
+/// The parent's axes faint and the rotated axes bold, X red, Y green, Z
+/// blue. Dragging sideways turns about the parent's z, up and down about the
+/// screen's horizontal.
+fn draw_gizmo(ui: &mut egui::Ui, value: &mut AxisAngle) {
+    let (response, painter) =
+        ui.allocate_painter(egui::vec2(GIZMO_SIZE, GIZMO_SIZE), egui::Sense::drag());
+    let rect = response.rect;
+    painter.rect_filled(rect, 4.0, ui.visuals().extreme_bg_color);
+    let [right, up, towards] = view_axes();
+    let center = rect.center();
+    let scale = GIZMO_SIZE as f64 * 0.38;
+    let project = |v: [f64; 3]| {
+        center + egui::vec2((dot(v, right) * scale) as f32, (-dot(v, up) * scale) as f32)
+    };
+
+    let q = value.quaternion();
+    let colors = [
+        egui::Color32::from_rgb(220, 60, 60),
+        egui::Color32::from_rgb(60, 190, 60),
+        egui::Color32::from_rgb(70, 110, 230),
+    ];
+    let units = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+    for (unit, color) in units.iter().zip(colors) {
+        painter.line_segment(
+            [center, project(*unit)],
+            egui::Stroke::new(1.0, color.gamma_multiply(0.35)),
+        );
+    }
+    // Farthest axis first so the nearer ones are drawn over it
+    let mut rotated = units
+        .iter()
+        .zip(colors)
+        .zip(["X", "Y", "Z"])
+        .map(|((unit, color), label)| (pose::rotate_vector(q, *unit), color, label))
+        .collect::<Vec<_>>();
+    rotated.sort_by(|a, b| dot(a.0, towards).total_cmp(&dot(b.0, towards)));
+    for (axis, color, label) in rotated {
+        let end = project(axis);
+        painter.line_segment([center, end], egui::Stroke::new(3.0, color));
+        painter.text(
+            end,
+            egui::Align2::CENTER_CENTER,
+            label,
+            egui::FontId::proportional(12.0),
+            color,
+        );
+    }
+    let response = response.on_hover_text("Drag to turn the frame");
+
+    let drag = response.drag_delta();
+    if drag != egui::Vec2::ZERO {
+        let turn = pose::quaternion_multiply(
+            pose::axis_angle_to_quaternion(right, drag.y as f64 * DRAG_SPEED),
+            pose::axis_angle_to_quaternion([0.0, 0.0, 1.0], drag.x as f64 * DRAG_SPEED),
+        );
+        value.set_quaternion(pose::quaternion_multiply(turn, q));
+    }
+}
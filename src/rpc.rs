@@ -0,0 +1,225 @@
+//! Optional local JSON-RPC 2.0 interface for test automation, exposing the same
+//! backend operations as the script console. One request per line over TCP:
+//!
+//! ```text
+//! {"jsonrpc": "2.0", "id": 1, "method": "lookup", "params": {"parent": "world", "child": "p1"}}
+//! ```
+
+use crate::backend;
+use crate::pose::Pose;
+use crate::provenance::Method;
+use micro_sp::*;
+use serde_json::{Value, json};
+use std::sync::Arc;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+};
+
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const BACKEND_ERROR: i64 = -32000;
+
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn invalid_params(message: impl Into<String>) -> Self {
+        Self {
+            code: INVALID_PARAMS,
+            message: message.into(),
+        }
+    }
+
+    fn backend(message: String) -> Self {
+        Self {
+            code: BACKEND_ERROR,
+            message,
+        }
+    }
+}
+
+fn sp_value_to_json(value: SPValue) -> Value {
+    match value {
+        SPValue::String(StringOrUnknown::String(s)) => Value::from(s),
+        SPValue::Bool(BoolOrUnknown::Bool(b)) => Value::from(b),
+        SPValue::Float64(FloatOrUnknown::Float64(f)) => Value::from(f.0),
+        SPValue::Array(ArrayOrUnknown::Array(values)) => {
+            Value::Array(values.into_iter().map(sp_value_to_json).collect())
+        }
+        other => Value::from(format!("{:?}", other)),
+    }
+}
+
+fn json_to_sp_value(value: &Value) -> Result<SPValue, RpcError> {
+    match value {
+        Value::Bool(b) => Ok(b.to_spvalue()),
+        Value::Number(n) => n
+            .as_f64()
+            .map(|f| f.to_spvalue())
+            .ok_or_else(|| RpcError::invalid_params("Number out of range")),
+        Value::String(s) => Ok(s.as_str().to_spvalue()),
+        Value::Array(values) => Ok(SPValue::Array(ArrayOrUnknown::Array(
+            values
+                .iter()
+                .map(json_to_sp_value)
+                .collect::<Result<Vec<SPValue>, RpcError>>()?,
+        ))),
+        _ => Err(RpcError::invalid_params(format!(
+            "Unsupported value {}",
+            value
+        ))),
+    }
+}
+
+fn pose_to_json(pose: &Pose) -> Value {
+    let [x, y, z] = pose.translation;
+    let [qx, qy, qz, qw] = pose.rotation;
+    json!({"x": x, "y": y, "z": z, "qx": qx, "qy": qy, "qz": qz, "qw": qw})
+}
+
+fn json_to_pose(value: &Value) -> Result<Pose, RpcError> {
+    let get = |key: &str, default: f64| -> Result<f64, RpcError> {
+        match value.get(key) {
+            None => Ok(default),
+            Some(v) => v.as_f64().ok_or_else(|| {
+                RpcError::invalid_params(format!("Pose field '{}' is not a number", key))
+            }),
+        }
+    };
+    Ok(Pose {
+        translation: [get("x", 0.0)?, get("y", 0.0)?, get("z", 0.0)?],
+        rotation: [
+            get("qx", 0.0)?,
+            get("qy", 0.0)?,
+            get("qz", 0.0)?,
+            get("qw", 1.0)?,
+        ],
+    })
+}
+
+fn str_param<'a>(params: &'a Value, key: &str) -> Result<&'a str, RpcError> {
+    params
+        .get(key)
+        .and_then(Value::as_str)
+        .ok_or_else(|| RpcError::invalid_params(format!("Missing string parameter '{}'", key)))
+}
+
+async fn call(
+    con: Arc<ConnectionManager>,
+    method: &str,
+    params: &Value,
+) -> Result<Value, RpcError> {
+    match method {
+        "frames" => Ok(Value::from(
+            backend::frame_names(con).await.map_err(RpcError::backend)?,
+        )),
+        "lookup" => {
+            let (parent, child) = (str_param(params, "parent")?, str_param(params, "child")?);
+            let pose = backend::lookup(con, parent, child)
+                .await
+                .map_err(RpcError::backend)?;
+            Ok(pose_to_json(&pose))
+        }
+        "publish_frame" => {
+            let (parent, child) = (str_param(params, "parent")?, str_param(params, "child")?);
+            let pose = json_to_pose(params.get("pose").unwrap_or(&Value::Null))?;
+            backend::publish_frame(con, parent, child, &pose, Method::Computed)
+                .await
+                .map_err(RpcError::backend)?;
+            Ok(Value::Null)
+        }
+        "get" => Ok(backend::get_value(con, str_param(params, "key")?)
+            .await
+            .map(sp_value_to_json)
+            .unwrap_or(Value::Null)),
+        "set" => {
+            let key = str_param(params, "key")?;
+            let value = json_to_sp_value(params.get("value").unwrap_or(&Value::Null))?;
+            backend::set_values_guarded(con, vec![(key.to_string(), value)])
+                .await
+                .map_err(RpcError::backend)?;
+            Ok(Value::Null)
+        }
+        "send_command" => {
+            let robot_id = str_param(params, "robot_id")?;
+            let parameters = params
+                .get("parameters")
+                .and_then(Value::as_object)
+                .ok_or_else(|| RpcError::invalid_params("Missing object parameter 'parameters'"))?
+                .iter()
+                .map(|(key, value)| Ok((key.clone(), json_to_sp_value(value)?)))
+                .collect::<Result<Vec<(String, SPValue)>, RpcError>>()?;
+            backend::send_command(con, robot_id, parameters, backend::COMMAND_TIMEOUT)
+                .await
+                .map_err(RpcError::backend)?;
+            Ok(Value::Null)
+        }
+        "submit_orders" => {
+            // Webhook for the MES, the orders show up in the Order Handler
+            crate::mes::receive(params.clone());
+            Ok(Value::Null)
+        }
+        _ => Err(RpcError {
+            code: METHOD_NOT_FOUND,
+            message: format!("Unknown method '{}'", method),
+        }),
+    }
+}
+
+async fn handle_request(con: Arc<ConnectionManager>, line: &str) -> Value {
+    let request: Value = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(e) => {
+            return json!({"jsonrpc": "2.0", "id": null,
+                "error": {"code": -32700, "message": format!("Parse error: {}", e)}});
+        }
+    };
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let method = request.get("method").and_then(Value::as_str).unwrap_or("");
+    let params = request.get("params").cloned().unwrap_or(Value::Null);
+    match call(con, method, &params).await {
+        Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+        Err(e) => json!({"jsonrpc": "2.0", "id": id,
+            "error": {"code": e.code, "message": e.message}}),
+    }
+}
+
+async fn serve_client(con: Arc<ConnectionManager>, stream: TcpStream) {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut response = handle_request(con.clone(), &line).await.to_string();
+        response.push('\n');
+        if writer.write_all(response.as_bytes()).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Listens on localhost only; remote access is out of scope for a test hook.
+pub fn spawn_rpc_server(handle: &tokio::runtime::Handle, con: Arc<ConnectionManager>, port: u16) {
+    handle.spawn(async move {
+        let listener = match TcpListener::bind(("127.0.0.1", port)).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                log::error!("Failed to start the RPC server on port {}: {}", port, e);
+                return;
+            }
+        };
+        log::info!("RPC server listening on 127.0.0.1:{}", port);
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    tokio::spawn(serve_client(con.clone(), stream));
+                }
+                Err(e) => log::error!("RPC server failed to accept a connection: {}", e),
+            }
+        }
+    });
+}
@@ -0,0 +1,175 @@
+use crate::pose::{self, Pose};
+use micro_sp::SPTransformStamped;
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
+/// Frame every scene tree is expected to hang from.
+pub const ROOT_FRAME: &str = "world";
+
+/// Thresholds used by the scene checks.
+#[derive(Debug, Clone)]
+pub struct LintConfig {
+    /// Translations longer than this (m) are reported as suspicious.
+    pub max_translation: f64,
+    /// Allowed deviation of a quaternion's norm from 1.
+    pub quaternion_tolerance: f64,
+    /// Transforms not updated for longer than this are reported as stale.
+    pub stale_after: Duration,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self {
+            max_translation: 5.0,
+            quaternion_tolerance: 1e-3,
+            stale_after: Duration::from_secs(30 * 86_400),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LintSeverity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone)]
+pub struct LintIssue {
+    pub severity: LintSeverity,
+    /// Key of the offending frame, used to jump to it.
+    pub frame: String,
+    pub message: String,
+}
+
+/// Runs all scene checks and returns the issues, errors first.
+pub fn lint_scene(
+    transforms: &HashMap<String, SPTransformStamped>,
+    config: &LintConfig,
+) -> Vec<LintIssue> {
+    let mut issues = Vec::new();
+    check_orphans(transforms, &mut issues);
+    check_cycles(transforms, &mut issues);
+    check_duplicate_names(transforms, &mut issues);
+    for (key, tf) in transforms {
+        check_transform(key, tf, config, &mut issues);
+    }
+    issues.sort_by(|a, b| {
+        a.severity
+            .cmp(&b.severity)
+            .then_with(|| a.frame.cmp(&b.frame))
+    });
+    issues
+}
+
+fn check_orphans(transforms: &HashMap<String, SPTransformStamped>, issues: &mut Vec<LintIssue>) {
+    for (key, tf) in transforms {
+        if tf.parent_frame_id != ROOT_FRAME && !transforms.contains_key(&tf.parent_frame_id) {
+            issues.push(LintIssue {
+                severity: LintSeverity::Error,
+                frame: key.clone(),
+                message: format!("Orphan: parent '{}' does not exist", tf.parent_frame_id),
+            });
+        }
+    }
+}
+
+fn check_cycles(transforms: &HashMap<String, SPTransformStamped>, issues: &mut Vec<LintIssue>) {
+    let mut reported = HashSet::new();
+    for key in transforms.keys() {
+        let mut visited = Vec::new();
+        let mut current = key.clone();
+        while let Some(tf) = transforms.get(&current) {
+            if let Some(pos) = visited.iter().position(|v| *v == current) {
+                let cycle = visited[pos..].to_vec();
+                let mut canonical = cycle.clone();
+                canonical.sort();
+                if reported.insert(canonical) {
+                    issues.push(LintIssue {
+                        severity: LintSeverity::Error,
+                        frame: current.clone(),
+                        message: format!("Cycle: {} -> {}", cycle.join(" -> "), current),
+                    });
+                }
+                break;
+            }
+            visited.push(current.clone());
+            current = tf.parent_frame_id.clone();
+        }
+    }
+}
+
+fn check_duplicate_names(
+    transforms: &HashMap<String, SPTransformStamped>,
+    issues: &mut Vec<LintIssue>,
+) {
+    let mut by_name: HashMap<String, Vec<&String>> = HashMap::new();
+    for (key, tf) in transforms {
+        if *key != tf.child_frame_id {
+            issues.push(LintIssue {
+                severity: LintSeverity::Error,
+                frame: key.clone(),
+                message: format!(
+                    "Stored under '{}' but child_frame_id is '{}'",
+                    key, tf.child_frame_id
+                ),
+            });
+        }
+        by_name
+            .entry(tf.child_frame_id.to_lowercase())
+            .or_default()
+            .push(key);
+    }
+    for keys in by_name.values().filter(|keys| keys.len() > 1) {
+        for key in keys {
+            issues.push(LintIssue {
+                severity: LintSeverity::Warning,
+                frame: (*key).clone(),
+                message: format!(
+                    "Duplicate child name (case-insensitive) with {}",
+                    keys.iter()
+                        .filter(|k| *k != key)
+                        .map(|k| k.as_str())
+                        .collect::<Vec<&str>>()
+                        .join(", ")
+                ),
+            });
+        }
+    }
+}
+
+fn check_transform(
+    key: &str,
+    tf: &SPTransformStamped,
+    config: &LintConfig,
+    issues: &mut Vec<LintIssue>,
+) {
+    let pose = Pose::from_sp_transform(&tf.transform);
+    let norm = pose::quaternion_norm(pose.rotation);
+    if (norm - 1.0).abs() > config.quaternion_tolerance {
+        issues.push(LintIssue {
+            severity: LintSeverity::Error,
+            frame: key.to_string(),
+            message: format!("Denormalized quaternion (norm {:.5})", norm),
+        });
+    }
+    let t = pose.translation;
+    let length = (t[0] * t[0] + t[1] * t[1] + t[2] * t[2]).sqrt();
+    if length > config.max_translation {
+        issues.push(LintIssue {
+            severity: LintSeverity::Warning,
+            frame: key.to_string(),
+            message: format!("Suspiciously large translation ({:.3} m)", length),
+        });
+    }
+    if let Ok(age) = tf.time_stamp.elapsed() {
+        if age > config.stale_after {
+            issues.push(LintIssue {
+                severity: LintSeverity::Warning,
+                frame: key.to_string(),
+                message: format!("Stale: last updated {} days ago", age.as_secs() / 86_400),
+            });
+        }
+    }
+}
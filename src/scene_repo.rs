@@ -0,0 +1,370 @@
+//! Version control of the scene directory, where exported frames and scenes
+//! are saved. If the directory is a git repository, the Scene Versions window
+//! shows changed files, commits them and browses the history of a file, so
+//! calibration changes stay traceable. Uses the `git` command line.
+
+use eframe::egui;
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+    sync::RwLock,
+};
+
+static SCENE_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Empty for none.
+pub fn set_scene_dir(dir: &str) {
+    let dir = dir.trim();
+    *SCENE_DIR.write().unwrap() = (!dir.is_empty()).then(|| PathBuf::from(dir));
+}
+
+/// Where export file dialogs start, if configured.
+pub fn scene_dir() -> Option<PathBuf> {
+    SCENE_DIR.read().unwrap().clone()
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+pub fn is_repo(dir: &Path) -> bool {
+    git(dir, &["rev-parse", "--is-inside-work-tree"]).is_ok_and(|out| out.trim() == "true")
+}
+
+/// A file with uncommitted changes, path relative to the repository root.
+pub struct FileStatus {
+    pub path: String,
+    /// Two-letter porcelain code, e.g. ` M` or `??`.
+    pub code: String,
+}
+
+impl FileStatus {
+    pub fn description(&self) -> &'static str {
+        match self.code.as_str() {
+            "??" => "new",
+            code if code.contains('D') => "deleted",
+            code if code.contains('A') => "added",
+            _ => "modified",
+        }
+    }
+}
+
+pub fn status(dir: &Path) -> Result<Vec<FileStatus>, String> {
+    let out = git(
+        dir,
+        &["status", "--porcelain", "--untracked-files=all", "--", "."],
+    )?;
+    Ok(out
+        .lines()
+        .filter(|line| line.len() > 3)
+        .map(|line| FileStatus {
+            code: line[..2].to_string(),
+            path: line[3..].trim_matches('"').to_string(),
+        })
+        .collect())
+}
+
+/// Commits only the given files, whatever else is staged.
+pub fn commit(dir: &Path, files: &[String], message: &str) -> Result<String, String> {
+    let mut add = vec!["add", "--all", "--"];
+    add.extend(files.iter().map(String::as_str));
+    git(dir, &add)?;
+    let mut commit = vec!["commit", "-m", message, "--"];
+    commit.extend(files.iter().map(String::as_str));
+    git(dir, &commit)?;
+    let hash = git(dir, &["rev-parse", "--short", "HEAD"])?;
+    Ok(format!(
+        "Committed {} files as {}",
+        files.len(),
+        hash.trim()
+    ))
+}
+
+pub struct LogEntry {
+    pub hash: String,
+    pub date: String,
+    pub author: String,
+    pub subject: String,
+}
+
+/// Commits touching a file, newest first.
+pub fn log(dir: &Path, file: &str) -> Result<Vec<LogEntry>, String> {
+    let out = git(
+        dir,
+        &[
+            "log",
+            "--follow",
+            "--format=%h%x1f%ad%x1f%an%x1f%s",
+            "--date=format:%Y-%m-%d %H:%M",
+            "--",
+            file,
+        ],
+    )?;
+    Ok(out
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\u{1f}');
+            Some(LogEntry {
+                hash: fields.next()?.to_string(),
+                date: fields.next()?.to_string(),
+                author: fields.next()?.to_string(),
+                subject: fields.next()?.to_string(),
+            })
+        })
+        .collect())
+}
+
+/// Diff of a file in one commit.
+pub fn show(dir: &Path, hash: &str, file: &str) -> Result<String, String> {
+    git(dir, &["show", "--format=%B", hash, "--", file])
+}
+
+/// Working tree changes of a file that aren't committed yet.
+pub fn diff_uncommitted(dir: &Path, file: &str) -> Result<String, String> {
+    git(dir, &["diff", "HEAD", "--", file])
+}
+
+#[derive(Default)]
+pub struct SceneVersionsWindow {
+    pub open: bool,
+    /// Checked on refresh rather than every frame, git is a process spawn.
+    is_repo: bool,
+    files: Vec<FileStatus>,
+    selected_files: Vec<String>,
+    message: String,
+    /// File whose history is shown.
+    history_file: String,
+    history: Vec<LogEntry>,
+    diff: Option<String>,
+    status: Option<Result<String, String>>,
+}
+
+impl SceneVersionsWindow {
+    pub fn show(&mut self) {
+        self.open = true;
+        self.refresh();
+    }
+
+    fn refresh(&mut self) {
+        let Some(dir) = scene_dir() else {
+            return;
+        };
+        self.is_repo = is_repo(&dir);
+        if !self.is_repo {
+            return;
+        }
+        match status(&dir) {
+            Ok(files) => {
+                self.selected_files
+                    .retain(|selected| files.iter().any(|f| &f.path == selected));
+                self.files = files;
+            }
+            Err(e) => self.status = Some(Err(e)),
+        }
+    }
+
+    fn load_history(&mut self, dir: &Path, file: String) {
+        self.diff = None;
+        match log(dir, &file) {
+            Ok(history) => self.history = history,
+            Err(e) => {
+                self.history.clear();
+                self.status = Some(Err(e));
+            }
+        }
+        self.history_file = file;
+    }
+
+    pub fn draw(&mut self, ctx: &egui::Context) {
+        if !self.open {
+            return;
+        }
+        let mut open = true;
+        egui::Window::new("Scene Versions")
+            .open(&mut open)
+            .default_width(640.0)
+            .show(ctx, |ui| {
+                let Some(dir) = scene_dir() else {
+                    ui.label("Set the scene directory in the Settings tab first.");
+                    return;
+                };
+                if !self.is_repo {
+                    ui.label(format!(
+                        "{} is not a git repository. Run `git init` there to track \
+                         calibration changes.",
+                        dir.display()
+                    ));
+                    if ui.button("Check Again").clicked() {
+                        self.refresh();
+                    }
+                    return;
+                }
+                ui.horizontal(|ui| {
+                    ui.label(format!("Repository: {}", dir.display()));
+                    if ui.button("Refresh").clicked() {
+                        self.refresh();
+                    }
+                });
+                match &self.status {
+                    Some(Ok(message)) => {
+                        ui.colored_label(egui::Color32::GREEN, message);
+                    }
+                    Some(Err(e)) => {
+                        ui.colored_label(egui::Color32::RED, e);
+                    }
+                    None => (),
+                }
+                ui.separator();
+                self.draw_changes(ui, &dir);
+                ui.separator();
+                self.draw_history(ui, &dir);
+            });
+        if !open {
+            self.open = false;
+        }
+    }
+
+    fn draw_changes(&mut self, ui: &mut egui::Ui, dir: &Path) {
+        ui.strong("Uncommitted changes");
+        if self.files.is_empty() {
+            ui.weak("Everything is committed.");
+            return;
+        }
+        let mut history = None;
+        egui::Grid::new("scene_versions_changes")
+            .num_columns(4)
+            .striped(true)
+            .show(ui, |ui| {
+                for file in &self.files {
+                    let mut selected = self.selected_files.contains(&file.path);
+                    if ui.checkbox(&mut selected, "").changed() {
+                        if selected {
+                            self.selected_files.push(file.path.clone());
+                        } else {
+                            self.selected_files.retain(|f| f != &file.path);
+                        }
+                    }
+                    ui.monospace(&file.path);
+                    ui.label(file.description());
+                    if ui.small_button("History").clicked() {
+                        history = Some(file.path.clone());
+                    }
+                    ui.end_row();
+                }
+            });
+        if let Some(file) = history {
+            self.load_history(dir, file);
+        }
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.message)
+                    .hint_text("What changed and why, e.g. recalibrated table after move")
+                    .desired_width(360.0),
+            );
+            let can_commit = !self.selected_files.is_empty() && !self.message.trim().is_empty();
+            if ui
+                .add_enabled(
+                    can_commit,
+                    egui::Button::new(format!("Commit ({})", self.selected_files.len())),
+                )
+                .clicked()
+            {
+                let result = commit(dir, &self.selected_files, self.message.trim());
+                match &result {
+                    Ok(_) => {
+                        self.message.clear();
+                        self.selected_files.clear();
+                    }
+                    Err(e) => log::error!("Failed to commit scene files: {}", e),
+                }
+                self.status = Some(result);
+                self.refresh();
+                if !self.history_file.is_empty() {
+                    self.load_history(dir, self.history_file.clone());
+                }
+            }
+        });
+    }
+
+    fn draw_history(&mut self, ui: &mut egui::Ui, dir: &Path) {
+        ui.horizontal(|ui| {
+            ui.strong("History of");
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.history_file)
+                    .hint_text("file in the repository")
+                    .desired_width(280.0),
+            );
+            let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            if ui.button("Show").clicked() || submitted {
+                self.load_history(dir, self.history_file.trim().to_string());
+            }
+        });
+        if self.history_file.is_empty() {
+            return;
+        }
+        let mut show_diff = None;
+        egui::ScrollArea::vertical()
+            .id_salt("scene_versions_history")
+            .max_height(160.0)
+            .show(ui, |ui| {
+                egui::Grid::new("scene_versions_history_grid")
+                    .num_columns(4)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        if ui.link("uncommitted").clicked() {
+                            show_diff = Some(None);
+                        }
+                        ui.label("");
+                        ui.label("");
+                        ui.label("");
+                        ui.end_row();
+                        for entry in &self.history {
+                            if ui.link(&entry.hash).clicked() {
+                                show_diff = Some(Some(entry.hash.clone()));
+                            }
+                            ui.label(&entry.date);
+                            ui.label(&entry.author);
+                            ui.label(&entry.subject);
+                            ui.end_row();
+                        }
+                    });
+            });
+        if let Some(hash) = show_diff {
+            let result = match &hash {
+                Some(hash) => show(dir, hash, &self.history_file),
+                None => diff_uncommitted(dir, &self.history_file),
+            };
+            match result {
+                Ok(diff) => self.diff = Some(diff),
+                Err(e) => self.status = Some(Err(e)),
+            }
+        }
+        if let Some(diff) = &self.diff {
+            egui::ScrollArea::both()
+                .id_salt("scene_versions_diff")
+                .max_height(300.0)
+                .show(ui, |ui| {
+                    for line in diff.lines() {
+                        let color = if line.starts_with('+') && !line.starts_with("+++") {
+                            egui::Color32::from_rgb(90, 190, 90)
+                        } else if line.starts_with('-') && !line.starts_with("---") {
+                            egui::Color32::from_rgb(220, 90, 90)
+                        } else {
+                            ui.visuals().text_color()
+                        };
+                        ui.label(egui::RichText::new(line).monospace().color(color));
+                    }
+                });
+        }
+    }
+}
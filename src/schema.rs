@@ -0,0 +1,304 @@
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// Version written into every file the GUI exports. Bump it together with a
+/// new entry in `MIGRATIONS` whenever an exported format changes.
+pub const SCHEMA_VERSION: u64 = 1;
+
+/// A single frame with its metadata, as exported from the Lookup tab.
+pub const FRAME_KIND: &str = "frame";
+
+/// All frames of the transform tree, as exported from the Transforms tab.
+pub const SCENE_KIND: &str = "scene";
+
+/// Recorded waypoints of the Robot tab's waypoint program.
+pub const WAYPOINT_PROGRAM_KIND: &str = "waypoint_program";
+
+/// Exported document: the payload's own fields plus the version stamp at the top level,
+/// so consumers of the unversioned format keep working.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Versioned<T> {
+    pub schema_version: u64,
+    pub kind: String,
+    #[serde(flatten)]
+    pub data: T,
+}
+
+impl<T> Versioned<T> {
+    pub fn new(kind: &str, data: T) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            kind: kind.to_string(),
+            data,
+        }
+    }
+}
+
+/// Migration `i` upgrades a document from version `i` to `i + 1`.
+const MIGRATIONS: &[fn(&mut Value)] = &[migrate_v0_to_v1];
+
+/// Version 0 files were written before the stamp existed and carry no kind either.
+fn migrate_v0_to_v1(document: &mut Value) {
+    let Some(object) = document.as_object_mut() else {
+        return;
+    };
+    if !object.contains_key("kind") && object.contains_key("child_frame_id") {
+        object.insert("kind".to_string(), Value::from(FRAME_KIND));
+    }
+    if !object.contains_key("kind") && object.contains_key("waypoints") {
+        object.insert("kind".to_string(), Value::from(WAYPOINT_PROGRAM_KIND));
+    }
+    object.insert("schema_version".to_string(), Value::from(1));
+}
+
+pub struct Loaded<T> {
+    pub data: T,
+    /// Set when the file comes from a newer GUI version and may not load completely.
+    pub warning: Option<String>,
+}
+
+/// Parses an exported document, migrating files from older GUI versions.
+pub fn load<T: DeserializeOwned>(text: &str, kind: &str) -> Result<Loaded<T>, String> {
+    let mut document: Value =
+        serde_json::from_str(text).map_err(|e| format!("Invalid JSON: {}", e))?;
+    let version = document
+        .get("schema_version")
+        .and_then(Value::as_u64)
+        .unwrap_or(0);
+
+    let warning = if version > SCHEMA_VERSION {
+        Some(format!(
+            "Saved by a newer GUI version (schema {}, this GUI supports up to {}), \
+             fields it doesn't know are ignored.",
+            version, SCHEMA_VERSION
+        ))
+    } else {
+        for migration in &MIGRATIONS[version as usize..] {
+            migration(&mut document);
+        }
+        None
+    };
+
+    let errors = Validator::check(&document, text, kind);
+    if !errors.is_empty() {
+        return Err(errors
+            .iter()
+            .map(FieldError::to_string)
+            .collect::<Vec<String>>()
+            .join("\n"));
+    }
+    // Files from a newer GUI may have fields this one doesn't know
+    if version <= SCHEMA_VERSION {
+        check_unknown_fields::<T>(&document, text)?;
+    }
+
+    // Unmigrated files are parsed from the text so errors point at a line
+    let versioned: Versioned<T> = if version == SCHEMA_VERSION {
+        serde_json::from_str(text)
+    } else {
+        serde_json::from_value(document)
+    }
+    .map_err(|e| format!("Unexpected content for schema {}: {}", version, e))?;
+    if versioned.kind != kind {
+        return Err(format!(
+            "Expected a '{}' file, got '{}'",
+            kind, versioned.kind
+        ));
+    }
+    Ok(Loaded {
+        data: versioned.data,
+        warning,
+    })
+}
+
+/// The payload types deny unknown fields, which `Versioned` can't pass on
+/// through `flatten`, so the payload is deserialized on its own to find them.
+/// Other errors are left to parsing the whole document.
+fn check_unknown_fields<T: DeserializeOwned>(document: &Value, text: &str) -> Result<(), String> {
+    let mut payload = document.clone();
+    if let Some(object) = payload.as_object_mut() {
+        object.remove("schema_version");
+        object.remove("kind");
+    }
+    let Err(e) = serde_json::from_value::<T>(payload) else {
+        return Ok(());
+    };
+    let message = e.to_string();
+    let Some(field) = message
+        .strip_prefix("unknown field `")
+        .and_then(|rest| rest.split('`').next())
+    else {
+        return Ok(());
+    };
+    Err(match line_of(text, &[field]) {
+        Some(line) => format!("line {}: {}", line, message),
+        None => message,
+    })
+}
+
+/// Line of the field at `path`, each key searched after the previous one.
+fn line_of(text: &str, path: &[&str]) -> Option<usize> {
+    if path.is_empty() {
+        return None;
+    }
+    let mut offset = 0;
+    for key in path {
+        let quoted = format!("\"{}\"", key);
+        offset += text[offset..].find(&quoted)?;
+    }
+    Some(text[..offset].matches('\n').count() + 1)
+}
+
+/// A problem with one field of an imported document.
+#[derive(Debug)]
+pub struct FieldError {
+    /// Path of the field, e.g. `frames.tool0.transform.rotation`.
+    pub path: String,
+    /// Line of the field in the file, when it could be found.
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}: {}", line, self.path, self.message),
+            None => write!(f, "{}: {}", self.path, self.message),
+        }
+    }
+}
+
+fn child<'a>(path: &[&'a str], key: &'a str) -> Vec<&'a str> {
+    let mut path = path.to_vec();
+    path.push(key);
+    path
+}
+
+/// Checks beyond what deserializing catches: empty or equal frame ids and
+/// transforms that aren't poses.
+struct Validator<'a> {
+    text: &'a str,
+    errors: Vec<FieldError>,
+}
+
+impl<'a> Validator<'a> {
+    fn check(document: &Value, text: &'a str, kind: &str) -> Vec<FieldError> {
+        let mut validator = Self {
+            text,
+            errors: Vec::new(),
+        };
+        let Some(object) = document.as_object() else {
+            validator.error(&[], "the document isn't a JSON object");
+            return validator.errors;
+        };
+        match object
+            .get("kind")
+            .and_then(Value::as_str)
+            .filter(|found| *found == kind)
+        {
+            Some(FRAME_KIND) => validator.frame(&[], object, None),
+            Some(SCENE_KIND) => match object.get("frames") {
+                Some(Value::Object(frames)) => {
+                    for (name, frame) in frames {
+                        match frame.as_object() {
+                            Some(frame) => validator.frame(
+                                &["frames", name.as_str()],
+                                frame,
+                                Some(name.as_str()),
+                            ),
+                            None => {
+                                validator.error(&["frames", name.as_str()], "expected an object")
+                            }
+                        }
+                    }
+                }
+                Some(_) => validator.error(&["frames"], "expected an object of frames"),
+                None => validator.error(&["frames"], "missing"),
+            },
+            // Wrong or missing kinds are reported after deserializing
+            _ => (),
+        }
+        validator.errors
+    }
+
+    fn error(&mut self, path: &[&str], message: &str) {
+        self.errors.push(FieldError {
+            path: if path.is_empty() {
+                "(top level)".to_string()
+            } else {
+                path.join(".")
+            },
+            line: line_of(self.text, path),
+            message: message.to_string(),
+        });
+    }
+
+    /// A frame, `name` being its key in a scene.
+    fn frame(&mut self, path: &[&str], frame: &Map<String, Value>, name: Option<&str>) {
+        let mut id = |field: &str| {
+            let path = child(path, field);
+            match frame.get(field) {
+                Some(Value::String(id)) if !id.trim().is_empty() => Some(id.clone()),
+                Some(Value::String(_)) => {
+                    self.error(&path, "the frame id is empty");
+                    None
+                }
+                Some(_) => {
+                    self.error(&path, "expected a string");
+                    None
+                }
+                None => {
+                    self.error(&path, "missing");
+                    None
+                }
+            }
+        };
+        let child_id = id("child_frame_id");
+        let parent_id = id("parent_frame_id");
+        match (&child_id, &parent_id, name) {
+            (Some(child_id), Some(parent_id), _) if child_id == parent_id => self.error(
+                &child(path, "parent_frame_id"),
+                "the frame is its own parent",
+            ),
+            (Some(child_id), _, Some(name)) if child_id != name => self.error(
+                &child(path, "child_frame_id"),
+                &format!("doesn't match the frame's key {}", name),
+            ),
+            _ => (),
+        }
+        let path = child(path, "transform");
+        match frame.get("transform") {
+            Some(Value::Object(transform)) => self.transform(&path, transform),
+            Some(_) => self.error(&path, "expected an object"),
+            None => self.error(&path, "missing"),
+        }
+    }
+
+    fn transform(&mut self, path: &[&str], transform: &Map<String, Value>) {
+        for (field, axes) in [
+            ("translation", &["x", "y", "z"][..]),
+            ("rotation", &["x", "y", "z", "w"][..]),
+        ] {
+            let path = child(path, field);
+            let Some(part) = transform.get(field).and_then(Value::as_object) else {
+                self.error(
+                    &path,
+                    &format!("expected an object with {}", axes.join(", ")),
+                );
+                continue;
+            };
+            let values = axes
+                .iter()
+                .map(|axis| part.get(*axis).and_then(Value::as_f64))
+                .collect::<Option<Vec<f64>>>();
+            match values {
+                None => self.error(&path, &format!("expected the numbers {}", axes.join(", "))),
+                Some(q) if field == "rotation" && q.iter().map(|v| v * v).sum::<f64>() < 1e-12 => {
+                    self.error(&path, "the quaternion has zero length")
+                }
+                Some(_) => (),
+            }
+        }
+    }
+}
@@ -0,0 +1,108 @@
+//! "Export view as image": the active tab or the whole window, including
+//! open windows like the preview, saved as PNG for work instructions.
+
+use eframe::egui;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportArea {
+    ActiveTab,
+    Window,
+}
+
+pub struct ViewExporter {
+    /// Screen area of the active tab's content, updated every frame.
+    tab_rect: Option<egui::Rect>,
+    /// Where the requested screenshot goes and what part of it.
+    pending: Option<(PathBuf, ExportArea)>,
+    status: Option<Result<String, String>>,
+}
+
+impl ViewExporter {
+    pub fn new() -> Self {
+        Self {
+            tab_rect: None,
+            pending: None,
+            status: None,
+        }
+    }
+
+    pub fn set_tab_rect(&mut self, rect: egui::Rect) {
+        self.tab_rect = Some(rect);
+    }
+
+    /// The export button, to put into the top bar.
+    pub fn button(&mut self, ui: &mut egui::Ui, tab_name: &str) {
+        let response = ui
+            .menu_button("📷", |ui| {
+                if ui.button("Active tab").clicked() {
+                    self.request(ui.ctx(), tab_name, ExportArea::ActiveTab);
+                    ui.close();
+                }
+                if ui.button("Whole window").clicked() {
+                    self.request(ui.ctx(), tab_name, ExportArea::Window);
+                    ui.close();
+                }
+            })
+            .response;
+        match &self.status {
+            Some(Ok(status)) => response.on_hover_text(status),
+            Some(Err(e)) => {
+                response.on_hover_text(egui::RichText::new(e).color(egui::Color32::RED))
+            }
+            None => response.on_hover_text("Export view as image"),
+        };
+    }
+
+    fn request(&mut self, ctx: &egui::Context, tab_name: &str, area: ExportArea) {
+        let file_name = format!(
+            "{}_{}.png",
+            tab_name.to_lowercase().replace(' ', "_"),
+            crate::storage::now_secs()
+        );
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("PNG", &["png"])
+            .set_file_name(file_name)
+            .save_file()
+        else {
+            return;
+        };
+        self.pending = Some((path, area));
+        ctx.send_viewport_cmd(egui::ViewportCommand::Screenshot(Default::default()));
+    }
+
+    /// Saves the screenshot once the renderer delivers it, call every frame.
+    pub fn update(&mut self, ctx: &egui::Context) {
+        if self.pending.is_none() {
+            return;
+        }
+        let image = ctx.input(|i| {
+            i.raw.events.iter().find_map(|event| match event {
+                egui::Event::Screenshot { image, .. } => Some(image.clone()),
+                _ => None,
+            })
+        });
+        let (Some(image), Some((path, area))) = (image, self.pending.take()) else {
+            return;
+        };
+        let image = match (area, self.tab_rect) {
+            (ExportArea::ActiveTab, Some(rect)) => {
+                image.region(&rect, Some(ctx.pixels_per_point()))
+            }
+            _ => (*image).clone(),
+        };
+        self.status = Some(save_png(&image, &path).map(|_| format!("Saved {}", path.display())));
+        if let Some(Err(e)) = &self.status {
+            log::error!("{}", e);
+        }
+    }
+}
+
+fn save_png(image: &egui::ColorImage, path: &PathBuf) -> Result<(), String> {
+    let [width, height] = image.size;
+    let buffer = image::RgbaImage::from_raw(width as u32, height as u32, image.as_raw().to_vec())
+        .ok_or("Screenshot has an unexpected size")?;
+    buffer
+        .save_with_format(path, image::ImageFormat::Png)
+        .map_err(|e| format!("Failed to save {}: {}", path.display(), e))
+}
@@ -0,0 +1,248 @@
+//! Screwdriving with the spindle resource: the program the spindle runs and
+//! its torque target go out with the command, the final torque, angle and
+//! OK/NOK verdict are read back when it is done.
+
+use crate::history::HistoryEntry;
+use crate::locale::{self, LocalizedDragValue};
+use eframe::egui;
+use micro_sp::*;
+use ordered_float::OrderedFloat;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, sync::Arc};
+
+/// Variables of the command, after the robot id prefix.
+pub const VARIABLES: [&str; 2] = ["screw_program", "screw_torque_target"];
+
+/// Variables the spindle reports the result in, after the robot id prefix.
+pub const RESULT_VARIABLES: [&str; 3] = ["screw_final_torque", "screw_final_angle", "screw_result"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScrewParams {
+    /// Program number on the spindle controller.
+    pub program: u32,
+    /// Nm
+    pub torque_target: f64,
+}
+
+impl Default for ScrewParams {
+    fn default() -> Self {
+        Self {
+            program: 1,
+            torque_target: 2.0,
+        }
+    }
+}
+
+impl ScrewParams {
+    /// What the command writes, by full key.
+    pub fn values(&self, robot_id: &str) -> Vec<(String, SPValue)> {
+        [self.program as f64, self.torque_target]
+            .iter()
+            .zip(VARIABLES)
+            .map(|(value, suffix)| {
+                (
+                    format!("{}_{}", robot_id, suffix),
+                    SPValue::Float64(FloatOrUnknown::Float64(OrderedFloat(*value))),
+                )
+            })
+            .collect()
+    }
+
+    /// Why the spindle would reject the command, if it would.
+    pub fn problem(&self) -> Option<&'static str> {
+        if self.program == 0 {
+            Some("Spindle programs are numbered from 1")
+        } else if self.torque_target <= 0.0 {
+            Some("The torque target must be above zero")
+        } else {
+            None
+        }
+    }
+
+    pub fn draw(&mut self, ui: &mut egui::Ui) {
+        egui::Grid::new("screwdriving_grid")
+            .num_columns(4)
+            .spacing([20.0, 4.0])
+            .show(ui, |ui| {
+                ui.label("Program:");
+                ui.add(egui::DragValue::new(&mut self.program).range(0..=999));
+                ui.label("Torque Target:");
+                ui.add(
+                    egui::DragValue::new(&mut self.torque_target)
+                        .localized()
+                        .suffix(" Nm")
+                        .speed(0.01)
+                        .range(0.0..=100.0),
+                );
+                ui.end_row();
+            });
+        if let Some(problem) = self.problem() {
+            ui.colored_label(egui::Color32::YELLOW, problem);
+        }
+    }
+}
+
+/// What the spindle reported for one screw. Values it didn't report are `None`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ProcessResult {
+    pub program: u32,
+    /// Nm
+    pub torque: Option<f64>,
+    /// Degrees
+    pub angle: Option<f64>,
+    pub ok: Option<bool>,
+}
+
+fn float(value: Option<SPValue>) -> Option<f64> {
+    match value? {
+        SPValue::Float64(FloatOrUnknown::Float64(f)) => Some(f.0),
+        _ => None,
+    }
+}
+
+/// Reads the result variables of the last screw.
+pub async fn read_result(
+    con: Arc<ConnectionManager>,
+    robot_id: &str,
+    program: u32,
+) -> ProcessResult {
+    let mut connection = con.get_connection().await;
+    let mut values = Vec::new();
+    for suffix in RESULT_VARIABLES {
+        let key = format!("{}_{}", robot_id, suffix);
+        values.push(StateManager::get_sp_value(&mut connection, &key).await);
+    }
+    let mut values = values.into_iter();
+    let torque = float(values.next().flatten());
+    let angle = float(values.next().flatten());
+    let ok = match values.next().flatten() {
+        Some(SPValue::String(StringOrUnknown::String(result))) => {
+            match result.to_uppercase().as_str() {
+                "OK" => Some(true),
+                "NOK" => Some(false),
+                _ => None,
+            }
+        }
+        _ => None,
+    };
+    ProcessResult {
+        program,
+        torque,
+        angle,
+        ok,
+    }
+}
+
+impl ProcessResult {
+    pub fn draw(&self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            match self.ok {
+                Some(true) => ui.colored_label(egui::Color32::GREEN, "OK"),
+                Some(false) => ui.colored_label(egui::Color32::RED, "NOK"),
+                None => ui.colored_label(egui::Color32::YELLOW, "No verdict"),
+            };
+            ui.label(format!(
+                "Program {}, final torque {} Nm, angle {}°",
+                self.program,
+                self.torque
+                    .map(|t| locale::format_number(t, 3))
+                    .unwrap_or("-".to_string()),
+                self.angle
+                    .map(|a| locale::format_number(a, 1))
+                    .unwrap_or("-".to_string())
+            ));
+        });
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct ProgramSummary {
+    count: usize,
+    ok: usize,
+    nok: usize,
+    torques: Vec<f64>,
+    angles: Vec<f64>,
+}
+
+fn mean_and_std(values: &[f64]) -> Option<(f64, f64)> {
+    if values.is_empty() {
+        return None;
+    }
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let var = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+    Some((mean, var.sqrt()))
+}
+
+/// OK rate and torque and angle spread per robot and program, from the
+/// screws recorded in the command history.
+pub fn draw_statistics(ui: &mut egui::Ui, entries: &[HistoryEntry]) {
+    let mut summaries: BTreeMap<(String, u32), ProgramSummary> = BTreeMap::new();
+    for entry in entries {
+        let Some(result) = &entry.process else {
+            continue;
+        };
+        let summary = summaries
+            .entry((entry.robot_id.clone(), result.program))
+            .or_default();
+        summary.count += 1;
+        match result.ok {
+            Some(true) => summary.ok += 1,
+            Some(false) => summary.nok += 1,
+            None => (),
+        }
+        summary.torques.extend(result.torque);
+        summary.angles.extend(result.angle);
+    }
+    if summaries.is_empty() {
+        ui.weak("No screwdriving results recorded yet.");
+        return;
+    }
+    let spread = |values: &[f64], decimals| match mean_and_std(values) {
+        Some((mean, std)) => format!(
+            "{} ± {}",
+            locale::format_number(mean, decimals),
+            locale::format_number(std, decimals)
+        ),
+        None => "-".to_string(),
+    };
+    egui::Grid::new("screwdriving_statistics_grid")
+        .num_columns(7)
+        .spacing([20.0, 2.0])
+        .striped(true)
+        .show(ui, |ui| {
+            ui.strong("Robot");
+            ui.strong("Program");
+            ui.strong("Screws");
+            ui.strong("OK");
+            ui.strong("NOK");
+            ui.strong("Torque (Nm)");
+            ui.strong("Angle (°)");
+            ui.end_row();
+            for ((robot_id, program), summary) in &summaries {
+                ui.label(robot_id);
+                ui.label(program.to_string());
+                ui.label(summary.count.to_string());
+                let judged = summary.ok + summary.nok;
+                let rate = if judged > 0 {
+                    summary.ok as f64 / judged as f64 * 100.0
+                } else {
+                    0.0
+                };
+                ui.label(format!(
+                    "{} ({} %)",
+                    summary.ok,
+                    locale::format_number(rate, 1)
+                ));
+                if summary.nok > 0 {
+                    ui.colored_label(egui::Color32::RED, summary.nok.to_string());
+                } else {
+                    ui.label("0");
+                }
+                ui.monospace(spread(&summary.torques, 3));
+                ui.monospace(spread(&summary.angles, 1));
+                ui.end_row();
+            }
+        });
+}
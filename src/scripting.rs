@@ -0,0 +1,372 @@
+use crate::backend;
+use crate::pose::Pose;
+use crate::provenance::Method;
+use crate::recorder;
+use crate::storage;
+use crate::workers;
+use eframe::egui;
+use micro_sp::*;
+use poll_promise::Promise;
+use rfd::FileDialog;
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Map};
+use std::sync::{
+    Arc, Mutex,
+    atomic::{AtomicBool, Ordering},
+};
+
+const SCRIPT_FILE: &str = "console.rhai";
+
+const EXAMPLE_SCRIPT: &str = r#"// Teach 10 poses along a line, 1 cm apart along x of p0
+let start = lookup("world", "p0");
+for i in 0..10 {
+    let pose = start;
+    pose.x += 0.01 * i;
+    publish_frame("world", `line_${i}`, pose);
+    print(`taught line_${i}`);
+}
+"#;
+
+const API_HELP: &str = "\
+frames() -> array of frame names
+lookup(parent, child) -> #{x, y, z, qx, qy, qz, qw}
+publish_frame(parent, child, pose)
+get(key) -> value or ()
+set(key, value)
+    refused while a cell guard is open
+send_command(robot, #{command_type: \"unsafe_move_l\", goal_feature_id: \"p1\", ...})
+    writes {robot}_<key> for every entry, triggers and waits for the result,
+    refused while the robot is disabled or a cell guard is open
+sleep(seconds)
+print(text)";
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+fn sp_value_to_dynamic(value: SPValue) -> Dynamic {
+    match value {
+        SPValue::String(StringOrUnknown::String(s)) => Dynamic::from(s),
+        SPValue::Bool(BoolOrUnknown::Bool(b)) => Dynamic::from(b),
+        SPValue::Float64(FloatOrUnknown::Float64(f)) => Dynamic::from(f.0),
+        SPValue::Array(ArrayOrUnknown::Array(values)) => {
+            Dynamic::from_array(values.into_iter().map(sp_value_to_dynamic).collect())
+        }
+        other => Dynamic::from(format!("{:?}", other)),
+    }
+}
+
+/// Numbers always become floats, the state has no use for script integers.
+fn dynamic_to_sp_value(value: Dynamic) -> ScriptResult<SPValue> {
+    if let Ok(b) = value.as_bool() {
+        Ok(b.to_spvalue())
+    } else if let Ok(f) = value.as_float() {
+        Ok(f.to_spvalue())
+    } else if let Ok(i) = value.as_int() {
+        Ok((i as f64).to_spvalue())
+    } else if value.is_string() {
+        Ok(value.into_string()?.to_spvalue())
+    } else if value.is_array() {
+        let values = value
+            .into_array()?
+            .into_iter()
+            .map(dynamic_to_sp_value)
+            .collect::<ScriptResult<Vec<SPValue>>>()?;
+        Ok(SPValue::Array(ArrayOrUnknown::Array(values)))
+    } else {
+        Err(format!("Unsupported value type {}", value.type_name()).into())
+    }
+}
+
+fn pose_to_map(pose: &Pose) -> Map {
+    let [x, y, z] = pose.translation;
+    let [qx, qy, qz, qw] = pose.rotation;
+    let mut map = Map::new();
+    for (key, value) in [
+        ("x", x),
+        ("y", y),
+        ("z", z),
+        ("qx", qx),
+        ("qy", qy),
+        ("qz", qz),
+        ("qw", qw),
+    ] {
+        map.insert(key.into(), Dynamic::from(value));
+    }
+    map
+}
+
+fn map_to_pose(map: &Map) -> ScriptResult<Pose> {
+    let get = |key: &str, default: f64| -> ScriptResult<f64> {
+        match map.get(key) {
+            None => Ok(default),
+            Some(v) => v
+                .as_float()
+                .or_else(|_| v.as_int().map(|i| i as f64))
+                .map_err(|_| format!("Pose field '{}' is not a number", key).into()),
+        }
+    };
+    Ok(Pose {
+        translation: [get("x", 0.0)?, get("y", 0.0)?, get("z", 0.0)?],
+        rotation: [
+            get("qx", 0.0)?,
+            get("qy", 0.0)?,
+            get("qz", 0.0)?,
+            get("qw", 1.0)?,
+        ],
+    })
+}
+
+/// Engine with the backend bindings. Everything else (files, network) stays
+/// out of reach of scripts.
+fn build_engine(
+    handle: tokio::runtime::Handle,
+    con: Arc<ConnectionManager>,
+    output: Arc<Mutex<Vec<String>>>,
+    stop: Arc<AtomicBool>,
+) -> Engine {
+    let mut engine = Engine::new();
+
+    let print_output = output.clone();
+    engine.on_print(move |text| print_output.lock().unwrap().push(text.to_string()));
+    let debug_output = output;
+    engine.on_debug(move |text, _, _| debug_output.lock().unwrap().push(text.to_string()));
+    let progress_stop = stop.clone();
+    engine.on_progress(move |_| {
+        progress_stop
+            .load(Ordering::Relaxed)
+            .then(|| Dynamic::from("stopped by user"))
+    });
+
+    let (h, c) = (handle.clone(), con.clone());
+    engine.register_fn("frames", move || -> ScriptResult<Array> {
+        let names = h.block_on(backend::frame_names(c.clone()))?;
+        Ok(names.into_iter().map(Dynamic::from).collect())
+    });
+
+    let (h, c) = (handle.clone(), con.clone());
+    engine.register_fn(
+        "lookup",
+        move |parent: &str, child: &str| -> ScriptResult<Map> {
+            let pose = h.block_on(backend::lookup(c.clone(), parent, child))?;
+            Ok(pose_to_map(&pose))
+        },
+    );
+
+    let (h, c) = (handle.clone(), con.clone());
+    engine.register_fn(
+        "publish_frame",
+        move |parent: &str, child: &str, pose: Map| -> ScriptResult<()> {
+            let pose = map_to_pose(&pose)?;
+            Ok(h.block_on(backend::publish_frame(
+                c.clone(),
+                parent,
+                child,
+                &pose,
+                Method::Computed,
+            ))?)
+        },
+    );
+
+    let (h, c) = (handle.clone(), con.clone());
+    engine.register_fn("get", move |key: &str| -> Dynamic {
+        h.block_on(backend::get_value(c.clone(), key))
+            .map(sp_value_to_dynamic)
+            .unwrap_or(Dynamic::UNIT)
+    });
+
+    let (h, c) = (handle.clone(), con.clone());
+    engine.register_fn(
+        "set",
+        move |key: &str, value: Dynamic| -> ScriptResult<()> {
+            let value = dynamic_to_sp_value(value)?;
+            Ok(h.block_on(backend::set_values_guarded(
+                c.clone(),
+                vec![(key.to_string(), value)],
+            ))?)
+        },
+    );
+
+    let (h, c) = (handle, con);
+    engine.register_fn(
+        "send_command",
+        move |robot_id: &str, parameters: Map| -> ScriptResult<()> {
+            let parameters = parameters
+                .into_iter()
+                .map(|(key, value)| Ok((key.to_string(), dynamic_to_sp_value(value)?)))
+                .collect::<ScriptResult<Vec<(String, SPValue)>>>()?;
+            Ok(h.block_on(backend::send_command(
+                c.clone(),
+                robot_id,
+                parameters,
+                backend::COMMAND_TIMEOUT,
+            ))?)
+        },
+    );
+
+    let sleep_stop = stop;
+    let sleep = move |seconds: f64| {
+        // Sleep in small steps so Stop doesn't have to wait for the whole duration
+        let until =
+            std::time::Instant::now() + std::time::Duration::from_secs_f64(seconds.max(0.0));
+        while std::time::Instant::now() < until && !sleep_stop.load(Ordering::Relaxed) {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        }
+    };
+    let sleep_int = sleep.clone();
+    engine.register_fn("sleep", sleep);
+    engine.register_fn("sleep", move |seconds: i64| sleep_int(seconds as f64));
+
+    engine
+}
+
+struct ScriptRun {
+    promise: Promise<Result<(), String>>,
+    stop: Arc<AtomicBool>,
+}
+
+pub struct ScriptTab {
+    source: String,
+    output: Arc<Mutex<Vec<String>>>,
+    run: Option<ScriptRun>,
+}
+
+impl ScriptTab {
+    pub fn new() -> Self {
+        let source = std::fs::read_to_string(storage::data_file(SCRIPT_FILE))
+            .unwrap_or_else(|_| EXAMPLE_SCRIPT.to_string());
+        Self {
+            source,
+            output: Arc::new(Mutex::new(Vec::new())),
+            run: None,
+        }
+    }
+
+    pub fn ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+    ) {
+        self.poll_run();
+
+        ui.horizontal(|ui| {
+            ui.heading("Script Console");
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.button("Clear Output").clicked() {
+                    self.output.lock().unwrap().clear();
+                }
+                if ui.button("Save Script…").clicked() {
+                    self.save_script();
+                }
+                if recorder::is_recording() {
+                    if ui.button("Stop Recording").clicked() {
+                        if let Some(script) = recorder::stop() {
+                            self.source = script;
+                            self.output
+                                .lock()
+                                .unwrap()
+                                .push("Recorded session loaded into the editor.".to_string());
+                        }
+                    }
+                } else if ui
+                    .button("Record Session")
+                    .on_hover_text(
+                        "Record lookups, frame edits, state writes and robot commands \
+                         made in the GUI as a script that replays them",
+                    )
+                    .clicked()
+                {
+                    recorder::start();
+                }
+                if let Some(run) = &self.run {
+                    if ui.button("Stop").clicked() {
+                        run.stop.store(true, Ordering::Relaxed);
+                    }
+                    ui.spinner();
+                } else if ui.button("Run").clicked() {
+                    self.spawn_run(handle, connection);
+                }
+            });
+        });
+        ui.separator();
+
+        egui::CollapsingHeader::new("API")
+            .id_salt("script_api_help")
+            .show(ui, |ui| {
+                ui.monospace(API_HELP);
+            });
+
+        ui.add_enabled(
+            self.run.is_none(),
+            egui::TextEdit::multiline(&mut self.source)
+                .code_editor()
+                .desired_rows(16)
+                .desired_width(f32::INFINITY),
+        );
+
+        ui.separator();
+        egui::ScrollArea::vertical()
+            .id_salt("script_output_scroll_area")
+            .auto_shrink([false; 2])
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                for line in self.output.lock().unwrap().iter() {
+                    ui.monospace(line);
+                }
+            });
+    }
+
+    fn poll_run(&mut self) {
+        let Some(run) = self.run.take() else {
+            return;
+        };
+        match run.promise.try_take() {
+            Ok(result) => {
+                let message = match result {
+                    Ok(()) => "Script finished.".to_string(),
+                    Err(e) => format!("Script error: {}", e),
+                };
+                self.output.lock().unwrap().push(message);
+            }
+            Err(promise) => {
+                self.run = Some(ScriptRun {
+                    promise,
+                    stop: run.stop,
+                })
+            }
+        }
+    }
+
+    fn save_script(&mut self) {
+        let Some(path) = FileDialog::new()
+            .add_filter("Rhai script", &["rhai"])
+            .set_file_name("session.rhai")
+            .save_file()
+        else {
+            return;
+        };
+        let message = match std::fs::write(&path, &self.source) {
+            Ok(()) => format!("Saved script to {}", path.display()),
+            Err(e) => format!("Failed to save script to {}: {}", path.display(), e),
+        };
+        self.output.lock().unwrap().push(message);
+    }
+
+    fn spawn_run(&mut self, handle: &tokio::runtime::Handle, connection: &Arc<ConnectionManager>) {
+        if let Err(e) = std::fs::create_dir_all(storage::data_dir())
+            .and_then(|_| std::fs::write(storage::data_file(SCRIPT_FILE), &self.source))
+        {
+            log::error!("Failed to save script: {}", e);
+        }
+        let stop = Arc::new(AtomicBool::new(false));
+        let (handle, con_clone) = (handle.clone(), connection.clone());
+        let (output, engine_stop) = (self.output.clone(), stop.clone());
+        let source = self.source.clone();
+        self.run = Some(ScriptRun {
+            // The engine isn't Send, so it is built on the script thread
+            promise: workers::spawn_long("script", move || {
+                let engine = build_engine(handle, con_clone, output, engine_stop);
+                engine.run(&source).map_err(|e| e.to_string())
+            }),
+            stop,
+        });
+    }
+}
@@ -0,0 +1,116 @@
+//! Parameters of the driver's force-guided spiral search: the TCP spirals
+//! around the goal in its XY plane, pressing along Z, until it drops into
+//! the hole or the force, radius or time runs out.
+
+use crate::expression::Unit;
+use crate::locale::LocalizedDragValue;
+use eframe::egui;
+use micro_sp::{FloatOrUnknown, SPValue};
+use ordered_float::OrderedFloat;
+use serde::{Deserialize, Serialize};
+
+/// Variables of the search, after the robot id prefix.
+pub const VARIABLES: [&str; 4] = [
+    "search_radius",
+    "search_pitch",
+    "search_max_force",
+    "search_timeout",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchParams {
+    /// Largest distance from the goal the spiral reaches, m.
+    pub radius: f64,
+    /// Distance between two turns of the spiral, m.
+    pub pitch: f64,
+    /// Contact force the search presses with and aborts above, N.
+    pub max_force: f64,
+    /// s
+    pub timeout: f64,
+}
+
+impl Default for SearchParams {
+    fn default() -> Self {
+        Self {
+            radius: 0.005,
+            pitch: 0.0005,
+            max_force: 20.0,
+            timeout: 10.0,
+        }
+    }
+}
+
+impl SearchParams {
+    /// What the command writes, by full key.
+    pub fn values(&self, robot_id: &str) -> Vec<(String, SPValue)> {
+        [self.radius, self.pitch, self.max_force, self.timeout]
+            .iter()
+            .zip(VARIABLES)
+            .map(|(value, suffix)| {
+                (
+                    format!("{}_{}", robot_id, suffix),
+                    SPValue::Float64(FloatOrUnknown::Float64(OrderedFloat(*value))),
+                )
+            })
+            .collect()
+    }
+
+    /// Why the driver would reject the search, if it would.
+    pub fn problem(&self) -> Option<&'static str> {
+        if self.radius <= 0.0 || self.pitch <= 0.0 {
+            Some("Search radius and pitch must be above zero")
+        } else if self.pitch > self.radius {
+            Some("The pitch is larger than the search radius, the spiral has no turn")
+        } else if self.max_force <= 0.0 || self.timeout <= 0.0 {
+            Some("Max force and timeout must be above zero")
+        } else {
+            None
+        }
+    }
+
+    pub fn draw(&mut self, ui: &mut egui::Ui) {
+        egui::Grid::new("spiral_search_grid")
+            .num_columns(4)
+            .spacing([20.0, 4.0])
+            .show(ui, |ui| {
+                ui.label("Search Radius:");
+                ui.add(
+                    egui::DragValue::new(&mut self.radius)
+                        .localized_in(Unit::Meter)
+                        .suffix(" m")
+                        .speed(0.0001)
+                        .range(0.0..=0.05),
+                );
+                ui.label("Pitch:");
+                ui.add(
+                    egui::DragValue::new(&mut self.pitch)
+                        .localized_in(Unit::Meter)
+                        .suffix(" m")
+                        .speed(0.0001)
+                        .range(0.0..=0.01),
+                );
+                ui.end_row();
+                ui.label("Max Force:");
+                ui.add(
+                    egui::DragValue::new(&mut self.max_force)
+                        .localized()
+                        .suffix(" N")
+                        .speed(0.1)
+                        .range(0.0..=200.0),
+                );
+                ui.label("Timeout:");
+                ui.add(
+                    egui::DragValue::new(&mut self.timeout)
+                        .localized()
+                        .suffix(" s")
+                        .speed(0.1)
+                        .range(0.0..=120.0),
+                );
+                ui.end_row();
+            });
+        if let Some(problem) = self.problem() {
+            ui.colored_label(egui::Color32::YELLOW, problem);
+        }
+    }
+}
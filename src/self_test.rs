@@ -0,0 +1,330 @@
+//! Checks run on launch before the control tabs are enabled: the backend
+//! answers, the expected robots are registered, the transform tree has its
+//! root and the configuration files can be read. The operator sees the
+//! results and continues from there.
+
+use crate::scene_lint::ROOT_FRAME;
+use crate::storage;
+use crate::workers;
+use eframe::egui;
+use micro_sp::*;
+use poll_promise::Promise;
+use std::{sync::Arc, time::Duration};
+
+/// A backend that doesn't answer by then is reported as unreachable.
+const BACKEND_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Files in the data directory the GUI reads its configuration from, each
+/// checked by its module against the type it loads.
+const CONFIG_FILES: [(&str, fn() -> Option<Result<(), String>>); 22] = [
+    (crate::settings::SETTINGS_FILE, crate::settings::check_file),
+    (crate::payloads::PAYLOADS_FILE, crate::payloads::check_file),
+    (
+        crate::joint_presets::JOINT_PRESETS_FILE,
+        crate::joint_presets::check_file,
+    ),
+    (crate::presets::PRESETS_FILE, crate::presets::check_file),
+    (
+        crate::joint_limits::JOINT_LIMITS_FILE,
+        crate::joint_limits::check_file,
+    ),
+    (crate::access::FIELD_LOCKS_FILE, crate::access::check_file),
+    (
+        crate::pose_filter::FILTERS_FILE,
+        crate::pose_filter::check_file,
+    ),
+    (
+        crate::speed_zones::ZONES_FILE,
+        crate::speed_zones::check_file,
+    ),
+    (crate::teach::TEACH_FILE, crate::teach::check_file),
+    (crate::lookup::CAPTURE_FILE, crate::lookup::check_file),
+    (
+        crate::export_profiles::PROFILES_FILE,
+        crate::export_profiles::check_file,
+    ),
+    (
+        crate::kinematics::ROBOT_MODELS_FILE,
+        crate::kinematics::check_file,
+    ),
+    (
+        crate::custom_panels::CUSTOM_PANELS_FILE,
+        crate::custom_panels::check_file,
+    ),
+    (crate::products::PRODUCTS_FILE, crate::products::check_file),
+    (
+        crate::workspace::WORKSPACES_FILE,
+        crate::workspace::check_file,
+    ),
+    (crate::mes::MES_FILE, crate::mes::check_file),
+    (
+        crate::utilization::UTILIZATION_FILE,
+        crate::utilization::check_file,
+    ),
+    (
+        crate::maintenance::MAINTENANCE_FILE,
+        crate::maintenance::check_file,
+    ),
+    (
+        crate::subroutines::SUBROUTINES_FILE,
+        crate::subroutines::check_file,
+    ),
+    (
+        crate::signal_tower::TOWER_FILE,
+        crate::signal_tower::check_file,
+    ),
+    (
+        crate::input_devices::INPUT_BINDINGS_FILE,
+        crate::input_devices::check_file,
+    ),
+    (crate::orders::ORDERS_FILE, crate::orders::check_file),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Pass,
+    /// Not a failure, but worth a look.
+    Warn,
+    Fail,
+}
+
+#[derive(Debug, Clone)]
+struct CheckItem {
+    name: String,
+    outcome: Outcome,
+    detail: String,
+}
+
+impl CheckItem {
+    fn new(name: impl Into<String>, outcome: Outcome, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            outcome,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Missing files are fine, the GUI starts from defaults. A file that exists
+/// but doesn't parse would silently be replaced by them.
+fn check_config_file(name: &str, check: fn() -> Option<Result<(), String>>) -> CheckItem {
+    let label = format!("Config {}", name);
+    match check() {
+        None => CheckItem::new(label, Outcome::Pass, "not present, defaults are used"),
+        Some(Ok(())) => CheckItem::new(label, Outcome::Pass, "readable"),
+        Some(Err(e)) => CheckItem::new(label, Outcome::Fail, e),
+    }
+}
+
+async fn run_checks(con: Arc<ConnectionManager>, expected_robots: Vec<String>) -> Vec<CheckItem> {
+    let mut items = Vec::new();
+    let state = tokio::time::timeout(BACKEND_TIMEOUT, async {
+        let mut connection = con.get_connection().await;
+        StateManager::get_full_state(&mut connection).await
+    })
+    .await;
+    let backend_ok = matches!(state, Ok(Some(_)));
+    match state {
+        Ok(Some(state)) => {
+            items.push(CheckItem::new(
+                "Backend reachable",
+                Outcome::Pass,
+                format!("{} state variables", state.state.len()),
+            ));
+            if expected_robots.is_empty() {
+                items.push(CheckItem::new(
+                    "Expected robots",
+                    Outcome::Warn,
+                    "none set in the Settings tab",
+                ));
+            }
+            for robot_id in &expected_robots {
+                let registered = state
+                    .state
+                    .contains_key(&format!("{}_request_trigger", robot_id))
+                    && state
+                        .state
+                        .contains_key(&format!("{}_command_type", robot_id));
+                items.push(if registered {
+                    CheckItem::new(format!("Robot {}", robot_id), Outcome::Pass, "registered")
+                } else {
+                    CheckItem::new(
+                        format!("Robot {}", robot_id),
+                        Outcome::Fail,
+                        "not registered, register it in the Resources tab",
+                    )
+                });
+            }
+        }
+        Ok(None) => items.push(CheckItem::new(
+            "Backend reachable",
+            Outcome::Fail,
+            "connected, but the state couldn't be read",
+        )),
+        Err(_) => items.push(CheckItem::new(
+            "Backend reachable",
+            Outcome::Fail,
+            format!("no answer after {} s", BACKEND_TIMEOUT.as_secs()),
+        )),
+    }
+
+    // Skipped without a backend, it would only time out again
+    if backend_ok {
+        let transforms = tokio::time::timeout(BACKEND_TIMEOUT, async {
+            let mut connection = con.get_connection().await;
+            TransformsManager::get_all_transforms(&mut connection).await
+        })
+        .await;
+        let label = format!("Transform root {}", ROOT_FRAME);
+        items.push(match transforms {
+            Ok(Ok(transforms)) => {
+                let children = transforms
+                    .values()
+                    .filter(|tf| tf.parent_frame_id == ROOT_FRAME)
+                    .count();
+                if transforms.contains_key(ROOT_FRAME) || children > 0 {
+                    CheckItem::new(label, Outcome::Pass, format!("{} child frames", children))
+                } else {
+                    CheckItem::new(label, Outcome::Fail, "no frame is attached to it")
+                }
+            }
+            Ok(Err(e)) => CheckItem::new(
+                label,
+                Outcome::Fail,
+                format!("failed to get the transforms: {}", e),
+            ),
+            Err(_) => CheckItem::new(
+                label,
+                Outcome::Fail,
+                format!("no answer after {} s", BACKEND_TIMEOUT.as_secs()),
+            ),
+        });
+    }
+
+    items.extend(
+        CONFIG_FILES
+            .iter()
+            .map(|(name, check)| check_config_file(name, *check)),
+    );
+    items
+}
+
+pub struct SelfTest {
+    promise: Option<Promise<Vec<CheckItem>>>,
+    items: Vec<CheckItem>,
+    /// Set once the operator continues to the control tabs.
+    done: bool,
+}
+
+impl SelfTest {
+    pub fn start(
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+        expected_robots: Vec<String>,
+    ) -> Self {
+        let mut self_test = Self {
+            promise: None,
+            items: Vec::new(),
+            done: false,
+        };
+        self_test.run(handle, connection, expected_robots);
+        self_test
+    }
+
+    fn run(
+        &mut self,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+        expected_robots: Vec<String>,
+    ) {
+        let handle = handle.clone();
+        let con_clone = connection.clone();
+        self.items.clear();
+        self.promise = Some(workers::spawn("self_test", move || {
+            handle.block_on(run_checks(con_clone, expected_robots))
+        }));
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// The summary screen shown in place of the tabs.
+    pub fn ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+        expected_robots: Vec<String>,
+    ) {
+        if let Some(promise) = self.promise.take() {
+            match promise.try_take() {
+                Ok(items) => self.items = items,
+                Err(promise) => self.promise = Some(promise),
+            }
+        }
+
+        ui.heading("Startup Self-Test");
+        ui.separator();
+        if self.promise.is_some() {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Running checks...");
+            });
+            return;
+        }
+
+        egui::Grid::new("self_test_grid")
+            .num_columns(3)
+            .striped(true)
+            .spacing([20.0, 4.0])
+            .show(ui, |ui| {
+                for item in &self.items {
+                    match item.outcome {
+                        Outcome::Pass => ui.colored_label(egui::Color32::GREEN, "PASS"),
+                        Outcome::Warn => ui.colored_label(egui::Color32::YELLOW, "WARN"),
+                        Outcome::Fail => ui.colored_label(egui::Color32::RED, "FAIL"),
+                    };
+                    ui.label(&item.name);
+                    ui.weak(&item.detail);
+                    ui.end_row();
+                }
+            });
+
+        let failed = self
+            .items
+            .iter()
+            .filter(|item| item.outcome == Outcome::Fail)
+            .count();
+        ui.add_space(8.0);
+        if failed > 0 {
+            ui.colored_label(
+                egui::Color32::RED,
+                format!(
+                    "{} of {} checks failed. Robot commands may not work until they are fixed.",
+                    failed,
+                    self.items.len()
+                ),
+            );
+        } else {
+            ui.colored_label(egui::Color32::GREEN, "All checks passed.");
+        }
+        ui.horizontal(|ui| {
+            let label = if failed > 0 {
+                "Continue Anyway"
+            } else {
+                "Continue"
+            };
+            if ui.button(label).clicked() {
+                self.done = true;
+            }
+            if ui.button("Run Again").clicked() {
+                self.run(handle, connection, expected_robots);
+            }
+        });
+        ui.weak(format!(
+            "Configuration is read from {}",
+            storage::data_dir().display()
+        ));
+    }
+}
@@ -0,0 +1,234 @@
+//! Robot commands run one after the other through `backend::send_command`,
+//! each sent only once the previous one reports `succeeded`, so every step
+//! gets the retry policy and a history entry. The command queue and the
+//! waypoint programs only differ in how they build the steps.
+
+use crate::backend;
+use crate::workers;
+use eframe::egui;
+use micro_sp::*;
+use poll_promise::Promise;
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+use std::time::Duration;
+
+#[derive(Clone)]
+pub struct Step {
+    pub robot_id: String,
+    pub label: String,
+    /// Without the robot prefix, as `backend::send_command` takes them.
+    pub parameters: Vec<(String, SPValue)>,
+}
+
+impl Step {
+    pub fn new(
+        robot_id: &str,
+        command_type: &str,
+        goal: Option<&str>,
+        parameters: Vec<(String, SPValue)>,
+    ) -> Self {
+        let label = match goal {
+            Some(goal) => format!("{} {} → {}", robot_id, command_type, goal),
+            None => format!("{} {}", robot_id, command_type),
+        };
+        Self {
+            robot_id: robot_id.to_string(),
+            label,
+            parameters,
+        }
+    }
+}
+
+/// How a run ended.
+pub struct Outcome {
+    pub done: usize,
+    /// Over all steps, for the status next to the list.
+    pub retries: u32,
+    /// What stopped the run, `None` when every step was done.
+    pub error: Option<String>,
+}
+
+impl Outcome {
+    /// ", 2 retries" when there were any.
+    pub fn retries_text(&self) -> String {
+        match self.retries {
+            0 => String::new(),
+            1 => ", 1 retry".to_string(),
+            n => format!(", {} retries", n),
+        }
+    }
+}
+
+/// Runs `steps` in order until one fails or the run is cancelled.
+async fn run_steps(
+    con: Arc<ConnectionManager>,
+    steps: Vec<Step>,
+    timeout: Duration,
+    current: Arc<AtomicUsize>,
+    cancel: Arc<AtomicBool>,
+) -> Outcome {
+    let mut outcome = Outcome {
+        done: 0,
+        retries: 0,
+        error: None,
+    };
+    for step in &steps {
+        if cancel.load(Ordering::Relaxed) {
+            outcome.error = Some("Stopped".to_string());
+            return outcome;
+        }
+        current.store(outcome.done, Ordering::Relaxed);
+        match backend::send_command_cancellable(
+            con.clone(),
+            &step.robot_id,
+            step.parameters.clone(),
+            timeout,
+            &cancel,
+        )
+        .await
+        {
+            Ok(retries) => outcome.retries += retries,
+            Err(e) => {
+                outcome.error = Some(format!("{}: {}", step.label, e));
+                return outcome;
+            }
+        }
+        outcome.done += 1;
+    }
+    outcome
+}
+
+pub struct Run {
+    promise: Promise<Outcome>,
+    current: Arc<AtomicUsize>,
+    cancel: Arc<AtomicBool>,
+    len: usize,
+}
+
+impl Run {
+    /// `timeout` is the longest a single step may take.
+    pub fn start(
+        name: &str,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+        steps: Vec<Step>,
+        timeout: Duration,
+    ) -> Self {
+        let len = steps.len();
+        let current = Arc::new(AtomicUsize::new(0));
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (current_clone, cancel_clone) = (current.clone(), cancel.clone());
+        let handle = handle.clone();
+        let con_clone = connection.clone();
+        Self {
+            promise: workers::spawn_long(name, move || {
+                handle.block_on(run_steps(
+                    con_clone,
+                    steps,
+                    timeout,
+                    current_clone,
+                    cancel_clone,
+                ))
+            }),
+            current,
+            cancel,
+            len,
+        }
+    }
+
+    /// Index of the running step.
+    pub fn current(&self) -> usize {
+        self.current.load(Ordering::Relaxed)
+    }
+
+    /// "step 2 of 5" of the running step, for the busy overlay.
+    pub fn progress(&self) -> String {
+        format!("step {} of {}", self.current() + 1, self.len)
+    }
+
+    /// Whether a step is still running, also after a cancel.
+    pub fn is_running(&self) -> bool {
+        self.promise.ready().is_none()
+    }
+
+    /// Stops after the running step.
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    /// How the run ended, once it is over. The run is handed back while it
+    /// goes on.
+    pub fn finish(self) -> Result<Outcome, Self> {
+        match self.promise.try_take() {
+            Ok(result) => Ok(result),
+            Err(promise) => Err(Self { promise, ..self }),
+        }
+    }
+}
+
+/// How a row of a step list was changed.
+pub enum RowEdit {
+    Swap(usize, usize),
+    Remove(usize),
+}
+
+impl RowEdit {
+    pub fn apply<T>(self, items: &mut Vec<T>) {
+        match self {
+            RowEdit::Swap(a, b) => items.swap(a, b),
+            RowEdit::Remove(i) => {
+                items.remove(i);
+            }
+        }
+    }
+}
+
+/// Number of row `i`, or a spinner while it runs.
+pub fn draw_row_number(ui: &mut egui::Ui, i: usize, running: Option<usize>) {
+    if running == Some(i) {
+        ui.spinner();
+    } else {
+        ui.label(format!("{}.", i + 1));
+    }
+}
+
+/// ⬆⬇🗑 of row `i` of `count`, disabled while a run is going on.
+pub fn draw_row_controls(
+    ui: &mut egui::Ui,
+    i: usize,
+    count: usize,
+    enabled: bool,
+) -> Option<RowEdit> {
+    let mut edit = None;
+    ui.add_enabled_ui(enabled, |ui| {
+        ui.horizontal(|ui| {
+            if ui.add_enabled(i > 0, egui::Button::new("⬆")).clicked() {
+                edit = Some(RowEdit::Swap(i - 1, i));
+            }
+            if ui
+                .add_enabled(i + 1 < count, egui::Button::new("⬇"))
+                .clicked()
+            {
+                edit = Some(RowEdit::Swap(i, i + 1));
+            }
+            if ui.button("🗑").clicked() {
+                edit = Some(RowEdit::Remove(i));
+            }
+        });
+    });
+    edit
+}
+
+pub fn draw_status(ui: &mut egui::Ui, status: &Option<Result<String, String>>) {
+    match status {
+        Some(Ok(message)) => {
+            ui.colored_label(egui::Color32::GREEN, message);
+        }
+        Some(Err(e)) => {
+            ui.colored_label(egui::Color32::RED, e);
+        }
+        None => (),
+    }
+}
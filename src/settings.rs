@@ -0,0 +1,424 @@
+use crate::cell_guards::{self, CellGuard};
+use crate::locale::{self, DecimalSeparator, LocalizedDragValue, Precision};
+use crate::provenance;
+use crate::retry::{self, ErrorClass, RetryPolicies, RetryPolicy};
+use crate::scene_repo;
+use crate::shift_report;
+use crate::shutdown::ExitBehavior;
+use crate::storage;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+
+pub const SETTINGS_FILE: &str = "settings.json";
+
+pub fn check_file() -> Option<Result<(), String>> {
+    storage::check_json::<Settings>(SETTINGS_FILE)
+}
+
+/// GUI preferences of this operator station.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Recorded in the frames this station creates or changes.
+    pub operator_name: String,
+    pub decimal_separator: DecimalSeparator,
+    /// Decimals of poses in the frame list and in exported files.
+    pub precision: Precision,
+    /// Local JSON-RPC interface for test automation, read at startup.
+    pub rpc_enabled: bool,
+    pub rpc_port: u16,
+    pub on_exit: ExitBehavior,
+    /// Automatic retries of failed commands, by command type.
+    pub retry: RetryPolicies,
+    /// Where frames and scenes are exported, optionally a git repository.
+    pub scene_dir: String,
+    /// Force ripple during moves above which the Robot tab warns, N RMS.
+    pub vibration_threshold: f64,
+    /// Difference between the live joints and the goal frame's taught
+    /// configuration above which the Robot tab warns, degrees.
+    pub joint_drift_threshold: f64,
+    /// Where shift reports are written, the data folder's `reports` if empty.
+    pub report_dir: String,
+    /// Write the report of a shift when it ends.
+    pub auto_shift_report: bool,
+    /// Doors, fences and light curtains that block commands while open.
+    pub cell_guards: Vec<CellGuard>,
+    /// Robots the startup self-test expects to be registered, comma separated.
+    pub expected_robots: String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            operator_name: String::new(),
+            decimal_separator: DecimalSeparator::default(),
+            precision: Precision::default(),
+            rpc_enabled: false,
+            rpc_port: 8765,
+            on_exit: ExitBehavior::default(),
+            retry: RetryPolicies::default(),
+            scene_dir: String::new(),
+            vibration_threshold: 5.0,
+            joint_drift_threshold: 30.0,
+            report_dir: String::new(),
+            auto_shift_report: false,
+            cell_guards: Vec::new(),
+            expected_robots: String::new(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        let settings: Settings = storage::load_json(SETTINGS_FILE).unwrap_or_default();
+        settings.apply();
+        settings
+    }
+
+    pub fn save(&self) {
+        if let Err(e) = storage::save_json(SETTINGS_FILE, self) {
+            log::error!("Failed to save settings: {}", e);
+        }
+    }
+
+    pub fn expected_robot_ids(&self) -> Vec<String> {
+        self.expected_robots
+            .split(',')
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Pushes the settings to the parts of the GUI that read them globally.
+    fn apply(&self) {
+        locale::set_decimal_separator(self.decimal_separator);
+        locale::set_precision(self.precision);
+        provenance::set_operator(&self.operator_name);
+        retry::set_policies(self.retry.clone());
+        scene_repo::set_scene_dir(&self.scene_dir);
+    }
+}
+
+pub struct SettingsTab {
+    settings: Settings,
+    new_retry_command: String,
+}
+
+impl SettingsTab {
+    pub fn new() -> Self {
+        Self {
+            settings: Settings::load(),
+            new_retry_command: String::new(),
+        }
+    }
+
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Settings");
+        ui.separator();
+
+        let mut changed = false;
+        egui::Grid::new("settings_grid")
+            .num_columns(2)
+            .spacing([20.0, 8.0])
+            .show(ui, |ui| {
+                ui.label("Operator:");
+                changed |= ui
+                    .add(
+                        egui::TextEdit::singleline(&mut self.settings.operator_name)
+                            .hint_text("recorded in frames taught or changed here")
+                            .desired_width(300.0),
+                    )
+                    .changed();
+                ui.end_row();
+
+                ui.label("Decimal separator:");
+                egui::ComboBox::from_id_salt("decimal_separator")
+                    .selected_text(self.settings.decimal_separator.to_string())
+                    .show_ui(ui, |ui| {
+                        for separator in [DecimalSeparator::Point, DecimalSeparator::Comma] {
+                            changed |= ui
+                                .selectable_value(
+                                    &mut self.settings.decimal_separator,
+                                    separator,
+                                    separator.to_string(),
+                                )
+                                .changed();
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("Pose decimals:");
+                ui.horizontal(|ui| {
+                    let precision = &mut self.settings.precision;
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut precision.translation_decimals)
+                                .range(0..=9)
+                                .prefix("m: "),
+                        )
+                        .changed();
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut precision.rotation_decimals)
+                                .range(0..=9)
+                                .prefix("quaternion: "),
+                        )
+                        .changed();
+                    changed |= ui
+                        .checkbox(&mut precision.round_exports, "Round when saving files")
+                        .on_hover_text(
+                            "Exported frames and scene files keep only these decimals, \n\
+                             so their diffs don't show float noise.",
+                        )
+                        .changed();
+                });
+                ui.end_row();
+
+                ui.label("Automation interface:");
+                ui.horizontal(|ui| {
+                    changed |= ui
+                        .checkbox(&mut self.settings.rpc_enabled, "JSON-RPC on 127.0.0.1:")
+                        .changed();
+                    changed |= ui
+                        .add_enabled(
+                            self.settings.rpc_enabled,
+                            egui::DragValue::new(&mut self.settings.rpc_port).range(1024..=65535),
+                        )
+                        .changed();
+                    ui.weak("(applies after restart)");
+                });
+                ui.end_row();
+
+                ui.label("On exit:");
+                egui::ComboBox::from_id_salt("on_exit")
+                    .selected_text(self.settings.on_exit.to_string())
+                    .show_ui(ui, |ui| {
+                        for behavior in [
+                            ExitBehavior::Ask,
+                            ExitBehavior::StopRobots,
+                            ExitBehavior::Close,
+                        ] {
+                            changed |= ui
+                                .selectable_value(
+                                    &mut self.settings.on_exit,
+                                    behavior,
+                                    behavior.to_string(),
+                                )
+                                .changed();
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("Scene directory:");
+                ui.horizontal(|ui| {
+                    changed |= ui
+                        .add(
+                            egui::TextEdit::singleline(&mut self.settings.scene_dir)
+                                .hint_text("exports start here, may be a git repository")
+                                .desired_width(300.0),
+                        )
+                        .changed();
+                    if ui.button("Browse…").clicked() {
+                        if let Some(dir) = rfd::FileDialog::new().pick_folder() {
+                            self.settings.scene_dir = dir.display().to_string();
+                            changed = true;
+                        }
+                    }
+                });
+                ui.end_row();
+
+                ui.label("Vibration warning:");
+                ui.horizontal(|ui| {
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut self.settings.vibration_threshold)
+                                .localized()
+                                .range(0.1..=100.0)
+                                .speed(0.1)
+                                .suffix(" N RMS"),
+                        )
+                        .changed();
+                    ui.weak("force ripple during moves");
+                });
+                ui.end_row();
+
+                ui.label("Taught joints warning:");
+                ui.horizontal(|ui| {
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut self.settings.joint_drift_threshold)
+                                .localized()
+                                .range(1.0..=180.0)
+                                .speed(0.5)
+                                .suffix("°"),
+                        )
+                        .changed();
+                    ui.weak("live joints away from the goal frame's preferred configuration");
+                });
+                ui.end_row();
+
+                ui.label("Expected robots:");
+                ui.horizontal(|ui| {
+                    changed |= ui
+                        .add(
+                            egui::TextEdit::singleline(&mut self.settings.expected_robots)
+                                .hint_text("r1, r2")
+                                .desired_width(300.0),
+                        )
+                        .changed();
+                    ui.weak("checked by the startup self-test");
+                });
+                ui.end_row();
+
+                ui.label("Shift reports:");
+                ui.horizontal(|ui| {
+                    let default_dir = shift_report::default_report_dir().display().to_string();
+                    changed |= ui
+                        .add(
+                            egui::TextEdit::singleline(&mut self.settings.report_dir)
+                                .hint_text(default_dir)
+                                .desired_width(300.0),
+                        )
+                        .changed();
+                    if ui.button("Browse…").clicked() {
+                        if let Some(dir) = rfd::FileDialog::new().pick_folder() {
+                            self.settings.report_dir = dir.display().to_string();
+                            changed = true;
+                        }
+                    }
+                    changed |= ui
+                        .checkbox(
+                            &mut self.settings.auto_shift_report,
+                            "Write when a shift ends",
+                        )
+                        .changed();
+                });
+                ui.end_row();
+            });
+        ui.weak("Number inputs accept both 0.5 and 0,5 regardless of this setting.");
+
+        ui.add_space(8.0);
+        egui::CollapsingHeader::new("Command retry policy")
+            .id_salt("retry_policy")
+            .show(ui, |ui| {
+                changed |= self.draw_retry_policies(ui);
+            });
+        egui::CollapsingHeader::new("Cell guards")
+            .id_salt("cell_guards")
+            .show(ui, |ui| {
+                ui.label(
+                    "Send Command is disabled while a guard isn't closed or can't be read, \
+                     except in the Engineer role.",
+                );
+                changed |= cell_guards::draw_editor(ui, &mut self.settings.cell_guards);
+            });
+
+        if changed {
+            self.settings.apply();
+            self.settings.save();
+        }
+    }
+
+    fn draw_retry_policies(&mut self, ui: &mut egui::Ui) -> bool {
+        ui.label(
+            "Failed commands are sent again automatically. Each retry is logged in the \
+             command history.",
+        );
+        let mut changed = false;
+        let mut remove = None;
+        egui::Grid::new("retry_policy_grid")
+            .num_columns(6)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("Command");
+                ui.strong("Max retries");
+                ui.strong("Backoff");
+                ui.strong("Factor");
+                ui.strong("Retry on");
+                ui.label("");
+                ui.end_row();
+                ui.label("All others");
+                changed |= draw_retry_policy(ui, &mut self.settings.retry.default);
+                ui.label("");
+                ui.end_row();
+                for (command, policy) in self.settings.retry.commands.iter_mut() {
+                    ui.monospace(command);
+                    changed |= draw_retry_policy(ui, policy);
+                    if ui.button("🗑").clicked() {
+                        remove = Some(command.clone());
+                    }
+                    ui.end_row();
+                }
+            });
+        if let Some(command) = remove {
+            self.settings.retry.commands.remove(&command);
+            changed = true;
+        }
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.new_retry_command)
+                    .hint_text("command type, e.g. safe_move_j")
+                    .desired_width(200.0),
+            );
+            let command = self.new_retry_command.trim();
+            let can_add =
+                !command.is_empty() && !self.settings.retry.commands.contains_key(command);
+            if ui
+                .add_enabled(can_add, egui::Button::new("Add Policy"))
+                .clicked()
+            {
+                self.settings
+                    .retry
+                    .commands
+                    .insert(command.to_string(), RetryPolicy::default());
+                self.new_retry_command.clear();
+                changed = true;
+            }
+        });
+        changed
+    }
+}
+
+/// The editable columns of one policy row.
+fn draw_retry_policy(ui: &mut egui::Ui, policy: &mut RetryPolicy) -> bool {
+    let mut changed = ui
+        .add(egui::DragValue::new(&mut policy.max_retries).range(0..=10))
+        .changed();
+    changed |= ui
+        .add(
+            egui::DragValue::new(&mut policy.backoff_secs)
+                .localized()
+                .range(0.0..=60.0)
+                .speed(0.1)
+                .suffix(" s"),
+        )
+        .changed();
+    changed |= ui
+        .add(
+            egui::DragValue::new(&mut policy.backoff_factor)
+                .localized()
+                .range(1.0..=5.0)
+                .speed(0.1)
+                .prefix("×"),
+        )
+        .on_hover_text("Each wait is this many times longer than the previous one")
+        .changed();
+    ui.horizontal(|ui| {
+        for class in ErrorClass::ALL {
+            let mut enabled = policy.retry_on.contains(&class);
+            if ui.checkbox(&mut enabled, class.to_string()).changed() {
+                policy.retry_on.retain(|c| *c != class);
+                if enabled {
+                    policy.retry_on.push(class);
+                }
+                changed = true;
+            }
+        }
+    });
+    changed
+}
@@ -0,0 +1,238 @@
+//! End-of-shift report as a self-contained HTML page, put together from what
+//! the GUI logs anyway: order completions, the command history, utilization
+//! and the maintenance notes.
+
+use crate::history::{self, HistoryEntry};
+use crate::locale;
+use crate::maintenance::{MaintenanceLog, MaintenanceNote};
+use crate::order_history::{self, CompletionRecord, Outcome};
+use crate::storage;
+use crate::utilization::{SHIFTS, Utilization, UtilizationTracker};
+use std::{collections::BTreeMap, path::PathBuf};
+
+/// Folder reports go to when none is configured.
+pub fn default_report_dir() -> PathBuf {
+    storage::data_file("reports")
+}
+
+pub struct ShiftReport {
+    day: String,
+    shift: usize,
+    start: u64,
+    end: u64,
+    orders: Vec<CompletionRecord>,
+    commands: Vec<HistoryEntry>,
+    utilization: Vec<(String, Utilization)>,
+    notes: Vec<(String, MaintenanceNote)>,
+}
+
+impl ShiftReport {
+    pub fn collect(tracker: &UtilizationTracker, day: &str, shift: usize) -> Result<Self, String> {
+        let (start, end) = tracker
+            .shift_window(day, shift)
+            .ok_or_else(|| format!("Invalid day {}", day))?;
+        let in_shift = |t: u64| (start..end).contains(&t);
+        let utilization = tracker
+            .records
+            .iter()
+            .filter(|(k, _)| k.day == day && k.shift == shift)
+            .map(|(k, u)| (k.robot_id.clone(), *u))
+            .collect();
+        let notes = MaintenanceLog::load()
+            .resources
+            .into_iter()
+            .flat_map(|(resource, entry)| {
+                entry
+                    .notes
+                    .into_iter()
+                    .map(move |note| (resource.clone(), note))
+            })
+            .filter(|(_, note)| in_shift(note.timestamp))
+            .collect();
+        Ok(Self {
+            day: day.to_string(),
+            shift,
+            start,
+            end,
+            orders: order_history::load()
+                .into_iter()
+                .filter(|r| in_shift(r.finished))
+                .collect(),
+            commands: history::load()
+                .into_iter()
+                .filter(|e| in_shift(e.timestamp))
+                .collect(),
+            utilization,
+            notes,
+        })
+    }
+
+    pub fn file_name(&self) -> String {
+        let shift = SHIFTS[self.shift.min(2)]
+            .split_whitespace()
+            .next()
+            .unwrap_or("shift");
+        format!("shift_report_{}_{}.html", self.day, shift)
+    }
+
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        let title = format!("Shift report {} {}", self.day, SHIFTS[self.shift.min(2)]);
+        html.push_str(&format!(
+            "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{}</title>\n\
+             <style>body{{font-family:sans-serif;margin:2em}}table{{border-collapse:collapse;margin-bottom:1.5em}}\
+             td,th{{border:1px solid #999;padding:2px 8px;text-align:left}}.failed{{color:#b00}}</style>\n\
+             </head><body>\n<h1>{}</h1>\n<p>{} to {} UTC, generated {} UTC</p>\n",
+            escape(&title),
+            escape(&title),
+            storage::format_timestamp(self.start),
+            storage::format_timestamp(self.end),
+            storage::format_timestamp(storage::now_secs())
+        ));
+
+        let completed = self
+            .orders
+            .iter()
+            .filter(|o| o.outcome == Outcome::Completed)
+            .count();
+        html.push_str(&format!(
+            "<h2>Orders</h2>\n<p>{} finished, {} completed, {} failed or cancelled</p>\n",
+            self.orders.len(),
+            completed,
+            self.orders.len() - completed
+        ));
+        if !self.orders.is_empty() {
+            html.push_str(
+                "<table><tr><th>Order</th><th>Program</th><th>Quantity</th><th>Finished</th>\
+                 <th>Duration</th><th>Outcome</th></tr>\n",
+            );
+            for o in &self.orders {
+                let class = if o.outcome == Outcome::Completed {
+                    ""
+                } else {
+                    " class=\"failed\""
+                };
+                html.push_str(&format!(
+                    "<tr{}><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{} s</td><td>{}</td></tr>\n",
+                    class,
+                    escape(&o.order_id),
+                    escape(&o.program),
+                    o.quantity,
+                    storage::format_timestamp(o.finished),
+                    o.duration_secs(),
+                    o.outcome
+                ));
+            }
+            html.push_str("</table>\n");
+        }
+
+        html.push_str("<h2>Commands</h2>\n");
+        let mut counts: BTreeMap<(&str, &str), (usize, u32)> = BTreeMap::new();
+        for e in &self.commands {
+            let count = counts
+                .entry((e.robot_id.as_str(), e.command_type.as_str()))
+                .or_default();
+            if e.retry == 0 {
+                count.0 += 1;
+            } else {
+                count.1 += 1;
+            }
+        }
+        if counts.is_empty() {
+            html.push_str("<p>No commands sent from this station.</p>\n");
+        } else {
+            html.push_str(
+                "<table><tr><th>Robot</th><th>Command</th><th>Sent</th><th>Retries</th></tr>\n",
+            );
+            for ((robot, command), (sent, retries)) in &counts {
+                html.push_str(&format!(
+                    "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                    escape(robot),
+                    escape(command),
+                    sent,
+                    retries
+                ));
+            }
+            html.push_str("</table>\n");
+        }
+
+        html.push_str("<h2>Alarms</h2>\n");
+        let alarms = self
+            .orders
+            .iter()
+            .flat_map(|o| o.alarms.iter().map(move |a| (o, a)))
+            .collect::<Vec<_>>();
+        if alarms.is_empty() {
+            html.push_str("<p>None.</p>\n");
+        } else {
+            html.push_str("<ul>\n");
+            for (order, alarm) in alarms {
+                html.push_str(&format!(
+                    "<li>{} (order {})</li>\n",
+                    escape(alarm),
+                    escape(&order.order_id)
+                ));
+            }
+            html.push_str("</ul>\n");
+        }
+
+        html.push_str("<h2>Utilization</h2>\n");
+        if self.utilization.is_empty() {
+            html.push_str("<p>Nothing recorded.</p>\n");
+        } else {
+            html.push_str(
+                "<table><tr><th>Robot</th><th>Busy</th><th>Idle</th><th>Utilization</th></tr>\n",
+            );
+            for (robot, u) in &self.utilization {
+                html.push_str(&format!(
+                    "<tr><td>{}</td><td>{} min</td><td>{} min</td><td>{} %</td></tr>\n",
+                    escape(robot),
+                    locale::format_number(u.busy_seconds / 60.0, 0),
+                    locale::format_number(u.idle_seconds / 60.0, 0),
+                    locale::format_number(u.ratio() * 100.0, 1)
+                ));
+            }
+            html.push_str("</table>\n");
+        }
+
+        html.push_str("<h2>Notes</h2>\n");
+        if self.notes.is_empty() {
+            html.push_str("<p>None.</p>\n");
+        } else {
+            html.push_str("<ul>\n");
+            for (resource, note) in &self.notes {
+                html.push_str(&format!(
+                    "<li>{} {}{}: {}</li>\n",
+                    storage::format_timestamp(note.timestamp),
+                    escape(resource),
+                    if note.service { " [service]" } else { "" },
+                    escape(&note.text)
+                ));
+            }
+            html.push_str("</ul>\n");
+        }
+        html.push_str("</body></html>\n");
+        html
+    }
+
+    /// Writes the report into `dir`, or the default folder if it is empty.
+    pub fn save(&self, dir: &str) -> Result<PathBuf, String> {
+        let dir = if dir.trim().is_empty() {
+            default_report_dir()
+        } else {
+            PathBuf::from(dir)
+        };
+        std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+        let path = dir.join(self.file_name());
+        std::fs::write(&path, self.to_html())
+            .map_err(|e| format!("Failed to write {:?}: {}", path, e))?;
+        Ok(path)
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,269 @@
+//! What happens when the window is closed: optionally cancel running robot
+//! commands and the planner goal, then wait for pending writes before the
+//! connection is dropped.
+
+use crate::backend;
+use crate::workers;
+use eframe::egui;
+use micro_sp::*;
+use poll_promise::Promise;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// How long closing waits for the stop writes before giving up on them.
+const STOP_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ExitBehavior {
+    /// Close right away, robots keep running.
+    Close,
+    #[default]
+    Ask,
+    StopRobots,
+}
+
+impl std::fmt::Display for ExitBehavior {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExitBehavior::Close => write!(f, "Just close"),
+            ExitBehavior::Ask => write!(f, "Ask"),
+            ExitBehavior::StopRobots => write!(f, "Stop robots and cancel goal"),
+        }
+    }
+}
+
+/// Stops every robot the way the Stop button does, idle or between the steps
+/// of a run, cancels the commands that are executing and clears the goal if
+/// the planner has one. Returns the robots whose command was cancelled.
+async fn stop_robots(
+    con: Arc<ConnectionManager>,
+    robot_ids: Vec<String>,
+    goal_variable: String,
+) -> Result<Vec<String>, String> {
+    let mut cancelled = Vec::new();
+    let mut values = Vec::new();
+    for robot_id in &robot_ids {
+        let request_state =
+            backend::get_value(con.clone(), &format!("{}_request_state", robot_id)).await;
+        if let Some(SPValue::String(StringOrUnknown::String(s))) = request_state {
+            if s == "executing" {
+                values.push((format!("{}_request_cancel", robot_id), true.to_spvalue()));
+                cancelled.push(robot_id.clone());
+            }
+        }
+    }
+    if let Some(SPValue::String(StringOrUnknown::String(goal))) =
+        backend::get_value(con.clone(), &goal_variable).await
+    {
+        if !goal.is_empty() {
+            values.push((goal_variable, "".to_spvalue()));
+        }
+    }
+    tokio::time::timeout(STOP_TIMEOUT, async {
+        backend::set_values(con.clone(), values).await;
+        for robot_id in robot_ids {
+            crate::robot::cancel_current_goal(con.clone(), robot_id).await;
+        }
+    })
+    .await
+    .map_err(|_| "Timed out writing the stop requests".to_string())?;
+    Ok(cancelled)
+}
+
+enum Phase {
+    Running,
+    Asking,
+    /// Stop chosen. The runs of the GUI are cancelled this frame, the robots
+    /// are stopped from the next one on so no run sends another step.
+    CancellingRuns,
+    Stopping(Promise<Result<Vec<String>, String>>),
+    Failed(String),
+    /// Waiting for the writes in `pending` before closing.
+    Flushing,
+    Closing,
+}
+
+pub struct ShutdownGuard {
+    phase: Phase,
+    /// What is still being written, closing waits for it.
+    pending: Vec<&'static str>,
+}
+
+impl ShutdownGuard {
+    pub fn new() -> Self {
+        Self {
+            phase: Phase::Running,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Updated every frame before `update`.
+    pub fn set_pending(&mut self, pending: Vec<&'static str>) {
+        self.pending = pending;
+    }
+
+    /// Whether the runs of the GUI have to be cancelled, before the robots
+    /// are stopped or while closing waits for them.
+    pub fn cancels_runs(&self) -> bool {
+        matches!(
+            self.phase,
+            Phase::CancellingRuns | Phase::Stopping(_) | Phase::Flushing
+        )
+    }
+
+    /// Intercepts close requests according to `behavior`. Returns true on the
+    /// frame the window actually closes, so the caller can flush what it keeps.
+    pub fn update(
+        &mut self,
+        ctx: &egui::Context,
+        behavior: ExitBehavior,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+        robot_ids: Vec<String>,
+        goal_variable: String,
+    ) -> bool {
+        let close_requested = ctx.input(|i| i.viewport().close_requested());
+        let mut choice = None;
+        let mut failure = None;
+
+        match self.phase {
+            Phase::Closing => return close_requested,
+            Phase::Running if !close_requested => return false,
+            Phase::Running => match behavior {
+                ExitBehavior::Close => choice = Some(Choice::Close),
+                ExitBehavior::Ask => self.phase = Phase::Asking,
+                ExitBehavior::StopRobots => choice = Some(Choice::Stop),
+            },
+            _ => (),
+        }
+        if close_requested {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+        }
+
+        match &self.phase {
+            Phase::Asking => {
+                choice = choice.or(draw_dialog(ctx, |ui| {
+                    ui.label("Cancel running robot commands and the planner goal before closing?");
+                    ui.horizontal(|ui| {
+                        if ui.button("Stop Robots and Close").clicked() {
+                            Some(Choice::Stop)
+                        } else if ui.button("Close Without Stopping").clicked() {
+                            Some(Choice::Close)
+                        } else if ui.button("Cancel").clicked() {
+                            Some(Choice::Cancel)
+                        } else {
+                            None
+                        }
+                    })
+                    .inner
+                }));
+            }
+            Phase::Stopping(promise) => match promise.ready() {
+                Some(Ok(stopped)) => {
+                    if !stopped.is_empty() {
+                        log::info!("Cancelled commands of {} on exit", stopped.join(", "));
+                    }
+                    choice = Some(Choice::Close);
+                }
+                Some(Err(e)) => {
+                    log::error!("Failed to stop the robots on exit: {}", e);
+                    failure = Some(e.clone());
+                }
+                None => {
+                    draw_dialog(ctx, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.label("Stopping robots…");
+                        });
+                        None
+                    });
+                }
+            },
+            Phase::Failed(e) => {
+                choice = draw_dialog(ctx, |ui| {
+                    ui.colored_label(
+                        egui::Color32::RED,
+                        format!("Failed to stop the robots: {}", e),
+                    );
+                    ui.horizontal(|ui| {
+                        if ui.button("Retry").clicked() {
+                            Some(Choice::Stop)
+                        } else if ui.button("Close Anyway").clicked() {
+                            Some(Choice::Close)
+                        } else if ui.button("Cancel").clicked() {
+                            Some(Choice::Cancel)
+                        } else {
+                            None
+                        }
+                    })
+                    .inner
+                });
+            }
+            Phase::CancellingRuns => {
+                let handle = handle.clone();
+                let con_clone = connection.clone();
+                self.phase = Phase::Stopping(workers::spawn_urgent("stop_on_exit", move || {
+                    handle.block_on(stop_robots(con_clone, robot_ids, goal_variable))
+                }));
+                ctx.request_repaint();
+                return false;
+            }
+            Phase::Flushing if self.pending.is_empty() => choice = Some(Choice::Close),
+            Phase::Flushing => {
+                choice = draw_dialog(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(format!(
+                            "Waiting for {} to finish…",
+                            self.pending.join(", ")
+                        ));
+                    });
+                    ui.button("Close Anyway")
+                        .clicked()
+                        .then_some(Choice::ForceClose)
+                });
+            }
+            Phase::Running | Phase::Closing => (),
+        }
+
+        if let Some(e) = failure {
+            self.phase = Phase::Failed(e);
+        }
+        match choice {
+            Some(Choice::Stop) => self.phase = Phase::CancellingRuns,
+            Some(Choice::Close) if !self.pending.is_empty() => self.phase = Phase::Flushing,
+            Some(Choice::Close | Choice::ForceClose) => {
+                if !self.pending.is_empty() {
+                    log::warn!("Closed while {} was still pending", self.pending.join(", "));
+                }
+                self.phase = Phase::Closing;
+                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            }
+            Some(Choice::Cancel) => self.phase = Phase::Running,
+            None => (),
+        }
+        false
+    }
+}
+
+enum Choice {
+    Stop,
+    /// Closes once nothing is pending.
+    Close,
+    /// Closes without waiting for pending writes.
+    ForceClose,
+    Cancel,
+}
+
+fn draw_dialog(
+    ctx: &egui::Context,
+    add_contents: impl FnOnce(&mut egui::Ui) -> Option<Choice>,
+) -> Option<Choice> {
+    egui::Window::new("Close controller")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+        .show(ctx, add_contents)
+        .and_then(|response| response.inner)
+        .flatten()
+}
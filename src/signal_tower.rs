@@ -0,0 +1,324 @@
+//! Signal tower and HMI lamps of the cell, each a boolean state variable.
+//! They are switched by hand or follow the robots' alarms, the same ones the
+//! order handler records: e.g. red on an alarm, green while a robot moves.
+
+use crate::order_history;
+use crate::state_poller::StatePoller;
+use crate::storage;
+use crate::workers;
+use eframe::egui;
+use micro_sp::*;
+use poll_promise::Promise;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::Arc};
+
+pub const TOWER_FILE: &str = "signal_tower.json";
+
+pub fn check_file() -> Option<Result<(), String>> {
+    storage::check_json::<TowerConfig>(TOWER_FILE)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellStatus {
+    Alarm,
+    Running,
+    Idle,
+}
+
+impl CellStatus {
+    fn label(&self) -> &'static str {
+        match self {
+            CellStatus::Alarm => "Alarm",
+            CellStatus::Running => "Running",
+            CellStatus::Idle => "Idle",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Lamp {
+    pub name: String,
+    pub variable: String,
+    /// Statuses the lamp is lit in while automated.
+    pub on_alarm: bool,
+    pub on_running: bool,
+    pub on_idle: bool,
+}
+
+impl Lamp {
+    fn new(name: &str, variable: &str, on_alarm: bool, on_running: bool, on_idle: bool) -> Self {
+        Self {
+            name: name.to_string(),
+            variable: variable.to_string(),
+            on_alarm,
+            on_running,
+            on_idle,
+        }
+    }
+
+    fn lit_in(&self, status: CellStatus) -> bool {
+        match status {
+            CellStatus::Alarm => self.on_alarm,
+            CellStatus::Running => self.on_running,
+            CellStatus::Idle => self.on_idle,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct TowerConfig {
+    lamps: Vec<Lamp>,
+    /// Lamps follow the cell status instead of only being switched by hand.
+    automate: bool,
+}
+
+impl Default for TowerConfig {
+    fn default() -> Self {
+        Self {
+            lamps: vec![
+                Lamp::new("Red", "tower_red", true, false, false),
+                Lamp::new("Yellow", "tower_yellow", false, false, true),
+                Lamp::new("Green", "tower_green", false, true, false),
+                Lamp::new("Buzzer", "tower_buzzer", false, false, false),
+            ],
+            automate: false,
+        }
+    }
+}
+
+/// Status of the cell from the robots' polled state, with the alarm texts.
+fn cell_status(poller: &StatePoller, robot_ids: &[String]) -> (CellStatus, Vec<String>) {
+    let mut values = HashMap::new();
+    for key in status_keys(robot_ids) {
+        if let Some(value) = poller.get(&key) {
+            values.insert(key, value.clone());
+        }
+    }
+    let alarms = robot_ids
+        .iter()
+        .flat_map(|robot_id| order_history::robot_alarms(robot_id, &values))
+        .collect::<Vec<String>>();
+    let running = robot_ids.iter().any(|robot_id| {
+        crate::state_poller::sp_value_to_display(values.get(&format!("{}_request_state", robot_id)))
+            == "executing"
+    });
+    let status = if !alarms.is_empty() {
+        CellStatus::Alarm
+    } else if running {
+        CellStatus::Running
+    } else {
+        CellStatus::Idle
+    };
+    (status, alarms)
+}
+
+fn status_keys(robot_ids: &[String]) -> Vec<String> {
+    robot_ids
+        .iter()
+        .flat_map(|robot_id| {
+            [
+                format!("{}_request_state", robot_id),
+                format!("{}_safety_mode", robot_id),
+            ]
+        })
+        .collect()
+}
+
+pub struct SignalTower {
+    pub open: bool,
+    config: TowerConfig,
+    status: Option<(CellStatus, Vec<String>)>,
+    /// Status the lamps were last set for, so they are only written on changes.
+    applied: Option<CellStatus>,
+    write_promise: Option<Promise<()>>,
+}
+
+impl SignalTower {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            config: storage::load_json(TOWER_FILE).unwrap_or_default(),
+            status: None,
+            applied: None,
+            write_promise: None,
+        }
+    }
+
+    fn save(&self) {
+        if let Err(e) = storage::save_json(TOWER_FILE, &self.config) {
+            log::error!("Failed to save the signal tower: {}", e);
+        }
+    }
+
+    /// The lamps, and the robots' state while the lamps are automated.
+    pub fn keys(&self, robot_ids: &[String]) -> Vec<String> {
+        let mut keys = self
+            .config
+            .lamps
+            .iter()
+            .filter(|lamp| !lamp.variable.trim().is_empty())
+            .map(|lamp| lamp.variable.clone())
+            .collect::<Vec<String>>();
+        if self.config.automate {
+            keys.extend(status_keys(robot_ids));
+        }
+        keys
+    }
+
+    fn write(
+        &mut self,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+        values: Vec<(String, SPValue)>,
+    ) {
+        let handle = handle.clone();
+        let con_clone = connection.clone();
+        self.write_promise = Some(workers::spawn("signal_tower", move || {
+            handle.block_on(crate::backend::set_values(con_clone, values))
+        }));
+    }
+
+    /// Call once per frame. Sets the lamps when the automated status changes.
+    pub fn update(
+        &mut self,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+        poller: &StatePoller,
+        robot_ids: &[String],
+    ) {
+        if let Some(promise) = &self.write_promise {
+            if promise.ready().is_none() {
+                return;
+            }
+            self.write_promise = None;
+        }
+        if !self.config.automate {
+            self.status = None;
+            self.applied = None;
+            return;
+        }
+        let (status, alarms) = cell_status(poller, robot_ids);
+        self.status = Some((status, alarms));
+        if self.applied == Some(status) {
+            return;
+        }
+        log::info!("Signal tower: {}", status.label());
+        let values = self
+            .config
+            .lamps
+            .iter()
+            .filter(|lamp| !lamp.variable.trim().is_empty())
+            .map(|lamp| (lamp.variable.clone(), lamp.lit_in(status).to_spvalue()))
+            .collect();
+        self.write(handle, connection, values);
+        self.applied = Some(status);
+    }
+
+    /// Lamp switches and their automation, in a window while `open`.
+    pub fn window(
+        &mut self,
+        ctx: &egui::Context,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+        poller: &StatePoller,
+    ) {
+        let mut open = self.open;
+        let mut changed = false;
+        let mut switch = None;
+        let mut remove = None;
+        egui::Window::new("Signal Tower")
+            .open(&mut open)
+            .resizable(true)
+            .show(ctx, |ui| {
+                changed |= ui
+                    .checkbox(&mut self.config.automate, "Follow the cell status")
+                    .on_hover_text(
+                        "Lamps are set when the status changes, switching one by hand \n\
+                         holds until the next change.",
+                    )
+                    .changed();
+                if let Some((status, alarms)) = &self.status {
+                    let color = match status {
+                        CellStatus::Alarm => egui::Color32::RED,
+                        CellStatus::Running => egui::Color32::GREEN,
+                        CellStatus::Idle => egui::Color32::YELLOW,
+                    };
+                    ui.horizontal(|ui| {
+                        ui.label("Cell status:");
+                        ui.colored_label(color, status.label())
+                            .on_hover_text(alarms.join("\n"));
+                    });
+                }
+                egui::Grid::new("signal_tower_grid")
+                    .num_columns(8)
+                    .spacing([10.0, 4.0])
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.strong("Lamp");
+                        ui.strong("Variable");
+                        ui.strong("State");
+                        ui.strong("");
+                        ui.strong("Alarm");
+                        ui.strong("Running");
+                        ui.strong("Idle");
+                        ui.label("");
+                        ui.end_row();
+                        for (i, lamp) in self.config.lamps.iter_mut().enumerate() {
+                            changed |= ui
+                                .add(egui::TextEdit::singleline(&mut lamp.name).desired_width(80.0))
+                                .changed();
+                            changed |= ui
+                                .add(
+                                    egui::TextEdit::singleline(&mut lamp.variable)
+                                        .font(egui::TextStyle::Monospace)
+                                        .desired_width(120.0),
+                                )
+                                .changed();
+                            match poller.get(&lamp.variable) {
+                                Some(SPValue::Bool(BoolOrUnknown::Bool(true))) => {
+                                    ui.colored_label(egui::Color32::GREEN, "● on")
+                                }
+                                Some(SPValue::Bool(BoolOrUnknown::Bool(false))) => ui.weak("○ off"),
+                                _ => ui.weak("-"),
+                            };
+                            ui.horizontal(|ui| {
+                                if ui.small_button("On").clicked() {
+                                    switch = Some((lamp.variable.clone(), true));
+                                }
+                                if ui.small_button("Off").clicked() {
+                                    switch = Some((lamp.variable.clone(), false));
+                                }
+                            });
+                            changed |= ui.checkbox(&mut lamp.on_alarm, "").changed();
+                            changed |= ui.checkbox(&mut lamp.on_running, "").changed();
+                            changed |= ui.checkbox(&mut lamp.on_idle, "").changed();
+                            if ui.button("🗑").clicked() {
+                                remove = Some(i);
+                            }
+                            ui.end_row();
+                        }
+                    });
+                if ui.button("Add Lamp").clicked() {
+                    self.config.lamps.push(Lamp::default());
+                    changed = true;
+                }
+            });
+        self.open = open;
+        if let Some(i) = remove {
+            self.config.lamps.remove(i);
+            changed = true;
+        }
+        if changed {
+            // Lamps or their statuses may differ now, set them again
+            self.applied = None;
+            self.save();
+        }
+        if let Some((variable, on)) = switch {
+            if self.write_promise.is_none() && !variable.trim().is_empty() {
+                self.write(handle, connection, vec![(variable, on.to_spvalue())]);
+            }
+        }
+    }
+}
@@ -0,0 +1,266 @@
+//! Actual TCP speed of a robot plotted against the commanded velocity, to
+//! check that the driver respects the velocity and the speed override.
+//!
+//! The speed is read from `{robot}_tcp_speed` when the driver writes it,
+//! otherwise estimated from successive lookups of the TCP.
+
+use crate::locale;
+use crate::pose::Pose;
+use crate::state_poller::{StatePoller, sp_value_to_display};
+use crate::workers;
+use eframe::egui;
+use micro_sp::*;
+use poll_promise::Promise;
+use std::{
+    collections::VecDeque,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(100);
+/// Seconds of history in the chart.
+const WINDOW_SECONDS: f64 = 20.0;
+const CHART_HEIGHT: f32 = 140.0;
+/// Margin over the commanded velocity before the speed counts as too fast.
+const TOLERANCE: f64 = 1.1;
+
+pub fn tcp_speed_key(robot_id: &str) -> String {
+    format!("{}_tcp_speed", robot_id)
+}
+
+struct SpeedSample {
+    /// Seconds since the monitor was created.
+    t: f64,
+    /// m/s.
+    actual: f64,
+    /// m/s, `None` for joint moves where the velocity is a joint speed.
+    commanded: Option<f64>,
+    executing: bool,
+}
+
+async fn lookup_tcp(
+    con: Arc<ConnectionManager>,
+    baseframe: String,
+    tcp: String,
+) -> Result<(Instant, Pose), String> {
+    let mut connection = con.get_connection().await;
+    let tf = TransformsManager::lookup_transform(&mut connection, &baseframe, &tcp)
+        .await
+        .map_err(|e| format!("Failed to look up {} in {}: {}", tcp, baseframe, e))?;
+    Ok((Instant::now(), Pose::from_sp_transform(&tf.transform)))
+}
+
+pub struct SpeedMonitor {
+    pub open: bool,
+    started: Instant,
+    samples: VecDeque<SpeedSample>,
+    lookup_promise: Option<Promise<Result<(Instant, Pose), String>>>,
+    last_pose: Option<(Instant, Pose)>,
+    last_sample: Option<Instant>,
+    error: Option<String>,
+}
+
+impl SpeedMonitor {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            started: Instant::now(),
+            samples: VecDeque::new(),
+            lookup_promise: None,
+            last_pose: None,
+            last_sample: None,
+            error: None,
+        }
+    }
+
+    fn push(&mut self, at: Instant, actual: f64, commanded: Option<f64>, executing: bool) {
+        let t = at.duration_since(self.started).as_secs_f64();
+        self.samples.push_back(SpeedSample {
+            t,
+            actual,
+            commanded,
+            executing,
+        });
+        while self
+            .samples
+            .front()
+            .is_some_and(|s| s.t < t - WINDOW_SECONDS)
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Takes a sample when due, call every frame while the monitor is open.
+    pub fn update(
+        &mut self,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+        poller: &StatePoller,
+        robot_id: &str,
+        frames: Option<(String, String)>,
+        commanded: Option<f64>,
+    ) {
+        let executing =
+            sp_value_to_display(poller.get(&format!("{}_request_state", robot_id))) == "executing";
+        if let Some(promise) = self.lookup_promise.take() {
+            match promise.try_take() {
+                Ok(Ok((at, pose))) => {
+                    if let Some((last_at, last)) = self.last_pose {
+                        let dt = at.duration_since(last_at).as_secs_f64();
+                        if dt > 0.0 {
+                            let distance = (0..3)
+                                .map(|i| (pose.translation[i] - last.translation[i]).powi(2))
+                                .sum::<f64>()
+                                .sqrt();
+                            self.push(at, distance / dt, commanded, executing);
+                        }
+                    }
+                    self.last_pose = Some((at, pose));
+                    self.error = None;
+                }
+                Ok(Err(e)) => {
+                    self.last_pose = None;
+                    self.error = Some(e);
+                }
+                Err(promise) => {
+                    self.lookup_promise = Some(promise);
+                    return;
+                }
+            }
+        }
+        if self
+            .last_sample
+            .is_some_and(|last| last.elapsed() < SAMPLE_INTERVAL)
+        {
+            return;
+        }
+        self.last_sample = Some(Instant::now());
+        if let Some(SPValue::Float64(FloatOrUnknown::Float64(speed))) =
+            poller.get(&tcp_speed_key(robot_id))
+        {
+            self.push(Instant::now(), speed.0, commanded, executing);
+            return;
+        }
+        let Some((baseframe, tcp)) = frames else {
+            self.error = Some("Select a baseframe and a TCP to estimate the speed.".to_string());
+            return;
+        };
+        let handle = handle.clone();
+        let con_clone = connection.clone();
+        self.lookup_promise = Some(workers::spawn("speed_monitor", move || {
+            handle.block_on(lookup_tcp(con_clone, baseframe, tcp))
+        }));
+    }
+
+    pub fn draw(&mut self, ui: &mut egui::Ui) {
+        if let Some(e) = &self.error {
+            ui.colored_label(egui::Color32::RED, e);
+        }
+        let Some(last) = self.samples.back() else {
+            ui.weak("No samples yet.");
+            return;
+        };
+        let mm_s = |x: f64| format!("{} mm/s", locale::format_number(x * 1000.0, 0));
+        ui.horizontal(|ui| {
+            ui.label("Actual:");
+            ui.monospace(mm_s(last.actual));
+            ui.separator();
+            ui.label("Commanded:");
+            match last.commanded {
+                Some(commanded) => ui.monospace(mm_s(commanded)),
+                None => ui
+                    .weak("-")
+                    .on_hover_text("The velocity of joint moves is a joint speed"),
+            };
+        });
+        let too_fast = self.samples.iter().any(|s| {
+            s.executing
+                && s.commanded
+                    .is_some_and(|commanded| s.actual > commanded * TOLERANCE)
+        });
+        if too_fast {
+            ui.colored_label(
+                egui::Color32::YELLOW,
+                "⚠ The TCP moved faster than commanded, check the driver's speed scaling.",
+            );
+        }
+        self.draw_chart(ui);
+    }
+
+    fn draw_chart(&self, ui: &mut egui::Ui) {
+        let width = ui.available_width().min(900.0);
+        let (response, painter) =
+            ui.allocate_painter(egui::vec2(width, CHART_HEIGHT), egui::Sense::hover());
+        let rect = response.rect;
+        let plot = egui::Rect::from_min_max(
+            rect.min + egui::vec2(48.0, 4.0),
+            rect.max - egui::vec2(4.0, 4.0),
+        );
+        let end = self.samples.back().map(|s| s.t).unwrap_or(0.0);
+        let max_speed = self
+            .samples
+            .iter()
+            .map(|s| s.actual.max(s.commanded.unwrap_or(0.0)))
+            .fold(0.05, f64::max)
+            * 1.2;
+        let to_screen = |t: f64, speed: f64| {
+            egui::pos2(
+                plot.right() - ((end - t) / WINDOW_SECONDS) as f32 * plot.width(),
+                plot.bottom() - (speed / max_speed) as f32 * plot.height(),
+            )
+        };
+
+        let text_color = ui.visuals().text_color();
+        let grid_stroke = egui::Stroke::new(1.0, ui.visuals().weak_text_color());
+        for fraction in [0.0, 0.5, 1.0] {
+            let y = plot.bottom() - fraction * plot.height();
+            painter.line_segment(
+                [egui::pos2(plot.left(), y), egui::pos2(plot.right(), y)],
+                grid_stroke,
+            );
+            painter.text(
+                egui::pos2(plot.left() - 4.0, y),
+                egui::Align2::RIGHT_CENTER,
+                locale::format_number(fraction as f64 * max_speed * 1000.0, 0),
+                egui::FontId::proportional(10.0),
+                text_color,
+            );
+        }
+        // Executing periods shaded
+        for pair in self.samples.iter().collect::<Vec<_>>().windows(2) {
+            if pair[0].executing {
+                let from = to_screen(pair[0].t, 0.0).x;
+                let to = to_screen(pair[1].t, 0.0).x;
+                painter.rect_filled(
+                    egui::Rect::from_x_y_ranges(from..=to, plot.y_range()),
+                    0.0,
+                    egui::Color32::from_rgba_unmultiplied(80, 160, 230, 24),
+                );
+            }
+        }
+        let commanded = self
+            .samples
+            .iter()
+            .filter_map(|s| s.commanded.map(|c| to_screen(s.t, c)))
+            .collect::<Vec<egui::Pos2>>();
+        painter.add(egui::Shape::dashed_line(
+            &commanded,
+            egui::Stroke::new(1.5, egui::Color32::from_rgb(230, 160, 40)),
+            6.0,
+            4.0,
+        ));
+        let actual = self
+            .samples
+            .iter()
+            .map(|s| to_screen(s.t, s.actual))
+            .collect::<Vec<egui::Pos2>>();
+        painter.add(egui::Shape::line(
+            actual,
+            egui::Stroke::new(1.5, egui::Color32::LIGHT_BLUE),
+        ));
+        response.on_hover_text(
+            "Blue: actual TCP speed, orange: commanded velocity (mm/s), \
+             shaded while a command executes",
+        );
+    }
+}
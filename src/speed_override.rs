@@ -0,0 +1,159 @@
+use crate::state_poller::StatePoller;
+use crate::workers;
+use eframe::egui;
+use micro_sp::*;
+use ordered_float::OrderedFloat;
+use poll_promise::Promise;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How long after a write the slider keeps its value, until the poller has
+/// read the written one back.
+const SYNC_GRACE: Duration = Duration::from_secs(1);
+
+/// Override above which the label is highlighted as a high speed.
+const HIGH_SPEED_PERCENT: f64 = 75.0;
+
+pub fn velocity_scaling_key(robot_id: &str) -> String {
+    format!("{}_global_velocity_scaling", robot_id)
+}
+
+pub fn acceleration_scaling_key(robot_id: &str) -> String {
+    format!("{}_global_acceleration_scaling", robot_id)
+}
+
+/// Polled scaling factor of `key`, if the robot has one.
+pub fn live_scaling(poller: &StatePoller, key: &str) -> Option<f64> {
+    match poller.get(key) {
+        Some(SPValue::Float64(FloatOrUnknown::Float64(scaling))) => Some(scaling.0),
+        _ => None,
+    }
+}
+
+/// The only writer of the global scaling, shared by the speed override and
+/// the dashboard so both always show what the robot uses.
+pub async fn write_scaling(
+    con: Arc<ConnectionManager>,
+    robot_id: String,
+    velocity: f64,
+    acceleration: f64,
+) {
+    let state = State::new()
+        .add(assign!(
+            fv!(&&acceleration_scaling_key(&robot_id)),
+            SPValue::Float64(FloatOrUnknown::Float64(OrderedFloat(acceleration)))
+        ))
+        .add(assign!(
+            fv!(&&velocity_scaling_key(&robot_id)),
+            SPValue::Float64(FloatOrUnknown::Float64(OrderedFloat(velocity)))
+        ));
+    crate::recorder::record_state(&state);
+    let mut connection = con.get_connection().await;
+    StateManager::set_state(&mut connection, &state).await;
+}
+
+/// Global speed override, drawn in the tab bar so it is visible from every tab.
+/// Like the teach pendant slider it scales both velocity and acceleration and
+/// is written to the state as soon as it changes. It starts from the robot's
+/// polled velocity scaling and follows it when something else, like the
+/// dashboard, changes it.
+pub struct SpeedOverride {
+    /// Robot the slider was last synced with.
+    robot_id: String,
+    percent: f64,
+    write_promise: Option<Promise<()>>,
+    /// Set when the slider moved while a write was in flight.
+    pending_write: bool,
+    last_write: Option<Instant>,
+    /// Polled scaling last copied into the slider.
+    live_seen: Option<f64>,
+}
+
+impl SpeedOverride {
+    pub fn new() -> Self {
+        Self {
+            robot_id: String::new(),
+            percent: 100.0,
+            write_promise: None,
+            pending_write: false,
+            last_write: None,
+            live_seen: None,
+        }
+    }
+
+    /// Takes over a polled scaling that changed, unless the slider's own
+    /// write may not have been read back yet. A new robot starts over from
+    /// its own scaling instead of getting the old robot's value written.
+    fn sync(&mut self, robot_id: &str, live: Option<f64>) {
+        if self.robot_id != robot_id {
+            self.robot_id = robot_id.to_string();
+            self.pending_write = false;
+            self.last_write = None;
+            self.live_seen = None;
+        }
+        let writing = self.pending_write
+            || self.write_promise.is_some()
+            || self
+                .last_write
+                .is_some_and(|last| last.elapsed() < SYNC_GRACE);
+        if let Some(live) = live {
+            if !writing && self.live_seen != Some(live) {
+                self.live_seen = Some(live);
+                self.percent = (live * 100.0).clamp(0.0, 100.0);
+            }
+        }
+    }
+
+    /// The current override as a 0.0-1.0 scaling factor.
+    pub fn scaling(&self) -> f64 {
+        self.percent / 100.0
+    }
+
+    pub fn ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+        robot_id: &str,
+        live: Option<f64>,
+    ) {
+        if let Some(promise) = &self.write_promise {
+            if promise.ready().is_some() {
+                self.write_promise = None;
+            }
+        }
+        self.sync(robot_id, live);
+
+        let color = if self.percent > HIGH_SPEED_PERCENT {
+            egui::Color32::from_rgb(230, 160, 40)
+        } else {
+            ui.visuals().text_color()
+        };
+        // Until the robot's scaling is read there is nothing to override
+        let response = ui
+            .add_enabled(
+                self.live_seen.is_some(),
+                egui::Slider::new(&mut self.percent, 0.0..=100.0)
+                    .suffix(" %")
+                    .step_by(1.0)
+                    .fixed_decimals(0),
+            )
+            .on_disabled_hover_text("Waiting for the robot's velocity scaling");
+        ui.colored_label(color, format!("Speed Override ({}):", robot_id));
+
+        if response.changed() {
+            self.pending_write = true;
+        }
+        if self.pending_write && self.write_promise.is_none() {
+            self.pending_write = false;
+            self.last_write = Some(Instant::now());
+            let scaling = self.scaling();
+            let robot_id = robot_id.to_string();
+            let handle = handle.clone();
+            let con_clone = connection.clone();
+            self.write_promise = Some(workers::spawn("speed_override", move || {
+                handle.block_on(write_scaling(con_clone, robot_id, scaling, scaling))
+            }));
+        }
+    }
+}
@@ -0,0 +1,219 @@
+//! Zones where moves run at reduced speed, e.g. the operator side of the
+//! table. Paths are checked against them before they run and, when enforced,
+//! steps with their goal inside a zone are sent slower.
+
+use crate::locale::{self, LocalizedDragValue};
+use crate::pose::Pose;
+use crate::storage;
+use crate::workspace::{self, Envelope, EnvelopeShape};
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+pub const ZONES_FILE: &str = "speed_zones.json";
+
+pub fn check_file() -> Option<Result<(), String>> {
+    storage::check_json::<SpeedZones>(ZONES_FILE)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpeedZone {
+    pub name: String,
+    pub area: Envelope,
+    /// Share of the commanded velocity and acceleration allowed inside, %.
+    pub speed_percent: f64,
+}
+
+impl SpeedZone {
+    pub fn factor(&self) -> f64 {
+        (self.speed_percent / 100.0).clamp(0.0, 1.0)
+    }
+}
+
+/// Zones of this station, for every robot.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpeedZones {
+    zones: Vec<SpeedZone>,
+    /// Scale down steps inside a zone instead of only warning about them.
+    pub enforce: bool,
+}
+
+impl SpeedZones {
+    pub fn load() -> Self {
+        storage::load_json(ZONES_FILE).unwrap_or_default()
+    }
+
+    fn save(&self) {
+        if let Err(e) = storage::save_json(ZONES_FILE, self) {
+            log::error!("Failed to save speed zones: {}", e);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.zones.is_empty()
+    }
+
+    /// Frames the zones are given in, to look up with the waypoints.
+    pub fn frames(&self) -> Vec<String> {
+        self.zones.iter().map(|z| z.area.frame.clone()).collect()
+    }
+
+    /// The slowest zone `waypoint` lies in. `poses` are in one common frame
+    /// and have to contain the waypoint and the zone frames.
+    pub fn zone_at(
+        &self,
+        poses: &HashMap<String, Pose>,
+        waypoint: &str,
+    ) -> Result<Option<&SpeedZone>, String> {
+        if self.zones.is_empty() {
+            return Ok(None);
+        }
+        let point = poses
+            .get(waypoint)
+            .ok_or_else(|| format!("{} isn't looked up", waypoint))?
+            .translation;
+        let mut slowest: Option<&SpeedZone> = None;
+        for zone in &self.zones {
+            let frame_pose = poses
+                .get(&zone.area.frame)
+                .ok_or_else(|| format!("{} isn't looked up", zone.area.frame))?;
+            if zone.area.outside_by(frame_pose, point) > 0.0 {
+                continue;
+            }
+            if slowest.is_none_or(|s| zone.speed_percent < s.speed_percent) {
+                slowest = Some(zone);
+            }
+        }
+        Ok(slowest)
+    }
+
+    /// Editor for the zones, saved on every change.
+    pub fn draw_editor(&mut self, ui: &mut egui::Ui, frames: &[String]) {
+        let mut changed = false;
+        let mut remove = None;
+        changed |= ui
+            .checkbox(&mut self.enforce, "Reduce the speed of steps inside a zone")
+            .on_hover_text("Off, paths with steps inside a zone are only warned about.")
+            .changed();
+        for (i, zone) in self.zones.iter_mut().enumerate() {
+            egui::Frame::group(ui.style()).show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    changed |= ui
+                        .add(egui::TextEdit::singleline(&mut zone.name).desired_width(120.0))
+                        .changed();
+                    ui.label("in");
+                    egui::ComboBox::from_id_salt(("speed_zone_frame", i))
+                        .selected_text(&zone.area.frame)
+                        .show_ui(ui, |ui| {
+                            for frame in frames {
+                                changed |= ui
+                                    .selectable_value(&mut zone.area.frame, frame.clone(), frame)
+                                    .changed();
+                            }
+                        });
+                    ui.label("Speed:");
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut zone.speed_percent)
+                                .localized()
+                                .suffix(" %")
+                                .speed(1.0)
+                                .range(0.0..=100.0),
+                        )
+                        .changed();
+                    if ui.button("🗑").clicked() {
+                        remove = Some(i);
+                    }
+                });
+                egui::Grid::new(("speed_zone_values", i))
+                    .num_columns(4)
+                    .show(ui, |ui| match &mut zone.area.shape {
+                        EnvelopeShape::Sphere { center, radius } => {
+                            ui.label("Center (m):");
+                            changed |= workspace::draw_xyz(ui, center);
+                            ui.end_row();
+                            ui.label("Radius (m):");
+                            changed |= ui
+                                .add(
+                                    egui::DragValue::new(radius)
+                                        .localized()
+                                        .speed(0.01)
+                                        .range(0.0..=10.0),
+                                )
+                                .changed();
+                            ui.end_row();
+                        }
+                        EnvelopeShape::Box { min, max } => {
+                            ui.label("Min (m):");
+                            changed |= workspace::draw_xyz(ui, min);
+                            ui.end_row();
+                            ui.label("Max (m):");
+                            changed |= workspace::draw_xyz(ui, max);
+                            ui.end_row();
+                        }
+                    });
+            });
+        }
+        ui.horizontal(|ui| {
+            let frame = frames
+                .iter()
+                .find(|f| *f == "world")
+                .or(frames.first())
+                .cloned()
+                .unwrap_or_else(|| "world".to_string());
+            let name = format!("Zone {}", self.zones.len() + 1);
+            if ui.button("Add Box").clicked() {
+                self.zones.push(SpeedZone {
+                    name: name.clone(),
+                    area: Envelope {
+                        frame: frame.clone(),
+                        shape: EnvelopeShape::Box {
+                            min: [-0.5, -0.5, 0.0],
+                            max: [0.5, 0.5, 1.0],
+                        },
+                    },
+                    speed_percent: 25.0,
+                });
+                changed = true;
+            }
+            if ui.button("Add Sphere").clicked() {
+                self.zones.push(SpeedZone {
+                    name,
+                    area: Envelope {
+                        frame,
+                        shape: EnvelopeShape::Sphere {
+                            center: [0.0; 3],
+                            radius: 0.5,
+                        },
+                    },
+                    speed_percent: 25.0,
+                });
+                changed = true;
+            }
+        });
+        if let Some(i) = remove {
+            self.zones.remove(i);
+            changed = true;
+        }
+        if changed {
+            self.save();
+        }
+    }
+}
+
+/// Warning line for a step with its goal inside a zone.
+pub fn zone_warning(step: usize, frame: &str, zone: &SpeedZone, enforced: bool) -> String {
+    let percent = locale::format_number(zone.speed_percent, 0);
+    if enforced {
+        format!(
+            "Step {} ({}) is in {}, it runs at {} % speed",
+            step, frame, zone.name, percent
+        )
+    } else {
+        format!(
+            "Step {} ({}) is in the reduced-speed zone {} ({} %) but runs at full speed",
+            step, frame, zone.name, percent
+        )
+    }
+}
@@ -0,0 +1,112 @@
+use crate::workers;
+use micro_sp::*;
+use poll_promise::Promise;
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// How often the watched variables are re-read from the state.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+async fn get_sp_values(con: Arc<ConnectionManager>, keys: Vec<String>) -> HashMap<String, SPValue> {
+    let mut connection = con.get_connection().await;
+    let mut values = HashMap::new();
+    for key in keys {
+        if let Some(value) = StateManager::get_sp_value(&mut connection, &key).await {
+            values.insert(key, value);
+        }
+    }
+    values
+}
+
+/// Periodically reads a set of state variables in the background so that
+/// widgets showing live data don't each have to manage their own promise.
+pub struct StatePoller {
+    /// Keys watched since the last update. Rebuilt every frame, so keys
+    /// nobody watches any more drop out of the next read.
+    watched: BTreeSet<String>,
+    values: HashMap<String, SPValue>,
+    /// Keys the last finished read covered, to tell missing values from
+    /// ones not read yet.
+    polled: BTreeSet<String>,
+    /// Keys of the read in progress.
+    reading: BTreeSet<String>,
+    promise: Option<Promise<HashMap<String, SPValue>>>,
+    last_poll: Option<Instant>,
+}
+
+impl StatePoller {
+    pub fn new() -> Self {
+        Self {
+            watched: BTreeSet::new(),
+            values: HashMap::new(),
+            polled: BTreeSet::new(),
+            reading: BTreeSet::new(),
+            promise: None,
+            last_poll: None,
+        }
+    }
+
+    /// Adds keys to the set that is polled from the next cycle on. Keys have
+    /// to be watched again every frame before `update` to stay in it.
+    pub fn watch<I: IntoIterator<Item = String>>(&mut self, keys: I) {
+        self.watched.extend(keys);
+    }
+
+    /// Last polled value of a watched key, if it exists in the state.
+    pub fn get(&self, key: &str) -> Option<&SPValue> {
+        self.values.get(key)
+    }
+
+    /// Whether `key` was read yet. Until then `get` returns `None` for it
+    /// whether or not it exists.
+    pub fn polled(&self, key: &str) -> bool {
+        self.polled.contains(key)
+    }
+
+    /// Call once per frame, after the frame's `watch` calls. Collects finished
+    /// reads and starts a new one when due.
+    pub fn update(&mut self, handle: &tokio::runtime::Handle, connection: &Arc<ConnectionManager>) {
+        let watched = std::mem::take(&mut self.watched);
+        if let Some(promise) = self.promise.take() {
+            match promise.try_take() {
+                Ok(values) => {
+                    self.values = values;
+                    self.polled = std::mem::take(&mut self.reading);
+                }
+                Err(promise) => {
+                    self.promise = Some(promise);
+                    return;
+                }
+            }
+        }
+
+        let due = self
+            .last_poll
+            .is_none_or(|last| last.elapsed() >= POLL_INTERVAL);
+        if !due || watched.is_empty() {
+            return;
+        }
+        self.last_poll = Some(Instant::now());
+        let keys = watched.iter().cloned().collect::<Vec<String>>();
+        self.reading = watched;
+        let handle = handle.clone();
+        let con_clone = connection.clone();
+        self.promise = Some(workers::spawn("state_poller", move || {
+            handle.block_on(get_sp_values(con_clone, keys))
+        }));
+    }
+}
+
+/// Renders a polled value for display, `UNKNOWN` and missing keys included.
+pub fn sp_value_to_display(value: Option<&SPValue>) -> String {
+    match value {
+        Some(SPValue::String(StringOrUnknown::String(s))) => s.clone(),
+        Some(SPValue::Bool(BoolOrUnknown::Bool(b))) => b.to_string(),
+        Some(SPValue::Float64(FloatOrUnknown::Float64(f))) => crate::locale::format_number(f.0, 3),
+        Some(other) => format!("{:?}", other),
+        None => "-".to_string(),
+    }
+}
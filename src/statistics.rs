@@ -0,0 +1,279 @@
+use crate::history::{self, HistoryEntry};
+use crate::locale;
+use crate::screwdriving;
+use crate::shift_report::ShiftReport;
+use crate::utilization::{SHIFTS, Utilization, UtilizationTracker};
+use eframe::egui;
+use rfd::FileDialog;
+use std::collections::BTreeMap;
+
+const CHART_HEIGHT: f32 = 140.0;
+
+pub struct StatisticsTab {
+    days_shown: usize,
+    export_status: Option<String>,
+    /// Day and shift of the report to generate, the previous shift until set.
+    report_shift: Option<(String, usize)>,
+    report_status: Option<Result<String, String>>,
+    /// Command history for the screwdriving results, read when first shown.
+    process_history: Option<Vec<HistoryEntry>>,
+}
+
+impl StatisticsTab {
+    pub fn new() -> Self {
+        Self {
+            days_shown: 14,
+            export_status: None,
+            report_shift: None,
+            report_status: None,
+            process_history: None,
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui, tracker: &mut UtilizationTracker, report_dir: &str) {
+        ui.horizontal(|ui| {
+            ui.heading("Robot Utilization");
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.button("Export CSV").clicked() {
+                    self.export_csv(tracker);
+                }
+                ui.add(
+                    egui::DragValue::new(&mut self.days_shown)
+                        .range(1..=90)
+                        .suffix(" days"),
+                );
+                ui.label("Show:");
+                if ui
+                    .add(
+                        egui::DragValue::new(&mut tracker.utc_offset_hours)
+                            .range(-12..=14)
+                            .prefix("UTC "),
+                    )
+                    .on_hover_text("Local time offset used to split days into shifts.")
+                    .changed()
+                {
+                    tracker.save();
+                }
+                ui.label("Shift time zone:");
+            });
+        });
+        if let Some(status) = &self.export_status {
+            ui.label(status);
+        }
+        ui.label(
+            "Busy is time spent with request_state \"executing\", sampled while the GUI is running.",
+        );
+        self.draw_shift_report(ui, tracker, report_dir);
+        egui::CollapsingHeader::new("Screwdriving Results")
+            .id_salt("statistics_screwdriving")
+            .show(ui, |ui| {
+                if ui.button("Refresh").clicked() {
+                    self.process_history = None;
+                }
+                let entries = self.process_history.get_or_insert_with(history::load);
+                screwdriving::draw_statistics(ui, entries);
+            });
+        ui.separator();
+
+        if tracker.records.is_empty() {
+            ui.label(
+                "No utilization recorded yet. Robots are picked up from the Resources tab and the controllers.",
+            );
+            return;
+        }
+
+        egui::ScrollArea::vertical()
+            .id_salt("statistics_scroll_area")
+            .auto_shrink([false; 2])
+            .show(ui, |ui| {
+                for robot_id in tracker.robots() {
+                    // Ids that never showed up in the state have nothing to chart
+                    let daily = tracker.daily(robot_id);
+                    if daily.is_empty() {
+                        continue;
+                    }
+                    egui::CollapsingHeader::new(robot_id)
+                        .id_salt(format!("statistics_{}", robot_id))
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            let shown = daily
+                                .iter()
+                                .rev()
+                                .take(self.days_shown)
+                                .rev()
+                                .map(|(d, u)| (d.clone(), *u))
+                                .collect::<Vec<_>>();
+                            draw_utilization_chart(ui, &shown);
+                            if let Some((last_day, _)) = shown.last() {
+                                draw_shift_table(ui, tracker, robot_id, last_day);
+                            }
+                        });
+                }
+            });
+    }
+
+    fn draw_shift_report(
+        &mut self,
+        ui: &mut egui::Ui,
+        tracker: &UtilizationTracker,
+        report_dir: &str,
+    ) {
+        let (day, shift) = self
+            .report_shift
+            .get_or_insert_with(|| tracker.previous_shift());
+        ui.horizontal(|ui| {
+            ui.label("Shift report:");
+            ui.add(
+                egui::TextEdit::singleline(day)
+                    .hint_text("YYYY-MM-DD")
+                    .desired_width(90.0),
+            );
+            egui::ComboBox::from_id_salt("report_shift")
+                .selected_text(SHIFTS[*shift])
+                .show_ui(ui, |ui| {
+                    for (i, name) in SHIFTS.iter().enumerate() {
+                        ui.selectable_value(shift, i, *name);
+                    }
+                });
+            if ui
+                .button("Generate")
+                .on_hover_text(
+                    "Orders, commands, alarms, utilization and maintenance notes of the shift \
+                     as an HTML page.",
+                )
+                .clicked()
+            {
+                self.report_status = Some(
+                    ShiftReport::collect(tracker, day, *shift)
+                        .and_then(|report| report.save(report_dir))
+                        .map(|path| format!("Written to {}", path.display())),
+                );
+            }
+        });
+        match &self.report_status {
+            Some(Ok(status)) => {
+                ui.label(status);
+            }
+            Some(Err(e)) => {
+                ui.colored_label(egui::Color32::RED, e);
+            }
+            None => (),
+        }
+    }
+
+    fn export_csv(&mut self, tracker: &UtilizationTracker) {
+        let file_path = FileDialog::new()
+            .add_filter("CSV", &["csv"])
+            .set_file_name("utilization.csv")
+            .save_file();
+        if let Some(path) = file_path {
+            self.export_status = Some(match std::fs::write(&path, tracker.to_csv()) {
+                Ok(_) => format!("Exported to {}", path.display()),
+                Err(e) => format!("Failed to export: {}", e),
+            });
+        }
+    }
+}
+
+/// Bar chart of the busy share per day.
+fn draw_utilization_chart(ui: &mut egui::Ui, days: &[(String, Utilization)]) {
+    if days.is_empty() {
+        ui.label("No data yet.");
+        return;
+    }
+    let width = ui.available_width().min(900.0);
+    let (response, painter) =
+        ui.allocate_painter(egui::vec2(width, CHART_HEIGHT), egui::Sense::hover());
+    let rect = response.rect;
+    let plot = egui::Rect::from_min_max(
+        rect.min + egui::vec2(36.0, 4.0),
+        rect.max - egui::vec2(4.0, 18.0),
+    );
+    let text_color = ui.visuals().text_color();
+    let grid_stroke = egui::Stroke::new(1.0, ui.visuals().weak_text_color());
+    for fraction in [0.0, 0.5, 1.0] {
+        let y = plot.bottom() - fraction * plot.height();
+        painter.line_segment(
+            [egui::pos2(plot.left(), y), egui::pos2(plot.right(), y)],
+            grid_stroke,
+        );
+        painter.text(
+            egui::pos2(plot.left() - 4.0, y),
+            egui::Align2::RIGHT_CENTER,
+            format!("{:.0}%", fraction * 100.0),
+            egui::FontId::proportional(10.0),
+            text_color,
+        );
+    }
+
+    let slot = plot.width() / days.len() as f32;
+    let mut hovered = None;
+    for (i, (day, u)) in days.iter().enumerate() {
+        let x = plot.left() + slot * i as f32;
+        let bar = egui::Rect::from_min_max(
+            egui::pos2(
+                x + slot * 0.15,
+                plot.bottom() - u.ratio() as f32 * plot.height(),
+            ),
+            egui::pos2(x + slot * 0.85, plot.bottom()),
+        );
+        painter.rect_filled(bar, 2.0, egui::Color32::from_rgb(80, 160, 230));
+        // Only label as many days as fit
+        let label_every = (40.0 / slot).ceil().max(1.0) as usize;
+        if i % label_every == 0 {
+            painter.text(
+                egui::pos2(x + slot * 0.5, plot.bottom() + 2.0),
+                egui::Align2::CENTER_TOP,
+                day.get(5..).unwrap_or(day.as_str()),
+                egui::FontId::proportional(10.0),
+                text_color,
+            );
+        }
+        let column = egui::Rect::from_x_y_ranges(x..=x + slot, plot.y_range());
+        if response.hover_pos().is_some_and(|p| column.contains(p)) {
+            hovered = Some((day, u));
+        }
+    }
+    if let Some((day, u)) = hovered {
+        response.on_hover_text(format!(
+            "{}\nBusy: {}\nIdle: {}\nUtilization: {} %",
+            day,
+            format_duration(u.busy_seconds),
+            format_duration(u.idle_seconds),
+            locale::format_number(u.ratio() * 100.0, 1)
+        ));
+    }
+}
+
+fn draw_shift_table(ui: &mut egui::Ui, tracker: &UtilizationTracker, robot_id: &str, day: &str) {
+    let shifts = tracker
+        .records
+        .iter()
+        .filter(|(k, _)| k.robot_id == robot_id && k.day == day)
+        .map(|(k, u)| (k.shift, *u))
+        .collect::<BTreeMap<usize, Utilization>>();
+    ui.label(format!("Shifts on {}:", day));
+    egui::Grid::new(format!("shift_table_{}", robot_id))
+        .striped(true)
+        .num_columns(4)
+        .show(ui, |ui| {
+            ui.strong("Shift");
+            ui.strong("Busy");
+            ui.strong("Idle");
+            ui.strong("Utilization");
+            ui.end_row();
+            for (i, name) in SHIFTS.iter().enumerate() {
+                let u = shifts.get(&i).copied().unwrap_or_default();
+                ui.label(*name);
+                ui.monospace(format_duration(u.busy_seconds));
+                ui.monospace(format_duration(u.idle_seconds));
+                ui.monospace(format!("{} %", locale::format_number(u.ratio() * 100.0, 1)));
+                ui.end_row();
+            }
+        });
+}
+
+fn format_duration(seconds: f64) -> String {
+    let total = seconds.max(0.0) as u64;
+    format!("{}h {:02}m", total / 3600, (total % 3600) / 60)
+}
@@ -0,0 +1,112 @@
+use serde::{Serialize, de::DeserializeOwned};
+use std::path::PathBuf;
+
+/// Directory where the GUI keeps its own files (history, presets, settings).
+/// Overridable with `MICRO_SP_GUI_DIR`, defaults to `~/.micro_sp_gui`.
+pub fn data_dir() -> PathBuf {
+    if let Ok(dir) = std::env::var("MICRO_SP_GUI_DIR") {
+        return PathBuf::from(dir);
+    }
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    PathBuf::from(home).join(".micro_sp_gui")
+}
+
+pub fn data_file(name: &str) -> PathBuf {
+    data_dir().join(name)
+}
+
+/// Reads a JSON file from the data directory, `None` if it is missing or invalid.
+pub fn load_json<T: DeserializeOwned>(name: &str) -> Option<T> {
+    let path = data_file(name);
+    let content = std::fs::read_to_string(&path).ok()?;
+    match serde_json::from_str(&content) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            log::error!("Failed to parse {:?}: {}", path, e);
+            None
+        }
+    }
+}
+
+/// Whether a data file parses as the `T` its module loads, for the startup
+/// self-test. `None` if it is missing, the module then starts from defaults.
+pub fn check_json<T: DeserializeOwned>(name: &str) -> Option<Result<(), String>> {
+    check_file(name, |content| {
+        serde_json::from_str::<T>(content)
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    })
+}
+
+/// `check_json` for files in other formats, `parse` being the module's parser.
+pub fn check_file(
+    name: &str,
+    parse: impl FnOnce(&str) -> Result<(), String>,
+) -> Option<Result<(), String>> {
+    let path = data_file(name);
+    if !path.exists() {
+        return None;
+    }
+    Some(
+        std::fs::read_to_string(&path)
+            .map_err(|e| format!("can't be read: {}", e))
+            .and_then(|content| parse(&content).map_err(|e| format!("invalid: {}", e))),
+    )
+}
+
+pub fn save_json<T: Serialize>(name: &str, value: &T) -> Result<(), String> {
+    let path = data_file(name);
+    std::fs::create_dir_all(data_dir()).map_err(|e| format!("Failed to create data dir: {}", e))?;
+    let json = serde_json::to_string_pretty(value)
+        .map_err(|e| format!("JSON serialization error: {}", e))?;
+    std::fs::write(&path, json).map_err(|e| format!("Failed to write {:?}: {}", path, e))
+}
+
+/// Seconds since the unix epoch, used to timestamp everything the GUI stores.
+pub fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Formats unix seconds as `YYYY-MM-DD HH:MM` (UTC).
+pub fn format_timestamp(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+    // Civil from days, Howard Hinnant's algorithm
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        y,
+        m,
+        d,
+        rem / 3600,
+        (rem % 3600) / 60
+    )
+}
+
+/// Unix seconds at 00:00 UTC of a `YYYY-MM-DD` date.
+pub fn parse_date(day: &str) -> Option<u64> {
+    let mut parts = day.splitn(3, '-').map(|p| p.parse::<i64>().ok());
+    let (y, m, d) = (parts.next()??, parts.next()??, parts.next()??);
+    if !(1..=12).contains(&m) || !(1..=31).contains(&d) {
+        return None;
+    }
+    // Days from civil, the inverse of `format_timestamp`
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((m + 9) % 12) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+    u64::try_from(days * 86_400).ok()
+}
@@ -0,0 +1,89 @@
+//! Named groups of path steps (e.g. "regrip", "clean nozzle") that paths call
+//! by reference, so a change to the subroutine reaches every path using it.
+
+use crate::path::PathSegment;
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub const SUBROUTINES_FILE: &str = "subroutines.json";
+
+pub fn check_file() -> Option<Result<(), String>> {
+    storage::check_json::<SubroutineLibrary>(SUBROUTINES_FILE)
+}
+
+/// Goal frames starting with this are parameters, bound by each call.
+pub const PARAMETER_PREFIX: char = '$';
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Subroutine {
+    /// Frame parameters, referenced as `$name` by the steps.
+    pub parameters: Vec<String>,
+    pub steps: Vec<PathSegment>,
+}
+
+/// A path step that runs a subroutine, with a frame for every parameter.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SubroutineCall {
+    pub name: String,
+    pub bindings: BTreeMap<String, String>,
+}
+
+impl SubroutineCall {
+    /// Frame bound to `parameter`, the parameter name itself if unbound.
+    pub fn binding<'a>(&'a self, parameter: &'a str) -> &'a str {
+        self.bindings
+            .get(parameter)
+            .map(|frame| frame.as_str())
+            .filter(|frame| !frame.is_empty())
+            .unwrap_or(parameter)
+    }
+
+    pub fn summary(&self) -> String {
+        let bindings = self
+            .bindings
+            .iter()
+            .map(|(parameter, frame)| format!("{}={}", parameter, frame))
+            .collect::<Vec<String>>()
+            .join(", ");
+        format!("▶ {}({})", self.name, bindings)
+    }
+}
+
+/// The subroutines saved on this station, by name.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SubroutineLibrary {
+    subroutines: BTreeMap<String, Subroutine>,
+}
+
+impl SubroutineLibrary {
+    pub fn load() -> Self {
+        storage::load_json(SUBROUTINES_FILE).unwrap_or_default()
+    }
+
+    fn save(&self) {
+        if let Err(e) = storage::save_json(SUBROUTINES_FILE, self) {
+            log::error!("Failed to save subroutines: {}", e);
+        }
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &String> {
+        self.subroutines.keys()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Subroutine> {
+        self.subroutines.get(name)
+    }
+
+    /// Stores (or replaces) a subroutine and writes the library to disk.
+    pub fn insert(&mut self, name: &str, subroutine: Subroutine) {
+        self.subroutines.insert(name.to_string(), subroutine);
+        self.save();
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        if self.subroutines.remove(name).is_some() {
+            self.save();
+        }
+    }
+}
@@ -5,14 +5,58 @@ use eframe::egui;
 use micro_sp::{ConnectionManager, SPTransform, SPTransformStamped, TransformsManager};
 use poll_promise::Promise;
 use serde::Serialize;
+use std::collections::HashSet;
 use std::sync::Arc;
 
-#[derive(PartialEq, Eq, Debug)]
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
 enum AppTab {
+    Resources,
     RobotTab,
     Transforms,
     Lookup,
+    Path,
+    Goal,
+    Statistics,
+    Script,
     AnotherTab,
+    InputDevices,
+    Custom,
+    Settings,
+}
+
+impl AppTab {
+    /// Order of the tab bar.
+    const ALL: [AppTab; 12] = [
+        AppTab::Resources,
+        AppTab::Transforms,
+        AppTab::Lookup,
+        AppTab::RobotTab,
+        AppTab::Path,
+        AppTab::Goal,
+        AppTab::Statistics,
+        AppTab::Script,
+        AppTab::AnotherTab,
+        AppTab::InputDevices,
+        AppTab::Custom,
+        AppTab::Settings,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            AppTab::Resources => "Resources",
+            AppTab::Transforms => "Transforms Controller",
+            AppTab::Lookup => "Lookup",
+            AppTab::RobotTab => "Robot Controller",
+            AppTab::Path => "Path Editor",
+            AppTab::Goal => "Planner Goal",
+            AppTab::Statistics => "Statistics",
+            AppTab::Script => "Script Console",
+            AppTab::AnotherTab => "Order Handler",
+            AppTab::InputDevices => "Input Devices",
+            AppTab::Custom => "Custom",
+            AppTab::Settings => "Settings",
+        }
+    }
 }
 
 pub struct MyApp {
@@ -21,64 +65,454 @@ pub struct MyApp {
     transforms_tab: crate::transforms::TransformsTab,
     lookup_tab: crate::lookup::LookupTab,
     robot_tab: crate::robot::RobotTab,
+    path_tab: crate::path::PathTab,
+    goal_tab: crate::goal::GoalTab,
+    resources_tab: crate::resources::ResourcesTab,
+    statistics_tab: crate::statistics::StatisticsTab,
+    script_tab: crate::scripting::ScriptTab,
     another_tab: crate::another::AnotherTab,
+    custom_tab: crate::custom_panels::CustomTab,
+    settings_tab: crate::settings::SettingsTab,
+    speed_override: crate::speed_override::SpeedOverride,
+    role_switch: crate::access::RoleSwitch,
+    input_devices: crate::input_devices::InputDevices,
+    view_exporter: crate::screenshot::ViewExporter,
+    instructions: crate::work_instructions::InstructionPanel,
+    signal_tower: crate::signal_tower::SignalTower,
+    exporters: crate::exporters::ExporterRegistry,
+    state_poller: crate::state_poller::StatePoller,
+    utilization: crate::utilization::UtilizationTracker,
     active_tab: AppTab,
+    /// Tab that panicked and is paused until the operator retries or disables it.
+    tab_crash: Option<(AppTab, crate::panic_guard::PanicReport)>,
+    disabled_tabs: HashSet<AppTab>,
+    shutdown: crate::shutdown::ShutdownGuard,
+    /// Shown in place of the tabs until the operator continues.
+    self_test: crate::self_test::SelfTest,
 }
 
 impl eframe::App for MyApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         ctx.request_repaint();
+        let mut pending = Vec::new();
+        if self.transforms_tab.writing() {
+            pending.push("frame writes");
+        }
+        if self.custom_tab.writing() {
+            pending.push("custom panel writes");
+        }
+        if self.robot_tab.running() {
+            pending.push("the running queue");
+        }
+        if self.path_tab.running() {
+            pending.push("the running path");
+        }
+        self.shutdown.set_pending(pending);
+        if self.shutdown.cancels_runs() {
+            self.robot_tab.cancel_runs();
+            self.path_tab.cancel_run();
+        }
+        let closing = self.shutdown.update(
+            ctx,
+            self.settings_tab.settings().on_exit,
+            &self.handle,
+            &self.connection,
+            self.known_robot_ids(),
+            self.goal_tab.goal_variable(),
+        );
+        if closing {
+            self.utilization.save();
+        }
+        let instructions = self.another_tab.instructions_path();
+        self.instructions.show(ctx, instructions.as_deref());
         egui::CentralPanel::default().show(ctx, |ui| {
             self.ui(ui);
         });
+        self.path_tab.draw_operator_prompt(ctx);
+        self.signal_tower
+            .window(ctx, &self.handle, &self.connection, &self.state_poller);
+        self.view_exporter.update(ctx);
     }
 }
 
 impl MyApp {
     pub async fn new(handle: tokio::runtime::Handle) -> Self {
         let connection = Arc::new(ConnectionManager::new().await);
+        let settings_tab = crate::settings::SettingsTab::new();
+        if settings_tab.settings().rpc_enabled {
+            crate::rpc::spawn_rpc_server(
+                &handle,
+                connection.clone(),
+                settings_tab.settings().rpc_port,
+            );
+        }
+        let self_test = crate::self_test::SelfTest::start(
+            &handle,
+            &connection,
+            settings_tab.settings().expected_robot_ids(),
+        );
         Self {
             handle,
             connection,
             transforms_tab: crate::transforms::TransformsTab::new(),
             lookup_tab: crate::lookup::LookupTab::new(),
             robot_tab: crate::robot::RobotTab::new(),
+            path_tab: crate::path::PathTab::new(),
+            goal_tab: crate::goal::GoalTab::new(),
+            resources_tab: crate::resources::ResourcesTab::new(),
+            statistics_tab: crate::statistics::StatisticsTab::new(),
+            script_tab: crate::scripting::ScriptTab::new(),
             another_tab: crate::another::AnotherTab::new(),
+            custom_tab: crate::custom_panels::CustomTab::new(),
+            settings_tab,
+            speed_override: crate::speed_override::SpeedOverride::new(),
+            role_switch: crate::access::RoleSwitch::new(),
+            input_devices: crate::input_devices::InputDevices::new(),
+            view_exporter: crate::screenshot::ViewExporter::new(),
+            instructions: crate::work_instructions::InstructionPanel::new(),
+            signal_tower: crate::signal_tower::SignalTower::new(),
+            exporters: crate::exporters::ExporterRegistry::new(),
+            state_poller: crate::state_poller::StatePoller::new(),
+            utilization: crate::utilization::UtilizationTracker::load(),
             active_tab: AppTab::RobotTab,
+            tab_crash: None,
+            disabled_tabs: HashSet::new(),
+            shutdown: crate::shutdown::ShutdownGuard::new(),
+            self_test,
         }
     }
 
+    /// Robots the GUI knows about from the resources list and the open tabs.
+    fn known_robot_ids(&self) -> Vec<String> {
+        let mut ids = self.resources_tab.robot_ids();
+        ids.push(self.robot_tab.robot_id().to_string());
+        ids.push(self.path_tab.robot_id().to_string());
+        ids.retain(|id| !id.is_empty());
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+
     // Main UI function now acts as a tab controller
     fn ui(&mut self, ui: &mut egui::Ui) {
+        if !self.self_test.is_done() {
+            self.self_test.ui(
+                ui,
+                &self.handle,
+                &self.connection,
+                self.settings_tab.settings().expected_robot_ids(),
+            );
+            return;
+        }
+        let robot_id = self.robot_tab.robot_id().to_string();
+        self.state_poller
+            .watch(crate::banner::banner_keys(&robot_id));
+        let path_robot_id = self.path_tab.robot_id().to_string();
+        self.state_poller
+            .watch([crate::resources::enabled_key(&path_robot_id)]);
+        let goal_variable_keys = self.path_tab.goal_variable_keys();
+        self.state_poller.watch(goal_variable_keys.clone());
+        self.utilization
+            .track([robot_id.clone(), path_robot_id.clone()]);
+        self.utilization.track(self.resources_tab.robot_ids());
+        self.state_poller.watch(self.utilization.watch_keys());
+        let velocity_scaling_key = crate::speed_override::velocity_scaling_key(&robot_id);
+        let acceleration_scaling_key = crate::speed_override::acceleration_scaling_key(&robot_id);
+        self.state_poller.watch([
+            velocity_scaling_key.clone(),
+            acceleration_scaling_key.clone(),
+        ]);
+        let capabilities_key = crate::capabilities::capabilities_key(&robot_id);
+        self.state_poller.watch([capabilities_key.clone()]);
+        self.state_poller
+            .watch(self.robot_tab.written_variable_keys());
+        self.state_poller.watch(self.robot_tab.speed_monitor_keys());
+        self.state_poller.watch(self.robot_tab.vibration_keys());
+        self.state_poller.watch(self.robot_tab.gripper_keys());
+        if self.active_tab == AppTab::Custom {
+            self.state_poller.watch(self.custom_tab.keys());
+        }
+        self.state_poller
+            .watch(self.robot_tab.command_feedback_keys());
+        let joint_states_key = crate::joint_drift::joint_states_key(&robot_id);
+        self.state_poller.watch([joint_states_key.clone()]);
+        let robot_ids = self.known_robot_ids();
+        self.state_poller.watch(self.signal_tower.keys(&robot_ids));
+        let cell_guards = self.settings_tab.settings().cell_guards.clone();
+        crate::cell_guards::set_configured(&cell_guards);
+        self.state_poller
+            .watch(crate::cell_guards::guard_keys(&cell_guards));
+        self.state_poller.update(&self.handle, &self.connection);
+        self.signal_tower.update(
+            &self.handle,
+            &self.connection,
+            &self.state_poller,
+            &robot_ids,
+        );
+        self.another_tab
+            .update_background(&self.handle, &self.connection, robot_ids);
+        self.transforms_tab
+            .update_background(&self.handle, &self.connection);
+        self.robot_tab.set_driver_capabilities(
+            self.state_poller
+                .get(&capabilities_key)
+                .and_then(crate::capabilities::DriverCapabilities::from_sp_value),
+        );
+        self.robot_tab.set_live_joints(
+            self.state_poller
+                .get(&joint_states_key)
+                .and_then(crate::joint_drift::floats),
+        );
+        self.path_tab.set_goal_variables(
+            goal_variable_keys
+                .into_iter()
+                .map(|key| {
+                    let variable = crate::path::GoalVariable::polled(&self.state_poller, &key);
+                    (key, variable)
+                })
+                .collect(),
+        );
+        self.utilization.sample(&self.state_poller);
+        if let Some((day, shift)) = self.utilization.take_finished_shift() {
+            let settings = self.settings_tab.settings();
+            if settings.auto_shift_report {
+                match crate::shift_report::ShiftReport::collect(&self.utilization, &day, shift)
+                    .and_then(|report| report.save(&settings.report_dir))
+                {
+                    Ok(path) => log::info!("Shift report written to {}", path.display()),
+                    Err(e) => log::error!("Failed to write the shift report: {}", e),
+                }
+            }
+        }
+        for action in self.input_devices.update() {
+            match action {
+                crate::input_devices::InputAction::Stop => {
+                    self.robot_tab.stop(&self.handle, &self.connection)
+                }
+                crate::input_devices::InputAction::RunPreset(name) => {
+                    self.robot_tab.open_preset(&name);
+                    self.active_tab = AppTab::RobotTab;
+                }
+                // Read continuously by the jog controls
+                crate::input_devices::InputAction::Jog(_) => (),
+            }
+        }
+        self.robot_tab.set_device_jog(self.input_devices.jog());
+        self.robot_tab.set_live_scaling(
+            crate::speed_override::live_scaling(&self.state_poller, &velocity_scaling_key),
+            crate::speed_override::live_scaling(&self.state_poller, &acceleration_scaling_key),
+        );
+        self.robot_tab
+            .set_resource_enabled(crate::banner::is_robot_enabled(
+                &self.state_poller,
+                &robot_id,
+            ));
+        self.path_tab
+            .set_resource_enabled(crate::banner::is_robot_enabled(
+                &self.state_poller,
+                &path_robot_id,
+            ));
+        let open_guards = crate::cell_guards::open_guards(&self.state_poller, &cell_guards);
+        let blocked = crate::cell_guards::blocked_reason(&open_guards);
+        self.robot_tab.set_command_blocked(blocked.clone());
+        self.path_tab.set_command_blocked(blocked);
+
         // Draw the horizontal tab bar
+        ui.horizontal_wrapped(|ui| {
+            for tab in AppTab::ALL {
+                let mut label = egui::RichText::new(tab.label());
+                if self.disabled_tabs.contains(&tab) {
+                    label = label.strikethrough().weak();
+                }
+                ui.selectable_value(&mut self.active_tab, tab, label);
+            }
+        });
+
+        // The speed override stays visible regardless of the active tab
         ui.horizontal(|ui| {
-            ui.selectable_value(
-                &mut self.active_tab,
-                AppTab::Transforms,
-                "Transforms Controller",
-            );
-            ui.selectable_value(&mut self.active_tab, AppTab::Lookup, "Lookup");
-            ui.selectable_value(&mut self.active_tab, AppTab::RobotTab, "Robot Controller");
-            ui.selectable_value(&mut self.active_tab, AppTab::AnotherTab, "Order Handler");
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                self.view_exporter.button(ui, self.active_tab.label());
+                ui.toggle_value(&mut self.instructions.open, "📖")
+                    .on_hover_text("Work instruction of the running or armed order");
+                ui.toggle_value(&mut self.signal_tower.open, "🚦")
+                    .on_hover_text("Signal tower and HMI lamps");
+                self.role_switch.ui(ui);
+                self.speed_override.ui(
+                    ui,
+                    &self.handle,
+                    &self.connection,
+                    &robot_id,
+                    crate::speed_override::live_scaling(&self.state_poller, &velocity_scaling_key),
+                );
+                if crate::recorder::is_recording() {
+                    ui.colored_label(egui::Color32::RED, "● REC")
+                        .on_hover_text("Session recording, stop it in the Script Console");
+                }
+                crate::workers::draw(ui);
+            });
         });
 
+        crate::banner::draw_robot_state_banner(
+            ui,
+            &self.state_poller,
+            &robot_id,
+            (!cell_guards.is_empty()).then_some(open_guards.as_slice()),
+        );
+
         ui.separator();
 
+        if let Some((tab, report)) = &self.tab_crash {
+            let action = crate::panic_guard::draw_report_dialog(ui.ctx(), tab.label(), report);
+            match action {
+                Some(crate::panic_guard::ReportAction::Disable) => {
+                    self.disabled_tabs.insert(*tab);
+                    self.tab_crash = None;
+                }
+                Some(crate::panic_guard::ReportAction::Retry) => self.tab_crash = None,
+                None => (),
+            }
+        }
+
+        let tab = self.active_tab;
+        let tab_top = ui.cursor().top();
+        if self.disabled_tabs.contains(&tab) {
+            ui.label(format!(
+                "{} is disabled after an internal error.",
+                tab.label()
+            ));
+            if ui.button("Re-enable").clicked() {
+                self.disabled_tabs.remove(&tab);
+            }
+        } else if matches!(&self.tab_crash, Some((crashed, _)) if *crashed == tab) {
+            ui.label(format!(
+                "{} is paused after an internal error.",
+                tab.label()
+            ));
+        } else if let Err(report) = crate::panic_guard::catch(|| self.tab_ui(ui)) {
+            log::error!("{} tab panicked: {}", tab.label(), report.message);
+            self.tab_crash = Some((tab, report));
+        }
+        self.view_exporter.set_tab_rect(egui::Rect::from_x_y_ranges(
+            ui.max_rect().x_range(),
+            tab_top..=ui.min_rect().bottom(),
+        ));
+        let tab_rect =
+            egui::Rect::from_x_y_ranges(ui.max_rect().x_range(), tab_top..=ui.max_rect().bottom());
+        self.draw_busy_overlay(ui.ctx(), tab_rect);
+    }
+
+    /// Blocks the active tab while it runs an operation its controls would conflict with.
+    fn draw_busy_overlay(&mut self, ctx: &egui::Context, rect: egui::Rect) {
+        match self.active_tab {
+            AppTab::RobotTab => {
+                if let Some(busy) = self.robot_tab.busy() {
+                    let mut rect = rect;
+                    rect.min.y = rect.min.y.max(self.robot_tab.controls_bottom());
+                    if crate::busy_overlay::draw(ctx, rect, &busy) {
+                        self.robot_tab.stop(&self.handle, &self.connection);
+                    }
+                }
+            }
+            AppTab::Transforms => {
+                if let Some(busy) = self.transforms_tab.busy() {
+                    crate::busy_overlay::draw(ctx, rect, &busy);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn tab_ui(&mut self, ui: &mut egui::Ui) {
         // Match on the active tab and call the `ui` method for that specific tab,
         // passing in any shared state it needs (like the handle and connection).
         match self.active_tab {
+            AppTab::Resources => {
+                let action = self.resources_tab.ui(ui, &self.handle, &self.connection);
+                match action {
+                    Some(crate::resources::ResourceAction::OpenRobotController(robot_id)) => {
+                        self.robot_tab.set_robot_id(&robot_id);
+                        self.active_tab = AppTab::RobotTab;
+                    }
+                    Some(crate::resources::ResourceAction::OpenPathEditor(robot_id)) => {
+                        self.path_tab.set_robot_id(&robot_id);
+                        self.active_tab = AppTab::Path;
+                    }
+                    // Handled inside the tab
+                    Some(_) | None => (),
+                }
+            }
             AppTab::RobotTab => {
                 self.robot_tab.ui(ui, &self.handle, &self.connection);
+                self.robot_tab.draw_command_feedback(ui, &self.state_poller);
+                self.robot_tab.draw_joint_drift(
+                    ui,
+                    &self.state_poller,
+                    self.settings_tab.settings().joint_drift_threshold,
+                );
+                self.robot_tab.draw_vibration(
+                    ui,
+                    &self.state_poller,
+                    self.settings_tab.settings().vibration_threshold,
+                );
+                self.robot_tab
+                    .draw_gripper(ui, &self.handle, &self.connection, &self.state_poller);
+                self.robot_tab
+                    .draw_written_variables(ui, &self.state_poller);
+                self.robot_tab.draw_speed_monitor(
+                    ui,
+                    &self.handle,
+                    &self.connection,
+                    &self.state_poller,
+                    self.speed_override.scaling(),
+                );
             }
             AppTab::Transforms => {
-                self.transforms_tab.ui(ui);
+                let action =
+                    self.transforms_tab
+                        .ui(ui, &self.handle, &self.connection, &self.exporters);
+                if let Some(crate::transforms::TransformsAction::OpenInPathEditor(waypoints)) =
+                    action
+                {
+                    self.path_tab.load_waypoints(waypoints);
+                    self.active_tab = AppTab::Path;
+                }
             }
             AppTab::Lookup => {
-                self.lookup_tab.ui(ui, &self.handle, &self.connection);
+                self.lookup_tab
+                    .ui(ui, &self.handle, &self.connection, &self.exporters);
+            }
+            AppTab::Path => {
+                self.path_tab.ui(ui, &self.handle, &self.connection);
+            }
+            AppTab::Goal => {
+                self.goal_tab.ui(ui, &self.handle, &self.connection);
+            }
+            AppTab::Statistics => {
+                self.statistics_tab.ui(
+                    ui,
+                    &mut self.utilization,
+                    &self.settings_tab.settings().report_dir,
+                );
+            }
+            AppTab::Script => {
+                self.script_tab.ui(ui, &self.handle, &self.connection);
             }
 
             AppTab::AnotherTab => {
-                self.another_tab.ui(ui);
+                let goal_variable = self.goal_tab.goal_variable();
+                self.another_tab
+                    .ui(ui, &self.handle, &self.connection, goal_variable);
+            }
+            AppTab::InputDevices => {
+                let presets = self.robot_tab.preset_names();
+                self.input_devices.ui(ui, &presets);
+            }
+            AppTab::Custom => {
+                self.custom_tab
+                    .ui(ui, &self.handle, &self.connection, &self.state_poller);
+            }
+            AppTab::Settings => {
+                self.settings_tab.ui(ui);
             }
         }
     }
@@ -0,0 +1,198 @@
+//! Teaching a series of poses: every click stores the current TCP pose as a
+//! frame under the baseframe, named from a template with a counter, e.g.
+//! `tray1_slot_{n:02}` gives `tray1_slot_01`, `tray1_slot_02`, ...
+
+use crate::backend;
+use crate::provenance::Method;
+use crate::storage;
+use crate::workers;
+use eframe::egui;
+use micro_sp::*;
+use poll_promise::Promise;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+pub const TEACH_FILE: &str = "teach_series.json";
+
+pub fn check_file() -> Option<Result<(), String>> {
+    storage::check_json::<TeachConfig>(TEACH_FILE)
+}
+
+/// Kept between sessions so a series can be continued after a restart.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct TeachConfig {
+    template: String,
+    /// Counter the next taught frame gets.
+    next: u32,
+}
+
+impl Default for TeachConfig {
+    fn default() -> Self {
+        Self {
+            template: "taught_{n:02}".to_string(),
+            next: 1,
+        }
+    }
+}
+
+/// The name `template` gives for `n`. The template has one `{n}`, or
+/// `{n:0W}` to zero-pad the counter to W digits.
+pub fn render(template: &str, n: u32) -> Result<String, String> {
+    let start = template
+        .find("{n")
+        .ok_or("The template needs a {n} for the counter")?;
+    let end = start
+        + template[start..]
+            .find('}')
+            .ok_or("The {n} of the template isn't closed")?;
+    let counter = match &template[start + 2..end] {
+        "" => n.to_string(),
+        format => {
+            let width = format
+                .strip_prefix(":0")
+                .and_then(|w| w.parse::<usize>().ok())
+                .filter(|w| *w <= 9)
+                .ok_or_else(|| format!("Unknown counter format {{n{}}}, use {{n:02}}", format))?;
+            format!("{:0width$}", n, width = width)
+        }
+    };
+    let rest = &template[end + 1..];
+    if rest.contains("{n") {
+        return Err("The template can only have one {n}".to_string());
+    }
+    let name = format!("{}{}{}", &template[..start], counter, rest);
+    if name.chars().any(|c| c.is_whitespace() || "{}".contains(c)) {
+        return Err(format!("{} isn't a valid frame name", name));
+    }
+    Ok(name)
+}
+
+/// Stores the pose of `tcp` in `baseframe` as the frame `name`.
+async fn teach_pose(
+    con: Arc<ConnectionManager>,
+    baseframe: String,
+    tcp: String,
+    name: String,
+) -> Result<String, String> {
+    let pose = backend::lookup(con.clone(), &baseframe, &tcp).await?;
+    backend::publish_frame(con, &baseframe, &name, &pose, Method::Taught).await?;
+    Ok(format!("Taught {} in {}", name, baseframe))
+}
+
+pub struct TeachSeries {
+    config: TeachConfig,
+    promise: Option<Promise<Result<String, String>>>,
+    status: Option<Result<String, String>>,
+}
+
+impl TeachSeries {
+    pub fn new() -> Self {
+        Self {
+            config: storage::load_json(TEACH_FILE).unwrap_or_default(),
+            promise: None,
+            status: None,
+        }
+    }
+
+    fn save(&self) {
+        if let Err(e) = storage::save_json(TEACH_FILE, &self.config) {
+            log::error!("Failed to save the teach series: {}", e);
+        }
+    }
+
+    /// `frames` are the baseframe and the TCP from the Robot tab.
+    pub fn draw(
+        &mut self,
+        ui: &mut egui::Ui,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+        frames: Result<(String, String), String>,
+        transform_keys: &[String],
+    ) {
+        if let Some(promise) = self.promise.take() {
+            match promise.try_take() {
+                Ok(result) => {
+                    if result.is_ok() {
+                        self.config.next += 1;
+                        self.save();
+                    }
+                    self.status = Some(result);
+                }
+                Err(promise) => self.promise = Some(promise),
+            }
+        }
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            ui.label("Name template:");
+            changed |= ui
+                .add(
+                    egui::TextEdit::singleline(&mut self.config.template)
+                        .font(egui::TextStyle::Monospace)
+                        .desired_width(180.0),
+                )
+                .on_hover_text("{n} is the counter, {n:02} pads it to two digits.")
+                .changed();
+            ui.label("Next n:");
+            changed |= ui
+                .add(egui::DragValue::new(&mut self.config.next).range(0..=99999))
+                .changed();
+            if ui.button("Reset").clicked() {
+                self.config.next = 1;
+                changed = true;
+            }
+        });
+        if changed {
+            self.save();
+        }
+
+        let name = render(&self.config.template, self.config.next);
+        let busy = self.promise.is_some();
+        ui.horizontal(|ui| match &name {
+            Ok(name) => {
+                ui.label("Next frame:");
+                ui.monospace(name);
+                if transform_keys.contains(name) {
+                    ui.colored_label(egui::Color32::YELLOW, "exists, teaching overwrites it");
+                }
+            }
+            Err(e) => {
+                ui.colored_label(egui::Color32::RED, e);
+            }
+        });
+        ui.horizontal(|ui| {
+            let button = ui
+                .add_enabled(
+                    !busy && name.is_ok() && frames.is_ok(),
+                    egui::Button::new("Teach Current Pose"),
+                )
+                .on_hover_text("Store the TCP pose as a frame under the baseframe.")
+                .on_disabled_hover_text(match &frames {
+                    Err(e) => e.clone(),
+                    Ok(_) => "Fix the template or wait for the last pose.".to_string(),
+                });
+            if button.clicked() {
+                if let (Ok(name), Ok((baseframe, tcp))) = (name, frames) {
+                    self.status = None;
+                    let handle = handle.clone();
+                    let con_clone = connection.clone();
+                    self.promise = Some(workers::spawn("teach_pose", move || {
+                        handle.block_on(teach_pose(con_clone, baseframe, tcp, name))
+                    }));
+                }
+            }
+            if busy {
+                ui.spinner();
+            }
+            match &self.status {
+                None => (),
+                Some(Ok(message)) => {
+                    ui.colored_label(egui::Color32::GREEN, message);
+                }
+                Some(Err(e)) => {
+                    ui.colored_label(egui::Color32::RED, e);
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,227 @@
+//! Recently changed transforms. All transforms are re-read in the background
+//! and compared with the last read, so frames moved by vision or calibration
+//! show up without fetching the list by hand.
+
+use crate::locale;
+use crate::measure::Measurement;
+use crate::pose::Pose;
+use crate::storage;
+use crate::workers;
+use eframe::egui;
+use micro_sp::*;
+use poll_promise::Promise;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Smaller moves are taken as noise of republishing the same pose.
+const MIN_DISTANCE: f64 = 1e-6;
+const MIN_ANGLE_DEG: f64 = 1e-4;
+
+async fn get_all_transforms(
+    con: Arc<ConnectionManager>,
+) -> Result<HashMap<String, SPTransformStamped>, String> {
+    let mut connection = con.get_connection().await;
+    TransformsManager::get_all_transforms(&mut connection)
+        .await
+        .map_err(|e| format!("Failed to get the transforms: {}", e))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    /// Distance in meters and angle in degrees from the previous pose.
+    Moved {
+        distance: f64,
+        angle: f64,
+    },
+    Reparented {
+        from: String,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct TransformChange {
+    pub frame: String,
+    pub kind: ChangeKind,
+    /// Seconds since the unix epoch, when the change was noticed.
+    pub time: u64,
+}
+
+fn diff(
+    old: &HashMap<String, SPTransformStamped>,
+    new: &HashMap<String, SPTransformStamped>,
+    time: u64,
+) -> Vec<TransformChange> {
+    let mut changes = Vec::new();
+    for (name, tf) in new {
+        let kind = match old.get(name) {
+            None => ChangeKind::Added,
+            Some(previous) if previous.parent_frame_id != tf.parent_frame_id => {
+                ChangeKind::Reparented {
+                    from: previous.parent_frame_id.clone(),
+                }
+            }
+            Some(previous) => {
+                let before = Pose::from_sp_transform(&previous.transform);
+                let after = Pose::from_sp_transform(&tf.transform);
+                let moved = Measurement::new(&before.inverse().compose(&after));
+                if moved.distance < MIN_DISTANCE && moved.angle < MIN_ANGLE_DEG {
+                    continue;
+                }
+                ChangeKind::Moved {
+                    distance: moved.distance,
+                    angle: moved.angle,
+                }
+            }
+        };
+        changes.push(TransformChange {
+            frame: name.clone(),
+            kind,
+            time,
+        });
+    }
+    for name in old.keys().filter(|name| !new.contains_key(*name)) {
+        changes.push(TransformChange {
+            frame: name.clone(),
+            kind: ChangeKind::Removed,
+            time,
+        });
+    }
+    changes.sort_by(|a, b| a.frame.cmp(&b.frame));
+    changes
+}
+
+pub struct TransformFeed {
+    pub enabled: bool,
+    /// How many changes are kept.
+    capacity: usize,
+    /// Newest first.
+    changes: VecDeque<TransformChange>,
+    /// Last read, `None` until the first one so the initial scene isn't
+    /// reported as added.
+    last: Option<HashMap<String, SPTransformStamped>>,
+    promise: Option<Promise<Result<HashMap<String, SPTransformStamped>, String>>>,
+    last_poll: Option<Instant>,
+    error: Option<String>,
+}
+
+impl TransformFeed {
+    pub fn new() -> Self {
+        Self {
+            enabled: true,
+            capacity: 20,
+            changes: VecDeque::new(),
+            last: None,
+            promise: None,
+            last_poll: None,
+            error: None,
+        }
+    }
+
+    /// Call once per frame, whatever tab is shown.
+    pub fn update(&mut self, handle: &tokio::runtime::Handle, connection: &Arc<ConnectionManager>) {
+        if let Some(promise) = self.promise.take() {
+            match promise.try_take() {
+                Ok(Ok(transforms)) => {
+                    self.error = None;
+                    if let Some(last) = &self.last {
+                        for change in diff(last, &transforms, storage::now_secs()) {
+                            self.changes.push_front(change);
+                        }
+                        self.changes.truncate(self.capacity);
+                    }
+                    self.last = Some(transforms);
+                }
+                Ok(Err(e)) => self.error = Some(e),
+                Err(promise) => {
+                    self.promise = Some(promise);
+                    return;
+                }
+            }
+        }
+
+        let due = self
+            .last_poll
+            .is_none_or(|last| last.elapsed() >= POLL_INTERVAL);
+        if !self.enabled || !due {
+            return;
+        }
+        self.last_poll = Some(Instant::now());
+        let handle = handle.clone();
+        let con_clone = connection.clone();
+        self.promise = Some(workers::spawn("transform_feed", move || {
+            handle.block_on(get_all_transforms(con_clone))
+        }));
+    }
+
+    /// The feed, `focus` is set to the frame clicked.
+    pub fn ui(&mut self, ui: &mut egui::Ui, focus: &mut Option<String>) {
+        ui.horizontal(|ui| {
+            if ui
+                .checkbox(&mut self.enabled, "Watch for changes")
+                .changed()
+                && !self.enabled
+            {
+                // Changes while paused would show as one jump
+                self.last = None;
+            }
+            ui.label("Keep last:");
+            if ui
+                .add(egui::DragValue::new(&mut self.capacity).range(1..=500))
+                .changed()
+            {
+                self.changes.truncate(self.capacity);
+            }
+            if ui.button("Clear").clicked() {
+                self.changes.clear();
+            }
+        });
+        if let Some(e) = &self.error {
+            ui.colored_label(egui::Color32::RED, e);
+        }
+        if self.changes.is_empty() {
+            ui.weak("No changes seen yet.");
+            return;
+        }
+        let now = storage::now_secs();
+        egui::Grid::new("transform_feed_grid")
+            .num_columns(3)
+            .spacing([20.0, 2.0])
+            .striped(true)
+            .show(ui, |ui| {
+                ui.strong("Frame");
+                ui.strong("Change");
+                ui.strong("When");
+                ui.end_row();
+                for change in &self.changes {
+                    if ui.link(&change.frame).clicked() {
+                        *focus = Some(change.frame.clone());
+                    }
+                    match &change.kind {
+                        ChangeKind::Added => ui.colored_label(egui::Color32::GREEN, "added"),
+                        ChangeKind::Removed => ui.colored_label(egui::Color32::RED, "removed"),
+                        ChangeKind::Moved { distance, angle } => ui.monospace(format!(
+                            "{} mm, {}°",
+                            locale::format_number(distance * 1000.0, 2),
+                            locale::format_number(*angle, 2)
+                        )),
+                        ChangeKind::Reparented { from } => {
+                            ui.label(format!("moved from parent {}", from))
+                        }
+                    };
+                    ui.label(format!(
+                        "{} ({} s ago)",
+                        storage::format_timestamp(change.time),
+                        now.saturating_sub(change.time)
+                    ));
+                    ui.end_row();
+                }
+            });
+    }
+}
@@ -1,29 +1,2169 @@
+use crate::busy_overlay::Busy;
+use crate::exporters::{self, ExporterRegistry};
+use crate::expression::Unit;
+use crate::history::{self, FrameUsage};
+use crate::inspection::InspectionGrid;
+use crate::locale::{self, LocalizedDragValue};
+use crate::lookup::JsonOutputWithMetadata;
+use crate::measure::MeasureWindow;
+use crate::offline_scene::{self, ChangeKind, OfflineScene, SceneChange};
+use crate::pose::{self, FrameMetadata, Pose};
+use crate::pose_filter::PoseFilterWindow;
+use crate::provenance::{self, Method};
+use crate::rotation_editor::{self, AxisAngle};
+use crate::scene_lint::{self, LintConfig, LintIssue, LintSeverity};
+use crate::scene_repo::SceneVersionsWindow;
+use crate::schema;
+use crate::transform_feed::TransformFeed;
+use crate::workers;
 use eframe::egui;
+use micro_sp::{ConnectionManager, SPTransformStamped, SPValue, ToSPValue, TransformsManager};
+use poll_promise::Promise;
+use rfd::FileDialog;
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::Arc,
+};
+
+async fn get_all_transforms(con: Arc<ConnectionManager>) -> HashMap<String, SPTransformStamped> {
+    let mut connection = con.get_connection().await;
+    match TransformsManager::get_all_transforms(&mut connection).await {
+        Ok(tfs) => tfs,
+        Err(e) => {
+            log::error!("GUI Failed to get all transforms with: {e}!");
+            HashMap::new()
+        }
+    }
+}
+
+/// Like `get_all_transforms`, but a failure is an error instead of an empty
+/// scene, which would make every offline frame look new.
+async fn fetch_live_scene(
+    con: Arc<ConnectionManager>,
+) -> Result<HashMap<String, SPTransformStamped>, String> {
+    let mut connection = con.get_connection().await;
+    TransformsManager::get_all_transforms(&mut connection)
+        .await
+        .map_err(|e| format!("Failed to get the live transforms: {}", e))
+}
+
+pub async fn publish_transform(
+    con: Arc<ConnectionManager>,
+    transform: SPTransformStamped,
+) -> Result<String, String> {
+    crate::recorder::record_publish_frame(&transform);
+    let mut connection = con.get_connection().await;
+    match TransformsManager::insert_transform(&mut connection, &transform).await {
+        Ok(()) => Ok(format!("Published frame '{}'", transform.child_frame_id)),
+        Err(e) => {
+            log::error!("GUI Failed to insert transform with: {e}!");
+            Err(format!("GUI Failed to insert transform with: {e}"))
+        }
+    }
+}
+
+async fn lookup_transform(
+    con: Arc<ConnectionManager>,
+    parent: &str,
+    child: &str,
+) -> Result<SPTransformStamped, String> {
+    let mut connection = con.get_connection().await;
+    match TransformsManager::lookup_transform(&mut connection, parent, child).await {
+        Ok(tfs) => Ok(tfs),
+        Err(e) => {
+            log::error!("GUI Failed to lookup transform with: {e}!");
+            Err(format!("GUI Failed to lookup transform with: {e}"))
+        }
+    }
+}
+
+/// Looks up `source` relative to the new parent, applies the offset and
+/// publishes the result under the new name.
+async fn duplicate_transform(
+    con: Arc<ConnectionManager>,
+    source: SPTransformStamped,
+    parent: String,
+    name: String,
+    delta: Pose,
+    axes: OffsetAxes,
+) -> Result<String, String> {
+    let relative = if parent == source.parent_frame_id {
+        source.transform.clone()
+    } else {
+        lookup_transform(con.clone(), &parent, &source.child_frame_id)
+            .await?
+            .transform
+    };
+    let base = Pose::from_sp_transform(&relative);
+    let pose = match axes {
+        OffsetAxes::Frame => base.compose(&delta),
+        OffsetAxes::Parent => base.offset_in_parent(&delta),
+    };
+    let metadata = FrameMetadata {
+        tcp_id: name.clone(),
+        enable_transform: source.enable_transform,
+        active_transform: source.active_transform,
+    };
+    let mut transform = pose::make_transform_stamped(&parent, &name, &pose, &metadata);
+    provenance::stamp(&mut transform, Method::Computed);
+    publish_transform(con, transform).await
+}
+
+async fn copy_pose_part(
+    con: Arc<ConnectionManager>,
+    target: SPTransformStamped,
+    source: String,
+    part: PosePart,
+) -> Result<String, String> {
+    let source_pose = Pose::from_sp_transform(
+        &lookup_transform(con.clone(), &target.parent_frame_id, &source)
+            .await?
+            .transform,
+    );
+    let transform = part.copied_onto(&target, &source_pose);
+    publish_transform(con, transform).await
+}
+
+async fn publish_transforms(
+    con: Arc<ConnectionManager>,
+    transforms: Vec<SPTransformStamped>,
+) -> Result<String, String> {
+    let names = transforms
+        .iter()
+        .map(|t| t.child_frame_id.clone())
+        .collect::<Vec<String>>();
+    for transform in transforms {
+        publish_transform(con.clone(), transform).await?;
+    }
+    Ok(format!("Published frames {}", names.join(", ")))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RotationInput {
+    Rpy,
+    Quaternion,
+    AxisAngle,
+}
+
+/// Which axes a duplicate offset is expressed in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum OffsetAxes {
+    /// The source frame's own axes (e.g. "-0.1 m along tool z").
+    Frame,
+    /// The parent frame's axes (e.g. "+0.1 m up in world").
+    Parent,
+}
+
+/// State of the "Duplicate frame…" dialog.
+struct DuplicateFrameDialog {
+    source: SPTransformStamped,
+    name: String,
+    parent: Option<String>,
+    offset_translation: [f64; 3],
+    offset_rpy: [f64; 3],
+    offset_axes: OffsetAxes,
+}
+
+impl DuplicateFrameDialog {
+    fn new(source: SPTransformStamped) -> Self {
+        Self {
+            name: format!("{}_copy", source.child_frame_id),
+            parent: Some(source.parent_frame_id.clone()),
+            source,
+            offset_translation: [0.0; 3],
+            offset_rpy: [0.0; 3],
+            offset_axes: OffsetAxes::Frame,
+        }
+    }
+
+    fn delta(&self) -> Pose {
+        Pose {
+            translation: self.offset_translation,
+            rotation: pose::rpy_to_quaternion(self.offset_rpy),
+        }
+    }
+
+    /// Returns the reason the copy cannot be published, if any.
+    fn validate(&self, existing: &[String]) -> Option<String> {
+        validate_new_frame(&self.name, &self.parent, existing)
+    }
+}
+
+/// Half of a pose that "Copy Pose From…" takes over from the source frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PosePart {
+    Rotation,
+    Translation,
+}
+
+impl PosePart {
+    /// `target` with this part replaced by `source`, given in the target's parent.
+    fn copied_onto(&self, target: &SPTransformStamped, source: &Pose) -> SPTransformStamped {
+        let current = Pose::from_sp_transform(&target.transform);
+        let pose = match self {
+            PosePart::Rotation => Pose {
+                rotation: source.rotation,
+                ..current
+            },
+            PosePart::Translation => Pose {
+                translation: source.translation,
+                ..current
+            },
+        };
+        let mut transform = target.clone();
+        transform.transform = pose.to_sp_transform();
+        provenance::stamp(&mut transform, Method::Computed);
+        transform
+    }
+}
+
+/// State of the "Copy Pose From…" dialog.
+struct CopyPoseDialog {
+    target: SPTransformStamped,
+    source: Option<String>,
+    part: PosePart,
+}
+
+impl CopyPoseDialog {
+    fn validate(&self) -> Option<String> {
+        match &self.source {
+            None => Some("Select the frame to copy from".to_string()),
+            Some(source) if *source == self.target.child_frame_id => {
+                Some("Select another frame than the target".to_string())
+            }
+            Some(_) => None,
+        }
+    }
+}
+
+/// Axis of the goal frame along which the tool approaches it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ApproachAxis {
+    PlusX,
+    MinusX,
+    PlusY,
+    MinusY,
+    PlusZ,
+    MinusZ,
+}
+
+impl std::fmt::Display for ApproachAxis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ApproachAxis::PlusX => write!(f, "+X"),
+            ApproachAxis::MinusX => write!(f, "-X"),
+            ApproachAxis::PlusY => write!(f, "+Y"),
+            ApproachAxis::MinusY => write!(f, "-Y"),
+            ApproachAxis::PlusZ => write!(f, "+Z"),
+            ApproachAxis::MinusZ => write!(f, "-Z"),
+        }
+    }
+}
+
+impl ApproachAxis {
+    fn variants() -> &'static [ApproachAxis] {
+        &[
+            ApproachAxis::PlusZ,
+            ApproachAxis::MinusZ,
+            ApproachAxis::PlusX,
+            ApproachAxis::MinusX,
+            ApproachAxis::PlusY,
+            ApproachAxis::MinusY,
+        ]
+    }
+
+    fn unit_vector(&self) -> [f64; 3] {
+        match self {
+            ApproachAxis::PlusX => [1.0, 0.0, 0.0],
+            ApproachAxis::MinusX => [-1.0, 0.0, 0.0],
+            ApproachAxis::PlusY => [0.0, 1.0, 0.0],
+            ApproachAxis::MinusY => [0.0, -1.0, 0.0],
+            ApproachAxis::PlusZ => [0.0, 0.0, 1.0],
+            ApproachAxis::MinusZ => [0.0, 0.0, -1.0],
+        }
+    }
+}
+
+/// State of the "Approach/Retreat…" dialog.
+struct ApproachRetreatDialog {
+    goal: SPTransformStamped,
+    axis: ApproachAxis,
+    approach_distance: f64,
+    generate_retreat: bool,
+    retreat_distance: f64,
+}
+
+impl ApproachRetreatDialog {
+    fn new(goal: SPTransformStamped) -> Self {
+        Self {
+            goal,
+            axis: ApproachAxis::PlusZ,
+            approach_distance: 0.1,
+            generate_retreat: true,
+            retreat_distance: 0.1,
+        }
+    }
+
+    fn approach_name(&self) -> String {
+        format!("{}_approach", self.goal.child_frame_id)
+    }
+
+    fn retreat_name(&self) -> String {
+        format!("{}_retreat", self.goal.child_frame_id)
+    }
+
+    /// The goal backed off by `distance` against the approach direction,
+    /// expressed in the goal's own axes and published under the goal's parent.
+    fn backed_off(&self, name: &str, distance: f64) -> SPTransformStamped {
+        let axis = self.axis.unit_vector();
+        let delta = Pose {
+            translation: [
+                -axis[0] * distance,
+                -axis[1] * distance,
+                -axis[2] * distance,
+            ],
+            rotation: [0.0, 0.0, 0.0, 1.0],
+        };
+        let pose = Pose::from_sp_transform(&self.goal.transform).compose(&delta);
+        let metadata = FrameMetadata {
+            tcp_id: name.to_string(),
+            enable_transform: self.goal.enable_transform,
+            active_transform: self.goal.active_transform,
+        };
+        let mut transform =
+            pose::make_transform_stamped(&self.goal.parent_frame_id, name, &pose, &metadata);
+        provenance::stamp(&mut transform, Method::Computed);
+        transform
+    }
+
+    fn to_transforms(&self) -> Vec<SPTransformStamped> {
+        let mut transforms = vec![self.backed_off(&self.approach_name(), self.approach_distance)];
+        if self.generate_retreat {
+            transforms.push(self.backed_off(&self.retreat_name(), self.retreat_distance));
+        }
+        transforms
+    }
+}
+
+/// State of the "Inspection Grid…" dialog.
+struct InspectionGridDialog {
+    surface: SPTransformStamped,
+    grid: InspectionGrid,
+    open_in_path_editor: bool,
+}
+
+impl InspectionGridDialog {
+    fn new(surface: SPTransformStamped) -> Self {
+        Self {
+            surface,
+            grid: InspectionGrid::default(),
+            open_in_path_editor: true,
+        }
+    }
+
+    fn to_transforms(&self) -> Vec<SPTransformStamped> {
+        self.grid
+            .poses()
+            .into_iter()
+            .map(|(name, pose)| {
+                let mut transform = pose::make_transform_stamped(
+                    &self.surface.child_frame_id,
+                    &name,
+                    &pose,
+                    &FrameMetadata::for_frame(&name),
+                );
+                provenance::stamp(&mut transform, Method::Computed);
+                transform
+            })
+            .collect()
+    }
+}
+
+/// Requests to other tabs, handled by the app.
+pub enum TransformsAction {
+    /// Frames to load as the waypoints of the path editor.
+    OpenInPathEditor(Vec<String>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MetadataValueType {
+    Bool,
+    String,
+    Float,
+}
+
+/// State of the "Bulk Edit Metadata…" dialog.
+struct BulkEditDialog {
+    frames: Vec<String>,
+    key: String,
+    value_type: MetadataValueType,
+    bool_value: bool,
+    text_value: String,
+    float_value: f64,
+}
+
+impl BulkEditDialog {
+    fn new(frames: Vec<String>) -> Self {
+        Self {
+            frames,
+            key: "enable_transform".to_string(),
+            value_type: MetadataValueType::Bool,
+            bool_value: false,
+            text_value: String::new(),
+            float_value: 0.0,
+        }
+    }
+
+    fn value(&self) -> SPValue {
+        match self.value_type {
+            MetadataValueType::Bool => self.bool_value.to_spvalue(),
+            MetadataValueType::String => self.text_value.as_str().to_spvalue(),
+            MetadataValueType::Float => self.float_value.to_spvalue(),
+        }
+    }
+
+    /// The selected frames with the field set, ready to be published.
+    fn to_transforms(
+        &self,
+        transforms: &HashMap<String, SPTransformStamped>,
+    ) -> Vec<SPTransformStamped> {
+        let value = self.value();
+        self.frames
+            .iter()
+            .filter_map(|name| transforms.get(name))
+            .map(|tf| {
+                let mut tf = tf.clone();
+                pose::set_metadata_field(&mut tf, self.key.trim(), value.clone());
+                tf
+            })
+            .collect()
+    }
+}
+
+/// State of the "New Frame" dialog.
+struct NewFrameDialog {
+    name: String,
+    parent: Option<String>,
+    translation: [f64; 3],
+    rotation_input: RotationInput,
+    rpy: [f64; 3],
+    quaternion: [f64; 4],
+    axis_angle: AxisAngle,
+    enable_transform: bool,
+    active_transform: bool,
+}
+
+impl NewFrameDialog {
+    fn new() -> Self {
+        Self {
+            name: String::new(),
+            parent: Some("world".to_string()),
+            translation: [0.0; 3],
+            rotation_input: RotationInput::Rpy,
+            rpy: [0.0; 3],
+            quaternion: [0.0, 0.0, 0.0, 1.0],
+            axis_angle: AxisAngle::default(),
+            enable_transform: true,
+            active_transform: false,
+        }
+    }
+
+    fn pose(&self) -> Pose {
+        let rotation = match self.rotation_input {
+            RotationInput::Rpy => pose::rpy_to_quaternion(self.rpy),
+            RotationInput::Quaternion => pose::normalize_quaternion(self.quaternion),
+            RotationInput::AxisAngle => self.axis_angle.quaternion(),
+        };
+        Pose {
+            translation: self.translation,
+            rotation,
+        }
+    }
+
+    /// Switches the rotation input, carrying the rotation entered so far over.
+    fn set_rotation_input(&mut self, input: RotationInput) {
+        if input == self.rotation_input {
+            return;
+        }
+        let rotation = self.pose().rotation;
+        match input {
+            RotationInput::Rpy => self.rpy = pose::quaternion_to_rpy(rotation),
+            RotationInput::Quaternion => self.quaternion = rotation,
+            RotationInput::AxisAngle => self.axis_angle.set_quaternion(rotation),
+        }
+        self.rotation_input = input;
+    }
+
+    /// Returns the reason the frame cannot be published, if any.
+    fn validate(&self, existing: &[String]) -> Option<String> {
+        if let Some(reason) = validate_new_frame(&self.name, &self.parent, existing) {
+            return Some(reason);
+        }
+        if self.rotation_input == RotationInput::Quaternion
+            && pose::quaternion_norm(self.quaternion) < 1e-9
+        {
+            return Some("Quaternion has zero length".to_string());
+        }
+        None
+    }
+
+    fn to_transform(&self) -> SPTransformStamped {
+        let name = self.name.trim();
+        let metadata = FrameMetadata {
+            tcp_id: name.to_string(),
+            enable_transform: self.enable_transform,
+            active_transform: self.active_transform,
+        };
+        let mut transform = pose::make_transform_stamped(
+            self.parent.as_deref().unwrap_or("world"),
+            name,
+            &self.pose(),
+            &metadata,
+        );
+        provenance::stamp(&mut transform, Method::Manual);
+        transform
+    }
+}
+
+/// Differences between the offline scene and the live store, chosen for pushing.
+struct SyncDialog {
+    changes: Vec<SceneChange>,
+    selected: BTreeSet<String>,
+}
 
-/// Holds all the state for the "Another" tab
 pub struct TransformsTab {
-    // You could add tab-specific state here
-    // counter: i32,
+    get_all_transforms_promise: Option<Promise<HashMap<String, SPTransformStamped>>>,
+    transforms: HashMap<String, SPTransformStamped>,
+    transform_keys: Vec<String>,
+    new_frame_dialog: Option<NewFrameDialog>,
+    duplicate_frame_dialog: Option<DuplicateFrameDialog>,
+    copy_pose_dialog: Option<CopyPoseDialog>,
+    approach_retreat_dialog: Option<ApproachRetreatDialog>,
+    inspection_grid_dialog: Option<InspectionGridDialog>,
+    /// Inspection sequence to hand to the path editor once its frames are published.
+    pending_sequence: Option<Vec<String>>,
+    action: Option<TransformsAction>,
+    bulk_edit_dialog: Option<BulkEditDialog>,
+    selected_frames: BTreeSet<String>,
+    lint_config: LintConfig,
+    lint_issues: Option<Vec<LintIssue>>,
+    focused_frame: Option<String>,
+    scroll_to_focused: bool,
+    publish_promise: Option<Promise<Result<String, String>>>,
+    publish_status: Option<Result<String, String>>,
+    /// Problems with the files of the last import, one line per file.
+    import_warnings: Vec<String>,
+    frame_usage: HashMap<String, FrameUsage>,
+    unused_days: u64,
+    /// Scene file being edited instead of the live store.
+    offline: Option<OfflineScene>,
+    sync_dialog: Option<SyncDialog>,
+    sync_fetch_promise: Option<Promise<Result<HashMap<String, SPTransformStamped>, String>>>,
+    sync_push_promise: Option<Promise<Result<String, String>>>,
+    scene_versions: SceneVersionsWindow,
+    measure: MeasureWindow,
+    pose_filters: PoseFilterWindow,
+    /// Frame the list's coordinate readout is relative to.
+    reference_frame: String,
+    feed: TransformFeed,
 }
 
 impl TransformsTab {
-    /// Create a new `AnotherTab` with default state
     pub fn new() -> Self {
         Self {
-            // counter: 0,
+            get_all_transforms_promise: None,
+            transforms: HashMap::new(),
+            transform_keys: Vec::new(),
+            new_frame_dialog: None,
+            duplicate_frame_dialog: None,
+            copy_pose_dialog: None,
+            approach_retreat_dialog: None,
+            inspection_grid_dialog: None,
+            pending_sequence: None,
+            action: None,
+            bulk_edit_dialog: None,
+            selected_frames: BTreeSet::new(),
+            lint_config: LintConfig::default(),
+            lint_issues: None,
+            focused_frame: None,
+            scroll_to_focused: false,
+            publish_promise: None,
+            publish_status: None,
+            import_warnings: Vec::new(),
+            frame_usage: HashMap::new(),
+            unused_days: 30,
+            offline: None,
+            sync_dialog: None,
+            sync_fetch_promise: None,
+            sync_push_promise: None,
+            scene_versions: SceneVersionsWindow::default(),
+            measure: MeasureWindow::default(),
+            pose_filters: PoseFilterWindow::new(),
+            reference_frame: scene_lint::ROOT_FRAME.to_string(),
+            feed: TransformFeed::new(),
         }
     }
-    /// Draw the UI for the "Another" tab
-    pub fn ui(&mut self, ui: &mut egui::Ui) {
-        ui.heading("This is Another Tab");
-        ui.label("You can put completely different UI elements here.");
-        ui.add_space(10.0);
-        ui.label("For example, this could be a settings page, a log viewer, or another tool.");
 
-        // Example of stateful widget
-        // if ui.button("Click me").clicked() {
-        //     self.counter += 1;
-        // }
-        // ui.label(format!("Counter: {}", self.counter));
+    /// Frames being written, which the tab's dialogs would conflict with.
+    /// A write can't be taken back halfway, so there is no cancel.
+    pub fn busy(&self) -> Option<Busy> {
+        (self.publish_promise.is_some() || self.sync_push_promise.is_some()).then(|| Busy {
+            message: "Writing frames…".to_string(),
+            cancel: None,
+        })
+    }
+
+    /// Whether a publish or sync push is still being written, checked on exit
+    /// while the tab may not be shown.
+    pub fn writing(&self) -> bool {
+        [&self.publish_promise, &self.sync_push_promise]
+            .into_iter()
+            .flatten()
+            .any(|promise| promise.ready().is_none())
     }
-}
\ No newline at end of file
+
+    /// Keeps the recent changes feed going while other tabs are shown.
+    pub fn update_background(
+        &mut self,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+    ) {
+        self.feed.update(handle, connection);
+        self.pose_filters.update(handle, connection);
+    }
+
+    pub fn ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+        exporters: &ExporterRegistry,
+    ) -> Option<TransformsAction> {
+        ui.horizontal(|ui| {
+            ui.heading("Transforms Controller");
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui
+                    .add_enabled(
+                        !self.transforms.is_empty(),
+                        egui::Button::new("Check Scene"),
+                    )
+                    .clicked()
+                {
+                    self.lint_issues =
+                        Some(scene_lint::lint_scene(&self.transforms, &self.lint_config));
+                }
+                if ui.button("New Frame").clicked() {
+                    self.new_frame_dialog = Some(NewFrameDialog::new());
+                }
+                if ui
+                    .button("Scene Versions…")
+                    .on_hover_text("Commit exported scene files and browse their history")
+                    .clicked()
+                {
+                    self.scene_versions.show();
+                }
+                if ui
+                    .add_enabled(!self.transforms.is_empty(), egui::Button::new("Measure…"))
+                    .on_hover_text("Distance and rotation between two frames, kept up to date")
+                    .clicked()
+                {
+                    self.measure.show(self.focused_frame.clone());
+                }
+                if ui
+                    .button("Pose Filters…")
+                    .on_hover_text("Smooth frames from vision and publish them as {frame}_filtered")
+                    .clicked()
+                {
+                    self.pose_filters.open = true;
+                }
+                self.draw_offline_controls(ui, handle, connection);
+                ui.add_enabled_ui(!self.transforms.is_empty(), |ui| {
+                    ui.menu_button("Export Scene…", |ui| {
+                        for exporter in exporters.scene_exporters() {
+                            if ui.button(exporter.name()).clicked() {
+                                let content = exporter.export_scene(&self.transforms);
+                                match exporters::save_with_exporter(exporter, "scene", content) {
+                                    Ok(Some(msg)) => self.publish_status = Some(Ok(msg)),
+                                    Ok(None) => (),
+                                    Err(e) => self.publish_status = Some(Err(e)),
+                                }
+                                ui.close();
+                            }
+                        }
+                    });
+                });
+                if ui
+                    .add_enabled(
+                        self.publish_promise.is_none(),
+                        egui::Button::new("Import Frames…"),
+                    )
+                    .on_hover_text("Publish frames from files exported in the Lookup tab.")
+                    .clicked()
+                {
+                    self.import_frames(handle, connection);
+                }
+                if ui
+                    .add_enabled(
+                        !self.selected_frames.is_empty(),
+                        egui::Button::new(format!(
+                            "Bulk Edit Metadata… ({})",
+                            self.selected_frames.len()
+                        )),
+                    )
+                    .clicked()
+                {
+                    let frames = self.selected_frames.iter().cloned().collect();
+                    self.bulk_edit_dialog = Some(BulkEditDialog::new(frames));
+                }
+            });
+        });
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            if let Some(scene) = &self.offline {
+                ui.colored_label(
+                    egui::Color32::from_rgb(230, 160, 40),
+                    format!(
+                        "OFFLINE: editing {}{}, changes are not live until synced",
+                        scene.name(),
+                        if scene.dirty { " (unsaved)" } else { "" }
+                    ),
+                );
+                return;
+            }
+            let is_fetching_list = self.poll_transforms_promise(ui);
+            if !is_fetching_list && ui.button("Fetch Transforms").clicked() {
+                self.spawn_transforms_promise(handle, connection);
+            }
+            if is_fetching_list {
+                ui.label("Loading data...");
+            }
+        });
+        self.poll_sync_promises(handle, connection);
+
+        self.poll_publish_promise(handle, connection);
+        match &self.publish_status {
+            Some(Ok(msg)) => {
+                ui.colored_label(egui::Color32::GREEN, msg);
+            }
+            Some(Err(e)) => {
+                ui.colored_label(egui::Color32::RED, format!("Error: {}", e));
+            }
+            None => (),
+        }
+        for warning in &self.import_warnings {
+            ui.colored_label(egui::Color32::YELLOW, warning);
+        }
+
+        ui.separator();
+        self.draw_usage_report(ui);
+        egui::CollapsingHeader::new("Recent Changes")
+            .id_salt("transform_feed_header")
+            .show(ui, |ui| {
+                let mut focus = None;
+                self.feed.ui(ui, &mut focus);
+                if focus.is_some() {
+                    self.focused_frame = focus;
+                    self.scroll_to_focused = true;
+                }
+            });
+        self.draw_transform_list(ui);
+
+        self.draw_new_frame_dialog(ui.ctx(), handle, connection);
+        self.draw_duplicate_frame_dialog(ui.ctx(), handle, connection);
+        self.draw_copy_pose_dialog(ui.ctx(), handle, connection);
+        self.draw_approach_retreat_dialog(ui.ctx(), handle, connection);
+        self.draw_inspection_grid_dialog(ui.ctx(), handle, connection);
+        self.draw_bulk_edit_dialog(ui.ctx(), handle, connection);
+        self.draw_lint_report(ui.ctx());
+        self.draw_sync_dialog(ui.ctx(), handle, connection);
+        self.scene_versions.draw(ui.ctx());
+        self.measure
+            .draw(ui.ctx(), handle, connection, &self.transform_keys);
+        self.pose_filters.draw(ui.ctx(), &self.transform_keys);
+        self.action.take()
+    }
+
+    /// Publishes frames to the live store, or puts them into the offline
+    /// scene while one is open.
+    fn publish_frames(
+        &mut self,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+        transforms: Vec<SPTransformStamped>,
+    ) {
+        self.publish_status = None;
+        if let Some(scene) = &mut self.offline {
+            let names = transforms
+                .iter()
+                .map(|t| t.child_frame_id.clone())
+                .collect::<Vec<String>>();
+            scene.insert(transforms);
+            self.publish_status = Some(Ok(format!(
+                "Changed frames {} in {}",
+                names.join(", "),
+                scene.name()
+            )));
+            self.refresh_offline_frames();
+            self.close_dialogs();
+            return;
+        }
+        let handle = handle.clone();
+        let con_clone = connection.clone();
+        self.publish_promise = Some(workers::spawn("transform_publisher", move || {
+            handle.block_on(publish_transforms(con_clone, transforms))
+        }));
+    }
+
+    /// The dialogs that publish are closed once their frames are published.
+    fn close_dialogs(&mut self) {
+        self.new_frame_dialog = None;
+        self.duplicate_frame_dialog = None;
+        self.copy_pose_dialog = None;
+        self.approach_retreat_dialog = None;
+        self.inspection_grid_dialog = None;
+        self.bulk_edit_dialog = None;
+        if let Some(sequence) = self.pending_sequence.take() {
+            self.action = Some(TransformsAction::OpenInPathEditor(sequence));
+        }
+    }
+
+    /// Shows the offline scene's frames in the list.
+    fn refresh_offline_frames(&mut self) {
+        if let Some(scene) = &self.offline {
+            let frames = scene.frames.clone();
+            self.process_transforms_result(&frames);
+        }
+    }
+
+    /// Drawn right to left in the header.
+    fn draw_offline_controls(
+        &mut self,
+        ui: &mut egui::Ui,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+    ) {
+        let Some(dirty) = self.offline.as_ref().map(|scene| scene.dirty) else {
+            if ui
+                .button("Open Scene File…")
+                .on_hover_text("Edit an exported JSON scene without the live store")
+                .clicked()
+            {
+                self.open_offline_scene();
+            }
+            return;
+        };
+        if ui
+            .button("Close Scene")
+            .on_hover_text("Back to the live frames, unsaved changes are lost")
+            .clicked()
+        {
+            self.offline = None;
+            self.sync_dialog = None;
+            self.spawn_transforms_promise(handle, connection);
+            return;
+        }
+        if ui
+            .add_enabled(
+                self.sync_fetch_promise.is_none(),
+                egui::Button::new("Sync with Live…"),
+            )
+            .on_hover_text("Compare with the live store and push selected changes")
+            .clicked()
+        {
+            self.spawn_sync_fetch(handle, connection);
+        }
+        if ui
+            .add_enabled(dirty, egui::Button::new("Save Scene"))
+            .clicked()
+        {
+            if let Some(scene) = &mut self.offline {
+                self.publish_status = Some(scene.save());
+            }
+        }
+    }
+
+    fn open_offline_scene(&mut self) {
+        let mut dialog = FileDialog::new();
+        if let Some(dir) = crate::scene_repo::scene_dir() {
+            dialog = dialog.set_directory(dir);
+        }
+        let Some(path) = dialog.add_filter("JSON", &["json"]).pick_file() else {
+            return;
+        };
+        match OfflineScene::open(path) {
+            Ok((scene, warning)) => {
+                self.import_warnings = warning.into_iter().collect();
+                self.publish_status = Some(Ok(format!(
+                    "Opened {} with {} frames",
+                    scene.name(),
+                    scene.frames.len()
+                )));
+                self.get_all_transforms_promise = None;
+                self.offline = Some(scene);
+                self.refresh_offline_frames();
+            }
+            Err(e) => self.publish_status = Some(Err(e)),
+        }
+    }
+
+    fn spawn_sync_fetch(
+        &mut self,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+    ) {
+        let handle = handle.clone();
+        let con_clone = connection.clone();
+        self.sync_fetch_promise = Some(workers::spawn("scene_sync_fetch", move || {
+            handle.block_on(fetch_live_scene(con_clone))
+        }));
+    }
+
+    fn poll_sync_promises(
+        &mut self,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+    ) {
+        if let Some(promise) = self.sync_fetch_promise.take() {
+            match promise.try_take() {
+                Ok(Ok(live)) => {
+                    if let Some(scene) = &self.offline {
+                        let changes = offline_scene::diff(&scene.frames, &live);
+                        // Keep the operator's choice when the diff is refreshed after a push
+                        let previous = self.sync_dialog.take().map(|d| d.selected);
+                        let selected = changes
+                            .iter()
+                            .filter(|c| c.kind != ChangeKind::LiveOnly)
+                            .filter(|c| previous.as_ref().is_none_or(|p| p.contains(&c.name)))
+                            .map(|c| c.name.clone())
+                            .collect();
+                        self.sync_dialog = Some(SyncDialog { changes, selected });
+                    }
+                }
+                Ok(Err(e)) => self.publish_status = Some(Err(e)),
+                Err(promise) => self.sync_fetch_promise = Some(promise),
+            }
+        }
+        if let Some(promise) = self.sync_push_promise.take() {
+            match promise.try_take() {
+                Ok(result) => {
+                    self.publish_status = Some(result);
+                    self.spawn_sync_fetch(handle, connection);
+                }
+                Err(promise) => self.sync_push_promise = Some(promise),
+            }
+        }
+    }
+
+    fn draw_sync_dialog(
+        &mut self,
+        ctx: &egui::Context,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+    ) {
+        let Some(dialog) = &mut self.sync_dialog else {
+            return;
+        };
+        let mut open = true;
+        let mut push = false;
+        let busy = self.sync_push_promise.is_some() || self.sync_fetch_promise.is_some();
+        egui::Window::new("Sync with Live")
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                if dialog.changes.is_empty() {
+                    ui.colored_label(
+                        egui::Color32::GREEN,
+                        "The scene file matches the live store.",
+                    );
+                    return;
+                }
+                egui::ScrollArea::vertical()
+                    .max_height(400.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("scene_sync_grid")
+                            .num_columns(4)
+                            .striped(true)
+                            .show(ui, |ui| {
+                                ui.label("");
+                                ui.strong("Frame");
+                                ui.strong("Change");
+                                ui.strong("Details");
+                                ui.end_row();
+                                for change in &dialog.changes {
+                                    let mut selected = dialog.selected.contains(&change.name);
+                                    let pushable = change.kind != ChangeKind::LiveOnly;
+                                    if ui
+                                        .add_enabled(
+                                            pushable,
+                                            egui::Checkbox::new(&mut selected, ""),
+                                        )
+                                        .changed()
+                                    {
+                                        if selected {
+                                            dialog.selected.insert(change.name.clone());
+                                        } else {
+                                            dialog.selected.remove(&change.name);
+                                        }
+                                    }
+                                    ui.label(&change.name);
+                                    match change.kind {
+                                        ChangeKind::Added => {
+                                            ui.colored_label(egui::Color32::GREEN, "added")
+                                        }
+                                        ChangeKind::Modified => {
+                                            ui.colored_label(egui::Color32::YELLOW, "modified")
+                                        }
+                                        ChangeKind::LiveOnly => ui.weak("live only"),
+                                    };
+                                    ui.label(&change.summary);
+                                    ui.end_row();
+                                }
+                            });
+                    });
+                ui.weak("Frames only in the live store are never removed.");
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(
+                            !dialog.selected.is_empty() && !busy,
+                            egui::Button::new(format!("Push Selected ({})", dialog.selected.len())),
+                        )
+                        .clicked()
+                    {
+                        push = true;
+                    }
+                    if busy {
+                        ui.spinner();
+                    }
+                });
+            });
+        if push {
+            let transforms = dialog
+                .changes
+                .iter()
+                .filter(|c| dialog.selected.contains(&c.name))
+                .filter_map(|c| c.local.clone())
+                .collect::<Vec<SPTransformStamped>>();
+            let handle = handle.clone();
+            let con_clone = connection.clone();
+            self.sync_push_promise = Some(workers::spawn("scene_sync_push", move || {
+                handle.block_on(publish_transforms(con_clone, transforms))
+            }));
+        }
+        if !open {
+            self.sync_dialog = None;
+        }
+    }
+
+    /// Scene check results; clicking an issue scrolls to and highlights its frame.
+    fn draw_lint_report(&mut self, ctx: &egui::Context) {
+        let Some(issues) = &self.lint_issues else {
+            return;
+        };
+
+        let mut open = true;
+        let mut rerun = false;
+        let mut focus = None;
+        egui::Window::new("Scene Check")
+            .open(&mut open)
+            .resizable(true)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Max translation:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.lint_config.max_translation)
+                            .localized_in(Unit::Meter)
+                            .suffix(" m")
+                            .speed(0.1)
+                            .range(0.0..=100.0),
+                    );
+                    ui.label("Stale after:");
+                    let mut days = self.lint_config.stale_after.as_secs() / 86_400;
+                    if ui
+                        .add(
+                            egui::DragValue::new(&mut days)
+                                .suffix(" days")
+                                .range(1..=3650),
+                        )
+                        .changed()
+                    {
+                        self.lint_config.stale_after =
+                            std::time::Duration::from_secs(days * 86_400);
+                    }
+                    if ui.button("Re-check").clicked() {
+                        rerun = true;
+                    }
+                });
+                ui.separator();
+                if issues.is_empty() {
+                    ui.colored_label(egui::Color32::GREEN, "No issues found.");
+                    return;
+                }
+                ui.label(format!("{} issues:", issues.len()));
+                egui::ScrollArea::vertical()
+                    .id_salt("scene_check_scroll_area")
+                    .max_height(300.0)
+                    .show(ui, |ui| {
+                        for issue in issues {
+                            ui.horizontal(|ui| {
+                                let (color, label) = match issue.severity {
+                                    LintSeverity::Error => (egui::Color32::RED, "error"),
+                                    LintSeverity::Warning => (egui::Color32::YELLOW, "warning"),
+                                };
+                                ui.colored_label(color, label);
+                                if ui.link(&issue.frame).clicked() {
+                                    focus = Some(issue.frame.clone());
+                                }
+                                ui.label(&issue.message);
+                            });
+                        }
+                    });
+            });
+
+        if focus.is_some() {
+            self.focused_frame = focus;
+            self.scroll_to_focused = true;
+        }
+        if rerun {
+            self.lint_issues = Some(scene_lint::lint_scene(&self.transforms, &self.lint_config));
+        } else if !open {
+            self.lint_issues = None;
+        }
+    }
+
+    /// Frames that no command used in the last `unused_days`, as cleanup candidates.
+    fn draw_usage_report(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Usage Report")
+            .id_salt("transforms_usage_report")
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Not used in the last");
+                    ui.add(
+                        egui::DragValue::new(&mut self.unused_days)
+                            .range(1..=365)
+                            .suffix(" days"),
+                    );
+                    if ui.button("Reload History").clicked() {
+                        self.frame_usage = history::frame_usage(&history::load());
+                    }
+                });
+                let cutoff = crate::storage::now_secs().saturating_sub(self.unused_days * 86_400);
+                let unused = self
+                    .transform_keys
+                    .iter()
+                    .filter(|k| {
+                        self.frame_usage
+                            .get(*k)
+                            .is_none_or(|u| u.last_used < cutoff)
+                    })
+                    .collect::<Vec<&String>>();
+                ui.label(format!(
+                    "{} of {} frames are candidates for deletion:",
+                    unused.len(),
+                    self.transform_keys.len()
+                ));
+                egui::ScrollArea::vertical()
+                    .id_salt("transforms_unused_scroll_area")
+                    .max_height(120.0)
+                    .show(ui, |ui| {
+                        for key in unused {
+                            let total = self.frame_usage.get(key).map(|u| u.count).unwrap_or(0);
+                            ui.label(format!("{} (used {} times in total)", key, total));
+                        }
+                    });
+            });
+    }
+
+    fn draw_transform_list(&mut self, ui: &mut egui::Ui) {
+        if !self.transform_keys.is_empty() {
+            ui.horizontal(|ui| {
+                ui.label("Coordinates relative to:");
+                egui::ComboBox::from_id_salt("transforms_reference_frame")
+                    .selected_text(&self.reference_frame)
+                    .show_ui(ui, |ui| {
+                        let root = scene_lint::ROOT_FRAME.to_string();
+                        ui.selectable_value(&mut self.reference_frame, root.clone(), root);
+                        for key in &self.transform_keys {
+                            ui.selectable_value(&mut self.reference_frame, key.clone(), key);
+                        }
+                    });
+            });
+        }
+        egui::ScrollArea::vertical()
+            .id_salt("transforms_list_scroll_area")
+            .auto_shrink([false; 2])
+            .show(ui, |ui| {
+                if self.transform_keys.is_empty() {
+                    ui.label("No transforms loaded.");
+                    return;
+                }
+                let mut duplicate = None;
+                let mut copy_pose = None;
+                let mut approach_retreat = None;
+                let mut inspection_grid = None;
+                egui::Grid::new("transforms_list_grid")
+                    .num_columns(11)
+                    .spacing([20.0, 4.0])
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.label("");
+                        ui.strong("Child");
+                        ui.strong("Parent");
+                        ui.strong("Translation (m)");
+                        ui.strong(format!("In {} (m, deg)", self.reference_frame));
+                        ui.strong("Uses");
+                        ui.strong("Origin");
+                        ui.label("");
+                        ui.label("");
+                        ui.label("");
+                        ui.label("");
+                        ui.end_row();
+                        for key in &self.transform_keys {
+                            let Some(tf) = self.transforms.get(key) else {
+                                continue;
+                            };
+                            let t = Pose::from_sp_transform(&tf.transform).translation;
+                            let mut selected = self.selected_frames.contains(key);
+                            if ui.checkbox(&mut selected, "").changed() {
+                                if selected {
+                                    self.selected_frames.insert(key.clone());
+                                } else {
+                                    self.selected_frames.remove(key);
+                                }
+                            }
+                            if self.focused_frame.as_deref() == Some(key.as_str()) {
+                                let response = ui.colored_label(
+                                    egui::Color32::YELLOW,
+                                    egui::RichText::new(&tf.child_frame_id).strong(),
+                                );
+                                if self.scroll_to_focused {
+                                    response.scroll_to_me(Some(egui::Align::Center));
+                                    self.scroll_to_focused = false;
+                                }
+                            } else {
+                                ui.label(&tf.child_frame_id);
+                            }
+                            ui.label(&tf.parent_frame_id);
+                            ui.monospace(locale::format_numbers(
+                                &t,
+                                locale::precision().translation_decimals,
+                            ));
+                            match pose::resolve(&self.transforms, key, &self.reference_frame) {
+                                Some(resolved) => draw_coordinate_readout(ui, &resolved),
+                                None => {
+                                    ui.weak("-").on_hover_text(format!(
+                                        "Not in the same tree as {}",
+                                        self.reference_frame
+                                    ));
+                                }
+                            }
+                            let uses = self.frame_usage.get(key).map(|u| u.count).unwrap_or(0);
+                            ui.label(uses.to_string());
+                            provenance::draw(ui, tf);
+                            if ui.small_button("Duplicate frame…").clicked() {
+                                duplicate = Some(tf.clone());
+                            }
+                            if ui
+                                .small_button("Copy Pose From…")
+                                .on_hover_text(
+                                    "Take over only the rotation or the translation of another frame",
+                                )
+                                .clicked()
+                            {
+                                copy_pose = Some(tf.clone());
+                            }
+                            if ui.small_button("Approach/Retreat…").clicked() {
+                                approach_retreat = Some(tf.clone());
+                            }
+                            if ui
+                                .small_button("Inspection Grid…")
+                                .on_hover_text("Camera poses over this frame as a surface")
+                                .clicked()
+                            {
+                                inspection_grid = Some(tf.clone());
+                            }
+                            ui.end_row();
+                        }
+                    });
+                if let Some(source) = duplicate {
+                    self.duplicate_frame_dialog = Some(DuplicateFrameDialog::new(source));
+                }
+                if let Some(target) = copy_pose {
+                    self.copy_pose_dialog = Some(CopyPoseDialog {
+                        target,
+                        source: None,
+                        part: PosePart::Rotation,
+                    });
+                }
+                if let Some(goal) = approach_retreat {
+                    self.approach_retreat_dialog = Some(ApproachRetreatDialog::new(goal));
+                }
+                if let Some(surface) = inspection_grid {
+                    self.inspection_grid_dialog = Some(InspectionGridDialog::new(surface));
+                }
+            });
+    }
+
+    fn draw_new_frame_dialog(
+        &mut self,
+        ctx: &egui::Context,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+    ) {
+        let Some(dialog) = &mut self.new_frame_dialog else {
+            return;
+        };
+
+        let mut open = true;
+        let mut publish = false;
+        let mut cancel = false;
+        let validation = dialog.validate(&self.transform_keys);
+        let is_publishing = self.publish_promise.is_some();
+
+        egui::Window::new("New Frame")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Name:");
+                    ui.text_edit_singleline(&mut dialog.name);
+                });
+                draw_frame_selector(
+                    ui,
+                    "Parent:",
+                    "new_frame_parent_select",
+                    &mut dialog.parent,
+                    &self.transform_keys,
+                );
+
+                ui.separator();
+                ui.label("Translation:");
+                draw_xyz_inputs(ui, &mut dialog.translation, "new_frame_translation");
+
+                ui.horizontal(|ui| {
+                    ui.label("Rotation:");
+                    let mut input = dialog.rotation_input;
+                    ui.radio_value(&mut input, RotationInput::Rpy, "RPY");
+                    ui.radio_value(&mut input, RotationInput::Quaternion, "Quaternion");
+                    ui.radio_value(&mut input, RotationInput::AxisAngle, "Axis-angle");
+                    dialog.set_rotation_input(input);
+                });
+                match dialog.rotation_input {
+                    RotationInput::Rpy => draw_rpy_inputs(ui, &mut dialog.rpy, "new_frame_rpy"),
+                    RotationInput::Quaternion => {
+                        draw_quaternion_inputs(ui, &mut dialog.quaternion, "new_frame_quat")
+                    }
+                    RotationInput::AxisAngle => {
+                        rotation_editor::draw(ui, &mut dialog.axis_angle, "new_frame_axis_angle")
+                    }
+                }
+
+                ui.separator();
+                ui.checkbox(&mut dialog.enable_transform, "Enable Transform");
+                ui.checkbox(&mut dialog.active_transform, "Active Transform");
+
+                ui.separator();
+                if let Some(reason) = &validation {
+                    ui.colored_label(egui::Color32::YELLOW, reason);
+                }
+                ui.horizontal(|ui| {
+                    ui.add_enabled_ui(validation.is_none() && !is_publishing, |ui| {
+                        if ui.button("Publish").clicked() {
+                            publish = true;
+                        }
+                    });
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                    if is_publishing {
+                        ui.spinner();
+                    }
+                });
+            });
+
+        if publish {
+            let transform = dialog.to_transform();
+            self.publish_frames(handle, connection, vec![transform]);
+        }
+        if !open || cancel {
+            self.new_frame_dialog = None;
+        }
+    }
+
+    fn draw_duplicate_frame_dialog(
+        &mut self,
+        ctx: &egui::Context,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+    ) {
+        let Some(dialog) = &mut self.duplicate_frame_dialog else {
+            return;
+        };
+
+        let mut open = true;
+        let mut publish = false;
+        let mut cancel = false;
+        let validation = dialog.validate(&self.transform_keys);
+        let is_publishing = self.publish_promise.is_some();
+
+        egui::Window::new(format!("Duplicate '{}'", dialog.source.child_frame_id))
+            .id(egui::Id::new("duplicate_frame_window"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("New name:");
+                    ui.text_edit_singleline(&mut dialog.name);
+                });
+                draw_frame_selector(
+                    ui,
+                    "Parent:",
+                    "duplicate_frame_parent_select",
+                    &mut dialog.parent,
+                    &self.transform_keys,
+                );
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label("Offset in:");
+                    ui.radio_value(&mut dialog.offset_axes, OffsetAxes::Frame, "Frame axes");
+                    ui.radio_value(&mut dialog.offset_axes, OffsetAxes::Parent, "Parent axes");
+                    ui.label("ℹ").on_hover_text(
+                        "Frame axes: the offset follows the source frame's orientation,\n\
+                         e.g. z = -0.1 m backs off along the tool axis.\n\
+                         Parent axes: the offset is applied in the parent frame.",
+                    );
+                });
+                ui.label("Translation offset:");
+                draw_xyz_inputs(ui, &mut dialog.offset_translation, "duplicate_offset_xyz");
+                ui.label("Rotation offset:");
+                draw_rpy_inputs(ui, &mut dialog.offset_rpy, "duplicate_offset_rpy");
+
+                ui.separator();
+                if let Some(reason) = &validation {
+                    ui.colored_label(egui::Color32::YELLOW, reason);
+                }
+                ui.horizontal(|ui| {
+                    ui.add_enabled_ui(validation.is_none() && !is_publishing, |ui| {
+                        if ui.button("Duplicate").clicked() {
+                            publish = true;
+                        }
+                    });
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                    if is_publishing {
+                        ui.spinner();
+                    }
+                });
+            });
+
+        if publish {
+            let source = dialog.source.clone();
+            let parent = dialog.parent.clone().unwrap_or_default();
+            let name = dialog.name.trim().to_string();
+            let delta = dialog.delta();
+            let axes = dialog.offset_axes;
+            if self.offline.is_some() {
+                // Offline there is no tree to look the source up in another parent
+                if parent != source.parent_frame_id {
+                    self.publish_status = Some(Err(
+                        "Offline, duplicates can only keep the source's parent".to_string(),
+                    ));
+                    return;
+                }
+                let base = Pose::from_sp_transform(&source.transform);
+                let pose = match axes {
+                    OffsetAxes::Frame => base.compose(&delta),
+                    OffsetAxes::Parent => base.offset_in_parent(&delta),
+                };
+                let metadata = FrameMetadata {
+                    tcp_id: name.clone(),
+                    enable_transform: source.enable_transform,
+                    active_transform: source.active_transform,
+                };
+                let mut transform = pose::make_transform_stamped(&parent, &name, &pose, &metadata);
+                provenance::stamp(&mut transform, Method::Computed);
+                self.publish_frames(handle, connection, vec![transform]);
+                return;
+            }
+            self.publish_status = None;
+            let handle = handle.clone();
+            let con_clone = connection.clone();
+            self.publish_promise = Some(workers::spawn("transform_duplicator", move || {
+                handle.block_on(duplicate_transform(
+                    con_clone, source, parent, name, delta, axes,
+                ))
+            }));
+        }
+        if !open || cancel {
+            self.duplicate_frame_dialog = None;
+        }
+    }
+
+    fn draw_copy_pose_dialog(
+        &mut self,
+        ctx: &egui::Context,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+    ) {
+        let Some(dialog) = &mut self.copy_pose_dialog else {
+            return;
+        };
+
+        let mut open = true;
+        let mut publish = false;
+        let mut cancel = false;
+        let validation = dialog.validate();
+        let is_publishing = self.publish_promise.is_some();
+
+        egui::Window::new(format!("Copy Pose onto '{}'", dialog.target.child_frame_id))
+            .id(egui::Id::new("copy_pose_window"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                draw_frame_selector(
+                    ui,
+                    "From:",
+                    "copy_pose_source_select",
+                    &mut dialog.source,
+                    &self.transform_keys,
+                );
+                ui.horizontal(|ui| {
+                    ui.label("Copy:");
+                    ui.radio_value(&mut dialog.part, PosePart::Rotation, "Rotation only");
+                    ui.radio_value(&mut dialog.part, PosePart::Translation, "Translation only");
+                });
+                ui.label(format!(
+                    "The other half of {} and its parent {} stay as they are.",
+                    dialog.target.child_frame_id, dialog.target.parent_frame_id
+                ));
+
+                ui.separator();
+                if let Some(reason) = &validation {
+                    ui.colored_label(egui::Color32::YELLOW, reason);
+                }
+                ui.horizontal(|ui| {
+                    ui.add_enabled_ui(validation.is_none() && !is_publishing, |ui| {
+                        if ui.button("Copy").clicked() {
+                            publish = true;
+                        }
+                    });
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                    if is_publishing {
+                        ui.spinner();
+                    }
+                });
+            });
+
+        if let (true, Some(source)) = (publish, dialog.source.clone()) {
+            let target = dialog.target.clone();
+            let part = dialog.part;
+            if self.offline.is_some() {
+                let Some(source_pose) =
+                    pose::resolve(&self.transforms, &source, &target.parent_frame_id)
+                else {
+                    self.publish_status = Some(Err(format!(
+                        "{} isn't in the same tree as {}",
+                        source, target.child_frame_id
+                    )));
+                    return;
+                };
+                let transform = part.copied_onto(&target, &source_pose);
+                self.publish_frames(handle, connection, vec![transform]);
+                return;
+            }
+            self.publish_status = None;
+            let handle = handle.clone();
+            let con_clone = connection.clone();
+            self.publish_promise = Some(workers::spawn("transform_pose_copy", move || {
+                handle.block_on(copy_pose_part(con_clone, target, source, part))
+            }));
+        }
+        if !open || cancel {
+            self.copy_pose_dialog = None;
+        }
+    }
+
+    /// Loads exported frame files, migrating older schema versions, and publishes them.
+    fn import_frames(
+        &mut self,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+    ) {
+        let Some(paths) = FileDialog::new().add_filter("JSON", &["json"]).pick_files() else {
+            return;
+        };
+        self.import_warnings.clear();
+        let mut transforms = Vec::new();
+        for path in paths {
+            let loaded = std::fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|text| schema::load::<JsonOutputWithMetadata>(&text, schema::FRAME_KIND));
+            match loaded {
+                Ok(loaded) => {
+                    if let Some(warning) = loaded.warning {
+                        self.import_warnings
+                            .push(format!("{}: {}", path.display(), warning));
+                    }
+                    let mut transform = loaded.data.to_transform_stamped();
+                    provenance::stamp(&mut transform, Method::Imported);
+                    transforms.push(transform);
+                }
+                Err(e) => self
+                    .import_warnings
+                    .push(format!("{}: {}", path.display(), e)),
+            }
+        }
+        if transforms.is_empty() {
+            return;
+        }
+        self.publish_frames(handle, connection, transforms);
+    }
+
+    fn poll_publish_promise(
+        &mut self,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+    ) {
+        let Some(promise) = &self.publish_promise else {
+            return;
+        };
+        if let std::task::Poll::Ready(result) = promise.poll() {
+            match result {
+                Ok(msg) => {
+                    self.publish_status = Some(Ok(msg.clone()));
+                    self.close_dialogs();
+                    self.spawn_transforms_promise(handle, connection);
+                }
+                Err(e) => {
+                    self.publish_status = Some(Err(e.clone()));
+                    self.pending_sequence = None;
+                }
+            }
+            self.publish_promise = None;
+        }
+    }
+
+    fn draw_approach_retreat_dialog(
+        &mut self,
+        ctx: &egui::Context,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+    ) {
+        let Some(dialog) = &mut self.approach_retreat_dialog else {
+            return;
+        };
+
+        let mut open = true;
+        let mut publish = false;
+        let mut cancel = false;
+        let is_publishing = self.publish_promise.is_some();
+        let existing = [dialog.approach_name(), dialog.retreat_name()]
+            .into_iter()
+            .filter(|name| self.transform_keys.contains(name))
+            .collect::<Vec<String>>();
+
+        egui::Window::new(format!(
+            "Approach/Retreat for '{}'",
+            dialog.goal.child_frame_id
+        ))
+        .id(egui::Id::new("approach_retreat_window"))
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Approach Axis:");
+                egui::ComboBox::from_id_salt("approach_axis_select")
+                    .selected_text(dialog.axis.to_string())
+                    .show_ui(ui, |ui| {
+                        for variant in ApproachAxis::variants() {
+                            ui.selectable_value(&mut dialog.axis, *variant, variant.to_string());
+                        }
+                    });
+                ui.label("ℹ").on_hover_text(
+                    "Direction, in the goal frame's axes, in which the tool moves \n\
+                         when approaching the goal. The generated frames are backed \n\
+                         off against this direction.",
+                );
+            });
+            ui.horizontal(|ui| {
+                ui.label("Approach Distance:");
+                ui.add(
+                    egui::DragValue::new(&mut dialog.approach_distance)
+                        .localized_in(Unit::Meter)
+                        .suffix(" m")
+                        .speed(0.001)
+                        .range(0.0..=1.0),
+                );
+            });
+            ui.checkbox(&mut dialog.generate_retreat, "Generate Retreat Frame");
+            ui.add_enabled_ui(dialog.generate_retreat, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Retreat Distance:");
+                    ui.add(
+                        egui::DragValue::new(&mut dialog.retreat_distance)
+                            .localized_in(Unit::Meter)
+                            .suffix(" m")
+                            .speed(0.001)
+                            .range(0.0..=1.0),
+                    );
+                });
+            });
+
+            ui.separator();
+            ui.label(format!("Creates: {}", dialog.approach_name()));
+            if dialog.generate_retreat {
+                ui.label(format!("Creates: {}", dialog.retreat_name()));
+            }
+            if !existing.is_empty() {
+                ui.colored_label(
+                    egui::Color32::YELLOW,
+                    format!("Will overwrite: {}", existing.join(", ")),
+                );
+            }
+            ui.horizontal(|ui| {
+                ui.add_enabled_ui(!is_publishing, |ui| {
+                    if ui.button("Generate").clicked() {
+                        publish = true;
+                    }
+                });
+                if ui.button("Cancel").clicked() {
+                    cancel = true;
+                }
+                if is_publishing {
+                    ui.spinner();
+                }
+            });
+        });
+
+        if publish {
+            let transforms = dialog.to_transforms();
+            self.publish_frames(handle, connection, transforms);
+        }
+        if !open || cancel {
+            self.approach_retreat_dialog = None;
+        }
+    }
+
+    fn draw_inspection_grid_dialog(
+        &mut self,
+        ctx: &egui::Context,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+    ) {
+        let Some(dialog) = &mut self.inspection_grid_dialog else {
+            return;
+        };
+
+        let mut open = true;
+        let mut publish = false;
+        let mut cancel = false;
+        let is_publishing = self.publish_promise.is_some();
+        let validation = dialog.grid.validate();
+        let grid = &mut dialog.grid;
+        let poses = grid.poses();
+        let existing = poses
+            .iter()
+            .filter(|(name, _)| self.transform_keys.contains(name))
+            .count();
+
+        egui::Window::new(format!(
+            "Inspection Grid over '{}'",
+            dialog.surface.child_frame_id
+        ))
+        .id(egui::Id::new("inspection_grid_window"))
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            egui::Grid::new("inspection_grid_params")
+                .num_columns(2)
+                .spacing([10.0, 4.0])
+                .show(ui, |ui| {
+                    ui.label("Name prefix:");
+                    ui.text_edit_singleline(&mut grid.prefix);
+                    ui.end_row();
+
+                    ui.label("Rows × columns:");
+                    ui.horizontal(|ui| {
+                        ui.add(egui::DragValue::new(&mut grid.rows).range(1..=50));
+                        ui.label("×");
+                        ui.add(egui::DragValue::new(&mut grid.columns).range(1..=50));
+                    });
+                    ui.end_row();
+
+                    ui.label("Spacing (X, Y):");
+                    ui.horizontal(|ui| {
+                        for spacing in [&mut grid.spacing_x, &mut grid.spacing_y] {
+                            ui.add(
+                                egui::DragValue::new(spacing)
+                                    .localized_in(Unit::Meter)
+                                    .suffix(" m")
+                                    .speed(0.001)
+                                    .range(0.0..=2.0),
+                            );
+                        }
+                    });
+                    ui.end_row();
+
+                    ui.label("Standoff:");
+                    ui.add(
+                        egui::DragValue::new(&mut grid.standoff)
+                            .localized_in(Unit::Meter)
+                            .suffix(" m")
+                            .speed(0.001)
+                            .range(0.0..=3.0),
+                    );
+                    ui.end_row();
+
+                    ui.label("Tilt:");
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            egui::DragValue::new(&mut grid.tilt)
+                                .localized_in(Unit::Radian)
+                                .suffix(" rad")
+                                .speed(0.01)
+                                .range(-1.2..=1.2),
+                        );
+                        ui.label("ℹ").on_hover_text(
+                            "The grid lies in the XY plane of the surface frame, centered \n\
+                             on its origin. Cameras look along -Z of the surface at their \n\
+                             grid point; tilt leans the view about the surface Y axis.",
+                        );
+                    });
+                    ui.end_row();
+                });
+            ui.checkbox(
+                &mut dialog.open_in_path_editor,
+                "Open the scan sequence in the Path Editor",
+            );
+
+            ui.separator();
+            ui.label(format!(
+                "Creates {} frames: {} … {}",
+                poses.len(),
+                poses.first().map(|(n, _)| n.as_str()).unwrap_or("-"),
+                poses.last().map(|(n, _)| n.as_str()).unwrap_or("-"),
+            ));
+            if existing > 0 {
+                ui.colored_label(
+                    egui::Color32::YELLOW,
+                    format!("Will overwrite {} existing frames", existing),
+                );
+            }
+            if let Some(reason) = &validation {
+                ui.colored_label(egui::Color32::YELLOW, reason);
+            }
+            ui.horizontal(|ui| {
+                ui.add_enabled_ui(validation.is_none() && !is_publishing, |ui| {
+                    if ui.button("Generate").clicked() {
+                        publish = true;
+                    }
+                });
+                if ui.button("Cancel").clicked() {
+                    cancel = true;
+                }
+                if is_publishing {
+                    ui.spinner();
+                }
+            });
+        });
+
+        if publish {
+            let transforms = dialog.to_transforms();
+            self.pending_sequence = dialog.open_in_path_editor.then(|| {
+                transforms
+                    .iter()
+                    .map(|t| t.child_frame_id.clone())
+                    .collect()
+            });
+            self.publish_frames(handle, connection, transforms);
+        }
+        if !open || cancel {
+            self.inspection_grid_dialog = None;
+        }
+    }
+
+    fn draw_bulk_edit_dialog(
+        &mut self,
+        ctx: &egui::Context,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+    ) {
+        let Some(dialog) = &mut self.bulk_edit_dialog else {
+            return;
+        };
+
+        let mut open = true;
+        let mut apply = false;
+        let mut cancel = false;
+        let is_publishing = self.publish_promise.is_some();
+
+        egui::Window::new("Bulk Edit Metadata")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(true)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Field:");
+                    ui.text_edit_singleline(&mut dialog.key);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Type:");
+                    ui.radio_value(&mut dialog.value_type, MetadataValueType::Bool, "Bool");
+                    ui.radio_value(&mut dialog.value_type, MetadataValueType::String, "String");
+                    ui.radio_value(&mut dialog.value_type, MetadataValueType::Float, "Float");
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Value:");
+                    match dialog.value_type {
+                        MetadataValueType::Bool => {
+                            ui.checkbox(&mut dialog.bool_value, "");
+                        }
+                        MetadataValueType::String => {
+                            ui.text_edit_singleline(&mut dialog.text_value);
+                        }
+                        MetadataValueType::Float => {
+                            ui.add(
+                                egui::DragValue::new(&mut dialog.float_value)
+                                    .localized()
+                                    .speed(0.01),
+                            );
+                        }
+                    }
+                });
+
+                ui.separator();
+                ui.label(format!("Preview ({} frames):", dialog.frames.len()));
+                let key = dialog.key.trim().to_string();
+                let new_value = sp_value_label(&dialog.value());
+                egui::ScrollArea::vertical()
+                    .id_salt("bulk_edit_preview_scroll_area")
+                    .max_height(200.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("bulk_edit_preview_grid")
+                            .num_columns(3)
+                            .striped(true)
+                            .show(ui, |ui| {
+                                ui.strong("Frame");
+                                ui.strong("Current");
+                                ui.strong("New");
+                                ui.end_row();
+                                for name in &dialog.frames {
+                                    let current = self
+                                        .transforms
+                                        .get(name)
+                                        .and_then(|tf| pose::get_metadata_field(tf, &key))
+                                        .map(sp_value_label)
+                                        .unwrap_or_else(|| "-".to_string());
+                                    ui.label(name);
+                                    ui.label(current);
+                                    ui.label(&new_value);
+                                    ui.end_row();
+                                }
+                            });
+                    });
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.add_enabled_ui(!key.is_empty() && !is_publishing, |ui| {
+                        if ui.button("Apply").clicked() {
+                            apply = true;
+                        }
+                    });
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                    if is_publishing {
+                        ui.spinner();
+                    }
+                });
+            });
+
+        if apply {
+            let transforms = dialog.to_transforms(&self.transforms);
+            self.publish_frames(handle, connection, transforms);
+        }
+        if !open || cancel {
+            self.bulk_edit_dialog = None;
+        }
+    }
+
+    /// Polls the transforms promise.
+    /// Returns true if the promise is still pending, false otherwise.
+    fn poll_transforms_promise(&mut self, ui: &mut egui::Ui) -> bool {
+        let Some(promise) = self.get_all_transforms_promise.take() else {
+            return false;
+        };
+
+        match promise.poll() {
+            std::task::Poll::Ready(result) => {
+                self.process_transforms_result(result);
+                false
+            }
+            std::task::Poll::Pending => {
+                self.get_all_transforms_promise = Some(promise);
+                ui.spinner();
+                true
+            }
+        }
+    }
+
+    fn process_transforms_result(&mut self, result: &HashMap<String, SPTransformStamped>) {
+        let mut keys: Vec<String> = result.keys().cloned().collect();
+        keys.sort_unstable();
+        self.transform_keys = keys;
+        self.transforms = result.clone();
+        self.selected_frames.retain(|k| result.contains_key(k));
+        self.frame_usage = history::frame_usage(&history::load());
+    }
+
+    fn spawn_transforms_promise(
+        &mut self,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+    ) {
+        let handle = handle.clone();
+        let con_clone = connection.clone();
+        self.get_all_transforms_promise = Some(workers::spawn("fetcher", move || {
+            handle.block_on(get_all_transforms(con_clone))
+        }));
+    }
+}
+
+/// Translation and RPY of a resolved pose with a menu to copy them.
+fn draw_coordinate_readout(ui: &mut egui::Ui, pose: &Pose) {
+    let rpy = pose::quaternion_to_rpy(pose.rotation).map(f64::to_degrees);
+    ui.horizontal(|ui| {
+        ui.monospace(format!(
+            "{} | {}",
+            locale::format_numbers(&pose.translation, locale::precision().translation_decimals),
+            locale::format_numbers(&rpy, 2)
+        ));
+        ui.menu_button("📋", |ui| {
+            let with = |rest: &[f64]| {
+                let values = [&pose.translation[..], rest].concat();
+                locale::format_numbers(&values, 6)
+            };
+            let copied = if ui.button("XYZ").clicked() {
+                Some(with(&[]))
+            } else if ui.button("XYZ + RPY (deg)").clicked() {
+                Some(with(&rpy))
+            } else if ui.button("XYZ + quaternion").clicked() {
+                Some(with(&pose.rotation))
+            } else {
+                None
+            };
+            if let Some(text) = copied {
+                ui.ctx().copy_text(text);
+                ui.close();
+            }
+        })
+        .response
+        .on_hover_text("Copy coordinates");
+    });
+}
+
+fn sp_value_label(value: &SPValue) -> String {
+    crate::state_poller::sp_value_to_display(Some(value))
+}
+
+/// Returns the reason a frame with this name and parent cannot be created, if any.
+fn validate_new_frame(name: &str, parent: &Option<String>, existing: &[String]) -> Option<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Some("Frame name is empty".to_string());
+    }
+    if existing.iter().any(|k| k == name) {
+        return Some(format!("Frame '{}' already exists", name));
+    }
+    match parent {
+        None => Some("Parent not selected".to_string()),
+        Some(parent) if parent == name => Some("Frame cannot be its own parent".to_string()),
+        Some(_) => None,
+    }
+}
+
+fn draw_frame_selector(
+    ui: &mut egui::Ui,
+    label_text: &str,
+    id_source: &str,
+    selection: &mut Option<String>,
+    keys: &[String],
+) {
+    ui.horizontal(|ui| {
+        ui.label(label_text);
+        let selected_text = selection.as_deref().unwrap_or("Select...");
+
+        egui::ComboBox::from_id_salt(id_source)
+            .selected_text(selected_text)
+            .show_ui(ui, |ui| {
+                ui.selectable_value(selection, None, "None");
+                for key in keys {
+                    ui.selectable_value(selection, Some(key.clone()), key);
+                }
+            });
+    });
+}
+
+fn draw_xyz_inputs(ui: &mut egui::Ui, xyz: &mut [f64; 3], id_prefix: &str) {
+    egui::Grid::new(id_prefix)
+        .num_columns(6)
+        .spacing([10.0, 4.0])
+        .show(ui, |ui| {
+            for (label, value) in ["x:", "y:", "z:"].iter().zip(xyz.iter_mut()) {
+                ui.label(*label);
+                ui.add(
+                    egui::DragValue::new(value)
+                        .localized_in(Unit::Meter)
+                        .suffix(" m")
+                        .speed(0.001),
+                );
+            }
+            ui.end_row();
+        });
+}
+
+fn draw_rpy_inputs(ui: &mut egui::Ui, rpy: &mut [f64; 3], id_prefix: &str) {
+    egui::Grid::new(id_prefix)
+        .num_columns(6)
+        .spacing([10.0, 4.0])
+        .show(ui, |ui| {
+            for (label, value) in ["roll:", "pitch:", "yaw:"].iter().zip(rpy.iter_mut()) {
+                ui.label(*label);
+                ui.add(
+                    egui::DragValue::new(value)
+                        .localized_in(Unit::Radian)
+                        .suffix(" rad")
+                        .range(-std::f64::consts::PI..=std::f64::consts::PI)
+                        .speed(0.01),
+                );
+            }
+            ui.end_row();
+        });
+}
+
+fn draw_quaternion_inputs(ui: &mut egui::Ui, quaternion: &mut [f64; 4], id_prefix: &str) {
+    egui::Grid::new(id_prefix)
+        .num_columns(8)
+        .spacing([10.0, 4.0])
+        .show(ui, |ui| {
+            for (label, value) in ["x:", "y:", "z:", "w:"].iter().zip(quaternion.iter_mut()) {
+                ui.label(*label);
+                ui.add(
+                    egui::DragValue::new(value)
+                        .localized()
+                        .range(-1.0..=1.0)
+                        .speed(0.001),
+                );
+            }
+            ui.end_row();
+        });
+}
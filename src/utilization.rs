@@ -0,0 +1,209 @@
+use crate::state_poller::{StatePoller, sp_value_to_display};
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    time::{Duration, Instant},
+};
+
+pub const UTILIZATION_FILE: &str = "utilization.json";
+
+pub fn check_file() -> Option<Result<(), String>> {
+    storage::check_json::<UtilizationFile>(UTILIZATION_FILE)
+}
+const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+/// Gaps longer than this (app suspended, connection lost) are not counted.
+const MAX_SAMPLE_GAP: Duration = Duration::from_secs(10);
+
+/// Three 8 hour shifts starting at 06:00 local time.
+pub const SHIFTS: [&str; 3] = ["A (06-14)", "B (14-22)", "C (22-06)"];
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Utilization {
+    pub busy_seconds: f64,
+    pub idle_seconds: f64,
+}
+
+impl Utilization {
+    pub fn ratio(&self) -> f64 {
+        let total = self.busy_seconds + self.idle_seconds;
+        if total > 0.0 {
+            self.busy_seconds / total
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Key of one accumulation bucket.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct UtilizationKey {
+    /// `YYYY-MM-DD` of the shift start.
+    pub day: String,
+    pub shift: usize,
+    pub robot_id: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct UtilizationFile {
+    utc_offset_hours: i64,
+    records: Vec<(UtilizationKey, Utilization)>,
+}
+
+/// Accumulates busy (executing) vs idle time per robot, day and shift from the
+/// polled `{robot}_request_state`.
+pub struct UtilizationTracker {
+    pub utc_offset_hours: i64,
+    pub records: BTreeMap<UtilizationKey, Utilization>,
+    robots: BTreeSet<String>,
+    last_sample: Option<Instant>,
+    last_save: Instant,
+    /// Shift of the last sample, to notice when one ends.
+    last_shift: Option<(String, usize)>,
+    finished_shift: Option<(String, usize)>,
+}
+
+impl UtilizationTracker {
+    pub fn load() -> Self {
+        let file: UtilizationFile = storage::load_json(UTILIZATION_FILE).unwrap_or_default();
+        let records: BTreeMap<UtilizationKey, Utilization> = file.records.into_iter().collect();
+        // Keep tracking robots from earlier sessions even before they are seen again
+        let robots = records.keys().map(|k| k.robot_id.clone()).collect();
+        Self {
+            utc_offset_hours: file.utc_offset_hours,
+            records,
+            robots,
+            last_sample: None,
+            last_save: Instant::now(),
+            last_shift: None,
+            finished_shift: None,
+        }
+    }
+
+    pub fn save(&self) {
+        let file = UtilizationFile {
+            utc_offset_hours: self.utc_offset_hours,
+            records: self.records.iter().map(|(k, v)| (k.clone(), *v)).collect(),
+        };
+        if let Err(e) = storage::save_json(UTILIZATION_FILE, &file) {
+            log::error!("Failed to save utilization: {}", e);
+        }
+    }
+
+    pub fn track<I: IntoIterator<Item = String>>(&mut self, robot_ids: I) {
+        self.robots
+            .extend(robot_ids.into_iter().filter(|id| !id.is_empty()));
+    }
+
+    /// Variables the state poller has to watch for the tracked robots.
+    pub fn watch_keys(&self) -> Vec<String> {
+        self.robots
+            .iter()
+            .map(|r| format!("{}_request_state", r))
+            .collect()
+    }
+
+    pub fn robots(&self) -> &BTreeSet<String> {
+        &self.robots
+    }
+
+    /// Sums the records of one robot over each day, most recent day last.
+    pub fn daily(&self, robot_id: &str) -> BTreeMap<String, Utilization> {
+        let mut days: BTreeMap<String, Utilization> = BTreeMap::new();
+        for (key, u) in self.records.iter().filter(|(k, _)| k.robot_id == robot_id) {
+            let day = days.entry(key.day.clone()).or_default();
+            day.busy_seconds += u.busy_seconds;
+            day.idle_seconds += u.idle_seconds;
+        }
+        days
+    }
+
+    /// Call once per frame, after the poller has been updated.
+    pub fn sample(&mut self, poller: &StatePoller) {
+        let now = Instant::now();
+        let Some(last) = self.last_sample.replace(now) else {
+            return;
+        };
+        let dt = now.duration_since(last);
+        if dt > MAX_SAMPLE_GAP {
+            return;
+        }
+        let (day, shift) = self.current_shift();
+        if let Some(last) = self.last_shift.replace((day.clone(), shift)) {
+            if last != (day.clone(), shift) {
+                self.finished_shift = Some(last);
+            }
+        }
+        for robot_id in &self.robots {
+            let key = format!("{}_request_state", robot_id);
+            let Some(value) = poller.get(&key) else {
+                continue;
+            };
+            let entry = self
+                .records
+                .entry(UtilizationKey {
+                    day: day.clone(),
+                    shift,
+                    robot_id: robot_id.clone(),
+                })
+                .or_default();
+            if sp_value_to_display(Some(value)) == "executing" {
+                entry.busy_seconds += dt.as_secs_f64();
+            } else {
+                entry.idle_seconds += dt.as_secs_f64();
+            }
+        }
+        if self.last_save.elapsed() >= SAVE_INTERVAL {
+            self.save();
+            self.last_save = now;
+        }
+    }
+
+    /// The shift that ended since the last call, once.
+    pub fn take_finished_shift(&mut self) -> Option<(String, usize)> {
+        self.finished_shift.take()
+    }
+
+    /// Day and shift index of the current local time.
+    pub fn current_shift(&self) -> (String, usize) {
+        self.shift_at(storage::now_secs())
+    }
+
+    /// Day and shift index of the shift before the current one.
+    pub fn previous_shift(&self) -> (String, usize) {
+        self.shift_at(storage::now_secs().saturating_sub(8 * 3600))
+    }
+
+    /// Start and end of a shift in unix seconds.
+    pub fn shift_window(&self, day: &str, shift: usize) -> Option<(u64, u64)> {
+        let shifted = storage::parse_date(day)? as i64 + shift as i64 * 8 * 3600;
+        let start = shifted + 6 * 3600 - self.utc_offset_hours * 3600;
+        let start = u64::try_from(start).ok()?;
+        Some((start, start + 8 * 3600))
+    }
+
+    fn shift_at(&self, secs: u64) -> (String, usize) {
+        let local = secs as i64 + self.utc_offset_hours * 3600;
+        // Shifting by 6 hours makes shift A start at midnight of its "day"
+        let shifted = (local - 6 * 3600).max(0) as u64;
+        let hour = (shifted % 86_400) / 3600;
+        let day = storage::format_timestamp(shifted)[..10].to_string();
+        (day, (hour / 8) as usize)
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("day,shift,robot_id,busy_s,idle_s,utilization\n");
+        for (key, u) in &self.records {
+            csv.push_str(&format!(
+                "{},{},{},{:.0},{:.0},{:.3}\n",
+                key.day,
+                SHIFTS[key.shift.min(2)],
+                key.robot_id,
+                u.busy_seconds,
+                u.idle_seconds,
+                u.ratio()
+            ));
+        }
+        csv
+    }
+}
@@ -0,0 +1,117 @@
+//! Rough vibration indicator from the force feedback during moves: the RMS
+//! of the force around its rolling mean and the jerk of the force signal.
+//! High values usually mean a too aggressive acceleration or sharp corners
+//! where a blend radius would help.
+
+use crate::locale;
+use crate::state_poller::{StatePoller, sp_value_to_display};
+use eframe::egui;
+use micro_sp::{FloatOrUnknown, SPValue};
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// The poller refreshes the force this often, sampling faster repeats values.
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(250);
+/// Samples in the rolling window, five seconds.
+const WINDOW: usize = 20;
+
+pub fn force_feedback_key(robot_id: &str) -> String {
+    format!("{}_force_feedback", robot_id)
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VibrationLevel {
+    /// N, RMS of the force around the window mean.
+    pub rms: f64,
+    /// N/s², largest second difference of the force in the window.
+    pub jerk: f64,
+}
+
+impl VibrationLevel {
+    fn of(forces: &VecDeque<f64>) -> Self {
+        if forces.len() < 3 {
+            return Self::default();
+        }
+        let mean = forces.iter().sum::<f64>() / forces.len() as f64;
+        let rms =
+            (forces.iter().map(|f| (f - mean).powi(2)).sum::<f64>() / forces.len() as f64).sqrt();
+        let dt = SAMPLE_INTERVAL.as_secs_f64();
+        let jerk = forces
+            .iter()
+            .zip(forces.iter().skip(1))
+            .zip(forces.iter().skip(2))
+            .map(|((a, b), c)| (c - 2.0 * b + a).abs() / (dt * dt))
+            .fold(0.0, f64::max);
+        Self { rms, jerk }
+    }
+}
+
+/// Watches the force of one robot while it executes a command and keeps the
+/// peak level of the last move.
+#[derive(Default)]
+pub struct VibrationMonitor {
+    forces: VecDeque<f64>,
+    last_sample: Option<Instant>,
+    executing: bool,
+    /// Highest level of the running or last move.
+    peak: Option<VibrationLevel>,
+}
+
+impl VibrationMonitor {
+    /// Call every frame with the polled state.
+    pub fn update(&mut self, poller: &StatePoller, robot_id: &str) {
+        let executing =
+            sp_value_to_display(poller.get(&format!("{}_request_state", robot_id))) == "executing";
+        if executing && !self.executing {
+            self.forces.clear();
+            self.peak = None;
+        }
+        self.executing = executing;
+        if !executing
+            || self
+                .last_sample
+                .is_some_and(|last| last.elapsed() < SAMPLE_INTERVAL)
+        {
+            return;
+        }
+        self.last_sample = Some(Instant::now());
+        let Some(SPValue::Float64(FloatOrUnknown::Float64(force))) =
+            poller.get(&force_feedback_key(robot_id))
+        else {
+            return;
+        };
+        self.forces.push_back(force.0);
+        if self.forces.len() > WINDOW {
+            self.forces.pop_front();
+        }
+        let level = VibrationLevel::of(&self.forces);
+        let peak = self.peak.get_or_insert_default();
+        peak.rms = peak.rms.max(level.rms);
+        peak.jerk = peak.jerk.max(level.jerk);
+    }
+
+    /// One line with the level, turned into a warning above `threshold` N RMS.
+    pub fn draw(&self, ui: &mut egui::Ui, threshold: f64) {
+        let Some(peak) = self.peak else {
+            return;
+        };
+        let text = format!(
+            "Force ripple {} N RMS, jerk {} N/s²{}",
+            locale::format_number(peak.rms, 1),
+            locale::format_number(peak.jerk, 0),
+            if self.executing { "" } else { " (last move)" }
+        );
+        if peak.rms > threshold {
+            ui.colored_label(egui::Color32::YELLOW, format!("⚠ {}", text))
+                .on_hover_text(format!(
+                    "Above the {} N threshold set in Settings. Try a lower acceleration, \
+                     or a blend radius if the path has sharp corners.",
+                    locale::format_number(threshold, 1)
+                ));
+        } else {
+            ui.weak(text);
+        }
+    }
+}
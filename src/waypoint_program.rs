@@ -0,0 +1,408 @@
+//! Named programs of recorded waypoints, a lightweight teach pendant: the
+//! current joint positions or the selected goal frame are recorded together
+//! with the motion to reach them, arranged in order and run one move after
+//! the other by the same runner as the command queue. Programs are saved as
+//! versioned JSON.
+
+use crate::commissioning::TestMoveFrames;
+use crate::locale::{self, LocalizedDragValue};
+use crate::schema::{self, Versioned};
+use crate::sequence::{self, Run, Step};
+use eframe::egui;
+use micro_sp::*;
+use rfd::FileDialog;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Longest a single move of a program may take.
+const MOVE_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum Target {
+    /// Joint positions recorded from the robot, in radians.
+    Joints(Vec<f64>),
+    Frame(String),
+}
+
+impl std::fmt::Display for Target {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Target::Joints(joints) => write!(f, "[{}]", locale::format_numbers(joints, 3)),
+            Target::Frame(frame) => write!(f, "{}", frame),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum Motion {
+    Linear,
+    Joint,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Waypoint {
+    target: Target,
+    /// Recorded joint positions are always reached with a joint move.
+    motion: Motion,
+    velocity: f64,
+    acceleration: f64,
+    blend_radius: f64,
+}
+
+impl Waypoint {
+    fn command(&self) -> &'static str {
+        match (&self.target, self.motion) {
+            (Target::Frame(_), Motion::Linear) => "unsafe_move_l",
+            _ => "unsafe_move_j",
+        }
+    }
+
+    fn parameters(&self, frames: &TestMoveFrames) -> Vec<(String, SPValue)> {
+        let (goal, joints) = match &self.target {
+            Target::Frame(frame) => (frame.clone(), None),
+            Target::Joints(joints) => (frames.tcp.clone(), Some(joints)),
+        };
+        let mut parameters = vec![
+            ("command_type".to_string(), self.command().to_spvalue()),
+            ("accelleration".to_string(), self.acceleration.to_spvalue()),
+            ("velocity".to_string(), self.velocity.to_spvalue()),
+            ("use_execution_time".to_string(), false.to_spvalue()),
+            (
+                "use_blend_radius".to_string(),
+                (self.blend_radius > 0.0).to_spvalue(),
+            ),
+            ("blend_radius".to_string(), self.blend_radius.to_spvalue()),
+            (
+                "use_joint_positions".to_string(),
+                joints.is_some().to_spvalue(),
+            ),
+            ("use_preferred_joint_config".to_string(), false.to_spvalue()),
+            ("use_relative_pose".to_string(), false.to_spvalue()),
+            (
+                "baseframe_id".to_string(),
+                frames.baseframe.as_str().to_spvalue(),
+            ),
+            (
+                "faceplate_id".to_string(),
+                frames.faceplate.as_str().to_spvalue(),
+            ),
+            ("goal_feature_id".to_string(), goal.as_str().to_spvalue()),
+            ("tcp_id".to_string(), frames.tcp.as_str().to_spvalue()),
+            ("root_frame_id".to_string(), "world".to_spvalue()),
+        ];
+        if let Some(joints) = joints {
+            parameters.push((
+                "joint_positions".to_string(),
+                SPValue::Array(ArrayOrUnknown::Array(
+                    joints.iter().map(|x| x.to_spvalue()).collect(),
+                )),
+            ));
+        }
+        parameters
+    }
+
+    fn step(&self, frames: &TestMoveFrames) -> Step {
+        Step::new(
+            &frames.robot_id,
+            self.command(),
+            Some(&self.target.to_string()),
+            self.parameters(frames),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct WaypointProgram {
+    name: String,
+    waypoints: Vec<Waypoint>,
+}
+
+impl Default for WaypointProgram {
+    fn default() -> Self {
+        Self {
+            name: "program".to_string(),
+            waypoints: Vec::new(),
+        }
+    }
+}
+
+pub struct ProgramPanel {
+    program: WaypointProgram,
+    /// Motion of newly recorded waypoints.
+    motion: Motion,
+    velocity: f64,
+    acceleration: f64,
+    run: Option<Run>,
+    status: Option<Result<String, String>>,
+}
+
+impl ProgramPanel {
+    pub fn new() -> Self {
+        Self {
+            program: WaypointProgram::default(),
+            motion: Motion::Linear,
+            velocity: 0.1,
+            acceleration: 0.1,
+            run: None,
+            status: None,
+        }
+    }
+
+    pub fn run(&self) -> Option<&Run> {
+        self.run.as_ref()
+    }
+
+    fn poll(&mut self) {
+        let Some(run) = self.run.take() else {
+            return;
+        };
+        match run.finish() {
+            Ok(outcome) => {
+                let retries = outcome.retries_text();
+                self.status = Some(match outcome.error {
+                    None => Ok(format!("Reached {} waypoints{}", outcome.done, retries)),
+                    Some(e) => Err(format!("{} reached{}, then {}", outcome.done, retries, e)),
+                });
+            }
+            Err(run) => self.run = Some(run),
+        }
+    }
+
+    fn record(&mut self, target: Target) {
+        self.program.waypoints.push(Waypoint {
+            target,
+            motion: self.motion,
+            velocity: self.velocity,
+            acceleration: self.acceleration,
+            blend_radius: 0.0,
+        });
+    }
+
+    fn save(&mut self) {
+        let Some(path) = FileDialog::new()
+            .add_filter("JSON", &["json"])
+            .set_file_name(format!("{}.json", self.program.name))
+            .save_file()
+        else {
+            return;
+        };
+        self.status = Some(
+            serde_json::to_string_pretty(&Versioned::new(
+                schema::WAYPOINT_PROGRAM_KIND,
+                &self.program,
+            ))
+            .map_err(|e| format!("JSON serialization error: {}", e))
+            .and_then(|json| {
+                std::fs::write(&path, json)
+                    .map_err(|e| format!("Failed to write {:?}: {}", path, e))
+            })
+            .map(|()| format!("Saved {}", path.display())),
+        );
+    }
+
+    fn load(&mut self) {
+        let Some(path) = FileDialog::new().add_filter("JSON", &["json"]).pick_file() else {
+            return;
+        };
+        let program = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {:?}: {}", path, e))
+            .and_then(|content| {
+                schema::load::<WaypointProgram>(&content, schema::WAYPOINT_PROGRAM_KIND)
+                    .map_err(|e| format!("{:?} is not a waypoint program: {}", path, e))
+            });
+        self.status = Some(program.map(|loaded| {
+            self.program = loaded.data;
+            match loaded.warning {
+                Some(warning) => format!("Loaded {}. {}", path.display(), warning),
+                None => format!("Loaded {}", path.display()),
+            }
+        }));
+    }
+
+    /// `joints` are the live joint positions and `goal` the goal frame
+    /// selected above. `frames` is an error while the robot can't be moved.
+    pub fn draw(
+        &mut self,
+        ui: &mut egui::Ui,
+        handle: &tokio::runtime::Handle,
+        connection: &Arc<ConnectionManager>,
+        frames: Result<TestMoveFrames, String>,
+        joints: Option<&[f64]>,
+        goal: Option<&str>,
+    ) {
+        self.poll();
+        let running = self.run.as_ref().map(Run::current);
+        ui.horizontal(|ui| {
+            ui.label("Program:");
+            ui.add_enabled(
+                running.is_none(),
+                egui::TextEdit::singleline(&mut self.program.name).desired_width(160.0),
+            );
+            if ui
+                .add_enabled(running.is_none(), egui::Button::new("Load…"))
+                .clicked()
+            {
+                self.load();
+            }
+            if ui.button("Save…").clicked() {
+                self.save();
+            }
+        });
+
+        ui.add_enabled_ui(running.is_none(), |ui| {
+            ui.horizontal(|ui| {
+                ui.label("New waypoints:");
+                ui.radio_value(&mut self.motion, Motion::Linear, "MoveL");
+                ui.radio_value(&mut self.motion, Motion::Joint, "MoveJ");
+                ui.add(
+                    egui::DragValue::new(&mut self.velocity)
+                        .localized()
+                        .prefix("v ")
+                        .speed(0.01)
+                        .range(0.001..=1.0),
+                );
+                ui.add(
+                    egui::DragValue::new(&mut self.acceleration)
+                        .localized()
+                        .prefix("a ")
+                        .speed(0.01)
+                        .range(0.001..=1.0),
+                );
+            });
+            ui.horizontal(|ui| {
+                let record = ui
+                    .add_enabled(joints.is_some(), egui::Button::new("Record Current Pose"))
+                    .on_hover_text("Add the current joint positions, reached with MoveJ")
+                    .on_disabled_hover_text("No joint states from the robot");
+                if record.clicked() {
+                    if let Some(joints) = joints {
+                        self.record(Target::Joints(joints.to_vec()));
+                    }
+                }
+                let add = ui
+                    .add_enabled(goal.is_some(), egui::Button::new("Add Goal Frame"))
+                    .on_hover_text("Add the goal frame selected above")
+                    .on_disabled_hover_text("Select a goal frame above");
+                if add.clicked() {
+                    if let Some(goal) = goal {
+                        self.record(Target::Frame(goal.to_string()));
+                    }
+                }
+            });
+        });
+
+        let mut edit = None;
+        let count = self.program.waypoints.len();
+        egui::Grid::new("waypoint_program_grid")
+            .num_columns(6)
+            .striped(true)
+            .show(ui, |ui| {
+                for (i, waypoint) in self.program.waypoints.iter_mut().enumerate() {
+                    sequence::draw_row_number(ui, i, running);
+                    ui.monospace(waypoint.target.to_string());
+                    ui.add_enabled_ui(running.is_none(), |ui| {
+                        let joints = matches!(waypoint.target, Target::Joints(_));
+                        ui.add_enabled_ui(!joints, |ui| {
+                            egui::ComboBox::from_id_salt(("waypoint_motion", i))
+                                .selected_text(match waypoint.command() {
+                                    "unsafe_move_l" => "MoveL",
+                                    _ => "MoveJ",
+                                })
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(
+                                        &mut waypoint.motion,
+                                        Motion::Linear,
+                                        "MoveL",
+                                    );
+                                    ui.selectable_value(
+                                        &mut waypoint.motion,
+                                        Motion::Joint,
+                                        "MoveJ",
+                                    );
+                                });
+                        });
+                    });
+                    ui.add_enabled_ui(running.is_none(), |ui| {
+                        ui.horizontal(|ui| {
+                            ui.add(
+                                egui::DragValue::new(&mut waypoint.velocity)
+                                    .localized()
+                                    .prefix("v ")
+                                    .speed(0.01)
+                                    .range(0.001..=1.0),
+                            );
+                            ui.add(
+                                egui::DragValue::new(&mut waypoint.acceleration)
+                                    .localized()
+                                    .prefix("a ")
+                                    .speed(0.01)
+                                    .range(0.001..=1.0),
+                            );
+                        });
+                    });
+                    ui.add_enabled(
+                        running.is_none(),
+                        egui::DragValue::new(&mut waypoint.blend_radius)
+                            .localized()
+                            .prefix("blend ")
+                            .suffix(" m")
+                            .speed(0.001)
+                            .range(0.0..=0.2),
+                    );
+                    if let Some(row_edit) =
+                        sequence::draw_row_controls(ui, i, count, running.is_none())
+                    {
+                        edit = Some(row_edit);
+                    }
+                    ui.end_row();
+                }
+            });
+        if let Some(edit) = edit {
+            edit.apply(&mut self.program.waypoints);
+        }
+        if self.program.waypoints.is_empty() {
+            ui.weak("No waypoints, record some above.");
+        }
+
+        let problem = frames.as_ref().err().cloned();
+        ui.horizontal(|ui| match &self.run {
+            Some(run) => {
+                if ui
+                    .button("Stop Program")
+                    .on_hover_text("Stop after the running move")
+                    .clicked()
+                {
+                    run.cancel();
+                }
+            }
+            None => {
+                let start = ui
+                    .add_enabled(
+                        problem.is_none() && !self.program.waypoints.is_empty(),
+                        egui::Button::new("Run Program"),
+                    )
+                    .on_disabled_hover_text(problem.clone().unwrap_or_default());
+                if start.clicked() {
+                    if let Ok(frames) = &frames {
+                        self.status = None;
+                        let steps = self
+                            .program
+                            .waypoints
+                            .iter()
+                            .map(|waypoint| waypoint.step(frames))
+                            .collect();
+                        self.run = Some(Run::start(
+                            "waypoint_program",
+                            handle,
+                            connection,
+                            steps,
+                            MOVE_TIMEOUT,
+                        ));
+                    }
+                }
+            }
+        });
+        sequence::draw_status(ui, &self.status);
+    }
+}
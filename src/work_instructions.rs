@@ -0,0 +1,358 @@
+//! Work instruction of the running or armed order, shown in a side panel next
+//! to the controls. Markdown and simple HTML are rendered as blocks: headings,
+//! lists, code, images and paragraphs. Inline formatting is dropped.
+
+use eframe::egui;
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+/// How often the file is checked for changes while shown.
+const RELOAD_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, PartialEq)]
+enum Block {
+    Heading(usize, String),
+    Bullet(String),
+    Numbered(String, String),
+    Code(String),
+    /// Path as written, resolved against the document's folder when drawn.
+    Image(String),
+    Rule,
+    Paragraph(String),
+}
+
+/// Removes the inline markup the panel doesn't render.
+fn plain(text: &str) -> String {
+    let text = text.replace("**", "").replace("__", "").replace('`', "");
+    // Links keep their text
+    let mut out = String::new();
+    let mut rest = text.as_str();
+    while let Some(start) = rest.find('[') {
+        let Some(mid) = rest[start..].find("](").map(|i| start + i) else {
+            break;
+        };
+        let Some(end) = rest[mid..].find(')').map(|i| mid + i) else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        out.push_str(&rest[start + 1..mid]);
+        rest = &rest[end + 1..];
+    }
+    out.push_str(rest);
+    out.trim().to_string()
+}
+
+fn parse_markdown(text: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut code: Option<Vec<&str>> = None;
+    let flush = |paragraph: &mut Vec<&str>, blocks: &mut Vec<Block>| {
+        if !paragraph.is_empty() {
+            blocks.push(Block::Paragraph(plain(&paragraph.join(" "))));
+            paragraph.clear();
+        }
+    };
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") {
+            match code.take() {
+                Some(lines) => blocks.push(Block::Code(lines.join("\n"))),
+                None => {
+                    flush(&mut paragraph, &mut blocks);
+                    code = Some(Vec::new());
+                }
+            }
+            continue;
+        }
+        if let Some(lines) = code.as_mut() {
+            lines.push(line);
+            continue;
+        }
+        if trimmed.is_empty() {
+            flush(&mut paragraph, &mut blocks);
+            continue;
+        }
+        let hashes = trimmed.chars().take_while(|c| *c == '#').count();
+        let numbered = trimmed
+            .split_once(". ")
+            .filter(|(n, _)| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()));
+        let block = if (1..=6).contains(&hashes) && trimmed[hashes..].starts_with(' ') {
+            Block::Heading(hashes, plain(&trimmed[hashes..]))
+        } else if trimmed == "---" || trimmed == "***" {
+            Block::Rule
+        } else if let Some(item) = trimmed
+            .strip_prefix("- ")
+            .or_else(|| trimmed.strip_prefix("* "))
+        {
+            Block::Bullet(plain(item))
+        } else if let Some((n, item)) = numbered {
+            Block::Numbered(n.to_string(), plain(item))
+        } else if let Some(path) = trimmed
+            .strip_prefix("![")
+            .and_then(|rest| rest.split_once("]("))
+            .and_then(|(_, rest)| rest.strip_suffix(')'))
+        {
+            Block::Image(path.to_string())
+        } else {
+            paragraph.push(trimmed);
+            continue;
+        };
+        flush(&mut paragraph, &mut blocks);
+        blocks.push(block);
+    }
+    if let Some(lines) = code {
+        blocks.push(Block::Code(lines.join("\n")));
+    }
+    flush(&mut paragraph, &mut blocks);
+    blocks
+}
+
+/// Value of `attribute="..."` in the inside of a tag.
+fn attribute<'a>(tag: &'a str, attribute: &str) -> Option<&'a str> {
+    let start = tag.find(&format!("{}=\"", attribute))? + attribute.len() + 2;
+    let end = tag[start..].find('"')? + start;
+    Some(&tag[start..end])
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+/// Turns the HTML into markdown-like lines and parses those.
+fn parse_html(html: &str) -> Vec<Block> {
+    let mut markdown = String::new();
+    let mut rest = html;
+    let mut numbered = 0;
+    let mut in_ordered = false;
+    let mut in_pre = false;
+    while let Some(start) = rest.find('<') {
+        let text = &rest[..start];
+        if in_pre {
+            markdown.push_str(&unescape(text));
+        } else {
+            // Runs of whitespace are one space, like a browser shows them
+            let mut collapsed = String::new();
+            for c in text.chars() {
+                if !c.is_whitespace() {
+                    collapsed.push(c);
+                } else if !collapsed.ends_with(' ') {
+                    collapsed.push(' ');
+                }
+            }
+            markdown.push_str(&unescape(&collapsed));
+        }
+        let Some(end) = rest[start..].find('>').map(|i| start + i) else {
+            break;
+        };
+        let tag = &rest[start + 1..end];
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        let closing = tag.starts_with('/');
+        match (name.as_str(), closing) {
+            ("h1" | "h2" | "h3" | "h4", false) => {
+                let level = name[1..].parse::<usize>().unwrap_or(1);
+                markdown.push_str(&format!("\n\n{} ", "#".repeat(level)));
+            }
+            ("ol", false) => {
+                in_ordered = true;
+                numbered = 0;
+            }
+            ("ul", false) => in_ordered = false,
+            ("li", false) if in_ordered => {
+                numbered += 1;
+                markdown.push_str(&format!("\n{}. ", numbered));
+            }
+            ("li", false) => markdown.push_str("\n- "),
+            ("pre", false) => {
+                in_pre = true;
+                markdown.push_str("\n```\n");
+            }
+            ("pre", true) => {
+                in_pre = false;
+                markdown.push_str("\n```\n");
+            }
+            ("hr", _) => markdown.push_str("\n\n---\n\n"),
+            ("img", _) => {
+                if let Some(src) = attribute(tag, "src") {
+                    markdown.push_str(&format!("\n\n![]({})\n\n", src));
+                }
+            }
+            ("br", _) => markdown.push('\n'),
+            ("p" | "div" | "h1" | "h2" | "h3" | "h4" | "ol" | "ul" | "table" | "tr", _) => {
+                markdown.push_str("\n\n")
+            }
+            _ => (),
+        }
+        rest = &rest[end + 1..];
+    }
+    markdown.push_str(&unescape(rest));
+    parse_markdown(&markdown)
+}
+
+fn load(path: &Path) -> Result<Vec<Block>, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let html = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("html") || e.eq_ignore_ascii_case("htm"));
+    Ok(if html {
+        parse_html(&text)
+    } else {
+        parse_markdown(&text)
+    })
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+pub struct InstructionPanel {
+    pub open: bool,
+    path: Option<PathBuf>,
+    modified: Option<SystemTime>,
+    last_check: Option<Instant>,
+    document: Result<Vec<Block>, String>,
+}
+
+impl InstructionPanel {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            path: None,
+            modified: None,
+            last_check: None,
+            document: Ok(Vec::new()),
+        }
+    }
+
+    /// Loads the instruction when it changed, or the file did.
+    fn update(&mut self, path: Option<&str>) {
+        let path = path.map(PathBuf::from);
+        let due = self
+            .last_check
+            .is_none_or(|last| last.elapsed() >= RELOAD_INTERVAL);
+        if path == self.path && !due {
+            return;
+        }
+        self.last_check = Some(Instant::now());
+        let modified = path.as_deref().and_then(modified);
+        if path == self.path && modified == self.modified {
+            return;
+        }
+        self.document = match &path {
+            Some(path) => load(path),
+            None => Ok(Vec::new()),
+        };
+        self.path = path;
+        self.modified = modified;
+    }
+
+    /// Right side panel with the instruction at `path`, the running or armed
+    /// order's. Draw before the central panel.
+    pub fn show(&mut self, ctx: &egui::Context, path: Option<&str>) {
+        if !self.open {
+            return;
+        }
+        self.update(path);
+        egui::SidePanel::right("work_instructions_panel")
+            .resizable(true)
+            .default_width(360.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.heading("Work Instruction");
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui.button("✖").clicked() {
+                            self.open = false;
+                        }
+                    });
+                });
+                match &self.path {
+                    Some(path) => ui.weak(path.display().to_string()),
+                    None => ui.weak(
+                        "No instruction for the running or armed order. Set one per program \
+                         or order in the Order Handler.",
+                    ),
+                };
+                ui.separator();
+                let dir = self
+                    .path
+                    .as_deref()
+                    .and_then(Path::parent)
+                    .map(Path::to_path_buf)
+                    .unwrap_or_default();
+                egui::ScrollArea::vertical()
+                    .id_salt("work_instructions_scroll")
+                    .auto_shrink([false; 2])
+                    .show(ui, |ui| match &self.document {
+                        Ok(blocks) => {
+                            for block in blocks {
+                                draw_block(ui, block, &dir);
+                            }
+                        }
+                        Err(e) => {
+                            ui.colored_label(egui::Color32::RED, e);
+                        }
+                    });
+            });
+    }
+}
+
+fn draw_block(ui: &mut egui::Ui, block: &Block, dir: &Path) {
+    match block {
+        Block::Heading(level, text) => {
+            let size = match level {
+                1 => 22.0,
+                2 => 18.0,
+                _ => 15.0,
+            };
+            ui.add_space(6.0);
+            ui.label(egui::RichText::new(text).size(size).strong());
+        }
+        Block::Bullet(text) => {
+            ui.horizontal_wrapped(|ui| {
+                ui.label("•");
+                ui.label(text);
+            });
+        }
+        Block::Numbered(n, text) => {
+            ui.horizontal_wrapped(|ui| {
+                ui.strong(format!("{}.", n));
+                ui.label(text);
+            });
+        }
+        Block::Code(code) => {
+            egui::Frame::group(ui.style()).show(ui, |ui| {
+                ui.monospace(code);
+            });
+        }
+        Block::Image(path) => {
+            let path = if Path::new(path).is_absolute() {
+                PathBuf::from(path)
+            } else {
+                dir.join(path)
+            };
+            ui.add(
+                egui::Image::new(format!("file://{}", path.display()))
+                    .max_width(ui.available_width()),
+            );
+        }
+        Block::Rule => {
+            ui.separator();
+        }
+        Block::Paragraph(text) => {
+            ui.add_space(2.0);
+            ui.label(text);
+        }
+    }
+}
@@ -0,0 +1,145 @@
+//! The threads background work runs on. Every fetch, write and command wait
+//! goes through `spawn`, which runs at most `MAX_WORKERS` of them at a time
+//! and queues the rest, so clicking a fetch button repeatedly can't pile up
+//! threads. Runs that take minutes get a thread of their own through
+//! `spawn_long`, so they never hold the slots commands and polling need. The
+//! count is shown in the tab bar.
+
+use eframe::egui;
+use poll_promise::Promise;
+use std::{
+    collections::VecDeque,
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+const MAX_WORKERS: usize = 8;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+struct Pool {
+    /// Threads that are running jobs, at most `MAX_WORKERS`.
+    workers: usize,
+    queue: VecDeque<Job>,
+}
+
+static POOL: Mutex<Pool> = Mutex::new(Pool {
+    workers: 0,
+    queue: VecDeque::new(),
+});
+
+/// Jobs that are queued or running, urgent ones included.
+static TASKS: AtomicUsize = AtomicUsize::new(0);
+
+/// Wraps `f` so its result ends up in the promise, and counts it.
+fn job<T, F>(f: F) -> (Job, Promise<T>)
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let (sender, promise) = Promise::new();
+    TASKS.fetch_add(1, Ordering::Relaxed);
+    (Box::new(move || sender.send(f())), promise)
+}
+
+/// Runs `job`, a panic in it only ends that job and not the worker.
+fn run(job: Job) {
+    if std::panic::catch_unwind(std::panic::AssertUnwindSafe(job)).is_err() {
+        log::error!("A background task panicked");
+    }
+    TASKS.fetch_sub(1, Ordering::Relaxed);
+}
+
+/// Runs jobs until the queue is empty, then gives its slot back.
+fn work(first: Job) {
+    let mut next = Some(first);
+    while let Some(job) = next {
+        run(job);
+        let mut pool = POOL.lock().unwrap_or_else(|e| e.into_inner());
+        next = pool.queue.pop_front();
+        if next.is_none() {
+            pool.workers -= 1;
+        }
+    }
+}
+
+/// Runs `f` on a worker thread, or queues it while all workers are busy.
+pub fn spawn<T, F>(name: &str, f: F) -> Promise<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let (job, promise) = job(f);
+    let mut pool = POOL.lock().unwrap_or_else(|e| e.into_inner());
+    if pool.workers >= MAX_WORKERS {
+        log::debug!("All workers busy, {} queued", name);
+        pool.queue.push_back(job);
+        return promise;
+    }
+    pool.workers += 1;
+    drop(pool);
+    std::thread::Builder::new()
+        .name(name.to_string())
+        .spawn(move || work(job))
+        .expect("Failed to spawn a worker thread");
+    promise
+}
+
+/// Runs `f` on a thread of its own, outside the pool.
+fn spawn_dedicated<T, F>(name: &str, f: F) -> Promise<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let (job, promise) = job(f);
+    std::thread::Builder::new()
+        .name(name.to_string())
+        .spawn(move || run(job))
+        .expect("Failed to spawn a thread");
+    promise
+}
+
+/// Like `spawn`, but never waits behind other jobs. Only for stopping robots,
+/// which can't queue behind a slow fetch.
+pub fn spawn_urgent<T, F>(name: &str, f: F) -> Promise<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    spawn_dedicated(name, f)
+}
+
+/// Like `spawn`, for runs that can take minutes: paths, queues, presets,
+/// scripts, test moves, screwdriving and MES requests.
+pub fn spawn_long<T, F>(name: &str, f: F) -> Promise<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    spawn_dedicated(name, f)
+}
+
+/// Number of background tasks that are queued or running.
+pub fn tasks() -> usize {
+    TASKS.load(Ordering::Relaxed)
+}
+
+/// Spinner with the task count, nothing while the GUI is idle.
+pub fn draw(ui: &mut egui::Ui) {
+    let tasks = tasks();
+    if tasks == 0 {
+        return;
+    }
+    ui.spinner();
+    ui.weak(match tasks {
+        1 => "1 background task running".to_string(),
+        n => format!("{} background tasks running", n),
+    })
+    .on_hover_text(format!(
+        "At most {} short tasks run at the same time, the rest wait for a free \
+         worker. Long runs have threads of their own.",
+        MAX_WORKERS
+    ));
+}
@@ -0,0 +1,203 @@
+//! Reach envelope of each robot, a sphere or box in one of its frames
+//! (usually the base). Waypoints outside it are flagged before a path runs.
+
+use crate::locale::{self, LocalizedDragValue};
+use crate::pose::{self, Pose};
+use crate::storage;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub const WORKSPACES_FILE: &str = "workspaces.json";
+
+pub fn check_file() -> Option<Result<(), String>> {
+    storage::check_json::<Workspaces>(WORKSPACES_FILE)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnvelopeShape {
+    Sphere { center: [f64; 3], radius: f64 },
+    Box { min: [f64; 3], max: [f64; 3] },
+}
+
+impl EnvelopeShape {
+    /// How far `point` is outside the shape, 0 inside.
+    pub fn outside_by(&self, point: [f64; 3]) -> f64 {
+        match self {
+            EnvelopeShape::Sphere { center, radius } => {
+                let distance = (0..3)
+                    .map(|i| (point[i] - center[i]).powi(2))
+                    .sum::<f64>()
+                    .sqrt();
+                (distance - radius).max(0.0)
+            }
+            EnvelopeShape::Box { min, max } => (0..3)
+                .map(|i| (min[i] - point[i]).max(point[i] - max[i]).max(0.0).powi(2))
+                .sum::<f64>()
+                .sqrt(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Envelope {
+    /// Frame the shape is given in.
+    pub frame: String,
+    pub shape: EnvelopeShape,
+}
+
+impl Envelope {
+    /// How far a point given in a frame where the envelope frame is at
+    /// `frame_pose` is outside the envelope.
+    pub fn outside_by(&self, frame_pose: &Pose, point: [f64; 3]) -> f64 {
+        let t = frame_pose.translation;
+        let q = frame_pose.rotation;
+        let local = pose::rotate_vector(
+            [-q[0], -q[1], -q[2], q[3]],
+            [point[0] - t[0], point[1] - t[1], point[2] - t[2]],
+        );
+        self.shape.outside_by(local)
+    }
+}
+
+/// Envelopes by robot id, saved on this station.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Workspaces {
+    robots: BTreeMap<String, Envelope>,
+}
+
+impl Workspaces {
+    pub fn load() -> Self {
+        storage::load_json(WORKSPACES_FILE).unwrap_or_default()
+    }
+
+    fn save(&self) {
+        if let Err(e) = storage::save_json(WORKSPACES_FILE, self) {
+            log::error!("Failed to save workspaces: {}", e);
+        }
+    }
+
+    pub fn get(&self, robot_id: &str) -> Option<&Envelope> {
+        self.robots.get(robot_id)
+    }
+
+    /// Editor for the envelope of one robot, saved on every change.
+    pub fn draw_editor(&mut self, ui: &mut egui::Ui, robot_id: &str, frames: &[String]) {
+        let mut envelope = self.robots.get(robot_id).cloned();
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            ui.label("Shape:");
+            let shape_name = match envelope.as_ref().map(|e| e.shape) {
+                None => "None",
+                Some(EnvelopeShape::Sphere { .. }) => "Sphere",
+                Some(EnvelopeShape::Box { .. }) => "Box",
+            };
+            egui::ComboBox::from_id_salt("workspace_shape")
+                .selected_text(shape_name)
+                .show_ui(ui, |ui| {
+                    let frame = envelope
+                        .as_ref()
+                        .map(|e| e.frame.clone())
+                        .unwrap_or_else(|| "base_link".to_string());
+                    if ui.selectable_label(shape_name == "None", "None").clicked() {
+                        envelope = None;
+                        changed = true;
+                    }
+                    if ui
+                        .selectable_label(shape_name == "Sphere", "Sphere")
+                        .clicked()
+                    {
+                        envelope = Some(Envelope {
+                            frame: frame.clone(),
+                            shape: EnvelopeShape::Sphere {
+                                center: [0.0; 3],
+                                radius: 0.85,
+                            },
+                        });
+                        changed = true;
+                    }
+                    if ui.selectable_label(shape_name == "Box", "Box").clicked() {
+                        envelope = Some(Envelope {
+                            frame,
+                            shape: EnvelopeShape::Box {
+                                min: [-0.8, -0.8, 0.0],
+                                max: [0.8, 0.8, 1.0],
+                            },
+                        });
+                        changed = true;
+                    }
+                });
+            if let Some(envelope) = &mut envelope {
+                ui.label("in");
+                egui::ComboBox::from_id_salt("workspace_frame")
+                    .selected_text(&envelope.frame)
+                    .show_ui(ui, |ui| {
+                        for frame in frames {
+                            changed |= ui
+                                .selectable_value(&mut envelope.frame, frame.clone(), frame)
+                                .changed();
+                        }
+                    });
+            }
+        });
+        if let Some(envelope) = &mut envelope {
+            egui::Grid::new("workspace_values")
+                .num_columns(4)
+                .show(ui, |ui| match &mut envelope.shape {
+                    EnvelopeShape::Sphere { center, radius } => {
+                        ui.label("Center (m):");
+                        changed |= draw_xyz(ui, center);
+                        ui.end_row();
+                        ui.label("Radius (m):");
+                        changed |= ui
+                            .add(
+                                egui::DragValue::new(radius)
+                                    .localized()
+                                    .speed(0.01)
+                                    .range(0.0..=10.0),
+                            )
+                            .changed();
+                        ui.end_row();
+                    }
+                    EnvelopeShape::Box { min, max } => {
+                        ui.label("Min (m):");
+                        changed |= draw_xyz(ui, min);
+                        ui.end_row();
+                        ui.label("Max (m):");
+                        changed |= draw_xyz(ui, max);
+                        ui.end_row();
+                    }
+                });
+        }
+        if changed {
+            match envelope {
+                Some(envelope) => self.robots.insert(robot_id.to_string(), envelope),
+                None => self.robots.remove(robot_id),
+            };
+            self.save();
+        }
+    }
+}
+
+/// Three drag values in a row, true if one changed.
+pub fn draw_xyz(ui: &mut egui::Ui, xyz: &mut [f64; 3]) -> bool {
+    let mut changed = false;
+    for value in xyz.iter_mut() {
+        changed |= ui
+            .add(egui::DragValue::new(value).localized().speed(0.01))
+            .changed();
+    }
+    changed
+}
+
+/// Warning line for a waypoint outside the envelope.
+pub fn reach_warning(step: usize, frame: &str, robot_id: &str, outside_by: f64) -> String {
+    format!(
+        "Step {} ({}) is {} mm outside the workspace of {}",
+        step,
+        frame,
+        locale::format_number(outside_by * 1000.0, 0),
+        robot_id
+    )
+}